@@ -0,0 +1,54 @@
+use core_model::SourceContext;
+use ingest::sync_adapter;
+use remi_benches::{
+    SyntheticAdapter, bench, iterations, message_count, normalize, open_store, remove_db,
+    synthetic_records, temp_db,
+};
+
+fn main() {
+    let messages = message_count();
+    let iters = iterations(3);
+    println!("ingest benchmarks: {messages} synthetic messages");
+
+    let records = synthetic_records(messages);
+    bench("normalize", iters, || (), |_| normalize(&records));
+
+    let batch = normalize(&records);
+    bench(
+        "save_batch",
+        iters,
+        || {
+            let path = temp_db("save");
+            (open_store(&path), path)
+        },
+        |(mut store, path)| {
+            store.save_batch(&batch).expect("save batch");
+            drop(store);
+            remove_db(&path);
+        },
+    );
+
+    let adapter = SyntheticAdapter::new(messages);
+    bench(
+        "sync_adapter",
+        iters,
+        || {
+            let path = temp_db("sync");
+            (open_store(&path), path)
+        },
+        |(mut store, path)| {
+            let saved = sync_adapter(
+                &adapter,
+                &SourceContext::default(),
+                &mut store,
+                #[cfg(feature = "semantic")]
+                false,
+                |_| {},
+            )
+            .expect("sync synthetic adapter");
+            drop(store);
+            remove_db(&path);
+            saved
+        },
+    );
+}
@@ -0,0 +1,121 @@
+use remi_benches::{bench, iterations, message_count, remove_db, seeded_store};
+use search::{ReasoningFilter, Weights, search_sessions};
+use store_sqlite::SqliteStore;
+
+const QUERIES: &[(&str, &str, bool)] = &[
+    ("fts_term", "deadlock", false),
+    ("fts_multi_term", "retry backoff timeout", false),
+    ("fts_phrase", "\"cargo test\"", false),
+    ("fts_raw", "retry OR migration", true),
+    ("fts_substring_fallback", "adlo", false),
+];
+
+fn main() {
+    let messages = message_count();
+    let iters = iterations(20);
+    println!("search benchmarks: {messages} synthetic messages");
+
+    let (path, store) = seeded_store("search", messages);
+    for (name, query, raw) in QUERIES {
+        bench(name, iters, || (), |_| run_query(&store, query, *raw));
+    }
+
+    #[cfg(feature = "semantic")]
+    hybrid::run(&store, iters);
+
+    drop(store);
+    remove_db(&path);
+}
+
+fn run_query(store: &SqliteStore, query: &str, raw: bool) -> usize {
+    search_sessions(
+        store,
+        query,
+        20,
+        raw,
+        Weights::default(),
+        ReasoningFilter::Include,
+        None,
+        #[cfg(feature = "semantic")]
+        None,
+        #[cfg(feature = "semantic")]
+        None,
+        #[cfg(feature = "semantic")]
+        None,
+    )
+    .expect("search")
+    .0
+    .len()
+}
+
+#[cfg(feature = "semantic")]
+mod hybrid {
+    use embeddings::Embedder;
+    use remi_benches::bench;
+    use search::{ReasoningFilter, SemanticCache, Weights, search_sessions};
+    use store_sqlite::SqliteStore;
+
+    pub const MODEL_DIR_ENV: &str = "REMI_BENCH_MODEL_DIR";
+
+    // Stored vectors are synthetic so the bench measures ranking, not 100k model calls.
+    pub fn run(store: &SqliteStore, iters: usize) {
+        let Some(model_dir) = std::env::var_os(MODEL_DIR_ENV) else {
+            println!("hybrid: skipped, set {MODEL_DIR_ENV} to an embedding model directory");
+            return;
+        };
+        let mut embedder = Embedder::new(&model_dir, None, None).expect("load embedding model");
+        let dim = embedder.embed("probe", true).expect("embed probe").len();
+        let mut idx = 0;
+        for session in store.list_sessions().expect("list sessions") {
+            for message in store
+                .get_session_messages(&session.id)
+                .expect("load messages")
+            {
+                store
+                    .save_embedding(
+                        &message.id,
+                        embedder.model_id(),
+                        &synthetic_vector(idx, dim),
+                    )
+                    .expect("save embedding");
+                idx += 1;
+            }
+        }
+
+        let mut cache = SemanticCache::default();
+        bench(
+            "hybrid_search",
+            iters,
+            || (),
+            |_| {
+                search_sessions(
+                    store,
+                    "retry deadlock",
+                    20,
+                    false,
+                    Weights::default(),
+                    ReasoningFilter::Include,
+                    None,
+                    Some(&mut embedder),
+                    Some(&mut cache),
+                    None,
+                )
+                .expect("hybrid search")
+                .0
+                .len()
+            },
+        );
+    }
+
+    fn synthetic_vector(seed: usize, dim: usize) -> Vec<f32> {
+        let mut state = seed as u64 ^ 0x9e37_79b9_7f4a_7c15;
+        (0..dim)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                ((state >> 40) as f32 / (1u64 << 24) as f32) - 0.5
+            })
+            .collect()
+    }
+}
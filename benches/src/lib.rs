@@ -0,0 +1,283 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, TimeZone, Utc};
+use core_model::{
+    AgentAdapter, AgentKind, ArchiveCapability, Message, NativeRecord, NormalizedBatch, Provenance,
+    Session, SourceContext, deterministic_id,
+};
+use serde_json::json;
+use store_sqlite::SqliteStore;
+
+pub const DEFAULT_MESSAGES: usize = 100_000;
+pub const MESSAGES_PER_SESSION: usize = 50;
+
+const WORDS: &[&str] = &[
+    "retry",
+    "backoff",
+    "panic",
+    "index",
+    "cargo",
+    "test",
+    "sqlite",
+    "cursor",
+    "session",
+    "adapter",
+    "normalize",
+    "checkpoint",
+    "release",
+    "tagging",
+    "linker",
+    "error",
+    "timeout",
+    "tokio",
+    "mutex",
+    "deadlock",
+    "schema",
+    "migration",
+    "embedding",
+    "vector",
+    "query",
+    "rollout",
+    "compaction",
+    "snapshot",
+    "fixture",
+    "golden",
+    "parser",
+    "timestamp",
+];
+
+pub fn env_usize(name: &str, default: usize) -> usize {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+pub fn message_count() -> usize {
+    env_usize("REMI_BENCH_MESSAGES", DEFAULT_MESSAGES)
+}
+
+pub fn iterations(default: usize) -> usize {
+    env_usize("REMI_BENCH_ITERS", default).max(1)
+}
+
+pub struct Timing {
+    pub name: String,
+    pub samples: Vec<Duration>,
+}
+
+impl Timing {
+    pub fn report(&self) {
+        let mut sorted = self.samples.clone();
+        sorted.sort();
+        let total: Duration = sorted.iter().sum();
+        println!(
+            "{:<40} iters={:<4} min={:>10.3?} median={:>10.3?} mean={:>10.3?} max={:>10.3?}",
+            self.name,
+            sorted.len(),
+            sorted[0],
+            sorted[sorted.len() / 2],
+            total / sorted.len() as u32,
+            sorted[sorted.len() - 1],
+        );
+    }
+}
+
+// `setup` runs untimed before every sample so each one starts from the same state.
+pub fn bench<S, T>(
+    name: &str,
+    iters: usize,
+    mut setup: impl FnMut() -> S,
+    mut routine: impl FnMut(S) -> T,
+) -> Timing {
+    let mut samples = Vec::with_capacity(iters);
+    for _ in 0..iters {
+        let input = setup();
+        let started = Instant::now();
+        std::hint::black_box(routine(input));
+        samples.push(started.elapsed());
+    }
+    let timing = Timing {
+        name: name.to_string(),
+        samples,
+    };
+    timing.report();
+    timing
+}
+
+pub fn temp_db(label: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "remi-bench-{label}-{}-{}.db",
+        std::process::id(),
+        Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    ));
+    remove_db(&path);
+    path
+}
+
+pub fn remove_db(path: &Path) {
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{suffix}", path.display()));
+    }
+}
+
+pub fn open_store(path: &Path) -> SqliteStore {
+    let store = SqliteStore::open(path).expect("open bench store");
+    store.init_schema().expect("init bench schema");
+    store
+}
+
+fn base_ts() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap()
+}
+
+// Deterministic pseudo-random prose so FTS sees a realistic term spread.
+pub fn synthetic_content(seed: usize) -> String {
+    let mut state = (seed as u64)
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1);
+    let len = 12 + (seed % 40);
+    let mut words = Vec::with_capacity(len);
+    for _ in 0..len {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        words.push(WORDS[(state >> 33) as usize % WORDS.len()]);
+    }
+    if seed.is_multiple_of(7) {
+        format!(
+            "{}\n```rust\nfn retry() -> anyhow::Result<()> {{ Ok(()) }}\n```",
+            words.join(" ")
+        )
+    } else {
+        words.join(" ")
+    }
+}
+
+pub fn synthetic_records(messages: usize) -> Vec<NativeRecord> {
+    let start = base_ts();
+    (0..messages)
+        .map(|idx| {
+            let session = idx / MESSAGES_PER_SESSION;
+            NativeRecord {
+                source_id: format!("bench-{session:06}:{idx:08}"),
+                updated_at: start + chrono::Duration::seconds(idx as i64),
+                payload: json!({
+                    "session": format!("bench-{session:06}"),
+                    "role": if idx.is_multiple_of(2) { "user" } else { "assistant" },
+                    "content": synthetic_content(idx),
+                }),
+            }
+        })
+        .collect()
+}
+
+pub fn normalize(records: &[NativeRecord]) -> NormalizedBatch {
+    let kind = AgentKind::Pi;
+    let mut batch = NormalizedBatch::default();
+    for rec in records {
+        let thread = rec.payload["session"].as_str().unwrap_or_default();
+        let session_id = deterministic_id(&[kind.as_str(), "session", thread]);
+        let message_id = deterministic_id(&[kind.as_str(), "message", &rec.source_id]);
+        if batch.sessions.last().is_none_or(|s| s.id != session_id) {
+            batch.sessions.push(Session {
+                id: session_id.clone(),
+                agent: kind,
+                source_ref: thread.to_string(),
+                title: format!("bench session {thread}"),
+                created_at: rec.updated_at,
+                updated_at: rec.updated_at,
+            });
+        }
+        if let Some(session) = batch.sessions.last_mut() {
+            session.updated_at = rec.updated_at;
+        }
+        batch.messages.push(Message {
+            id: message_id.clone(),
+            session_id,
+            role: rec.payload["role"].as_str().unwrap_or("user").to_string(),
+            content: rec.payload["content"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            ts: rec.updated_at,
+        });
+        batch.provenance.push(Provenance {
+            id: deterministic_id(&["prov", &message_id]),
+            entity_type: "message".to_string(),
+            entity_id: message_id,
+            agent: kind,
+            source_path: "/bench/synthetic.jsonl".to_string(),
+            source_id: rec.source_id.clone(),
+            workspace: None,
+            repo: None,
+        });
+    }
+    batch
+}
+
+// Hands pre-built records to the real ingest pipeline without touching disk.
+pub struct SyntheticAdapter {
+    records: Vec<NativeRecord>,
+}
+
+impl SyntheticAdapter {
+    pub fn new(messages: usize) -> Self {
+        Self {
+            records: synthetic_records(messages),
+        }
+    }
+}
+
+impl AgentAdapter for SyntheticAdapter {
+    fn kind(&self) -> AgentKind {
+        AgentKind::Pi
+    }
+
+    fn discover_source_paths(&self, _ctx: &SourceContext) -> anyhow::Result<Vec<String>> {
+        Ok(vec!["/bench/synthetic.jsonl".to_string()])
+    }
+
+    fn scan_changes_since(
+        &self,
+        _source_paths: &[String],
+        cursor: Option<&str>,
+    ) -> anyhow::Result<Vec<NativeRecord>> {
+        if cursor.is_some() {
+            return Ok(Vec::new());
+        }
+        Ok(self.records.clone())
+    }
+
+    fn normalize(&self, records: &[NativeRecord]) -> anyhow::Result<NormalizedBatch> {
+        Ok(normalize(records))
+    }
+
+    fn checkpoint_cursor(&self, records: &[NativeRecord]) -> Option<String> {
+        records.last().map(|r| r.updated_at.to_rfc3339())
+    }
+
+    fn archive_capability(&self) -> ArchiveCapability {
+        ArchiveCapability::CentralizedCopy
+    }
+
+    fn tracks_file_state(&self) -> bool {
+        false
+    }
+}
+
+// Builds a populated on-disk store once for read-side benchmarks.
+pub fn seeded_store(label: &str, messages: usize) -> (PathBuf, SqliteStore) {
+    let path = temp_db(label);
+    let mut store = open_store(&path);
+    let records = synthetic_records(messages);
+    for chunk in records.chunks(10_000) {
+        store
+            .save_batch(&normalize(chunk))
+            .expect("seed bench store");
+    }
+    (path, store)
+}
@@ -0,0 +1,572 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use adapter_common::{BaseDirs, CaptureOptions, DiscoveryOptions, ScanStats, SourceTimezone};
+use chrono::{DateTime, Duration, Utc};
+use core_model::{
+    AgentAdapter, AgentKind, ArchiveCapability, NativeRecord, NormalizedBatch, ScanReport, Session,
+    SourceContext, deterministic_id,
+};
+use rayon::prelude::*;
+use serde_json::{Value, json};
+use tracing::debug;
+
+pub const CHAT_HISTORY: &str = ".aider.chat.history.md";
+pub const INPUT_HISTORY: &str = ".aider.input.history";
+
+// Without configured roots the home directory is searched this deep, which
+// covers `~/project` and `~/code/project`.
+const DEFAULT_ROOT_DEPTH: usize = 3;
+
+const SESSION_HEADER: &str = "# aider chat started at ";
+
+// Aider keeps its history inside each project rather than in one place, so
+// discovery walks the roots set with `[discovery.agents.aider] roots`.
+#[derive(Default)]
+pub struct AiderAdapter {
+    stats: ScanStats,
+    discovery: DiscoveryOptions,
+    capture: CaptureOptions,
+    timezone: Option<SourceTimezone>,
+}
+
+impl AiderAdapter {
+    pub fn with_discovery(discovery: DiscoveryOptions) -> Self {
+        Self {
+            discovery,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_capture(mut self, capture: CaptureOptions) -> Self {
+        self.capture = capture;
+        self
+    }
+
+    pub fn with_timezone(mut self, timezone: Option<SourceTimezone>) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    pub fn discover_in(&self, dirs: &BaseDirs) -> Vec<String> {
+        let (roots, options) = if self.discovery.roots.is_empty() {
+            let options = DiscoveryOptions {
+                max_depth: self.discovery.max_depth.min(DEFAULT_ROOT_DEPTH),
+                ..self.discovery.clone()
+            };
+            (vec![dirs.home_path("")], options)
+        } else {
+            let roots = self
+                .discovery
+                .roots
+                .iter()
+                .map(|root| resolve_root(dirs, root))
+                .collect();
+            (roots, self.discovery.clone())
+        };
+        let mut paths: Vec<String> = roots
+            .iter()
+            .flat_map(|root| adapter_common::collect_files(root, "md", &options))
+            .filter(|path| {
+                Path::new(path)
+                    .file_name()
+                    .is_some_and(|n| n == CHAT_HISTORY)
+            })
+            .collect();
+        paths.sort_unstable();
+        paths.dedup();
+        paths
+    }
+}
+
+// `~/code` and relative roots are taken from the home directory.
+fn resolve_root(dirs: &BaseDirs, root: &Path) -> PathBuf {
+    let text = root.to_string_lossy();
+    if text == "~" {
+        dirs.home_path("")
+    } else if let Some(rest) = text.strip_prefix("~/") {
+        dirs.home_path(rest)
+    } else if root.is_relative() {
+        dirs.home_path(&text)
+    } else {
+        root.to_path_buf()
+    }
+}
+
+impl AgentAdapter for AiderAdapter {
+    fn kind(&self) -> AgentKind {
+        AgentKind::Aider
+    }
+
+    fn discover_source_paths(&self, ctx: &SourceContext) -> anyhow::Result<Vec<String>> {
+        let Some(dirs) = adapter_common::discovery_dirs(ctx, self.kind()) else {
+            return Ok(Vec::new());
+        };
+        let paths = self.discover_in(&dirs);
+        debug!(files = paths.len(), "aider adapter discovered source paths");
+        Ok(paths)
+    }
+
+    fn scan_changes_since(
+        &self,
+        source_paths: &[String],
+        cursor: Option<&str>,
+    ) -> anyhow::Result<Vec<NativeRecord>> {
+        self.stats.reset();
+        load_histories(
+            source_paths,
+            cursor,
+            &self.stats,
+            SourceTimezone::for_agent(self.kind(), self.timezone),
+        )
+    }
+
+    fn normalize(&self, records: &[NativeRecord]) -> anyhow::Result<NormalizedBatch> {
+        Ok(normalize_records(AgentKind::Aider, records, &self.capture))
+    }
+
+    fn checkpoint_cursor(&self, records: &[NativeRecord]) -> Option<String> {
+        adapter_common::checkpoint_cursor_from_records(records)
+    }
+
+    fn archive_capability(&self) -> ArchiveCapability {
+        ArchiveCapability::CentralizedCopy
+    }
+
+    fn scan_report(&self) -> Option<ScanReport> {
+        Some(self.stats.report())
+    }
+
+    fn source_label(&self) -> String {
+        "Aider".to_string()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    User,
+    Assistant,
+    // `> ` lines: what aider itself reports, such as applied edits and commits.
+    Tool,
+}
+
+impl Kind {
+    fn role(self) -> &'static str {
+        match self {
+            Kind::User => "user",
+            Kind::Assistant => "assistant",
+            Kind::Tool => "tool",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Chat {
+    started: String,
+    messages: Vec<(Kind, String)>,
+}
+
+// Each `# aider chat started at` header opens a chat. User input is written
+// as `#### ` lines, aider's own output as `> ` lines, and everything else is
+// the model's reply. Code fences in a reply are copied verbatim, so their
+// `####` and `>` lines stay part of it.
+fn parse_chats(text: &str) -> Vec<Chat> {
+    let mut chats: Vec<Chat> = Vec::new();
+    let mut current: Option<(Kind, Vec<&str>)> = None;
+    let mut in_fence = false;
+    let flush = |chats: &mut Vec<Chat>, current: &mut Option<(Kind, Vec<&str>)>| {
+        if let Some((kind, lines)) = current.take() {
+            let content = lines.join("\n").trim().to_string();
+            if let Some(chat) = chats.last_mut()
+                && !content.is_empty()
+            {
+                chat.messages.push((kind, content));
+            }
+        }
+    };
+    for line in text.lines() {
+        if let Some(started) = line.strip_prefix(SESSION_HEADER) {
+            flush(&mut chats, &mut current);
+            chats.push(Chat {
+                started: started.trim().to_string(),
+                messages: Vec::new(),
+            });
+            in_fence = false;
+            continue;
+        }
+        let fence = line.trim_start().starts_with("```");
+        let (kind, content) = if in_fence {
+            in_fence = !fence;
+            (Some(Kind::Assistant), line)
+        } else if let Some(rest) = line.strip_prefix("####") {
+            (Some(Kind::User), rest.strip_prefix(' ').unwrap_or(rest))
+        } else if let Some(rest) = line.strip_prefix('>') {
+            (Some(Kind::Tool), rest.strip_prefix(' ').unwrap_or(rest))
+        } else if line.trim().is_empty() {
+            (None, line)
+        } else {
+            in_fence = fence;
+            (Some(Kind::Assistant), line)
+        };
+        match (kind, current.as_mut()) {
+            // Blank lines belong to whatever block they sit in.
+            (None, Some((_, lines))) => lines.push(content),
+            (None, None) => {}
+            (Some(kind), Some((open, lines))) if *open == kind => lines.push(content),
+            (Some(kind), _) => {
+                flush(&mut chats, &mut current);
+                current = Some((kind, vec![content]));
+            }
+        }
+    }
+    flush(&mut chats, &mut current);
+    chats
+}
+
+// `.aider.input.history` stamps each prompt the user typed:
+// `# <timestamp>` followed by the prompt as `+` lines.
+fn parse_inputs(text: &str, tz: SourceTimezone) -> Vec<(DateTime<Utc>, String)> {
+    let mut inputs: Vec<(DateTime<Utc>, Vec<&str>)> = Vec::new();
+    for line in text.lines() {
+        if let Some(ts) = line.strip_prefix("# ") {
+            if let Some(ts) = adapter_common::parse_timestamp(ts, tz) {
+                inputs.push((ts, Vec::new()));
+            }
+        } else if let Some(rest) = line.strip_prefix('+')
+            && let Some((_, lines)) = inputs.last_mut()
+        {
+            lines.push(rest);
+        }
+    }
+    inputs
+        .into_iter()
+        .map(|(ts, lines)| (ts, lines.join("\n").trim().to_string()))
+        .collect()
+}
+
+fn load_histories(
+    source_paths: &[String],
+    cursor: Option<&str>,
+    stats: &ScanStats,
+    tz: SourceTimezone,
+) -> anyhow::Result<Vec<NativeRecord>> {
+    let parsed_cursor = cursor.and_then(adapter_common::parse_cursor);
+    let mut out: Vec<NativeRecord> = source_paths
+        .par_iter()
+        .flat_map(|path| {
+            stats.file_seen();
+            let file_mtime = adapter_common::file_mtime(path);
+            if let Some(ref cur) = parsed_cursor
+                && let Some(mtime) = file_mtime
+                && mtime <= cur.ts
+            {
+                stats.file_skipped();
+                return Vec::new();
+            }
+            let Ok(text) = fs::read_to_string(path) else {
+                stats.file_skipped();
+                return Vec::new();
+            };
+            let project = Path::new(path)
+                .parent()
+                .map(|dir| dir.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let project_name = Path::new(&project)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let inputs = fs::read_to_string(Path::new(&project).join(INPUT_HISTORY))
+                .map(|text| parse_inputs(&text, tz))
+                .unwrap_or_default();
+            let mut next_input = 0;
+
+            let mut records = Vec::new();
+            for chat in parse_chats(&text) {
+                let Some(started) = adapter_common::parse_timestamp(&chat.started, tz) else {
+                    stats.parse_errors(1);
+                    continue;
+                };
+                // Keyed by the project's directory name rather than its full
+                // path, so a moved or re-cloned project keeps its sessions.
+                let chat_key = format!("{project_name}#{}", chat.started);
+                let title = chat
+                    .messages
+                    .iter()
+                    .find(|(kind, _)| *kind == Kind::User)
+                    .map_or_else(|| project.clone(), |(_, text)| title_from(text));
+                // Replies are placed just after the prompt they answer.
+                let mut ts = started;
+                for (idx, (kind, content)) in chat.messages.into_iter().enumerate() {
+                    ts += Duration::milliseconds(1);
+                    if kind == Kind::User
+                        && let Some(offset) = inputs[next_input..]
+                            .iter()
+                            .position(|(_, input)| *input == content)
+                    {
+                        next_input += offset + 1;
+                        ts = ts.max(inputs[next_input - 1].0);
+                    }
+                    let source_id = format!("{chat_key}:{idx}");
+                    if let Some(ref cur) = parsed_cursor
+                        && adapter_common::should_skip(ts, &source_id, cur)
+                    {
+                        continue;
+                    }
+                    records.push(NativeRecord {
+                        source_id,
+                        updated_at: ts,
+                        payload: json!({
+                            "role": kind.role(),
+                            "content": content,
+                            "__chat_key": chat_key,
+                            "__chat_ts": started.to_rfc3339(),
+                            "__chat_title": title,
+                            "__project": project,
+                        }),
+                    });
+                }
+            }
+            records
+        })
+        .collect();
+
+    out.sort_by(|a, b| {
+        a.updated_at
+            .cmp(&b.updated_at)
+            .then_with(|| a.source_id.cmp(&b.source_id))
+    });
+    debug!(total = out.len(), "aider histories loaded");
+    Ok(out)
+}
+
+fn normalize_records(
+    kind: AgentKind,
+    records: &[NativeRecord],
+    capture: &CaptureOptions,
+) -> NormalizedBatch {
+    debug!(kind = %kind, records = records.len(), "normalizing aider records");
+    let mut batch = NormalizedBatch::default();
+    let mut sessions: HashMap<String, Session> = HashMap::new();
+    for rec in records {
+        let role = adapter_common::normalize_role(
+            rec.payload.get("role").and_then(Value::as_str),
+            core_model::Role::User,
+        );
+        let content =
+            adapter_common::extract_content_text_with(rec.payload.get("content"), capture);
+        if content.is_empty() {
+            continue;
+        }
+        let field = |key: &str| rec.payload.get(key).and_then(Value::as_str);
+        let chat_key = field("__chat_key").unwrap_or(&rec.source_id).to_string();
+        let created_at = field("__chat_ts")
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+            .map_or(rec.updated_at, |ts| ts.with_timezone(&Utc));
+
+        let session_id = deterministic_id(&[kind.as_str(), "session", &chat_key]);
+        let message_id = deterministic_id(&[kind.as_str(), "message", &rec.source_id]);
+        let session = sessions
+            .entry(session_id.clone())
+            .or_insert_with(|| Session {
+                id: session_id.clone(),
+                agent: kind,
+                source_ref: chat_key.clone(),
+                title: field("__chat_title").unwrap_or(&chat_key).to_string(),
+                created_at,
+                updated_at: rec.updated_at,
+            });
+        session.created_at = session.created_at.min(created_at);
+        session.updated_at = session.updated_at.max(rec.updated_at);
+
+        batch.messages.push(core_model::Message {
+            id: message_id.clone(),
+            session_id,
+            role,
+            content,
+            ts: rec.updated_at,
+        });
+        batch.provenance.push(core_model::Provenance {
+            id: deterministic_id(&["prov", &message_id]),
+            entity_type: "message".to_string(),
+            entity_id: message_id,
+            agent: kind,
+            source_path: field("__project").unwrap_or(kind.as_str()).to_string(),
+            source_id: rec.source_id.clone(),
+            workspace: field("__project").map(str::to_string),
+            repo: None,
+        });
+    }
+    let mut ordered: Vec<Session> = sessions.into_values().collect();
+    ordered.sort_by(|a, b| {
+        a.updated_at
+            .cmp(&b.updated_at)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    batch.sessions.extend(ordered);
+    debug!(
+        sessions = batch.sessions.len(),
+        messages = batch.messages.len(),
+        "aider records normalized"
+    );
+    batch
+}
+
+fn title_from(text: &str) -> String {
+    let line = text.lines().next().unwrap_or_default().trim();
+    if line.chars().count() > 80 {
+        format!("{}…", line.chars().take(80).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adapter_common::Platform;
+
+    const HISTORY: &str = "
+# aider chat started at 2025-07-01 09:00:00
+
+> Aider v0.82.0
+> Model: sonnet with diff edit format
+
+#### fix the login redirect loop
+#### in auth.py
+
+The callback drops the session cookie.
+
+auth.py
+```python
+def callback(): ...
+```
+
+> Applied edit to auth.py
+> Commit 1a2b3c4 fix: keep the session cookie
+
+# aider chat started at 2025-07-02 10:00:00
+
+#### /ask what does retry() do?
+
+It retries with backoff.
+";
+
+    const INPUTS: &str = "
+# 2025-07-01 09:00:30.123456
++fix the login redirect loop
++in auth.py
+
+# 2025-07-02 10:00:05.000000
++/ask what does retry() do?
+";
+
+    #[test]
+    fn transcripts_split_into_chats_and_roles() {
+        let chats = parse_chats(HISTORY);
+        assert_eq!(chats.len(), 2);
+        assert_eq!(chats[0].started, "2025-07-01 09:00:00");
+        let kinds: Vec<Kind> = chats[0].messages.iter().map(|(k, _)| *k).collect();
+        assert_eq!(
+            kinds,
+            vec![Kind::Tool, Kind::User, Kind::Assistant, Kind::Tool]
+        );
+        assert_eq!(
+            chats[0].messages[1].1,
+            "fix the login redirect loop\nin auth.py"
+        );
+        assert!(chats[0].messages[2].1.ends_with("def callback(): ...\n```"));
+        assert_eq!(
+            chats[0].messages[3].1,
+            "Applied edit to auth.py\nCommit 1a2b3c4 fix: keep the session cookie"
+        );
+        assert_eq!(chats[1].messages.len(), 2);
+    }
+
+    #[test]
+    fn fenced_headings_and_quotes_stay_in_the_reply() {
+        let chats = parse_chats(
+            "# aider chat started at 2025-07-01 09:00:00\n\
+             #### add usage\n\
+             README.md\n\
+             ```markdown\n\
+             #### Usage\n\
+             \n\
+             > Run `app --help` first.\n\
+             ```\n\
+             > Applied edit to README.md\n",
+        );
+        let kinds: Vec<Kind> = chats[0].messages.iter().map(|(k, _)| *k).collect();
+        assert_eq!(kinds, vec![Kind::User, Kind::Assistant, Kind::Tool]);
+        assert!(chats[0].messages[1].1.contains("#### Usage\n\n> Run"));
+    }
+
+    #[test]
+    fn histories_are_discovered_under_roots_and_timed_from_inputs() {
+        let home = std::env::temp_dir().join(format!("remi_aider_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&home);
+        let project = home.join("code/app");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::write(project.join(CHAT_HISTORY), HISTORY).unwrap();
+        std::fs::write(project.join(INPUT_HISTORY), INPUTS).unwrap();
+        std::fs::write(project.join("README.md"), "# app").unwrap();
+        let dirs = BaseDirs::new(Platform::Linux, home.clone());
+        let chat = project.join(CHAT_HISTORY).to_string_lossy().to_string();
+
+        assert_eq!(
+            AiderAdapter::default().discover_in(&dirs),
+            vec![chat.clone()]
+        );
+        let elsewhere = AiderAdapter::with_discovery(DiscoveryOptions {
+            roots: vec![PathBuf::from("~/other")],
+            ..Default::default()
+        });
+        assert!(elsewhere.discover_in(&dirs).is_empty());
+        let configured = AiderAdapter::with_discovery(DiscoveryOptions {
+            roots: vec![PathBuf::from("~/code")],
+            ..Default::default()
+        });
+        assert_eq!(configured.discover_in(&dirs), vec![chat.clone()]);
+
+        let records = load_histories(
+            std::slice::from_ref(&chat),
+            None,
+            &ScanStats::default(),
+            SourceTimezone::Utc,
+        )
+        .unwrap();
+        assert_eq!(records.len(), 6);
+        let user = &records[1];
+        assert_eq!(user.payload["role"], "user");
+        assert_eq!(
+            user.updated_at.to_rfc3339(),
+            "2025-07-01T09:00:30.123456+00:00"
+        );
+        assert!(records[2].updated_at > user.updated_at);
+
+        let batch = configured.normalize(&records).unwrap();
+        assert_eq!(batch.sessions.len(), 2);
+        let first = &batch.sessions[0];
+        assert_eq!(first.title, "fix the login redirect loop");
+        assert_eq!(first.created_at.to_rfc3339(), "2025-07-01T09:00:00+00:00");
+        assert_eq!(batch.sessions[1].title, "/ask what does retry() do?");
+        assert_eq!(batch.messages[3].role, "tool");
+        assert_eq!(
+            batch.provenance[0].source_path,
+            project.to_string_lossy().to_string()
+        );
+
+        let cursor = configured.checkpoint_cursor(&records).unwrap();
+        let unchanged = load_histories(
+            &[chat],
+            Some(&cursor),
+            &ScanStats::default(),
+            SourceTimezone::Utc,
+        )
+        .unwrap();
+        assert!(unchanged.is_empty());
+        let _ = std::fs::remove_dir_all(&home);
+    }
+}
@@ -0,0 +1,11 @@
+use adapter_common::golden::assert_golden;
+
+#[test]
+fn history_fixture_matches_golden() {
+    assert_golden(
+        &aider::AiderAdapter::default(),
+        env!("CARGO_MANIFEST_DIR"),
+        "history",
+        "md",
+    );
+}
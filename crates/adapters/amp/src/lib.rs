@@ -1,25 +1,59 @@
-use std::{collections::HashMap, fs, path::PathBuf};
+use std::{collections::HashMap, fs};
 
-use chrono::{DateTime, Duration, TimeZone, Utc};
+use adapter_common::{BaseDirs, CaptureOptions, DiscoveryOptions, ScanStats, SourceTimezone};
+use chrono::{DateTime, Duration, Utc};
 use core_model::{
-    AgentAdapter, AgentKind, ArchiveCapability, NativeRecord, NormalizedBatch, Session,
-    deterministic_id,
+    AgentAdapter, AgentKind, ArchiveCapability, NativeRecord, NormalizedBatch, ScanReport, Session,
+    SourceContext, deterministic_id,
 };
 use rayon::prelude::*;
 use serde_json::Value;
 use tracing::debug;
 
-pub struct AmpAdapter;
+#[derive(Default)]
+pub struct AmpAdapter {
+    stats: ScanStats,
+    discovery: DiscoveryOptions,
+    capture: CaptureOptions,
+    timezone: Option<SourceTimezone>,
+}
+
+impl AmpAdapter {
+    pub fn with_discovery(discovery: DiscoveryOptions) -> Self {
+        Self {
+            discovery,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_capture(mut self, capture: CaptureOptions) -> Self {
+        self.capture = capture;
+        self
+    }
+
+    pub fn with_timezone(mut self, timezone: Option<SourceTimezone>) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    pub fn discover_in(&self, dirs: &BaseDirs) -> Vec<String> {
+        dirs.data_paths("amp/threads")
+            .iter()
+            .flat_map(|dir| adapter_common::collect_files(dir, "json", &self.discovery))
+            .collect()
+    }
+}
 
 impl AgentAdapter for AmpAdapter {
     fn kind(&self) -> AgentKind {
         AgentKind::Amp
     }
 
-    fn discover_source_paths(&self) -> anyhow::Result<Vec<String>> {
-        let base = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
-        let paths =
-            adapter_common::collect_files_with_ext(&base.join(".local/share/amp/threads"), "json");
+    fn discover_source_paths(&self, ctx: &SourceContext) -> anyhow::Result<Vec<String>> {
+        let Some(dirs) = adapter_common::discovery_dirs(ctx, self.kind()) else {
+            return Ok(Vec::new());
+        };
+        let paths = self.discover_in(&dirs);
         debug!(files = paths.len(), "amp adapter discovered source paths");
         Ok(paths)
     }
@@ -29,11 +63,17 @@ impl AgentAdapter for AmpAdapter {
         source_paths: &[String],
         cursor: Option<&str>,
     ) -> anyhow::Result<Vec<NativeRecord>> {
-        load_thread_json(source_paths, cursor)
+        self.stats.reset();
+        load_thread_json(
+            source_paths,
+            cursor,
+            &self.stats,
+            SourceTimezone::for_agent(self.kind(), self.timezone),
+        )
     }
 
     fn normalize(&self, records: &[NativeRecord]) -> anyhow::Result<NormalizedBatch> {
-        Ok(normalize_records(AgentKind::Amp, records))
+        Ok(normalize_records(AgentKind::Amp, records, &self.capture))
     }
 
     fn checkpoint_cursor(&self, records: &[NativeRecord]) -> Option<String> {
@@ -43,6 +83,14 @@ impl AgentAdapter for AmpAdapter {
     fn archive_capability(&self) -> ArchiveCapability {
         ArchiveCapability::CentralizedCopy
     }
+
+    fn scan_report(&self) -> Option<ScanReport> {
+        Some(self.stats.report())
+    }
+
+    fn source_label(&self) -> String {
+        "Amp".to_string()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -56,18 +104,21 @@ struct UsageLedgerIndex {
     by_index: HashMap<usize, DateTime<Utc>>,
 }
 
-fn normalize_records(kind: AgentKind, records: &[NativeRecord]) -> NormalizedBatch {
+fn normalize_records(
+    kind: AgentKind,
+    records: &[NativeRecord],
+    capture: &CaptureOptions,
+) -> NormalizedBatch {
     debug!(kind = %kind, records = records.len(), "normalizing amp records");
     let mut batch = NormalizedBatch::default();
     let mut sessions: HashMap<String, SessionAccum> = HashMap::new();
     for rec in records {
-        let role = rec
-            .payload
-            .get("role")
-            .and_then(Value::as_str)
-            .unwrap_or("user")
-            .to_string();
-        let content = adapter_common::extract_content_text(rec.payload.get("content"));
+        let role = adapter_common::normalize_role(
+            rec.payload.get("role").and_then(Value::as_str),
+            core_model::Role::User,
+        );
+        let content =
+            adapter_common::extract_content_text_with(rec.payload.get("content"), capture);
         if content.is_empty() {
             continue;
         }
@@ -135,6 +186,12 @@ fn normalize_records(kind: AgentKind, records: &[NativeRecord]) -> NormalizedBat
                 .unwrap_or(kind.as_str())
                 .to_string(),
             source_id: rec.source_id.clone(),
+            workspace: rec
+                .payload
+                .get("__workspace_path")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            repo: None,
         });
     }
     let mut ordered_sessions: Vec<_> = sessions.into_values().map(|entry| entry.session).collect();
@@ -155,23 +212,29 @@ fn normalize_records(kind: AgentKind, records: &[NativeRecord]) -> NormalizedBat
 fn load_thread_json(
     source_paths: &[String],
     cursor: Option<&str>,
+    stats: &ScanStats,
+    tz: SourceTimezone,
 ) -> anyhow::Result<Vec<NativeRecord>> {
     let parsed_cursor = cursor.and_then(adapter_common::parse_cursor);
     let mut out: Vec<NativeRecord> = source_paths
         .par_iter()
         .flat_map(|path| {
+            stats.file_seen();
             let file_mtime = adapter_common::file_mtime(path);
             if let Some(ref cur) = parsed_cursor
                 && let Some(mtime) = file_mtime
                 && mtime <= cur.ts
             {
+                stats.file_skipped();
                 return Vec::new();
             }
 
             let Ok(content) = fs::read_to_string(path) else {
+                stats.file_skipped();
                 return Vec::new();
             };
             let Ok(val): Result<Value, _> = serde_json::from_str(&content) else {
+                stats.parse_errors(1);
                 return Vec::new();
             };
 
@@ -181,13 +244,13 @@ fn load_thread_json(
                 .and_then(Value::as_str)
                 .map(ToOwned::to_owned)
                 .unwrap_or_else(|| thread_id.clone());
-            let thread_ts = extract_timestamp(&val).or_else(|| {
+            let thread_ts = extract_timestamp(&val, tz).or_else(|| {
                 val.get("created")
                     .or_else(|| val.get("createdAt"))
-                    .and_then(extract_timestamp)
+                    .and_then(|v| extract_timestamp(v, tz))
             });
             let workspace_path = extract_workspace_path(&val);
-            let usage_index = build_usage_ledger_index(&val);
+            let usage_index = build_usage_ledger_index(&val, tz);
 
             let messages = val.get("messages").and_then(Value::as_array);
             let Some(messages) = messages else {
@@ -199,14 +262,18 @@ fn load_thread_json(
                 .enumerate()
                 .filter_map(|(idx, message)| {
                     let message_id = parse_message_id(message, idx, &thread_id);
-                    let ts = message_timestamp(
+                    let Some(ts) = message_timestamp(
                         message,
                         &message_id,
                         idx,
                         thread_ts,
                         file_mtime,
                         &usage_index,
-                    );
+                        tz,
+                    ) else {
+                        stats.parse_errors(1);
+                        return None;
+                    };
                     let source_id = format!("{thread_id}:{message_id}");
                     if let Some(ref cur) = parsed_cursor
                         && adapter_common::should_skip(ts, &source_id, cur)
@@ -354,37 +421,34 @@ fn message_timestamp(
     thread_ts: Option<DateTime<Utc>>,
     file_mtime: Option<DateTime<Utc>>,
     usage_index: &UsageLedgerIndex,
-) -> DateTime<Utc> {
-    if let Some(ts) = extract_timestamp(message) {
-        return ts;
+    tz: SourceTimezone,
+) -> Option<DateTime<Utc>> {
+    if let Some(ts) = extract_timestamp(message, tz) {
+        return Some(ts);
     }
 
     if let Some(ts) = usage_index.by_message_id.get(message_id) {
-        return *ts;
+        return Some(*ts);
     }
     if let Some(ts) = usage_index.by_index.get(&idx) {
-        return *ts;
+        return Some(*ts);
     }
 
     if let Some(thread_ts) = thread_ts {
-        return thread_ts + Duration::milliseconds(idx as i64);
-    }
-
-    if let Some(file_mtime) = file_mtime {
-        return file_mtime;
+        return Some(thread_ts + Duration::milliseconds(idx as i64));
     }
 
-    Utc.timestamp_opt(0, 0).single().unwrap_or_else(Utc::now) + Duration::milliseconds(idx as i64)
+    file_mtime
 }
 
-fn build_usage_ledger_index(thread: &Value) -> UsageLedgerIndex {
+fn build_usage_ledger_index(thread: &Value, tz: SourceTimezone) -> UsageLedgerIndex {
     let mut out = UsageLedgerIndex::default();
     let Some(entries) = thread.get("usageLedger").and_then(Value::as_array) else {
         return out;
     };
 
     for (idx, entry) in entries.iter().enumerate() {
-        let Some(ts) = extract_timestamp(entry) else {
+        let Some(ts) = extract_timestamp(entry, tz) else {
             continue;
         };
 
@@ -423,72 +487,38 @@ fn build_usage_ledger_index(thread: &Value) -> UsageLedgerIndex {
     out
 }
 
-fn extract_timestamp(value: &Value) -> Option<DateTime<Utc>> {
-    if let Some(ts) = parse_ts_field(value.get("timestamp")) {
+fn extract_timestamp(value: &Value, tz: SourceTimezone) -> Option<DateTime<Utc>> {
+    if let Some(ts) = parse_ts_field(value.get("timestamp"), tz) {
         return Some(ts);
     }
-    if let Some(ts) = parse_ts_field(value.get("ts")) {
+    if let Some(ts) = parse_ts_field(value.get("ts"), tz) {
         return Some(ts);
     }
-    if let Some(ts) = parse_ts_field(value.get("sentAt")) {
+    if let Some(ts) = parse_ts_field(value.get("sentAt"), tz) {
         return Some(ts);
     }
     if let Some(ts) = value.get("meta").and_then(|meta| {
-        parse_ts_field(meta.get("sentAt")).or_else(|| parse_ts_field(meta.get("timestamp")))
+        parse_ts_field(meta.get("sentAt"), tz).or_else(|| parse_ts_field(meta.get("timestamp"), tz))
     }) {
         return Some(ts);
     }
-    if let Some(ts) = parse_ts_field(value.get("created")) {
+    if let Some(ts) = parse_ts_field(value.get("created"), tz) {
         return Some(ts);
     }
-    if let Some(ts) = parse_ts_field(value.get("createdAt")) {
+    if let Some(ts) = parse_ts_field(value.get("createdAt"), tz) {
         return Some(ts);
     }
     if let Some(ts) = value.get("time").and_then(|time| {
-        parse_ts_field(time.get("created")).or_else(|| parse_ts_field(time.get("timestamp")))
+        parse_ts_field(time.get("created"), tz)
+            .or_else(|| parse_ts_field(time.get("timestamp"), tz))
     }) {
         return Some(ts);
     }
     None
 }
 
-fn parse_ts_field(value: Option<&Value>) -> Option<DateTime<Utc>> {
-    let value = value?;
-    match value {
-        Value::String(s) => parse_rfc3339(s),
-        Value::Number(n) => {
-            if let Some(i) = n.as_i64() {
-                return parse_epoch(i);
-            }
-            if let Some(u) = n.as_u64() {
-                return parse_epoch(u as i64);
-            }
-            let f = n.as_f64()?;
-            if !f.is_finite() {
-                return None;
-            }
-            parse_epoch(f as i64)
-        }
-        _ => None,
-    }
-}
-
-fn parse_epoch(epoch: i64) -> Option<DateTime<Utc>> {
-    let abs = epoch.unsigned_abs();
-    if abs >= 1_000_000_000_000_000_000 {
-        let secs = epoch.div_euclid(1_000_000_000);
-        let nanos = epoch.rem_euclid(1_000_000_000) as u32;
-        return Utc.timestamp_opt(secs, nanos).single();
-    }
-    if abs >= 1_000_000_000_000_000 {
-        let secs = epoch.div_euclid(1_000_000);
-        let micros = epoch.rem_euclid(1_000_000) as u32;
-        return Utc.timestamp_opt(secs, micros * 1_000).single();
-    }
-    if abs >= 1_000_000_000_000 {
-        return Utc.timestamp_millis_opt(epoch).single();
-    }
-    Utc.timestamp_opt(epoch, 0).single()
+fn parse_ts_field(value: Option<&Value>, tz: SourceTimezone) -> Option<DateTime<Utc>> {
+    adapter_common::parse_timestamp_value(value?, tz)
 }
 
 fn parse_rfc3339(input: &str) -> Option<DateTime<Utc>> {
@@ -500,6 +530,8 @@ fn parse_rfc3339(input: &str) -> Option<DateTime<Utc>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use adapter_common::Platform;
+    use chrono::TimeZone;
 
     #[test]
     fn normalize_amp_thread_hashes_session_id_and_preserves_source_ref() {
@@ -518,7 +550,7 @@ mod tests {
             updated_at: ts,
             payload,
         };
-        let batch = normalize_records(AgentKind::Amp, &[rec]);
+        let batch = normalize_records(AgentKind::Amp, &[rec], &CaptureOptions::default());
         assert_eq!(batch.sessions.len(), 1);
         assert_eq!(batch.messages.len(), 1);
         assert_eq!(
@@ -592,7 +624,9 @@ mod tests {
             Some(thread_ts),
             Some(file_ts),
             &usage,
-        );
+            SourceTimezone::Utc,
+        )
+        .unwrap();
         let ts2 = message_timestamp(
             &usage_message,
             "m-usage",
@@ -600,7 +634,9 @@ mod tests {
             Some(thread_ts),
             Some(file_ts),
             &usage,
-        );
+            SourceTimezone::Utc,
+        )
+        .unwrap();
         let ts3 = message_timestamp(
             &thread_only,
             "m-thread",
@@ -608,8 +644,31 @@ mod tests {
             Some(thread_ts),
             Some(file_ts),
             &usage,
+            SourceTimezone::Utc,
+        )
+        .unwrap();
+        let ts4 = message_timestamp(
+            &thread_only,
+            "m-file",
+            0,
+            None,
+            Some(file_ts),
+            &usage,
+            SourceTimezone::Utc,
+        )
+        .unwrap();
+        assert!(
+            message_timestamp(
+                &thread_only,
+                "m-none",
+                0,
+                None,
+                None,
+                &usage,
+                SourceTimezone::Utc
+            )
+            .is_none()
         );
-        let ts4 = message_timestamp(&thread_only, "m-file", 0, None, Some(file_ts), &usage);
 
         assert_eq!(ts1.to_rfc3339(), "2025-01-01T00:00:00+00:00");
         assert_eq!(
@@ -622,31 +681,6 @@ mod tests {
         assert_eq!(ts4, file_ts);
     }
 
-    #[test]
-    fn parse_epoch_handles_negative_nanos_and_micros() {
-        let neg_nanos = -1_700_000_000_123_456_789_i64;
-        let nanos_ts = parse_epoch(neg_nanos).expect("negative nanos timestamp should parse");
-        let expected_nanos = Utc
-            .timestamp_opt(
-                neg_nanos.div_euclid(1_000_000_000),
-                neg_nanos.rem_euclid(1_000_000_000) as u32,
-            )
-            .single()
-            .unwrap();
-        assert_eq!(nanos_ts, expected_nanos);
-
-        let neg_micros = -1_700_000_123_456_789_i64;
-        let micros_ts = parse_epoch(neg_micros).expect("negative micros timestamp should parse");
-        let expected_micros = Utc
-            .timestamp_opt(
-                neg_micros.div_euclid(1_000_000),
-                (neg_micros.rem_euclid(1_000_000) as u32) * 1_000,
-            )
-            .single()
-            .unwrap();
-        assert_eq!(micros_ts, expected_micros);
-    }
-
     #[test]
     fn load_thread_json_uses_deterministic_ordering() {
         let dir = std::env::temp_dir().join(format!("remi_amp_{}", std::process::id()));
@@ -666,9 +700,41 @@ mod tests {
         )
         .unwrap();
 
-        let records = load_thread_json(&[file.to_string_lossy().to_string()], None).unwrap();
+        let records = load_thread_json(
+            &[file.to_string_lossy().to_string()],
+            None,
+            &ScanStats::default(),
+            SourceTimezone::Utc,
+        )
+        .unwrap();
         assert_eq!(records.len(), 2);
         assert_eq!(records[0].source_id, "thread-a:a");
         assert_eq!(records[1].source_id, "thread-a:b");
     }
+
+    #[test]
+    fn discovery_finds_threads_under_each_platform_data_dir() {
+        let home = std::env::temp_dir().join(format!("remi_amp_platforms_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&home);
+        let adapter = AmpAdapter::default();
+        for (platform, dir) in [
+            (Platform::Windows, home.join("AppData/Local/amp/threads")),
+            (
+                Platform::MacOs,
+                home.join("Library/Application Support/amp/threads"),
+            ),
+            (Platform::Linux, home.join(".local/share/amp/threads")),
+        ] {
+            std::fs::create_dir_all(&dir).unwrap();
+            let thread = dir.join("T-1.json");
+            std::fs::write(&thread, "{}").unwrap();
+            let found = adapter.discover_in(&BaseDirs::new(platform, home.clone()));
+            assert!(
+                found.contains(&thread.to_string_lossy().to_string()),
+                "{platform:?}"
+            );
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+        let _ = std::fs::remove_dir_all(&home);
+    }
 }
@@ -0,0 +1,11 @@
+use adapter_common::golden::assert_golden;
+
+#[test]
+fn usage_ledger_fixture_matches_golden() {
+    assert_golden(
+        &amp::AmpAdapter::default(),
+        env!("CARGO_MANIFEST_DIR"),
+        "usage_ledger",
+        "json",
+    );
+}
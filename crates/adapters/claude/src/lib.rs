@@ -1,52 +1,86 @@
-use std::{collections::HashMap, fs};
+use std::collections::{HashMap, hash_map::Entry};
+use std::fs;
+use std::io::{BufRead, BufReader};
 
+use adapter_common::{BaseDirs, CaptureOptions, DiscoveryOptions, ScanStats, SourceTimezone};
+use chrono::{DateTime, Utc};
 use core_model::{
-    AgentAdapter, AgentKind, ArchiveCapability, NativeRecord, NormalizedBatch, deterministic_id,
+    AgentAdapter, AgentKind, ArchiveCapability, NativeRecord, NormalizedBatch, ScanReport,
+    SourceContext, deterministic_id,
 };
 use rayon::prelude::*;
+use serde::Serialize;
 use serde_json::Value;
 use tracing::debug;
 
-pub struct ClaudeAdapter;
+#[derive(Default)]
+pub struct ClaudeAdapter {
+    stats: ScanStats,
+    discovery: DiscoveryOptions,
+    capture: CaptureOptions,
+    timezone: Option<SourceTimezone>,
+}
 
-impl AgentAdapter for ClaudeAdapter {
-    fn kind(&self) -> AgentKind {
-        AgentKind::Claude
+impl ClaudeAdapter {
+    pub fn with_discovery(discovery: DiscoveryOptions) -> Self {
+        Self {
+            discovery,
+            ..Default::default()
+        }
     }
 
-    fn discover_source_paths(&self) -> anyhow::Result<Vec<String>> {
-        let base = dirs::home_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
-        let mut out = Vec::new();
-        out.extend(adapter_common::collect_files_with_ext(
-            &base.join(".claude/transcripts"),
-            "jsonl",
-        ));
-        out.extend(adapter_common::collect_files_with_ext(
-            &base.join(".claude/projects"),
-            "jsonl",
-        ));
-        out.extend(adapter_common::collect_files_with_ext(
-            &base.join(".local/share/claude-code"),
-            "jsonl",
-        ));
-        debug!(files = out.len(), "claude adapter discovered source paths");
-        Ok(out)
+    pub fn with_capture(mut self, capture: CaptureOptions) -> Self {
+        self.capture = capture;
+        self
     }
 
-    fn scan_changes_since(
+    pub fn with_timezone(mut self, timezone: Option<SourceTimezone>) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    pub fn discover_in(&self, dirs: &BaseDirs) -> Vec<String> {
+        let mut roots = vec![
+            dirs.home_path(".claude/transcripts"),
+            dirs.home_path(".claude/projects"),
+        ];
+        roots.extend(dirs.data_paths("claude-code"));
+        roots
+            .iter()
+            .flat_map(|dir| adapter_common::collect_files(dir, "jsonl", &self.discovery))
+            .collect()
+    }
+
+    // Same scan as `scan_changes_since`, also returning one decision for every
+    // candidate that lost to another copy of the same record.
+    pub fn scan_traced(
         &self,
         source_paths: &[String],
         cursor: Option<&str>,
-    ) -> anyhow::Result<Vec<NativeRecord>> {
+    ) -> (Vec<NativeRecord>, Vec<DedupeDecision>) {
+        self.scan(source_paths, cursor, true)
+    }
+
+    fn scan(
+        &self,
+        source_paths: &[String],
+        cursor: Option<&str>,
+        trace: bool,
+    ) -> (Vec<NativeRecord>, Vec<DedupeDecision>) {
+        self.stats.reset();
+        let stats = &self.stats;
+        let tz = SourceTimezone::for_agent(self.kind(), self.timezone);
         let parsed_cursor = cursor.and_then(adapter_common::parse_cursor);
         let candidates: Vec<CandidateRecord> = source_paths
             .par_iter()
             .flat_map(|path| {
+                stats.file_seen();
                 let file_mtime = adapter_common::file_mtime(path);
                 if let Some(ref cur) = parsed_cursor
                     && let Some(mtime) = file_mtime
                     && mtime <= cur.ts
                 {
+                    stats.file_skipped();
                     return Vec::new();
                 }
 
@@ -59,6 +93,7 @@ impl AgentAdapter for ClaudeAdapter {
                     .to_string();
 
                 let Ok(content) = fs::read_to_string(path) else {
+                    stats.file_skipped();
                     return Vec::new();
                 };
 
@@ -71,10 +106,14 @@ impl AgentAdapter for ClaudeAdapter {
                         }
 
                         let line_number = line_idx + 1;
-                        let mut val: Value = serde_json::from_str(line).ok()?;
-                        let ts = adapter_common::extract_ts(&val)
-                            .or(file_mtime)
-                            .unwrap_or_else(chrono::Utc::now);
+                        let Ok(mut val) = serde_json::from_str::<Value>(line) else {
+                            stats.parse_errors(1);
+                            return None;
+                        };
+                        let Some(ts) = adapter_common::extract_ts(&val, tz).or(file_mtime) else {
+                            stats.parse_errors(1);
+                            return None;
+                        };
 
                         let source_id = extract_message_identity(&val).unwrap_or_else(|| {
                             deterministic_id(&["claude", path, &line_number.to_string(), line])
@@ -107,6 +146,7 @@ impl AgentAdapter for ClaudeAdapter {
                             dedupe_key,
                             priority,
                             richness,
+                            line: line_number,
                             record: NativeRecord {
                                 source_id,
                                 updated_at: ts,
@@ -118,16 +158,25 @@ impl AgentAdapter for ClaudeAdapter {
             })
             .collect();
 
+        let mut decisions = Vec::new();
         let mut deduped: HashMap<String, CandidateRecord> = HashMap::new();
         for candidate in candidates {
-            deduped
-                .entry(candidate.dedupe_key.clone())
-                .and_modify(|existing| {
-                    if should_replace(existing, &candidate) {
-                        *existing = candidate.clone();
+            match deduped.entry(candidate.dedupe_key.clone()) {
+                Entry::Vacant(slot) => {
+                    slot.insert(candidate);
+                }
+                Entry::Occupied(mut slot) => {
+                    let (replace, reason) = compare(slot.get(), &candidate);
+                    let dropped = if replace {
+                        slot.insert(candidate)
+                    } else {
+                        candidate
+                    };
+                    if trace {
+                        decisions.push(DedupeDecision::new(slot.get(), &dropped, reason));
                     }
-                })
-                .or_insert(candidate);
+                }
+            }
         }
 
         let mut out: Vec<NativeRecord> = deduped.into_values().map(|c| c.record).collect();
@@ -137,12 +186,47 @@ impl AgentAdapter for ClaudeAdapter {
                 .then_with(|| a.source_id.cmp(&b.source_id))
         });
         debug!(total = out.len(), "claude scan complete");
+        (out, decisions)
+    }
+}
+
+impl AgentAdapter for ClaudeAdapter {
+    fn kind(&self) -> AgentKind {
+        AgentKind::Claude
+    }
+
+    fn discover_source_paths(&self, ctx: &SourceContext) -> anyhow::Result<Vec<String>> {
+        let Some(dirs) = adapter_common::discovery_dirs(ctx, self.kind()) else {
+            return Ok(Vec::new());
+        };
+        let out = self.discover_in(&dirs);
+        debug!(files = out.len(), "claude adapter discovered source paths");
         Ok(out)
     }
 
+    fn scan_changes_since(
+        &self,
+        source_paths: &[String],
+        cursor: Option<&str>,
+    ) -> anyhow::Result<Vec<NativeRecord>> {
+        let trace = tracing::enabled!(tracing::Level::DEBUG);
+        let (records, decisions) = self.scan(source_paths, cursor, trace);
+        for d in &decisions {
+            debug!(
+                key = %d.key,
+                reason = d.reason.as_str(),
+                kept = %d.kept,
+                dropped = %d.dropped,
+                same_content = d.same_content,
+                "claude dedupe dropped candidate"
+            );
+        }
+        Ok(records)
+    }
+
     fn normalize(&self, records: &[NativeRecord]) -> anyhow::Result<NormalizedBatch> {
         debug!(records = records.len(), "normalizing claude records");
-        normalize_records(AgentKind::Claude, records)
+        normalize_records(AgentKind::Claude, records, &self.capture)
     }
 
     fn checkpoint_cursor(&self, records: &[NativeRecord]) -> Option<String> {
@@ -152,16 +236,144 @@ impl AgentAdapter for ClaudeAdapter {
     fn archive_capability(&self) -> ArchiveCapability {
         ArchiveCapability::CentralizedCopy
     }
+
+    fn scan_report(&self) -> Option<ScanReport> {
+        Some(self.stats.report())
+    }
+
+    fn source_label(&self) -> String {
+        "Claude Code".to_string()
+    }
+
+    // Copies of a session under projects, transcripts and the data dir, and
+    // its sidechain `agent-*` files, all carry the same `sessionId`; each
+    // group is deduped as one scan.
+    fn scan_groups(&self, source_paths: &[String]) -> Vec<Vec<String>> {
+        let mut groups: Vec<Vec<String>> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        for path in source_paths {
+            match index.entry(group_key(path)) {
+                Entry::Occupied(slot) => groups[*slot.get()].push(path.clone()),
+                Entry::Vacant(slot) => {
+                    slot.insert(groups.len());
+                    groups.push(vec![path.clone()]);
+                }
+            }
+        }
+        groups
+    }
+}
+
+// Lines at the head of a file checked for a session id before falling back to
+// the file stem; summary lines carry none.
+const GROUP_KEY_LINES: usize = 8;
+
+fn group_key(path: &str) -> String {
+    fs::File::open(path)
+        .ok()
+        .and_then(|file| {
+            BufReader::new(file)
+                .lines()
+                .map_while(Result::ok)
+                .take(GROUP_KEY_LINES)
+                .filter_map(|line| serde_json::from_str::<Value>(&line).ok())
+                .find_map(|val| val.get("sessionId")?.as_str().map(str::to_string))
+        })
+        .unwrap_or_else(|| {
+            std::path::Path::new(path)
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or(path)
+                .to_string()
+        })
 }
 
-#[derive(Clone)]
 struct CandidateRecord {
     dedupe_key: String,
     priority: i64,
     richness: usize,
+    line: usize,
     record: NativeRecord,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DedupeReason {
+    Priority,
+    Richness,
+    Timestamp,
+    SourceId,
+}
+
+impl DedupeReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Priority => "priority",
+            Self::Richness => "richness",
+            Self::Timestamp => "timestamp",
+            Self::SourceId => "source_id",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DedupeCandidate {
+    pub source_path: String,
+    pub line: usize,
+    pub source_id: String,
+    pub priority: i64,
+    pub richness: usize,
+    pub ts: DateTime<Utc>,
+}
+
+impl DedupeCandidate {
+    fn new(candidate: &CandidateRecord) -> Self {
+        let payload = &candidate.record.payload;
+        Self {
+            source_path: payload
+                .get("__source_path")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            line: candidate.line,
+            source_id: candidate.record.source_id.clone(),
+            priority: candidate.priority,
+            richness: candidate.richness,
+            ts: candidate.record.updated_at,
+        }
+    }
+}
+
+impl std::fmt::Display for DedupeCandidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.source_path, self.line)
+    }
+}
+
+// `same_content` is false when the dropped copy's text differs from the kept
+// one, which is the case worth checking by hand.
+#[derive(Debug, Clone, Serialize)]
+pub struct DedupeDecision {
+    pub key: String,
+    pub reason: DedupeReason,
+    pub same_content: bool,
+    pub kept: DedupeCandidate,
+    pub dropped: DedupeCandidate,
+}
+
+impl DedupeDecision {
+    fn new(kept: &CandidateRecord, dropped: &CandidateRecord, reason: DedupeReason) -> Self {
+        Self {
+            key: kept.dedupe_key.clone(),
+            reason,
+            same_content: content_text(&kept.record.payload)
+                == content_text(&dropped.record.payload),
+            kept: DedupeCandidate::new(kept),
+            dropped: DedupeCandidate::new(dropped),
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 enum SourceKind {
     Project,
@@ -170,6 +382,7 @@ enum SourceKind {
 }
 
 fn source_kind(path: &str) -> SourceKind {
+    let path = path.replace('\\', "/");
     if path.contains(".claude/projects") {
         SourceKind::Project
     } else if path.contains(".claude/transcripts") {
@@ -187,17 +400,30 @@ fn source_priority(kind: SourceKind) -> i64 {
     }
 }
 
-fn should_replace(existing: &CandidateRecord, candidate: &CandidateRecord) -> bool {
+// Whether `candidate` replaces `existing`, and the first rule that decided it.
+fn compare(existing: &CandidateRecord, candidate: &CandidateRecord) -> (bool, DedupeReason) {
     if candidate.priority != existing.priority {
-        return candidate.priority > existing.priority;
+        return (
+            candidate.priority > existing.priority,
+            DedupeReason::Priority,
+        );
     }
     if candidate.richness != existing.richness {
-        return candidate.richness > existing.richness;
+        return (
+            candidate.richness > existing.richness,
+            DedupeReason::Richness,
+        );
     }
     if candidate.record.updated_at != existing.record.updated_at {
-        return candidate.record.updated_at > existing.record.updated_at;
+        return (
+            candidate.record.updated_at > existing.record.updated_at,
+            DedupeReason::Timestamp,
+        );
     }
-    candidate.record.source_id < existing.record.source_id
+    (
+        candidate.record.source_id < existing.record.source_id,
+        DedupeReason::SourceId,
+    )
 }
 
 fn dedupe_key(
@@ -246,14 +472,16 @@ fn payload_richness(payload: &Value) -> usize {
     if payload.get("slug").and_then(Value::as_str).is_some() {
         score += 10;
     }
+    score + content_text(payload).len().min(1_000)
+}
+
+fn content_text(payload: &Value) -> String {
     let message_node = if payload.get("message").is_some_and(Value::is_object) {
         payload.get("message")
     } else {
         Some(payload)
     };
-    let content =
-        adapter_common::extract_content_text(message_node.and_then(|node| node.get("content")));
-    score + content.len().min(1_000)
+    adapter_common::extract_content_text(message_node.and_then(|node| node.get("content")))
 }
 
 fn extract_message_identity(payload: &Value) -> Option<String> {
@@ -314,7 +542,11 @@ fn fallback_session_key_from_path(source_path: Option<&str>) -> Option<String> {
         .map(ToOwned::to_owned)
 }
 
-fn normalize_records(kind: AgentKind, records: &[NativeRecord]) -> anyhow::Result<NormalizedBatch> {
+fn normalize_records(
+    kind: AgentKind,
+    records: &[NativeRecord],
+    capture: &CaptureOptions,
+) -> anyhow::Result<NormalizedBatch> {
     let mut batch = NormalizedBatch::default();
     let mut sessions: HashMap<String, core_model::Session> = HashMap::new();
 
@@ -328,18 +560,14 @@ fn normalize_records(kind: AgentKind, records: &[NativeRecord]) -> anyhow::Resul
             continue;
         };
 
-        let role = message
-            .get("role")
-            .and_then(Value::as_str)
-            .or_else(|| {
-                rec.payload
-                    .get("type")
-                    .and_then(Value::as_str)
-                    .filter(|t| matches!(*t, "user" | "assistant" | "system" | "tool"))
-            })
-            .unwrap_or("user")
-            .to_string();
-        let content = adapter_common::extract_content_text(message.get("content"));
+        let role = adapter_common::normalize_role(
+            message
+                .get("role")
+                .and_then(Value::as_str)
+                .or_else(|| rec.payload.get("type").and_then(Value::as_str)),
+            core_model::Role::User,
+        );
+        let content = adapter_common::extract_content_text_with(message.get("content"), capture);
         if content.is_empty() {
             continue;
         }
@@ -394,6 +622,12 @@ fn normalize_records(kind: AgentKind, records: &[NativeRecord]) -> anyhow::Resul
             agent: kind,
             source_path: source_path.unwrap_or(kind.as_str()).to_string(),
             source_id: rec.source_id.clone(),
+            workspace: rec
+                .payload
+                .get("cwd")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            repo: None,
         });
     }
 
@@ -415,6 +649,7 @@ fn normalize_records(kind: AgentKind, records: &[NativeRecord]) -> anyhow::Resul
 #[cfg(test)]
 mod tests {
     use super::*;
+    use adapter_common::Platform;
     use chrono::Utc;
     use core_model::NativeRecord;
 
@@ -429,7 +664,8 @@ mod tests {
                 "sessionId": "s1"
             }),
         };
-        let batch = normalize_records(AgentKind::Claude, &[rec]).unwrap();
+        let batch =
+            normalize_records(AgentKind::Claude, &[rec], &CaptureOptions::default()).unwrap();
         assert_eq!(batch.messages.len(), 1);
         assert_eq!(batch.messages[0].role, "user");
     }
@@ -445,7 +681,8 @@ mod tests {
                 "sessionId": "s1"
             }),
         };
-        let batch = normalize_records(AgentKind::Claude, &[rec]).unwrap();
+        let batch =
+            normalize_records(AgentKind::Claude, &[rec], &CaptureOptions::default()).unwrap();
         assert_eq!(batch.messages.len(), 1);
         assert_eq!(batch.messages[0].role, "assistant");
     }
@@ -462,7 +699,8 @@ mod tests {
                 "sessionId": "s1"
             }),
         };
-        let batch = normalize_records(AgentKind::Claude, &[rec]).unwrap();
+        let batch =
+            normalize_records(AgentKind::Claude, &[rec], &CaptureOptions::default()).unwrap();
         assert_eq!(batch.messages.len(), 1);
         assert_eq!(batch.messages[0].content, "direct text");
     }
@@ -479,13 +717,14 @@ mod tests {
                 "slug": "my-conversation"
             }),
         };
-        let batch = normalize_records(AgentKind::Claude, &[rec]).unwrap();
+        let batch =
+            normalize_records(AgentKind::Claude, &[rec], &CaptureOptions::default()).unwrap();
         assert_eq!(batch.sessions[0].title, "my-conversation");
     }
 
     #[test]
     fn scan_prefers_project_source_for_same_message_id() {
-        let adapter = ClaudeAdapter;
+        let adapter = ClaudeAdapter::default();
         let dir = std::env::temp_dir().join(format!("remi_claude_pref_{}", std::process::id()));
         let _ = std::fs::remove_dir_all(&dir);
         let projects_dir = dir.join(".claude/projects");
@@ -521,9 +760,57 @@ mod tests {
         assert!(source_path.contains(".claude/projects"));
     }
 
+    #[test]
+    fn scan_traced_explains_dropped_candidates() {
+        let adapter = ClaudeAdapter::default();
+        let dir = std::env::temp_dir().join(format!("remi_claude_trace_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let projects_dir = dir.join(".claude/projects");
+        std::fs::create_dir_all(&projects_dir).unwrap();
+        let project_file = projects_dir.join("proj.jsonl");
+        let share_file = dir.join("share.jsonl");
+        let copy_file = dir.join("copy.jsonl");
+        std::fs::write(
+            &project_file,
+            r#"{"id":"msg-1","role":"assistant","content":"kept","timestamp":"2025-01-15T00:00:00+00:00","sessionId":"s1"}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            &share_file,
+            r#"{"id":"msg-1","role":"assistant","content":"edited","timestamp":"2025-01-15T00:00:00+00:00","sessionId":"s1"}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            &copy_file,
+            r#"{"id":"msg-2","role":"user","content":"hi","timestamp":"2025-01-15T00:00:00+00:00","sessionId":"s1","slug":"x"}
+{"id":"msg-2","role":"user","content":"hi","timestamp":"2025-01-15T00:00:00+00:00","sessionId":"s1"}"#,
+        )
+        .unwrap();
+
+        let paths: Vec<String> = [&share_file, &project_file, &copy_file]
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        let (records, mut decisions) = adapter.scan_traced(&paths, None);
+        assert_eq!(records.len(), 2);
+        assert_eq!(decisions.len(), 2);
+        decisions.sort_by(|a, b| a.key.cmp(&b.key));
+
+        assert_eq!(decisions[0].key, "id:msg-1");
+        assert_eq!(decisions[0].reason, DedupeReason::Priority);
+        assert!(decisions[0].kept.source_path.contains(".claude/projects"));
+        assert!(decisions[0].dropped.source_path.ends_with("share.jsonl"));
+        assert!(!decisions[0].same_content);
+
+        assert_eq!(decisions[1].reason, DedupeReason::Richness);
+        assert_eq!((decisions[1].kept.line, decisions[1].dropped.line), (1, 2));
+        assert!(decisions[1].same_content);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn scan_dedupes_overlapping_sources_without_ids() {
-        let adapter = ClaudeAdapter;
+        let adapter = ClaudeAdapter::default();
         let dir = std::env::temp_dir().join(format!("remi_claude_overlap_{}", std::process::id()));
         let _ = std::fs::remove_dir_all(&dir);
         let projects_dir = dir.join(".claude/projects");
@@ -544,11 +831,12 @@ mod tests {
         ];
         let records = adapter.scan_changes_since(&paths, None).unwrap();
         assert_eq!(records.len(), 1);
+        assert_eq!(adapter.scan_groups(&paths), vec![paths.clone()]);
     }
 
     #[test]
     fn scan_keeps_repeated_no_id_lines_from_same_file() {
-        let adapter = ClaudeAdapter;
+        let adapter = ClaudeAdapter::default();
         let dir = std::env::temp_dir().join(format!("remi_claude_repeat_{}", std::process::id()));
         let _ = std::fs::remove_dir_all(&dir);
         std::fs::create_dir_all(&dir).unwrap();
@@ -589,14 +877,15 @@ mod tests {
             },
         ];
 
-        let batch = normalize_records(AgentKind::Claude, &records).unwrap();
+        let batch =
+            normalize_records(AgentKind::Claude, &records, &CaptureOptions::default()).unwrap();
         assert_eq!(batch.sessions.len(), 1);
         assert_eq!(batch.messages.len(), 2);
     }
 
     #[test]
     fn malformed_line_skipped() {
-        let adapter = ClaudeAdapter;
+        let adapter = ClaudeAdapter::default();
         let dir =
             std::env::temp_dir().join(format!("remi_claude_malformed_{}", std::process::id()));
         let _ = std::fs::remove_dir_all(&dir);
@@ -611,5 +900,34 @@ mod tests {
         let records = adapter.scan_changes_since(&paths, None).unwrap();
         assert_eq!(records.len(), 1);
         assert_eq!(records[0].source_id, "1");
+        let report = adapter.scan_report().unwrap();
+        assert_eq!(report.files_seen, 1);
+        assert_eq!(report.parse_errors, 1);
+    }
+
+    #[test]
+    fn discovery_covers_home_and_platform_data_dirs() {
+        let home =
+            std::env::temp_dir().join(format!("remi_claude_platforms_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&home);
+        let project = home.join(".claude/projects/p/s.jsonl");
+        let roaming = home.join("AppData/Roaming/claude-code/s.jsonl");
+        let mac = home.join("Library/Application Support/claude-code/s.jsonl");
+        for file in [&project, &roaming, &mac] {
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, "").unwrap();
+        }
+        let adapter = ClaudeAdapter::default();
+        let windows = adapter.discover_in(&BaseDirs::new(Platform::Windows, home.clone()));
+        let macos = adapter.discover_in(&BaseDirs::new(Platform::MacOs, home.clone()));
+        let path = |p: &std::path::Path| p.to_string_lossy().to_string();
+        assert!(windows.contains(&path(&project)) && windows.contains(&path(&roaming)));
+        assert!(!windows.contains(&path(&mac)));
+        assert!(macos.contains(&path(&project)) && macos.contains(&path(&mac)));
+        assert!(matches!(
+            source_kind(r"C:\Users\u\.claude\projects\p\s.jsonl"),
+            SourceKind::Project
+        ));
+        let _ = std::fs::remove_dir_all(&home);
     }
 }
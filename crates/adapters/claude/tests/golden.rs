@@ -0,0 +1,11 @@
+use adapter_common::golden::assert_golden;
+
+#[test]
+fn dedupe_fixture_matches_golden() {
+    assert_golden(
+        &claude::ClaudeAdapter::default(),
+        env!("CARGO_MANIFEST_DIR"),
+        "dedupe",
+        "jsonl",
+    );
+}
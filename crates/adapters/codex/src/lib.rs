@@ -1,23 +1,56 @@
-use std::{fs, io::BufRead, path::PathBuf};
+use std::{fs, io::BufRead};
 
+use adapter_common::{BaseDirs, CaptureOptions, DiscoveryOptions, ScanStats, SourceTimezone};
 use chrono::{DateTime, Utc};
 use core_model::{
-    AgentAdapter, AgentKind, ArchiveCapability, NativeRecord, NormalizedBatch, deterministic_id,
+    AgentAdapter, AgentKind, ArchiveCapability, NativeRecord, NormalizedBatch, ScanReport,
+    SourceContext, deterministic_id,
 };
 use rayon::prelude::*;
 use serde_json::Value;
 use tracing::debug;
 
-pub struct CodexAdapter;
+#[derive(Default)]
+pub struct CodexAdapter {
+    stats: ScanStats,
+    discovery: DiscoveryOptions,
+    capture: CaptureOptions,
+    timezone: Option<SourceTimezone>,
+}
+
+impl CodexAdapter {
+    pub fn with_discovery(discovery: DiscoveryOptions) -> Self {
+        Self {
+            discovery,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_capture(mut self, capture: CaptureOptions) -> Self {
+        self.capture = capture;
+        self
+    }
+
+    pub fn with_timezone(mut self, timezone: Option<SourceTimezone>) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    pub fn discover_in(&self, dirs: &BaseDirs) -> Vec<String> {
+        adapter_common::collect_files(&dirs.home_path(".codex/sessions"), "jsonl", &self.discovery)
+    }
+}
 
 impl AgentAdapter for CodexAdapter {
     fn kind(&self) -> AgentKind {
         AgentKind::Codex
     }
 
-    fn discover_source_paths(&self) -> anyhow::Result<Vec<String>> {
-        let base = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
-        let paths = adapter_common::collect_files_with_ext(&base.join(".codex/sessions"), "jsonl");
+    fn discover_source_paths(&self, ctx: &SourceContext) -> anyhow::Result<Vec<String>> {
+        let Some(dirs) = adapter_common::discovery_dirs(ctx, self.kind()) else {
+            return Ok(Vec::new());
+        };
+        let paths = self.discover_in(&dirs);
         debug!(files = paths.len(), "codex adapter discovered source paths");
         Ok(paths)
     }
@@ -27,11 +60,18 @@ impl AgentAdapter for CodexAdapter {
         source_paths: &[String],
         cursor: Option<&str>,
     ) -> anyhow::Result<Vec<NativeRecord>> {
-        load_rollout_jsonl(source_paths, cursor)
+        self.stats.reset();
+        load_rollout_jsonl(
+            source_paths,
+            cursor,
+            &self.stats,
+            SourceTimezone::for_agent(self.kind(), self.timezone),
+            &self.capture,
+        )
     }
 
     fn normalize(&self, records: &[NativeRecord]) -> anyhow::Result<NormalizedBatch> {
-        Ok(normalize_records(records))
+        Ok(normalize_records(records, &self.capture))
     }
 
     fn checkpoint_cursor(&self, records: &[NativeRecord]) -> Option<String> {
@@ -41,6 +81,14 @@ impl AgentAdapter for CodexAdapter {
     fn archive_capability(&self) -> ArchiveCapability {
         ArchiveCapability::CentralizedCopy
     }
+
+    fn scan_report(&self) -> Option<ScanReport> {
+        Some(self.stats.report())
+    }
+
+    fn source_label(&self) -> String {
+        "Codex".to_string()
+    }
 }
 
 fn parse_rfc3339(input: &str) -> Option<DateTime<Utc>> {
@@ -52,22 +100,30 @@ fn parse_rfc3339(input: &str) -> Option<DateTime<Utc>> {
 fn load_rollout_jsonl(
     source_paths: &[String],
     cursor: Option<&str>,
+    stats: &ScanStats,
+    tz: SourceTimezone,
+    capture: &CaptureOptions,
 ) -> anyhow::Result<Vec<NativeRecord>> {
     let parsed_cursor = cursor.and_then(adapter_common::parse_cursor);
     let mut out: Vec<NativeRecord> = source_paths
         .par_iter()
         .flat_map(|path| {
+            stats.file_seen();
             let file_mtime = adapter_common::file_mtime(path);
             if let Some(ref cur) = parsed_cursor
                 && let Some(mtime) = file_mtime
                 && mtime <= cur.ts
             {
+                stats.file_skipped();
                 return Vec::new();
             }
 
             let file = match fs::File::open(path) {
                 Ok(f) => f,
-                Err(_) => return Vec::new(),
+                Err(_) => {
+                    stats.file_skipped();
+                    return Vec::new();
+                }
             };
             let reader = std::io::BufReader::new(file);
             let lines: Vec<String> = reader.lines().map_while(Result::ok).collect();
@@ -81,6 +137,7 @@ fn load_rollout_jsonl(
             let mut first_user_text: Option<String> = None;
             let mut records = Vec::new();
             let mut msg_index = 0usize;
+            let mut system_index = 0usize;
 
             for line in &lines {
                 let trimmed = line.trim();
@@ -88,16 +145,19 @@ fn load_rollout_jsonl(
                     continue;
                 }
                 let Ok(val): Result<Value, _> = serde_json::from_str(trimmed) else {
+                    stats.parse_errors(1);
                     continue;
                 };
 
                 let line_type = val.get("type").and_then(Value::as_str).unwrap_or("");
-                let line_ts = val
+                let Some(line_ts) = val
                     .get("timestamp")
-                    .and_then(Value::as_str)
-                    .and_then(parse_rfc3339)
+                    .and_then(|v| adapter_common::parse_timestamp_value(v, tz))
                     .or(file_mtime)
-                    .unwrap_or_else(Utc::now);
+                else {
+                    stats.parse_errors(1);
+                    continue;
+                };
 
                 match line_type {
                     "session_meta" => {
@@ -124,12 +184,15 @@ fn load_rollout_jsonl(
                             .get("role")
                             .and_then(Value::as_str)
                             .unwrap_or("user");
-                        if role == "developer" || role == "system" {
+                        let is_system = role == "developer" || role == "system";
+                        if is_system && !capture.system_prompts {
                             continue;
                         }
 
-                        let content_text =
-                            adapter_common::extract_content_text(payload.get("content"));
+                        let content_text = adapter_common::extract_content_text_with(
+                            payload.get("content"),
+                            capture,
+                        );
                         if content_text.is_empty() {
                             continue;
                         }
@@ -147,8 +210,19 @@ fn load_rollout_jsonl(
                         } else {
                             session_id.clone()
                         };
-                        let source_id = format!("{sid}:{msg_index}");
-                        msg_index += 1;
+                        // System prompts use their own counter so toggling capture
+                        // leaves the ids of regular messages unchanged.
+                        let source_id = if is_system {
+                            system_index += 1;
+                            format!("{sid}:system:{}", system_index - 1)
+                        } else {
+                            msg_index += 1;
+                            format!("{sid}:{}", msg_index - 1)
+                        };
+                        let native_id = payload
+                            .get("id")
+                            .and_then(Value::as_str)
+                            .filter(|id| !id.is_empty());
 
                         if let Some(ref cur) = parsed_cursor
                             && adapter_common::should_skip(line_ts, &source_id, cur)
@@ -173,8 +247,18 @@ fn load_rollout_jsonl(
                         if let Some(content) = payload.get("content") {
                             obj.insert("content".to_string(), content.clone());
                         }
+                        if let Some(native_id) = native_id {
+                            obj.insert(
+                                "__message_key".to_string(),
+                                Value::String(format!("{sid}:id:{native_id}")),
+                            );
+                        }
+                        if is_system {
+                            obj.insert("__system".to_string(), Value::Bool(true));
+                        } else {
+                            obj.insert("__thread_title".to_string(), Value::String(title));
+                        }
                         obj.insert("__thread_id".to_string(), Value::String(sid.clone()));
-                        obj.insert("__thread_title".to_string(), Value::String(title));
                         if let Some(ts) = session_ts {
                             obj.insert("__thread_ts".to_string(), Value::String(ts.to_rfc3339()));
                         }
@@ -205,21 +289,22 @@ fn load_rollout_jsonl(
     Ok(out)
 }
 
-fn normalize_records(records: &[NativeRecord]) -> NormalizedBatch {
+fn normalize_records(records: &[NativeRecord], capture: &CaptureOptions) -> NormalizedBatch {
     let kind = AgentKind::Codex;
     debug!(records = records.len(), "normalizing codex records");
     let mut batch = NormalizedBatch::default();
     let mut sessions: std::collections::HashMap<String, core_model::Session> =
         std::collections::HashMap::new();
+    let mut system_prompts: std::collections::BTreeMap<String, Vec<Value>> =
+        std::collections::BTreeMap::new();
 
     for rec in records {
-        let role = rec
-            .payload
-            .get("role")
-            .and_then(Value::as_str)
-            .unwrap_or("user")
-            .to_string();
-        let content = adapter_common::extract_content_text(rec.payload.get("content"));
+        let role = adapter_common::normalize_role(
+            rec.payload.get("role").and_then(Value::as_str),
+            core_model::Role::User,
+        );
+        let content =
+            adapter_common::extract_content_text_with(rec.payload.get("content"), capture);
         if content.is_empty() {
             continue;
         }
@@ -230,12 +315,16 @@ fn normalize_records(records: &[NativeRecord]) -> NormalizedBatch {
             .and_then(Value::as_str)
             .unwrap_or(&rec.source_id)
             .to_string();
-        let title = rec
-            .payload
-            .get("__thread_title")
-            .and_then(Value::as_str)
-            .unwrap_or(&thread_id)
-            .to_string();
+        let is_system = rec.payload.get("__system").and_then(Value::as_bool) == Some(true);
+        let title = if is_system {
+            String::new()
+        } else {
+            rec.payload
+                .get("__thread_title")
+                .and_then(Value::as_str)
+                .unwrap_or(&thread_id)
+                .to_string()
+        };
         let created_at = rec
             .payload
             .get("__thread_ts")
@@ -244,7 +333,8 @@ fn normalize_records(records: &[NativeRecord]) -> NormalizedBatch {
             .unwrap_or(rec.updated_at);
 
         let session_id = deterministic_id(&[kind.as_str(), "session", &thread_id]);
-        let message_id = deterministic_id(&[kind.as_str(), "message", &rec.source_id]);
+        let message_id =
+            deterministic_id(&[kind.as_str(), "message", adapter_common::message_key(rec)]);
         let session = sessions
             .entry(session_id.clone())
             .or_insert_with(|| core_model::Session {
@@ -264,6 +354,17 @@ fn normalize_records(records: &[NativeRecord]) -> NormalizedBatch {
         if session.title.is_empty() && !title.is_empty() {
             session.title = title;
         }
+        if is_system {
+            system_prompts
+                .entry(session_id)
+                .or_default()
+                .push(serde_json::json!({
+                    "role": rec.payload.get("role").and_then(Value::as_str).unwrap_or("system"),
+                    "content": content,
+                    "ts": rec.updated_at.to_rfc3339(),
+                }));
+            continue;
+        }
 
         batch.messages.push(core_model::Message {
             id: message_id.clone(),
@@ -285,16 +386,40 @@ fn normalize_records(records: &[NativeRecord]) -> NormalizedBatch {
                 .unwrap_or(kind.as_str())
                 .to_string(),
             source_id: rec.source_id.clone(),
+            workspace: rec
+                .payload
+                .get("__workspace_path")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            repo: None,
         });
     }
 
-    let mut ordered_sessions: Vec<_> = sessions.into_values().collect();
+    let mut ordered_sessions: Vec<_> = sessions
+        .into_values()
+        .map(|mut session| {
+            if session.title.is_empty() {
+                session.title = session.source_ref.clone();
+            }
+            session
+        })
+        .collect();
     ordered_sessions.sort_by(|a, b| {
         a.updated_at
             .cmp(&b.updated_at)
             .then_with(|| a.id.cmp(&b.id))
     });
     batch.sessions.extend(ordered_sessions);
+    batch
+        .session_meta
+        .extend(
+            system_prompts
+                .into_iter()
+                .map(|(session_id, prompts)| core_model::SessionMeta {
+                    session_id,
+                    meta: serde_json::json!({ "system_prompts": prompts }),
+                }),
+        );
     debug!(
         sessions = batch.sessions.len(),
         messages = batch.messages.len(),
@@ -306,6 +431,7 @@ fn normalize_records(records: &[NativeRecord]) -> NormalizedBatch {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use adapter_common::Platform;
     use std::io::Write;
 
     fn tempdir() -> std::path::PathBuf {
@@ -339,7 +465,14 @@ mod tests {
                 r#"{"timestamp":"2025-01-15T10:30:02Z","type":"response_item","payload":{"type":"message","role":"assistant","content":[{"type":"output_text","text":"hi there"}]}}"#,
             ],
         );
-        let records = load_rollout_jsonl(&[path], None).unwrap();
+        let records = load_rollout_jsonl(
+            &[path],
+            None,
+            &ScanStats::default(),
+            SourceTimezone::Utc,
+            &CaptureOptions::default(),
+        )
+        .unwrap();
         assert_eq!(records.len(), 2);
         assert_eq!(records[0].source_id, "sess-1:0");
         assert_eq!(records[1].source_id, "sess-1:1");
@@ -400,7 +533,7 @@ mod tests {
             },
         ];
 
-        let batch = normalize_records(&records);
+        let batch = normalize_records(&records, &CaptureOptions::default());
         assert_eq!(batch.sessions.len(), 1);
         assert_eq!(batch.messages.len(), 2);
         assert_eq!(batch.provenance.len(), 2);
@@ -414,6 +547,42 @@ mod tests {
         assert_eq!(batch.provenance[0].source_path, "/home/user/project");
     }
 
+    #[test]
+    fn message_ids_follow_native_ids_across_rewrites() {
+        let dir = tempdir();
+        let meta = r#"{"timestamp":"2025-01-15T10:30:00Z","type":"session_meta","payload":{"id":"sess-3","cwd":"/tmp"}}"#;
+        let first = r#"{"timestamp":"2025-01-15T10:30:01Z","type":"response_item","payload":{"type":"message","id":"msg_a","role":"user","content":[{"type":"input_text","text":"first"}]}}"#;
+        let second = r#"{"timestamp":"2025-01-15T10:30:02Z","type":"response_item","payload":{"type":"message","id":"msg_b","role":"assistant","content":[{"type":"output_text","text":"second"}]}}"#;
+        let path = write_rollout(&dir, &[meta, first, second]);
+        let before = normalize_records(
+            &load_rollout_jsonl(
+                &[path],
+                None,
+                &ScanStats::default(),
+                SourceTimezone::Utc,
+                &CaptureOptions::default(),
+            )
+            .unwrap(),
+            &CaptureOptions::default(),
+        );
+
+        let path = write_rollout(&dir, &[meta, second]);
+        let after = normalize_records(
+            &load_rollout_jsonl(
+                &[path],
+                None,
+                &ScanStats::default(),
+                SourceTimezone::Utc,
+                &CaptureOptions::default(),
+            )
+            .unwrap(),
+            &CaptureOptions::default(),
+        );
+
+        assert_eq!(after.messages.len(), 1);
+        assert_eq!(after.messages[0].id, before.messages[1].id);
+    }
+
     #[test]
     fn skip_developer_and_system_roles() {
         let dir = tempdir();
@@ -427,7 +596,14 @@ mod tests {
                 r#"{"timestamp":"2025-01-15T10:30:04Z","type":"response_item","payload":{"type":"message","role":"assistant","content":[{"type":"output_text","text":"real answer"}]}}"#,
             ],
         );
-        let records = load_rollout_jsonl(&[path], None).unwrap();
+        let records = load_rollout_jsonl(
+            &[path],
+            None,
+            &ScanStats::default(),
+            SourceTimezone::Utc,
+            &CaptureOptions::default(),
+        )
+        .unwrap();
         assert_eq!(records.len(), 2);
         let roles: Vec<&str> = records
             .iter()
@@ -435,4 +611,70 @@ mod tests {
             .collect();
         assert_eq!(roles, vec!["user", "assistant"]);
     }
+
+    #[test]
+    fn capture_system_prompts_into_session_meta() {
+        let dir = tempdir();
+        let path = write_rollout(
+            &dir,
+            &[
+                r#"{"timestamp":"2025-01-15T10:30:00Z","type":"session_meta","payload":{"id":"sess-3","cwd":"/tmp"}}"#,
+                r#"{"timestamp":"2025-01-15T10:30:01Z","type":"response_item","payload":{"type":"message","role":"developer","content":[{"type":"text","text":"system prompt"}]}}"#,
+                r#"{"timestamp":"2025-01-15T10:30:03Z","type":"response_item","payload":{"type":"message","role":"user","content":[{"type":"input_text","text":"real question"}]}}"#,
+            ],
+        );
+        let skipped = load_rollout_jsonl(
+            std::slice::from_ref(&path),
+            None,
+            &ScanStats::default(),
+            SourceTimezone::Utc,
+            &CaptureOptions::default(),
+        )
+        .unwrap();
+        let records = load_rollout_jsonl(
+            &[path],
+            None,
+            &ScanStats::default(),
+            SourceTimezone::Utc,
+            &CaptureOptions {
+                system_prompts: true,
+                ..CaptureOptions::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].source_id, "sess-3:system:0");
+        assert_eq!(records[1].source_id, skipped[0].source_id);
+
+        let batch = normalize_records(&records, &CaptureOptions::default());
+        assert_eq!(batch.messages.len(), 1);
+        assert_eq!(batch.messages[0].content, "real question");
+        assert_eq!(batch.sessions[0].title, "real question");
+        assert_eq!(batch.session_meta.len(), 1);
+        assert_eq!(batch.session_meta[0].session_id, batch.sessions[0].id);
+        assert_eq!(
+            batch.session_meta[0].meta["system_prompts"][0]["content"],
+            "system prompt"
+        );
+        assert_eq!(
+            batch.session_meta[0].meta["system_prompts"][0]["role"],
+            "developer"
+        );
+    }
+
+    #[test]
+    fn discovery_uses_the_home_dotdir_on_every_platform() {
+        let home = tempdir();
+        let file = home.join(".codex/sessions/2025/01/01/rollout.jsonl");
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(&file, "").unwrap();
+        for platform in [Platform::Linux, Platform::MacOs, Platform::Windows] {
+            let found = CodexAdapter::default().discover_in(&BaseDirs::new(platform, home.clone()));
+            assert_eq!(
+                found,
+                vec![file.to_string_lossy().to_string()],
+                "{platform:?}"
+            );
+        }
+    }
 }
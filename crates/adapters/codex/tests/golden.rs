@@ -0,0 +1,11 @@
+use adapter_common::golden::assert_golden;
+
+#[test]
+fn basic_fixture_matches_golden() {
+    assert_golden(
+        &codex::CodexAdapter::default(),
+        env!("CARGO_MANIFEST_DIR"),
+        "basic",
+        "jsonl",
+    );
+}
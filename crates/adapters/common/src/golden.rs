@@ -0,0 +1,94 @@
+use std::{fs, path::Path};
+
+use core_model::{AgentAdapter, NormalizedBatch};
+
+use crate::collect_files_with_ext;
+
+pub const UPDATE_ENV: &str = "REMI_UPDATE_GOLDEN";
+const ROOT_PLACEHOLDER: &str = "$FIXTURES";
+
+// Scans `<manifest_dir>/tests/fixtures/<case>` with a fresh cursor and compares
+// the normalized batch against `<manifest_dir>/tests/golden/<case>.json`.
+// Set REMI_UPDATE_GOLDEN=1 to rewrite the snapshot instead.
+pub fn assert_golden(adapter: &dyn AgentAdapter, manifest_dir: &str, case: &str, ext: &str) {
+    let base = Path::new(manifest_dir).join("tests");
+    let root = base.join("fixtures").join(case);
+    let snapshot_path = base.join("golden").join(format!("{case}.json"));
+
+    let actual = scan_fixtures(adapter, &root, ext);
+
+    // Snapshots must not depend on where the fixtures live on disk.
+    let relocated = std::env::temp_dir().join(format!(
+        "remi-golden-{}-{}-{case}",
+        adapter.kind(),
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&relocated);
+    copy_dir(&root, &relocated);
+    let relocated_actual = scan_fixtures(adapter, &relocated, ext);
+    let _ = fs::remove_dir_all(&relocated);
+    assert_eq!(
+        actual, relocated_actual,
+        "normalization output for {case} depends on the fixture location"
+    );
+
+    if std::env::var_os(UPDATE_ENV).is_some() {
+        fs::create_dir_all(snapshot_path.parent().unwrap()).expect("create golden dir");
+        fs::write(&snapshot_path, &actual).expect("write golden snapshot");
+        return;
+    }
+    let expected = fs::read_to_string(&snapshot_path).unwrap_or_else(|_| {
+        panic!(
+            "missing golden snapshot {}; run with {UPDATE_ENV}=1 to create it",
+            snapshot_path.display()
+        )
+    });
+    if expected != actual {
+        let line = expected
+            .lines()
+            .zip(actual.lines())
+            .position(|(e, a)| e != a)
+            .unwrap_or_else(|| expected.lines().count().min(actual.lines().count()));
+        panic!(
+            "normalization output for {case} differs from {} at line {}:\n  expected: {}\n  actual:   {}\nrerun with {UPDATE_ENV}=1 if the change is intended",
+            snapshot_path.display(),
+            line + 1,
+            expected.lines().nth(line).unwrap_or("<eof>"),
+            actual.lines().nth(line).unwrap_or("<eof>"),
+        );
+    }
+}
+
+fn scan_fixtures(adapter: &dyn AgentAdapter, root: &Path, ext: &str) -> String {
+    let sources = collect_files_with_ext(root, ext);
+    assert!(
+        !sources.is_empty(),
+        "no .{ext} fixtures under {}",
+        root.display()
+    );
+    let records = adapter
+        .scan_changes_since(&sources, None)
+        .expect("scan fixtures");
+    let batch = adapter.normalize(&records).expect("normalize fixtures");
+    render_snapshot(batch, root)
+}
+
+fn copy_dir(from: &Path, to: &Path) {
+    fs::create_dir_all(to).expect("create fixture copy");
+    for entry in fs::read_dir(from).expect("read fixtures").flatten() {
+        let target = to.join(entry.file_name());
+        if entry.path().is_dir() {
+            copy_dir(&entry.path(), &target);
+        } else {
+            fs::copy(entry.path(), &target).expect("copy fixture");
+        }
+    }
+}
+
+fn render_snapshot(batch: NormalizedBatch, root: &Path) -> String {
+    let json = serde_json::to_string_pretty(&batch).expect("serialize batch");
+    let root = serde_json::to_string(&root.to_string_lossy()).expect("serialize root");
+    let mut out = json.replace(root.trim_matches('"'), ROOT_PLACEHOLDER);
+    out.push('\n');
+    out
+}
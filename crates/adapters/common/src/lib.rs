@@ -1,53 +1,362 @@
-use std::{collections::HashMap, fs, io::BufRead, path::Path, time::SystemTime};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::BufRead,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    time::{Duration, SystemTime},
+};
 
-use chrono::{DateTime, TimeZone, Utc};
-use core_model::{AgentKind, NativeRecord, NormalizedBatch, deterministic_id};
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, TimeZone, Utc};
+use core_model::{
+    AgentKind, DirListing, NativeRecord, NormalizedBatch, Role, ScanReport, SourceContext,
+    deterministic_id,
+};
 use rayon::prelude::*;
 use serde_json::Value;
 use tracing::{debug, instrument, trace, warn};
 
-pub fn collect_files_with_ext(root: &Path, ext: &str) -> Vec<String> {
-    let mut out = Vec::new();
-    let mut stack = vec![root.to_path_buf()];
-    while let Some(dir) = stack.pop() {
-        let Ok(entries) = fs::read_dir(&dir) else {
+pub mod golden;
+
+pub const DEFAULT_MAX_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, Copy)]
+pub struct CaptureOptions {
+    pub system_prompts: bool,
+    pub reasoning: bool,
+}
+
+impl Default for CaptureOptions {
+    fn default() -> Self {
+        Self {
+            system_prompts: false,
+            reasoning: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DiscoveryOptions {
+    pub max_depth: usize,
+    pub same_filesystem: bool,
+    pub exclude: Vec<String>,
+    pub listing_cache: Option<Arc<DirListingCache>>,
+    pub modified_since: Option<DateTime<Utc>>,
+    pub modified_before: Option<DateTime<Utc>>,
+    // Directories to search instead of the agent's default location, for
+    // agents that keep their history inside each project.
+    pub roots: Vec<PathBuf>,
+}
+
+impl Default for DiscoveryOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_DEPTH,
+            same_filesystem: false,
+            exclude: Vec::new(),
+            listing_cache: None,
+            modified_since: None,
+            modified_before: None,
+            roots: Vec::new(),
+        }
+    }
+}
+
+impl DiscoveryOptions {
+    // Patterns containing `/` match the path relative to the scanned root;
+    // others match any single file or directory name.
+    pub fn is_excluded(&self, root: &Path, path: &Path) -> bool {
+        if self.exclude.is_empty() {
+            return false;
+        }
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default();
+        let relative = path
+            .strip_prefix(root)
+            .unwrap_or(path)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        self.exclude.iter().any(|pattern| {
+            let target = if pattern.contains('/') {
+                relative.as_str()
+            } else {
+                &name
+            };
+            glob_match(pattern.as_bytes(), target.as_bytes())
+        })
+    }
+
+    fn in_mtime_window(&self, path: &Path) -> bool {
+        if self.modified_since.is_none() && self.modified_before.is_none() {
+            return true;
+        }
+        let Some(mtime) = path.to_str().and_then(file_mtime) else {
+            return false;
+        };
+        self.modified_since.is_none_or(|since| mtime >= since)
+            && self.modified_before.is_none_or(|before| mtime < before)
+    }
+}
+
+// `*` and `?` stay within one path segment; `**` spans segments.
+pub fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', rest @ ..] => match rest.strip_prefix(b"/") {
+            Some(rest) => (0..=text.len())
+                .filter(|&i| i == 0 || text[i - 1] == b'/')
+                .any(|i| glob_match(rest, &text[i..])),
+            None => (0..=text.len()).any(|i| glob_match(rest, &text[i..])),
+        },
+        [b'*', rest @ ..] => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != b'/')
+            .any(|i| glob_match(rest, &text[i..])),
+        [b'?', rest @ ..] => {
+            matches!(text, [c, tail @ ..] if *c != b'/' && glob_match(rest, tail))
+        }
+        [c, rest @ ..] => matches!(text, [d, tail @ ..] if c == d && glob_match(rest, tail)),
+    }
+}
+
+// Listings of directories modified this recently are not cached, since a
+// coarse mtime could hide an entry added right after the read.
+const LISTING_SETTLE_SECS: i64 = 2;
+
+// Reuses a directory's entries while its mtime is unchanged. Directory mtimes
+// only cover direct entries, so subdirectories are still visited and checked.
+#[derive(Debug, Default)]
+pub struct DirListingCache {
+    entries: Mutex<HashMap<String, DirListing>>,
+    changed: Mutex<Vec<(String, DirListing)>>,
+}
+
+impl DirListingCache {
+    pub fn new(entries: HashMap<String, DirListing>) -> Self {
+        Self {
+            entries: Mutex::new(entries),
+            changed: Mutex::default(),
+        }
+    }
+
+    pub fn take_changed(&self) -> Vec<(String, DirListing)> {
+        std::mem::take(&mut *self.changed.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    fn listing(&self, dir: &Path) -> Option<DirListing> {
+        let modified_at = file_mtime(dir.to_str()?)?;
+        let key = dir.to_string_lossy().into_owned();
+        if let Some(hit) = self
+            .entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&key)
+            .filter(|l| l.modified_at == modified_at)
+        {
+            return Some(hit.clone());
+        }
+        let listing = read_listing(dir, modified_at)?;
+        if Utc::now() - modified_at > chrono::Duration::seconds(LISTING_SETTLE_SECS) {
+            self.entries
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(key.clone(), listing.clone());
+            self.changed
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push((key, listing.clone()));
+        }
+        Some(listing)
+    }
+}
+
+fn read_listing(dir: &Path, modified_at: DateTime<Utc>) -> Option<DirListing> {
+    let entries = fs::read_dir(dir).ok()?;
+    let mut listing = DirListing {
+        modified_at,
+        ..Default::default()
+    };
+    for entry in entries.flatten() {
+        let Ok(name) = entry.file_name().into_string() else {
             continue;
         };
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.is_dir() {
-                stack.push(path);
-            } else if path.extension().and_then(|e| e.to_str()) == Some(ext)
-                && let Some(s) = path.to_str()
-            {
-                out.push(s.to_string());
-            }
+        if entry.path().is_dir() {
+            listing.dirs.push(name);
+        } else {
+            listing.files.push(name);
         }
     }
-    out.sort();
+    listing.dirs.sort_unstable();
+    listing.files.sort_unstable();
+    Some(listing)
+}
+
+#[cfg(unix)]
+fn device_id(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).ok().map(|m| m.dev())
+}
+
+#[cfg(not(unix))]
+fn device_id(_path: &Path) -> Option<u64> {
+    None
+}
+
+pub fn collect_files_with_ext(root: &Path, ext: &str) -> Vec<String> {
+    collect_files(root, ext, &DiscoveryOptions::default())
+}
+
+// Walks one depth level at a time, listing each level's directories in parallel.
+pub fn collect_files(root: &Path, ext: &str, options: &DiscoveryOptions) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut visited = HashSet::new();
+    let root_device = options.same_filesystem.then(|| device_id(root)).flatten();
+    let mut frontier = vec![root.to_path_buf()];
+    let mut depth = 0usize;
+    while !frontier.is_empty() {
+        // Canonical paths catch symlink loops and links back into the tree.
+        let canonical: Vec<_> = frontier.par_iter().map(|d| d.canonicalize().ok()).collect();
+        let dirs: Vec<PathBuf> = frontier
+            .into_iter()
+            .zip(canonical)
+            .filter_map(|(dir, canonical)| {
+                if visited.insert(canonical?) {
+                    Some(dir)
+                } else {
+                    debug!(dir = %dir.display(), "skipping already visited directory");
+                    None
+                }
+            })
+            .collect();
+        let level: Vec<(Vec<String>, Vec<PathBuf>)> = dirs
+            .par_iter()
+            .filter_map(|dir| {
+                let listing = match &options.listing_cache {
+                    Some(cache) => cache.listing(dir),
+                    None => read_listing(dir, DateTime::<Utc>::default()),
+                }?;
+                let files = listing
+                    .files
+                    .iter()
+                    .map(|name| dir.join(name))
+                    .filter(|path| {
+                        path.extension().and_then(|e| e.to_str()) == Some(ext)
+                            && !options.is_excluded(root, path)
+                            && options.in_mtime_window(path)
+                    })
+                    .filter_map(|path| path.to_str().map(str::to_string))
+                    .collect();
+                let subdirs = listing
+                    .dirs
+                    .iter()
+                    .map(|name| dir.join(name))
+                    .filter(|path| {
+                        if options.is_excluded(root, path) {
+                            trace!(path = %path.display(), "excluded by discovery pattern");
+                            return false;
+                        }
+                        if depth >= options.max_depth {
+                            debug!(dir = %path.display(), max_depth = options.max_depth, "skipping directory beyond max depth");
+                            return false;
+                        }
+                        if root_device.is_some() && device_id(path) != root_device {
+                            debug!(dir = %path.display(), "skipping directory on another filesystem");
+                            return false;
+                        }
+                        true
+                    })
+                    .collect();
+                Some((files, subdirs))
+            })
+            .collect();
+        frontier = Vec::new();
+        for (files, subdirs) in level {
+            out.extend(files);
+            frontier.extend(subdirs);
+        }
+        depth += 1;
+    }
+    out.sort_unstable();
     debug!(ext, count = out.len(), root = %root.display(), "collected files");
     out
 }
 
+// Every adapter counts a source file through `ScanStats::file_seen` before
+// reading it, so a pause set here spaces out the reads of a whole sync.
+static FILE_PAUSE_MICROS: AtomicU64 = AtomicU64::new(0);
+
+pub fn set_file_pause(pause: Duration) {
+    FILE_PAUSE_MICROS.store(pause.as_micros() as u64, Ordering::Relaxed);
+}
+
+#[derive(Debug, Default)]
+pub struct ScanStats {
+    files_seen: AtomicUsize,
+    files_skipped: AtomicUsize,
+    parse_errors: AtomicUsize,
+}
+
+impl ScanStats {
+    pub fn reset(&self) {
+        self.files_seen.store(0, Ordering::Relaxed);
+        self.files_skipped.store(0, Ordering::Relaxed);
+        self.parse_errors.store(0, Ordering::Relaxed);
+    }
+
+    pub fn file_seen(&self) {
+        self.files_seen.fetch_add(1, Ordering::Relaxed);
+        let pause = FILE_PAUSE_MICROS.load(Ordering::Relaxed);
+        if pause > 0 {
+            std::thread::sleep(Duration::from_micros(pause));
+        }
+    }
+
+    pub fn file_skipped(&self) {
+        self.files_skipped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn parse_errors(&self, count: usize) {
+        self.parse_errors.fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub fn report(&self) -> ScanReport {
+        ScanReport {
+            files_seen: self.files_seen.load(Ordering::Relaxed),
+            files_skipped: self.files_skipped.load(Ordering::Relaxed),
+            parse_errors: self.parse_errors.load(Ordering::Relaxed),
+        }
+    }
+}
+
 pub fn file_mtime(path: &str) -> Option<DateTime<Utc>> {
     let modified: SystemTime = fs::metadata(path).ok()?.modified().ok()?;
     Some(DateTime::<Utc>::from(modified))
 }
 
-#[instrument(skip(source_paths), fields(files = source_paths.len()))]
+#[instrument(skip(source_paths, stats), fields(files = source_paths.len()))]
 pub fn load_jsonl(
     source_paths: &[String],
     cursor: Option<&str>,
+    stats: &ScanStats,
+    tz: SourceTimezone,
 ) -> anyhow::Result<Vec<NativeRecord>> {
     let parsed_cursor = cursor.and_then(parse_cursor);
     let mut out: Vec<NativeRecord> = source_paths
         .par_iter()
         .flat_map(|path| {
+            stats.file_seen();
             let file_mtime = file_mtime(path);
             if let Some(ref cur) = parsed_cursor
                 && let Some(mtime) = file_mtime
                 && mtime <= cur.ts
             {
+                stats.file_skipped();
                 return Vec::new();
             }
             let stem = Path::new(path)
@@ -57,7 +366,10 @@ pub fn load_jsonl(
                 .to_string();
             let file = match fs::File::open(path) {
                 Ok(file) => file,
-                Err(_) => return Vec::new(),
+                Err(_) => {
+                    stats.file_skipped();
+                    return Vec::new();
+                }
             };
             let reader = std::io::BufReader::new(file);
             let mut records = Vec::new();
@@ -73,7 +385,10 @@ pub fn load_jsonl(
                         continue;
                     }
                 };
-                let ts = extract_ts(&val).or(file_mtime).unwrap_or_else(Utc::now);
+                let Some(ts) = extract_ts(&val, tz).or(file_mtime) else {
+                    skipped_lines += 1;
+                    continue;
+                };
                 if let Some(obj) = val.as_object_mut() {
                     obj.insert("__source_path".to_string(), Value::String(path.clone()));
                     obj.insert("__session_seed".to_string(), Value::String(stem.clone()));
@@ -95,6 +410,7 @@ pub fn load_jsonl(
                 });
             }
             if skipped_lines > 0 {
+                stats.parse_errors(skipped_lines);
                 warn!(path = %path, skipped_lines, "skipped malformed jsonl lines");
             }
             trace!(path = %path, records = records.len(), "parsed jsonl file");
@@ -114,7 +430,11 @@ pub fn load_jsonl(
     Ok(out)
 }
 
-pub fn normalize_jsonl_records(kind: AgentKind, records: &[NativeRecord]) -> NormalizedBatch {
+pub fn normalize_jsonl_records(
+    kind: AgentKind,
+    records: &[NativeRecord],
+    capture: &CaptureOptions,
+) -> NormalizedBatch {
     let mut batch = NormalizedBatch::default();
     let mut sessions: HashMap<String, core_model::Session> = HashMap::new();
     for rec in records {
@@ -124,12 +444,8 @@ pub fn normalize_jsonl_records(kind: AgentKind, records: &[NativeRecord]) -> Nor
         let Some(message) = rec.payload.get("message") else {
             continue;
         };
-        let role = message
-            .get("role")
-            .and_then(Value::as_str)
-            .unwrap_or("user")
-            .to_string();
-        let content = extract_content_text(message.get("content"));
+        let role = normalize_role(message.get("role").and_then(Value::as_str), Role::User);
+        let content = extract_content_text_with(message.get("content"), capture);
         if content.is_empty() {
             continue;
         }
@@ -189,6 +505,12 @@ pub fn normalize_jsonl_records(kind: AgentKind, records: &[NativeRecord]) -> Nor
                 .unwrap_or(kind.as_str())
                 .to_string(),
             source_id: rec.source_id.clone(),
+            workspace: rec
+                .payload
+                .get("__workspace_path")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            repo: None,
         });
     }
     let mut ordered_sessions: Vec<_> = sessions.into_values().collect();
@@ -201,6 +523,81 @@ pub fn normalize_jsonl_records(kind: AgentKind, records: &[NativeRecord]) -> Nor
     batch
 }
 
+// Every adapter derives a session id from its session key and keeps the key as
+// `source_ref`, so user aliases (`source_ref -> canonical key`) can be applied
+// to any batch by re-deriving the id from the canonical key.
+pub fn apply_session_aliases(
+    kind: AgentKind,
+    batch: &mut NormalizedBatch,
+    aliases: &HashMap<String, String>,
+) {
+    if aliases.is_empty() {
+        return;
+    }
+    let mut renamed: HashMap<String, String> = HashMap::new();
+    let mut sessions: Vec<core_model::Session> = Vec::with_capacity(batch.sessions.len());
+    for mut session in batch.sessions.drain(..) {
+        if let Some(canonical) = resolve_alias(aliases, &session.source_ref) {
+            let id = deterministic_id(&[kind.as_str(), "session", canonical]);
+            renamed.insert(session.id.clone(), id.clone());
+            if session.title == session.source_ref {
+                session.title = canonical.to_string();
+            }
+            session.id = id;
+            session.source_ref = canonical.to_string();
+        }
+        match sessions.iter_mut().find(|s| s.id == session.id) {
+            Some(existing) => {
+                existing.created_at = existing.created_at.min(session.created_at);
+                existing.updated_at = existing.updated_at.max(session.updated_at);
+                if existing.title == existing.source_ref {
+                    existing.title = session.title;
+                }
+            }
+            None => sessions.push(session),
+        }
+    }
+    batch.sessions = sessions;
+    if renamed.is_empty() {
+        return;
+    }
+    let rename = |id: &mut String| {
+        if let Some(new) = renamed.get(id.as_str()) {
+            *id = new.clone();
+        }
+    };
+    batch
+        .messages
+        .iter_mut()
+        .for_each(|m| rename(&mut m.session_id));
+    batch
+        .events
+        .iter_mut()
+        .for_each(|e| rename(&mut e.session_id));
+    batch
+        .artifacts
+        .iter_mut()
+        .for_each(|a| rename(&mut a.session_id));
+    batch
+        .session_meta
+        .iter_mut()
+        .for_each(|m| rename(&mut m.session_id));
+}
+
+// Aliases may chain (`a -> b`, `b -> c`); a cycle stops at the last key
+// before it repeats.
+fn resolve_alias<'a>(aliases: &'a HashMap<String, String>, key: &str) -> Option<&'a str> {
+    let mut seen = HashSet::from([key]);
+    let mut current = aliases.get(key)?.as_str();
+    while let Some(next) = aliases.get(current) {
+        if !seen.insert(current) {
+            break;
+        }
+        current = next.as_str();
+    }
+    Some(current)
+}
+
 pub fn checkpoint_cursor_from_records(records: &[NativeRecord]) -> Option<String> {
     records
         .iter()
@@ -212,8 +609,19 @@ pub fn checkpoint_cursor_from_records(records: &[NativeRecord]) -> Option<String
         .map(|r| encode_cursor(r.updated_at, &r.source_id))
 }
 
+pub fn message_key(rec: &NativeRecord) -> &str {
+    rec.payload
+        .get("__message_key")
+        .and_then(Value::as_str)
+        .unwrap_or(&rec.source_id)
+}
+
+const LEGACY_CURSOR_SEPARATOR: char = '\x1f';
+
+// Cursors are JSON so source ids can hold any byte; checkpoints written before
+// that used `<rfc3339>\x1f<source_id>` and still parse.
 pub fn encode_cursor(ts: DateTime<Utc>, source_id: &str) -> String {
-    format!("{}\x1f{}", ts.to_rfc3339(), source_id)
+    serde_json::json!({ "ts": ts.to_rfc3339(), "id": source_id }).to_string()
 }
 
 pub struct ParsedCursor {
@@ -222,57 +630,384 @@ pub struct ParsedCursor {
 }
 
 pub fn parse_cursor(cursor: &str) -> Option<ParsedCursor> {
-    let (ts_str, id) = cursor.split_once('\x1f')?;
-    let ts = DateTime::parse_from_rfc3339(ts_str)
+    let (ts_str, id) = if cursor.starts_with('{') {
+        let val: Value = serde_json::from_str(cursor).ok()?;
+        (
+            val.get("ts")?.as_str()?.to_string(),
+            val.get("id")?.as_str()?.to_string(),
+        )
+    } else {
+        let (ts_str, id) = cursor.split_once(LEGACY_CURSOR_SEPARATOR)?;
+        (ts_str.to_string(), id.to_string())
+    };
+    let ts = DateTime::parse_from_rfc3339(&ts_str)
         .ok()
         .map(|d| d.with_timezone(&Utc))?;
-    Some(ParsedCursor {
-        ts,
-        source_id: id.to_string(),
-    })
+    Some(ParsedCursor { ts, source_id: id })
 }
 
 pub fn should_skip(ts: DateTime<Utc>, source_id: &str, cursor: &ParsedCursor) -> bool {
     ts < cursor.ts || (ts == cursor.ts && source_id <= cursor.source_id.as_str())
 }
 
-pub fn extract_ts(val: &Value) -> Option<DateTime<Utc>> {
-    if let Some(s) = val.get("timestamp").and_then(Value::as_str) {
-        return DateTime::parse_from_rfc3339(s)
-            .ok()
-            .map(|dt| dt.with_timezone(&Utc));
+pub fn source_home_env_var(kind: AgentKind) -> String {
+    format!("REMI_{}_HOME", kind.as_str().to_ascii_uppercase())
+}
+
+// The per-agent override wins so home-less environments (containers, service
+// accounts) can still point an adapter at its data.
+pub fn env_source_context() -> SourceContext {
+    let var = |name: &str| {
+        std::env::var_os(name)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    };
+    SourceContext {
+        home: dirs::home_dir(),
+        overrides: AgentKind::ALL
+            .iter()
+            .filter_map(|&kind| Some((kind, var(&source_home_env_var(kind))?)))
+            .collect(),
+        xdg_data_home: var("XDG_DATA_HOME"),
+        app_data: var("APPDATA"),
+        local_app_data: var("LOCALAPPDATA"),
     }
-    if let Some(ms) = val
-        .get("message")
-        .and_then(|m| m.get("timestamp"))
-        .and_then(Value::as_i64)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Linux,
+    MacOs,
+    Windows,
+}
+
+impl Platform {
+    pub fn current() -> Self {
+        if cfg!(windows) {
+            Platform::Windows
+        } else if cfg!(target_os = "macos") {
+            Platform::MacOs
+        } else {
+            Platform::Linux
+        }
+    }
+}
+
+// The roots adapters search under. Fields left `None` fall back to their
+// platform defaults below `home`, so tests can build one for any platform.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BaseDirs {
+    pub platform: Platform,
+    pub home: PathBuf,
+    pub xdg_data_home: Option<PathBuf>,
+    pub app_data: Option<PathBuf>,
+    pub local_app_data: Option<PathBuf>,
+}
+
+impl BaseDirs {
+    pub fn new(platform: Platform, home: PathBuf) -> Self {
+        Self {
+            platform,
+            home,
+            xdg_data_home: None,
+            app_data: None,
+            local_app_data: None,
+        }
+    }
+
+    // With a per-agent override only paths under that directory are
+    // searched, so the platform's environment cannot leak into it.
+    pub fn for_agent(ctx: &SourceContext, kind: AgentKind) -> Option<Self> {
+        let home = ctx.home_for(kind)?.to_path_buf();
+        let dirs = Self::new(Platform::current(), home);
+        if ctx.is_overridden(kind) {
+            return Some(dirs);
+        }
+        Some(Self {
+            xdg_data_home: ctx.xdg_data_home.clone(),
+            app_data: ctx.app_data.clone(),
+            local_app_data: ctx.local_app_data.clone(),
+            ..dirs
+        })
+    }
+
+    // Per-user application data roots, most specific first. `~/.local/share`
+    // is kept on every platform since several agents use it regardless of OS.
+    pub fn data_dirs(&self) -> Vec<PathBuf> {
+        let local_share = self.home.join(".local").join("share");
+        let mut dirs = match self.platform {
+            Platform::Linux => vec![self.xdg_data_home.clone()],
+            Platform::MacOs => vec![
+                Some(self.home.join("Library").join("Application Support")),
+                self.xdg_data_home.clone(),
+            ],
+            Platform::Windows => vec![
+                Some(
+                    self.local_app_data
+                        .clone()
+                        .unwrap_or_else(|| self.home.join("AppData").join("Local")),
+                ),
+                Some(
+                    self.app_data
+                        .clone()
+                        .unwrap_or_else(|| self.home.join("AppData").join("Roaming")),
+                ),
+            ],
+        }
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+        dirs.push(local_share);
+        let mut seen = HashSet::new();
+        dirs.retain(|dir| seen.insert(dir.clone()));
+        dirs
+    }
+
+    pub fn data_paths(&self, relative: &str) -> Vec<PathBuf> {
+        self.data_dirs()
+            .into_iter()
+            .map(|dir| relative.split('/').fold(dir, |path, part| path.join(part)))
+            .collect()
+    }
+
+    pub fn home_path(&self, relative: &str) -> PathBuf {
+        relative
+            .split('/')
+            .fold(self.home.clone(), |path, part| path.join(part))
+    }
+}
+
+pub fn discovery_dirs(ctx: &SourceContext, kind: AgentKind) -> Option<BaseDirs> {
+    let dirs = BaseDirs::for_agent(ctx, kind);
+    if dirs.is_none() {
+        warn!(
+            agent = %kind,
+            reason = "no_home_dir",
+            override_var = %source_home_env_var(kind),
+            "skipping source discovery"
+        );
+    }
+    dirs
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SourceTimezone {
+    #[default]
+    Utc,
+    Local,
+    Fixed(FixedOffset),
+}
+
+impl SourceTimezone {
+    pub fn env_var(kind: AgentKind) -> String {
+        format!("REMI_{}_TZ", kind.as_str().to_ascii_uppercase())
+    }
+
+    // `REMI_<AGENT>_TZ` wins over the timezone set in config, and naive
+    // timestamps are read as UTC when neither is set.
+    pub fn for_agent(kind: AgentKind, configured: Option<Self>) -> Self {
+        let var = Self::env_var(kind);
+        let fallback = configured.unwrap_or_default();
+        let Ok(raw) = std::env::var(&var) else {
+            return fallback;
+        };
+        raw.parse().unwrap_or_else(|err| {
+            warn!(var = %var, value = %raw, error = %err, "ignoring invalid source timezone");
+            fallback
+        })
+    }
+
+    pub fn resolve(&self, naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self {
+            SourceTimezone::Utc => Some(naive.and_utc()),
+            SourceTimezone::Local => Local
+                .from_local_datetime(&naive)
+                .earliest()
+                .map(|dt| dt.with_timezone(&Utc)),
+            SourceTimezone::Fixed(offset) => offset
+                .from_local_datetime(&naive)
+                .single()
+                .map(|dt| dt.with_timezone(&Utc)),
+        }
+    }
+}
+
+impl std::str::FromStr for SourceTimezone {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        match trimmed.to_ascii_lowercase().as_str() {
+            "utc" | "z" => return Ok(SourceTimezone::Utc),
+            "local" => return Ok(SourceTimezone::Local),
+            _ => {}
+        }
+        parse_utc_offset(trimmed)
+            .map(SourceTimezone::Fixed)
+            .ok_or_else(|| anyhow::anyhow!("expected utc, local, or an offset like +02:00: {s}"))
+    }
+}
+
+fn parse_utc_offset(raw: &str) -> Option<FixedOffset> {
+    let (sign, rest) = match raw.as_bytes().first()? {
+        b'+' => (1, &raw[1..]),
+        b'-' => (-1, &raw[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = match rest.split_once(':') {
+        Some((h, m)) => (h, m),
+        None if rest.len() == 4 => rest.split_at(2),
+        None => (rest, "0"),
+    };
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+const NAIVE_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+    "%Y/%m/%d %H:%M:%S",
+];
+
+pub fn parse_timestamp(input: &str, tz: SourceTimezone) -> Option<DateTime<Utc>> {
+    let input = input.trim();
+    if input.is_empty() {
+        return None;
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc3339(input) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc2822(input) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    if let Ok(epoch) = input.parse::<i64>() {
+        return parse_epoch(epoch);
+    }
+    if let Ok(epoch) = input.parse::<f64>() {
+        return parse_epoch_f64(epoch);
+    }
+    NAIVE_FORMATS
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(input, fmt).ok())
+        .and_then(|naive| tz.resolve(naive))
+}
+
+pub fn parse_timestamp_value(value: &Value, tz: SourceTimezone) -> Option<DateTime<Utc>> {
+    match value {
+        Value::String(s) => parse_timestamp(s, tz),
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                return parse_epoch(i);
+            }
+            if let Some(u) = n.as_u64() {
+                return parse_epoch(u as i64);
+            }
+            parse_epoch_f64(n.as_f64()?)
+        }
+        _ => None,
+    }
+}
+
+fn parse_epoch_f64(epoch: f64) -> Option<DateTime<Utc>> {
+    if !epoch.is_finite() {
+        return None;
+    }
+    if epoch.abs() < 1_000_000_000_000.0 {
+        let secs = epoch.floor();
+        let nanos = ((epoch - secs) * 1_000_000_000.0).round() as u32;
+        return Utc
+            .timestamp_opt(secs as i64, nanos.min(999_999_999))
+            .single();
+    }
+    parse_epoch(epoch as i64)
+}
+
+pub fn parse_epoch(epoch: i64) -> Option<DateTime<Utc>> {
+    let abs = epoch.unsigned_abs();
+    if abs >= 1_000_000_000_000_000_000 {
+        let secs = epoch.div_euclid(1_000_000_000);
+        let nanos = epoch.rem_euclid(1_000_000_000) as u32;
+        return Utc.timestamp_opt(secs, nanos).single();
+    }
+    if abs >= 1_000_000_000_000_000 {
+        let secs = epoch.div_euclid(1_000_000);
+        let micros = epoch.rem_euclid(1_000_000) as u32;
+        return Utc.timestamp_opt(secs, micros * 1_000).single();
+    }
+    if abs >= 1_000_000_000_000 {
+        return Utc.timestamp_millis_opt(epoch).single();
+    }
+    Utc.timestamp_opt(epoch, 0).single()
+}
+
+pub fn extract_ts(val: &Value, tz: SourceTimezone) -> Option<DateTime<Utc>> {
+    if let Some(ts) = val
+        .get("timestamp")
+        .and_then(|v| parse_timestamp_value(v, tz))
     {
-        return Utc.timestamp_millis_opt(ms).single();
+        return Some(ts);
     }
-    None
+    val.get("message")
+        .and_then(|m| m.get("timestamp"))
+        .and_then(|v| parse_timestamp_value(v, tz))
+}
+
+pub fn normalize_role(raw: Option<&str>, fallback: Role) -> String {
+    raw.and_then(Role::from_native)
+        .unwrap_or(fallback)
+        .as_str()
+        .to_string()
 }
 
 pub fn extract_content_text(content: Option<&Value>) -> String {
+    extract_content_text_with(content, &CaptureOptions::default())
+}
+
+pub fn extract_content_text_with(content: Option<&Value>, capture: &CaptureOptions) -> String {
     let Some(content) = content else {
         return String::new();
     };
     let mut lines = Vec::new();
-    extract_content_lines(content, &mut lines);
+    extract_content_lines(content, &mut lines, capture);
     lines.join("\n")
 }
 
-fn extract_content_lines(value: &Value, lines: &mut Vec<String>) {
+// Each reasoning block becomes a single prefixed line so it can be told apart
+// from the rest of the message after the fact (see `core_model::split_reasoning`).
+pub fn reasoning_line(text: &str) -> Option<String> {
+    let flattened = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!flattened.is_empty()).then(|| format!("{}{flattened}", core_model::REASONING_PREFIX))
+}
+
+fn extract_content_lines(value: &Value, lines: &mut Vec<String>, capture: &CaptureOptions) {
     match value {
         Value::Null => {}
         Value::String(s) => push_non_empty_line(lines, s),
         Value::Array(arr) => {
             for item in arr {
-                extract_content_lines(item, lines);
+                extract_content_lines(item, lines, capture);
             }
         }
         Value::Object(obj) => {
             if let Some(kind) = obj.get("type").and_then(Value::as_str) {
                 match kind {
+                    "thinking" | "reasoning" => {
+                        let text = obj
+                            .get("thinking")
+                            .or_else(|| obj.get("text"))
+                            .and_then(Value::as_str);
+                        if capture.reasoning
+                            && let Some(line) = text.and_then(reasoning_line)
+                        {
+                            lines.push(line);
+                        }
+                        return;
+                    }
                     "tool_use" => {
                         if let Some(text) = format_tool_use(value) {
                             lines.push(text);
@@ -292,11 +1027,16 @@ fn extract_content_lines(value: &Value, lines: &mut Vec<String>) {
             if let Some(text) = obj.get("text").and_then(Value::as_str) {
                 push_non_empty_line(lines, text);
             }
-            if let Some(thinking) = obj.get("thinking").and_then(Value::as_str) {
-                push_non_empty_line(lines, thinking);
+            if capture.reasoning
+                && let Some(line) = obj
+                    .get("thinking")
+                    .and_then(Value::as_str)
+                    .and_then(reasoning_line)
+            {
+                lines.push(line);
             }
             if let Some(content) = obj.get("content") {
-                extract_content_lines(content, lines);
+                extract_content_lines(content, lines, capture);
             }
         }
         Value::Bool(b) => lines.push(b.to_string()),
@@ -440,7 +1180,30 @@ mod tests {
             {"thinking": "thought"},
             {"text": "answer"}
         ]);
-        assert_eq!(extract_content_text(Some(&val)), "thought\nanswer");
+        assert_eq!(
+            extract_content_text(Some(&val)),
+            "reasoning: thought\nanswer"
+        );
+        let without = CaptureOptions {
+            reasoning: false,
+            ..CaptureOptions::default()
+        };
+        assert_eq!(extract_content_text_with(Some(&val), &without), "answer");
+    }
+
+    #[test]
+    fn reasoning_blocks_are_single_labeled_lines() {
+        let val = serde_json::json!([
+            {"type": "thinking", "thinking": "step one\n\nstep two"},
+            {"type": "reasoning", "text": "  "},
+            {"type": "text", "text": "done"}
+        ]);
+        let content = extract_content_text(Some(&val));
+        assert_eq!(content, "reasoning: step one step two\ndone");
+        assert_eq!(
+            core_model::split_reasoning(&content),
+            ("step one step two".to_string(), "done".to_string())
+        );
     }
 
     #[test]
@@ -477,21 +1240,179 @@ mod tests {
     #[test]
     fn extract_ts_rfc3339() {
         let val = serde_json::json!({"timestamp": "2025-01-15T10:30:00+00:00"});
-        let ts = extract_ts(&val).unwrap();
+        let ts = extract_ts(&val, SourceTimezone::Utc).unwrap();
         assert_eq!(ts.to_rfc3339(), "2025-01-15T10:30:00+00:00");
     }
 
     #[test]
     fn extract_ts_millis() {
         let val = serde_json::json!({"message": {"timestamp": 1705312200000_i64}});
-        let ts = extract_ts(&val);
+        let ts = extract_ts(&val, SourceTimezone::Utc);
         assert!(ts.is_some());
     }
 
+    #[test]
+    fn parse_timestamp_accepts_common_formats() {
+        let expected = Utc.with_ymd_and_hms(2025, 1, 15, 10, 30, 0).unwrap();
+        for input in [
+            "2025-01-15T10:30:00Z",
+            "2025-01-15T12:30:00+02:00",
+            "Wed, 15 Jan 2025 10:30:00 +0000",
+            "1736937000",
+            "1736937000000",
+            "2025-01-15 10:30:00",
+            "2025-01-15T10:30:00",
+        ] {
+            assert_eq!(
+                parse_timestamp(input, SourceTimezone::Utc),
+                Some(expected),
+                "{input}"
+            );
+        }
+        assert!(parse_timestamp("", SourceTimezone::Utc).is_none());
+        assert!(parse_timestamp("yesterday", SourceTimezone::Utc).is_none());
+    }
+
+    #[test]
+    fn parse_timestamp_applies_source_timezone_to_naive_values() {
+        let tz: SourceTimezone = "+02:00".parse().unwrap();
+        assert_eq!(
+            parse_timestamp("2025-01-15 12:30:00", tz),
+            Some(Utc.with_ymd_and_hms(2025, 1, 15, 10, 30, 0).unwrap())
+        );
+        assert_eq!(
+            parse_timestamp("2025-01-15T10:30:00Z", tz),
+            Some(Utc.with_ymd_and_hms(2025, 1, 15, 10, 30, 0).unwrap())
+        );
+        assert_eq!(
+            "-0530".parse::<SourceTimezone>().unwrap(),
+            SourceTimezone::Fixed(FixedOffset::west_opt(5 * 3600 + 1800).unwrap())
+        );
+        assert_eq!(
+            "UTC".parse::<SourceTimezone>().unwrap(),
+            SourceTimezone::Utc
+        );
+        assert_eq!(
+            "local".parse::<SourceTimezone>().unwrap(),
+            SourceTimezone::Local
+        );
+        assert!("Mars/Olympus".parse::<SourceTimezone>().is_err());
+        assert_eq!(SourceTimezone::env_var(AgentKind::Amp), "REMI_AMP_TZ");
+    }
+
+    #[test]
+    fn source_timezone_falls_back_to_config_then_utc() {
+        let tz: SourceTimezone = "+02:00".parse().unwrap();
+        if std::env::var_os(SourceTimezone::env_var(AgentKind::Gemini)).is_none() {
+            assert_eq!(SourceTimezone::for_agent(AgentKind::Gemini, Some(tz)), tz);
+            assert_eq!(
+                SourceTimezone::for_agent(AgentKind::Gemini, None),
+                SourceTimezone::Utc
+            );
+        }
+    }
+
+    #[test]
+    fn data_dirs_follow_the_platform() {
+        let home = PathBuf::from("/home/u");
+        let local_share = home.join(".local").join("share");
+        assert_eq!(
+            BaseDirs::new(Platform::Linux, home.clone()).data_dirs(),
+            vec![local_share.clone()]
+        );
+        let linux = BaseDirs {
+            xdg_data_home: Some(PathBuf::from("/data")),
+            ..BaseDirs::new(Platform::Linux, home.clone())
+        };
+        assert_eq!(
+            linux.data_dirs(),
+            vec![PathBuf::from("/data"), local_share.clone()]
+        );
+        assert_eq!(
+            BaseDirs::new(Platform::MacOs, home.clone()).data_dirs(),
+            vec![
+                home.join("Library").join("Application Support"),
+                local_share.clone()
+            ]
+        );
+        assert_eq!(
+            BaseDirs::new(Platform::Windows, home.clone()).data_dirs(),
+            vec![
+                home.join("AppData").join("Local"),
+                home.join("AppData").join("Roaming"),
+                local_share.clone()
+            ]
+        );
+        let windows = BaseDirs {
+            app_data: Some(PathBuf::from("/roaming")),
+            local_app_data: Some(PathBuf::from("/local")),
+            ..BaseDirs::new(Platform::Windows, home.clone())
+        };
+        assert_eq!(
+            windows.data_paths("amp/threads"),
+            vec![
+                PathBuf::from("/local").join("amp").join("threads"),
+                PathBuf::from("/roaming").join("amp").join("threads"),
+                local_share.join("amp").join("threads")
+            ]
+        );
+    }
+
+    #[test]
+    fn agent_overrides_ignore_the_platform_dirs() {
+        let mut ctx = SourceContext {
+            xdg_data_home: Some(PathBuf::from("/data")),
+            ..SourceContext::with_home("/home/u")
+        };
+        ctx.overrides
+            .insert(AgentKind::Amp, PathBuf::from("/srv/amp"));
+        let amp = BaseDirs::for_agent(&ctx, AgentKind::Amp).unwrap();
+        assert_eq!(amp.home, PathBuf::from("/srv/amp"));
+        assert_eq!(amp.xdg_data_home, None);
+        let codex = BaseDirs::for_agent(&ctx, AgentKind::Codex).unwrap();
+        assert_eq!(codex.home, PathBuf::from("/home/u"));
+        assert_eq!(codex.xdg_data_home, Some(PathBuf::from("/data")));
+        assert!(BaseDirs::for_agent(&SourceContext::default(), AgentKind::Codex).is_none());
+    }
+
+    #[test]
+    fn source_home_override_is_per_agent() {
+        assert_eq!(
+            source_home_env_var(AgentKind::OpenCode),
+            "REMI_OPENCODE_HOME"
+        );
+        assert_eq!(source_home_env_var(AgentKind::Pi), "REMI_PI_HOME");
+    }
+
+    #[test]
+    fn parse_epoch_handles_negative_nanos_and_micros() {
+        let neg_nanos = -1_700_000_000_123_456_789_i64;
+        let nanos_ts = parse_epoch(neg_nanos).expect("negative nanos timestamp should parse");
+        let expected_nanos = Utc
+            .timestamp_opt(
+                neg_nanos.div_euclid(1_000_000_000),
+                neg_nanos.rem_euclid(1_000_000_000) as u32,
+            )
+            .single()
+            .unwrap();
+        assert_eq!(nanos_ts, expected_nanos);
+
+        let neg_micros = -1_700_000_123_456_789_i64;
+        let micros_ts = parse_epoch(neg_micros).expect("negative micros timestamp should parse");
+        let expected_micros = Utc
+            .timestamp_opt(
+                neg_micros.div_euclid(1_000_000),
+                (neg_micros.rem_euclid(1_000_000) as u32) * 1_000,
+            )
+            .single()
+            .unwrap();
+        assert_eq!(micros_ts, expected_micros);
+    }
+
     #[test]
     fn extract_ts_missing() {
         let val = serde_json::json!({"foo": "bar"});
-        assert!(extract_ts(&val).is_none());
+        assert!(extract_ts(&val, SourceTimezone::Utc).is_none());
     }
 
     #[test]
@@ -548,6 +1469,25 @@ mod tests {
         assert_eq!(parsed.source_id, "my-id");
     }
 
+    #[test]
+    fn parse_cursor_accepts_legacy_separator_format() {
+        let parsed = parse_cursor("2025-01-15T10:30:00+00:00\x1fsess:a\x1fb").unwrap();
+        assert_eq!(parsed.ts.to_rfc3339(), "2025-01-15T10:30:00+00:00");
+        assert_eq!(parsed.source_id, "sess:a\x1fb");
+    }
+
+    #[test]
+    fn cursor_roundtrips_ids_with_separator_and_json_bytes() {
+        let t = Utc::now();
+        for id in ["a\x1fb", "\x1f", "{\"ts\":1}", "quote\"and\\slash", ""] {
+            let parsed = parse_cursor(&encode_cursor(t, id)).unwrap();
+            assert_eq!(parsed.ts, t);
+            assert_eq!(parsed.source_id, id);
+        }
+        assert!(parse_cursor("{\"ts\":\"2025-01-15T10:30:00+00:00\"}").is_none());
+        assert!(parse_cursor("{not json").is_none());
+    }
+
     #[test]
     fn parse_cursor_rejects_plain_timestamp() {
         assert!(parse_cursor("2025-01-15T10:30:00+00:00").is_none());
@@ -621,7 +1561,7 @@ mod tests {
         let mut f = std::fs::File::create(&file).unwrap();
         writeln!(f, r#"{{"id":"1","type":"message","message":{{"role":"user","content":[{{"text":"hello"}}]}},"timestamp":"2025-01-15T10:30:00+00:00"}}"#).unwrap();
         let paths = vec![file.to_str().unwrap().to_string()];
-        let records = load_jsonl(&paths, None).unwrap();
+        let records = load_jsonl(&paths, None, &ScanStats::default(), SourceTimezone::Utc).unwrap();
         assert_eq!(records.len(), 1);
         assert_eq!(records[0].source_id, "1");
     }
@@ -635,7 +1575,13 @@ mod tests {
         writeln!(f, r#"{{"id":"2","type":"message","message":{{"role":"user","content":[{{"text":"new"}}]}},"timestamp":"2025-01-20T00:00:00+00:00"}}"#).unwrap();
         let paths = vec![file.to_str().unwrap().to_string()];
         let cursor = "2025-01-15T00:00:00+00:00\x1fsome-id".to_string();
-        let records = load_jsonl(&paths, Some(&cursor)).unwrap();
+        let records = load_jsonl(
+            &paths,
+            Some(&cursor),
+            &ScanStats::default(),
+            SourceTimezone::Utc,
+        )
+        .unwrap();
         assert_eq!(records.len(), 1);
         assert_eq!(records[0].source_id, "2");
     }
@@ -662,7 +1608,13 @@ mod tests {
         .unwrap();
         let paths = vec![file.to_str().unwrap().to_string()];
         let cursor = "2025-01-10T00:00:00+00:00\x1fmmm".to_string();
-        let records = load_jsonl(&paths, Some(&cursor)).unwrap();
+        let records = load_jsonl(
+            &paths,
+            Some(&cursor),
+            &ScanStats::default(),
+            SourceTimezone::Utc,
+        )
+        .unwrap();
         assert_eq!(records.len(), 1);
         assert_eq!(records[0].source_id, "zzz");
     }
@@ -677,11 +1629,29 @@ mod tests {
         f.write_all(b"\n").unwrap();
         let mtime = file_mtime(file.to_str().unwrap()).unwrap();
         let paths = vec![file.to_str().unwrap().to_string()];
-        let records = load_jsonl(&paths, None).unwrap();
+        let records = load_jsonl(&paths, None, &ScanStats::default(), SourceTimezone::Utc).unwrap();
         assert_eq!(records.len(), 1);
         assert_eq!(records[0].updated_at, mtime);
     }
 
+    #[test]
+    fn load_jsonl_reads_naive_timestamps_in_the_source_timezone() {
+        let dir = tempdir();
+        let file = dir.join("sess.jsonl");
+        std::fs::write(
+            &file,
+            r#"{"id":"1","timestamp":"2025-01-15 12:30:00","message":{"content":"hi"}}"#,
+        )
+        .unwrap();
+        let paths = vec![file.to_str().unwrap().to_string()];
+        let tz: SourceTimezone = "+02:00".parse().unwrap();
+        let records = load_jsonl(&paths, None, &ScanStats::default(), tz).unwrap();
+        assert_eq!(
+            records[0].updated_at,
+            Utc.with_ymd_and_hms(2025, 1, 15, 10, 30, 0).unwrap()
+        );
+    }
+
     #[test]
     fn load_jsonl_skips_when_cursor_at_or_after_mtime() {
         let dir = tempdir();
@@ -693,7 +1663,13 @@ mod tests {
         let mtime = file_mtime(file.to_str().unwrap()).unwrap();
         let cursor = encode_cursor(mtime, "zzz");
         let paths = vec![file.to_str().unwrap().to_string()];
-        let records = load_jsonl(&paths, Some(&cursor)).unwrap();
+        let records = load_jsonl(
+            &paths,
+            Some(&cursor),
+            &ScanStats::default(),
+            SourceTimezone::Utc,
+        )
+        .unwrap();
         assert!(records.is_empty());
     }
 
@@ -709,7 +1685,7 @@ mod tests {
                 "__source_path": "/test/path"
             }),
         };
-        let batch = normalize_jsonl_records(AgentKind::Pi, &[rec]);
+        let batch = normalize_jsonl_records(AgentKind::Pi, &[rec], &CaptureOptions::default());
         assert_eq!(batch.sessions.len(), 1);
         assert_eq!(batch.messages.len(), 1);
         assert_eq!(batch.provenance.len(), 1);
@@ -717,6 +1693,41 @@ mod tests {
         assert_eq!(batch.messages[0].content, "hi there");
     }
 
+    #[test]
+    fn session_aliases_regroup_sessions_under_the_canonical_key() {
+        let record = |id: &str, session: &str| NativeRecord {
+            source_id: id.to_string(),
+            updated_at: Utc::now(),
+            payload: serde_json::json!({
+                "type": "message",
+                "message": {"role": "user", "content": "hi"},
+                "sessionId": session,
+            }),
+        };
+        let mut batch = normalize_jsonl_records(
+            AgentKind::Pi,
+            &[record("r1", "legacy"), record("r2", "canonical")],
+            &CaptureOptions::default(),
+        );
+        let aliases = HashMap::from([
+            ("legacy".to_string(), "middle".to_string()),
+            ("middle".to_string(), "canonical".to_string()),
+        ]);
+        apply_session_aliases(AgentKind::Pi, &mut batch, &aliases);
+        let id = deterministic_id(&["pi", "session", "canonical"]);
+        assert_eq!(batch.sessions.len(), 1);
+        assert_eq!(batch.sessions[0].id, id);
+        assert_eq!(batch.sessions[0].source_ref, "canonical");
+        assert!(batch.messages.iter().all(|m| m.session_id == id));
+
+        let looped = HashMap::from([
+            ("a".to_string(), "b".to_string()),
+            ("b".to_string(), "a".to_string()),
+        ]);
+        assert_eq!(resolve_alias(&looped, "a"), Some("a"));
+        assert_eq!(resolve_alias(&looped, "c"), None);
+    }
+
     #[test]
     fn normalize_skips_non_message() {
         let rec = NativeRecord {
@@ -727,7 +1738,7 @@ mod tests {
                 "message": {"role": "assistant", "content": [{"text": "hi"}]}
             }),
         };
-        let batch = normalize_jsonl_records(AgentKind::Pi, &[rec]);
+        let batch = normalize_jsonl_records(AgentKind::Pi, &[rec], &CaptureOptions::default());
         assert!(batch.sessions.is_empty());
     }
 
@@ -739,8 +1750,37 @@ mod tests {
         writeln!(f, "this is not json").unwrap();
         writeln!(f, r#"{{"id":"1","type":"message","message":{{"role":"user","content":[{{"text":"ok"}}]}},"timestamp":"2025-01-15T10:30:00+00:00"}}"#).unwrap();
         let paths = vec![file.to_str().unwrap().to_string()];
-        let records = load_jsonl(&paths, None).unwrap();
+        let stats = ScanStats::default();
+        let records = load_jsonl(&paths, None, &stats, SourceTimezone::Utc).unwrap();
         assert_eq!(records.len(), 1);
+        assert_eq!(
+            stats.report(),
+            ScanReport {
+                files_seen: 1,
+                files_skipped: 0,
+                parse_errors: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn scan_stats_counts_cursor_skipped_files() {
+        let dir = tempdir();
+        let file = dir.join("sess.jsonl");
+        std::fs::write(&file, "{\"id\":\"1\"}\n").unwrap();
+        let mtime = file_mtime(file.to_str().unwrap()).unwrap();
+        let cursor = encode_cursor(mtime, "zzz");
+        let paths = vec![
+            file.to_str().unwrap().to_string(),
+            dir.join("missing.jsonl").to_str().unwrap().to_string(),
+        ];
+        let stats = ScanStats::default();
+        load_jsonl(&paths, Some(&cursor), &stats, SourceTimezone::Utc).unwrap();
+        let report = stats.report();
+        assert_eq!(report.files_seen, 2);
+        assert_eq!(report.files_skipped, 2);
+        stats.reset();
+        assert_eq!(stats.report(), ScanReport::default());
     }
 
     #[test]
@@ -757,6 +1797,163 @@ mod tests {
         assert_eq!(names, vec!["a.jsonl", "b.jsonl", "c.jsonl"]);
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn collect_files_survives_symlink_cycles() {
+        let dir = tempdir();
+        std::fs::create_dir_all(dir.join("a/b")).unwrap();
+        std::fs::write(dir.join("a/b/s.jsonl"), "{}").unwrap();
+        std::os::unix::fs::symlink(dir.join("a"), dir.join("a/b/loop")).unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("a/up")).unwrap();
+        let files = collect_files_with_ext(&dir, "jsonl");
+        assert_eq!(files.len(), 1, "{files:?}");
+    }
+
+    #[test]
+    fn collect_files_respects_max_depth() {
+        let dir = tempdir();
+        std::fs::create_dir_all(dir.join("one/two")).unwrap();
+        std::fs::write(dir.join("top.jsonl"), "{}").unwrap();
+        std::fs::write(dir.join("one/mid.jsonl"), "{}").unwrap();
+        std::fs::write(dir.join("one/two/deep.jsonl"), "{}").unwrap();
+        let names = |max_depth| {
+            let options = DiscoveryOptions {
+                max_depth,
+                ..Default::default()
+            };
+            collect_files(&dir, "jsonl", &options)
+                .iter()
+                .map(|f| {
+                    Path::new(f)
+                        .file_name()
+                        .unwrap()
+                        .to_str()
+                        .unwrap()
+                        .to_string()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(0), vec!["top.jsonl"]);
+        assert_eq!(names(1), vec!["mid.jsonl", "top.jsonl"]);
+        assert_eq!(names(2).len(), 3);
+    }
+
+    #[test]
+    fn collect_files_applies_exclude_globs() {
+        let dir = tempdir();
+        std::fs::create_dir_all(dir.join("proj/subagents")).unwrap();
+        std::fs::create_dir_all(dir.join("node_modules/pkg")).unwrap();
+        std::fs::write(dir.join("proj/keep.jsonl"), "{}").unwrap();
+        std::fs::write(dir.join("proj/tmp-1.jsonl"), "{}").unwrap();
+        std::fs::write(dir.join("proj/subagents/agent.jsonl"), "{}").unwrap();
+        std::fs::write(dir.join("node_modules/pkg/x.jsonl"), "{}").unwrap();
+        let options = DiscoveryOptions {
+            exclude: vec![
+                "node_modules".to_string(),
+                "tmp-*.jsonl".to_string(),
+                "**/subagents/**".to_string(),
+            ],
+            ..Default::default()
+        };
+        let files = collect_files(&dir, "jsonl", &options);
+        assert_eq!(files.len(), 1, "{files:?}");
+        assert!(files[0].ends_with("proj/keep.jsonl"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn listing_cache_reuses_unchanged_directories() {
+        let dir = tempdir();
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("sub/a.jsonl"), "{}").unwrap();
+        let hour_ago = SystemTime::now() - std::time::Duration::from_secs(3600);
+        let set_mtime = |path: &Path, at: SystemTime| {
+            fs::File::open(path).unwrap().set_modified(at).unwrap();
+        };
+        set_mtime(&dir, hour_ago);
+        set_mtime(&dir.join("sub"), hour_ago);
+
+        let cache = Arc::new(DirListingCache::default());
+        let options = DiscoveryOptions {
+            listing_cache: Some(cache.clone()),
+            ..Default::default()
+        };
+        assert_eq!(collect_files(&dir, "jsonl", &options).len(), 1);
+        assert_eq!(cache.take_changed().len(), 2);
+
+        // Same mtime: the cached listing is trusted and the new file is not seen.
+        std::fs::write(dir.join("sub/b.jsonl"), "{}").unwrap();
+        set_mtime(&dir.join("sub"), hour_ago);
+        assert_eq!(collect_files(&dir, "jsonl", &options).len(), 1);
+        assert!(cache.take_changed().is_empty());
+
+        set_mtime(
+            &dir.join("sub"),
+            hour_ago + std::time::Duration::from_secs(60),
+        );
+        assert_eq!(collect_files(&dir, "jsonl", &options).len(), 2);
+        assert_eq!(cache.take_changed().len(), 1);
+
+        // Recently modified directories are listed but not cached.
+        set_mtime(&dir.join("sub"), SystemTime::now());
+        assert_eq!(collect_files(&dir, "jsonl", &options).len(), 2);
+        assert!(cache.take_changed().is_empty());
+    }
+
+    #[test]
+    fn collect_files_filters_by_mtime_window() {
+        let dir = tempdir();
+        std::fs::write(dir.join("old.jsonl"), "{}").unwrap();
+        std::fs::write(dir.join("new.jsonl"), "{}").unwrap();
+        let month_ago = SystemTime::now() - std::time::Duration::from_secs(30 * 86_400);
+        fs::File::options()
+            .write(true)
+            .open(dir.join("old.jsonl"))
+            .unwrap()
+            .set_modified(month_ago)
+            .unwrap();
+        let cutoff = Utc::now() - chrono::Duration::days(7);
+        let names = |options: DiscoveryOptions| {
+            collect_files(&dir, "jsonl", &options)
+                .iter()
+                .map(|f| {
+                    Path::new(f)
+                        .file_name()
+                        .unwrap()
+                        .to_str()
+                        .unwrap()
+                        .to_string()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(DiscoveryOptions {
+                modified_since: Some(cutoff),
+                ..Default::default()
+            }),
+            vec!["new.jsonl"]
+        );
+        assert_eq!(
+            names(DiscoveryOptions {
+                modified_before: Some(cutoff),
+                ..Default::default()
+            }),
+            vec!["old.jsonl"]
+        );
+    }
+
+    #[test]
+    fn glob_match_scopes_wildcards_to_segments() {
+        assert!(glob_match(b"*.jsonl", b"a.jsonl"));
+        assert!(!glob_match(b"*.jsonl", b"dir/a.jsonl"));
+        assert!(glob_match(b"**/a.jsonl", b"a.jsonl"));
+        assert!(glob_match(b"**/a.jsonl", b"x/y/a.jsonl"));
+        assert!(!glob_match(b"**/a.jsonl", b"x/ba.jsonl"));
+        assert!(glob_match(b"x/**", b"x/y/z"));
+        assert!(glob_match(b"s?ss", b"sess"));
+        assert!(!glob_match(b"a?b", b"a/b"));
+    }
+
     fn tempdir() -> std::path::PathBuf {
         use std::sync::atomic::{AtomicU64, Ordering};
         static COUNTER: AtomicU64 = AtomicU64::new(0);
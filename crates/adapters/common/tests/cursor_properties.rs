@@ -0,0 +1,275 @@
+use std::collections::{BTreeMap, HashSet};
+
+use adapter_common::{
+    ScanStats, SourceTimezone, checkpoint_cursor_from_records, encode_cursor, load_jsonl,
+    parse_cursor, should_skip,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use core_model::NativeRecord;
+use serde_json::{Value, json};
+
+// proptest is not vendored, so cases come from a seeded generator instead.
+// Override the case count with REMI_PROPTEST_CASES; failures print the seed.
+const DEFAULT_CASES: u64 = 256;
+const ID_CHARS: &[char] = &[
+    'a', 'b', 'z', '0', '9', ':', '-', '\x1f', ' ', 'é', '中', '\n',
+];
+
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed ^ 0x9e37_79b9_7f4a_7c15)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        self.0 >> 33
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+fn cases() -> u64 {
+    std::env::var("REMI_PROPTEST_CASES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_CASES)
+}
+
+fn for_each_seed(mut check: impl FnMut(u64, &mut Rng)) {
+    for seed in 0..cases() {
+        check(seed, &mut Rng::new(seed));
+    }
+}
+
+fn gen_id(rng: &mut Rng) -> String {
+    (0..rng.below(6))
+        .map(|_| ID_CHARS[rng.below(ID_CHARS.len())])
+        .collect()
+}
+
+// A narrow timestamp range with sub-second offsets forces timestamp ties and
+// exercises nanosecond round-tripping through the cursor string.
+fn gen_ts(rng: &mut Rng) -> DateTime<Utc> {
+    let base = Utc.with_ymd_and_hms(2020, 3, 1, 12, 0, 0).unwrap();
+    let nanos = match rng.below(3) {
+        0 => 0,
+        1 => rng.below(1_000) as i64 * 1_000_000,
+        _ => rng.below(1_000_000_000) as i64,
+    };
+    base + chrono::Duration::seconds(rng.below(4) as i64) + chrono::Duration::nanoseconds(nanos)
+}
+
+fn gen_records(rng: &mut Rng) -> Vec<NativeRecord> {
+    let mut seen = HashSet::new();
+    let mut records = Vec::new();
+    for _ in 0..rng.below(24) {
+        let source_id = gen_id(rng);
+        if !seen.insert(source_id.clone()) {
+            continue;
+        }
+        records.push(NativeRecord {
+            source_id,
+            updated_at: gen_ts(rng),
+            payload: Value::Null,
+        });
+    }
+    records
+}
+
+fn key(rec: &NativeRecord) -> (DateTime<Utc>, String) {
+    (rec.updated_at, rec.source_id.clone())
+}
+
+// Splits the (ts, id)-sorted records into arrival waves: everything in a wave
+// sorts after everything in earlier waves, as appended log lines do.
+fn gen_waves(rng: &mut Rng, mut records: Vec<NativeRecord>) -> Vec<Vec<NativeRecord>> {
+    records.sort_by_key(key);
+    let mut waves = Vec::new();
+    let mut rest = records.as_slice();
+    while !rest.is_empty() {
+        let take = 1 + rng.below(rest.len());
+        waves.push(rest[..take].to_vec());
+        rest = &rest[take..];
+    }
+    waves
+}
+
+#[test]
+fn cursor_roundtrips_any_id() {
+    for_each_seed(|seed, rng| {
+        let ts = gen_ts(rng);
+        let id = gen_id(rng);
+        let parsed = parse_cursor(&encode_cursor(ts, &id))
+            .unwrap_or_else(|| panic!("seed {seed}: cursor for {id:?} did not parse"));
+        assert_eq!(parsed.ts, ts, "seed {seed}");
+        assert_eq!(parsed.source_id, id, "seed {seed}");
+    });
+}
+
+#[test]
+fn legacy_cursors_parse_like_structured_ones() {
+    for_each_seed(|seed, rng| {
+        let ts = gen_ts(rng);
+        let id = gen_id(rng);
+        let legacy = parse_cursor(&format!("{}\x1f{id}", ts.to_rfc3339()))
+            .unwrap_or_else(|| panic!("seed {seed}: legacy cursor for {id:?} did not parse"));
+        assert_eq!((legacy.ts, legacy.source_id), (ts, id), "seed {seed}");
+    });
+}
+
+#[test]
+fn should_skip_matches_lexicographic_order() {
+    for_each_seed(|seed, rng| {
+        let (a_ts, a_id) = (gen_ts(rng), gen_id(rng));
+        let (b_ts, b_id) = (gen_ts(rng), gen_id(rng));
+        let cursor = parse_cursor(&encode_cursor(b_ts, &b_id)).unwrap();
+        assert_eq!(
+            should_skip(a_ts, &a_id, &cursor),
+            (a_ts, a_id.as_str()) <= (b_ts, b_id.as_str()),
+            "seed {seed}: ({a_ts}, {a_id:?}) against cursor ({b_ts}, {b_id:?})"
+        );
+    });
+}
+
+#[test]
+fn checkpoint_ignores_record_order() {
+    for_each_seed(|seed, rng| {
+        let mut records = gen_records(rng);
+        let expected = records
+            .iter()
+            .map(key)
+            .max()
+            .map(|(ts, id)| encode_cursor(ts, &id));
+        rng.shuffle(&mut records);
+        assert_eq!(
+            checkpoint_cursor_from_records(&records),
+            expected,
+            "seed {seed}"
+        );
+        let cursor = expected.as_deref().and_then(parse_cursor);
+        if let Some(cursor) = cursor {
+            assert!(
+                records
+                    .iter()
+                    .all(|r| should_skip(r.updated_at, &r.source_id, &cursor)),
+                "seed {seed}: checkpoint does not cover every record it was built from"
+            );
+        }
+    });
+}
+
+#[test]
+fn incremental_syncs_deliver_each_record_once() {
+    for_each_seed(|seed, rng| {
+        let records = gen_records(rng);
+        let total = records.len();
+        let mut visible = Vec::new();
+        let mut cursor: Option<String> = None;
+        let mut delivered: BTreeMap<String, usize> = BTreeMap::new();
+        for wave in gen_waves(rng, records) {
+            visible.extend(wave);
+            // Scanners see every record still on disk, in no particular order.
+            let mut scanned = visible.clone();
+            rng.shuffle(&mut scanned);
+            let parsed = cursor.as_deref().and_then(parse_cursor);
+            let fresh: Vec<NativeRecord> = scanned
+                .into_iter()
+                .filter(|r| {
+                    parsed
+                        .as_ref()
+                        .is_none_or(|c| !should_skip(r.updated_at, &r.source_id, c))
+                })
+                .collect();
+            for rec in &fresh {
+                *delivered.entry(rec.source_id.clone()).or_default() += 1;
+            }
+            if let Some(next) = checkpoint_cursor_from_records(&fresh) {
+                cursor = Some(next);
+            }
+        }
+        assert_eq!(delivered.len(), total, "seed {seed}: records were skipped");
+        assert!(
+            delivered.values().all(|n| *n == 1),
+            "seed {seed}: records were duplicated: {delivered:?}"
+        );
+    });
+}
+
+#[test]
+fn load_jsonl_resumes_from_checkpoint_without_gaps() {
+    let root = std::env::temp_dir().join(format!("remi-cursor-props-{}", std::process::id()));
+    let cases = cases().min(64);
+    for seed in 0..cases {
+        let rng = &mut Rng::new(seed);
+        let dir = root.join(seed.to_string());
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let paths: Vec<String> = (0..1 + rng.below(3))
+            .map(|i| {
+                dir.join(format!("s{i}.jsonl"))
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        let mut lines: Vec<Vec<String>> = vec![Vec::new(); paths.len()];
+
+        let records = gen_records(rng);
+        let total = records.len();
+        let mut cursor: Option<String> = None;
+        let mut delivered: BTreeMap<String, usize> = BTreeMap::new();
+        for wave in gen_waves(rng, records) {
+            let mut wave = wave;
+            rng.shuffle(&mut wave);
+            for rec in &wave {
+                let file = rng.below(paths.len());
+                let line = json!({
+                    "id": rec.source_id,
+                    "timestamp": rec.updated_at.to_rfc3339(),
+                    "role": "user",
+                    "content": "x",
+                });
+                lines[file].push(line.to_string());
+            }
+            for (path, body) in paths.iter().zip(&lines) {
+                std::fs::write(path, body.join("\n")).unwrap();
+            }
+
+            let fresh = load_jsonl(
+                &paths,
+                cursor.as_deref(),
+                &ScanStats::default(),
+                SourceTimezone::Utc,
+            )
+            .unwrap();
+            assert!(
+                fresh.windows(2).all(|w| key(&w[0]) <= key(&w[1])),
+                "seed {seed}: load_jsonl output is not sorted"
+            );
+            for rec in &fresh {
+                *delivered.entry(rec.source_id.clone()).or_default() += 1;
+            }
+            if let Some(next) = checkpoint_cursor_from_records(&fresh) {
+                cursor = Some(next);
+            }
+        }
+        assert_eq!(delivered.len(), total, "seed {seed}: records were skipped");
+        assert!(
+            delivered.values().all(|n| *n == 1),
+            "seed {seed}: records were duplicated: {delivered:?}"
+        );
+    }
+    let _ = std::fs::remove_dir_all(&root);
+}
@@ -1,31 +1,61 @@
-use std::{fs, io::BufRead, path::PathBuf};
+use std::{fs, io::BufRead};
 
+use adapter_common::{BaseDirs, CaptureOptions, DiscoveryOptions, ScanStats, SourceTimezone};
 use chrono::{DateTime, Utc};
 use core_model::{
-    AgentAdapter, AgentKind, ArchiveCapability, NativeRecord, NormalizedBatch, deterministic_id,
+    AgentAdapter, AgentKind, ArchiveCapability, NativeRecord, NormalizedBatch, ScanReport,
+    SourceContext, deterministic_id,
 };
 use rayon::prelude::*;
 use serde_json::Value;
 use tracing::debug;
 
-pub struct DroidAdapter;
+#[derive(Default)]
+pub struct DroidAdapter {
+    stats: ScanStats,
+    discovery: DiscoveryOptions,
+    capture: CaptureOptions,
+    timezone: Option<SourceTimezone>,
+}
+
+impl DroidAdapter {
+    pub fn with_discovery(discovery: DiscoveryOptions) -> Self {
+        Self {
+            discovery,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_capture(mut self, capture: CaptureOptions) -> Self {
+        self.capture = capture;
+        self
+    }
+
+    pub fn with_timezone(mut self, timezone: Option<SourceTimezone>) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    pub fn discover_in(&self, dirs: &BaseDirs) -> Vec<String> {
+        let mut roots = vec![dirs.home_path(".factory/sessions")];
+        roots.extend(dirs.data_paths("factory-droid/sessions"));
+        roots
+            .iter()
+            .flat_map(|dir| adapter_common::collect_files(dir, "jsonl", &self.discovery))
+            .collect()
+    }
+}
 
 impl AgentAdapter for DroidAdapter {
     fn kind(&self) -> AgentKind {
         AgentKind::Droid
     }
 
-    fn discover_source_paths(&self) -> anyhow::Result<Vec<String>> {
-        let base = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
-        let mut out = Vec::new();
-        out.extend(adapter_common::collect_files_with_ext(
-            &base.join(".factory/sessions"),
-            "jsonl",
-        ));
-        out.extend(adapter_common::collect_files_with_ext(
-            &base.join(".local/share/factory-droid/sessions"),
-            "jsonl",
-        ));
+    fn discover_source_paths(&self, ctx: &SourceContext) -> anyhow::Result<Vec<String>> {
+        let Some(dirs) = adapter_common::discovery_dirs(ctx, self.kind()) else {
+            return Ok(Vec::new());
+        };
+        let out = self.discover_in(&dirs);
         debug!(files = out.len(), "droid adapter discovered source paths");
         Ok(out)
     }
@@ -35,7 +65,14 @@ impl AgentAdapter for DroidAdapter {
         source_paths: &[String],
         cursor: Option<&str>,
     ) -> anyhow::Result<Vec<NativeRecord>> {
-        load_droid_jsonl(source_paths, cursor)
+        self.stats.reset();
+        load_droid_jsonl(
+            source_paths,
+            cursor,
+            &self.stats,
+            SourceTimezone::for_agent(self.kind(), self.timezone),
+            &self.capture,
+        )
     }
 
     fn normalize(&self, records: &[NativeRecord]) -> anyhow::Result<NormalizedBatch> {
@@ -49,6 +86,14 @@ impl AgentAdapter for DroidAdapter {
     fn archive_capability(&self) -> ArchiveCapability {
         ArchiveCapability::CentralizedCopy
     }
+
+    fn scan_report(&self) -> Option<ScanReport> {
+        Some(self.stats.report())
+    }
+
+    fn source_label(&self) -> String {
+        "Factory Droid".to_string()
+    }
 }
 
 fn parse_rfc3339(input: &str) -> Option<DateTime<Utc>> {
@@ -83,6 +128,17 @@ fn extract_text_only(content: Option<&Value>) -> String {
     texts.join("\n")
 }
 
+fn extract_reasoning_lines(content: Option<&Value>) -> Vec<String> {
+    let Some(Value::Array(arr)) = content else {
+        return Vec::new();
+    };
+    arr.iter()
+        .filter(|item| item.get("type").and_then(Value::as_str) == Some("thinking"))
+        .filter_map(|item| item.get("thinking").and_then(Value::as_str))
+        .filter_map(adapter_common::reasoning_line)
+        .collect()
+}
+
 fn has_only_tool_blocks(content: Option<&Value>) -> bool {
     let Some(Value::Array(arr)) = content else {
         return false;
@@ -101,22 +157,30 @@ fn has_only_tool_blocks(content: Option<&Value>) -> bool {
 fn load_droid_jsonl(
     source_paths: &[String],
     cursor: Option<&str>,
+    stats: &ScanStats,
+    tz: SourceTimezone,
+    capture: &CaptureOptions,
 ) -> anyhow::Result<Vec<NativeRecord>> {
     let parsed_cursor = cursor.and_then(adapter_common::parse_cursor);
     let mut out: Vec<NativeRecord> = source_paths
         .par_iter()
         .flat_map(|path| {
+            stats.file_seen();
             let file_mtime = adapter_common::file_mtime(path);
             if let Some(ref cur) = parsed_cursor
                 && let Some(mtime) = file_mtime
                 && mtime <= cur.ts
             {
+                stats.file_skipped();
                 return Vec::new();
             }
 
             let file = match fs::File::open(path) {
                 Ok(f) => f,
-                Err(_) => return Vec::new(),
+                Err(_) => {
+                    stats.file_skipped();
+                    return Vec::new();
+                }
             };
             let reader = std::io::BufReader::new(file);
             let lines: Vec<String> = reader.lines().map_while(Result::ok).collect();
@@ -138,6 +202,7 @@ fn load_droid_jsonl(
                     continue;
                 }
                 let Ok(val): Result<Value, _> = serde_json::from_str(trimmed) else {
+                    stats.parse_errors(1);
                     continue;
                 };
 
@@ -164,8 +229,7 @@ fn load_droid_jsonl(
                         }
                         let header_ts = val
                             .get("timestamp")
-                            .and_then(Value::as_str)
-                            .and_then(parse_rfc3339)
+                            .and_then(|v| adapter_common::parse_timestamp_value(v, tz))
                             .or(file_mtime);
                         if session_ts.is_none() {
                             session_ts = header_ts;
@@ -195,12 +259,14 @@ fn load_droid_jsonl(
                             first_user_text = Some(text.clone());
                         }
 
-                        let line_ts = val
+                        let Some(line_ts) = val
                             .get("timestamp")
-                            .and_then(Value::as_str)
-                            .and_then(parse_rfc3339)
+                            .and_then(|v| adapter_common::parse_timestamp_value(v, tz))
                             .or(file_mtime)
-                            .unwrap_or_else(Utc::now);
+                        else {
+                            stats.parse_errors(1);
+                            continue;
+                        };
 
                         if session_ts.is_none() {
                             session_ts = Some(line_ts);
@@ -237,6 +303,14 @@ fn load_droid_jsonl(
                             })
                             .unwrap_or_else(|| sid.clone());
 
+                        let text = if capture.reasoning {
+                            let mut lines = extract_reasoning_lines(content);
+                            lines.push(text);
+                            lines.join("\n")
+                        } else {
+                            text
+                        };
+
                         let mut obj = serde_json::Map::new();
                         obj.insert("role".to_string(), Value::String(role.to_string()));
                         obj.insert(
@@ -284,12 +358,10 @@ fn normalize_records(records: &[NativeRecord]) -> NormalizedBatch {
         std::collections::HashMap::new();
 
     for rec in records {
-        let role = rec
-            .payload
-            .get("role")
-            .and_then(Value::as_str)
-            .unwrap_or("user")
-            .to_string();
+        let role = adapter_common::normalize_role(
+            rec.payload.get("role").and_then(Value::as_str),
+            core_model::Role::User,
+        );
         let content = rec
             .payload
             .get("content")
@@ -363,6 +435,12 @@ fn normalize_records(records: &[NativeRecord]) -> NormalizedBatch {
                 .unwrap_or(kind.as_str())
                 .to_string(),
             source_id: rec.source_id.clone(),
+            workspace: rec
+                .payload
+                .get("__workspace_path")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            repo: None,
         });
     }
 
@@ -384,6 +462,7 @@ fn normalize_records(records: &[NativeRecord]) -> NormalizedBatch {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use adapter_common::Platform;
     use std::io::Write;
 
     fn tempdir() -> std::path::PathBuf {
@@ -417,7 +496,14 @@ mod tests {
                 r#"{"type":"message","id":"m2","timestamp":"2026-02-11T09:52:41.189Z","message":{"role":"assistant","content":[{"type":"text","text":"I'll build a bundled release"}]}}"#,
             ],
         );
-        let records = load_droid_jsonl(&[path], None).unwrap();
+        let records = load_droid_jsonl(
+            &[path],
+            None,
+            &ScanStats::default(),
+            SourceTimezone::Utc,
+            &CaptureOptions::default(),
+        )
+        .unwrap();
         assert_eq!(records.len(), 2);
         assert_eq!(records[0].source_id, "sess-1:0");
         assert_eq!(records[1].source_id, "sess-1:1");
@@ -473,7 +559,14 @@ mod tests {
                 r#"{"type":"message","id":"m5","timestamp":"2026-02-11T09:52:46.000Z","message":{"role":"assistant","content":[{"type":"text","text":"All done"}]}}"#,
             ],
         );
-        let records = load_droid_jsonl(&[path], None).unwrap();
+        let records = load_droid_jsonl(
+            &[path],
+            None,
+            &ScanStats::default(),
+            SourceTimezone::Utc,
+            &CaptureOptions::default(),
+        )
+        .unwrap();
         assert_eq!(records.len(), 3);
         let roles: Vec<&str> = records
             .iter()
@@ -543,7 +636,14 @@ mod tests {
                 r#"{"type":"message","id":"m1","timestamp":"2026-02-11T10:00:00Z","message":{"role":"user","content":[{"type":"text","text":"first user message as fallback"}]}}"#,
             ],
         );
-        let records = load_droid_jsonl(&[path], None).unwrap();
+        let records = load_droid_jsonl(
+            &[path],
+            None,
+            &ScanStats::default(),
+            SourceTimezone::Utc,
+            &CaptureOptions::default(),
+        )
+        .unwrap();
         assert_eq!(records.len(), 1);
         assert_eq!(
             records[0]
@@ -563,7 +663,14 @@ mod tests {
                 r#"{"type":"message","id":"m1","timestamp":"2026-02-11T10:00:00Z","message":{"role":"user","content":[{"type":"text","text":"user msg"}]}}"#,
             ],
         );
-        let records2 = load_droid_jsonl(&[path2], None).unwrap();
+        let records2 = load_droid_jsonl(
+            &[path2],
+            None,
+            &ScanStats::default(),
+            SourceTimezone::Utc,
+            &CaptureOptions::default(),
+        )
+        .unwrap();
         assert_eq!(
             records2[0]
                 .payload
@@ -582,7 +689,14 @@ mod tests {
                 r#"{"type":"message","id":"m1","timestamp":"2026-02-11T10:00:00Z","message":{"role":"user","content":[{"type":"text","text":"user msg as title"}]}}"#,
             ],
         );
-        let records3 = load_droid_jsonl(&[path3], None).unwrap();
+        let records3 = load_droid_jsonl(
+            &[path3],
+            None,
+            &ScanStats::default(),
+            SourceTimezone::Utc,
+            &CaptureOptions::default(),
+        )
+        .unwrap();
         assert_eq!(
             records3[0]
                 .payload
@@ -593,4 +707,66 @@ mod tests {
             "user msg as title"
         );
     }
+
+    #[test]
+    fn reasoning_is_labeled_and_optional() {
+        let dir = tempdir();
+        let path = write_session(
+            &dir,
+            &[
+                r#"{"type":"session_start","id":"sess-r","title":"think","cwd":"/tmp"}"#,
+                r#"{"type":"message","id":"m1","timestamp":"2026-02-11T09:52:37.424Z","message":{"role":"assistant","content":[{"type":"thinking","thinking":"weigh the options"},{"type":"text","text":"use a mutex"}]}}"#,
+                r#"{"type":"message","id":"m2","timestamp":"2026-02-11T09:52:38.424Z","message":{"role":"assistant","content":[{"type":"thinking","thinking":"only thoughts"}]}}"#,
+            ],
+        );
+        let scan = |capture: CaptureOptions| {
+            let records = load_droid_jsonl(
+                std::slice::from_ref(&path),
+                None,
+                &ScanStats::default(),
+                SourceTimezone::Utc,
+                &capture,
+            )
+            .unwrap();
+            normalize_records(&records)
+        };
+        let with = scan(CaptureOptions::default());
+        assert_eq!(with.messages.len(), 1);
+        assert_eq!(
+            with.messages[0].content,
+            "reasoning: weigh the options\nuse a mutex"
+        );
+        let without = scan(CaptureOptions {
+            reasoning: false,
+            ..CaptureOptions::default()
+        });
+        assert_eq!(without.messages[0].content, "use a mutex");
+        assert_eq!(without.messages[0].id, with.messages[0].id);
+    }
+
+    #[test]
+    fn discovery_covers_home_and_platform_data_dirs() {
+        let home = tempdir();
+        let factory = home.join(".factory/sessions/s.jsonl");
+        let local = home.join("AppData/Local/factory-droid/sessions/s.jsonl");
+        let mac = home.join("Library/Application Support/factory-droid/sessions/s.jsonl");
+        for file in [&factory, &local, &mac] {
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, "").unwrap();
+        }
+        let path = |p: &std::path::Path| p.to_string_lossy().to_string();
+        let adapter = DroidAdapter::default();
+        assert_eq!(
+            adapter.discover_in(&BaseDirs::new(Platform::Windows, home.clone())),
+            vec![path(&factory), path(&local)]
+        );
+        assert_eq!(
+            adapter.discover_in(&BaseDirs::new(Platform::MacOs, home.clone())),
+            vec![path(&factory), path(&mac)]
+        );
+        assert_eq!(
+            adapter.discover_in(&BaseDirs::new(Platform::Linux, home)),
+            vec![path(&factory)]
+        );
+    }
 }
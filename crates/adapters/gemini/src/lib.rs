@@ -0,0 +1,489 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use adapter_common::{BaseDirs, CaptureOptions, DiscoveryOptions, ScanStats, SourceTimezone};
+use chrono::{DateTime, Duration, Utc};
+use core_model::{
+    AgentAdapter, AgentKind, ArchiveCapability, NativeRecord, NormalizedBatch, ScanReport, Session,
+    SourceContext, deterministic_id,
+};
+use rayon::prelude::*;
+use serde_json::{Value, json};
+use tracing::debug;
+
+// Gemini CLI rewrites one JSON file per chat under
+// `~/.gemini/tmp/<project hash>/chats/`.
+#[derive(Default)]
+pub struct GeminiAdapter {
+    stats: ScanStats,
+    discovery: DiscoveryOptions,
+    capture: CaptureOptions,
+    timezone: Option<SourceTimezone>,
+}
+
+impl GeminiAdapter {
+    pub fn with_discovery(discovery: DiscoveryOptions) -> Self {
+        Self {
+            discovery,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_capture(mut self, capture: CaptureOptions) -> Self {
+        self.capture = capture;
+        self
+    }
+
+    pub fn with_timezone(mut self, timezone: Option<SourceTimezone>) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    pub fn discover_in(&self, dirs: &BaseDirs) -> Vec<String> {
+        adapter_common::collect_files(&dirs.home_path(".gemini/tmp"), "json", &self.discovery)
+            .into_iter()
+            .filter(|path| is_chat_file(path))
+            .collect()
+    }
+}
+
+impl AgentAdapter for GeminiAdapter {
+    fn kind(&self) -> AgentKind {
+        AgentKind::Gemini
+    }
+
+    fn discover_source_paths(&self, ctx: &SourceContext) -> anyhow::Result<Vec<String>> {
+        let Some(dirs) = adapter_common::discovery_dirs(ctx, self.kind()) else {
+            return Ok(Vec::new());
+        };
+        let paths = self.discover_in(&dirs);
+        debug!(
+            files = paths.len(),
+            "gemini adapter discovered source paths"
+        );
+        Ok(paths)
+    }
+
+    fn scan_changes_since(
+        &self,
+        source_paths: &[String],
+        cursor: Option<&str>,
+    ) -> anyhow::Result<Vec<NativeRecord>> {
+        self.stats.reset();
+        load_chats(
+            source_paths,
+            cursor,
+            &self.stats,
+            SourceTimezone::for_agent(self.kind(), self.timezone),
+        )
+    }
+
+    fn normalize(&self, records: &[NativeRecord]) -> anyhow::Result<NormalizedBatch> {
+        Ok(normalize_records(AgentKind::Gemini, records, &self.capture))
+    }
+
+    fn checkpoint_cursor(&self, records: &[NativeRecord]) -> Option<String> {
+        adapter_common::checkpoint_cursor_from_records(records)
+    }
+
+    fn archive_capability(&self) -> ArchiveCapability {
+        ArchiveCapability::CentralizedCopy
+    }
+
+    fn scan_report(&self) -> Option<ScanReport> {
+        Some(self.stats.report())
+    }
+
+    fn source_label(&self) -> String {
+        "Gemini CLI".to_string()
+    }
+}
+
+// The same directory also holds `logs.json` and checkpoints, which are not
+// transcripts.
+fn is_chat_file(path: &str) -> bool {
+    Path::new(path)
+        .parent()
+        .and_then(Path::file_name)
+        .is_some_and(|dir| dir == "chats")
+}
+
+fn normalize_records(
+    kind: AgentKind,
+    records: &[NativeRecord],
+    capture: &CaptureOptions,
+) -> NormalizedBatch {
+    debug!(kind = %kind, records = records.len(), "normalizing gemini records");
+    let mut batch = NormalizedBatch::default();
+    let mut sessions: HashMap<String, Session> = HashMap::new();
+    for rec in records {
+        let role = adapter_common::normalize_role(
+            rec.payload.get("role").and_then(Value::as_str),
+            core_model::Role::User,
+        );
+        let content =
+            adapter_common::extract_content_text_with(rec.payload.get("content"), capture);
+        if content.is_empty() {
+            continue;
+        }
+
+        let chat_id = rec
+            .payload
+            .get("__chat_id")
+            .and_then(Value::as_str)
+            .unwrap_or(&rec.source_id)
+            .to_string();
+        let title = rec
+            .payload
+            .get("__chat_title")
+            .and_then(Value::as_str)
+            .unwrap_or(&chat_id)
+            .to_string();
+        let created_at = rec
+            .payload
+            .get("__chat_ts")
+            .and_then(Value::as_str)
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+            .map_or(rec.updated_at, |ts| ts.with_timezone(&Utc));
+
+        let session_id = deterministic_id(&[kind.as_str(), "session", &chat_id]);
+        let message_id = deterministic_id(&[kind.as_str(), "message", &rec.source_id]);
+        let session = sessions
+            .entry(session_id.clone())
+            .or_insert_with(|| Session {
+                id: session_id.clone(),
+                agent: kind,
+                source_ref: chat_id.clone(),
+                title: title.clone(),
+                created_at,
+                updated_at: rec.updated_at,
+            });
+        session.created_at = session.created_at.min(created_at);
+        session.updated_at = session.updated_at.max(rec.updated_at);
+
+        batch.messages.push(core_model::Message {
+            id: message_id.clone(),
+            session_id,
+            role,
+            content,
+            ts: rec.updated_at,
+        });
+        batch.provenance.push(core_model::Provenance {
+            id: deterministic_id(&["prov", &message_id]),
+            entity_type: "message".to_string(),
+            entity_id: message_id,
+            agent: kind,
+            source_path: rec
+                .payload
+                .get("__project_root")
+                .and_then(Value::as_str)
+                .or_else(|| rec.payload.get("__source_path").and_then(Value::as_str))
+                .unwrap_or(kind.as_str())
+                .to_string(),
+            source_id: rec.source_id.clone(),
+            workspace: rec
+                .payload
+                .get("__project_root")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            repo: None,
+        });
+    }
+    let mut ordered: Vec<Session> = sessions.into_values().collect();
+    ordered.sort_by(|a, b| {
+        a.updated_at
+            .cmp(&b.updated_at)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    batch.sessions.extend(ordered);
+    debug!(
+        sessions = batch.sessions.len(),
+        messages = batch.messages.len(),
+        "gemini records normalized"
+    );
+    batch
+}
+
+fn load_chats(
+    source_paths: &[String],
+    cursor: Option<&str>,
+    stats: &ScanStats,
+    tz: SourceTimezone,
+) -> anyhow::Result<Vec<NativeRecord>> {
+    let parsed_cursor = cursor.and_then(adapter_common::parse_cursor);
+    let mut out: Vec<NativeRecord> = source_paths
+        .par_iter()
+        .flat_map(|path| {
+            stats.file_seen();
+            let file_mtime = adapter_common::file_mtime(path);
+            if let Some(ref cur) = parsed_cursor
+                && let Some(mtime) = file_mtime
+                && mtime <= cur.ts
+            {
+                stats.file_skipped();
+                return Vec::new();
+            }
+            let Ok(text) = fs::read_to_string(path) else {
+                stats.file_skipped();
+                return Vec::new();
+            };
+            let Ok(chat) = serde_json::from_str::<Value>(&text) else {
+                stats.parse_errors(1);
+                return Vec::new();
+            };
+            let Some(messages) = chat.get("messages").and_then(Value::as_array) else {
+                return Vec::new();
+            };
+
+            let chat_id = chat
+                .get("sessionId")
+                .and_then(Value::as_str)
+                .filter(|id| !id.trim().is_empty())
+                .map(ToOwned::to_owned)
+                .unwrap_or_else(|| {
+                    Path::new(path)
+                        .file_stem()
+                        .and_then(|s| s.to_str())
+                        .unwrap_or(path)
+                        .to_string()
+                });
+            let chat_ts = chat
+                .get("startTime")
+                .and_then(|v| adapter_common::parse_timestamp_value(v, tz));
+            let title = messages
+                .iter()
+                .find(|m| m.get("type").and_then(Value::as_str) == Some("user"))
+                .map(|m| adapter_common::extract_content_text(m.get("content")))
+                .filter(|text| !text.is_empty())
+                .map(|text| title_from(&text))
+                .unwrap_or_else(|| chat_id.clone());
+            let project_root = project_root(path);
+
+            messages
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, message)| {
+                    let role = match message.get("type").and_then(Value::as_str) {
+                        Some("user") => "user",
+                        Some("gemini") => "assistant",
+                        // `info`, `error` and `warning` are notices from the CLI itself.
+                        _ => return None,
+                    };
+                    let Some(ts) = message
+                        .get("timestamp")
+                        .and_then(|v| adapter_common::parse_timestamp_value(v, tz))
+                        .or_else(|| chat_ts.map(|ts| ts + Duration::milliseconds(idx as i64)))
+                        .or(file_mtime)
+                    else {
+                        stats.parse_errors(1);
+                        return None;
+                    };
+                    let message_key = message
+                        .get("id")
+                        .and_then(Value::as_str)
+                        .filter(|id| !id.is_empty())
+                        .map_or_else(|| idx.to_string(), ToOwned::to_owned);
+                    let source_id = format!("{chat_id}:{message_key}");
+                    if let Some(ref cur) = parsed_cursor
+                        && adapter_common::should_skip(ts, &source_id, cur)
+                    {
+                        return None;
+                    }
+
+                    let mut payload = json!({
+                        "role": role,
+                        "content": content_blocks(message),
+                        "__chat_id": chat_id,
+                        "__chat_title": title,
+                        "__source_path": path,
+                    });
+                    if let Some(chat_ts) = chat_ts {
+                        payload["__chat_ts"] = Value::String(chat_ts.to_rfc3339());
+                    }
+                    if let Some(root) = &project_root {
+                        payload["__project_root"] = Value::String(root.clone());
+                    }
+                    Some(NativeRecord {
+                        source_id,
+                        updated_at: ts,
+                        payload,
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    out.sort_by(|a, b| {
+        a.updated_at
+            .cmp(&b.updated_at)
+            .then_with(|| a.source_id.cmp(&b.source_id))
+    });
+    debug!(total = out.len(), "gemini chats loaded");
+    Ok(out)
+}
+
+// Thoughts, text and tool calls as the content blocks the shared extractor
+// understands, in the order the CLI shows them.
+fn content_blocks(message: &Value) -> Value {
+    let mut blocks = Vec::new();
+    for thought in message
+        .get("thoughts")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let text = [thought.get("subject"), thought.get("description")]
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .filter(|s| !s.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join(": ");
+        blocks.push(json!({ "type": "thinking", "thinking": text }));
+    }
+    if let Some(content) = message.get("content") {
+        blocks.push(content.clone());
+    }
+    for call in message
+        .get("toolCalls")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        blocks.push(json!({
+            "type": "tool_use",
+            "name": call.get("name"),
+            "input": call.get("args"),
+        }));
+        if let Some(result) = call.get("resultDisplay").filter(|v| !v.is_null()) {
+            blocks.push(json!({ "type": "tool_result", "content": result }));
+        }
+    }
+    Value::Array(blocks)
+}
+
+// Newer CLI versions record the project directory next to the chats, since
+// the directory name is only a hash of it.
+fn project_root(chat_path: &str) -> Option<String> {
+    let dir = Path::new(chat_path).parent()?.parent()?;
+    let root = fs::read_to_string(dir.join(".project_root")).ok()?;
+    let root = root.trim();
+    (!root.is_empty()).then(|| root.to_string())
+}
+
+fn title_from(text: &str) -> String {
+    let line = text.lines().next().unwrap_or_default().trim();
+    if line.chars().count() > 80 {
+        format!("{}…", line.chars().take(80).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adapter_common::Platform;
+
+    const CHAT: &str = r#"{
+        "sessionId": "6f1c",
+        "projectHash": "ab12",
+        "startTime": "2025-07-01T09:00:00.000Z",
+        "lastUpdated": "2025-07-01T09:02:00.000Z",
+        "messages": [
+            {"id": "m1", "timestamp": "2025-07-01T09:00:00.000Z", "type": "user",
+             "content": "why does the login redirect loop?"},
+            {"id": "m2", "timestamp": "2025-07-01T09:00:01.000Z", "type": "info",
+             "content": "Switched to fallback model"},
+            {"id": "m3", "timestamp": "2025-07-01T09:01:00.000Z", "type": "gemini",
+             "content": "The cookie is dropped on redirect.",
+             "thoughts": [{"subject": "Checking auth", "description": "Look at   the callback."}],
+             "toolCalls": [{"id": "t1", "name": "read_file", "args": {"path": "src/auth.rs"},
+                            "resultDisplay": "fn callback() {}", "status": "success"}]}
+        ]
+    }"#;
+
+    fn write_chat(home: &Path) -> String {
+        let dir = home.join(".gemini/tmp/ab12/chats");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(home.join(".gemini/tmp/ab12/.project_root"), "/work/app\n").unwrap();
+        std::fs::write(home.join(".gemini/tmp/ab12/logs.json"), "[]").unwrap();
+        let chat = dir.join("session-2025-07-01T09-00-6f1c.json");
+        std::fs::write(&chat, CHAT).unwrap();
+        chat.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn chats_normalize_into_one_session_with_tools_and_thoughts() {
+        let home = std::env::temp_dir().join(format!("remi_gemini_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&home);
+        let chat = write_chat(&home);
+        let adapter = GeminiAdapter::default();
+        assert_eq!(
+            adapter.discover_in(&BaseDirs::new(Platform::Linux, home.clone())),
+            vec![chat.clone()]
+        );
+
+        let records = load_chats(
+            std::slice::from_ref(&chat),
+            None,
+            &ScanStats::default(),
+            SourceTimezone::Utc,
+        )
+        .unwrap();
+        let ids: Vec<&str> = records.iter().map(|r| r.source_id.as_str()).collect();
+        assert_eq!(ids, vec!["6f1c:m1", "6f1c:m3"]);
+
+        let batch = adapter.normalize(&records).unwrap();
+        assert_eq!(batch.sessions.len(), 1);
+        let session = &batch.sessions[0];
+        assert_eq!(session.source_ref, "6f1c");
+        assert_eq!(session.title, "why does the login redirect loop?");
+        assert_eq!(session.created_at.to_rfc3339(), "2025-07-01T09:00:00+00:00");
+        assert_eq!(batch.messages[1].role, "assistant");
+        assert_eq!(
+            batch.messages[1].content,
+            format!(
+                "{}Checking auth: Look at the callback.\nThe cookie is dropped on redirect.\ntool_use: read_file {{\"path\":\"src/auth.rs\"}}\ntool_result: fn callback() {{}}",
+                core_model::REASONING_PREFIX
+            )
+        );
+        assert_eq!(batch.provenance[0].source_path, "/work/app");
+
+        let cursor = adapter.checkpoint_cursor(&records).unwrap();
+        let unchanged = load_chats(
+            &[chat],
+            Some(&cursor),
+            &ScanStats::default(),
+            SourceTimezone::Utc,
+        )
+        .unwrap();
+        assert!(unchanged.is_empty());
+        let _ = std::fs::remove_dir_all(&home);
+    }
+
+    #[test]
+    fn chats_without_ids_fall_back_to_file_stem_and_index() {
+        let dir = std::env::temp_dir().join(format!("remi_gemini_ids_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let chat = dir.join("session-a.json");
+        std::fs::write(
+            &chat,
+            r#"{"startTime":"2025-07-01T09:00:00Z","messages":[{"type":"user","content":"hi"}]}"#,
+        )
+        .unwrap();
+        let records = load_chats(
+            &[chat.to_string_lossy().to_string()],
+            None,
+            &ScanStats::default(),
+            SourceTimezone::Utc,
+        )
+        .unwrap();
+        assert_eq!(records[0].source_id, "session-a:0");
+        assert_eq!(
+            records[0].updated_at.to_rfc3339(),
+            "2025-07-01T09:00:00+00:00"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,21 @@
+use adapter_common::golden::assert_golden;
+
+#[test]
+fn chats_fixture_matches_golden() {
+    assert_golden(
+        &gemini::GeminiAdapter::default(),
+        env!("CARGO_MANIFEST_DIR"),
+        "chats",
+        "json",
+    );
+}
+
+#[test]
+fn partial_fixture_matches_golden() {
+    assert_golden(
+        &gemini::GeminiAdapter::default(),
+        env!("CARGO_MANIFEST_DIR"),
+        "partial",
+        "json",
+    );
+}
@@ -1,40 +1,77 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
     fs,
     path::{Path, PathBuf},
-    sync::OnceLock,
+    sync::{Arc, Mutex, PoisonError},
 };
 
+use adapter_common::{BaseDirs, CaptureOptions, DiscoveryOptions, ScanStats, SourceTimezone};
 use chrono::{DateTime, TimeZone, Utc};
 use core_model::{
-    AgentAdapter, AgentKind, ArchiveCapability, NativeRecord, NormalizedBatch, deterministic_id,
+    AgentAdapter, AgentKind, ArchiveCapability, NativeRecord, NormalizedBatch, ScanReport,
+    SourceContext, deterministic_id,
 };
 use rayon::prelude::*;
 use rusqlite::{Connection, OpenFlags};
 use serde_json::Value;
-use tracing::debug;
+use tracing::{debug, warn};
 
-pub struct OpenCodeAdapter;
+#[derive(Default)]
+pub struct OpenCodeAdapter {
+    stats: ScanStats,
+    discovery: DiscoveryOptions,
+    capture: CaptureOptions,
+    timezone: Option<SourceTimezone>,
+}
+
+impl OpenCodeAdapter {
+    pub fn with_discovery(discovery: DiscoveryOptions) -> Self {
+        Self {
+            discovery,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_capture(mut self, capture: CaptureOptions) -> Self {
+        self.capture = capture;
+        self
+    }
+
+    pub fn with_timezone(mut self, timezone: Option<SourceTimezone>) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    pub fn discover_in(&self, dirs: &BaseDirs) -> Vec<String> {
+        let mut paths = Vec::new();
+        for root in dirs.data_paths("opencode") {
+            let sqlite = root.join("opencode.db");
+            if sqlite.is_file() {
+                paths.push(sqlite.to_string_lossy().to_string());
+            }
+            paths.extend(adapter_common::collect_files(
+                &root.join("storage").join("message"),
+                "json",
+                &self.discovery,
+            ));
+        }
+        paths
+    }
+}
 
 impl AgentAdapter for OpenCodeAdapter {
     fn kind(&self) -> AgentKind {
         AgentKind::OpenCode
     }
 
-    fn discover_source_paths(&self) -> anyhow::Result<Vec<String>> {
-        let base = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
-        let mut paths = Vec::new();
-        let sqlite = base.join(".local/share/opencode/opencode.db");
-        if sqlite.is_file() {
-            paths.push(sqlite.to_string_lossy().to_string());
-        }
-        paths.extend(adapter_common::collect_files_with_ext(
-            &base.join(".local/share/opencode/storage/message"),
-            "json",
-        ));
+    fn discover_source_paths(&self, ctx: &SourceContext) -> anyhow::Result<Vec<String>> {
+        let Some(dirs) = adapter_common::discovery_dirs(ctx, self.kind()) else {
+            return Ok(Vec::new());
+        };
+        let paths = self.discover_in(&dirs);
         debug!(
             files = paths.len(),
-            has_sqlite = sqlite.is_file(),
+            has_sqlite = paths.iter().any(|p| p.ends_with(".db")),
             "opencode adapter discovered source paths"
         );
         Ok(paths)
@@ -46,16 +83,24 @@ impl AgentAdapter for OpenCodeAdapter {
         cursor: Option<&str>,
     ) -> anyhow::Result<Vec<NativeRecord>> {
         debug!(files = source_paths.len(), cursor = ?cursor, "opencode scan starting");
-        load_message_json(source_paths, cursor)
+        self.stats.reset();
+        load_message_json(
+            source_paths,
+            cursor,
+            &self.stats,
+            SourceTimezone::for_agent(self.kind(), self.timezone),
+            &self.capture,
+        )
     }
 
     fn normalize(&self, records: &[NativeRecord]) -> anyhow::Result<NormalizedBatch> {
         debug!(records = records.len(), "normalizing opencode records");
-        Ok(normalize_records(
-            AgentKind::OpenCode,
-            records,
-            cached_session_meta_index(),
-        ))
+        let index = cached_session_meta_index(
+            records
+                .iter()
+                .filter_map(|rec| rec.payload.get("__source_path").and_then(Value::as_str)),
+        );
+        Ok(normalize_records(AgentKind::OpenCode, records, &index))
     }
 
     fn checkpoint_cursor(&self, records: &[NativeRecord]) -> Option<String> {
@@ -65,12 +110,77 @@ impl AgentAdapter for OpenCodeAdapter {
     fn archive_capability(&self) -> ArchiveCapability {
         ArchiveCapability::CentralizedCopy
     }
-}
 
-static SESSION_META_INDEX: OnceLock<SessionMetaIndex> = OnceLock::new();
+    fn scan_report(&self) -> Option<ScanReport> {
+        Some(self.stats.report())
+    }
+
+    fn source_label(&self) -> String {
+        "OpenCode".to_string()
+    }
+
+    // Message files are grouped in one directory per session, and the SQLite
+    // database holds its own session table.
+    fn estimate_sessions(&self, source_paths: &[String]) -> usize {
+        let mut dirs = HashSet::new();
+        let mut sessions = 0;
+        for path in source_paths {
+            if path.ends_with(".db") {
+                sessions += Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+                    .and_then(|conn| {
+                        conn.query_row("SELECT COUNT(*) FROM session", [], |r| r.get::<_, i64>(0))
+                    })
+                    .unwrap_or_default() as usize;
+            } else if let Some(parent) = Path::new(path).parent() {
+                dirs.insert(parent.to_path_buf());
+            }
+        }
+        sessions + dirs.len()
+    }
+
+    // One group per session directory; each database is a group of its own.
+    fn scan_groups(&self, source_paths: &[String]) -> Vec<Vec<String>> {
+        let mut groups: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
+        for path in source_paths {
+            let key = if path.ends_with(".db") {
+                PathBuf::from(path)
+            } else {
+                Path::new(path)
+                    .parent()
+                    .unwrap_or(Path::new(path))
+                    .to_path_buf()
+            };
+            groups.entry(key).or_default().push(path.clone());
+        }
+        groups.into_values().collect()
+    }
+
+    fn tracks_file_state(&self) -> bool {
+        false
+    }
+}
 
-fn cached_session_meta_index() -> &'static SessionMetaIndex {
-    SESSION_META_INDEX.get_or_init(load_session_meta_index)
+// Keyed by the session directories the index was read from, which sit beside
+// the scanned sources, so no lookup depends on the home directory.
+static SESSION_META_INDEX: Mutex<BTreeMap<Vec<PathBuf>, Arc<SessionMetaIndex>>> =
+    Mutex::new(BTreeMap::new());
+
+fn cached_session_meta_index<'a>(
+    source_paths: impl IntoIterator<Item = &'a str>,
+) -> Arc<SessionMetaIndex> {
+    let mut roots: Vec<PathBuf> = source_paths
+        .into_iter()
+        .filter_map(storage_root)
+        .map(|storage| storage.join("session"))
+        .collect();
+    roots.sort();
+    roots.dedup();
+    SESSION_META_INDEX
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .entry(roots)
+        .or_insert_with_key(|roots| Arc::new(load_session_meta_index(roots)))
+        .clone()
 }
 
 fn normalize_records(
@@ -82,12 +192,10 @@ fn normalize_records(
     let mut sessions: HashMap<String, core_model::Session> = HashMap::new();
 
     for rec in records {
-        let role = rec
-            .payload
-            .get("role")
-            .and_then(Value::as_str)
-            .unwrap_or("user")
-            .to_string();
+        let role = adapter_common::normalize_role(
+            rec.payload.get("role").and_then(Value::as_str),
+            core_model::Role::User,
+        );
         let content = rec
             .payload
             .get("__content")
@@ -173,6 +281,13 @@ fn normalize_records(
             agent: kind,
             source_path: source_path.unwrap_or(kind.as_str()).to_string(),
             source_id: rec.source_id.clone(),
+            workspace: rec
+                .payload
+                .pointer("/path/cwd")
+                .and_then(Value::as_str)
+                .or_else(|| session_meta.and_then(|meta| meta.directory.as_deref()))
+                .map(str::to_string),
+            repo: None,
         });
     }
 
@@ -194,6 +309,9 @@ fn normalize_records(
 fn load_message_json(
     source_paths: &[String],
     cursor: Option<&str>,
+    stats: &ScanStats,
+    tz: SourceTimezone,
+    capture: &CaptureOptions,
 ) -> anyhow::Result<Vec<NativeRecord>> {
     let (db_paths, json_paths): (Vec<_>, Vec<_>) = source_paths
         .iter()
@@ -201,7 +319,8 @@ fn load_message_json(
 
     let mut out = Vec::new();
     for db_path in &db_paths {
-        out.extend(load_message_sqlite(db_path, cursor)?);
+        stats.file_seen();
+        out.extend(load_message_sqlite(db_path, cursor, capture)?);
     }
     if json_paths.is_empty() {
         out.sort_by(|a, b| {
@@ -213,23 +332,35 @@ fn load_message_json(
     }
 
     let parsed_cursor = cursor.and_then(adapter_common::parse_cursor);
-    let session_meta_index = cached_session_meta_index();
+    let index = cached_session_meta_index(json_paths.iter().map(|p| p.as_str()));
+    let session_meta_index = index.as_ref();
 
     out.extend(
         json_paths
             .par_iter()
             .filter_map(|path| {
+                stats.file_seen();
                 let file_mtime = adapter_common::file_mtime(path);
                 if let Some(ref cur) = parsed_cursor
                     && let Some(mtime) = file_mtime
                     && mtime <= cur.ts
                 {
+                    stats.file_skipped();
                     return None;
                 }
 
-                let content = fs::read_to_string(path).ok()?;
-                let mut val: Value = serde_json::from_str(&content).ok()?;
-                let ts = extract_ts(&val).or(file_mtime).unwrap_or_else(Utc::now);
+                let Ok(content) = fs::read_to_string(path) else {
+                    stats.file_skipped();
+                    return None;
+                };
+                let Ok(mut val) = serde_json::from_str::<Value>(&content) else {
+                    stats.parse_errors(1);
+                    return None;
+                };
+                let Some(ts) = extract_ts(&val, tz).or(file_mtime) else {
+                    stats.parse_errors(1);
+                    return None;
+                };
                 let source_id = val
                     .get("id")
                     .and_then(Value::as_str)
@@ -241,7 +372,7 @@ fn load_message_json(
                     return None;
                 }
 
-                let content_text = extract_part_text(&source_id);
+                let content_text = extract_part_text(path, &source_id, capture);
                 if content_text.is_empty() {
                     return None;
                 }
@@ -282,7 +413,11 @@ fn load_message_json(
     Ok(out)
 }
 
-fn load_message_sqlite(db_path: &str, cursor: Option<&str>) -> anyhow::Result<Vec<NativeRecord>> {
+fn load_message_sqlite(
+    db_path: &str,
+    cursor: Option<&str>,
+    capture: &CaptureOptions,
+) -> anyhow::Result<Vec<NativeRecord>> {
     if !Path::new(db_path).is_file() {
         debug!(db_path, "sqlite db not found, skipping");
         return Ok(Vec::new());
@@ -339,7 +474,7 @@ fn load_message_sqlite(db_path: &str, cursor: Option<&str>) -> anyhow::Result<Ve
         let Ok(value): Result<Value, _> = serde_json::from_str(&data_json) else {
             continue;
         };
-        let Some(text) = extract_sqlite_part_text(&value) else {
+        let Some(text) = extract_sqlite_part_text(&value, capture) else {
             continue;
         };
 
@@ -355,8 +490,14 @@ fn load_message_sqlite(db_path: &str, cursor: Option<&str>) -> anyhow::Result<Ve
         let updated_at = Utc
             .timestamp_millis_opt(updated_ms)
             .single()
-            .or_else(|| Utc.timestamp_millis_opt(created_ms).single())
-            .unwrap_or_else(Utc::now);
+            .or_else(|| Utc.timestamp_millis_opt(created_ms).single());
+        let Some(updated_at) = updated_at else {
+            warn!(
+                message_id,
+                "skipping opencode message without a valid timestamp"
+            );
+            continue;
+        };
         if let Some(ref cur) = parsed_cursor
             && adapter_common::should_skip(updated_at, &message_id, cur)
         {
@@ -406,11 +547,11 @@ fn load_message_sqlite(db_path: &str, cursor: Option<&str>) -> anyhow::Result<Ve
     Ok(out)
 }
 
-fn extract_sqlite_part_text(part: &Value) -> Option<String> {
+fn extract_sqlite_part_text(part: &Value, capture: &CaptureOptions) -> Option<String> {
     if part.get("type").and_then(Value::as_str) == Some("tool") {
         return extract_sqlite_tool_part_text(part);
     }
-    let text = adapter_common::extract_content_text(Some(part));
+    let text = adapter_common::extract_content_text_with(Some(part), capture);
     if text.trim().is_empty() {
         None
     } else {
@@ -483,14 +624,29 @@ fn format_tool_payload(value: &Value) -> Option<String> {
     }
 }
 
-fn extract_part_text(message_id: &str) -> String {
+// Messages live in <storage>/message/<session>/<id>.json and the database at
+// <root>/opencode.db has its legacy storage at <root>/storage.
+fn storage_root(source_path: &str) -> Option<PathBuf> {
+    let path = Path::new(source_path);
+    if path.extension().is_some_and(|ext| ext == "db") {
+        return path.parent().map(|root| root.join("storage"));
+    }
+    path.ancestors()
+        .find(|dir| dir.file_name().is_some_and(|name| name == "message"))
+        .and_then(Path::parent)
+        .map(Path::to_path_buf)
+}
+
+// Parts sit beside the message tree under <storage>/part/<id>/.
+fn part_dir(message_path: &str, message_id: &str) -> Option<PathBuf> {
+    Some(storage_root(message_path)?.join("part").join(message_id))
+}
+
+fn extract_part_text(message_path: &str, message_id: &str, capture: &CaptureOptions) -> String {
     let mut out = String::new();
-    let Some(home) = dirs::home_dir() else {
+    let Some(part_dir) = part_dir(message_path, message_id) else {
         return out;
     };
-    let part_dir = home
-        .join(".local/share/opencode/storage/part")
-        .join(message_id);
     let mut files = adapter_common::collect_files_with_ext(&part_dir, "json");
     files.sort();
     for file in files {
@@ -500,12 +656,15 @@ fn extract_part_text(message_id: &str) -> String {
         let Ok(val): Result<Value, _> = serde_json::from_str(&content) else {
             continue;
         };
-        let text = val
-            .get("text")
-            .and_then(Value::as_str)
-            .unwrap_or("")
-            .trim()
-            .to_string();
+        let text = val.get("text").and_then(Value::as_str).unwrap_or("").trim();
+        let text = if val.get("type").and_then(Value::as_str) == Some("reasoning") {
+            if !capture.reasoning {
+                continue;
+            }
+            adapter_common::reasoning_line(text).unwrap_or_default()
+        } else {
+            text.to_string()
+        };
         if text.is_empty() {
             continue;
         }
@@ -617,28 +776,20 @@ fn normalize_session_key(candidate: &str) -> Option<String> {
     }
 }
 
-fn extract_ts(val: &Value) -> Option<DateTime<Utc>> {
-    if let Some(ms) = val
-        .get("time")
+fn extract_ts(val: &Value, tz: SourceTimezone) -> Option<DateTime<Utc>> {
+    val.get("time")
         .and_then(|t| t.get("created"))
-        .and_then(Value::as_i64)
-    {
-        return Utc.timestamp_millis_opt(ms).single();
-    }
-    if let Some(ms) = val.get("timestamp").and_then(Value::as_i64) {
-        return Utc.timestamp_millis_opt(ms).single();
-    }
-    if let Some(s) = val.get("timestamp").and_then(Value::as_str) {
-        return DateTime::parse_from_rfc3339(s)
-            .ok()
-            .map(|dt| dt.with_timezone(&Utc));
-    }
-    None
+        .and_then(|v| adapter_common::parse_timestamp_value(v, tz))
+        .or_else(|| {
+            val.get("timestamp")
+                .and_then(|v| adapter_common::parse_timestamp_value(v, tz))
+        })
 }
 
 #[derive(Clone)]
 struct SessionMeta {
     title: String,
+    directory: Option<String>,
     created_at: DateTime<Utc>,
     updated_at: DateTime<Utc>,
 }
@@ -681,6 +832,9 @@ impl SessionMetaIndex {
                 {
                     existing.title = meta.title.clone();
                 }
+                if existing.directory.is_none() {
+                    existing.directory = meta.directory.clone();
+                }
             })
             .or_insert(meta);
 
@@ -694,13 +848,9 @@ impl SessionMetaIndex {
     }
 }
 
-fn load_session_meta_index() -> SessionMetaIndex {
+fn load_session_meta_index(roots: &[PathBuf]) -> SessionMetaIndex {
     let mut out = SessionMetaIndex::default();
-    let Some(home) = dirs::home_dir() else {
-        return out;
-    };
-    let root = home.join(".local/share/opencode/storage/session");
-    let mut stack = vec![root];
+    let mut stack = roots.to_vec();
 
     while let Some(dir) = stack.pop() {
         let Ok(entries) = fs::read_dir(&dir) else {
@@ -749,7 +899,10 @@ fn load_session_meta_index() -> SessionMetaIndex {
                 .and_then(|t| t.get("created"))
                 .and_then(Value::as_i64)
                 .and_then(|ms| Utc.timestamp_millis_opt(ms).single())
-                .unwrap_or_else(Utc::now);
+                .or_else(|| path.to_str().and_then(adapter_common::file_mtime));
+            let Some(created_at) = created_at else {
+                continue;
+            };
             let updated_at = val
                 .get("time")
                 .and_then(|t| t.get("updated"))
@@ -762,6 +915,10 @@ fn load_session_meta_index() -> SessionMetaIndex {
                 aliases,
                 SessionMeta {
                     title,
+                    directory: val
+                        .get("directory")
+                        .and_then(Value::as_str)
+                        .map(str::to_string),
                     created_at,
                     updated_at,
                 },
@@ -775,6 +932,7 @@ fn load_session_meta_index() -> SessionMetaIndex {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use adapter_common::Platform;
     use rusqlite::params;
     use std::sync::atomic::{AtomicU64, Ordering};
 
@@ -788,6 +946,26 @@ mod tests {
         dir.join("opencode.db")
     }
 
+    #[test]
+    fn scan_groups_follow_session_directories() {
+        let paths: Vec<String> = [
+            "/d/storage/message/ses_a/msg_1.json",
+            "/d/opencode.db",
+            "/d/storage/message/ses_b/msg_1.json",
+            "/d/storage/message/ses_a/msg_2.json",
+        ]
+        .map(String::from)
+        .to_vec();
+        assert_eq!(
+            OpenCodeAdapter::default().scan_groups(&paths),
+            vec![
+                vec![paths[1].clone()],
+                vec![paths[0].clone(), paths[3].clone()],
+                vec![paths[2].clone()],
+            ]
+        );
+    }
+
     fn create_test_sqlite(db_path: &std::path::Path) {
         let conn = Connection::open(db_path).expect("open sqlite");
         conn.execute_batch(
@@ -862,6 +1040,19 @@ mod tests {
         .expect("insert part");
     }
 
+    #[test]
+    fn estimates_sessions_from_database_and_message_dirs() {
+        let db_path = temp_db_path();
+        create_test_sqlite(&db_path);
+        let sources = vec![
+            db_path.to_string_lossy().to_string(),
+            "/tmp/storage/message/session-a/1.json".to_string(),
+            "/tmp/storage/message/session-a/2.json".to_string(),
+            "/tmp/storage/message/session-b/1.json".to_string(),
+        ];
+        assert_eq!(OpenCodeAdapter::default().estimate_sessions(&sources), 3);
+    }
+
     #[test]
     fn resolve_session_key_prefers_meta_aliases() {
         let now = Utc::now();
@@ -871,6 +1062,7 @@ mod tests {
             vec!["legacy-session".to_string()],
             SessionMeta {
                 title: "Session Title".to_string(),
+                directory: None,
                 created_at: now,
                 updated_at: now,
             },
@@ -937,6 +1129,7 @@ mod tests {
             vec!["legacy-session".to_string()],
             SessionMeta {
                 title: "Canonical".to_string(),
+                directory: None,
                 created_at: now,
                 updated_at: now,
             },
@@ -976,8 +1169,9 @@ mod tests {
         let db_path = temp_db_path();
         create_test_sqlite(&db_path);
 
-        let records = load_message_sqlite(&db_path.to_string_lossy(), None)
-            .expect("sqlite records should load");
+        let records =
+            load_message_sqlite(&db_path.to_string_lossy(), None, &CaptureOptions::default())
+                .expect("sqlite records should load");
         assert_eq!(records.len(), 1);
         let payload = &records[0].payload;
         assert_eq!(records[0].source_id, "msg-1");
@@ -1004,8 +1198,14 @@ mod tests {
         let db_path = temp_db_path();
         create_test_sqlite(&db_path);
 
-        let records = load_message_json(&[db_path.to_string_lossy().to_string()], None)
-            .expect("load_message_json should read sqlite");
+        let records = load_message_json(
+            &[db_path.to_string_lossy().to_string()],
+            None,
+            &ScanStats::default(),
+            SourceTimezone::Utc,
+            &CaptureOptions::default(),
+        )
+        .expect("load_message_json should read sqlite");
         assert_eq!(records.len(), 1);
         assert_eq!(records[0].source_id, "msg-1");
     }
@@ -1074,7 +1274,9 @@ mod tests {
         )
         .expect("insert tool part");
 
-        let records = load_message_sqlite(&db_path.to_string_lossy(), None).expect("load sqlite");
+        let records =
+            load_message_sqlite(&db_path.to_string_lossy(), None, &CaptureOptions::default())
+                .expect("load sqlite");
         assert_eq!(records.len(), 1);
         let content = records[0]
             .payload
@@ -1084,4 +1286,41 @@ mod tests {
         assert!(content.contains("tool_use: bash {\"command\":\"pwd\"}"));
         assert!(content.contains("tool_result: /tmp"));
     }
+
+    #[test]
+    fn discovery_finds_storage_under_each_platform_data_dir() {
+        let home = temp_db_path().parent().unwrap().join("home");
+        let roaming = home.join("AppData/Roaming/opencode");
+        let mac = home.join("Library/Application Support/opencode");
+        for root in [&roaming, &mac] {
+            std::fs::create_dir_all(root.join("storage/message/ses_1")).unwrap();
+            std::fs::write(root.join("opencode.db"), "").unwrap();
+            std::fs::write(root.join("storage/message/ses_1/msg_1.json"), "{}").unwrap();
+        }
+        let path = |p: std::path::PathBuf| p.to_string_lossy().to_string();
+        let adapter = OpenCodeAdapter::default();
+        let windows = BaseDirs {
+            app_data: Some(home.join("AppData/Roaming")),
+            ..BaseDirs::new(Platform::Windows, home.clone())
+        };
+        assert_eq!(
+            adapter.discover_in(&windows),
+            vec![
+                path(roaming.join("opencode.db")),
+                path(roaming.join("storage/message/ses_1/msg_1.json"))
+            ]
+        );
+        assert_eq!(
+            adapter.discover_in(&BaseDirs::new(Platform::MacOs, home.clone())),
+            vec![
+                path(mac.join("opencode.db")),
+                path(mac.join("storage/message/ses_1/msg_1.json"))
+            ]
+        );
+        assert!(
+            adapter
+                .discover_in(&BaseDirs::new(Platform::Linux, home))
+                .is_empty()
+        );
+    }
 }
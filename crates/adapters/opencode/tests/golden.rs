@@ -0,0 +1,11 @@
+use adapter_common::golden::assert_golden;
+
+#[test]
+fn storage_fixture_matches_golden() {
+    assert_golden(
+        &opencode::OpenCodeAdapter::default(),
+        env!("CARGO_MANIFEST_DIR"),
+        "storage",
+        "json",
+    );
+}
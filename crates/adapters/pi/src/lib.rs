@@ -1,31 +1,61 @@
-use std::{fs, io::BufRead, path::PathBuf};
+use std::{fs, io::BufRead};
 
+use adapter_common::{BaseDirs, CaptureOptions, DiscoveryOptions, ScanStats, SourceTimezone};
 use chrono::{DateTime, Utc};
 use core_model::{
-    AgentAdapter, AgentKind, ArchiveCapability, NativeRecord, NormalizedBatch, deterministic_id,
+    AgentAdapter, AgentKind, ArchiveCapability, NativeRecord, NormalizedBatch, ScanReport,
+    SourceContext, deterministic_id,
 };
 use rayon::prelude::*;
 use serde_json::Value;
 use tracing::debug;
 
-pub struct PiAdapter;
+#[derive(Default)]
+pub struct PiAdapter {
+    stats: ScanStats,
+    discovery: DiscoveryOptions,
+    capture: CaptureOptions,
+    timezone: Option<SourceTimezone>,
+}
+
+impl PiAdapter {
+    pub fn with_discovery(discovery: DiscoveryOptions) -> Self {
+        Self {
+            discovery,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_capture(mut self, capture: CaptureOptions) -> Self {
+        self.capture = capture;
+        self
+    }
+
+    pub fn with_timezone(mut self, timezone: Option<SourceTimezone>) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    pub fn discover_in(&self, dirs: &BaseDirs) -> Vec<String> {
+        [".pi/agent/sessions", ".pi/sessions"]
+            .iter()
+            .flat_map(|dir| {
+                adapter_common::collect_files(&dirs.home_path(dir), "jsonl", &self.discovery)
+            })
+            .collect()
+    }
+}
 
 impl AgentAdapter for PiAdapter {
     fn kind(&self) -> AgentKind {
         AgentKind::Pi
     }
 
-    fn discover_source_paths(&self) -> anyhow::Result<Vec<String>> {
-        let base = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
-        let mut out = Vec::new();
-        out.extend(adapter_common::collect_files_with_ext(
-            &base.join(".pi/agent/sessions"),
-            "jsonl",
-        ));
-        out.extend(adapter_common::collect_files_with_ext(
-            &base.join(".pi/sessions"),
-            "jsonl",
-        ));
+    fn discover_source_paths(&self, ctx: &SourceContext) -> anyhow::Result<Vec<String>> {
+        let Some(dirs) = adapter_common::discovery_dirs(ctx, self.kind()) else {
+            return Ok(Vec::new());
+        };
+        let out = self.discover_in(&dirs);
         debug!(files = out.len(), "pi adapter discovered source paths");
         Ok(out)
     }
@@ -35,11 +65,17 @@ impl AgentAdapter for PiAdapter {
         source_paths: &[String],
         cursor: Option<&str>,
     ) -> anyhow::Result<Vec<NativeRecord>> {
-        load_pi_jsonl(source_paths, cursor)
+        self.stats.reset();
+        load_pi_jsonl(
+            source_paths,
+            cursor,
+            &self.stats,
+            SourceTimezone::for_agent(self.kind(), self.timezone),
+        )
     }
 
     fn normalize(&self, records: &[NativeRecord]) -> anyhow::Result<NormalizedBatch> {
-        Ok(normalize_records(records))
+        Ok(normalize_records(records, &self.capture))
     }
 
     fn checkpoint_cursor(&self, records: &[NativeRecord]) -> Option<String> {
@@ -49,6 +85,14 @@ impl AgentAdapter for PiAdapter {
     fn archive_capability(&self) -> ArchiveCapability {
         ArchiveCapability::CentralizedCopy
     }
+
+    fn scan_report(&self) -> Option<ScanReport> {
+        Some(self.stats.report())
+    }
+
+    fn source_label(&self) -> String {
+        "Pi".to_string()
+    }
 }
 
 fn parse_rfc3339(input: &str) -> Option<DateTime<Utc>> {
@@ -87,7 +131,7 @@ fn extract_text_only(content: Option<&Value>) -> String {
     parts.join("\n")
 }
 
-fn extract_message_content(role: &str, content: Option<&Value>) -> String {
+fn extract_message_content(role: &str, content: Option<&Value>, reasoning: bool) -> String {
     if role == "toolResult" {
         let text = extract_text_only(content);
         if text.is_empty() {
@@ -118,6 +162,15 @@ fn extract_message_content(role: &str, content: Option<&Value>) -> String {
                     parts.push(line);
                 }
             }
+            Some("thinking") if reasoning => {
+                if let Some(line) = obj
+                    .get("thinking")
+                    .and_then(Value::as_str)
+                    .and_then(adapter_common::reasoning_line)
+                {
+                    parts.push(line);
+                }
+            }
             _ => {}
         }
     }
@@ -159,22 +212,29 @@ fn format_tool_call(obj: &serde_json::Map<String, Value>) -> Option<String> {
 fn load_pi_jsonl(
     source_paths: &[String],
     cursor: Option<&str>,
+    stats: &ScanStats,
+    tz: SourceTimezone,
 ) -> anyhow::Result<Vec<NativeRecord>> {
     let parsed_cursor = cursor.and_then(adapter_common::parse_cursor);
     let mut out: Vec<NativeRecord> = source_paths
         .par_iter()
         .flat_map(|path| {
+            stats.file_seen();
             let file_mtime = adapter_common::file_mtime(path);
             if let Some(ref cur) = parsed_cursor
                 && let Some(mtime) = file_mtime
                 && mtime <= cur.ts
             {
+                stats.file_skipped();
                 return Vec::new();
             }
 
             let file = match fs::File::open(path) {
                 Ok(f) => f,
-                Err(_) => return Vec::new(),
+                Err(_) => {
+                    stats.file_skipped();
+                    return Vec::new();
+                }
             };
             let reader = std::io::BufReader::new(file);
             let lines: Vec<String> = reader.lines().map_while(Result::ok).collect();
@@ -195,16 +255,19 @@ fn load_pi_jsonl(
                     continue;
                 }
                 let Ok(val): Result<Value, _> = serde_json::from_str(trimmed) else {
+                    stats.parse_errors(1);
                     continue;
                 };
 
                 let line_type = val.get("type").and_then(Value::as_str).unwrap_or("");
-                let line_ts = val
+                let Some(line_ts) = val
                     .get("timestamp")
-                    .and_then(Value::as_str)
-                    .and_then(parse_rfc3339)
+                    .and_then(|v| adapter_common::parse_timestamp_value(v, tz))
                     .or(file_mtime)
-                    .unwrap_or_else(Utc::now);
+                else {
+                    stats.parse_errors(1);
+                    continue;
+                };
 
                 match line_type {
                     "session" => {
@@ -224,7 +287,9 @@ fn load_pi_jsonl(
                         };
                         let role = msg.get("role").and_then(Value::as_str).unwrap_or("user");
 
-                        let content_text = extract_message_content(role, msg.get("content"));
+                        // Reasoning is left out here so reasoning-only entries keep
+                        // being skipped and message ordinals stay stable.
+                        let content_text = extract_message_content(role, msg.get("content"), false);
                         if content_text.is_empty() {
                             continue;
                         }
@@ -257,6 +322,10 @@ fn load_pi_jsonl(
                         let source_id =
                             format_pi_source_id(&sid, msg_index, tool_result_id.as_deref());
                         msg_index += 1;
+                        let native_id = val
+                            .get("id")
+                            .and_then(Value::as_str)
+                            .filter(|id| !id.is_empty());
 
                         if let Some(ref cur) = parsed_cursor
                             && adapter_common::should_skip(line_ts, &source_id, cur)
@@ -282,6 +351,12 @@ fn load_pi_jsonl(
                         } else {
                             obj.insert("content".to_string(), Value::Array(vec![]));
                         }
+                        if let Some(native_id) = native_id {
+                            obj.insert(
+                                "__message_key".to_string(),
+                                Value::String(format!("{sid}:id:{native_id}")),
+                            );
+                        }
                         obj.insert("__thread_id".to_string(), Value::String(sid.clone()));
                         obj.insert("__thread_title".to_string(), Value::String(title));
                         if let Some(ts) = session_ts {
@@ -315,7 +390,7 @@ fn load_pi_jsonl(
     Ok(out)
 }
 
-fn normalize_records(records: &[NativeRecord]) -> NormalizedBatch {
+fn normalize_records(records: &[NativeRecord], capture: &CaptureOptions) -> NormalizedBatch {
     let kind = AgentKind::Pi;
     debug!(records = records.len(), "normalizing pi records");
     let mut batch = NormalizedBatch::default();
@@ -323,14 +398,14 @@ fn normalize_records(records: &[NativeRecord]) -> NormalizedBatch {
         std::collections::HashMap::new();
 
     for rec in records {
-        let role = rec
+        let native_role = rec
             .payload
             .get("role")
             .and_then(Value::as_str)
-            .unwrap_or("user")
-            .to_string();
-        let normalized_role = if role == "user" { "user" } else { "assistant" };
-        let content = extract_message_content(&role, rec.payload.get("content"));
+            .unwrap_or("user");
+        let role = adapter_common::normalize_role(Some(native_role), core_model::Role::Assistant);
+        let content =
+            extract_message_content(native_role, rec.payload.get("content"), capture.reasoning);
         if content.is_empty() {
             continue;
         }
@@ -355,7 +430,8 @@ fn normalize_records(records: &[NativeRecord]) -> NormalizedBatch {
             .unwrap_or(rec.updated_at);
 
         let session_id = deterministic_id(&[kind.as_str(), "session", &thread_id]);
-        let message_id = deterministic_id(&[kind.as_str(), "message", &rec.source_id]);
+        let message_id =
+            deterministic_id(&[kind.as_str(), "message", adapter_common::message_key(rec)]);
         let session = sessions
             .entry(session_id.clone())
             .or_insert_with(|| core_model::Session {
@@ -379,7 +455,7 @@ fn normalize_records(records: &[NativeRecord]) -> NormalizedBatch {
         batch.messages.push(core_model::Message {
             id: message_id.clone(),
             session_id: session_id.clone(),
-            role: normalized_role.to_string(),
+            role,
             content,
             ts: rec.updated_at,
         });
@@ -396,6 +472,12 @@ fn normalize_records(records: &[NativeRecord]) -> NormalizedBatch {
                 .unwrap_or(kind.as_str())
                 .to_string(),
             source_id: rec.source_id.clone(),
+            workspace: rec
+                .payload
+                .get("__workspace_path")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            repo: None,
         });
     }
 
@@ -417,6 +499,7 @@ fn normalize_records(records: &[NativeRecord]) -> NormalizedBatch {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use adapter_common::Platform;
     use std::io::Write;
 
     fn tempdir() -> std::path::PathBuf {
@@ -438,6 +521,25 @@ mod tests {
         file.to_str().unwrap().to_string()
     }
 
+    #[test]
+    fn naive_line_timestamps_use_source_timezone() {
+        let dir = tempdir();
+        let path = write_session(
+            &dir,
+            &[
+                r#"{"type":"session","version":3,"id":"sess-tz","timestamp":"2026-02-08 12:54:12"}"#,
+                r#"{"type":"message","id":"a1","timestamp":"2026-02-08 12:54:41","message":{"role":"user","content":[{"type":"text","text":"hello"}]}}"#,
+            ],
+        );
+        let tz: SourceTimezone = "+02:00".parse().unwrap();
+        let records = load_pi_jsonl(&[path], None, &ScanStats::default(), tz).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(
+            records[0].updated_at.to_rfc3339(),
+            "2026-02-08T10:54:41+00:00"
+        );
+    }
+
     #[test]
     fn load_pi_session_basic() {
         let dir = tempdir();
@@ -450,7 +552,8 @@ mod tests {
                 r#"{"type":"message","id":"bad2ad59","parentId":"5a68fc81","timestamp":"2026-02-08T10:54:45.731Z","message":{"role":"assistant","content":[{"type":"thinking","thinking":"let me think..."},{"type":"text","text":"Looking at the code..."}]}}"#,
             ],
         );
-        let records = load_pi_jsonl(&[path], None).unwrap();
+        let records =
+            load_pi_jsonl(&[path], None, &ScanStats::default(), SourceTimezone::Utc).unwrap();
         assert_eq!(records.len(), 2);
         assert_eq!(records[0].source_id, "sess-pi-1:00000000000000000000");
         assert_eq!(records[1].source_id, "sess-pi-1:00000000000000000001");
@@ -504,7 +607,8 @@ mod tests {
                 r#"{"type":"message","id":"m4","parentId":"m3","timestamp":"2026-02-08T10:55:03.000Z","message":{"role":"assistant","content":[{"type":"text","text":"All tests passed!"}]}}"#,
             ],
         );
-        let records = load_pi_jsonl(&[path], None).unwrap();
+        let records =
+            load_pi_jsonl(&[path], None, &ScanStats::default(), SourceTimezone::Utc).unwrap();
         assert_eq!(records.len(), 4);
         let source_ids: Vec<&str> = records.iter().map(|r| r.source_id.as_str()).collect();
         assert_eq!(
@@ -522,12 +626,9 @@ mod tests {
             .collect();
         assert_eq!(roles, vec!["user", "assistant", "toolResult", "assistant"]);
 
-        let batch = normalize_records(&records);
+        let batch = normalize_records(&records, &CaptureOptions::default());
         let batch_roles: Vec<&str> = batch.messages.iter().map(|msg| msg.role.as_str()).collect();
-        assert_eq!(
-            batch_roles,
-            vec!["user", "assistant", "assistant", "assistant"]
-        );
+        assert_eq!(batch_roles, vec!["user", "assistant", "tool", "assistant"]);
         let contents: Vec<&str> = batch
             .messages
             .iter()
@@ -581,7 +682,7 @@ mod tests {
             },
         ];
 
-        let batch = normalize_records(&records);
+        let batch = normalize_records(&records, &CaptureOptions::default());
         assert_eq!(batch.sessions.len(), 1);
         assert_eq!(batch.messages.len(), 2);
         assert_eq!(batch.provenance.len(), 2);
@@ -591,8 +692,42 @@ mod tests {
         assert_eq!(batch.messages[0].role, "user");
         assert_eq!(batch.messages[0].content, "check this app");
         assert_eq!(batch.messages[1].role, "assistant");
-        assert_eq!(batch.messages[1].content, "Looking at the code...");
+        assert_eq!(
+            batch.messages[1].content,
+            "reasoning: let me think...\nLooking at the code..."
+        );
         assert_eq!(batch.provenance[0].source_path, "/home/leo/code/Remi");
+
+        let without = normalize_records(
+            &records,
+            &CaptureOptions {
+                reasoning: false,
+                ..CaptureOptions::default()
+            },
+        );
+        assert_eq!(without.messages[1].content, "Looking at the code...");
+    }
+
+    #[test]
+    fn message_ids_follow_native_ids_after_compaction() {
+        let dir = tempdir();
+        let header = r#"{"type":"session","version":3,"id":"sess-pi-6","timestamp":"2026-02-08T10:54:12.530Z","cwd":"/tmp"}"#;
+        let first = r#"{"type":"message","id":"m1","parentId":null,"timestamp":"2026-02-08T10:55:00.000Z","message":{"role":"user","content":[{"type":"text","text":"old question"}]}}"#;
+        let second = r#"{"type":"message","id":"m2","parentId":"m1","timestamp":"2026-02-08T10:55:01.000Z","message":{"role":"assistant","content":[{"type":"text","text":"kept answer"}]}}"#;
+        let path = write_session(&dir, &[header, first, second]);
+        let before = normalize_records(
+            &load_pi_jsonl(&[path], None, &ScanStats::default(), SourceTimezone::Utc).unwrap(),
+            &CaptureOptions::default(),
+        );
+
+        let path = write_session(&dir, &[header, second]);
+        let after = normalize_records(
+            &load_pi_jsonl(&[path], None, &ScanStats::default(), SourceTimezone::Utc).unwrap(),
+            &CaptureOptions::default(),
+        );
+
+        assert_eq!(after.messages.len(), 1);
+        assert_eq!(after.messages[0].id, before.messages[1].id);
     }
 
     #[test]
@@ -607,7 +742,13 @@ mod tests {
         );
 
         let legacy_cursor = "2026-02-08T10:55:00+00:00\x1fsess-pi-3:0";
-        let records = load_pi_jsonl(&[path], Some(legacy_cursor)).unwrap();
+        let records = load_pi_jsonl(
+            &[path],
+            Some(legacy_cursor),
+            &ScanStats::default(),
+            SourceTimezone::Utc,
+        )
+        .unwrap();
 
         assert_eq!(records.len(), 1);
         assert_eq!(records[0].source_id, "sess-pi-3:00000000000000000000");
@@ -626,7 +767,13 @@ mod tests {
         );
 
         let cursor = "2026-02-08T10:55:00+00:00\x1fsess-pi-4:00000000000000000000";
-        let records = load_pi_jsonl(&[path], Some(cursor)).unwrap();
+        let records = load_pi_jsonl(
+            &[path],
+            Some(cursor),
+            &ScanStats::default(),
+            SourceTimezone::Utc,
+        )
+        .unwrap();
 
         assert_eq!(records.len(), 1);
         assert_eq!(records[0].source_id, "sess-pi-4:00000000000000000001");
@@ -646,7 +793,13 @@ mod tests {
             ],
         );
 
-        let records = load_pi_jsonl(std::slice::from_ref(&path), None).unwrap();
+        let records = load_pi_jsonl(
+            std::slice::from_ref(&path),
+            None,
+            &ScanStats::default(),
+            SourceTimezone::Utc,
+        )
+        .unwrap();
         let source_ids: Vec<&str> = records
             .iter()
             .map(|record| record.source_id.as_str())
@@ -662,9 +815,31 @@ mod tests {
         );
 
         let cursor = "2026-02-08T10:55:00+00:00\x1fsess-pi-5:00000000000000000002:toolResult:m3";
-        let records = load_pi_jsonl(&[path], Some(cursor)).unwrap();
+        let records = load_pi_jsonl(
+            &[path],
+            Some(cursor),
+            &ScanStats::default(),
+            SourceTimezone::Utc,
+        )
+        .unwrap();
 
         assert_eq!(records.len(), 1);
         assert_eq!(records[0].source_id, "sess-pi-5:00000000000000000003");
     }
+
+    #[test]
+    fn discovery_uses_the_home_dotdir_on_every_platform() {
+        let home = tempdir();
+        let file = home.join(".pi/agent/sessions/s.jsonl");
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(&file, "").unwrap();
+        for platform in [Platform::Linux, Platform::MacOs, Platform::Windows] {
+            let found = PiAdapter::default().discover_in(&BaseDirs::new(platform, home.clone()));
+            assert_eq!(
+                found,
+                vec![file.to_string_lossy().to_string()],
+                "{platform:?}"
+            );
+        }
+    }
 }
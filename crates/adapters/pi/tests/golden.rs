@@ -0,0 +1,11 @@
+use adapter_common::golden::assert_golden;
+
+#[test]
+fn tool_calls_fixture_matches_golden() {
+    assert_golden(
+        &pi::PiAdapter::default(),
+        env!("CARGO_MANIFEST_DIR"),
+        "tool_calls",
+        "jsonl",
+    );
+}
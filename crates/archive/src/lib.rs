@@ -1,4 +1,8 @@
-use std::{fs, path::PathBuf};
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
 
 use anyhow::Context;
 use chrono::Duration;
@@ -21,6 +25,8 @@ pub struct ArchiveBundle {
     pub events: Vec<core_model::Event>,
     pub artifacts: Vec<core_model::Artifact>,
     pub provenance: Vec<core_model::Provenance>,
+    #[serde(default)]
+    pub session_meta: Vec<core_model::SessionMeta>,
 }
 
 pub fn archive_plan(
@@ -36,6 +42,14 @@ pub fn archive_plan(
     Ok(run.id)
 }
 
+// Bundles are written to one subdirectory per run.
+pub fn archive_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("remi")
+        .join("archive")
+}
+
 #[instrument(skip(store), fields(run_id = %run_id))]
 pub fn archive_run(
     store: &SqliteStore,
@@ -53,11 +67,7 @@ pub fn archive_run(
         ));
     }
 
-    let base = dirs::data_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("remi")
-        .join("archive")
-        .join(run_id);
+    let base = archive_dir().join(run_id);
     fs::create_dir_all(&base)?;
 
     let mut bundle = ArchiveBundle {
@@ -67,23 +77,23 @@ pub fn archive_run(
         events: Vec::new(),
         artifacts: Vec::new(),
         provenance: Vec::new(),
+        session_meta: Vec::new(),
     };
     for item in &items {
-        if let Some(session) = store.get_session(&item.session_id)? {
-            bundle.sessions.push(session);
+        let Some(session) = store.get_session_bundle(&item.session_id)? else {
+            continue;
+        };
+        if let Some(meta) = session.meta {
+            bundle.session_meta.push(core_model::SessionMeta {
+                session_id: session.session.id.clone(),
+                meta,
+            });
         }
-        bundle
-            .messages
-            .extend(store.get_session_messages(&item.session_id)?);
-        bundle
-            .events
-            .extend(store.get_session_events(&item.session_id)?);
-        bundle
-            .artifacts
-            .extend(store.get_session_artifacts(&item.session_id)?);
-        bundle
-            .provenance
-            .extend(store.get_provenance_for_session(&item.session_id)?);
+        bundle.sessions.push(session.session);
+        bundle.messages.extend(session.messages);
+        bundle.events.extend(session.events);
+        bundle.artifacts.extend(session.artifacts);
+        bundle.provenance.extend(session.provenance);
     }
 
     let payload = serde_json::to_vec_pretty(&bundle)?;
@@ -138,8 +148,112 @@ pub fn archive_restore(store: &mut SqliteStore, bundle_path: &str) -> anyhow::Re
         events: bundle.events,
         artifacts: bundle.artifacts,
         provenance: bundle.provenance,
+        session_meta: bundle.session_meta,
     };
     let count = batch.sessions.len();
     store.save_batch(&batch)?;
     Ok(format!("restored {} sessions", count))
 }
+
+// Removes matching sessions from every bundle under `dir` and rewrites the
+// bundle and its manifest. Returns the removed session ids per run; with
+// `dry_run` nothing is written.
+pub fn wipe_bundles(
+    dir: &Path,
+    matches: impl Fn(&core_model::Session, &[&core_model::Message], &[&core_model::Provenance]) -> bool,
+    dry_run: bool,
+) -> anyhow::Result<Vec<(String, Vec<String>)>> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(Vec::new());
+    };
+    let mut wiped = Vec::new();
+    for entry in entries {
+        let base = entry?.path();
+        let bundle_path = base.join("sessions.json");
+        if !bundle_path.is_file() {
+            continue;
+        }
+        let bytes =
+            fs::read(&bundle_path).with_context(|| format!("reading {}", bundle_path.display()))?;
+        let mut bundle: ArchiveBundle = serde_json::from_slice(&bytes)
+            .with_context(|| format!("parsing {}", bundle_path.display()))?;
+        let removed: HashSet<String> = bundle
+            .sessions
+            .iter()
+            .filter(|session| {
+                let messages: Vec<&core_model::Message> = bundle
+                    .messages
+                    .iter()
+                    .filter(|m| m.session_id == session.id)
+                    .collect();
+                let provenance: Vec<&core_model::Provenance> = bundle
+                    .provenance
+                    .iter()
+                    .filter(|p| {
+                        p.entity_id == session.id || messages.iter().any(|m| m.id == p.entity_id)
+                    })
+                    .collect();
+                matches(session, &messages, &provenance)
+            })
+            .map(|session| session.id.clone())
+            .collect();
+        if removed.is_empty() {
+            continue;
+        }
+        let mut ids: Vec<String> = removed.iter().cloned().collect();
+        ids.sort();
+        wiped.push((bundle.run_id.clone(), ids));
+        if dry_run {
+            continue;
+        }
+        let mut entities: HashSet<String> = removed.clone();
+        entities.extend(
+            bundle
+                .messages
+                .iter()
+                .filter(|m| removed.contains(&m.session_id))
+                .map(|m| m.id.clone()),
+        );
+        entities.extend(
+            bundle
+                .events
+                .iter()
+                .filter(|e| removed.contains(&e.session_id))
+                .map(|e| e.id.clone()),
+        );
+        entities.extend(
+            bundle
+                .artifacts
+                .iter()
+                .filter(|a| removed.contains(&a.session_id))
+                .map(|a| a.id.clone()),
+        );
+        bundle.sessions.retain(|s| !removed.contains(&s.id));
+        bundle.messages.retain(|m| !removed.contains(&m.session_id));
+        bundle.events.retain(|e| !removed.contains(&e.session_id));
+        bundle
+            .artifacts
+            .retain(|a| !removed.contains(&a.session_id));
+        bundle
+            .session_meta
+            .retain(|m| !removed.contains(&m.session_id));
+        bundle
+            .provenance
+            .retain(|p| !entities.contains(&p.entity_id));
+
+        let payload = serde_json::to_vec_pretty(&bundle)?;
+        let manifest = ArchiveManifest {
+            run_id: bundle.run_id.clone(),
+            sessions: bundle.sessions.iter().map(|s| s.id.clone()).collect(),
+            checksum: blake3::hash(&payload).to_hex().to_string(),
+        };
+        fs::write(&bundle_path, &payload)?;
+        fs::write(
+            base.join("manifest.json"),
+            serde_json::to_vec_pretty(&manifest)?,
+        )?;
+        debug!(run_id = %bundle.run_id, removed = removed.len(), "archive bundle wiped");
+    }
+    wiped.sort();
+    Ok(wiped)
+}
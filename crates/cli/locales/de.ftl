@@ -0,0 +1,61 @@
+## Interactive search
+
+search-filter-prompt = Filter (unscharf; Felder: agent:, title:, id:, contains:; Beispiel: "agent:claude auth login") — Enter zum Beibehalten:
+search-select-prompt = Index wählen (Standard 0):
+search-open-prompt = Diese Sitzung öffnen? [J/n]:
+search-preview = Vorschau: { $title } ({ $id })
+search-no-preview = (keine passenden Nachrichten)
+search-message-count = { $count ->
+    [one] { $count } Nachr.
+   *[other] { $count } Nachr.
+}
+search-explain-hits = { $count ->
+    [one] { $count } passende Nachricht
+   *[other] { $count } passende Nachrichten
+}
+search-explain-terms = gefunden: { $matched }; fehlend: { $missing }
+
+## Sessions
+
+show-no-system-prompts = keine Systemprompts erfasst; [capture] system_prompts = true setzen und neu synchronisieren
+burst-label = Arbeitsphase { $burst } von { $total } · { $idle } Pause
+
+## Browse
+
+browse-help = Enter/n weiter · b zurück · <N> springen · y Nachricht kopieren · Y[N] Codeblock kopieren · p Quellpfad kopieren · q beenden
+browse-title = { $title } ({ $count ->
+    [one] { $count } Nachricht
+   *[other] { $count } Nachrichten
+})
+browse-empty = Sitzung { $id } enthält keine Nachrichten
+browse-unknown-key = unbekannte Taste { $key }; { browse-help }
+browse-last = letzte Nachricht; q zum Beenden
+browse-no-message = keine Nachricht { $n }; 1-{ $total } wählen
+browse-no-code-block = Nachricht hat keinen Codeblock { $n }
+browse-no-source = für diese Nachricht ist keine Herkunft gespeichert
+browse-code-count = ({ $count ->
+    [one] { $count } Codeblock
+   *[other] { $count } Codeblöcke
+})
+browse-copied = { $what ->
+    [message] Nachricht
+    [code] Codeblock
+   *[source] Quellpfad
+} kopiert ({ $chars } Zeichen) über { $via }
+browse-copy-failed = Kopieren fehlgeschlagen: { $error }
+
+## HTML export
+
+html-id = ID: { $id }
+html-message-count = { $count ->
+    [one] { $count } Nachricht
+   *[other] { $count } Nachrichten
+}
+html-stats = Statistik
+html-source = Quelle
+html-source-agent = Agent
+html-source-path = Pfad
+html-source-id = Quell-ID
+html-source-repo = Repository
+html-open-full = Vollständige Nachricht öffnen ({ $size })
+html-show-more = Mehr anzeigen ({ $size })
@@ -0,0 +1,64 @@
+# Remi user-facing strings. Message ids are shared by every locale; a missing
+# id falls back to this file.
+
+## Interactive search
+
+search-filter-prompt = filter (fuzzy; fields: agent:, title:, id:, contains:; example: "agent:claude auth login") — enter to keep:
+search-select-prompt = select index (default 0):
+search-open-prompt = open this session? [Y/n]:
+search-preview = preview: { $title } ({ $id })
+search-no-preview = (no matching messages)
+search-message-count = { $count ->
+    [one] { $count } msg
+   *[other] { $count } msgs
+}
+search-explain-hits = { $count ->
+    [one] { $count } matching message
+   *[other] { $count } matching messages
+}
+search-explain-terms = matched: { $matched }; missing: { $missing }
+
+## Sessions
+
+show-no-system-prompts = no system prompts captured; set [capture] system_prompts = true and resync
+burst-label = burst { $burst } of { $total } · { $idle } idle
+
+## Browse
+
+browse-help = enter/n next · b back · <N> jump · y copy message · Y[N] copy code block · p copy source path · q quit
+browse-title = { $title } ({ $count ->
+    [one] { $count } message
+   *[other] { $count } messages
+})
+browse-empty = session { $id } has no messages
+browse-unknown-key = unknown key { $key }; { browse-help }
+browse-last = last message; q to quit
+browse-no-message = no message { $n }; pick 1-{ $total }
+browse-no-code-block = message has no code block { $n }
+browse-no-source = no provenance recorded for this message
+browse-code-count = ({ $count ->
+    [one] { $count } code block
+   *[other] { $count } code blocks
+})
+browse-copied = copied { $what ->
+    [message] message
+    [code] code block
+   *[source] source path
+} ({ $chars } chars) via { $via }
+browse-copy-failed = copy failed: { $error }
+
+## HTML export
+
+html-id = ID: { $id }
+html-message-count = { $count ->
+    [one] { $count } message
+   *[other] { $count } messages
+}
+html-stats = Stats
+html-source = Source
+html-source-agent = agent
+html-source-path = path
+html-source-id = source id
+html-source-repo = repo
+html-open-full = Open full message ({ $size })
+html-show-more = Show more ({ $size })
@@ -0,0 +1,183 @@
+use core_model::{Message, Session};
+use store_sqlite::{Bookmark, SqliteStore};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Card {
+    pub front: String,
+    pub back: String,
+    pub tags: Vec<String>,
+}
+
+// `--filter` accepts `tag:<name>`; no filter exports every bookmark.
+pub fn parse_filter(raw: Option<&str>) -> anyhow::Result<Option<String>> {
+    let Some(raw) = raw else {
+        return Ok(None);
+    };
+    match raw.split_once(':') {
+        Some(("tag", tag)) if !tag.trim().is_empty() => Ok(Some(tag.trim().to_string())),
+        _ => anyhow::bail!("unsupported --filter {raw:?}; expected tag:<name>"),
+    }
+}
+
+pub fn cards(store: &SqliteStore, bookmarks: &[Bookmark]) -> anyhow::Result<Vec<Card>> {
+    let mut out = Vec::new();
+    for bookmark in bookmarks {
+        let Some(session) = store.get_session(&bookmark.message.session_id)? else {
+            continue;
+        };
+        let messages = store.get_session_messages(&session.id)?;
+        if let Some(card) = card_for(&session, &messages, bookmark) {
+            out.push(card);
+        }
+    }
+    Ok(out)
+}
+
+// A bookmarked prompt pairs with the next assistant reply; a bookmarked
+// reply pairs with the prompt before it.
+fn card_for(session: &Session, messages: &[Message], bookmark: &Bookmark) -> Option<Card> {
+    let position = messages.iter().position(|m| m.id == bookmark.message.id)?;
+    let (question, answer) = if bookmark.message.role == "user" {
+        let answer = messages[position + 1..]
+            .iter()
+            .take_while(|m| m.role != "user")
+            .filter(|m| m.role == "assistant")
+            .map(|m| card_text(&m.content))
+            .find(|text| !text.is_empty())?;
+        (card_text(&bookmark.message.content), answer)
+    } else {
+        let question = messages[..position]
+            .iter()
+            .rev()
+            .find(|m| m.role == "user")
+            .map(|m| card_text(&m.content))?;
+        (question, card_text(&bookmark.message.content))
+    };
+    if question.is_empty() || answer.is_empty() {
+        return None;
+    }
+    let mut tags = vec!["remi".to_string(), session.agent.as_str().to_string()];
+    tags.extend(
+        bookmark
+            .tags
+            .iter()
+            .map(|t| t.replace(char::is_whitespace, "_")),
+    );
+    Some(Card {
+        front: question,
+        back: answer,
+        tags,
+    })
+}
+
+// Reasoning and tool traffic stay out of the card.
+fn card_text(content: &str) -> String {
+    let (_, text) = core_model::split_reasoning(content);
+    text.lines()
+        .filter(|line| !line.starts_with("tool_use:") && !line.starts_with("tool_result:"))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+// Anki's text importer reads the `#` header lines (2.1.54+), so the file
+// imports as front/back notes with tags without touching the dialog.
+pub fn render_csv(cards: &[Card]) -> String {
+    let mut out = String::from("#separator:Comma\n#html:true\n#tags column:3\n");
+    for card in cards {
+        out.push_str(&format!(
+            "{},{},{}\n",
+            csv_field(&html_field(&card.front)),
+            csv_field(&html_field(&card.back)),
+            csv_field(&card.tags.join(" "))
+        ));
+    }
+    out
+}
+
+fn html_field(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\n', "<br>")
+}
+
+fn csv_field(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone, Utc};
+    use core_model::AgentKind;
+
+    fn message(i: i64, role: &str, content: &str) -> Message {
+        Message {
+            id: format!("m{i}"),
+            session_id: "s1".to_string(),
+            role: role.to_string(),
+            content: content.to_string(),
+            ts: Utc.with_ymd_and_hms(2025, 5, 1, 9, 0, 0).unwrap() + Duration::seconds(i),
+        }
+    }
+
+    fn bookmark(message: &Message, tags: &[&str]) -> Bookmark {
+        Bookmark {
+            message: message.clone(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn bookmarks_pair_prompts_with_replies() {
+        let session = Session {
+            id: "s1".to_string(),
+            agent: AgentKind::Claude,
+            source_ref: "/tmp/s1.jsonl".to_string(),
+            title: "lifetimes".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let messages = vec![
+            message(0, "user", "What does 'static mean?"),
+            message(1, "assistant", "tool_use: Read {\"path\":\"a.rs\"}"),
+            message(2, "tool", "tool_result: ok"),
+            message(
+                3,
+                "assistant",
+                "reasoning: thinking\nIt lives for the whole <program>.",
+            ),
+            message(4, "user", "thanks"),
+        ];
+
+        let from_prompt = card_for(&session, &messages, &bookmark(&messages[0], &["learning"]));
+        let card = from_prompt.unwrap();
+        assert_eq!(card.front, "What does 'static mean?");
+        assert_eq!(card.back, "It lives for the whole <program>.");
+        assert_eq!(card.tags, vec!["remi", "claude", "learning"]);
+
+        let from_reply = card_for(&session, &messages, &bookmark(&messages[3], &["rust tips"]));
+        assert_eq!(from_reply.unwrap().tags[2], "rust_tips");
+        assert!(card_for(&session, &messages, &bookmark(&messages[4], &[])).is_none());
+
+        let csv = render_csv(&[card]);
+        assert_eq!(
+            csv.lines().nth(3).unwrap(),
+            r#""What does 'static mean?","It lives for the whole &lt;program&gt;.","remi claude learning""#
+        );
+    }
+
+    #[test]
+    fn filter_accepts_tags_only() {
+        assert_eq!(parse_filter(None).unwrap(), None);
+        assert_eq!(
+            parse_filter(Some("tag:learning")).unwrap().as_deref(),
+            Some("learning")
+        );
+        assert!(parse_filter(Some("agent:claude")).is_err());
+        assert!(parse_filter(Some("tag:")).is_err());
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::{BTreeSet, HashMap};
+
+use regex::{Captures, Regex};
+
+// Accounts that say nothing about who ran the session.
+const GENERIC_USERS: &[&str] = &["root", "shared", "public", "default", "guest", "runner"];
+
+// Paths under these stay readable; they are the same on every machine.
+const SYSTEM_PREFIXES: &[&str] = &[
+    "/bin/", "/sbin/", "/usr/", "/etc/", "/dev/", "/proc/", "/sys/", "/lib/", "/lib64/",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Kind {
+    User,
+    Host,
+    Email,
+    Dir,
+}
+
+// Replaces usernames, hostnames, absolute paths and email addresses with
+// pseudonyms numbered in order of appearance, so the same value maps to the
+// same pseudonym everywhere in one export. Usernames and hostnames are taken
+// from the environment and learned from every text passed to `observe`, so
+// observe the whole export before anonymizing any of it.
+pub struct Anonymizer {
+    users: BTreeSet<String>,
+    hosts: BTreeSet<String>,
+    names: Option<Regex>,
+    pseudonyms: HashMap<(Kind, String), String>,
+    counts: HashMap<Kind, usize>,
+    email: Regex,
+    prompt: Regex,
+    home: Regex,
+    unix_path: Regex,
+    windows_path: Regex,
+    local_host: Regex,
+}
+
+impl Default for Anonymizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Anonymizer {
+    pub fn new() -> Self {
+        Self {
+            users: BTreeSet::new(),
+            hosts: BTreeSet::new(),
+            names: None,
+            pseudonyms: HashMap::new(),
+            counts: HashMap::new(),
+            email: Regex::new(
+                r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}\b",
+            )
+            .unwrap(),
+            prompt: Regex::new(r"\b([a-z_][a-z0-9_.-]*)@([A-Za-z][A-Za-z0-9-]*):").unwrap(),
+            home: Regex::new(r"(?:/home/|/Users/|[A-Za-z]:\\Users\\)([^/\\\s]+)").unwrap(),
+            unix_path: Regex::new(r#"(^|[\s"'`(=,\[<>])(~?/[\w.@+-]+(?:/[\w.@+-]+)+)"#).unwrap(),
+            windows_path: Regex::new(
+                r#"\b[A-Za-z]:\\(?:[^\\\s"'<>|:*?,;()]+\\)*[^\\\s"'<>|:*?,;()]+"#,
+            )
+            .unwrap(),
+            local_host: Regex::new(
+                r"\b[A-Za-z0-9-]+\.(?:local|lan|internal|localdomain|home\.arpa)\b",
+            )
+            .unwrap(),
+        }
+    }
+
+    // Seeds the current user and machine.
+    pub fn with_environment(mut self) -> Self {
+        for var in ["USER", "USERNAME", "LOGNAME"] {
+            if let Ok(user) = std::env::var(var) {
+                self.add_user(&user);
+            }
+        }
+        if let Some(home) = dirs::home_dir()
+            && let Some(name) = home.file_name()
+        {
+            self.add_user(&name.to_string_lossy());
+        }
+        for var in ["HOSTNAME", "COMPUTERNAME"] {
+            if let Ok(host) = std::env::var(var) {
+                self.add_host(&host);
+            }
+        }
+        if let Ok(host) = std::fs::read_to_string("/etc/hostname") {
+            self.add_host(&host);
+        }
+        self
+    }
+
+    pub fn observe(&mut self, text: &str) {
+        let found: Vec<(String, Option<String>)> = self
+            .home
+            .captures_iter(text)
+            .map(|c| (c[1].to_string(), None))
+            .chain(
+                self.prompt
+                    .captures_iter(text)
+                    .map(|c| (c[1].to_string(), Some(c[2].to_string()))),
+            )
+            .collect();
+        for (user, host) in found {
+            self.add_user(&user);
+            if let Some(host) = host {
+                self.add_host(&host);
+            }
+        }
+    }
+
+    fn add_user(&mut self, user: &str) {
+        let user = user.trim();
+        // Very short names would replace parts of ordinary words.
+        if user.len() >= 3 && !GENERIC_USERS.contains(&user.to_ascii_lowercase().as_str()) {
+            self.names = None;
+            self.users.insert(user.to_string());
+        }
+    }
+
+    fn add_host(&mut self, host: &str) {
+        let host = host.trim();
+        let host = host.split('.').next().unwrap_or(host);
+        if host.len() >= 3 && host != "localhost" {
+            self.names = None;
+            self.hosts.insert(host.to_string());
+        }
+    }
+
+    fn pseudonym(&mut self, kind: Kind, original: &str) -> String {
+        if let Some(existing) = self.pseudonyms.get(&(kind, original.to_string())) {
+            return existing.clone();
+        }
+        let n = self.counts.entry(kind).or_default();
+        *n += 1;
+        let pseudonym = match kind {
+            Kind::User => format!("user{n}"),
+            Kind::Host => format!("host{n}"),
+            Kind::Email => format!("person{n}@example.invalid"),
+            Kind::Dir => format!("/dir{n}"),
+        };
+        self.pseudonyms
+            .insert((kind, original.to_string()), pseudonym.clone());
+        pseudonym
+    }
+
+    // A path keeps its file name; the directory part becomes `/dirN`. Home
+    // relative paths keep their `~` and are numbered apart from absolute ones.
+    fn path(&mut self, path: &str) -> String {
+        let home = if path.starts_with('~') { "~" } else { "" };
+        let (dir, name) = path.split_at(path.rfind(['/', '\\']).unwrap_or(0));
+        if dir == "~" {
+            return path.to_string();
+        }
+        if name.contains('.') && !dir.is_empty() {
+            format!("{home}{}/{}", self.pseudonym(Kind::Dir, dir), &name[1..])
+        } else {
+            format!("{home}{}", self.pseudonym(Kind::Dir, path))
+        }
+    }
+
+    pub fn anonymize(&mut self, text: &str) -> String {
+        let email = self.email.clone();
+        let text = email.replace_all(text, |c: &Captures| self.pseudonym(Kind::Email, &c[0]));
+        let unix_path = self.unix_path.clone();
+        let text = unix_path.replace_all(&text, |c: &Captures| {
+            if SYSTEM_PREFIXES.iter().any(|p| c[2].starts_with(p)) {
+                return c[0].to_string();
+            }
+            format!("{}{}", &c[1], self.path(&c[2]))
+        });
+        let windows_path = self.windows_path.clone();
+        let text = windows_path.replace_all(&text, |c: &Captures| self.path(&c[0]));
+        let local_host = self.local_host.clone();
+        let text = local_host.replace_all(&text, |c: &Captures| self.pseudonym(Kind::Host, &c[0]));
+        let Some(names) = self.names() else {
+            return text.into_owned();
+        };
+        names
+            .replace_all(&text, |c: &Captures| {
+                let name = &c[0];
+                let kind = if self.users.contains(name) {
+                    Kind::User
+                } else {
+                    Kind::Host
+                };
+                self.pseudonym(kind, name)
+            })
+            .into_owned()
+    }
+
+    fn names(&mut self) -> Option<Regex> {
+        if self.names.is_none() && !(self.users.is_empty() && self.hosts.is_empty()) {
+            let mut names: Vec<&String> = self.users.iter().chain(&self.hosts).collect();
+            // Longest first so `alice2` is not matched as `alice`.
+            names.sort_by_key(|name| std::cmp::Reverse(name.len()));
+            let alternation: Vec<String> = names.iter().map(|n| regex::escape(n)).collect();
+            self.names = Regex::new(&format!(r"\b(?:{})\b", alternation.join("|"))).ok();
+        }
+        self.names.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identities_get_stable_pseudonyms() {
+        let mut anon = Anonymizer::new();
+        let first = "alice@laptop:~$ cat /home/alice/acme/src/main.rs\nmail alice.smith@acme.com";
+        let second = "see /home/alice/acme/src/lib.rs on build.local, ping alice";
+        anon.observe(first);
+        anon.observe(second);
+
+        let first = anon.anonymize(first);
+        assert_eq!(
+            first,
+            "user1@host1:~$ cat /dir1/main.rs\nmail person1@example.invalid"
+        );
+        let second = anon.anonymize(second);
+        assert_eq!(second, "see /dir1/lib.rs on host2, ping user1");
+    }
+
+    #[test]
+    fn paths_keep_file_names_and_system_locations() {
+        let mut anon = Anonymizer::new();
+        let text = anon.anonymize(
+            "ran /usr/bin/env in /srv/app and read ~/notes/todo.md, C:\\work\\acme\\app.py, https://github.com/o/r/blob/x.rs",
+        );
+        assert_eq!(
+            text,
+            "ran /usr/bin/env in /dir1 and read ~/dir2/todo.md, /dir3/app.py, https://github.com/o/r/blob/x.rs"
+        );
+    }
+
+    #[test]
+    fn short_and_generic_names_are_left_alone() {
+        let mut anon = Anonymizer::new();
+        anon.observe("/home/root/x /Users/al/y /home/bob/z");
+        assert_eq!(anon.anonymize("root al bob bobby"), "root al user1 bobby");
+    }
+}
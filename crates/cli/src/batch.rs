@@ -0,0 +1,95 @@
+use std::io::BufRead;
+use std::path::Path;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use core_model::JSON_SCHEMA_VERSION;
+use query::{FilterSpec, JsonSearchOutput, ReasoningFilter, SearchRequest, Weights};
+use serde_json::{Map, Value, json};
+
+// Options applied to every query of a batch or evaluation run.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub limit: usize,
+    pub raw_fts: bool,
+    pub reasoning: ReasoningFilter,
+    pub lang: Option<String>,
+    pub filters: FilterSpec,
+    pub as_of: Option<DateTime<Utc>>,
+    pub weights: Weights,
+    pub explain: bool,
+}
+
+impl Settings {
+    pub fn request<'a>(&self, query: &str) -> SearchRequest<'a> {
+        SearchRequest::new(query)
+            .limit(self.limit)
+            .raw_fts(self.raw_fts)
+            .reasoning(self.reasoning)
+            .lang(self.lang.clone())
+            .filters(self.filters.clone())
+            .as_of(self.as_of)
+            .weights(self.weights)
+            .explain(self.explain)
+    }
+}
+
+// One query per line. Blank lines and `#` comments are skipped, and a query
+// repeated later in the file is only run once. `-` reads stdin.
+pub fn read_queries(path: &Path) -> anyhow::Result<Vec<String>> {
+    let reader: Box<dyn BufRead> = if path == Path::new("-") {
+        Box::new(std::io::stdin().lock())
+    } else {
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("opening query file {}", path.display()))?;
+        Box::new(std::io::BufReader::new(file))
+    };
+    parse_queries(reader)
+}
+
+fn parse_queries(reader: impl BufRead) -> anyhow::Result<Vec<String>> {
+    let mut queries: Vec<String> = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let query = line.trim();
+        if query.is_empty() || query.starts_with('#') || queries.iter().any(|q| q == query) {
+            continue;
+        }
+        queries.push(query.to_string());
+    }
+    Ok(queries)
+}
+
+// Results are keyed by query; each value is the `remi search query --format
+// json` document for it.
+pub fn document(results: Vec<JsonSearchOutput>) -> anyhow::Result<Value> {
+    let count = results.len();
+    let mut by_query = Map::new();
+    for output in results {
+        by_query.insert(output.query.clone(), serde_json::to_value(output)?);
+    }
+    Ok(json!({
+        "schema_version": JSON_SCHEMA_VERSION,
+        "queries": count,
+        "results": by_query,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queries_skip_comments_blanks_and_repeats() {
+        let input = "# eval set\nretry loop\n\n  auth token  \nretry loop\n#auth\n";
+        assert_eq!(
+            parse_queries(input.as_bytes()).unwrap(),
+            vec!["retry loop", "auth token"]
+        );
+        assert!(
+            parse_queries("\n# only comments\n".as_bytes())
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,311 @@
+use std::io::{self, IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+use base64::Engine;
+use core_model::{Message, Provenance, Session};
+use owo_colors::OwoColorize;
+
+use crate::{i18n, ui};
+
+// Tried in order; the first one that accepts the text wins.
+const CLIPBOARD_COMMANDS: &[&[&str]] = &[
+    &["pbcopy"],
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+    &["clip.exe"],
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Next,
+    Back,
+    Jump(usize),
+    CopyMessage,
+    CopyCode(usize),
+    CopySource,
+    Help,
+    Quit,
+}
+
+// Keys are typed at a line prompt; numbers are 1-based like the on-screen
+// position.
+pub fn parse_action(input: &str) -> Option<Action> {
+    let input = input.trim();
+    let action = match input {
+        "" | "n" | "j" => Action::Next,
+        "b" | "k" => Action::Back,
+        "y" => Action::CopyMessage,
+        "p" => Action::CopySource,
+        "?" | "h" => Action::Help,
+        "q" => Action::Quit,
+        _ => {
+            if let Some(rest) = input.strip_prefix('Y') {
+                let n = if rest.is_empty() {
+                    1
+                } else {
+                    rest.parse().ok()?
+                };
+                Action::CopyCode(n)
+            } else {
+                Action::Jump(input.parse().ok()?)
+            }
+        }
+    };
+    Some(action)
+}
+
+// Bodies of fenced code blocks, in order. An unterminated fence runs to the
+// end of the message.
+pub fn code_blocks(content: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut open: Option<(char, usize, Vec<&str>)> = None;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if let Some((marker, len, lines)) = &mut open {
+            if trimmed.len() >= *len && trimmed.chars().all(|c| c == *marker) {
+                blocks.push(lines.join("\n"));
+                open = None;
+            } else {
+                lines.push(line);
+            }
+            continue;
+        }
+        for marker in ['`', '~'] {
+            let len = trimmed.chars().take_while(|c| *c == marker).count();
+            if len >= 3 {
+                open = Some((marker, len, Vec::new()));
+                break;
+            }
+        }
+    }
+    if let Some((_, _, lines)) = open {
+        blocks.push(lines.join("\n"));
+    }
+    blocks
+}
+
+pub fn source_path<'a>(message: &Message, provenance: &'a [Provenance]) -> Option<&'a str> {
+    provenance
+        .iter()
+        .find(|p| p.entity_type == "message" && p.entity_id == message.id)
+        .map(|p| p.source_path.as_str())
+}
+
+// Falls back to an OSC 52 escape, which most terminals (and tmux with
+// `set-clipboard on`) turn into a clipboard write, even over SSH.
+pub fn copy(text: &str) -> anyhow::Result<&'static str> {
+    for command in CLIPBOARD_COMMANDS {
+        let (program, args) = command.split_first().expect("clipboard command");
+        let Ok(mut child) = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        if child.wait()?.success() {
+            return Ok(program);
+        }
+    }
+    if !io::stdout().is_terminal() {
+        anyhow::bail!("no clipboard command found (tried pbcopy, wl-copy, xclip, xsel, clip.exe)");
+    }
+    let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+    print!("\x1b]52;c;{encoded}\x07");
+    io::stdout().flush()?;
+    Ok("terminal")
+}
+
+pub fn run(
+    session: &Session,
+    messages: &[Message],
+    provenance: &[Provenance],
+) -> anyhow::Result<()> {
+    if messages.is_empty() {
+        println!(
+            "{}",
+            i18n::text_with("browse-empty", &[("id", session.id.as_str().into())])
+        );
+        return Ok(());
+    }
+    println!(
+        "{}",
+        i18n::text_with(
+            "browse-title",
+            &[
+                ("title", session.title.as_str().into()),
+                ("count", messages.len().into()),
+            ],
+        )
+    );
+    println!("{}", i18n::text("browse-help"));
+    let mut position = 0;
+    let mut shown = None;
+    loop {
+        let m = &messages[position];
+        if shown != Some(position) {
+            print_message(m, position, messages.len());
+            shown = Some(position);
+        }
+        let Some(input) = read_key(position, messages.len())? else {
+            return Ok(());
+        };
+        let Some(action) = parse_action(&input) else {
+            let key = format!("{input:?}");
+            println!(
+                "{}",
+                i18n::text_with("browse-unknown-key", &[("key", key.into())])
+            );
+            continue;
+        };
+        let copied = match action {
+            Action::Next if position + 1 < messages.len() => {
+                position += 1;
+                continue;
+            }
+            Action::Next => {
+                println!("{}", i18n::text("browse-last"));
+                continue;
+            }
+            Action::Back => {
+                position = position.saturating_sub(1);
+                continue;
+            }
+            Action::Jump(n) if (1..=messages.len()).contains(&n) => {
+                position = n - 1;
+                continue;
+            }
+            Action::Jump(n) => {
+                println!(
+                    "{}",
+                    i18n::text_with(
+                        "browse-no-message",
+                        &[("n", n.into()), ("total", messages.len().into())]
+                    )
+                );
+                continue;
+            }
+            Action::Help => {
+                println!("{}", i18n::text("browse-help"));
+                continue;
+            }
+            Action::Quit => return Ok(()),
+            Action::CopyMessage => Some(("message", m.content.clone())),
+            Action::CopyCode(n) => {
+                let blocks = code_blocks(&m.content);
+                match blocks.into_iter().nth(n.saturating_sub(1)) {
+                    Some(block) => Some(("code", block)),
+                    None => {
+                        println!(
+                            "{}",
+                            i18n::text_with("browse-no-code-block", &[("n", n.into())])
+                        );
+                        None
+                    }
+                }
+            }
+            Action::CopySource => match source_path(m, provenance) {
+                Some(path) => Some(("source", path.to_string())),
+                None => {
+                    println!("{}", i18n::text("browse-no-source"));
+                    None
+                }
+            },
+        };
+        if let Some((what, text)) = copied {
+            match copy(&text) {
+                Ok(via) => println!(
+                    "{}",
+                    i18n::text_with(
+                        "browse-copied",
+                        &[
+                            ("what", what.into()),
+                            ("chars", text.chars().count().into()),
+                            ("via", via.into()),
+                        ],
+                    )
+                ),
+                Err(err) => println!(
+                    "{}",
+                    i18n::text_with(
+                        "browse-copy-failed",
+                        &[("error", format!("{err:#}").into())]
+                    )
+                ),
+            }
+        }
+    }
+}
+
+// `None` once stdin is closed, so piped input cannot spin on "next".
+fn read_key(position: usize, total: usize) -> anyhow::Result<Option<String>> {
+    let prompt = format!("[{}/{total}] > ", position + 1);
+    if ui::color_enabled() {
+        print!("{}", prompt.cyan());
+    } else {
+        print!("{prompt}");
+    }
+    io::stdout().flush()?;
+    let mut buf = String::new();
+    if io::stdin().read_line(&mut buf)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(buf.trim_end().to_string()))
+}
+
+fn print_message(m: &Message, position: usize, total: usize) {
+    let header = format!(
+        "[{}/{}] {} [{}] {}",
+        position + 1,
+        total,
+        m.ts.to_rfc3339(),
+        m.role,
+        m.id
+    );
+    println!();
+    if ui::color_enabled() {
+        println!("{}", header.bold());
+    } else {
+        println!("{header}");
+    }
+    println!("{}", m.content);
+    let blocks = code_blocks(&m.content).len();
+    if blocks > 0 {
+        println!("{}", i18n::count("browse-code-count", blocks));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_map_to_actions() {
+        assert_eq!(parse_action(""), Some(Action::Next));
+        assert_eq!(parse_action(" b "), Some(Action::Back));
+        assert_eq!(parse_action("12"), Some(Action::Jump(12)));
+        assert_eq!(parse_action("y"), Some(Action::CopyMessage));
+        assert_eq!(parse_action("Y"), Some(Action::CopyCode(1)));
+        assert_eq!(parse_action("Y3"), Some(Action::CopyCode(3)));
+        assert_eq!(parse_action("p"), Some(Action::CopySource));
+        assert_eq!(parse_action("Yx"), None);
+        assert_eq!(parse_action("zz"), None);
+    }
+
+    #[test]
+    fn code_blocks_follow_fences() {
+        let content =
+            "intro\n```rust\nfn main() {}\n```\ntext\n~~~\nls\n````\nstill ls\n~~~\n````sh\ntail";
+        assert_eq!(
+            code_blocks(content),
+            vec!["fn main() {}", "ls\n````\nstill ls", "tail"]
+        );
+        assert!(code_blocks("no fences here").is_empty());
+    }
+}
@@ -0,0 +1,477 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+
+use core_model::{AgentKind, JSON_SCHEMA_VERSION};
+use serde::Serialize;
+use serde_json::{Value, json};
+use store_sqlite::SqliteStore;
+
+use crate::digest::count_words;
+use crate::render::escape_html;
+
+const MAX_ITERATIONS: usize = 50;
+const POWER_ITERATIONS: usize = 100;
+const LABEL_TERMS: usize = 5;
+const TEXT_MEMBERS: usize = 5;
+const MAP_WIDTH: f32 = 900.0;
+const MAP_HEIGHT: f32 = 600.0;
+const MAP_PADDING: f32 = 30.0;
+const PALETTE: &[&str] = &[
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#7f7f7f",
+    "#bcbd22", "#17becf", "#393b79", "#ad494a",
+];
+
+pub struct Item {
+    pub session_id: String,
+    pub title: String,
+    pub agent: String,
+    pub vec: Vec<f32>,
+    pub words: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Member {
+    pub session_id: String,
+    pub title: String,
+    pub agent: String,
+    // Cosine similarity to the cluster centroid.
+    pub similarity: f32,
+    // Position on the first two principal components, as drawn on the map.
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Cluster {
+    pub id: usize,
+    pub terms: Vec<String>,
+    pub sessions: Vec<Member>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub sessions: usize,
+    pub clusters: Vec<Cluster>,
+}
+
+// Session vectors are the mean of their message embeddings; words come from
+// the title and message text, with reasoning left out as in the digest.
+// Only vectors from `model` are used, so sessions embedded by different
+// backends are never placed in the same space.
+pub fn load(
+    store: &SqliteStore,
+    model: &str,
+    agent: Option<AgentKind>,
+) -> anyhow::Result<Vec<Item>> {
+    let mut items = Vec::new();
+    for (session_id, vec) in store.load_session_embeddings(model)? {
+        let Some(session) = store.get_session(&session_id)? else {
+            continue;
+        };
+        if session.agent == AgentKind::Meta || agent.is_some_and(|a| a != session.agent) {
+            continue;
+        }
+        let mut words = BTreeMap::new();
+        count_words(&session.title, &mut words);
+        for m in store.get_session_messages(&session.id)? {
+            count_words(&m.content, &mut words);
+        }
+        items.push(Item {
+            session_id,
+            title: session.title,
+            agent: session.agent.as_str().to_string(),
+            vec,
+            words,
+        });
+    }
+    Ok(items)
+}
+
+// Roughly sqrt(n/2), the usual rule of thumb, kept to a readable range.
+pub fn default_k(n: usize) -> usize {
+    (((n as f64) / 2.0).sqrt().round() as usize)
+        .clamp(2, PALETTE.len())
+        .min(n)
+}
+
+pub fn build(items: Vec<Item>, k: usize) -> anyhow::Result<Report> {
+    if items.len() < 2 {
+        anyhow::bail!(
+            "clustering needs at least 2 sessions with embeddings, found {}; run `remi embed --rebuild` first",
+            items.len()
+        );
+    }
+    if k == 0 || k > items.len() {
+        anyhow::bail!(
+            "--k must be between 1 and the number of sessions ({})",
+            items.len()
+        );
+    }
+    let dim = items[0].vec.len();
+    if items.iter().any(|item| item.vec.len() != dim) {
+        anyhow::bail!("session embeddings have mixed dimensions; run `remi embed --rebuild`");
+    }
+    let points: Vec<Vec<f32>> = items.iter().map(|item| normalized(&item.vec)).collect();
+    let (assignments, centroids) = kmeans(&points, k);
+    let coords = project(&points);
+    let terms = label_terms(&items, &assignments, k);
+
+    let mut clusters: Vec<Cluster> = terms
+        .into_iter()
+        .map(|terms| Cluster {
+            id: 0,
+            terms,
+            sessions: Vec::new(),
+        })
+        .collect();
+    for (i, item) in items.into_iter().enumerate() {
+        let c = assignments[i];
+        clusters[c].sessions.push(Member {
+            session_id: item.session_id,
+            title: item.title,
+            agent: item.agent,
+            similarity: dot(&points[i], &centroids[c]),
+            x: coords[i].0,
+            y: coords[i].1,
+        });
+    }
+    clusters.retain(|c| !c.sessions.is_empty());
+    clusters.sort_by_key(|c| std::cmp::Reverse(c.sessions.len()));
+    for (i, cluster) in clusters.iter_mut().enumerate() {
+        cluster.id = i + 1;
+        cluster
+            .sessions
+            .sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    }
+    Ok(Report {
+        sessions: points.len(),
+        clusters,
+    })
+}
+
+// Spherical k-means: points and centroids are unit vectors and closeness is
+// the dot product. Seeding is deterministic, starting from the point nearest
+// the overall mean and then taking the point farthest from every seed, so
+// reruns over the same store give the same clusters.
+pub fn kmeans(points: &[Vec<f32>], k: usize) -> (Vec<usize>, Vec<Vec<f32>>) {
+    let center = normalized(&mean(points.iter()));
+    let first = argmax(points.iter().map(|p| dot(p, &center)));
+    let mut centroids = vec![points[first].clone()];
+    while centroids.len() < k {
+        let next = argmax(
+            points
+                .iter()
+                .map(|p| -centroids.iter().map(|c| dot(p, c)).fold(f32::MIN, f32::max)),
+        );
+        centroids.push(points[next].clone());
+    }
+
+    let mut assignments = vec![usize::MAX; points.len()];
+    for _ in 0..MAX_ITERATIONS {
+        let mut changed = false;
+        for (i, p) in points.iter().enumerate() {
+            let best = argmax(centroids.iter().map(|c| dot(p, c)));
+            if assignments[i] != best {
+                assignments[i] = best;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+        for (c, centroid) in centroids.iter_mut().enumerate() {
+            let members: Vec<&Vec<f32>> = points
+                .iter()
+                .zip(&assignments)
+                .filter(|(_, a)| **a == c)
+                .map(|(p, _)| p)
+                .collect();
+            // An emptied cluster keeps its old centroid.
+            if !members.is_empty() {
+                *centroid = normalized(&mean(members.into_iter()));
+            }
+        }
+    }
+    (assignments, centroids)
+}
+
+// Terms that many sessions of a cluster share and few sessions elsewhere
+// use: the number of member sessions containing the term, weighted by a
+// smoothed inverse document frequency so a word from a single session does
+// not outrank one the whole cluster uses. Terms in every session say
+// nothing about any cluster and are left out.
+fn label_terms(items: &[Item], assignments: &[usize], k: usize) -> Vec<Vec<String>> {
+    let mut df: HashMap<&str, usize> = HashMap::new();
+    let mut per_cluster: Vec<HashMap<&str, usize>> = vec![HashMap::new(); k];
+    for (item, &c) in items.iter().zip(assignments) {
+        for word in item.words.keys() {
+            *df.entry(word).or_default() += 1;
+            *per_cluster[c].entry(word).or_default() += 1;
+        }
+    }
+    let n = items.len() as f64;
+    per_cluster
+        .into_iter()
+        .map(|counts| {
+            let mut scored: Vec<(f64, &str)> = counts
+                .into_iter()
+                .filter(|(word, _)| (df[word] as f64) < n)
+                .map(|(word, count)| (count as f64 * (1.0 + n / df[word] as f64).ln(), word))
+                .collect();
+            scored.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+            scored
+                .into_iter()
+                .take(LABEL_TERMS)
+                .map(|(_, word)| word.to_string())
+                .collect()
+        })
+        .collect()
+}
+
+// The first two principal components by power iteration, with the second
+// kept orthogonal to the first.
+fn project(points: &[Vec<f32>]) -> Vec<(f32, f32)> {
+    let center = mean(points.iter());
+    let centered: Vec<Vec<f32>> = points
+        .iter()
+        .map(|p| p.iter().zip(&center).map(|(a, b)| a - b).collect())
+        .collect();
+    let first = component(&centered, None);
+    let second = component(&centered, Some(&first));
+    centered
+        .iter()
+        .map(|p| (dot(p, &first), dot(p, &second)))
+        .collect()
+}
+
+fn component(points: &[Vec<f32>], orthogonal_to: Option<&[f32]>) -> Vec<f32> {
+    let dim = points.first().map_or(0, Vec::len);
+    let mut v: Vec<f32> = (0..dim).map(|i| 1.0 + (i % 7) as f32).collect();
+    for _ in 0..POWER_ITERATIONS {
+        let mut next = vec![0.0; dim];
+        for p in points {
+            let weight = dot(p, &v);
+            next.iter_mut().zip(p).for_each(|(n, x)| *n += weight * x);
+        }
+        if let Some(other) = orthogonal_to {
+            let overlap = dot(&next, other);
+            next.iter_mut()
+                .zip(other)
+                .for_each(|(n, o)| *n -= overlap * o);
+        }
+        if next.iter().all(|x| *x == 0.0) {
+            break;
+        }
+        v = normalized(&next);
+    }
+    v
+}
+
+impl Report {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "schema_version": JSON_SCHEMA_VERSION,
+            "sessions": self.sessions,
+            "clusters": self.clusters,
+        })
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = format!(
+            "sessions={} clusters={}\n",
+            self.sessions,
+            self.clusters.len()
+        );
+        for cluster in &self.clusters {
+            let _ = writeln!(
+                out,
+                "\ncluster {} ({} sessions): {}",
+                cluster.id,
+                cluster.sessions.len(),
+                terms_label(cluster)
+            );
+            for m in cluster.sessions.iter().take(TEXT_MEMBERS) {
+                let _ = writeln!(
+                    out,
+                    "  {:.2} {} {} ({})",
+                    m.similarity, m.agent, m.title, m.session_id
+                );
+            }
+            if cluster.sessions.len() > TEXT_MEMBERS {
+                let _ = writeln!(
+                    out,
+                    "  ... and {} more",
+                    cluster.sessions.len() - TEXT_MEMBERS
+                );
+            }
+        }
+        out
+    }
+
+    pub fn render_html(&self) -> String {
+        let members = || self.clusters.iter().flat_map(|c| &c.sessions);
+        let (mut min_x, mut max_x, mut min_y, mut max_y) = (f32::MAX, f32::MIN, f32::MAX, f32::MIN);
+        for m in members() {
+            (min_x, max_x) = (min_x.min(m.x), max_x.max(m.x));
+            (min_y, max_y) = (min_y.min(m.y), max_y.max(m.y));
+        }
+        let scale = |v: f32, min: f32, max: f32, size: f32| {
+            let span = (max - min).max(f32::EPSILON);
+            MAP_PADDING + (v - min) / span * (size - 2.0 * MAP_PADDING)
+        };
+        let mut svg = format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{MAP_WIDTH}" height="{MAP_HEIGHT}" viewBox="0 0 {MAP_WIDTH} {MAP_HEIGHT}" style="border: 1px solid #d0d7de">"##
+        );
+        let mut legend = String::from("<ul style=\"list-style: none; padding: 0\">\n");
+        for cluster in &self.clusters {
+            let color = PALETTE[(cluster.id - 1) % PALETTE.len()];
+            let label = escape_html(&terms_label(cluster));
+            for m in &cluster.sessions {
+                let _ = write!(
+                    svg,
+                    "\n<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"5\" fill=\"{color}\" fill-opacity=\"0.75\"><title>{} ({})&#10;cluster {}: {label}</title></circle>",
+                    scale(m.x, min_x, max_x, MAP_WIDTH),
+                    MAP_HEIGHT - scale(m.y, min_y, max_y, MAP_HEIGHT),
+                    escape_html(&m.title),
+                    escape_html(&m.session_id),
+                    cluster.id
+                );
+            }
+            let _ = writeln!(
+                legend,
+                "<li><span style=\"color: {color}\">&#9679;</span> cluster {} ({} sessions): {label}</li>",
+                cluster.id,
+                cluster.sessions.len()
+            );
+        }
+        svg.push_str("\n</svg>\n");
+        legend.push_str("</ul>\n");
+        let title = format!(
+            "{} sessions in {} clusters",
+            self.sessions,
+            self.clusters.len()
+        );
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n</head>\n<body style=\"font-family: sans-serif\">\n<h1>{title}</h1>\n{svg}{legend}</body>\n</html>\n"
+        )
+    }
+}
+
+fn terms_label(cluster: &Cluster) -> String {
+    if cluster.terms.is_empty() {
+        "(no distinctive terms)".to_string()
+    } else {
+        cluster.terms.join(", ")
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn normalized(v: &[f32]) -> Vec<f32> {
+    let norm = dot(v, v).sqrt();
+    if norm == 0.0 {
+        return v.to_vec();
+    }
+    v.iter().map(|x| x / norm).collect()
+}
+
+fn mean<'a>(points: impl Iterator<Item = &'a Vec<f32>>) -> Vec<f32> {
+    let mut sum: Vec<f32> = Vec::new();
+    let mut n = 0;
+    for p in points {
+        if sum.is_empty() {
+            sum = vec![0.0; p.len()];
+        }
+        sum.iter_mut().zip(p).for_each(|(s, x)| *s += x);
+        n += 1;
+    }
+    sum.iter().map(|s| s / n.max(1) as f32).collect()
+}
+
+// The first index wins ties.
+fn argmax(values: impl Iterator<Item = f32>) -> usize {
+    values
+        .enumerate()
+        .fold(
+            (0, f32::MIN),
+            |best, (i, v)| {
+                if v > best.1 { (i, v) } else { best }
+            },
+        )
+        .0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: &str, vec: &[f32], text: &str) -> Item {
+        let mut words = BTreeMap::new();
+        count_words(text, &mut words);
+        Item {
+            session_id: id.to_string(),
+            title: format!("{id} title"),
+            agent: "codex".to_string(),
+            vec: vec.to_vec(),
+            words,
+        }
+    }
+
+    #[test]
+    fn separates_groups_and_labels_them_by_shared_terms() {
+        let items = vec![
+            item("a1", &[1.0, 0.1, 0.0], "login token refresh expired"),
+            item("a2", &[0.9, 0.0, 0.1], "oauth token refresh flow"),
+            item("a3", &[1.0, 0.2, 0.1], "token refresh retries"),
+            item("b1", &[0.0, 1.0, 0.1], "flaky tests in the parser"),
+            item("b2", &[0.1, 0.9, 0.0], "parser tests fixtures"),
+        ];
+        let report = build(items, 2).unwrap();
+        assert_eq!(report.sessions, 5);
+        let ids = |c: &Cluster| {
+            c.sessions
+                .iter()
+                .map(|m| m.session_id.clone())
+                .collect::<Vec<_>>()
+        };
+        let mut first = ids(&report.clusters[0]);
+        first.sort();
+        assert_eq!(first, ["a1", "a2", "a3"]);
+        assert_eq!(report.clusters[0].id, 1);
+        assert_eq!(report.clusters[0].terms[..2], ["refresh", "token"]);
+        assert_eq!(report.clusters[1].terms[..2], ["parser", "tests"]);
+        assert!(
+            report.clusters[0].sessions[0].similarity >= report.clusters[0].sessions[2].similarity
+        );
+
+        let text = report.to_text();
+        assert!(text.starts_with("sessions=5 clusters=2\n"), "{text}");
+        assert!(
+            text.contains("cluster 2 (2 sessions): parser, tests"),
+            "{text}"
+        );
+        let html = report.render_html();
+        assert_eq!(html.matches("<circle").count(), 5);
+        assert!(html.contains("b2 title (b2)"));
+    }
+
+    #[test]
+    fn rejects_unusable_input() {
+        assert!(build(vec![item("a", &[1.0], "x")], 1).is_err());
+        let two = || vec![item("a", &[1.0, 0.0], "x"), item("b", &[0.0, 1.0], "y")];
+        assert!(build(two(), 3).is_err());
+        assert!(build(two(), 0).is_err());
+        let mut mixed = two();
+        mixed[1].vec.push(1.0);
+        assert!(build(mixed, 2).is_err());
+    }
+
+    #[test]
+    fn default_k_grows_slowly() {
+        assert_eq!(default_k(2), 2);
+        assert_eq!(default_k(50), 5);
+        assert_eq!(default_k(10_000), PALETTE.len());
+    }
+}
@@ -0,0 +1,304 @@
+use std::fmt::Write as _;
+
+use chrono::{DateTime, Duration, Utc};
+use serde_json::{Value, json};
+use store_sqlite::SqliteStore;
+
+use crate::stats::SessionStats;
+
+const CANDIDATES: usize = 100;
+const TOP_TOOLS: usize = 3;
+
+#[derive(Debug, Clone)]
+pub struct AgentRun {
+    pub agent: String,
+    pub session_id: String,
+    pub title: String,
+    pub project: Option<String>,
+    pub stats: SessionStats,
+    pub files: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub query: String,
+    pub runs: Vec<AgentRun>,
+}
+
+impl Comparison {
+    // The best hit anchors the comparison; every other agent contributes its
+    // best hit updated within `window` of it and, when both sessions map to
+    // a repository, from the same repository.
+    pub fn build(store: &SqliteStore, query: &str, window: Duration) -> anyhow::Result<Self> {
+        let hits = query::SearchRequest::new(query)
+            .limit(CANDIDATES)
+            .execute(store)?
+            .sessions;
+        let mut runs: Vec<AgentRun> = Vec::new();
+        let mut anchor: Option<(DateTime<Utc>, Option<String>)> = None;
+        for hit in hits {
+            if runs.iter().any(|r| r.agent == hit.agent) {
+                continue;
+            }
+            let project = project_of(store, &hit.session_id)?;
+            if let Some((updated_at, anchor_project)) = &anchor {
+                if (hit.updated_at - *updated_at).abs() > window {
+                    continue;
+                }
+                if let (Some(a), Some(b)) = (anchor_project, &project)
+                    && a != b
+                {
+                    continue;
+                }
+            }
+            let Some(run) = agent_run(store, &hit.session_id, project.clone())? else {
+                continue;
+            };
+            if anchor.is_none() {
+                anchor = Some((hit.updated_at, project));
+            }
+            runs.push(run);
+        }
+        Ok(Self {
+            query: query.to_string(),
+            runs,
+        })
+    }
+
+    pub fn render_terminal(&self) -> String {
+        let mut out = format!("Compare: {}\n\n", self.query);
+        if self.runs.is_empty() {
+            out.push_str("No sessions matched.\n");
+            return out;
+        }
+        let rows: Vec<(&str, Vec<String>)> = vec![
+            ("session", self.column(|r| r.session_id.clone())),
+            ("title", self.column(|r| query::truncate_text(&r.title, 40))),
+            (
+                "project",
+                self.column(|r| r.project.clone().unwrap_or_else(|| "-".to_string())),
+            ),
+            (
+                "messages",
+                self.column(|r| r.stats.message_count.to_string()),
+            ),
+            ("duration", self.column(|r| r.stats.duration_label())),
+            (
+                "tool calls",
+                self.column(|r| {
+                    r.stats
+                        .tools
+                        .iter()
+                        .map(|(_, n)| n)
+                        .sum::<usize>()
+                        .to_string()
+                }),
+            ),
+            ("top tools", self.column(|r| top_tools(&r.stats))),
+            ("files changed", self.column(|r| r.files.len().to_string())),
+        ];
+        let label_width = rows.iter().map(|(l, _)| l.len()).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..self.runs.len())
+            .map(|i| {
+                rows.iter()
+                    .map(|(_, cells)| cells[i].chars().count())
+                    .chain(std::iter::once(self.runs[i].agent.len()))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let line = |label: &str, cells: Vec<&str>| {
+            let mut row = format!("{label:<label_width$}");
+            for (cell, width) in cells.iter().zip(&widths) {
+                let pad = width - cell.chars().count();
+                let _ = write!(row, "  {cell}{}", " ".repeat(pad));
+            }
+            row.trim_end().to_string()
+        };
+        let _ = writeln!(
+            out,
+            "{}",
+            line("", self.runs.iter().map(|r| r.agent.as_str()).collect())
+        );
+        for (label, cells) in &rows {
+            let _ = writeln!(
+                out,
+                "{}",
+                line(label, cells.iter().map(String::as_str).collect())
+            );
+        }
+        if self.runs.len() == 1 {
+            out.push_str("\nOnly one agent has a matching session in this window.\n");
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "schema_version": core_model::JSON_SCHEMA_VERSION,
+            "query": self.query,
+            "runs": self.runs.iter().map(|r| json!({
+                "agent": r.agent,
+                "session_id": r.session_id,
+                "title": r.title,
+                "project": r.project,
+                "messages": r.stats.message_count,
+                "duration_secs": r.stats.duration_secs,
+                "tools": r.stats.tools.iter().map(|(name, n)| json!({ "tool": name, "calls": n })).collect::<Vec<_>>(),
+                "files": r.files,
+            })).collect::<Vec<_>>(),
+        })
+    }
+
+    fn column(&self, cell: impl Fn(&AgentRun) -> String) -> Vec<String> {
+        self.runs.iter().map(cell).collect()
+    }
+}
+
+fn agent_run(
+    store: &SqliteStore,
+    session_id: &str,
+    project: Option<String>,
+) -> anyhow::Result<Option<AgentRun>> {
+    let (Some(session), Some(aggregate)) = (
+        store.get_session(session_id)?,
+        store.session_aggregate(session_id)?,
+    ) else {
+        return Ok(None);
+    };
+    let messages = store.get_session_messages(session_id)?;
+    let mut files: Vec<String> = Vec::new();
+    for m in &messages {
+        for path in core_model::tool_file_paths(&m.content) {
+            if !files.contains(&path) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(Some(AgentRun {
+        agent: session.agent.as_str().to_string(),
+        session_id: session.id.clone(),
+        title: query::session_title(&session, &messages),
+        project,
+        stats: SessionStats::new(aggregate, &messages),
+        files,
+    }))
+}
+
+fn project_of(store: &SqliteStore, session_id: &str) -> anyhow::Result<Option<String>> {
+    Ok(store
+        .get_provenance_for_session(session_id)?
+        .into_iter()
+        .find_map(|p| p.repo.map(|repo| repo.remote)))
+}
+
+fn top_tools(stats: &SessionStats) -> String {
+    if stats.tools.is_empty() {
+        return "-".to_string();
+    }
+    stats
+        .tools
+        .iter()
+        .take(TOP_TOOLS)
+        .map(|(name, n)| format!("{name}={n}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use core_model::{AgentKind, Message, NormalizedBatch, Session};
+
+    fn save(store: &mut SqliteStore, agent: AgentKind, id: &str, day: u32, replies: &[&str]) {
+        let at = Utc.with_ymd_and_hms(2025, 5, day, 9, 0, 0).unwrap();
+        let mut messages = vec![Message {
+            id: format!("{id}-0"),
+            session_id: id.to_string(),
+            role: "user".to_string(),
+            content: "migrate the config loader to toml".to_string(),
+            ts: at,
+        }];
+        messages.extend(replies.iter().enumerate().map(|(i, content)| Message {
+            id: format!("{id}-{}", i + 1),
+            session_id: id.to_string(),
+            role: "assistant".to_string(),
+            content: content.to_string(),
+            ts: at + Duration::minutes(i as i64 + 1),
+        }));
+        store
+            .save_batch(&NormalizedBatch {
+                sessions: vec![Session {
+                    id: id.to_string(),
+                    agent,
+                    source_ref: format!("/tmp/{id}.jsonl"),
+                    title: format!("{id} toml"),
+                    created_at: at,
+                    updated_at: at,
+                }],
+                messages,
+                ..Default::default()
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn picks_one_session_per_agent_within_the_window() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        save(
+            &mut store,
+            AgentKind::Claude,
+            "c1",
+            10,
+            &[
+                r#"tool_use: Edit {"file_path":"src/config.rs"}"#,
+                r#"tool_use: Edit {"file_path":"src/main.rs"}"#,
+                "Done.",
+            ],
+        );
+        save(
+            &mut store,
+            AgentKind::Codex,
+            "x1",
+            11,
+            &[r#"tool_use: shell {"command":"cargo test"}"#],
+        );
+        save(
+            &mut store,
+            AgentKind::Codex,
+            "x2",
+            11,
+            &["second codex try"],
+        );
+        save(&mut store, AgentKind::Pi, "p1", 28, &["too late"]);
+
+        let comparison =
+            Comparison::build(&store, "config loader toml", Duration::days(7)).unwrap();
+        let agents: Vec<&str> = comparison.runs.iter().map(|r| r.agent.as_str()).collect();
+        assert_eq!(comparison.runs.len(), 2);
+        assert!(agents.contains(&"claude") && agents.contains(&"codex"));
+        let claude = comparison
+            .runs
+            .iter()
+            .find(|r| r.agent == "claude")
+            .unwrap();
+        assert_eq!(claude.files, vec!["src/config.rs", "src/main.rs"]);
+        assert_eq!(claude.stats.message_count, 4);
+        assert_eq!(claude.stats.duration_label(), "3m");
+
+        let text = comparison.render_terminal();
+        assert!(text.starts_with("Compare: config loader toml\n\n"));
+        assert!(
+            text.lines()
+                .any(|l| l.starts_with("top tools") && l.contains("Edit=2"))
+        );
+        assert!(text.contains("files changed  2"));
+        assert_eq!(comparison.to_json()["runs"].as_array().unwrap().len(), 2);
+
+        let none = Comparison::build(&store, "kubernetes", Duration::days(7)).unwrap();
+        assert!(none.render_terminal().contains("No sessions matched."));
+    }
+}
@@ -1,30 +1,286 @@
-use std::path::PathBuf;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
+use adapter_common::{CaptureOptions, DiscoveryOptions, SourceTimezone};
+use anyhow::Context;
+use core_model::{AgentKind, SourceContext};
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize, Default)]
 pub struct Config {
+    pub locale: Option<String>,
+    #[cfg(feature = "semantic")]
     pub semantic: Option<SemanticConfig>,
+    pub redact: Option<RedactConfig>,
+    #[serde(default)]
+    pub database: DatabaseConfig,
+    #[serde(default)]
+    pub digest: DigestConfig,
+    #[serde(default)]
+    pub publish: PublishConfig,
+    #[serde(default)]
+    pub export: ExportConfig,
+    #[serde(default)]
+    pub discovery: DiscoveryConfig,
+    #[serde(default)]
+    pub capture: CaptureConfig,
+    #[serde(default)]
+    pub search: SearchConfig,
+    #[serde(default)]
+    pub performance: PerformanceConfig,
+    #[serde(default)]
+    pub agents: HashMap<String, AgentConfig>,
 }
 
+#[derive(Debug, Deserialize, Default)]
+pub struct SearchConfig {
+    #[serde(default)]
+    pub profiles: HashMap<String, SearchProfile>,
+}
+
+// Defaults for `remi search query --preset <name>`; flags given on the command
+// line win. `format` and `reasoning` take the same values as the flags.
+#[derive(Debug, Deserialize, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SearchProfile {
+    pub agent: Option<String>,
+    pub title: Option<String>,
+    pub id: Option<String>,
+    pub contains: Option<String>,
+    pub lang: Option<String>,
+    pub reasoning: Option<String>,
+    pub raw_fts: Option<bool>,
+    pub limit: Option<usize>,
+    pub format: Option<String>,
+    pub output_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub weights: WeightsConfig,
+}
+
+#[derive(Debug, Deserialize, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct WeightsConfig {
+    pub bm25: Option<f32>,
+    pub recency: Option<f32>,
+    pub semantic: Option<f32>,
+}
+
+impl WeightsConfig {
+    pub fn weights(&self) -> anyhow::Result<search::Weights> {
+        let base = search::Weights::default();
+        let weights = search::Weights {
+            bm25: self.bm25.unwrap_or(base.bm25),
+            recency: self.recency.unwrap_or(base.recency),
+            semantic: self.semantic.unwrap_or(base.semantic),
+        };
+        for (name, value) in [
+            ("bm25", weights.bm25),
+            ("recency", weights.recency),
+            ("semantic", weights.semantic),
+        ] {
+            if !value.is_finite() || value < 0.0 {
+                anyhow::bail!("weights.{name} must be a non-negative number, got {value}");
+            }
+        }
+        Ok(weights)
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct AgentConfig {
+    pub enabled: Option<bool>,
+    pub home: Option<PathBuf>,
+    pub hook: Option<PathBuf>,
+    pub timezone: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct CaptureConfig {
+    #[serde(default)]
+    pub system_prompts: bool,
+    pub reasoning: Option<bool>,
+    #[serde(default)]
+    pub raw_records: bool,
+}
+
+impl CaptureConfig {
+    pub fn options(&self) -> CaptureOptions {
+        let base = CaptureOptions::default();
+        CaptureOptions {
+            system_prompts: self.system_prompts,
+            reasoning: self.reasoning.unwrap_or(base.reasoning),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct DiscoveryConfig {
+    #[serde(flatten)]
+    pub defaults: DiscoveryRules,
+    #[serde(default)]
+    pub agents: HashMap<String, DiscoveryRules>,
+}
+
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct DiscoveryRules {
+    pub max_depth: Option<usize>,
+    pub same_filesystem: Option<bool>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub roots: Vec<PathBuf>,
+}
+
+impl DiscoveryConfig {
+    // Per-agent values override the defaults; exclude lists are combined.
+    pub fn options_for(&self, kind: AgentKind) -> DiscoveryOptions {
+        let agent = self.agents.get(kind.as_str());
+        let base = DiscoveryOptions::default();
+        DiscoveryOptions {
+            max_depth: agent
+                .and_then(|a| a.max_depth)
+                .or(self.defaults.max_depth)
+                .unwrap_or(base.max_depth),
+            same_filesystem: agent
+                .and_then(|a| a.same_filesystem)
+                .or(self.defaults.same_filesystem)
+                .unwrap_or(base.same_filesystem),
+            exclude: self
+                .defaults
+                .exclude
+                .iter()
+                .chain(agent.into_iter().flat_map(|a| a.exclude.iter()))
+                .cloned()
+                .collect(),
+            // Roots only mean something to the agent they are set for.
+            roots: agent.map(|a| a.roots.clone()).unwrap_or_default(),
+            ..base
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct RedactConfig {
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    pub replacement: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct DatabaseConfig {
+    #[serde(default)]
+    pub encrypted: bool,
+}
+
+// Unset means all cores for scanning and the embeddings default for
+// onnxruntime sessions.
+#[derive(Debug, Deserialize, Default)]
+pub struct PerformanceConfig {
+    pub scan_threads: Option<usize>,
+    pub embed_threads: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct DigestConfig {
+    pub notes_dir: Option<PathBuf>,
+    // Local time of day, `HH:MM`, at which `remi digest --watch` runs.
+    pub at: Option<String>,
+    pub slack_webhook: Option<String>,
+    pub discord_webhook: Option<String>,
+}
+
+impl DigestConfig {
+    pub fn at(&self) -> anyhow::Result<chrono::NaiveTime> {
+        let raw = self.at.as_deref().unwrap_or("00:15");
+        chrono::NaiveTime::parse_from_str(raw, "%H:%M")
+            .with_context(|| format!("invalid [digest] at {raw:?}; expected HH:MM"))
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct ExportConfig {
+    pub css: Option<PathBuf>,
+    pub burst_gap_minutes: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct PublishConfig {
+    pub github_token_env: Option<String>,
+    pub api_url: Option<String>,
+}
+
+#[cfg(feature = "semantic")]
 #[derive(Debug, Deserialize, Default)]
 pub struct SemanticConfig {
+    #[serde(default)]
     pub enabled: bool,
     pub model_path: Option<String>,
+    pub backend: Option<String>,
+    pub ort_dylib_path: Option<String>,
     pub pooling: Option<String>,
     pub query_prefix: Option<String>,
+    pub rerank_model_path: Option<String>,
+    pub rerank_url: Option<String>,
+    pub rerank_model: Option<String>,
+    pub rerank_api_key_env: Option<String>,
 }
 
 impl Config {
-    pub fn load() -> anyhow::Result<Self> {
-        let config_dir = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
-        let config_path = config_dir.join("remi").join("config.toml");
+    pub fn agent_enabled(&self, kind: AgentKind) -> bool {
+        self.agents
+            .get(kind.as_str())
+            .and_then(|a| a.enabled)
+            .unwrap_or(true)
+    }
+
+    pub fn hook_for(&self, kind: AgentKind) -> Option<&Path> {
+        self.agents
+            .get(kind.as_str())
+            .and_then(|a| a.hook.as_deref())
+            .filter(|script| !script.as_os_str().is_empty())
+    }
+
+    // Checked when the config is loaded; `REMI_<AGENT>_TZ` still wins over it
+    // in the adapter.
+    pub fn timezone_for(&self, kind: AgentKind) -> Option<SourceTimezone> {
+        self.agents
+            .get(kind.as_str())
+            .and_then(|a| a.timezone.as_deref())
+            .and_then(|tz| tz.parse().ok())
+    }
+
+    pub fn search_profile(&self, name: &str) -> anyhow::Result<&SearchProfile> {
+        self.search.profiles.get(name).ok_or_else(|| {
+            let mut known: Vec<&str> = self.search.profiles.keys().map(String::as_str).collect();
+            known.sort_unstable();
+            if known.is_empty() {
+                anyhow::anyhow!(
+                    "unknown search preset {name}; none are defined under [search.profiles]"
+                )
+            } else {
+                anyhow::anyhow!("unknown search preset {name}; known: {}", known.join(", "))
+            }
+        })
+    }
+
+    // `REMI_<AGENT>_HOME` in `ctx` wins over `[agents.<name>] home`.
+    pub fn source_context(&self, mut ctx: SourceContext) -> SourceContext {
+        for (name, agent) in &self.agents {
+            if let (Ok(kind), Some(home)) = (name.parse::<AgentKind>(), &agent.home) {
+                ctx.overrides.entry(kind).or_insert_with(|| home.clone());
+            }
+        }
+        ctx
+    }
 
+    pub fn load(config_path: &Path) -> anyhow::Result<Self> {
         if !config_path.exists() {
             return Ok(Self::default());
         }
 
-        let content = std::fs::read_to_string(&config_path).map_err(|e| {
+        let content = std::fs::read_to_string(config_path).map_err(|e| {
             anyhow::anyhow!(
                 "failed to read config file at {}: {}",
                 config_path.display(),
@@ -34,7 +290,181 @@ impl Config {
 
         let config: Config = toml::from_str(&content)
             .map_err(|e| anyhow::anyhow!("failed to parse config file: {}", e))?;
+        if let Some(name) = config
+            .agents
+            .keys()
+            .find(|name| name.parse::<AgentKind>().is_err())
+        {
+            anyhow::bail!("unknown agent [agents.{name}] in config file");
+        }
+        for (name, agent) in &config.agents {
+            if let Some(tz) = &agent.timezone {
+                tz.parse::<SourceTimezone>()
+                    .with_context(|| format!("invalid [agents.{name}] timezone"))?;
+            }
+        }
+        for (key, value) in [
+            ("scan_threads", config.performance.scan_threads),
+            ("embed_threads", config.performance.embed_threads),
+        ] {
+            if value == Some(0) {
+                anyhow::bail!("[performance] {key} must be at least 1");
+            }
+        }
 
         Ok(config)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discovery_rules_merge_agent_overrides() {
+        let config: Config = toml::from_str(
+            r#"
+[discovery]
+max_depth = 8
+exclude = ["node_modules"]
+
+[discovery.agents.claude]
+same_filesystem = true
+exclude = ["**/subagents/**"]
+
+[discovery.agents.aider]
+roots = ["~/code", "/srv/work"]
+"#,
+        )
+        .unwrap();
+        let claude = config.discovery.options_for(AgentKind::Claude);
+        assert_eq!(claude.max_depth, 8);
+        assert!(claude.same_filesystem);
+        assert_eq!(claude.exclude, vec!["node_modules", "**/subagents/**"]);
+        assert!(claude.roots.is_empty());
+        let aider = config.discovery.options_for(AgentKind::Aider);
+        assert_eq!(
+            aider.roots,
+            vec![PathBuf::from("~/code"), PathBuf::from("/srv/work")]
+        );
+
+        let pi = config.discovery.options_for(AgentKind::Pi);
+        assert!(!pi.same_filesystem);
+        assert_eq!(pi.exclude, vec!["node_modules"]);
+        let defaults = Config::default().discovery.options_for(AgentKind::Pi);
+        assert_eq!(defaults.max_depth, DiscoveryOptions::default().max_depth);
+        assert!(defaults.exclude.is_empty());
+    }
+
+    #[test]
+    fn agents_can_be_disabled() {
+        let config: Config = toml::from_str(
+            r#"
+[agents.droid]
+enabled = false
+
+[agents.pi]
+enabled = true
+"#,
+        )
+        .unwrap();
+        assert!(!config.agent_enabled(AgentKind::Droid));
+        assert!(config.agent_enabled(AgentKind::Pi));
+        assert!(config.agent_enabled(AgentKind::Amp));
+    }
+
+    #[test]
+    fn agent_homes_come_from_config_unless_the_env_sets_them() {
+        let config: Config = toml::from_str(
+            r#"
+[agents.codex]
+home = "/srv/codex"
+
+[agents.pi]
+home = "/srv/pi"
+"#,
+        )
+        .unwrap();
+        let mut env = SourceContext::with_home("/home/u");
+        env.overrides
+            .insert(AgentKind::Pi, PathBuf::from("/env/pi"));
+        let ctx = config.source_context(env);
+        assert_eq!(
+            ctx.home_for(AgentKind::Codex),
+            Some(Path::new("/srv/codex"))
+        );
+        assert_eq!(ctx.home_for(AgentKind::Pi), Some(Path::new("/env/pi")));
+        assert_eq!(ctx.home_for(AgentKind::Amp), Some(Path::new("/home/u")));
+    }
+
+    #[test]
+    fn search_profiles_fill_in_weights_and_report_unknown_names() {
+        let config: Config = toml::from_str(
+            r#"
+[search.profiles.debugging]
+agent = "claude"
+limit = 50
+format = "markdown"
+weights = { recency = 0.0 }
+"#,
+        )
+        .unwrap();
+        let profile = config.search_profile("debugging").unwrap();
+        assert_eq!(profile.agent.as_deref(), Some("claude"));
+        assert_eq!(profile.limit, Some(50));
+        let weights = profile.weights.weights().unwrap();
+        assert_eq!(weights.recency, 0.0);
+        assert_eq!(weights.bm25, search::Weights::default().bm25);
+
+        let err = config.search_profile("nope").unwrap_err().to_string();
+        assert!(err.contains("known: debugging"), "{err}");
+        let negative = WeightsConfig {
+            bm25: Some(-1.0),
+            ..Default::default()
+        };
+        assert!(negative.weights().is_err());
+        assert!(
+            toml::from_str::<Config>(
+                "[search.profiles.x]
+limt = 5
+"
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn performance_limits_must_be_positive() {
+        let path = std::env::temp_dir().join(format!("remi-config-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            "[performance]\nscan_threads = 2\nembed_threads = 1\n",
+        )
+        .unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.performance.scan_threads, Some(2));
+        assert_eq!(config.performance.embed_threads, Some(1));
+
+        std::fs::write(&path, "[performance]\nscan_threads = 0\n").unwrap();
+        let err = Config::load(&path).unwrap_err().to_string();
+        std::fs::remove_file(&path).unwrap();
+        assert!(err.contains("scan_threads must be at least 1"), "{err}");
+    }
+
+    #[test]
+    fn agent_timezones_are_parsed_and_checked_on_load() {
+        let path = std::env::temp_dir().join(format!("remi-config-tz-{}.toml", std::process::id()));
+        std::fs::write(&path, "[agents.aider]\ntimezone = \"+02:00\"\n").unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(
+            config.timezone_for(AgentKind::Aider),
+            Some("+02:00".parse().unwrap())
+        );
+        assert_eq!(config.timezone_for(AgentKind::Pi), None);
+
+        std::fs::write(&path, "[agents.aider]\ntimezone = \"Mars/Olympus\"\n").unwrap();
+        let err = format!("{:#}", Config::load(&path).unwrap_err());
+        std::fs::remove_file(&path).unwrap();
+        assert!(err.contains("invalid [agents.aider] timezone"), "{err}");
+    }
+}
@@ -0,0 +1,49 @@
+use core_model::{JSON_SCHEMA_VERSION, Message, Session};
+use serde_json::{Value, json};
+
+use crate::anonymize::Anonymizer;
+
+// One line of `remi export --format jsonl`: a session with its messages.
+// Anonymized exports also renumber ids, since stored ids are derived from
+// source paths.
+pub fn line(
+    index: usize,
+    session: &Session,
+    messages: &[Message],
+    anonymizer: Option<&mut Anonymizer>,
+) -> Value {
+    let (id, title, messages): (String, String, Vec<Value>) = match anonymizer {
+        Some(anon) => {
+            let id = format!("s{index}");
+            let messages = messages
+                .iter()
+                .enumerate()
+                .map(|(i, m)| message(format!("{id}-m{}", i + 1), m, anon.anonymize(&m.content)))
+                .collect();
+            (id, anon.anonymize(&session.title), messages)
+        }
+        None => (
+            session.id.clone(),
+            session.title.clone(),
+            messages
+                .iter()
+                .map(|m| message(m.id.clone(), m, m.content.clone()))
+                .collect(),
+        ),
+    };
+    json!({
+        "schema_version": JSON_SCHEMA_VERSION,
+        "session": {
+            "id": id,
+            "agent": session.agent.as_str(),
+            "title": title,
+            "created_at": session.created_at,
+            "updated_at": session.updated_at,
+        },
+        "messages": messages,
+    })
+}
+
+fn message(id: String, m: &Message, content: String) -> Value {
+    json!({ "id": id, "role": m.role, "content": content, "ts": m.ts })
+}
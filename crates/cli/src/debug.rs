@@ -0,0 +1,292 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+
+#[cfg(feature = "claude")]
+use claude::{DedupeDecision, DedupeReason};
+use core_model::{AgentAdapter, JSON_SCHEMA_VERSION, NormalizedBatch, ScanReport};
+use query::truncate_text;
+use serde_json::{Value, json};
+
+const CONTENT_PREVIEW_CHARS: usize = 400;
+const PAYLOAD_PREVIEW_CHARS: usize = 200;
+
+pub struct NormalizeRun {
+    pub agent: &'static str,
+    pub file: String,
+    pub records: usize,
+    pub report: Option<ScanReport>,
+    pub batch: NormalizedBatch,
+}
+
+// Scans with no cursor, so the whole file is read the way a first sync sees
+// it. The path is made absolute because adapters derive ids from it.
+pub fn normalize_file(adapter: &dyn AgentAdapter, file: &Path) -> anyhow::Result<NormalizeRun> {
+    if !file.is_file() {
+        anyhow::bail!("{} is not a file", file.display());
+    }
+    let file = std::path::absolute(file)?.to_string_lossy().into_owned();
+    let records = adapter.scan_changes_since(std::slice::from_ref(&file), None)?;
+    let report = adapter.scan_report();
+    let batch = adapter.normalize(&records)?;
+    Ok(NormalizeRun {
+        agent: adapter.kind().as_str(),
+        file,
+        records: records.len(),
+        report,
+        batch,
+    })
+}
+
+impl NormalizeRun {
+    pub fn to_json(&self) -> Value {
+        let report = self.report.unwrap_or_default();
+        json!({
+            "schema_version": JSON_SCHEMA_VERSION,
+            "agent": self.agent,
+            "file": self.file,
+            "scan": {
+                "records": self.records,
+                "files_seen": report.files_seen,
+                "files_skipped": report.files_skipped,
+                "parse_errors": report.parse_errors,
+            },
+            "batch": self.batch,
+        })
+    }
+
+    pub fn to_text(&self) -> String {
+        let batch = &self.batch;
+        let mut out = String::new();
+        let report = self.report.unwrap_or_default();
+        let _ = writeln!(
+            out,
+            "file={} agent={} records={} files_skipped={} parse_errors={}",
+            self.file, self.agent, self.records, report.files_skipped, report.parse_errors
+        );
+        let _ = writeln!(
+            out,
+            "sessions={} messages={} events={} artifacts={} provenance={} session_meta={}",
+            batch.sessions.len(),
+            batch.messages.len(),
+            batch.events.len(),
+            batch.artifacts.len(),
+            batch.provenance.len(),
+            batch.session_meta.len()
+        );
+        if self.records == 0 {
+            let _ = writeln!(
+                out,
+                "hint: no records were read; check that the file belongs to {} and is not empty",
+                self.agent
+            );
+        } else if batch.messages.is_empty() {
+            let _ = writeln!(
+                out,
+                "hint: records were read but none became messages; see `--json` for the batch"
+            );
+        }
+
+        // Messages and events are grouped under their session id, so ones
+        // that point at a session the batch lacks still show up.
+        let mut groups: BTreeMap<&str, (Vec<String>, Vec<String>)> = BTreeMap::new();
+        for m in &batch.messages {
+            let mut lines = vec![format!("{} [{}] {}", m.ts.to_rfc3339(), m.role, m.id)];
+            lines.extend(
+                truncate_text(&m.content, CONTENT_PREVIEW_CHARS)
+                    .lines()
+                    .map(|line| format!("  {line}")),
+            );
+            groups.entry(&m.session_id).or_default().0.extend(lines);
+        }
+        for e in &batch.events {
+            let payload = truncate_text(&e.payload.to_string(), PAYLOAD_PREVIEW_CHARS);
+            groups.entry(&e.session_id).or_default().1.push(format!(
+                "{} {} {payload}",
+                e.ts.to_rfc3339(),
+                e.kind
+            ));
+        }
+        for s in &batch.sessions {
+            let _ = writeln!(out, "\nsession {}", s.id);
+            let _ = writeln!(out, "  title: {}", s.title);
+            let _ = writeln!(
+                out,
+                "  created: {}  updated: {}",
+                s.created_at.to_rfc3339(),
+                s.updated_at.to_rfc3339()
+            );
+            let _ = writeln!(out, "  source: {}", s.source_ref);
+            write_group(&mut out, groups.remove(s.id.as_str()));
+        }
+        for (session_id, group) in groups {
+            let _ = writeln!(out, "\nsession {session_id} (not in batch)");
+            write_group(&mut out, Some(group));
+        }
+        out
+    }
+}
+
+#[cfg(feature = "claude")]
+pub struct DedupeRun {
+    pub files: Vec<String>,
+    pub records: usize,
+    pub decisions: Vec<DedupeDecision>,
+}
+
+// Dedupe is specific to the claude adapter, which reads the same messages
+// from project logs, transcripts and shared data directories.
+#[cfg(feature = "claude")]
+pub fn dedupe_files(files: &[std::path::PathBuf]) -> anyhow::Result<DedupeRun> {
+    let mut paths = Vec::with_capacity(files.len());
+    for file in files {
+        if !file.is_file() {
+            anyhow::bail!("{} is not a file", file.display());
+        }
+        paths.push(std::path::absolute(file)?.to_string_lossy().into_owned());
+    }
+    let (records, mut decisions) = claude::ClaudeAdapter::default().scan_traced(&paths, None);
+    decisions.sort_by(|a, b| {
+        (&a.dropped.source_path, a.dropped.line).cmp(&(&b.dropped.source_path, b.dropped.line))
+    });
+    Ok(DedupeRun {
+        files: paths,
+        records: records.len(),
+        decisions,
+    })
+}
+
+#[cfg(feature = "claude")]
+impl DedupeRun {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "schema_version": JSON_SCHEMA_VERSION,
+            "files": self.files,
+            "candidates": self.records + self.decisions.len(),
+            "kept": self.records,
+            "dropped": self.decisions,
+        })
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        let differs = self.decisions.iter().filter(|d| !d.same_content).count();
+        let _ = writeln!(
+            out,
+            "files={} candidates={} kept={} dropped={} content_differs={differs}",
+            self.files.len(),
+            self.records + self.decisions.len(),
+            self.records,
+            self.decisions.len()
+        );
+        for d in &self.decisions {
+            let _ = writeln!(
+                out,
+                "\ndropped {} ({})\n  kept {} ({})\n  reason: {}",
+                d.dropped,
+                d.dropped.source_id,
+                d.kept,
+                d.kept.source_id,
+                reason_detail(d)
+            );
+            if !d.same_content {
+                let _ = writeln!(out, "  content differs");
+            }
+        }
+        out
+    }
+}
+
+#[cfg(feature = "claude")]
+fn reason_detail(d: &DedupeDecision) -> String {
+    let (kept, dropped) = (&d.kept, &d.dropped);
+    match d.reason {
+        DedupeReason::Priority => format!(
+            "source priority {} over {}",
+            kept.priority, dropped.priority
+        ),
+        DedupeReason::Richness => {
+            format!("richer payload {} over {}", kept.richness, dropped.richness)
+        }
+        DedupeReason::Timestamp => format!(
+            "newer timestamp {} over {}",
+            kept.ts.to_rfc3339(),
+            dropped.ts.to_rfc3339()
+        ),
+        DedupeReason::SourceId => "identical copies; lower source id kept".to_string(),
+    }
+}
+
+fn write_group(out: &mut String, group: Option<(Vec<String>, Vec<String>)>) {
+    let (messages, events) = group.unwrap_or_default();
+    for (label, lines) in [("messages", messages), ("events", events)] {
+        if lines.is_empty() {
+            continue;
+        }
+        let _ = writeln!(out, "  {label}:");
+        for line in lines {
+            let _ = writeln!(out, "    {line}");
+        }
+    }
+}
+
+#[cfg(all(test, feature = "claude"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_one_transcript_without_a_store() {
+        let dir = std::env::temp_dir().join(format!("remi_debug_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("s.jsonl");
+        std::fs::write(
+            &file,
+            concat!(
+                r#"{"id":"m1","role":"user","content":"fix the login bug","timestamp":"2025-01-15T00:00:00Z","sessionId":"s1"}"#,
+                "\n",
+                r#"{"id":"m2","role":"assistant","content":"Done.","timestamp":"2025-01-15T00:01:00Z","sessionId":"s1"}"#,
+                "\nnot json\n",
+            ),
+        )
+        .unwrap();
+        let run = normalize_file(&claude::ClaudeAdapter::default(), &file).unwrap();
+        assert_eq!(run.records, 2);
+        assert_eq!(run.batch.messages.len(), 2);
+        let text = run.to_text();
+        assert!(text.contains("sessions=1 messages=2"), "{text}");
+        assert!(text.contains("[user]") && text.contains("  fix the login bug"));
+        assert!(text.contains("parse_errors=1"), "{text}");
+
+        let json = run.to_json();
+        assert_eq!(json["scan"]["records"], 2);
+        assert_eq!(json["batch"]["messages"][1]["content"], "Done.");
+
+        assert!(normalize_file(&claude::ClaudeAdapter::default(), &dir.join("missing")).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn dedupe_reports_the_losing_copy() {
+        let dir = std::env::temp_dir().join(format!("remi_debug_dedupe_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let line = r#"{"id":"m1","role":"user","content":"hi","timestamp":"2025-01-15T00:00:00Z","sessionId":"s1"}"#;
+        let rich = r#"{"id":"m1","role":"user","content":"hi","timestamp":"2025-01-15T00:00:00Z","sessionId":"s1","slug":"x"}"#;
+        let (a, b) = (dir.join("a.jsonl"), dir.join("b.jsonl"));
+        std::fs::write(&a, line).unwrap();
+        std::fs::write(&b, rich).unwrap();
+
+        let run = dedupe_files(&[a, b]).unwrap();
+        assert_eq!((run.records, run.decisions.len()), (1, 1));
+        let text = run.to_text();
+        assert!(
+            text.contains("candidates=2 kept=1 dropped=1 content_differs=0"),
+            "{text}"
+        );
+        assert!(
+            text.contains("a.jsonl:1 (m1)") && text.contains("richer payload"),
+            "{text}"
+        );
+        assert_eq!(run.to_json()["dropped"][0]["reason"], "richness");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
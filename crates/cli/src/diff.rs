@@ -0,0 +1,434 @@
+use std::{collections::HashSet, fmt::Write as _};
+
+use core_model::{Message, Session};
+use owo_colors::OwoColorize;
+
+use crate::{render::escape_html, stats};
+
+// Prompts whose word sets overlap at least this much are treated as the same
+// step, so reworded retries still line up.
+const MATCH_THRESHOLD: f64 = 0.5;
+const PREVIEW_CHARS: usize = 200;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Turn {
+    pub prompt: String,
+    pub outcome: String,
+    pub tools: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Row {
+    Both {
+        left: usize,
+        right: usize,
+        similarity: f64,
+    },
+    Left(usize),
+    Right(usize),
+}
+
+pub struct SessionDiff<'a> {
+    pub left: &'a Session,
+    pub right: &'a Session,
+    pub left_turns: Vec<Turn>,
+    pub right_turns: Vec<Turn>,
+    pub rows: Vec<Row>,
+}
+
+impl<'a> SessionDiff<'a> {
+    pub fn new(
+        left: &'a Session,
+        left_messages: &[Message],
+        right: &'a Session,
+        right_messages: &[Message],
+    ) -> Self {
+        let left_turns = turns(left_messages);
+        let right_turns = turns(right_messages);
+        let rows = align(&left_turns, &right_turns);
+        Self {
+            left,
+            right,
+            left_turns,
+            right_turns,
+            rows,
+        }
+    }
+
+    pub fn render_terminal(&self, use_color: bool) -> String {
+        let paint = |text: String, sign: char| match (use_color, sign) {
+            (false, _) => text,
+            (true, '-') => text.red().to_string(),
+            (true, '+') => text.green().to_string(),
+            (true, _) => text.dimmed().to_string(),
+        };
+        let mut out = String::new();
+        for (sign, session, turns) in [
+            ('-', self.left, &self.left_turns),
+            ('+', self.right, &self.right_turns),
+        ] {
+            let header = format!(
+                "{sign}{sign}{sign} {} {} {} ({} prompts)",
+                session.id,
+                session.agent.as_str(),
+                one_line(&session.title, 80),
+                turns.len()
+            );
+            let _ = writeln!(out, "{}", paint(header, sign));
+        }
+        for (step, row) in self.rows.iter().enumerate() {
+            out.push('\n');
+            match row {
+                Row::Both {
+                    left,
+                    right,
+                    similarity,
+                } => {
+                    let (l, r) = (&self.left_turns[*left], &self.right_turns[*right]);
+                    let same = l.prompt == r.prompt;
+                    let label = if same {
+                        "same prompt".to_string()
+                    } else {
+                        format!("{:.0}% similar prompt", similarity * 100.0)
+                    };
+                    let _ = writeln!(out, "@@ step {} ({label}) @@", step + 1);
+                    if same {
+                        let _ = writeln!(out, "  {}", one_line(&l.prompt, PREVIEW_CHARS));
+                    } else {
+                        let _ = writeln!(out, "{}", paint(prompt_line('-', l), '-'));
+                        let _ = writeln!(out, "{}", paint(prompt_line('+', r), '+'));
+                    }
+                    let _ = writeln!(out, "{}", paint(outcome_line('-', l), '-'));
+                    let _ = writeln!(out, "{}", paint(outcome_line('+', r), '+'));
+                }
+                Row::Left(i) => {
+                    let turn = &self.left_turns[*i];
+                    let _ = writeln!(out, "@@ step {} (only in {}) @@", step + 1, self.left.id);
+                    let _ = writeln!(out, "{}", paint(prompt_line('-', turn), '-'));
+                    let _ = writeln!(out, "{}", paint(outcome_line('-', turn), '-'));
+                }
+                Row::Right(j) => {
+                    let turn = &self.right_turns[*j];
+                    let _ = writeln!(out, "@@ step {} (only in {}) @@", step + 1, self.right.id);
+                    let _ = writeln!(out, "{}", paint(prompt_line('+', turn), '+'));
+                    let _ = writeln!(out, "{}", paint(outcome_line('+', turn), '+'));
+                }
+            }
+        }
+        out
+    }
+
+    pub fn render_html(&self) -> String {
+        let title = format!("{} vs {}", self.left.id, self.right.id);
+        let mut body = String::from("<table>\n<thead><tr><th>#</th>");
+        for session in [self.left, self.right] {
+            let _ = write!(
+                body,
+                "<th>{} <code>{}</code><br>{}</th>",
+                escape_html(session.agent.as_str()),
+                escape_html(&session.id),
+                escape_html(&one_line(&session.title, 120))
+            );
+        }
+        body.push_str("</tr></thead>\n<tbody>\n");
+        for (step, row) in self.rows.iter().enumerate() {
+            let (left, right, class) = match row {
+                Row::Both { left, right, .. } => (
+                    Some(&self.left_turns[*left]),
+                    Some(&self.right_turns[*right]),
+                    "both",
+                ),
+                Row::Left(i) => (Some(&self.left_turns[*i]), None, "left"),
+                Row::Right(j) => (None, Some(&self.right_turns[*j]), "right"),
+            };
+            let _ = write!(body, "<tr class=\"{class}\"><td>{}</td>", step + 1);
+            for turn in [left, right] {
+                match turn {
+                    Some(turn) => {
+                        let _ = write!(
+                            body,
+                            "<td><p class=\"prompt\">{}</p><p class=\"outcome\">{}</p>{}</td>",
+                            escape_html(&turn.prompt),
+                            escape_html(&turn.outcome),
+                            tools_html(&turn.tools)
+                        );
+                    }
+                    None => body.push_str("<td class=\"missing\"></td>"),
+                }
+            }
+            body.push_str("</tr>\n");
+        }
+        body.push_str("</tbody>\n</table>\n");
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\nbody {{ font-family: sans-serif; }}\ntable {{ border-collapse: collapse; width: 100%; table-layout: fixed; }}\nth, td {{ border: 1px solid #ddd; padding: 6px; vertical-align: top; white-space: pre-wrap; }}\nth:first-child, td:first-child {{ width: 2.5em; }}\n.prompt {{ font-weight: bold; }}\n.left td:nth-child(2) {{ background: #fee2e2; }}\n.right td:nth-child(3) {{ background: #dcfce7; }}\n.missing {{ background: #f3f4f6; }}\n.tools {{ color: #6b7280; font-size: 0.9em; }}\n</style>\n</head>\n<body>\n<h1>{title}</h1>\n{body}</body>\n</html>\n",
+            title = escape_html(&title)
+        )
+    }
+}
+
+// A turn is one user prompt plus everything up to the next prompt; the
+// outcome is the last assistant text in that span.
+fn turns(messages: &[Message]) -> Vec<Turn> {
+    let mut out: Vec<Turn> = Vec::new();
+    for message in messages {
+        let text = visible_text(&message.content);
+        if message.role == "user" {
+            if text.is_empty() {
+                continue;
+            }
+            out.push(Turn {
+                prompt: text,
+                outcome: String::new(),
+                tools: Vec::new(),
+            });
+            continue;
+        }
+        let Some(turn) = out.last_mut() else {
+            continue;
+        };
+        turn.tools
+            .extend(stats::tool_use_names(&message.content).map(str::to_string));
+        if message.role == "assistant" && !text.is_empty() {
+            turn.outcome = text;
+        }
+    }
+    out
+}
+
+pub fn visible_text(content: &str) -> String {
+    let (_, text) = core_model::split_reasoning(content);
+    text.lines()
+        .filter(|line| {
+            let line = line.trim_start();
+            !line.starts_with("tool_use:") && !line.starts_with("tool_result:")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    a.intersection(b).count() as f64 / a.union(b).count() as f64
+}
+
+// Longest common subsequence over prompts, where two prompts match when
+// they are similar enough; unmatched turns show up as one-sided rows.
+fn align(left: &[Turn], right: &[Turn]) -> Vec<Row> {
+    let left_words: Vec<_> = left.iter().map(|t| words(&t.prompt)).collect();
+    let right_words: Vec<_> = right.iter().map(|t| words(&t.prompt)).collect();
+    let (n, m) = (left.len(), right.len());
+    let mut scores = vec![vec![0.0_f64; m]; n];
+    for (i, a) in left_words.iter().enumerate() {
+        for (j, b) in right_words.iter().enumerate() {
+            scores[i][j] = similarity(a, b);
+        }
+    }
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if scores[i][j] >= MATCH_THRESHOLD {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut rows = Vec::new();
+    while i < n && j < m {
+        if scores[i][j] >= MATCH_THRESHOLD && lcs[i][j] == lcs[i + 1][j + 1] + 1 {
+            rows.push(Row::Both {
+                left: i,
+                right: j,
+                similarity: scores[i][j],
+            });
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            rows.push(Row::Left(i));
+            i += 1;
+        } else {
+            rows.push(Row::Right(j));
+            j += 1;
+        }
+    }
+    rows.extend((i..n).map(Row::Left));
+    rows.extend((j..m).map(Row::Right));
+    rows
+}
+
+fn prompt_line(sign: char, turn: &Turn) -> String {
+    format!("{sign} {}", one_line(&turn.prompt, PREVIEW_CHARS))
+}
+
+fn outcome_line(sign: char, turn: &Turn) -> String {
+    let outcome = if turn.outcome.is_empty() {
+        "(no reply)".to_string()
+    } else {
+        one_line(&turn.outcome, PREVIEW_CHARS)
+    };
+    if turn.tools.is_empty() {
+        format!("{sign}   => {outcome}")
+    } else {
+        format!(
+            "{sign}   => {outcome} [{} tool calls: {}]",
+            turn.tools.len(),
+            tool_summary(&turn.tools)
+        )
+    }
+}
+
+fn tools_html(tools: &[String]) -> String {
+    if tools.is_empty() {
+        return String::new();
+    }
+    format!(
+        "<p class=\"tools\">{} tool calls: {}</p>",
+        tools.len(),
+        escape_html(&tool_summary(tools))
+    )
+}
+
+fn tool_summary(tools: &[String]) -> String {
+    let mut unique: Vec<&str> = Vec::new();
+    for tool in tools {
+        if !unique.contains(&tool.as_str()) {
+            unique.push(tool);
+        }
+    }
+    unique.join(", ")
+}
+
+fn one_line(text: &str, max: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() > max {
+        format!("{}…", text.chars().take(max).collect::<String>())
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone, Utc};
+    use core_model::AgentKind;
+
+    fn session(id: &str, agent: AgentKind) -> Session {
+        let at = Utc.with_ymd_and_hms(2025, 5, 1, 9, 0, 0).unwrap();
+        Session {
+            id: id.to_string(),
+            agent,
+            source_ref: format!("/tmp/{id}.jsonl"),
+            title: format!("{id} retry"),
+            created_at: at,
+            updated_at: at,
+        }
+    }
+
+    fn messages(id: &str, items: &[(&str, &str)]) -> Vec<Message> {
+        let at = Utc.with_ymd_and_hms(2025, 5, 1, 9, 0, 0).unwrap();
+        items
+            .iter()
+            .enumerate()
+            .map(|(i, (role, content))| Message {
+                id: format!("{id}-{i}"),
+                session_id: id.to_string(),
+                role: role.to_string(),
+                content: content.to_string(),
+                ts: at + Duration::seconds(i as i64),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn retries_align_by_prompt() {
+        let left = session("a", AgentKind::Claude);
+        let right = session("b", AgentKind::Codex);
+        let left_messages = messages(
+            "a",
+            &[
+                ("user", "fix the failing login test"),
+                ("assistant", "tool_use: Read {\"path\":\"login.rs\"}"),
+                ("tool", "tool_result: ok"),
+                ("assistant", "reasoning: look\nFixed the timeout."),
+                ("user", "now update the changelog"),
+                ("assistant", "Done."),
+            ],
+        );
+        let right_messages = messages(
+            "b",
+            &[
+                ("user", "please fix the failing login test"),
+                ("assistant", "tool_use: bash {\"command\":\"cargo test\"}"),
+                ("assistant", "The test was flaky; added a retry."),
+                ("user", "add a regression test too"),
+            ],
+        );
+        let diff = SessionDiff::new(&left, &left_messages, &right, &right_messages);
+        assert_eq!(diff.left_turns[0].outcome, "Fixed the timeout.");
+        assert_eq!(diff.left_turns[0].tools, vec!["Read"]);
+        assert!(matches!(
+            diff.rows[0],
+            Row::Both {
+                left: 0,
+                right: 0,
+                ..
+            }
+        ));
+        assert_eq!(diff.rows[1..], [Row::Left(1), Row::Right(1)]);
+
+        let text = diff.render_terminal(false);
+        assert!(
+            text.starts_with("--- a claude a retry (2 prompts)\n+++ b codex b retry (2 prompts)\n")
+        );
+        assert!(text.contains("@@ step 1 (83% similar prompt) @@"));
+        assert!(text.contains("-   => Fixed the timeout. [1 tool calls: Read]"));
+        assert!(text.contains("+   => The test was flaky; added a retry. [1 tool calls: bash]"));
+        assert!(
+            text.contains(
+                "@@ step 3 (only in b) @@\n+ add a regression test too\n+   => (no reply)"
+            )
+        );
+
+        let html = diff.render_html();
+        assert!(html.contains("<title>a vs b</title>"));
+        assert!(html.contains("<tr class=\"right\"><td>3</td><td class=\"missing\"></td>"));
+    }
+
+    #[test]
+    fn unrelated_prompts_do_not_match() {
+        let turn = |prompt: &str| Turn {
+            prompt: prompt.to_string(),
+            outcome: String::new(),
+            tools: Vec::new(),
+        };
+        let rows = align(
+            &[turn("write docs"), turn("ship it")],
+            &[turn("refactor parser"), turn("ship it")],
+        );
+        assert_eq!(
+            rows,
+            vec![
+                Row::Left(0),
+                Row::Right(0),
+                Row::Both {
+                    left: 1,
+                    right: 1,
+                    similarity: 1.0
+                }
+            ]
+        );
+    }
+}
@@ -0,0 +1,388 @@
+use std::{collections::BTreeMap, fmt::Write as _};
+
+use chrono::{DateTime, Days, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use core_model::{AgentKind, Message, Session};
+use store_sqlite::SqliteStore;
+
+const TOPIC_LIMIT: usize = 8;
+const FILE_LIMIT: usize = 20;
+const STOPWORDS: &[&str] = &[
+    "about", "after", "again", "also", "because", "been", "before", "being", "could", "does",
+    "doing", "done", "each", "from", "have", "here", "into", "just", "like", "make", "more",
+    "need", "only", "other", "please", "should", "some", "still", "than", "that", "their", "them",
+    "then", "there", "these", "they", "this", "those", "using", "want", "were", "what", "when",
+    "where", "which", "while", "will", "with", "would", "your",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionDigest {
+    pub id: String,
+    pub agent: String,
+    pub title: String,
+    pub messages: usize,
+    pub is_new: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Digest {
+    // First and last day covered, inclusive.
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub sessions: Vec<SessionDigest>,
+    pub topics: Vec<(String, usize)>,
+    pub files: Vec<String>,
+}
+
+impl Digest {
+    // `day` is a calendar day in `tz`, so a local digest ends at local
+    // midnight rather than UTC midnight.
+    pub fn build<Tz: TimeZone>(
+        store: &SqliteStore,
+        day: NaiveDate,
+        tz: &Tz,
+    ) -> anyhow::Result<Self> {
+        let sessions = active_sessions(store, day, day, tz)?;
+        Self::for_sessions(store, day, day, tz, &sessions)
+    }
+
+    // Summarizes the messages the given sessions had between `from` and `to`
+    // in `tz`, busiest session first.
+    pub fn for_sessions<Tz: TimeZone>(
+        store: &SqliteStore,
+        from: NaiveDate,
+        to: NaiveDate,
+        tz: &Tz,
+        active: &[Session],
+    ) -> anyhow::Result<Self> {
+        let range = day_start(tz, from)..day_start(tz, to + Days::new(1));
+        let mut sessions = Vec::new();
+        let mut words: BTreeMap<String, usize> = BTreeMap::new();
+        let mut files: Vec<String> = Vec::new();
+        for session in active {
+            let messages = store.get_session_messages(&session.id)?;
+            let title = query::session_title(session, &messages);
+            let in_range: Vec<&Message> =
+                messages.iter().filter(|m| range.contains(&m.ts)).collect();
+            for m in &in_range {
+                if m.role == "user" {
+                    count_words(&m.content, &mut words);
+                }
+                for path in core_model::tool_file_paths(&m.content) {
+                    if !files.contains(&path) {
+                        files.push(path);
+                    }
+                }
+            }
+            sessions.push(SessionDigest {
+                id: session.id.clone(),
+                agent: session.agent.as_str().to_string(),
+                title,
+                messages: in_range.len(),
+                is_new: range.contains(&session.created_at),
+            });
+        }
+        sessions.sort_by(|a, b| b.messages.cmp(&a.messages).then_with(|| a.id.cmp(&b.id)));
+        let mut topics: Vec<(String, usize)> = words.into_iter().filter(|(_, n)| *n > 1).collect();
+        topics.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        topics.truncate(TOPIC_LIMIT);
+        files.sort();
+        Ok(Self {
+            from,
+            to,
+            sessions,
+            topics,
+            files,
+        })
+    }
+
+    pub fn file_name(&self) -> String {
+        format!("remi-digest-{}.md", self.from)
+    }
+
+    pub fn render_markdown(&self) -> String {
+        let heading = if self.from == self.to {
+            format!("# Agent digest for {}\n\n", self.from)
+        } else {
+            format!("# Agent digest for {} to {}\n\n", self.from, self.to)
+        };
+        heading + self.render_body().as_str()
+    }
+
+    pub fn render_body(&self) -> String {
+        let mut out = String::new();
+        if self.sessions.is_empty() {
+            out.push_str("No agent activity recorded.\n");
+            return out;
+        }
+        let new = self.sessions.iter().filter(|s| s.is_new).count();
+        let messages: usize = self.sessions.iter().map(|s| s.messages).sum();
+        let mut per_agent: BTreeMap<&str, usize> = BTreeMap::new();
+        for s in &self.sessions {
+            *per_agent.entry(s.agent.as_str()).or_default() += 1;
+        }
+        let agents = per_agent
+            .iter()
+            .map(|(agent, n)| format!("{agent} ({n})"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let _ = writeln!(
+            out,
+            "{} sessions active ({new} new), {messages} messages. Agents: {agents}.\n",
+            self.sessions.len()
+        );
+
+        for (heading, is_new) in [("New sessions", true), ("Continued sessions", false)] {
+            let rows: Vec<&SessionDigest> = self
+                .sessions
+                .iter()
+                .filter(|s| s.is_new == is_new)
+                .collect();
+            if rows.is_empty() {
+                continue;
+            }
+            let _ = writeln!(out, "## {heading}\n");
+            for s in rows {
+                let _ = writeln!(
+                    out,
+                    "- **{}** ({}, {} messages) `{}`",
+                    s.title.replace('\n', " "),
+                    s.agent,
+                    s.messages,
+                    s.id
+                );
+            }
+            out.push('\n');
+        }
+
+        if !self.topics.is_empty() {
+            out.push_str("## Key topics\n\n");
+            let topics = self
+                .topics
+                .iter()
+                .map(|(word, n)| format!("{word} ({n})"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let _ = writeln!(out, "{topics}\n");
+        }
+
+        if !self.files.is_empty() {
+            out.push_str("## Files touched\n\n");
+            for path in self.files.iter().take(FILE_LIMIT) {
+                let _ = writeln!(out, "- `{path}`");
+            }
+            if self.files.len() > FILE_LIMIT {
+                let _ = writeln!(out, "- and {} more", self.files.len() - FILE_LIMIT);
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+// Agent sessions with messages between `from` and `to` in `tz`. Rollups are
+// left out since they restate other sessions' activity.
+pub fn active_sessions<Tz: TimeZone>(
+    store: &SqliteStore,
+    from: NaiveDate,
+    to: NaiveDate,
+    tz: &Tz,
+) -> anyhow::Result<Vec<Session>> {
+    let mut sessions =
+        store.sessions_active_between(day_start(tz, from), day_start(tz, to + Days::new(1)))?;
+    sessions.retain(|s| s.agent != AgentKind::Meta);
+    sessions.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(sessions)
+}
+
+// Midnight of `day` in `tz`. Where a DST change skips midnight the day
+// starts at the first hour that exists.
+fn day_start<Tz: TimeZone>(tz: &Tz, day: NaiveDate) -> DateTime<Utc> {
+    (0..3)
+        .filter_map(|hour| {
+            let time = NaiveTime::from_hms_opt(hour, 0, 0)?;
+            tz.from_local_datetime(&day.and_time(time)).earliest()
+        })
+        .next()
+        .map_or_else(|| day.and_time(NaiveTime::MIN).and_utc(), |t| t.to_utc())
+}
+
+// The next time `at` comes round in `tz` after `now`, and the day whose digest
+// is due then: the one that ended before it.
+pub fn next_run<Tz: TimeZone>(now: &DateTime<Tz>, at: NaiveTime) -> (DateTime<Utc>, NaiveDate) {
+    let tz = now.timezone();
+    (0..3)
+        .map(|ahead| now.date_naive() + Days::new(ahead))
+        .find_map(|day| {
+            let local = day.and_time(at);
+            // When `at` falls into a DST gap, run an hour later.
+            let run = [local, local + Duration::hours(1)]
+                .iter()
+                .find_map(|t| tz.from_local_datetime(t).earliest())?;
+            (run > *now).then(|| (run.to_utc(), day - Days::new(1)))
+        })
+        .expect("`at` comes round within two days")
+}
+
+pub fn count_words(content: &str, words: &mut BTreeMap<String, usize>) {
+    let (_, text) = core_model::split_reasoning(content);
+    for raw in text.split(|c: char| !c.is_alphanumeric() && c != '_' && c != '-') {
+        let word = raw.trim_matches('-').to_lowercase();
+        if word.chars().count() < 4
+            || word.chars().all(|c| c.is_ascii_digit())
+            || STOPWORDS.contains(&word.as_str())
+        {
+            continue;
+        }
+        *words.entry(word).or_default() += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+    use core_model::NormalizedBatch;
+
+    fn batch(
+        agent: AgentKind,
+        id: &str,
+        created: u32,
+        messages: &[(&str, u32, &str)],
+    ) -> NormalizedBatch {
+        let at = |d: u32| Utc.with_ymd_and_hms(2025, 5, d, 12, 0, 0).unwrap();
+        NormalizedBatch {
+            sessions: vec![Session {
+                id: id.to_string(),
+                agent,
+                source_ref: format!("/src/{id}.jsonl"),
+                title: format!("{id} work"),
+                created_at: at(created),
+                updated_at: at(created),
+            }],
+            messages: messages
+                .iter()
+                .enumerate()
+                .map(|(i, (role, d, content))| Message {
+                    id: format!("{id}-{i}"),
+                    session_id: id.to_string(),
+                    role: role.to_string(),
+                    content: content.to_string(),
+                    ts: at(*d) + chrono::Duration::seconds(i as i64),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn digest_summarizes_one_day() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        store
+            .save_batch(&batch(
+                AgentKind::Claude,
+                "s1",
+                2,
+                &[
+                    ("user", 2, "migrate the parser"),
+                    (
+                        "user",
+                        3,
+                        "the parser migration still fails in parser tests",
+                    ),
+                    (
+                        "assistant",
+                        3,
+                        r#"tool_use: Edit {"file_path":"src/parser.rs","old_string":"a"}"#,
+                    ),
+                ],
+            ))
+            .unwrap();
+        store
+            .save_batch(&batch(
+                AgentKind::Codex,
+                "s2",
+                3,
+                &[
+                    ("user", 3, "add parser benchmarks"),
+                    (
+                        "assistant",
+                        3,
+                        "tool_use: apply_patch {\"patchText\":\"*** Add File: benches/parse.rs\\n+fn main() {}\"}",
+                    ),
+                ],
+            ))
+            .unwrap();
+
+        let day = NaiveDate::from_ymd_opt(2025, 5, 3).unwrap();
+        let digest = Digest::build(&store, day, &Utc).unwrap();
+        assert_eq!(digest.sessions.len(), 2);
+        assert_eq!(digest.sessions[0].id, "s1");
+        assert_eq!(digest.sessions[0].messages, 2);
+        assert!(!digest.sessions[0].is_new);
+        assert!(digest.sessions[1].is_new);
+        assert_eq!(digest.topics, vec![("parser".to_string(), 3)]);
+        assert_eq!(digest.files, vec!["benches/parse.rs", "src/parser.rs"]);
+
+        let markdown = digest.render_markdown();
+        assert!(markdown.starts_with("# Agent digest for 2025-05-03\n"));
+        assert!(
+            markdown
+                .contains("2 sessions active (1 new), 4 messages. Agents: claude (1), codex (1).")
+        );
+        assert!(markdown.contains("## New sessions\n\n- **s2 work** (codex, 2 messages) `s2`"));
+        assert!(markdown.contains("## Continued sessions\n\n- **s1 work**"));
+        assert!(markdown.contains("- `src/parser.rs`"));
+        assert_eq!(digest.file_name(), "remi-digest-2025-05-03.md");
+
+        let quiet = Digest::build(&store, day.succ_opt().unwrap(), &Utc).unwrap();
+        assert!(
+            quiet
+                .render_markdown()
+                .contains("No agent activity recorded.")
+        );
+    }
+
+    #[test]
+    fn days_end_at_local_midnight() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        // Noon UTC on the 2nd is already the 3rd fourteen hours ahead.
+        store
+            .save_batch(&batch(AgentKind::Claude, "s1", 2, &[("user", 2, "late")]))
+            .unwrap();
+        let ahead = FixedOffset::east_opt(14 * 3600).unwrap();
+        let day = |d: u32| NaiveDate::from_ymd_opt(2025, 5, d).unwrap();
+        assert_eq!(
+            Digest::build(&store, day(2), &Utc).unwrap().sessions.len(),
+            1
+        );
+        assert!(
+            Digest::build(&store, day(2), &ahead)
+                .unwrap()
+                .sessions
+                .is_empty()
+        );
+        let local = Digest::build(&store, day(3), &ahead).unwrap();
+        assert_eq!(local.sessions.len(), 1);
+        assert_eq!(local.sessions[0].messages, 1);
+        assert!(local.sessions[0].is_new);
+    }
+
+    #[test]
+    fn runs_are_scheduled_in_local_time() {
+        let tz = FixedOffset::west_opt(5 * 3600).unwrap();
+        let at = NaiveTime::from_hms_opt(0, 15, 0).unwrap();
+        let day = |d: u32| NaiveDate::from_ymd_opt(2025, 5, d).unwrap();
+
+        let evening = tz.with_ymd_and_hms(2025, 5, 3, 22, 0, 0).unwrap();
+        let (run, due) = next_run(&evening, at);
+        assert_eq!(run, Utc.with_ymd_and_hms(2025, 5, 4, 5, 15, 0).unwrap());
+        assert_eq!(due, day(3));
+
+        let early = tz.with_ymd_and_hms(2025, 5, 4, 0, 5, 0).unwrap();
+        assert_eq!(next_run(&early, at), (run, day(3)));
+        let after = tz.with_ymd_and_hms(2025, 5, 4, 0, 15, 0).unwrap();
+        assert_eq!(next_run(&after, at).1, day(4));
+    }
+}
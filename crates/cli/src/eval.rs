@@ -0,0 +1,219 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write as _;
+use std::path::Path;
+
+use anyhow::Context;
+use core_model::JSON_SCHEMA_VERSION;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+pub const DEFAULT_KS: &[usize] = &[1, 5, 10];
+
+// One line of a gold file. A query needs at least one expected session or
+// message; a message counts as found when a ranked session's top hit is it.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GoldCase {
+    pub query: String,
+    #[serde(default)]
+    pub sessions: Vec<String>,
+    #[serde(default)]
+    pub messages: Vec<String>,
+}
+
+pub fn read_gold(path: &Path) -> anyhow::Result<Vec<GoldCase>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("reading gold file {}", path.display()))?;
+    parse_gold(&content).with_context(|| format!("in gold file {}", path.display()))
+}
+
+fn parse_gold(content: &str) -> anyhow::Result<Vec<GoldCase>> {
+    let mut cases = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let case: GoldCase =
+            serde_json::from_str(line).with_context(|| format!("line {}", i + 1))?;
+        if case.sessions.is_empty() && case.messages.is_empty() {
+            anyhow::bail!(
+                "line {}: {:?} lists no expected sessions or messages",
+                i + 1,
+                case.query
+            );
+        }
+        cases.push(case);
+    }
+    if cases.is_empty() {
+        anyhow::bail!("no queries");
+    }
+    Ok(cases)
+}
+
+// A ranked result as far as scoring is concerned.
+pub struct Ranked {
+    pub session_id: String,
+    pub top_message_id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CaseResult {
+    pub query: String,
+    // 1-based rank of the first relevant result.
+    pub rank: Option<usize>,
+    pub reciprocal_rank: f64,
+    pub recall: BTreeMap<String, f64>,
+    pub expected: usize,
+    pub returned: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub queries: usize,
+    pub ks: Vec<usize>,
+    pub mrr: f64,
+    pub recall: BTreeMap<String, f64>,
+    pub cases: Vec<CaseResult>,
+}
+
+pub fn score_case(case: &GoldCase, ranked: &[Ranked], ks: &[usize]) -> CaseResult {
+    let sessions: HashSet<&str> = case.sessions.iter().map(String::as_str).collect();
+    let messages: HashSet<&str> = case.messages.iter().map(String::as_str).collect();
+    let relevant: Vec<bool> = ranked
+        .iter()
+        .map(|r| {
+            sessions.contains(r.session_id.as_str()) || messages.contains(r.top_message_id.as_str())
+        })
+        .collect();
+    let rank = relevant.iter().position(|hit| *hit).map(|i| i + 1);
+    let expected = sessions.len() + messages.len();
+    let recall = ks
+        .iter()
+        .map(|&k| {
+            let found = relevant.iter().take(k).filter(|hit| **hit).count();
+            (k.to_string(), found as f64 / expected as f64)
+        })
+        .collect();
+    CaseResult {
+        query: case.query.clone(),
+        rank,
+        reciprocal_rank: rank.map_or(0.0, |r| 1.0 / r as f64),
+        recall,
+        expected,
+        returned: ranked.len(),
+    }
+}
+
+pub fn report(cases: Vec<CaseResult>, ks: &[usize]) -> Report {
+    let n = cases.len().max(1) as f64;
+    let mrr = cases.iter().map(|c| c.reciprocal_rank).sum::<f64>() / n;
+    let recall = ks
+        .iter()
+        .map(|k| {
+            let key = k.to_string();
+            let sum: f64 = cases.iter().map(|c| c.recall[&key]).sum();
+            (key, sum / n)
+        })
+        .collect();
+    Report {
+        queries: cases.len(),
+        ks: ks.to_vec(),
+        mrr,
+        recall,
+        cases,
+    }
+}
+
+impl Report {
+    pub fn to_json(&self) -> anyhow::Result<Value> {
+        let mut value = serde_json::to_value(self)?;
+        value["schema_version"] = json!(JSON_SCHEMA_VERSION);
+        Ok(value)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = format!("queries={} mrr={:.3}", self.queries, self.mrr);
+        for k in &self.ks {
+            let _ = write!(out, " recall@{k}={:.3}", self.recall[&k.to_string()]);
+        }
+        out.push('\n');
+        let max_k = self.ks.iter().max().copied().unwrap_or_default();
+        let missed: Vec<&CaseResult> = self.cases.iter().filter(|c| c.rank.is_none()).collect();
+        if !missed.is_empty() {
+            let _ = writeln!(out, "missed in top {max_k} ({}):", missed.len());
+            for case in missed {
+                let _ = writeln!(out, "  {:?} ({} results)", case.query, case.returned);
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranked(ids: &[(&str, &str)]) -> Vec<Ranked> {
+        ids.iter()
+            .map(|(session, message)| Ranked {
+                session_id: session.to_string(),
+                top_message_id: message.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn gold_lines_need_a_query_and_a_target() {
+        let cases = parse_gold(
+            "{\"query\":\"retry\",\"sessions\":[\"s1\"]}\n\n{\"query\":\"auth\",\"messages\":[\"m9\"]}\n",
+        )
+        .unwrap();
+        assert_eq!(cases.len(), 2);
+        assert_eq!(cases[1].messages, vec!["m9"]);
+        let err = parse_gold("{\"query\":\"x\"}").unwrap_err().to_string();
+        assert!(err.contains("line 1"), "{err}");
+        assert!(parse_gold("{\"query\":\"x\",\"session\":[\"s\"]}").is_err());
+        assert!(parse_gold("\n").is_err());
+    }
+
+    #[test]
+    fn mrr_and_recall_follow_the_ranking() {
+        let ks = [1, 3];
+        let hit_second = GoldCase {
+            query: "a".into(),
+            sessions: vec!["s2".into(), "s9".into()],
+            messages: Vec::new(),
+        };
+        let by_message = GoldCase {
+            query: "b".into(),
+            sessions: Vec::new(),
+            messages: vec!["m1".into()],
+        };
+        let missed = GoldCase {
+            query: "c".into(),
+            sessions: vec!["nope".into()],
+            messages: Vec::new(),
+        };
+        let results = ranked(&[("s1", "m1"), ("s2", "m2"), ("s3", "m3")]);
+        let cases = vec![
+            score_case(&hit_second, &results, &ks),
+            score_case(&by_message, &results, &ks),
+            score_case(&missed, &results, &ks),
+        ];
+        assert_eq!(cases[0].rank, Some(2));
+        assert_eq!(cases[0].recall["1"], 0.0);
+        assert_eq!(cases[0].recall["3"], 0.5);
+        assert_eq!(cases[1].rank, Some(1));
+        assert_eq!(cases[2].reciprocal_rank, 0.0);
+
+        let report = report(cases, &ks);
+        assert!((report.mrr - 0.5).abs() < 1e-9);
+        assert!((report.recall["3"] - 0.5).abs() < 1e-9);
+        let text = report.to_text();
+        assert!(
+            text.starts_with("queries=3 mrr=0.500 recall@1=0.333 recall@3=0.500"),
+            "{text}"
+        );
+        assert!(text.contains("missed in top 3 (1):\n  \"c\""), "{text}");
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+use core_model::{JSON_SCHEMA_VERSION, RELATION_USES};
+use serde_json::{Value, json};
+use store_sqlite::Graph;
+
+pub fn to_json(graph: &Graph) -> Value {
+    let nodes: Vec<Value> = graph
+        .nodes
+        .iter()
+        .map(|n| json!({ "id": n.id, "kind": n.kind, "name": n.name, "sessions": n.sessions }))
+        .collect();
+    let edges: Vec<Value> = graph
+        .edges
+        .iter()
+        .map(|e| {
+            json!({
+                "source": e.source,
+                "target": e.target,
+                "relation": e.relation,
+                "sessions": e.sessions,
+            })
+        })
+        .collect();
+    json!({
+        "schema_version": JSON_SCHEMA_VERSION,
+        "nodes": nodes,
+        "edges": edges,
+    })
+}
+
+// Node shapes tell the kinds apart; `uses` edges point from tool to file and
+// co-occurrence edges are drawn without arrowheads. Line width follows the
+// number of sessions.
+pub fn to_dot(graph: &Graph) -> String {
+    let mut out = String::from("digraph remi {\n  node [style=filled, fontname=\"sans-serif\"];\n");
+    let names: HashMap<i64, String> = graph
+        .nodes
+        .iter()
+        .map(|n| (n.id, quote(&format!("{}:{}", n.kind, n.name))))
+        .collect();
+    for n in &graph.nodes {
+        let (shape, color) = match n.kind.as_str() {
+            "repo" => ("box", "#c6dbef"),
+            "file" => ("note", "#f2f2f2"),
+            "tool" => ("ellipse", "#c7e9c0"),
+            _ => ("octagon", "#fcbba1"),
+        };
+        let _ = writeln!(
+            out,
+            "  {} [label={}, shape={shape}, fillcolor=\"{color}\", tooltip=\"sessions: {}\"];",
+            names[&n.id],
+            quote(&n.name),
+            n.sessions
+        );
+    }
+    for e in &graph.edges {
+        let arrow = if e.relation == RELATION_USES {
+            ""
+        } else {
+            ", dir=none, style=dashed"
+        };
+        let _ = writeln!(
+            out,
+            "  {} -> {} [label=\"{}\", penwidth={:.1}{arrow}];",
+            names[&e.source],
+            names[&e.target],
+            e.sessions,
+            1.0 + (e.sessions as f64).ln()
+        );
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use store_sqlite::{GraphEdge, GraphNode};
+
+    fn sample() -> Graph {
+        let node = |id, kind: &str, name: &str| GraphNode {
+            id,
+            kind: kind.to_string(),
+            name: name.to_string(),
+            sessions: 2,
+        };
+        Graph {
+            nodes: vec![
+                node(1, "tool", "edit"),
+                node(2, "file", "src/\"odd\".rs"),
+                node(3, "error", "TypeError"),
+            ],
+            edges: vec![
+                GraphEdge {
+                    source: 1,
+                    target: 2,
+                    relation: "uses".to_string(),
+                    sessions: 2,
+                },
+                GraphEdge {
+                    source: 2,
+                    target: 3,
+                    relation: "co_occurs".to_string(),
+                    sessions: 1,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn dot_quotes_names_and_styles_relations() {
+        let dot = to_dot(&sample());
+        assert!(dot.starts_with("digraph remi {\n"), "{dot}");
+        assert!(dot.contains(r#""file:src/\"odd\".rs" [label="src/\"odd\".rs", shape=note"#));
+        assert!(dot.contains(r#""tool:edit" -> "file:src/\"odd\".rs" [label="2", penwidth=1.7];"#));
+        assert!(dot.contains(r#"[label="1", penwidth=1.0, dir=none, style=dashed];"#));
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[test]
+    fn json_lists_nodes_and_edges_by_id() {
+        let json = to_json(&sample());
+        assert_eq!(json["nodes"][2]["kind"], "error");
+        assert_eq!(json["edges"][0]["source"], 1);
+        assert_eq!(json["edges"][1]["relation"], "co_occurs");
+    }
+}
@@ -0,0 +1,531 @@
+use std::sync::{Mutex, OnceLock};
+
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use core_model::{Event, Message, Provenance, Session};
+use juniper::http::{GraphQLRequest, GraphQLResponse};
+use juniper::{
+    Definition, EmptyMutation, EmptySubscription, FieldError, ID, OperationType, RootNode,
+    graphql_object,
+};
+use serde_json::Value;
+use store_sqlite::SqliteStore;
+
+use crate::redact::Redactor;
+
+const DEFAULT_LIMIT: i32 = 20;
+const MAX_LIMIT: i32 = 500;
+
+type Schema<'a> = RootNode<Query<'a>, EmptyMutation, EmptySubscription>;
+
+// juniper requires resolvers to be Sync and a SQLite connection is not, so
+// the store sits behind a lock for the length of one request.
+struct Db<'a> {
+    store: Mutex<&'a mut SqliteStore>,
+    redactor: Option<&'a Redactor>,
+}
+
+impl Db<'_> {
+    fn with<T>(&self, f: impl FnOnce(&SqliteStore) -> anyhow::Result<T>) -> anyhow::Result<T> {
+        let store = self
+            .store
+            .lock()
+            .map_err(|_| anyhow!("store lock poisoned"))?;
+        f(&store)
+    }
+
+    fn redact(&self, text: &str) -> String {
+        match self.redactor {
+            Some(redactor) => redactor.redact(text).into_owned(),
+            None => text.to_string(),
+        }
+    }
+}
+
+// Runs one GraphQL request (query, operation name and variables) and returns
+// the standard `{"data", "errors"}` response.
+pub fn execute(
+    store: &mut SqliteStore,
+    redactor: Option<&Redactor>,
+    request: &GraphQLRequest,
+) -> GraphQLResponse {
+    let db = Db {
+        store: Mutex::new(store),
+        redactor,
+    };
+    let schema = schema(&db);
+    // juniper panics on a mutation when the schema has none, so other
+    // operations are refused before execution.
+    if let Ok(document) = juniper::parser::parse_document_source(&request.query, &schema.schema)
+        && document.iter().any(|definition| {
+            matches!(definition, Definition::Operation(op) if op.item.operation_type != OperationType::Query)
+        })
+    {
+        return GraphQLResponse::error(FieldError::new(
+            "only queries are supported",
+            juniper::Value::null(),
+        ));
+    }
+    request.execute_sync(&schema, &())
+}
+
+// The schema in GraphQL SDL, for `GET /graphql/schema`.
+pub fn sdl(store: &mut SqliteStore) -> String {
+    let db = Db {
+        store: Mutex::new(store),
+        redactor: None,
+    };
+    schema(&db).as_sdl()
+}
+
+fn schema<'a>(db: &'a Db<'a>) -> Schema<'a> {
+    RootNode::new(Query { db }, EmptyMutation::new(), EmptySubscription::new())
+}
+
+// Limits are capped at MAX_LIMIT; an unset limit means all rows.
+fn page(limit: Option<i32>, offset: Option<i32>) -> anyhow::Result<(usize, Option<usize>)> {
+    let offset = offset.unwrap_or(0);
+    if offset < 0 || limit.is_some_and(|n| n < 0) {
+        anyhow::bail!("limit and offset must be non-negative");
+    }
+    Ok((offset as usize, limit.map(|n| n.min(MAX_LIMIT) as usize)))
+}
+
+pub struct Query<'a> {
+    db: &'a Db<'a>,
+}
+
+#[graphql_object]
+impl<'a> Query<'a> {
+    fn sessions(
+        &self,
+        agent: Option<String>,
+        #[graphql(default = DEFAULT_LIMIT)] limit: Option<i32>,
+        #[graphql(default = 0)] offset: Option<i32>,
+    ) -> anyhow::Result<Vec<SessionNode<'a>>> {
+        let (offset, limit) = page(limit, offset)?;
+        let sessions = self
+            .db
+            .with(|store| store.sessions_page(agent.as_deref(), limit, offset))?;
+        Ok(sessions
+            .into_iter()
+            .map(|session| SessionNode::new(self.db, session))
+            .collect())
+    }
+
+    fn session(&self, id: ID) -> anyhow::Result<Option<SessionNode<'a>>> {
+        let session = self.db.with(|store| store.get_session(&id))?;
+        Ok(session.map(|session| SessionNode::new(self.db, session)))
+    }
+
+    fn message(&self, id: ID) -> anyhow::Result<Option<MessageNode<'a>>> {
+        let message = self.db.with(|store| store.get_message(&id))?;
+        Ok(message.map(|message| MessageNode {
+            db: self.db,
+            message,
+        }))
+    }
+}
+
+pub struct SessionNode<'a> {
+    db: &'a Db<'a>,
+    session: Session,
+    // Messages are loaded once per session and only when a field needs them.
+    messages: OnceLock<Vec<Message>>,
+}
+
+impl<'a> SessionNode<'a> {
+    fn new(db: &'a Db<'a>, session: Session) -> Self {
+        Self {
+            db,
+            session,
+            messages: OnceLock::new(),
+        }
+    }
+
+    fn all_messages(&self) -> anyhow::Result<&[Message]> {
+        if let Some(messages) = self.messages.get() {
+            return Ok(messages);
+        }
+        let loaded = self
+            .db
+            .with(|store| store.get_session_messages(&self.session.id))?;
+        Ok(self.messages.get_or_init(|| loaded))
+    }
+
+    fn node(&self, message: &Message) -> MessageNode<'a> {
+        MessageNode {
+            db: self.db,
+            message: message.clone(),
+        }
+    }
+}
+
+#[graphql_object(name = "Session")]
+impl<'a> SessionNode<'a> {
+    fn id(&self) -> ID {
+        ID::new(&self.session.id)
+    }
+
+    fn agent(&self) -> &str {
+        self.session.agent.as_str()
+    }
+
+    fn title(&self) -> String {
+        self.db.redact(&self.session.title)
+    }
+
+    fn source_ref(&self) -> &str {
+        &self.session.source_ref
+    }
+
+    fn created_at(&self) -> DateTime<Utc> {
+        self.session.created_at
+    }
+
+    fn updated_at(&self) -> DateTime<Utc> {
+        self.session.updated_at
+    }
+
+    fn message_count(&self) -> anyhow::Result<i32> {
+        Ok(self.all_messages()?.len() as i32)
+    }
+
+    fn first_message(&self) -> anyhow::Result<Option<MessageNode<'a>>> {
+        Ok(self.all_messages()?.first().map(|m| self.node(m)))
+    }
+
+    fn last_message(&self) -> anyhow::Result<Option<MessageNode<'a>>> {
+        Ok(self.all_messages()?.last().map(|m| self.node(m)))
+    }
+
+    fn messages(
+        &self,
+        role: Option<String>,
+        limit: Option<i32>,
+        #[graphql(default = 0)] offset: Option<i32>,
+    ) -> anyhow::Result<Vec<MessageNode<'a>>> {
+        let (offset, limit) = page(limit, offset)?;
+        Ok(self
+            .all_messages()?
+            .iter()
+            .filter(|m| role.as_deref().is_none_or(|r| m.role == r))
+            .skip(offset)
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|m| self.node(m))
+            .collect())
+    }
+
+    fn events(&self, kind: Option<String>, limit: Option<i32>) -> anyhow::Result<Vec<EventNode>> {
+        let (_, limit) = page(limit, None)?;
+        let events = self
+            .db
+            .with(|store| store.get_session_events(&self.session.id))?;
+        Ok(events
+            .into_iter()
+            .filter(|e| kind.as_deref().is_none_or(|k| e.kind == k))
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|mut event| {
+                if let Some(redactor) = self.db.redactor {
+                    redact_json(redactor, &mut event.payload);
+                }
+                EventNode(event)
+            })
+            .collect())
+    }
+
+    fn provenance(&self) -> anyhow::Result<Vec<ProvenanceNode>> {
+        let provenance = self
+            .db
+            .with(|store| store.get_provenance_for_session(&self.session.id))?;
+        Ok(provenance.into_iter().map(ProvenanceNode).collect())
+    }
+}
+
+pub struct MessageNode<'a> {
+    db: &'a Db<'a>,
+    message: Message,
+}
+
+#[graphql_object(name = "Message")]
+impl<'a> MessageNode<'a> {
+    fn id(&self) -> ID {
+        ID::new(&self.message.id)
+    }
+
+    fn session_id(&self) -> ID {
+        ID::new(&self.message.session_id)
+    }
+
+    fn role(&self) -> &str {
+        &self.message.role
+    }
+
+    fn content(&self) -> String {
+        self.db.redact(&self.message.content)
+    }
+
+    fn ts(&self) -> DateTime<Utc> {
+        self.message.ts
+    }
+
+    fn session(&self) -> anyhow::Result<Option<SessionNode<'a>>> {
+        let session = self
+            .db
+            .with(|store| store.get_session(&self.message.session_id))?;
+        Ok(session.map(|session| SessionNode::new(self.db, session)))
+    }
+}
+
+pub struct EventNode(Event);
+
+#[graphql_object(name = "Event")]
+impl EventNode {
+    fn id(&self) -> ID {
+        ID::new(&self.0.id)
+    }
+
+    fn session_id(&self) -> ID {
+        ID::new(&self.0.session_id)
+    }
+
+    fn kind(&self) -> &str {
+        &self.0.kind
+    }
+
+    #[graphql(description = "The event payload, encoded as a JSON string.")]
+    fn payload(&self) -> String {
+        self.0.payload.to_string()
+    }
+
+    fn ts(&self) -> DateTime<Utc> {
+        self.0.ts
+    }
+}
+
+pub struct ProvenanceNode(Provenance);
+
+#[graphql_object(name = "Provenance")]
+impl ProvenanceNode {
+    fn id(&self) -> ID {
+        ID::new(&self.0.id)
+    }
+
+    fn entity_type(&self) -> &str {
+        &self.0.entity_type
+    }
+
+    fn entity_id(&self) -> ID {
+        ID::new(&self.0.entity_id)
+    }
+
+    fn agent(&self) -> &str {
+        self.0.agent.as_str()
+    }
+
+    fn source_path(&self) -> &str {
+        &self.0.source_path
+    }
+
+    fn source_id(&self) -> &str {
+        &self.0.source_id
+    }
+
+    fn repo_remote(&self) -> Option<&str> {
+        self.0.repo.as_ref().map(|r| r.remote.as_str())
+    }
+
+    fn repo_path(&self) -> Option<&str> {
+        self.0.repo.as_ref().map(|r| r.path.as_str())
+    }
+}
+
+fn redact_json(redactor: &Redactor, value: &mut Value) {
+    match value {
+        Value::String(s) => *s = redactor.redact(s).into_owned(),
+        Value::Array(items) => items.iter_mut().for_each(|v| redact_json(redactor, v)),
+        Value::Object(map) => map.values_mut().for_each(|v| redact_json(redactor, v)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use core_model::{AgentKind, NormalizedBatch};
+    use serde_json::json;
+
+    fn store() -> SqliteStore {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let at = Utc.with_ymd_and_hms(2025, 5, 1, 9, 0, 0).unwrap();
+        for (id, agent, offset) in [("s1", AgentKind::Claude, 0), ("s2", AgentKind::Codex, 1)] {
+            store
+                .save_batch(&NormalizedBatch {
+                    sessions: vec![Session {
+                        id: id.to_string(),
+                        agent,
+                        source_ref: format!("/tmp/{id}.jsonl"),
+                        title: format!("{id} title"),
+                        created_at: at + Duration::hours(offset),
+                        updated_at: at + Duration::hours(offset),
+                    }],
+                    messages: ["user", "assistant", "user"]
+                        .iter()
+                        .enumerate()
+                        .map(|(i, role)| Message {
+                            id: format!("{id}-m{i}"),
+                            session_id: id.to_string(),
+                            role: role.to_string(),
+                            content: format!("{id} message {i}"),
+                            ts: at + Duration::hours(offset) + Duration::seconds(i as i64),
+                        })
+                        .collect(),
+                    events: vec![Event {
+                        id: format!("{id}-e0"),
+                        session_id: id.to_string(),
+                        kind: "tool_call".to_string(),
+                        payload: json!({ "name": "Read" }),
+                        ts: at,
+                    }],
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        store
+    }
+
+    fn run(store: &mut SqliteStore, query: &str, variables: Option<Value>) -> Value {
+        let request = GraphQLRequest::new(
+            query.to_string(),
+            None,
+            variables.map(|v| serde_json::from_value(v).unwrap()),
+        );
+        serde_json::to_value(execute(store, None, &request)).unwrap()
+    }
+
+    #[test]
+    fn sessions_with_first_last_and_counts_in_one_query() {
+        let mut store = store();
+        let response = run(
+            &mut store,
+            r#"query Overview {
+              sessions(limit: 5) {
+                id
+                agent
+                messageCount
+                first: firstMessage { content }
+                lastMessage { role ts }
+                prompts: messages(role: "user", limit: 1) { id }
+                events(kind: "tool_call") { kind payload }
+              }
+              session(id: "s1") { title }
+              missing: session(id: "nope") { id }
+              message(id: "s2-m1") { content session { id } }
+            }"#,
+            None,
+        );
+        let data = &response["data"];
+        assert_eq!(response.get("errors"), None);
+        assert_eq!(data["sessions"][0]["id"], "s2");
+        assert_eq!(data["sessions"][0]["messageCount"], 3);
+        assert_eq!(data["sessions"][0]["first"]["content"], "s2 message 0");
+        assert_eq!(data["sessions"][0]["lastMessage"]["role"], "user");
+        assert_eq!(data["sessions"][0]["prompts"], json!([{ "id": "s2-m0" }]));
+        let payload = data["sessions"][1]["events"][0]["payload"]
+            .as_str()
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(payload).unwrap()["name"],
+            "Read"
+        );
+        assert_eq!(data["session"]["title"], "s1 title");
+        assert_eq!(data["missing"], Value::Null);
+        assert_eq!(data["message"]["session"]["id"], "s2");
+    }
+
+    #[test]
+    fn variables_fragments_and_directives() {
+        let mut store = store();
+        let response = run(
+            &mut store,
+            r#"query ByAgent($agent: String, $withCount: Boolean!) {
+              sessions(agent: $agent) {
+                ...Summary
+                messageCount @include(if: $withCount)
+                ... on Session { lastMessage { id } }
+              }
+            }
+            fragment Summary on Session { id title }"#,
+            Some(json!({ "agent": "codex", "withCount": false })),
+        );
+        assert_eq!(response.get("errors"), None);
+        assert_eq!(
+            response["data"]["sessions"],
+            json!([{ "id": "s2", "title": "s2 title", "lastMessage": { "id": "s2-m2" } }])
+        );
+    }
+
+    #[test]
+    fn sessions_are_paged_by_the_store() {
+        let mut store = store();
+        for (query, expected) in [
+            ("{ sessions(limit: 1) { id } }", json!([{ "id": "s2" }])),
+            (
+                "{ sessions(limit: 1, offset: 1) { id } }",
+                json!([{ "id": "s1" }]),
+            ),
+            ("{ sessions(offset: 2) { id } }", json!([])),
+            (
+                r#"{ sessions(agent: "claude") { id } }"#,
+                json!([{ "id": "s1" }]),
+            ),
+            (
+                r#"{ sessions(agent: "claude", offset: 1) { id } }"#,
+                json!([]),
+            ),
+        ] {
+            let response = run(&mut store, query, None);
+            assert_eq!(response["data"]["sessions"], expected, "{query}");
+        }
+    }
+
+    #[test]
+    fn introspection_describes_the_schema() {
+        let mut store = store();
+        let response = run(
+            &mut store,
+            r#"{ __schema { queryType { name } } __type(name: "Session") { fields { name } } }"#,
+            None,
+        );
+        assert_eq!(response["data"]["__schema"]["queryType"]["name"], "Query");
+        let fields: Vec<&str> = response["data"]["__type"]["fields"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| f["name"].as_str().unwrap())
+            .collect();
+        assert!(fields.contains(&"messageCount") && fields.contains(&"provenance"));
+        let sdl = sdl(&mut store);
+        assert!(sdl.contains("type Session"), "{sdl}");
+    }
+
+    #[test]
+    fn invalid_queries_report_errors() {
+        let mut store = store();
+        for (query, expected) in [
+            ("{ sessions { nope } }", "nope"),
+            ("{ sessions }", "sessions"),
+            ("{ session { id } }", "id"),
+            ("{ sessions(limit: -1) { id } }", "non-negative"),
+            ("mutation { x }", "only queries"),
+            ("subscription { x }", "only queries"),
+            ("{ sessions { id } ", "Unexpected end"),
+        ] {
+            let response = run(&mut store, query, None);
+            let message = response["errors"][0]["message"].as_str().unwrap();
+            assert!(message.contains(expected), "{query}: {message}");
+        }
+    }
+}
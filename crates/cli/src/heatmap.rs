@@ -0,0 +1,269 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Days, NaiveDate};
+use owo_colors::OwoColorize;
+
+const WEEKDAY_LABELS: [&str; 7] = ["", "Mon", "", "Wed", "", "Fri", ""];
+const LEVEL_GLYPHS: [char; 5] = ['·', '░', '▒', '▓', '█'];
+const LEVEL_COLORS: [(u8, u8, u8); 5] = [
+    (235, 237, 240),
+    (155, 233, 168),
+    (64, 196, 99),
+    (48, 161, 78),
+    (33, 110, 57),
+];
+const CELL: u32 = 11;
+const GAP: u32 = 2;
+const LEFT: u32 = 30;
+const TOP: u32 = 20;
+
+// Weeks start on Sunday like GitHub's contribution graph, so the grid may
+// begin a few days before `from`.
+pub struct Heatmap {
+    from: NaiveDate,
+    to: NaiveDate,
+    counts: BTreeMap<NaiveDate, usize>,
+    max: usize,
+}
+
+impl Heatmap {
+    pub fn new(from: NaiveDate, to: NaiveDate, counts: BTreeMap<NaiveDate, usize>) -> Self {
+        let max = counts.values().copied().max().unwrap_or(0);
+        Self {
+            from,
+            to,
+            counts,
+            max,
+        }
+    }
+
+    // A rolling year ending today, or one calendar year.
+    pub fn range(year: Option<i32>, today: NaiveDate) -> anyhow::Result<(NaiveDate, NaiveDate)> {
+        match year {
+            Some(year) => {
+                let from = NaiveDate::from_ymd_opt(year, 1, 1)
+                    .ok_or_else(|| anyhow::anyhow!("invalid --year {year}"))?;
+                let to = NaiveDate::from_ymd_opt(year, 12, 31)
+                    .ok_or_else(|| anyhow::anyhow!("invalid --year {year}"))?;
+                Ok((from, to))
+            }
+            None => Ok((today - Days::new(364), today)),
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    pub fn active_days(&self) -> usize {
+        self.counts.values().filter(|c| **c > 0).count()
+    }
+
+    // Level 0 is reserved for empty days; the rest split the busiest day's
+    // count into quarters.
+    fn level(&self, count: usize) -> usize {
+        if count == 0 || self.max == 0 {
+            return 0;
+        }
+        (count * 4).div_ceil(self.max).clamp(1, 4)
+    }
+
+    fn grid_start(&self) -> NaiveDate {
+        self.from - Days::new(self.from.weekday().num_days_from_sunday() as u64)
+    }
+
+    fn weeks(&self) -> usize {
+        ((self.to - self.grid_start()).num_days() as usize) / 7 + 1
+    }
+
+    fn cell(&self, week: usize, weekday: usize) -> Option<NaiveDate> {
+        let day = self.grid_start() + Days::new((week * 7 + weekday) as u64);
+        (day >= self.from && day <= self.to).then_some(day)
+    }
+
+    fn count(&self, day: NaiveDate) -> usize {
+        self.counts.get(&day).copied().unwrap_or(0)
+    }
+
+    // Month labels sit above the first week containing the 1st of a month.
+    fn month_starts(&self) -> Vec<(usize, &'static str)> {
+        (0..self.weeks())
+            .filter_map(|week| {
+                let first = (0..7).filter_map(|d| self.cell(week, d)).find(|day| {
+                    day.day() == 1 || (week == 0 && *day == self.from && day.day() <= 7)
+                })?;
+                Some((week, month_name(first.month())))
+            })
+            .collect()
+    }
+
+    pub fn render_terminal(&self, use_color: bool) -> String {
+        let weeks = self.weeks();
+        let mut header = vec![' '; weeks * 2];
+        let mut next_free = 0;
+        for (week, name) in self.month_starts() {
+            let col = week * 2;
+            if col < next_free || col + name.len() > header.len() {
+                continue;
+            }
+            for (i, ch) in name.chars().enumerate() {
+                header[col + i] = ch;
+            }
+            next_free = col + name.len() + 1;
+        }
+        let mut out = format!("    {}\n", header.iter().collect::<String>().trim_end());
+        for (weekday, label) in WEEKDAY_LABELS.iter().enumerate() {
+            out.push_str(&format!("{label:<4}"));
+            for week in 0..weeks {
+                let Some(day) = self.cell(week, weekday) else {
+                    out.push_str("  ");
+                    continue;
+                };
+                let level = self.level(self.count(day));
+                let glyph = if use_color {
+                    '■'
+                } else {
+                    LEVEL_GLYPHS[level]
+                };
+                if use_color {
+                    let (r, g, b) = LEVEL_COLORS[level];
+                    out.push_str(&glyph.truecolor(r, g, b).to_string());
+                } else {
+                    out.push(glyph);
+                }
+                out.push(' ');
+            }
+            out.truncate(out.trim_end_matches(' ').len());
+            out.push('\n');
+        }
+        let legend: String = (0..5)
+            .map(|level| {
+                if use_color {
+                    let (r, g, b) = LEVEL_COLORS[level];
+                    format!("{} ", '■'.truecolor(r, g, b))
+                } else {
+                    format!("{} ", LEVEL_GLYPHS[level])
+                }
+            })
+            .collect();
+        out.push_str(&format!(
+            "\n{} messages on {} days ({} to {})   less {legend}more\n",
+            self.total(),
+            self.active_days(),
+            self.from,
+            self.to
+        ));
+        out
+    }
+
+    pub fn render_svg(&self) -> String {
+        let weeks = self.weeks() as u32;
+        let width = LEFT + weeks * (CELL + GAP);
+        let height = TOP + 7 * (CELL + GAP) + 20;
+        let mut svg = format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="sans-serif" font-size="9" fill="#57606a">"##
+        );
+        svg.push('\n');
+        for (week, name) in self.month_starts() {
+            let x = LEFT + week as u32 * (CELL + GAP);
+            svg.push_str(&format!(r#"<text x="{x}" y="{}">{name}</text>"#, TOP - 6));
+            svg.push('\n');
+        }
+        for (weekday, label) in WEEKDAY_LABELS.iter().enumerate() {
+            if label.is_empty() {
+                continue;
+            }
+            let y = TOP + weekday as u32 * (CELL + GAP) + CELL - 2;
+            svg.push_str(&format!(r#"<text x="0" y="{y}">{label}</text>"#));
+            svg.push('\n');
+        }
+        for week in 0..weeks as usize {
+            for weekday in 0..7 {
+                let Some(day) = self.cell(week, weekday) else {
+                    continue;
+                };
+                let count = self.count(day);
+                let (r, g, b) = LEVEL_COLORS[self.level(count)];
+                let x = LEFT + week as u32 * (CELL + GAP);
+                let y = TOP + weekday as u32 * (CELL + GAP);
+                let noun = if count == 1 { "message" } else { "messages" };
+                svg.push_str(&format!(
+                    r##"<rect x="{x}" y="{y}" width="{CELL}" height="{CELL}" rx="2" fill="#{r:02x}{g:02x}{b:02x}"><title>{day}: {count} {noun}</title></rect>"##
+                ));
+                svg.push('\n');
+            }
+        }
+        svg.push_str(&format!(
+            r#"<text x="{LEFT}" y="{}">{} messages on {} days</text>"#,
+            height - 4,
+            self.total(),
+            self.active_days()
+        ));
+        svg.push_str("\n</svg>\n");
+        svg
+    }
+
+    pub fn render_html(&self, title: &str) -> String {
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n</head>\n<body style=\"font-family: sans-serif\">\n<h1>{title}</h1>\n{}</body>\n</html>\n",
+            self.render_svg()
+        )
+    }
+}
+
+fn month_name(month: u32) -> &'static str {
+    [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ][(month - 1) as usize]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, m, d).unwrap()
+    }
+
+    #[test]
+    fn grid_aligns_to_sundays_and_scales_levels() {
+        let (from, to) = Heatmap::range(Some(2025), day(6, 1)).unwrap();
+        let heatmap = Heatmap::new(
+            from,
+            to,
+            BTreeMap::from([(day(1, 1), 1), (day(1, 2), 8), (day(3, 4), 3)]),
+        );
+        // 2025-01-01 is a Wednesday.
+        assert_eq!(
+            heatmap.grid_start(),
+            NaiveDate::from_ymd_opt(2024, 12, 29).unwrap()
+        );
+        assert_eq!(heatmap.cell(0, 0), None);
+        assert_eq!(heatmap.cell(0, 3), Some(day(1, 1)));
+        assert_eq!(heatmap.weeks(), 53);
+        assert_eq!([0, 1, 3, 8].map(|c| heatmap.level(c)), [0, 1, 2, 4]);
+        assert_eq!(heatmap.total(), 12);
+        assert_eq!(heatmap.active_days(), 3);
+        assert_eq!(heatmap.month_starts()[..2], [(0, "Jan"), (4, "Feb")]);
+
+        let text = heatmap.render_terminal(false);
+        let rows: Vec<&str> = text.lines().collect();
+        assert!(rows[0].trim_start().starts_with("Jan"));
+        assert!(rows[4].starts_with("Wed ░ "));
+        assert!(rows[5].starts_with("    █ "));
+        assert!(text.contains("12 messages on 3 days (2025-01-01 to 2025-12-31)"));
+
+        let svg = heatmap.render_svg();
+        assert_eq!(svg.matches("<rect").count(), 365);
+        assert!(svg.contains("<title>2025-01-02: 8 messages</title>"));
+        assert!(svg.contains("<title>2025-01-01: 1 message</title>"));
+    }
+
+    #[test]
+    fn rolling_range_covers_a_year() {
+        let (from, to) = Heatmap::range(None, day(6, 1)).unwrap();
+        assert_eq!(to, day(6, 1));
+        assert_eq!((to - from).num_days(), 364);
+        assert!(Heatmap::range(Some(300_000), day(6, 1)).is_err());
+    }
+}
@@ -0,0 +1,308 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::Context;
+use core_model::{
+    AgentAdapter, AgentKind, ArchiveCapability, NativeRecord, NormalizedBatch, ScanReport,
+    SourceContext,
+};
+use rhai::{AST, Dynamic, Engine, EvalAltResult, Scope};
+
+// Wraps an adapter so every normalized batch is edited by a Rhai script
+// before it is stored. The script runs in-process with `agent` and `batch`
+// in scope; whatever `batch` holds when it ends is saved.
+pub struct Hooked {
+    inner: Box<dyn AgentAdapter>,
+    script: Script,
+}
+
+impl Hooked {
+    pub fn new(inner: Box<dyn AgentAdapter>, script: Script) -> Self {
+        Self { inner, script }
+    }
+}
+
+impl AgentAdapter for Hooked {
+    fn kind(&self) -> AgentKind {
+        self.inner.kind()
+    }
+
+    fn discover_source_paths(&self, ctx: &SourceContext) -> anyhow::Result<Vec<String>> {
+        self.inner.discover_source_paths(ctx)
+    }
+
+    fn scan_changes_since(
+        &self,
+        source_paths: &[String],
+        cursor: Option<&str>,
+    ) -> anyhow::Result<Vec<NativeRecord>> {
+        self.inner.scan_changes_since(source_paths, cursor)
+    }
+
+    fn normalize(&self, records: &[NativeRecord]) -> anyhow::Result<NormalizedBatch> {
+        let batch = self.inner.normalize(records)?;
+        if batch.sessions.is_empty() && batch.messages.is_empty() {
+            return Ok(batch);
+        }
+        self.script.run(self.kind(), &batch)
+    }
+
+    fn checkpoint_cursor(&self, records: &[NativeRecord]) -> Option<String> {
+        self.inner.checkpoint_cursor(records)
+    }
+
+    fn archive_capability(&self) -> ArchiveCapability {
+        self.inner.archive_capability()
+    }
+
+    fn scan_report(&self) -> Option<ScanReport> {
+        self.inner.scan_report()
+    }
+
+    fn source_label(&self) -> String {
+        self.inner.source_label()
+    }
+
+    fn estimate_sessions(&self, source_paths: &[String]) -> usize {
+        self.inner.estimate_sessions(source_paths)
+    }
+
+    fn scan_groups(&self, source_paths: &[String]) -> Vec<Vec<String>> {
+        self.inner.scan_groups(source_paths)
+    }
+
+    fn tracks_file_state(&self) -> bool {
+        self.inner.tracks_file_state()
+    }
+}
+
+// Bounds on one hook run, so a runaway script fails the sync instead of
+// hanging it or exhausting memory. Strings and arrays leave room for the
+// largest batches adapters produce.
+const MAX_OPERATIONS: u64 = 50_000_000;
+const MAX_CALL_LEVELS: usize = 64;
+const MAX_EXPR_DEPTH: usize = 64;
+const MAX_FN_EXPR_DEPTH: usize = 32;
+const MAX_STRING_SIZE: usize = 64 * 1024 * 1024;
+const MAX_ARRAY_SIZE: usize = 1_000_000;
+const MAX_MAP_SIZE: usize = 10_000;
+const MAX_MODULES: usize = 16;
+
+// A compiled hook script. Compiling once up front means a syntax error
+// stops the command before anything is scanned, and each batch only pays
+// for evaluation.
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    name: String,
+}
+
+impl Script {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read hook {}", path.display()))?;
+        Self::compile(&source, &path.display().to_string())
+    }
+
+    pub fn compile(source: &str, name: &str) -> anyhow::Result<Self> {
+        let mut engine = Engine::new();
+        engine
+            .set_max_operations(MAX_OPERATIONS)
+            .set_max_call_levels(MAX_CALL_LEVELS)
+            .set_max_expr_depths(MAX_EXPR_DEPTH, MAX_FN_EXPR_DEPTH)
+            .set_max_string_size(MAX_STRING_SIZE)
+            .set_max_array_size(MAX_ARRAY_SIZE)
+            .set_max_map_size(MAX_MAP_SIZE)
+            .set_max_modules(MAX_MODULES);
+        // stdout carries command output such as --json documents, so
+        // `print` and `debug` go to the log instead.
+        engine.on_print(|text| tracing::info!(target: "remi::hook", "{text}"));
+        engine.on_debug(|text, _, pos| tracing::debug!(target: "remi::hook", %pos, "{text}"));
+        let ast = engine
+            .compile(source)
+            .map_err(|e| anyhow::anyhow!("hook {name} does not compile: {e}"))?;
+        Ok(Self {
+            engine,
+            ast,
+            name: name.to_string(),
+        })
+    }
+
+    fn run(&self, agent: AgentKind, batch: &NormalizedBatch) -> anyhow::Result<NormalizedBatch> {
+        let failed = |e: Box<EvalAltResult>| match *e {
+            EvalAltResult::ErrorTooManyOperations(_)
+            | EvalAltResult::ErrorStackOverflow(_)
+            | EvalAltResult::ErrorDataTooLarge(..)
+            | EvalAltResult::ErrorTooManyModules(_) => {
+                anyhow::anyhow!("{agent} hook {} hit a resource limit: {e}", self.name)
+            }
+            _ => anyhow::anyhow!("{agent} hook {} failed: {e}", self.name),
+        };
+        let mut input = rhai::serde::to_dynamic(batch).map_err(failed)?;
+        // Present even when empty, so scripts can push tags onto it.
+        if let Some(mut map) = input.write_lock::<rhai::Map>() {
+            map.entry("session_meta".into())
+                .or_insert_with(|| rhai::Array::new().into());
+        }
+        let mut scope = Scope::new();
+        scope.push_constant("agent", agent.as_str().to_string());
+        scope.push_dynamic("batch", input);
+        self.engine
+            .run_ast_with_scope(&mut scope, &self.ast)
+            .map_err(failed)?;
+        let output: Dynamic = scope
+            .get_value("batch")
+            .with_context(|| format!("{agent} hook {} removed `batch`", self.name))?;
+        let edited: NormalizedBatch = rhai::serde::from_dynamic(&output).map_err(|e| {
+            anyhow::anyhow!("{agent} hook {} left an invalid batch: {e}", self.name)
+        })?;
+        Ok(prune(edited))
+    }
+}
+
+// Dropped messages and sessions take their provenance, events, artifacts and
+// messages with them, so the store never sees dangling references.
+fn prune(mut batch: NormalizedBatch) -> NormalizedBatch {
+    let sessions: HashSet<String> = batch.sessions.iter().map(|s| s.id.clone()).collect();
+    batch.messages.retain(|m| sessions.contains(&m.session_id));
+    batch.events.retain(|e| sessions.contains(&e.session_id));
+    batch.artifacts.retain(|a| sessions.contains(&a.session_id));
+    batch
+        .session_meta
+        .retain(|m| sessions.contains(&m.session_id));
+    let messages: HashSet<&str> = batch.messages.iter().map(|m| m.id.as_str()).collect();
+    batch
+        .provenance
+        .retain(|p| p.entity_type != "message" || messages.contains(p.entity_id.as_str()));
+    batch
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use core_model::{Artifact, Event, Message, Provenance, Session};
+
+    fn batch() -> NormalizedBatch {
+        let now = Utc.timestamp_opt(1_746_091_800, 123_456_789).unwrap();
+        let message = |id: &str, content: &str| Message {
+            id: id.to_string(),
+            session_id: "s1".to_string(),
+            role: "user".to_string(),
+            content: content.to_string(),
+            ts: now,
+        };
+        NormalizedBatch {
+            sessions: vec![Session {
+                id: "s1".to_string(),
+                agent: AgentKind::Codex,
+                source_ref: "/tmp/s1.jsonl".to_string(),
+                title: "untitled".to_string(),
+                created_at: now,
+                updated_at: now,
+            }],
+            messages: vec![message("m1", "keep me"), message("m2", "heartbeat")],
+            provenance: ["m1", "m2"]
+                .iter()
+                .map(|id| Provenance {
+                    id: format!("p_{id}"),
+                    entity_type: "message".to_string(),
+                    entity_id: id.to_string(),
+                    agent: AgentKind::Codex,
+                    source_path: "/tmp/s1.jsonl".to_string(),
+                    source_id: id.to_string(),
+                    workspace: None,
+                    repo: None,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn run(source: &str) -> anyhow::Result<NormalizedBatch> {
+        Script::compile(source, "test.rhai")?.run(AgentKind::Codex, &batch())
+    }
+
+    #[test]
+    fn hook_script_edits_the_batch() {
+        let out = run(r#"
+            batch.sessions = batch.sessions.map(|s| {
+                s.title = `${agent}: ${s.title}`;
+                s
+            });
+            batch.messages.retain(|m| m.content != "heartbeat");
+            for s in batch.sessions {
+                batch.session_meta.push(#{ session_id: s.id, meta: #{ tags: ["work"] } });
+            }
+            print("edited");
+        "#)
+        .unwrap();
+        assert_eq!(out.sessions[0].title, "codex: untitled");
+        assert_eq!(out.sessions[0].created_at, batch().sessions[0].created_at);
+        assert_eq!(out.messages.len(), 1);
+        assert_eq!(out.provenance.len(), 1);
+        assert_eq!(out.session_meta[0].meta["tags"][0], "work");
+
+        let untouched = run("").unwrap();
+        assert_eq!(untouched.messages.len(), 2);
+        assert!(untouched.session_meta.is_empty());
+    }
+
+    #[test]
+    fn dropped_messages_lose_their_provenance() {
+        let mut edited = batch();
+        edited.messages.retain(|m| m.content != "heartbeat");
+        let out = prune(edited);
+        assert_eq!(out.provenance.len(), 1);
+        assert_eq!(out.provenance[0].entity_id, "m1");
+
+        let mut edited = batch();
+        edited.events.push(Event {
+            id: "e1".to_string(),
+            session_id: "s1".to_string(),
+            kind: "tool_call".to_string(),
+            payload: serde_json::Value::Null,
+            ts: edited.sessions[0].created_at,
+        });
+        edited.artifacts.push(Artifact {
+            id: "a1".to_string(),
+            session_id: "s1".to_string(),
+            path: "src/lib.rs".to_string(),
+            checksum: String::new(),
+            metadata: serde_json::Value::Null,
+        });
+        let kept = prune(edited.clone());
+        assert_eq!((kept.events.len(), kept.artifacts.len()), (1, 1));
+        edited.sessions.clear();
+        let out = prune(edited);
+        assert!(out.messages.is_empty() && out.provenance.is_empty());
+        assert!(out.events.is_empty() && out.artifacts.is_empty());
+    }
+
+    #[test]
+    fn failing_hooks_stop_the_sync() {
+        let err = run(r#"throw "nope";"#).unwrap_err().to_string();
+        assert!(err.contains("nope") && err.contains("test.rhai"), "{err}");
+        let err = run("batch = 42;").unwrap_err().to_string();
+        assert!(err.contains("invalid batch"), "{err}");
+        let err = run("batch.sessions = [").unwrap_err().to_string();
+        assert!(err.contains("does not compile"), "{err}");
+        assert!(Script::load(Path::new("/nonexistent/hook.rhai")).is_err());
+    }
+
+    #[test]
+    fn runaway_hooks_hit_a_limit() {
+        for source in [
+            "loop {}",
+            "fn deeper(n) { deeper(n + 1) } deeper(0);",
+            r#"let s = "x"; loop { s += s; }"#,
+            "let a = [0]; loop { a += a; }",
+        ] {
+            let err = run(source).unwrap_err().to_string();
+            assert!(err.contains("hit a resource limit"), "{source}: {err}");
+        }
+        let deep = format!("{}1{}", "(".repeat(200), ")".repeat(200));
+        let err = run(&deep).unwrap_err().to_string();
+        assert!(err.contains("does not compile"), "{err}");
+    }
+}
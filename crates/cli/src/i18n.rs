@@ -0,0 +1,173 @@
+use std::sync::OnceLock;
+
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue};
+use unic_langid::LanguageIdentifier;
+
+pub const LOCALE_ENV: &str = "REMI_LOCALE";
+pub const DEFAULT_LOCALE: &str = "en";
+
+// The first entry is the fallback for ids a translation does not define.
+const LOCALES: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+];
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+struct Catalog {
+    locale: &'static str,
+    bundles: Vec<FluentBundle<FluentResource>>,
+}
+
+impl Catalog {
+    fn new(locale: &'static str) -> Self {
+        let bundles = LOCALES
+            .iter()
+            .filter(|(name, _)| *name == locale || *name == DEFAULT_LOCALE)
+            .rev()
+            .map(|(name, source)| bundle(name, source))
+            .collect();
+        Self { locale, bundles }
+    }
+
+    fn format(&self, id: &str, args: Option<&FluentArgs>) -> String {
+        for bundle in &self.bundles {
+            let Some(pattern) = bundle.get_message(id).and_then(|m| m.value()) else {
+                continue;
+            };
+            let mut errors = Vec::new();
+            return bundle
+                .format_pattern(pattern, args, &mut errors)
+                .into_owned();
+        }
+        id.to_string()
+    }
+}
+
+fn bundle(name: &str, source: &str) -> FluentBundle<FluentResource> {
+    let langid: LanguageIdentifier = name.parse().expect("valid locale name");
+    let resource = FluentResource::try_new(source.to_string())
+        .unwrap_or_else(|(_, errors)| panic!("{name}.ftl does not parse: {errors:?}"));
+    let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+    // Isolation marks would show up as stray characters in terminals.
+    bundle.set_use_isolating(false);
+    bundle
+        .add_resource(resource)
+        .unwrap_or_else(|errors| panic!("{name}.ftl has duplicate ids: {errors:?}"));
+    bundle
+}
+
+pub fn supported() -> Vec<&'static str> {
+    LOCALES.iter().map(|(name, _)| *name).collect()
+}
+
+// `REMI_LOCALE` and the config value must name a supported locale; the POSIX
+// variables are only a hint and fall back to English.
+pub fn init(configured: Option<&str>) -> anyhow::Result<()> {
+    let explicit = std::env::var(LOCALE_ENV)
+        .ok()
+        .filter(|v| !v.is_empty())
+        .or_else(|| configured.map(str::to_string));
+    let locale = match explicit {
+        Some(value) => match_locale(&value).ok_or_else(|| {
+            anyhow::anyhow!(
+                "unsupported locale {value:?}; choose one of {}",
+                supported().join(", ")
+            )
+        })?,
+        None => ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+            .and_then(|value| match_locale(&value))
+            .unwrap_or(DEFAULT_LOCALE),
+    };
+    let _ = CATALOG.set(Catalog::new(locale));
+    Ok(())
+}
+
+// Accepts `de`, `de-AT`, and POSIX forms like `de_DE.UTF-8`.
+fn match_locale(value: &str) -> Option<&'static str> {
+    let tag = value.split(['.', '@']).next()?.replace('_', "-");
+    if tag == "C" || tag == "POSIX" {
+        return Some(DEFAULT_LOCALE);
+    }
+    let langid: LanguageIdentifier = tag.parse().ok()?;
+    LOCALES
+        .iter()
+        .map(|(name, _)| *name)
+        .find(|name| *name == langid.language.as_str())
+}
+
+fn catalog() -> &'static Catalog {
+    CATALOG.get_or_init(|| Catalog::new(DEFAULT_LOCALE))
+}
+
+pub fn locale() -> &'static str {
+    catalog().locale
+}
+
+pub fn text(id: &str) -> String {
+    catalog().format(id, None)
+}
+
+pub fn text_with(id: &str, args: &[(&str, FluentValue)]) -> String {
+    let mut fluent_args = FluentArgs::new();
+    for (name, value) in args {
+        fluent_args.set(*name, value.clone());
+    }
+    catalog().format(id, Some(&fluent_args))
+}
+
+pub fn count(id: &str, count: usize) -> String {
+    text_with(id, &[("count", count.into())])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locales_resolve_from_tags_and_posix_names() {
+        assert_eq!(match_locale("de"), Some("de"));
+        assert_eq!(match_locale("de_DE.UTF-8"), Some("de"));
+        assert_eq!(match_locale("de-AT"), Some("de"));
+        assert_eq!(match_locale("en_US.UTF-8"), Some("en"));
+        assert_eq!(match_locale("C.UTF-8"), Some("en"));
+        assert_eq!(match_locale("fr_FR"), None);
+    }
+
+    #[test]
+    fn translations_format_and_cover_every_id() {
+        let de = Catalog::new("de");
+        let en = Catalog::new("en");
+        let args = |n: usize| {
+            let mut args = FluentArgs::new();
+            args.set("count", n);
+            args
+        };
+        assert_eq!(en.format("html-message-count", Some(&args(1))), "1 message");
+        assert_eq!(
+            en.format("html-message-count", Some(&args(3))),
+            "3 messages"
+        );
+        assert_eq!(
+            de.format("html-message-count", Some(&args(3))),
+            "3 Nachrichten"
+        );
+        assert_eq!(de.format("html-stats", None), "Statistik");
+        assert_eq!(de.format("no-such-id", None), "no-such-id");
+
+        let ids = |source: &str| -> Vec<String> {
+            source
+                .lines()
+                .filter_map(|line| line.split_once(" = ").map(|(id, _)| id))
+                .filter(|id| !id.starts_with([' ', '#']))
+                .map(str::to_string)
+                .collect()
+        };
+        for (name, source) in &LOCALES[1..] {
+            assert_eq!(ids(source), ids(LOCALES[0].1), "{name} ids");
+        }
+    }
+}
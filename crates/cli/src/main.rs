@@ -1,22 +1,58 @@
 use std::{
     cell::RefCell,
+    collections::HashMap,
     fs,
     path::{Path, PathBuf},
+    sync::Arc,
     time::Instant,
 };
 
+use adapter_common::{DirListingCache, DiscoveryOptions};
 use anyhow::{Context, bail};
 use chrono::{DateTime, Utc};
-use clap::{Args, Parser, Subcommand, ValueEnum};
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
+use core_model::{AgentKind, SourceContext};
 use ingest::SyncPhase;
 use render::HtmlSafety;
 use store_sqlite::SqliteStore;
 use tracing::{debug, info, trace};
 
+mod anki;
+mod anonymize;
+mod batch;
+mod browse;
 #[cfg(feature = "semantic")]
+mod cluster;
+mod compare;
 mod config;
+mod corpus;
+mod debug;
+mod diff;
+mod digest;
+mod eval;
+mod graph;
+mod graphql;
+mod heatmap;
+mod hook;
+mod i18n;
+mod ndjson;
+mod nice;
+mod onboard;
+#[cfg(feature = "semantic")]
+mod ort_runtime;
+mod profile;
+mod publish;
+mod redact;
 mod render;
+mod rollup;
+mod schema;
+mod serve;
+mod stats;
+mod tail;
+mod template;
+mod theme;
 mod ui;
+mod wipe;
 
 #[derive(Parser)]
 #[command(name = "remi", version)]
@@ -24,6 +60,8 @@ mod ui;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
     #[cfg(feature = "semantic")]
     #[arg(long)]
     ort_dylib_path: Option<PathBuf>,
@@ -34,8 +72,21 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
-    Init,
+    Init {
+        #[arg(long, default_value_t = false)]
+        interactive: bool,
+    },
     Sync(SyncArgs),
+    Backfill {
+        #[arg(long, value_enum)]
+        agent: AgentOpt,
+        #[arg(long, value_name = "DATE")]
+        before: Option<String>,
+    },
+    Renormalize {
+        #[arg(long, value_enum)]
+        agent: AgentOpt,
+    },
     Docs {
         #[command(subcommand)]
         command: DocsCommand,
@@ -44,26 +95,244 @@ enum Commands {
         #[command(subcommand)]
         command: SessionsCommand,
     },
+    Trash {
+        #[command(subcommand)]
+        command: TrashCommand,
+    },
+    Messages {
+        #[command(subcommand)]
+        command: MessagesCommand,
+    },
+    Files {
+        #[command(subcommand)]
+        command: FilesCommand,
+    },
+    Links {
+        #[command(subcommand)]
+        command: LinksCommand,
+    },
+    Graph {
+        #[command(subcommand)]
+        command: GraphCommand,
+    },
+    #[command(name = "commands")]
+    CommandRuns {
+        #[command(subcommand)]
+        command: CommandsCommand,
+    },
+    Last {
+        #[arg(default_value_t = 10)]
+        count: usize,
+    },
+    Show {
+        #[arg(value_name = "SESSION_ID|last")]
+        session: String,
+        #[arg(long, default_value_t = false)]
+        stats: bool,
+        #[arg(long, default_value_t = false)]
+        system_prompts: bool,
+        #[arg(long, value_name = "MINUTES")]
+        burst_gap: Option<u64>,
+    },
     Search {
         #[command(subcommand)]
-        command: SearchCommand,
+        command: Box<SearchCommand>,
     },
     Archive {
         #[command(subcommand)]
         command: ArchiveCommand,
     },
+    Stats {
+        #[command(subcommand)]
+        command: StatsCommand,
+    },
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommand,
+    },
+    Secrets {
+        #[command(subcommand)]
+        command: SecretsCommand,
+    },
+    Tail {
+        #[arg(long)]
+        agent: Option<String>,
+        #[arg(long, value_name = "SESSION_ID")]
+        session: Option<String>,
+        #[arg(short = 'n', long, default_value_t = 10)]
+        lines: usize,
+        #[arg(long, default_value = "2s")]
+        interval: String,
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+    Render {
+        #[arg(long, required = true)]
+        dump_classes: bool,
+    },
+    Schema {
+        #[command(subcommand)]
+        command: SchemaCommand,
+    },
+    Debug {
+        #[command(subcommand)]
+        command: DebugCommand,
+    },
+    Eval {
+        #[arg(long, value_name = "FILE")]
+        gold: PathBuf,
+        #[arg(long = "k", value_name = "K", value_delimiter = ',', default_values_t = eval::DEFAULT_KS.to_vec())]
+        ks: Vec<usize>,
+        #[arg(long, value_name = "NAME")]
+        preset: Option<String>,
+        #[arg(long)]
+        agent: Option<String>,
+        #[arg(long, default_value_t = false)]
+        raw_fts: bool,
+        #[cfg(feature = "semantic")]
+        #[arg(long, value_enum, default_value_t = SemanticMode::Auto)]
+        semantic: SemanticMode,
+        #[cfg(feature = "semantic")]
+        #[arg(long, default_value_t = false)]
+        rerank: bool,
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+    Serve {
+        #[arg(long, default_value = "127.0.0.1:7070", value_name = "ADDR")]
+        addr: String,
+        #[arg(long, default_value_t = false)]
+        redact: bool,
+    },
+    #[cfg(feature = "grpc")]
+    Grpc {
+        #[arg(long, default_value = "127.0.0.1:50051", value_name = "ADDR")]
+        addr: String,
+        #[arg(long, default_value = "2s")]
+        interval: String,
+        #[arg(long, default_value_t = false)]
+        redact: bool,
+    },
+    Export {
+        #[arg(long, value_enum)]
+        format: ExportFormat,
+        #[arg(long, value_name = "FILTER")]
+        filter: Option<String>,
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+        #[arg(long, default_value_t = false)]
+        anonymize: bool,
+    },
+    Compare {
+        #[arg(long)]
+        query: String,
+        #[arg(long, default_value = "7d")]
+        window: String,
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+    Digest {
+        #[arg(long, value_name = "DATE")]
+        date: Option<String>,
+        #[arg(long, value_name = "DIR")]
+        out_dir: Option<PathBuf>,
+        #[arg(long, default_value_t = false, conflicts_with = "out_dir")]
+        stdout: bool,
+        #[arg(long, default_value_t = false, conflicts_with_all = ["date", "stdout"])]
+        watch: bool,
+    },
+    Wipe(WipeArgs),
+    Rollup {
+        #[arg(long, value_enum, default_value_t = RollupPeriod::Week)]
+        period: RollupPeriod,
+        #[arg(long, value_name = "DATE")]
+        date: Option<String>,
+        #[arg(long, default_value_t = 1)]
+        count: u32,
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+    },
     #[cfg(feature = "semantic")]
     Embed {
         #[arg(long)]
         rebuild: bool,
+        #[arg(long, conflicts_with = "rebuild")]
+        drain: bool,
+    },
+    #[cfg(feature = "semantic")]
+    Cluster {
+        #[arg(long)]
+        k: Option<usize>,
+        #[arg(long)]
+        agent: Option<String>,
+        #[arg(long, value_name = "FILE")]
+        map: Option<PathBuf>,
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+    Doctor {
+        #[arg(long)]
+        repair: bool,
+        #[arg(long, default_value_t = false, conflicts_with = "repair")]
+        features: bool,
+        #[cfg(feature = "semantic")]
+        #[arg(long, default_value_t = false)]
+        install_ort: bool,
+        #[cfg(feature = "semantic")]
+        #[arg(long, value_name = "VERSION", requires = "install_ort")]
+        ort_version: Option<String>,
+        #[cfg(feature = "semantic")]
+        #[arg(long, value_name = "HEX", requires = "install_ort")]
+        ort_sha256: Option<String>,
     },
-    Doctor,
 }
 
 #[derive(Args)]
+#[command(group(ArgGroup::new("selection").required(true).args(["agent", "all", "only"])))]
 struct SyncArgs {
     #[arg(long, value_enum)]
-    agent: AgentOpt,
+    agent: Option<AgentOpt>,
+    #[arg(long, default_value_t = false)]
+    all: bool,
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "AGENTS")]
+    only: Vec<AgentOpt>,
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "AGENTS")]
+    except: Vec<AgentOpt>,
+    #[arg(long, value_name = "DURATION")]
+    max_age: Option<String>,
+    #[arg(long, default_value_t = false)]
+    nice: bool,
+}
+
+impl SyncArgs {
+    // Agents named explicitly are synced even when disabled in the config;
+    // `--all` (or `--agent all`) covers only the enabled ones.
+    fn selected(&self, enabled: impl Fn(AgentKind) -> bool) -> anyhow::Result<Vec<AgentKind>> {
+        let every = self.all || self.agent.is_some_and(|a| a.kind().is_none());
+        if !every && !self.except.is_empty() {
+            bail!("--except only applies to --all");
+        }
+        let named: Vec<AgentKind> = match self.agent.and_then(AgentOpt::kind) {
+            Some(kind) => vec![kind],
+            None => self.only.iter().flat_map(|a| a.kinds()).collect(),
+        };
+        let selected: Vec<AgentKind> = AgentKind::ALL
+            .into_iter()
+            .filter(|kind| {
+                if every {
+                    enabled(*kind) && !self.except.iter().any(|a| a.kinds().contains(kind))
+                } else {
+                    named.contains(kind)
+                }
+            })
+            .collect();
+        if selected.is_empty() {
+            bail!(
+                "no agents selected to sync; check --except and [agents.<agent>] enabled in config"
+            );
+        }
+        Ok(selected)
+    }
 }
 
 #[derive(Clone, Copy, ValueEnum)]
@@ -74,13 +343,298 @@ enum AgentOpt {
     Claude,
     Amp,
     Codex,
+    Gemini,
+    Aider,
     All,
 }
 
+impl AgentOpt {
+    fn kind(self) -> Option<AgentKind> {
+        match self {
+            AgentOpt::Pi => Some(AgentKind::Pi),
+            AgentOpt::Droid => Some(AgentKind::Droid),
+            AgentOpt::Opencode => Some(AgentKind::OpenCode),
+            AgentOpt::Claude => Some(AgentKind::Claude),
+            AgentOpt::Amp => Some(AgentKind::Amp),
+            AgentOpt::Codex => Some(AgentKind::Codex),
+            AgentOpt::Gemini => Some(AgentKind::Gemini),
+            AgentOpt::Aider => Some(AgentKind::Aider),
+            AgentOpt::All => None,
+        }
+    }
+
+    fn kinds(self) -> Vec<AgentKind> {
+        self.kind()
+            .map_or_else(|| AgentKind::ALL.to_vec(), |kind| vec![kind])
+    }
+}
+
 #[derive(Subcommand)]
 enum SessionsCommand {
+    List {
+        #[arg(long = "ref", value_name = "REF")]
+        reference: Option<String>,
+    },
+    Show {
+        session_id: String,
+        #[arg(long, default_value_t = false)]
+        stats: bool,
+        #[arg(long, default_value_t = false)]
+        system_prompts: bool,
+        #[arg(long, value_name = "NAME")]
+        template: Option<String>,
+        #[arg(long, value_name = "MINUTES")]
+        burst_gap: Option<u64>,
+    },
+    Browse {
+        session_id: String,
+    },
+    Diff {
+        left: String,
+        right: String,
+        #[arg(long, value_enum, default_value_t = DiffFormat::Text)]
+        format: DiffFormat,
+    },
+    Split {
+        session_id: String,
+        #[arg(long, value_name = "MESSAGE_ID")]
+        at: String,
+    },
+    Merge {
+        into: String,
+        from: String,
+    },
+    Alias {
+        source_ref: String,
+        canonical: String,
+        #[arg(long, value_enum)]
+        agent: AgentOpt,
+    },
+    Delete {
+        session_id: String,
+    },
+    Publish(PublishArgs),
+    Share(ShareArgs),
+}
+
+#[derive(Args)]
+#[command(group(ArgGroup::new("criteria").required(true).multiple(true).args(["project", "matching"])))]
+struct WipeArgs {
+    #[arg(long, value_name = "PATH")]
+    project: Option<String>,
+    #[arg(long, value_name = "REGEX")]
+    matching: Option<String>,
+    #[arg(long, default_value_t = false)]
+    dry_run: bool,
+    #[arg(long, value_name = "FILE")]
+    report: Option<PathBuf>,
+    #[arg(long, default_value_t = false)]
+    json: bool,
+}
+
+#[derive(Args)]
+#[command(group(ArgGroup::new("webhook").required(true).args(["slack_webhook", "discord_webhook"])))]
+struct ShareArgs {
+    session_id: String,
+    #[arg(long, value_name = "URL")]
+    slack_webhook: Option<String>,
+    #[arg(long, value_name = "URL")]
+    discord_webhook: Option<String>,
+    #[arg(long = "message", value_name = "MESSAGE_ID")]
+    messages: Vec<String>,
+}
+
+#[derive(Args)]
+#[command(group(ArgGroup::new("target").required(true).args(["gist", "pr", "file"])))]
+struct PublishArgs {
+    session_id: String,
+    #[arg(long, default_value_t = false)]
+    gist: bool,
+    #[arg(long, default_value_t = false, requires = "gist")]
+    public: bool,
+    #[arg(long, value_name = "REF")]
+    pr: Option<String>,
+    #[arg(long, value_name = "PATH")]
+    file: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DiffFormat {
+    Text,
+    Html,
+}
+
+#[derive(Subcommand)]
+enum FilesCommand {
+    History {
+        path: String,
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum LinksCommand {
+    List {
+        #[arg(long)]
+        since: Option<String>,
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum GraphCommand {
+    Build,
+    Export {
+        #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+        format: GraphFormat,
+        #[arg(long, default_value_t = 1)]
+        min_sessions: usize,
+        #[arg(long, value_name = "KIND", value_delimiter = ',')]
+        kind: Vec<String>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum GraphFormat {
+    Dot,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum CommandsCommand {
+    Search {
+        query: String,
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TrashCommand {
     List,
-    Show { session_id: String },
+    Restore {
+        session_id: String,
+    },
+    Empty {
+        #[arg(long, value_name = "DATE")]
+        before: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum MessagesCommand {
+    Show {
+        message_id: String,
+        #[arg(long, default_value_t = 5)]
+        context: usize,
+    },
+    Bookmark {
+        message_id: String,
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+    },
+    Unbookmark {
+        message_id: String,
+    },
+    Bookmarks {
+        #[arg(long)]
+        tag: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfileCommand {
+    List,
+    Create { name: String },
+    Use { name: String },
+}
+
+#[derive(Subcommand)]
+enum SecretsCommand {
+    Set { name: String },
+    Get { name: String },
+    Delete { name: String },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    Anki,
+    #[value(alias = "ndjson")]
+    Jsonl,
+}
+
+#[derive(Subcommand)]
+enum SchemaCommand {
+    Dump {
+        #[arg(value_name = "NAME")]
+        name: Option<String>,
+        #[arg(long, default_value_t = false)]
+        list: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum DebugCommand {
+    Normalize {
+        #[arg(long, value_enum)]
+        agent: AgentOpt,
+        #[arg(long, value_name = "PATH")]
+        file: PathBuf,
+        #[arg(long, default_value_t = false)]
+        hook: bool,
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+    #[cfg(feature = "claude")]
+    Dedupe {
+        #[arg(long = "file", value_name = "PATH", required = true)]
+        files: Vec<PathBuf>,
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum StatsCommand {
+    Tools {
+        #[arg(long)]
+        agent: Option<String>,
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+    Projects {
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+    Storage {
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+    Heatmap {
+        #[arg(long)]
+        agent: Option<String>,
+        #[arg(long)]
+        year: Option<i32>,
+        #[arg(long, value_enum, default_value_t = HeatmapFormat::Text)]
+        format: HeatmapFormat,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum HeatmapFormat {
+    Text,
+    Html,
+    Svg,
 }
 
 #[derive(Subcommand)]
@@ -102,8 +656,10 @@ enum DocsCommand {
 enum SearchCommand {
     Query {
         query: String,
-        #[arg(long, value_enum, default_value_t = SearchFormat::Html)]
-        format: SearchFormat,
+        #[arg(long, value_name = "NAME")]
+        preset: Option<String>,
+        #[arg(long, value_enum)]
+        format: Option<SearchFormat>,
         #[arg(long, default_value_t = false)]
         no_interactive: bool,
         #[arg(long, value_enum, default_value_t = SelectMode::Auto)]
@@ -118,18 +674,76 @@ enum SearchCommand {
         id: Option<String>,
         #[arg(long)]
         contains: Option<String>,
+        #[arg(long)]
+        lang: Option<String>,
+        #[arg(long, value_enum)]
+        reasoning: Option<ReasoningMode>,
         #[arg(long, default_value_t = false)]
         raw_fts: bool,
+        #[arg(long)]
+        limit: Option<usize>,
         #[arg(long, value_enum, default_value_t = HtmlSafety::Relaxed)]
         html_safety: HtmlSafety,
+        #[arg(long, default_value_t = false)]
+        redact: bool,
+        #[arg(long, default_value_t = false)]
+        sidecars: bool,
         #[cfg(feature = "semantic")]
         #[arg(long, value_enum, default_value_t = SemanticMode::Auto)]
         semantic: SemanticMode,
+        #[cfg(feature = "semantic")]
+        #[arg(long, default_value_t = false)]
+        rerank: bool,
         #[arg(long)]
         output_dir: Option<PathBuf>,
+        #[arg(long, value_name = "NAME")]
+        template: Option<String>,
+        #[arg(long, value_name = "FILE")]
+        css: Option<PathBuf>,
+        #[arg(long, value_name = "DATE")]
+        as_of: Option<String>,
+        #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "1")]
+        copy_code: Option<usize>,
+        #[arg(long, default_value_t = false)]
+        explain: bool,
+    },
+    Batch {
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+        #[arg(long, value_enum, default_value_t = BatchFormat::Json)]
+        format: BatchFormat,
+        #[arg(long, value_name = "NAME")]
+        preset: Option<String>,
+        #[arg(long)]
+        agent: Option<String>,
+        #[arg(long)]
+        lang: Option<String>,
+        #[arg(long, value_enum)]
+        reasoning: Option<ReasoningMode>,
+        #[arg(long, default_value_t = false)]
+        raw_fts: bool,
+        #[arg(long)]
+        limit: Option<usize>,
+        #[cfg(feature = "semantic")]
+        #[arg(long, value_enum, default_value_t = SemanticMode::Auto)]
+        semantic: SemanticMode,
+        #[cfg(feature = "semantic")]
+        #[arg(long, default_value_t = false)]
+        rerank: bool,
+        #[arg(long, value_name = "DATE")]
+        as_of: Option<String>,
+        #[arg(long, default_value_t = false)]
+        explain: bool,
     },
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum BatchFormat {
+    Json,
+    #[value(alias = "ndjson")]
+    Jsonl,
+}
+
 #[derive(Subcommand)]
 enum ArchiveCommand {
     Plan {
@@ -159,6 +773,23 @@ enum SearchFormat {
     Html,
     Markdown,
     Json,
+    Ndjson,
+    Rawjson,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum RollupPeriod {
+    Day,
+    Week,
+}
+
+impl From<RollupPeriod> for rollup::Period {
+    fn from(period: RollupPeriod) -> Self {
+        match period {
+            RollupPeriod::Day => rollup::Period::Day,
+            RollupPeriod::Week => rollup::Period::Week,
+        }
+    }
 }
 
 #[derive(Clone, Copy, ValueEnum)]
@@ -167,6 +798,23 @@ enum SelectMode {
     Index,
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum ReasoningMode {
+    Include,
+    Exclude,
+    Only,
+}
+
+impl From<ReasoningMode> for search::ReasoningFilter {
+    fn from(mode: ReasoningMode) -> Self {
+        match mode {
+            ReasoningMode::Include => search::ReasoningFilter::Include,
+            ReasoningMode::Exclude => search::ReasoningFilter::Exclude,
+            ReasoningMode::Only => search::ReasoningFilter::Only,
+        }
+    }
+}
+
 #[cfg(feature = "semantic")]
 #[derive(Clone, Copy, ValueEnum)]
 enum SemanticMode {
@@ -181,10 +829,66 @@ impl SearchFormat {
             SearchFormat::Html => "html",
             SearchFormat::Markdown => "md",
             SearchFormat::Json => "json",
+            SearchFormat::Ndjson => "ndjson",
+            SearchFormat::Rawjson => "raw.json",
         }
     }
 }
 
+fn print_session(
+    store: &SqliteStore,
+    session_id: &str,
+    stats: bool,
+    system_prompts: bool,
+    burst_gap_minutes: u64,
+) -> anyhow::Result<()> {
+    trace!(session_id, "showing session messages");
+    let Some(bundle) = store.get_session_bundle(session_id)? else {
+        return Err(anyhow::anyhow!("session not found: {session_id}"));
+    };
+    let msgs = bundle.messages;
+    info!(messages = msgs.len(), "session messages listed");
+    if stats && let Some(aggregate) = store.session_aggregate(session_id)? {
+        for (key, value) in stats::SessionStats::new(aggregate, &msgs).lines() {
+            println!("{key}={value}");
+        }
+        println!();
+    }
+    if system_prompts {
+        let prompts = bundle
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.get("system_prompts"))
+            .and_then(|v| v.as_array())
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        if prompts.is_empty() {
+            println!("{}", i18n::text("show-no-system-prompts"));
+        }
+        for p in prompts {
+            println!(
+                "{} [{}] {}",
+                p.get("ts").and_then(|v| v.as_str()).unwrap_or_default(),
+                p.get("role").and_then(|v| v.as_str()).unwrap_or("system"),
+                p.get("content")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+            );
+        }
+        println!();
+    }
+    let mut breaks = stats::burst_breaks(&msgs, burst_gap_minutes)
+        .into_iter()
+        .peekable();
+    for (i, m) in msgs.iter().enumerate() {
+        if let Some(b) = breaks.next_if(|b| b.index == i) {
+            println!("\n-- {} --\n", b.label());
+        }
+        println!("{} [{}] {}", m.ts.to_rfc3339(), m.role, m.content);
+    }
+    Ok(())
+}
+
 fn sanitize_title(title: &str) -> String {
     let first_line = title.split('\n').next().unwrap_or(title);
     let char_count = first_line.chars().count();
@@ -198,15 +902,43 @@ fn sanitize_title(title: &str) -> String {
 
 fn command_name(cmd: &Commands) -> &'static str {
     match cmd {
-        Commands::Init => "init",
+        Commands::Init { .. } => "init",
         Commands::Sync(_) => "sync",
+        Commands::Backfill { .. } => "backfill",
+        Commands::Renormalize { .. } => "renormalize",
         Commands::Docs { .. } => "docs",
         Commands::Sessions { .. } => "sessions",
+        Commands::Trash { .. } => "trash",
+        Commands::Messages { .. } => "messages",
+        Commands::Files { .. } => "files",
+        Commands::Links { .. } => "links",
+        Commands::Graph { .. } => "graph",
+        Commands::CommandRuns { .. } => "commands",
+        Commands::Last { .. } => "last",
+        Commands::Show { .. } => "show",
         Commands::Search { .. } => "search",
         Commands::Archive { .. } => "archive",
+        Commands::Stats { .. } => "stats",
+        Commands::Profile { .. } => "profile",
+        Commands::Secrets { .. } => "secrets",
+        Commands::Tail { .. } => "tail",
+        Commands::Render { .. } => "render",
+        Commands::Schema { .. } => "schema",
+        Commands::Debug { .. } => "debug",
+        Commands::Eval { .. } => "eval",
+        Commands::Serve { .. } => "serve",
+        #[cfg(feature = "grpc")]
+        Commands::Grpc { .. } => "grpc",
+        Commands::Export { .. } => "export",
+        Commands::Compare { .. } => "compare",
+        Commands::Digest { .. } => "digest",
+        Commands::Rollup { .. } => "rollup",
+        Commands::Wipe(_) => "wipe",
         #[cfg(feature = "semantic")]
         Commands::Embed { .. } => "embed",
-        Commands::Doctor => "doctor",
+        #[cfg(feature = "semantic")]
+        Commands::Cluster { .. } => "cluster",
+        Commands::Doctor { .. } => "doctor",
     }
 }
 
@@ -228,16 +960,127 @@ fn main() -> anyhow::Result<()> {
     }
     let cli = Cli::parse();
     debug!(command = %command_name(&cli.command), "cli args parsed");
-    #[cfg(feature = "semantic")]
-    let config = config::Config::load()?;
+    let profiles = profile::Profiles::new();
+    let profile_env = std::env::var(profile::PROFILE_ENV).ok();
+    if let Commands::Profile { command } = &cli.command {
+        return run_profile_command(
+            &profiles,
+            command,
+            cli.profile.as_deref().or(profile_env.as_deref()),
+        );
+    }
+    if let Commands::Secrets { command } = &cli.command {
+        return run_secrets_command(command);
+    }
+    if let Commands::Doctor { features: true, .. } = &cli.command {
+        for (name, on) in compiled_features() {
+            println!("{name}={}", if on { "on" } else { "off" });
+        }
+        return Ok(());
+    }
+    if let Commands::Render { dump_classes: true } = &cli.command {
+        print!("{}", render::dump_classes());
+        return Ok(());
+    }
+    if let Commands::Schema {
+        command: SchemaCommand::Dump { name, list },
+    } = &cli.command
+    {
+        if *list {
+            schema::names().iter().for_each(|name| println!("{name}"));
+        } else {
+            let schema = schema::dump(name.as_deref())?;
+            println!("{}", serde_json::to_string_pretty(&schema)?);
+        }
+        return Ok(());
+    }
+    let profile = profiles.resolve(cli.profile.as_deref(), profile_env.as_deref())?;
+    debug!(profile = %profile.name, db = %profile.db_path.display(), "profile resolved");
+    let config = config::Config::load(&profile.config_path)?;
+    i18n::init(config.locale.as_deref())?;
+    let nice = matches!(&cli.command, Commands::Sync(args) if args.nice);
+    if nice {
+        if let Err(e) = nice::lower_priority() {
+            tracing::warn!(error = %e, "could not lower process priority for --nice");
+        }
+        adapter_common::set_file_pause(nice::FILE_PAUSE);
+    }
+    // Adapters scan files on the global rayon pool, so this caps sync.
+    if let Some(threads) = nice::scan_threads(config.performance.scan_threads, nice) {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .context("configuring [performance] scan_threads")?;
+    }
+    let sources = config.source_context(adapter_common::env_source_context());
+    // Runs before the database is opened; nothing is written.
+    if let Commands::Debug { command } = &cli.command {
+        let (document, text) = match command {
+            DebugCommand::Normalize {
+                agent,
+                file,
+                hook,
+                json,
+            } => {
+                let kind = agent
+                    .kind()
+                    .context("pick one agent; `all` cannot parse a single file")?;
+                let mut adapter =
+                    adapter_for(kind, |kind| config.discovery.options_for(kind), &config)?;
+                if *hook {
+                    adapter = with_hook(adapter, &config, &config_dir(&profile))?;
+                }
+                let run = debug::normalize_file(adapter.as_ref(), file)?;
+                (json.then(|| run.to_json()), run.to_text())
+            }
+            #[cfg(feature = "claude")]
+            DebugCommand::Dedupe { files, json } => {
+                let run = debug::dedupe_files(files)?;
+                (json.then(|| run.to_json()), run.to_text())
+            }
+        };
+        match document {
+            Some(document) => println!("{}", serde_json::to_string_pretty(&document)?),
+            None => print!("{text}"),
+        }
+        return Ok(());
+    }
     let t = Instant::now();
 
+    // Runs before the embedder loads, since loading is what needs the library.
     #[cfg(feature = "semantic")]
-    configure_ort(&cli)?;
+    if let Commands::Doctor {
+        install_ort: true,
+        ort_version,
+        ort_sha256,
+        ..
+    } = &cli.command
+    {
+        let version = ort_version
+            .as_deref()
+            .unwrap_or(ort_runtime::DEFAULT_ORT_VERSION);
+        let library =
+            ort_runtime::install(&ort_runtime::cache_root()?, version, ort_sha256.as_deref())?;
+        ort_runtime::persist(&profile.config_path, &library)?;
+        println!("ort_installed={}", library.display());
+        println!("config={}", profile.config_path.display());
+        return Ok(());
+    }
+
+    #[cfg(feature = "semantic")]
+    configure_ort(&cli, &config)?;
 
     info!("opening database");
-    let mut store = SqliteStore::open_default()?;
+    std::fs::create_dir_all(profile.data_dir())
+        .with_context(|| format!("creating {}", profile.data_dir().display()))?;
+    let db_key = if config.database.encrypted {
+        Some(secrets::database_key(&profile.name)?)
+    } else {
+        None
+    };
+    let mut store = SqliteStore::open_with_key(&profile.db_path, db_key.as_deref())?;
     store.init_schema()?;
+    store.set_retain_raw_records(config.capture.raw_records);
 
     #[cfg(feature = "semantic")]
     let mut embedder = if let Some(semantic) = &config.semantic {
@@ -247,13 +1090,34 @@ fn main() -> anyhow::Result<()> {
                 .as_ref()
                 .map(PathBuf::from)
                 .or_else(detect_model_path);
+            let backend: embeddings::Backend = semantic
+                .backend
+                .as_deref()
+                .map(str::parse)
+                .transpose()
+                .context("invalid [semantic] backend")?
+                .unwrap_or_default();
             if let Some(path) = model_path {
-                info!(path = %path.display(), "loading embedding model");
-                Some(embeddings::Embedder::new(
+                info!(path = %path.display(), backend = backend.as_str(), "loading embedding model");
+                let embedder = embeddings::Embedder::with_backend(
                     path,
+                    backend,
                     semantic.pooling.as_deref(),
                     semantic.query_prefix.as_deref(),
-                )?)
+                    config.performance.embed_threads,
+                )?;
+                info!(
+                    backend = embedder.backend().as_str(),
+                    "embedding model loaded"
+                );
+                let stale = store.requeue_stale_embeddings(embedder.model_id())?;
+                if stale > 0 {
+                    info!(
+                        stale,
+                        "queued messages embedded by another model; run `remi embed --drain` to replace them"
+                    );
+                }
+                Some(embedder)
             } else {
                 tracing::warn!("semantic search enabled but no model_path configured; skipping");
                 None
@@ -269,72 +1133,191 @@ fn main() -> anyhow::Result<()> {
     let mut semantic_cache = search::SemanticCache::default();
 
     match cli.command {
-        Commands::Init => {
+        Commands::Init { interactive } => {
+            if interactive {
+                let probes = onboard::probe(
+                    &remi::adapters(
+                        |kind| config.discovery.options_for(kind),
+                        config.capture.options(),
+                        |kind| config.timezone_for(kind),
+                    ),
+                    &sources,
+                )?;
+                #[cfg(feature = "semantic")]
+                let semantic = Some(config.semantic.as_ref().is_some_and(|s| s.enabled));
+                #[cfg(not(feature = "semantic"))]
+                let semantic = None;
+                onboard::run(
+                    &probes,
+                    |kind| config.agent_enabled(kind),
+                    semantic,
+                    &profile.config_path,
+                )?;
+            }
             info!(elapsed = ?t.elapsed(), "initialized");
         }
         Commands::Sync(args) => {
-            let synced = match args.agent {
-                AgentOpt::Pi => sync_with_timing(
-                    "pi",
-                    &pi::PiAdapter,
-                    &mut store,
-                    #[cfg(feature = "semantic")]
-                    embedder.as_mut(),
-                )?,
-                AgentOpt::Droid => sync_with_timing(
-                    "droid",
-                    &droid::DroidAdapter,
-                    &mut store,
-                    #[cfg(feature = "semantic")]
-                    embedder.as_mut(),
-                )?,
-                AgentOpt::Opencode => sync_with_timing(
-                    "opencode",
-                    &opencode::OpenCodeAdapter,
-                    &mut store,
-                    #[cfg(feature = "semantic")]
-                    embedder.as_mut(),
-                )?,
-                AgentOpt::Claude => sync_with_timing(
-                    "claude",
-                    &claude::ClaudeAdapter,
+            let cutoff = args
+                .max_age
+                .as_deref()
+                .map(|v| -> anyhow::Result<DateTime<Utc>> {
+                    let window =
+                        humantime::parse_duration(v).with_context(|| "invalid --max-age")?;
+                    Ok(Utc::now() - chrono::Duration::from_std(window)?)
+                })
+                .transpose()?;
+            let selected =
+                args.selected(|kind| config.agent_enabled(kind) && remi::adapter_compiled(kind))?;
+            if let Some(kind) = selected.iter().find(|kind| !remi::adapter_compiled(**kind)) {
+                bail!("this build of remi has no {kind} adapter (cargo feature `{kind}`)");
+            }
+            debug!(agents = ?selected, "selected agents to sync");
+            let mut synced = 0;
+            let mut listing_caches = HashMap::new();
+            for kind in selected {
+                let listings = store.get_dir_listings(kind.as_str())?;
+                listing_caches.insert(kind, Arc::new(DirListingCache::new(listings)));
+            }
+            let discovery = |kind| DiscoveryOptions {
+                listing_cache: listing_caches.get(&kind).cloned(),
+                modified_since: cutoff,
+                ..config.discovery.options_for(kind)
+            };
+            let chunk_size = if args.nice {
+                nice::CHUNK_SIZE
+            } else {
+                ingest::SYNC_CHUNK_SIZE
+            };
+            let hooks = config_dir(&profile);
+            for (name, adapter) in with_hooks(
+                remi::adapters(discovery, config.capture.options(), |kind| {
+                    config.timezone_for(kind)
+                }),
+                &config,
+                &hooks,
+            )? {
+                let Some(cache) = listing_caches.get(&adapter.kind()) else {
+                    continue;
+                };
+                synced += sync_with_timing(
+                    name,
+                    adapter.as_ref(),
+                    &sources,
                     &mut store,
                     #[cfg(feature = "semantic")]
-                    embedder.as_mut(),
-                )?,
-                AgentOpt::Amp => sync_with_timing(
-                    "amp",
-                    &amp::AmpAdapter,
+                    embedder.is_some(),
+                    chunk_size,
+                )?;
+                store.upsert_dir_listings(adapter.kind().as_str(), &cache.take_changed())?;
+                let kind = adapter.kind();
+                if let Some(cutoff) = cutoff {
+                    store.record_sync_cutoff(kind.as_str(), cutoff)?;
+                    info!(name, cutoff = %cutoff, "recorded --max-age cutoff for a later backfill");
+                } else if let Some(pending) = store.get_sync_cutoff(kind.as_str())? {
+                    let older = with_hook(
+                        adapter_for(
+                            kind,
+                            |kind| DiscoveryOptions {
+                                modified_before: Some(pending),
+                                ..config.discovery.options_for(kind)
+                            },
+                            &config,
+                        )?,
+                        &config,
+                        &hooks,
+                    )?;
+                    synced += backfill_with_timing(
+                        name,
+                        older.as_ref(),
+                        &sources,
+                        &mut store,
+                        #[cfg(feature = "semantic")]
+                        embedder.is_some(),
+                        None,
+                    )?;
+                    store.clear_sync_cutoff(kind.as_str())?;
+                }
+            }
+            info!(records = synced, elapsed = ?t.elapsed(), "synced");
+            // The sync is committed before embedding starts, so new messages
+            // are searchable by keyword while the queue drains.
+            #[cfg(feature = "semantic")]
+            if let Some(embedder) = embedder.as_mut() {
+                drain_embeddings(&store, embedder, t)?;
+            }
+            warn_on_drift(&store)?;
+        }
+        Commands::Backfill { agent, before } => {
+            let before = before
+                .as_deref()
+                .map(|raw| parse_cutoff("--before", raw))
+                .transpose()?;
+            for (name, adapter) in with_hooks(
+                remi::adapters(
+                    |kind| config.discovery.options_for(kind),
+                    config.capture.options(),
+                    |kind| config.timezone_for(kind),
+                ),
+                &config,
+                &config_dir(&profile),
+            )? {
+                let kind = adapter.kind();
+                if agent.kind().is_some_and(|selected| selected != kind) {
+                    continue;
+                }
+                let count = backfill_with_timing(
+                    name,
+                    adapter.as_ref(),
+                    &sources,
                     &mut store,
                     #[cfg(feature = "semantic")]
-                    embedder.as_mut(),
-                )?,
-                AgentOpt::Codex => sync_with_timing(
-                    "codex",
-                    &codex::CodexAdapter,
+                    embedder.is_some(),
+                    before,
+                )?;
+                if let Some(cutoff) = store.get_sync_cutoff(kind.as_str())?
+                    && before.is_none_or(|before| before >= cutoff)
+                {
+                    store.clear_sync_cutoff(kind.as_str())?;
+                }
+                println!("agent={name} backfilled={count}");
+            }
+            info!(elapsed = ?t.elapsed(), "backfilled");
+        }
+        Commands::Renormalize { agent } => {
+            for (name, adapter) in with_hooks(
+                remi::adapters(
+                    |kind| config.discovery.options_for(kind),
+                    config.capture.options(),
+                    |kind| config.timezone_for(kind),
+                ),
+                &config,
+                &config_dir(&profile),
+            )? {
+                if agent
+                    .kind()
+                    .is_some_and(|selected| selected != adapter.kind())
+                {
+                    continue;
+                }
+                let count = ingest::renormalize_adapter(
+                    adapter.as_ref(),
                     &mut store,
                     #[cfg(feature = "semantic")]
-                    embedder.as_mut(),
-                )?,
-                AgentOpt::All => {
-                    let mut total = 0;
-                    for (name, adapter) in adapters() {
-                        total += sync_with_timing(
-                            name,
-                            adapter.as_ref(),
-                            &mut store,
-                            #[cfg(feature = "semantic")]
-                            embedder.as_mut(),
-                        )?;
-                    }
-                    total
-                }
-            };
-            info!(records = synced, elapsed = ?t.elapsed(), "synced");
+                    embedder.is_some(),
+                    |phase| trace!(name, phase = ?phase, "renormalize phase"),
+                )?;
+                println!("agent={name} renormalized={count}");
+            }
+            if store.raw_records_usage()?.records == 0 {
+                println!(
+                    "hint: no raw records retained; set [capture] raw_records = true and run `remi backfill`"
+                );
+            }
+            info!(elapsed = ?t.elapsed(), "renormalized");
         }
         Commands::Docs { command } => match command {
             DocsCommand::Index { root } => {
-                let summary = index_docs_root(&root)?;
+                let summary = index_docs_root(&root, &profile.db_path, db_key.as_deref())?;
                 info!(
                     root = %root.display(),
                     indexed = summary.indexed,
@@ -358,7 +1341,13 @@ fn main() -> anyhow::Result<()> {
                 limit,
             } => {
                 info!(query = %query, raw_fts, limit, "docs searching");
-                let hits = search::search_docs_at(default_db_path(), &query, limit, raw_fts)?;
+                let hits = search::search_docs_at(
+                    &profile.db_path,
+                    db_key.as_deref(),
+                    &query,
+                    limit,
+                    raw_fts,
+                )?;
                 debug!(hits = hits.len(), "docs search returned hits");
                 if hits.is_empty() {
                     info!(elapsed = ?t.elapsed(), "no docs results");
@@ -373,29 +1362,484 @@ fn main() -> anyhow::Result<()> {
             }
         },
         Commands::Sessions { command } => match command {
-            SessionsCommand::List => {
-                let sessions = store.list_sessions()?;
+            SessionsCommand::List { reference } => {
+                let sessions =
+                    match reference {
+                        Some(reference) => {
+                            let parsed = core_model::IssueRef::parse(&reference).with_context(|| {
+                            format!("invalid --ref (expected owner/name#123 or #123): {reference}")
+                        })?;
+                            store.sessions_with_ref(&parsed)?
+                        }
+                        None => store.list_sessions()?,
+                    };
                 info!(sessions = sessions.len(), "sessions listed");
+                let use_color = ui::color_enabled();
                 for s in &sessions {
-                    println!("{} {} {}", s.id, s.agent.as_str(), sanitize_title(&s.title));
+                    println!(
+                        "{} {} {}",
+                        s.id,
+                        theme::paint_agent(s.agent.as_str(), s.agent.as_str(), use_color),
+                        sanitize_title(&s.title)
+                    );
+                }
+            }
+            SessionsCommand::Show {
+                session_id,
+                stats,
+                system_prompts,
+                template: Some(name),
+                burst_gap,
+            } => {
+                debug!(stats, system_prompts, ?burst_gap, "ignored with --template");
+                let template = template::Template::load(&config_dir(&profile), &name)?;
+                let session = store
+                    .get_session(&session_id)?
+                    .with_context(|| format!("session not found: {session_id}"))?;
+                let messages = store.get_session_messages(&session.id)?;
+                print!(
+                    "{}",
+                    render_template(&store, &template, &session, &messages)?
+                );
+            }
+            SessionsCommand::Show {
+                session_id,
+                stats,
+                system_prompts,
+                template: None,
+                burst_gap: gap,
+            } => {
+                let gap = burst_gap(gap, &config);
+                print_session(&store, &session_id, stats, system_prompts, gap)?;
+            }
+            SessionsCommand::Browse { session_id } => {
+                let Some(bundle) = store.get_session_bundle(&session_id)? else {
+                    bail!("session not found: {session_id}");
+                };
+                info!(messages = bundle.messages.len(), "browsing session");
+                browse::run(&bundle.session, &bundle.messages, &bundle.provenance)?;
+            }
+            SessionsCommand::Diff {
+                left,
+                right,
+                format,
+            } => {
+                let load = |id: &str| -> anyhow::Result<_> {
+                    let session = store
+                        .get_session(id)?
+                        .with_context(|| format!("session not found: {id}"))?;
+                    let messages = store.get_session_messages(id)?;
+                    Ok((session, messages))
+                };
+                let (left, left_messages) = load(&left)?;
+                let (right, right_messages) = load(&right)?;
+                let diff = diff::SessionDiff::new(&left, &left_messages, &right, &right_messages);
+                debug!(rows = diff.rows.len(), "sessions aligned");
+                match format {
+                    DiffFormat::Text => print!("{}", diff.render_terminal(ui::color_enabled())),
+                    DiffFormat::Html => print!("{}", diff.render_html()),
+                }
+            }
+            SessionsCommand::Split { session_id, at } => {
+                let new = store.split_session(&session_id, &at)?;
+                info!(%session_id, at = %at, new = %new.id, "session split");
+                println!("{}", new.id);
+            }
+            SessionsCommand::Merge { into, from } => {
+                let moved = store.merge_sessions(&into, &from)?;
+                info!(%into, %from, moved, "sessions merged");
+                println!("{into}");
+            }
+            SessionsCommand::Alias {
+                source_ref,
+                canonical,
+                agent,
+            } => {
+                let kind = agent
+                    .kind()
+                    .context("session keys are per agent; pass a single --agent")?;
+                match store.add_session_alias(kind, &source_ref, &canonical)? {
+                    Some(id) => {
+                        info!(agent = %kind, %source_ref, %canonical, session = %id, "session aliased");
+                        println!("{id}");
+                    }
+                    None => println!(
+                        "no stored {kind} session has source_ref {source_ref}; the alias applies from the next sync"
+                    ),
+                }
+            }
+            SessionsCommand::Publish(args) => {
+                let session = store
+                    .get_session(&args.session_id)?
+                    .with_context(|| format!("session not found: {}", args.session_id))?;
+                let messages = store.get_session_messages(&session.id)?;
+                let rules = config.redact.as_ref();
+                let redactor = redact::Redactor::new(
+                    rules.map(|r| r.patterns.as_slice()).unwrap_or_default(),
+                    rules.and_then(|r| r.replacement.as_deref()),
+                )?;
+                let (session, markdown) =
+                    publish::redacted_markdown(&session, &messages, &redactor);
+                if let Some(path) = args.file {
+                    std::fs::write(&path, &markdown)
+                        .with_context(|| format!("failed to write {}", path.display()))?;
+                    println!("{}", path.display());
+                    return Ok(());
+                }
+                let (target, payload) = match args.pr {
+                    Some(reference) => {
+                        let parsed = core_model::IssueRef::parse(&reference)
+                            .filter(|r| r.repo.is_some())
+                            .with_context(|| {
+                                format!("invalid --pr (expected owner/name#123): {reference}")
+                            })?;
+                        (
+                            publish::Target::Comment(parsed),
+                            publish::comment_payload(&session, &markdown),
+                        )
+                    }
+                    None => (
+                        publish::Target::Gist,
+                        publish::gist_payload(&session, &markdown, args.public),
+                    ),
+                };
+                let publisher = publish::Publisher::new(
+                    config.publish.api_url.as_deref(),
+                    github_token(&config)?,
+                );
+                let url = publisher.publish(&target, &payload)?;
+                info!(session = %session.id, url, "session published");
+                println!("{url}");
+            }
+            SessionsCommand::Share(args) => {
+                let session = store
+                    .get_session(&args.session_id)?
+                    .with_context(|| format!("session not found: {}", args.session_id))?;
+                let mut messages = store.get_session_messages(&session.id)?;
+                if let Some(missing) = args
+                    .messages
+                    .iter()
+                    .find(|id| !messages.iter().any(|m| &m.id == *id))
+                {
+                    anyhow::bail!("message {missing} is not in session {}", session.id);
+                }
+                let selected = !args.messages.is_empty();
+                if selected {
+                    messages.retain(|m| args.messages.contains(&m.id));
+                }
+                let rules = config.redact.as_ref();
+                let redactor = redact::Redactor::new(
+                    rules.map(|r| r.patterns.as_slice()).unwrap_or_default(),
+                    rules.and_then(|r| r.replacement.as_deref()),
+                )?;
+                let (session, messages) = publish::redacted(&session, &messages, &redactor);
+                let webhook = match (args.slack_webhook, args.discord_webhook) {
+                    (Some(url), _) => publish::Webhook::Slack(url),
+                    (None, Some(url)) => publish::Webhook::Discord(url),
+                    (None, None) => unreachable!("clap requires a webhook"),
+                };
+                webhook.post(&webhook.payload(&session, &messages, selected))?;
+                info!(session = %session.id, messages = messages.len(), "session shared");
+                println!("shared {}", session.id);
+            }
+            SessionsCommand::Delete { session_id } => {
+                store.trash_session(&session_id)?;
+                info!(%session_id, "session moved to trash");
+                println!(
+                    "moved {session_id} to the trash; undo with `remi trash restore {session_id}`"
+                );
+            }
+        },
+        Commands::Trash { command } => match command {
+            TrashCommand::List => {
+                let use_color = ui::color_enabled();
+                for (s, deleted_at) in store.trashed_sessions()? {
+                    println!(
+                        "{} {} {} {}",
+                        s.id,
+                        theme::paint_agent(s.agent.as_str(), s.agent.as_str(), use_color),
+                        deleted_at.to_rfc3339(),
+                        sanitize_title(&s.title)
+                    );
+                }
+            }
+            TrashCommand::Restore { session_id } => {
+                store.restore_session(&session_id)?;
+                info!(%session_id, "session restored");
+                println!("restored {session_id}");
+            }
+            TrashCommand::Empty { before } => {
+                let before = before
+                    .as_deref()
+                    .map(|raw| parse_cutoff("--before", raw))
+                    .transpose()?;
+                let purged = store.empty_trash(before)?;
+                info!(purged, "trash emptied");
+                println!("purged={purged}");
+            }
+        },
+        Commands::Messages { command } => match command {
+            MessagesCommand::Show {
+                message_id,
+                context,
+            } => {
+                let Some(message) = store.get_message(&message_id)? else {
+                    return Err(anyhow::anyhow!("message not found: {message_id}"));
+                };
+                let Some(session) = store.get_session(&message.session_id)? else {
+                    return Err(anyhow::anyhow!("session not found: {}", message.session_id));
+                };
+                let msgs = store.get_session_messages(&session.id)?;
+                let position = msgs
+                    .iter()
+                    .position(|m| m.id == message.id)
+                    .context("message missing from its session")?;
+                let start = position.saturating_sub(context);
+                let end = (position + context + 1).min(msgs.len());
+                info!(position, start, end, "message context resolved");
+                println!(
+                    "session={} agent={} title={}",
+                    session.id,
+                    session.agent,
+                    sanitize_title(&session.title)
+                );
+                println!("position={}/{}", position + 1, msgs.len());
+                println!();
+                for (idx, m) in msgs.iter().enumerate().take(end).skip(start) {
+                    let marker = if idx == position { ">" } else { " " };
+                    println!(
+                        "{marker} {} {} [{}] {}",
+                        m.id,
+                        m.ts.to_rfc3339(),
+                        m.role,
+                        m.content
+                    );
+                }
+            }
+            MessagesCommand::Bookmark { message_id, tags } => {
+                if store.get_message(&message_id)?.is_none() {
+                    bail!("message not found: {message_id}");
+                }
+                store.add_bookmark(&message_id, &tags)?;
+                info!(%message_id, tags = tags.len(), "message bookmarked");
+            }
+            MessagesCommand::Unbookmark { message_id } => {
+                if !store.remove_bookmark(&message_id)? {
+                    bail!("message is not bookmarked: {message_id}");
+                }
+            }
+            MessagesCommand::Bookmarks { tag } => {
+                for bookmark in store.bookmarks(tag.as_deref())? {
+                    let m = &bookmark.message;
+                    println!(
+                        "{}\t{}\t{}\t{}\t{}",
+                        m.id,
+                        m.ts.to_rfc3339(),
+                        m.role,
+                        bookmark.tags.join(","),
+                        query::truncate_text(&m.content.replace('\n', " "), 80)
+                    );
+                }
+            }
+        },
+        Commands::Files { command } => match command {
+            FilesCommand::History { path, limit, json } => {
+                let history = store.file_history(&path, limit as i64)?;
+                info!(sessions = history.len(), "file history loaded");
+                if json {
+                    let rows: Vec<_> = history
+                        .iter()
+                        .map(|h| {
+                            serde_json::json!({
+                                "session_id": h.session_id,
+                                "agent": h.agent.as_str(),
+                                "title": h.title,
+                                "mentions": h.mentions,
+                                "first_ts": h.first_ts.to_rfc3339(),
+                                "last_ts": h.last_ts.to_rfc3339(),
+                                "tools": h.tools,
+                                "paths": h.paths,
+                            })
+                        })
+                        .collect();
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&schema::document("sessions", rows))?
+                    );
+                } else {
+                    let use_color = ui::color_enabled();
+                    for h in &history {
+                        println!(
+                            "{} {} {} {} ({} mentions: {})",
+                            h.last_ts.format("%Y-%m-%d %H:%M"),
+                            h.session_id,
+                            theme::paint_agent(h.agent.as_str(), h.agent.as_str(), use_color),
+                            sanitize_title(&h.title),
+                            h.mentions,
+                            h.tools.join(", ")
+                        );
+                    }
+                }
+            }
+        },
+        Commands::Graph { command } => match command {
+            GraphCommand::Build => {
+                let (nodes, edges) = store.rebuild_graph()?;
+                info!(nodes, edges, elapsed = ?t.elapsed(), "graph rebuilt");
+                println!("nodes={nodes} edges={edges}");
+            }
+            GraphCommand::Export {
+                format,
+                min_sessions,
+                kind,
+            } => {
+                for k in &kind {
+                    k.parse::<core_model::EntityKind>()?;
+                }
+                let kinds: Vec<&str> = kind.iter().map(String::as_str).collect();
+                let graph = store.graph(min_sessions, &kinds)?;
+                info!(
+                    nodes = graph.nodes.len(),
+                    edges = graph.edges.len(),
+                    "graph loaded"
+                );
+                if graph.nodes.is_empty() && min_sessions <= 1 && kinds.is_empty() {
+                    eprintln!("hint: the graph is empty; run `remi graph build` to extract it");
+                }
+                match format {
+                    GraphFormat::Dot => print!("{}", graph::to_dot(&graph)),
+                    GraphFormat::Json => {
+                        println!("{}", serde_json::to_string_pretty(&graph::to_json(&graph))?)
+                    }
+                }
+            }
+        },
+        Commands::Links { command } => match command {
+            LinksCommand::List { since, limit, json } => {
+                let since = match since {
+                    Some(v) => {
+                        let d = humantime::parse_duration(&v).context("invalid --since")?;
+                        Some(Utc::now() - chrono::Duration::from_std(d)?)
+                    }
+                    None => None,
+                };
+                let links = store.list_links(since, limit as i64)?;
+                info!(links = links.len(), "links listed");
+                if json {
+                    let rows: Vec<_> = links
+                        .iter()
+                        .map(|l| {
+                            serde_json::json!({
+                                "url": l.url,
+                                "context": l.context,
+                                "session_id": l.session_id,
+                                "message_id": l.message_id,
+                                "agent": l.agent.as_str(),
+                                "role": l.role,
+                                "ts": l.ts.to_rfc3339(),
+                            })
+                        })
+                        .collect();
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&schema::document("links", rows))?
+                    );
+                } else {
+                    let use_color = ui::color_enabled();
+                    for l in &links {
+                        println!(
+                            "{} {} {} {}",
+                            l.ts.format("%Y-%m-%d %H:%M"),
+                            l.session_id,
+                            theme::paint_agent(l.agent.as_str(), l.agent.as_str(), use_color),
+                            l.url
+                        );
+                        println!("    {}", sanitize_title(&l.context));
+                    }
                 }
             }
-            SessionsCommand::Show { session_id } => {
-                trace!(session_id, "showing session messages");
-                let session = store.get_session(&session_id)?;
-                if session.is_none() {
-                    return Err(anyhow::anyhow!("session not found: {session_id}"));
+        },
+        Commands::CommandRuns { command } => match command {
+            CommandsCommand::Search { query, limit, json } => {
+                let hits = store.search_commands(&query, limit as i64)?;
+                info!(hits = hits.len(), "commands searched");
+                let use_color = ui::color_enabled();
+                let mut rows = Vec::new();
+                for hit in &hits {
+                    let prompt = store
+                        .prompt_before(&hit.message_id)?
+                        .map(|m| core_model::split_reasoning(&m.content).1);
+                    if json {
+                        rows.push(serde_json::json!({
+                            "session_id": hit.session_id,
+                            "message_id": hit.message_id,
+                            "agent": hit.agent.as_str(),
+                            "tool": hit.tool,
+                            "command": hit.command,
+                            "ts": hit.ts.to_rfc3339(),
+                            "prompt": prompt,
+                        }));
+                        continue;
+                    }
+                    println!(
+                        "{} {} {} $ {}",
+                        hit.ts.format("%Y-%m-%d %H:%M"),
+                        hit.session_id,
+                        theme::paint_agent(hit.agent.as_str(), hit.agent.as_str(), use_color),
+                        sanitize_title(&hit.command)
+                    );
+                    if let Some(prompt) = prompt.filter(|p| !p.trim().is_empty()) {
+                        println!("    prompt: {}", sanitize_title(prompt.trim()));
+                    }
                 }
-                let msgs = store.get_session_messages(&session_id)?;
-                info!(messages = msgs.len(), "session messages listed");
-                for m in &msgs {
-                    println!("{} [{}] {}", m.ts.to_rfc3339(), m.role, m.content);
+                if json {
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&schema::document("commands", rows))?
+                    );
                 }
             }
         },
-        Commands::Search { command } => match command {
+        Commands::Last { count } => {
+            let sessions = store.recent_sessions(count as i64)?;
+            info!(sessions = sessions.len(), "recent sessions listed");
+            let use_color = ui::color_enabled();
+            for s in &sessions {
+                let messages = store
+                    .session_aggregate(&s.id)?
+                    .map_or(0, |aggregate| aggregate.message_count);
+                println!(
+                    "{} {} {} {} ({messages} messages)",
+                    s.updated_at.format("%Y-%m-%d %H:%M"),
+                    s.id,
+                    theme::paint_agent(s.agent.as_str(), s.agent.as_str(), use_color),
+                    sanitize_title(&s.title)
+                );
+            }
+        }
+        Commands::Show {
+            session,
+            stats,
+            system_prompts,
+            burst_gap: gap,
+        } => {
+            let session_id = if session == "last" {
+                store
+                    .recent_sessions(1)?
+                    .into_iter()
+                    .next()
+                    .map(|s| s.id)
+                    .context("no sessions stored yet; run `remi sync` first")?
+            } else {
+                session
+            };
+            let gap = burst_gap(gap, &config);
+            print_session(&store, &session_id, stats, system_prompts, gap)?;
+        }
+        Commands::Search { command } => match *command {
             SearchCommand::Query {
                 query,
+                preset,
                 format,
                 no_interactive,
                 select,
@@ -404,13 +1848,46 @@ fn main() -> anyhow::Result<()> {
                 title,
                 id,
                 contains,
+                lang,
+                reasoning,
                 raw_fts,
+                limit,
                 html_safety,
+                redact,
+                sidecars,
                 #[cfg(feature = "semantic")]
                 semantic,
+                #[cfg(feature = "semantic")]
+                rerank,
                 output_dir,
+                template,
+                css,
+                as_of,
+                copy_code,
+                explain,
             } => {
                 info!(query = %query, "searching");
+                let preset = match &preset {
+                    Some(name) => config.search_profile(name)?.clone(),
+                    None => config::SearchProfile::default(),
+                };
+                let format = match format {
+                    Some(format) => format,
+                    None => preset_value("format", preset.format.as_deref())?
+                        .unwrap_or(SearchFormat::Html),
+                };
+                let reasoning = preset_reasoning(reasoning, &preset)?;
+                let raw_fts = raw_fts || preset.raw_fts.unwrap_or(false);
+                let lang = lang.or(preset.lang);
+                let output_dir = output_dir.or(preset.output_dir);
+                let weights = preset.weights.weights()?;
+                let as_of = as_of
+                    .as_deref()
+                    .map(|raw| parse_cutoff("--as-of", raw))
+                    .transpose()?;
+                let template = template
+                    .map(|name| template::Template::load(&config_dir(&profile), &name))
+                    .transpose()?;
                 trace!(no_interactive, raw_fts, "search parameters");
                 #[cfg(feature = "semantic")]
                 let search_embedder = match semantic {
@@ -423,35 +1900,38 @@ fn main() -> anyhow::Result<()> {
                         embedder.as_mut()
                     }
                 };
-                let hits = search::search_sessions(
-                    &store,
-                    &query,
-                    20,
-                    raw_fts,
-                    #[cfg(feature = "semantic")]
-                    search_embedder,
-                    #[cfg(feature = "semantic")]
-                    Some(&mut semantic_cache),
-                )?;
-                debug!(hits = hits.len(), "search returned hits");
-                if hits.is_empty() {
+                #[cfg(feature = "semantic")]
+                let mut reranker = rerank.then(|| load_reranker(&config)).transpose()?;
+                let filters = query::FilterSpec {
+                    agent: agent.or(preset.agent),
+                    title: title.or(preset.title),
+                    id: id.or(preset.id),
+                    contains: contains.or(preset.contains),
+                };
+                trace!(agent = ?filters.agent, title = ?filters.title, id = ?filters.id, contains = ?filters.contains, "applying filters");
+                let request = query::SearchRequest::new(query.clone())
+                    .raw_fts(raw_fts)
+                    .reasoning(reasoning.into())
+                    .lang(lang)
+                    .filters(filters)
+                    .as_of(as_of)
+                    .weights(weights)
+                    .explain(explain);
+                let request = match limit.or(preset.limit) {
+                    Some(limit) => request.limit(limit),
+                    None => request,
+                };
+                #[cfg(feature = "semantic")]
+                let request = request
+                    .embedder(search_embedder)
+                    .cache(Some(&mut semantic_cache))
+                    .reranker(reranker.as_mut());
+                let mut response = request.execute(&store)?;
+                let mut sessions = std::mem::take(&mut response.sessions);
+                if response.unfiltered == 0 {
                     info!(elapsed = ?t.elapsed(), "no results");
                     return Ok(());
                 }
-                let mut sessions = ui::build_session_displays(&store, &hits)?;
-                if sessions.is_empty() {
-                    info!(elapsed = ?t.elapsed(), "no sessions to display");
-                    return Ok(());
-                }
-
-                let filters = ui::FilterSpec {
-                    agent,
-                    title,
-                    id,
-                    contains,
-                };
-                trace!(agent = ?filters.agent, title = ?filters.title, id = ?filters.id, contains = ?filters.contains, "applying filters");
-                sessions = ui::apply_filters(&sessions, &filters);
                 if sessions.is_empty() {
                     return Err(anyhow::anyhow!("no sessions matched filters"));
                 }
@@ -469,14 +1949,16 @@ fn main() -> anyhow::Result<()> {
                     if selected_index >= sessions.len() {
                         return Err(anyhow::anyhow!("index out of range"));
                     }
+                    if explain && !matches!(format, SearchFormat::Json | SearchFormat::Ndjson) {
+                        ui::print_session_list(&sessions, &[]);
+                    }
                     let selected = sessions[selected_index].clone();
                     (selected, selected_index, sessions)
                 } else {
                     info!(sessions = sessions.len(), elapsed = ?t.elapsed(), "sessions matched");
                     ui::print_session_list(&sessions, &[]);
-                    let filter = ui::prompt_line(
-                        "filter (fuzzy; fields: agent:, title:, id:, contains:; example: \"agent:claude auth login\") — enter to keep: ",
-                    )?;
+                    let filter =
+                        ui::prompt_line(&format!("{} ", i18n::text("search-filter-prompt")))?;
                     let (mut filtered, terms) = if filter.trim().is_empty() {
                         (sessions.clone(), Vec::new())
                     } else {
@@ -489,53 +1971,768 @@ fn main() -> anyhow::Result<()> {
                         info!(sessions = filtered.len(), "sessions matched filter");
                     }
                     ui::print_session_list(&filtered, &terms);
-                    let choice = ui::prompt_line("select index (default 0): ")?;
-                    let selected_index = ui::parse_index(&choice, filtered.len())?;
+                    let mut preview_terms = query::query_terms(&query);
+                    preview_terms.extend(terms.iter().map(|term| term.to_lowercase()));
+                    let selected_index = loop {
+                        let choice =
+                            ui::prompt_line(&format!("{} ", i18n::text("search-select-prompt")))?;
+                        let selected_index = ui::parse_index(&choice, filtered.len())?;
+                        ui::print_preview(&filtered[selected_index], &preview_terms);
+                        let confirm =
+                            ui::prompt_line(&format!("{} ", i18n::text("search-open-prompt")))?;
+                        if !confirm.trim().eq_ignore_ascii_case("n") {
+                            break selected_index;
+                        }
+                    };
                     let selected = filtered[selected_index].clone();
                     (selected, selected_index, filtered)
                 };
 
-                if matches!(format, SearchFormat::Json) {
-                    let sessions = sessions
-                        .into_iter()
-                        .map(|item| ui::JsonSession {
-                            id: item.session_id,
-                            title: item.title,
-                            agent: item.agent,
-                            updated_at: item.updated_at,
-                            message_count: item.message_count,
-                            snippet: item.snippet,
-                            score: item.score,
-                        })
-                        .collect();
-                    let output = ui::JsonSearchOutput {
-                        query: query.clone(),
-                        selected_index,
-                        selected_session_id: selected.session_id.clone(),
-                        sessions,
+                if let Some(n) = copy_code {
+                    let message = store
+                        .get_message(&selected.top_message_id)?
+                        .with_context(|| "top hit message missing")?;
+                    let blocks = browse::code_blocks(&message.content);
+                    let Some(block) = blocks.get(n.saturating_sub(1)) else {
+                        bail!(
+                            "top hit {} has {} code block(s), not {n}; step through the session with `remi sessions browse {}`",
+                            message.id,
+                            blocks.len(),
+                            selected.session_id
+                        );
                     };
+                    let block = if redact {
+                        let rules = config.redact.as_ref();
+                        redact::Redactor::new(
+                            rules.map(|r| r.patterns.as_slice()).unwrap_or_default(),
+                            rules.and_then(|r| r.replacement.as_deref()),
+                        )?
+                        .redact(block)
+                        .into_owned()
+                    } else {
+                        block.clone()
+                    };
+                    let via = browse::copy(&block)?;
+                    info!(message = %message.id, block = n, via, "copied code block");
+                    println!(
+                        "copied code block {n} of {} ({} lines) from {} session {} via {via}",
+                        blocks.len(),
+                        block.lines().count(),
+                        selected.agent,
+                        selected.session_id
+                    );
+                    return Ok(());
+                }
+
+                if template.is_none() && matches!(format, SearchFormat::Json) {
+                    response.sessions = sessions;
+                    let output = response.into_json(selected_index);
                     println!("{}", serde_json::to_string_pretty(&output)?);
                     return Ok(());
                 }
-
-                let session = store
-                    .get_session(&selected.session_id)?
-                    .with_context(|| "selected session missing")?;
-                let messages = store.get_session_messages(&selected.session_id)?;
+                if template.is_none() && matches!(format, SearchFormat::Ndjson) {
+                    let mut writer = ndjson::Writer::open(None)?;
+                    for (rank, session) in sessions.into_iter().enumerate() {
+                        if writer.closed() {
+                            break;
+                        }
+                        writer.write(&query::ndjson_line(&query, rank, session))?;
+                    }
+                    writer.finish()?;
+                    return Ok(());
+                }
+
+                let store_sqlite::SessionBundle {
+                    mut session,
+                    mut messages,
+                    provenance,
+                    ..
+                } = store
+                    .get_session_bundle(&selected.session_id)?
+                    .with_context(|| "selected session missing")?;
+                if let Some(cutoff) = as_of {
+                    let known =
+                        store.ingested_before(messages.iter().map(|m| m.id.as_str()), cutoff)?;
+                    messages.retain(|m| known.contains(&m.id));
+                }
+                let redactor = if redact {
+                    let rules = config.redact.as_ref();
+                    let redactor = redact::Redactor::new(
+                        rules.map(|r| r.patterns.as_slice()).unwrap_or_default(),
+                        rules.and_then(|r| r.replacement.as_deref()),
+                    )?;
+                    session.title = redactor.redact(&session.title).into_owned();
+                    let redacted = redactor.redact_messages(&mut messages);
+                    info!(messages = redacted, "redacted messages for export");
+                    Some(redactor)
+                } else {
+                    None
+                };
+                let out_dir = ui::resolve_output_dir(output_dir)?;
+                if let Some(template) = &template {
+                    let rendered = render_template(&store, template, &session, &messages)?;
+                    let file_path =
+                        out_dir.join(format!("session_{}.{}", session.id, template.extension));
+                    std::fs::write(&file_path, rendered)?;
+                    println!("{}", file_path.display());
+                    return Ok(());
+                }
+                let sidecar_dir = format!("session_{}_files", session.id);
                 let rendered = match format {
                     SearchFormat::Html => {
-                        render::render_session_html(&session, &messages, html_safety)?
+                        let session_stats = store
+                            .session_aggregate(&session.id)?
+                            .map(|aggregate| stats::SessionStats::new(aggregate, &messages));
+                        let export = render::render_session_html(
+                            &session,
+                            &messages,
+                            &provenance,
+                            session_stats.as_ref(),
+                            html_safety,
+                            sidecars.then_some(sidecar_dir.as_str()),
+                            custom_css(css, &config)?.as_deref(),
+                            burst_gap(None, &config),
+                        )?;
+                        if !export.sidecars.is_empty() {
+                            let dir = out_dir.join(&sidecar_dir);
+                            std::fs::create_dir_all(&dir)?;
+                            for sidecar in &export.sidecars {
+                                std::fs::write(dir.join(&sidecar.file_name), &sidecar.content)?;
+                            }
+                            info!(files = export.sidecars.len(), dir = %dir.display(), "wrote sidecar files");
+                        }
+                        export.html
                     }
                     SearchFormat::Markdown => render::render_session_markdown(&session, &messages),
-                    SearchFormat::Json => unreachable!("handled earlier"),
+                    SearchFormat::Rawjson => {
+                        let mut records = store.session_raw_records(&session.id)?;
+                        if records.is_empty() {
+                            bail!(
+                                "no raw payloads retained for session {}; set [capture] raw_records = true and run `remi backfill`",
+                                session.id
+                            );
+                        }
+                        if let Some(redactor) = &redactor {
+                            for record in &mut records {
+                                redactor.redact_value(&mut record.payload);
+                            }
+                        }
+                        render::render_raw_records(&records, &provenance)?
+                    }
+                    SearchFormat::Json | SearchFormat::Ndjson => unreachable!("handled earlier"),
                 };
-                let out_dir = ui::resolve_output_dir(output_dir)?;
                 let file_path =
                     out_dir.join(format!("session_{}.{}", session.id, format.extension()));
                 std::fs::write(&file_path, rendered)?;
                 println!("{}", file_path.display());
             }
+            SearchCommand::Batch {
+                file,
+                format,
+                preset,
+                agent,
+                lang,
+                reasoning,
+                raw_fts,
+                limit,
+                #[cfg(feature = "semantic")]
+                semantic,
+                #[cfg(feature = "semantic")]
+                rerank,
+                as_of,
+                explain,
+            } => {
+                let mut settings = preset_settings(&config, preset.as_deref())?;
+                if let Some(reasoning) = reasoning {
+                    settings.reasoning = reasoning.into();
+                }
+                settings.raw_fts |= raw_fts;
+                settings.lang = lang.or(settings.lang);
+                settings.limit = limit.unwrap_or(settings.limit);
+                settings.filters.agent = agent.or(settings.filters.agent);
+                settings.as_of = as_of
+                    .as_deref()
+                    .map(|raw| parse_cutoff("--as-of", raw))
+                    .transpose()?;
+                settings.explain = explain;
+                #[cfg(feature = "semantic")]
+                if matches!(semantic, SemanticMode::On) && embedder.is_none() {
+                    tracing::warn!("semantic search requested but no embedder configured");
+                }
+                #[cfg(feature = "semantic")]
+                let mut reranker = rerank.then(|| load_reranker(&config)).transpose()?;
+                let queries = batch::read_queries(&file)?;
+                info!(queries = queries.len(), "running search batch");
+                // The embedder, reranker and vector cache are loaded once and
+                // shared by every query.
+                let mut results = Vec::with_capacity(queries.len());
+                let mut lines = matches!(format, BatchFormat::Jsonl)
+                    .then(|| ndjson::Writer::open(None))
+                    .transpose()?;
+                for (i, query) in queries.iter().enumerate() {
+                    if lines.as_ref().is_some_and(ndjson::Writer::closed) {
+                        break;
+                    }
+                    let request = settings.request(query);
+                    #[cfg(feature = "semantic")]
+                    let request = request
+                        .embedder(match semantic {
+                            SemanticMode::Off => None,
+                            SemanticMode::Auto | SemanticMode::On => embedder.as_mut(),
+                        })
+                        .cache(Some(&mut semantic_cache))
+                        .reranker(reranker.as_mut());
+                    let output = request
+                        .execute(&store)
+                        .with_context(|| format!("query {} ({query:?})", i + 1))?
+                        .into_json(0);
+                    match lines.as_mut() {
+                        Some(writer) => writer.write(&output)?,
+                        None => results.push(output),
+                    }
+                }
+                if let Some(writer) = lines {
+                    writer.finish()?;
+                } else {
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&batch::document(results)?)?
+                    );
+                }
+                info!(queries = queries.len(), elapsed = ?t.elapsed(), "search batch done");
+            }
+        },
+        Commands::Eval {
+            gold,
+            ks,
+            preset,
+            agent,
+            raw_fts,
+            #[cfg(feature = "semantic")]
+            semantic,
+            #[cfg(feature = "semantic")]
+            rerank,
+            json,
+        } => {
+            let mut ks = ks;
+            ks.sort_unstable();
+            ks.dedup();
+            if ks.first() == Some(&0) {
+                bail!("--k values must be at least 1");
+            }
+            let mut settings = preset_settings(&config, preset.as_deref())?;
+            settings.raw_fts |= raw_fts;
+            settings.filters.agent = agent.or(settings.filters.agent);
+            // Only the top max(k) results can count, whatever the preset says.
+            settings.limit = ks.last().copied().unwrap_or(query::DEFAULT_LIMIT);
+            #[cfg(feature = "semantic")]
+            if matches!(semantic, SemanticMode::On) && embedder.is_none() {
+                tracing::warn!("semantic search requested but no embedder configured");
+            }
+            #[cfg(feature = "semantic")]
+            let mut reranker = rerank.then(|| load_reranker(&config)).transpose()?;
+            let cases = eval::read_gold(&gold)?;
+            let mut results = Vec::with_capacity(cases.len());
+            for (i, case) in cases.iter().enumerate() {
+                let request = settings.request(&case.query);
+                #[cfg(feature = "semantic")]
+                let request = request
+                    .embedder(match semantic {
+                        SemanticMode::Off => None,
+                        SemanticMode::Auto | SemanticMode::On => embedder.as_mut(),
+                    })
+                    .cache(Some(&mut semantic_cache))
+                    .reranker(reranker.as_mut());
+                let ranked: Vec<eval::Ranked> = request
+                    .execute(&store)
+                    .with_context(|| format!("query {} ({:?})", i + 1, case.query))?
+                    .sessions
+                    .into_iter()
+                    .map(|s| eval::Ranked {
+                        session_id: s.session_id,
+                        top_message_id: s.top_message_id,
+                    })
+                    .collect();
+                results.push(eval::score_case(case, &ranked, &ks));
+            }
+            let report = eval::report(results, &ks);
+            info!(queries = report.queries, mrr = report.mrr, elapsed = ?t.elapsed(), "eval done");
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report.to_json()?)?);
+            } else {
+                print!("{}", report.to_text());
+            }
+        }
+        Commands::Stats { command } => match command {
+            StatsCommand::Tools { agent, limit, json } => {
+                let agent = agent.map(|a| a.parse::<AgentKind>()).transpose()?;
+                let mut analytics = stats::ToolAnalytics::default();
+                let mut scanned = 0usize;
+                for s in store.list_sessions()? {
+                    if agent.is_some_and(|a| a != s.agent) {
+                        continue;
+                    }
+                    let messages = store.get_session_messages(&s.id)?;
+                    analytics.add_session(s.agent.as_str(), &messages);
+                    scanned += 1;
+                }
+                let rows = analytics.rows(limit);
+                info!(
+                    sessions = scanned,
+                    tools = rows.len(),
+                    "tool usage aggregated"
+                );
+                if json {
+                    let rows: Vec<_> = rows
+                        .iter()
+                        .map(|r| {
+                            serde_json::json!({
+                                "agent": r.agent,
+                                "tool": r.tool,
+                                "calls": r.calls,
+                                "results": r.results,
+                                "failures": r.failures,
+                                "failure_rate": r.failure_rate(),
+                                "avg_output_bytes": r.avg_output_bytes(),
+                            })
+                        })
+                        .collect();
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&schema::document("tools", rows))?
+                    );
+                } else {
+                    for r in rows {
+                        println!(
+                            "agent={} tool={} calls={} results={} failures={} failure_rate={:.1}% avg_output_bytes={}",
+                            r.agent,
+                            r.tool,
+                            r.calls,
+                            r.results,
+                            r.failures,
+                            r.failure_rate() * 100.0,
+                            r.avg_output_bytes()
+                        );
+                    }
+                }
+            }
+            StatsCommand::Storage { json } => {
+                let report =
+                    stats::StorageReport::new(store.storage_usage()?, &archive::archive_dir());
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                } else {
+                    for line in report.lines() {
+                        println!("{line}");
+                    }
+                }
+            }
+            StatsCommand::Projects { json } => {
+                let projects = store.project_summaries()?;
+                if json {
+                    let rows: Vec<_> = projects
+                        .iter()
+                        .map(|p| {
+                            serde_json::json!({
+                                "repo": p.remote,
+                                "sessions": p.session_count,
+                                "messages": p.message_count,
+                                "last_active": p.last_ts.to_rfc3339(),
+                            })
+                        })
+                        .collect();
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&schema::document("projects", rows))?
+                    );
+                } else if projects.is_empty() {
+                    println!(
+                        "no sessions with a resolved git repository; workspaces are resolved when synced"
+                    );
+                } else {
+                    for p in projects {
+                        println!(
+                            "repo={} sessions={} messages={} last_active={}",
+                            p.remote,
+                            p.session_count,
+                            p.message_count,
+                            p.last_ts.to_rfc3339()
+                        );
+                    }
+                }
+            }
+            StatsCommand::Heatmap {
+                agent,
+                year,
+                format,
+            } => {
+                let agent = agent.map(|a| a.parse::<AgentKind>()).transpose()?;
+                let (from, to) = heatmap::Heatmap::range(year, Utc::now().date_naive())?;
+                let counts = store.messages_per_day(agent, from, to)?;
+                debug!(days = counts.len(), %from, %to, "daily message counts loaded");
+                let heatmap = heatmap::Heatmap::new(from, to, counts);
+                match format {
+                    HeatmapFormat::Text => {
+                        print!("{}", heatmap.render_terminal(ui::color_enabled()))
+                    }
+                    HeatmapFormat::Svg => print!("{}", heatmap.render_svg()),
+                    HeatmapFormat::Html => {
+                        let title = match agent {
+                            Some(agent) => format!("{} activity, {from} to {to}", agent.as_str()),
+                            None => format!("Agent activity, {from} to {to}"),
+                        };
+                        print!("{}", heatmap.render_html(&title));
+                    }
+                }
+            }
         },
+        Commands::Profile { .. }
+        | Commands::Secrets { .. }
+        | Commands::Render { .. }
+        | Commands::Schema { .. }
+        | Commands::Debug { .. } => {
+            unreachable!("profile, secrets and render commands run before the store opens")
+        }
+        Commands::Serve { addr, redact } => {
+            let listener = std::net::TcpListener::bind(&addr)
+                .with_context(|| format!("failed to listen on {addr}"))?;
+            let local = listener.local_addr()?;
+            let redactor = if redact {
+                let rules = config.redact.as_ref();
+                Some(redact::Redactor::new(
+                    rules.map(|r| r.patterns.as_slice()).unwrap_or_default(),
+                    rules.and_then(|r| r.replacement.as_deref()),
+                )?)
+            } else {
+                None
+            };
+            let mut server = serve::Server::new(&mut store, local.port(), redactor);
+            if let Some(css) = custom_css(None, &config)? {
+                server.custom_css(css);
+            }
+            server.burst_gap(burst_gap(None, &config));
+            if !local.ip().is_loopback() {
+                tracing::warn!(%local, "serving on a non-loopback address without authentication");
+                server.allow_host(addr.clone());
+            }
+            println!("serving on http://{local}/");
+            server.run(listener)?;
+        }
+        #[cfg(feature = "grpc")]
+        Commands::Grpc {
+            addr,
+            interval,
+            redact,
+        } => {
+            let interval = humantime::parse_duration(&interval).context("invalid --interval")?;
+            let listener = std::net::TcpListener::bind(&addr)
+                .with_context(|| format!("failed to listen on {addr}"))?;
+            let local = listener.local_addr()?;
+            let mut service = remi_grpc::Service::new(store).with_interval(interval);
+            if redact {
+                let rules = config.redact.as_ref();
+                let redactor = redact::Redactor::new(
+                    rules.map(|r| r.patterns.as_slice()).unwrap_or_default(),
+                    rules.and_then(|r| r.replacement.as_deref()),
+                )?;
+                service = service.with_redact(std::sync::Arc::new(move |text: &str| {
+                    redactor.redact(text).into_owned()
+                }));
+            }
+            if !local.ip().is_loopback() {
+                tracing::warn!(%local, "serving gRPC on a non-loopback address without authentication");
+            }
+            println!("serving gRPC on {local}");
+            remi_grpc::serve(service, listener)?;
+        }
+        Commands::Export {
+            format: ExportFormat::Anki,
+            filter,
+            out,
+            anonymize,
+        } => {
+            let tag = anki::parse_filter(filter.as_deref())?;
+            let bookmarks = store.bookmarks(tag.as_deref())?;
+            let mut cards = anki::cards(&store, &bookmarks)?;
+            info!(
+                bookmarks = bookmarks.len(),
+                cards = cards.len(),
+                "anki cards built"
+            );
+            if anonymize {
+                let mut anon = anonymize::Anonymizer::new().with_environment();
+                for card in &cards {
+                    anon.observe(&card.front);
+                    anon.observe(&card.back);
+                }
+                for card in &mut cards {
+                    card.front = anon.anonymize(&card.front);
+                    card.back = anon.anonymize(&card.back);
+                }
+            }
+            let csv = anki::render_csv(&cards);
+            match out {
+                Some(path) => {
+                    std::fs::write(&path, csv)
+                        .with_context(|| format!("failed to write {}", path.display()))?;
+                    eprintln!("wrote {} cards to {}", cards.len(), path.display());
+                }
+                None => print!("{csv}"),
+            }
+        }
+        Commands::Export {
+            format: ExportFormat::Jsonl,
+            filter,
+            out,
+            anonymize,
+        } => {
+            if filter.is_some() {
+                anyhow::bail!("--filter only applies to --format anki");
+            }
+            let sessions = store.list_sessions()?;
+            // Pseudonyms need every username and hostname up front, so an
+            // anonymized export reads the sessions twice.
+            let mut anon = anonymize.then(|| anonymize::Anonymizer::new().with_environment());
+            if let Some(anon) = anon.as_mut() {
+                for session in &sessions {
+                    anon.observe(&session.title);
+                    for m in store.get_session_messages(&session.id)? {
+                        anon.observe(&m.content);
+                    }
+                }
+            }
+            let mut writer = ndjson::Writer::open(out.as_deref())?;
+            for (i, session) in sessions.iter().enumerate() {
+                if writer.closed() {
+                    break;
+                }
+                let messages = store.get_session_messages(&session.id)?;
+                writer.write(&corpus::line(i + 1, session, &messages, anon.as_mut()))?;
+            }
+            let written = writer.lines();
+            writer.finish()?;
+            info!(sessions = written, anonymize, "corpus exported");
+            if let Some(path) = &out {
+                eprintln!("wrote {written} sessions to {}", path.display());
+            }
+        }
+        Commands::Compare {
+            query,
+            window,
+            json,
+        } => {
+            let window = humantime::parse_duration(&window).context("invalid --window")?;
+            let comparison =
+                compare::Comparison::build(&store, &query, chrono::Duration::from_std(window)?)?;
+            debug!(runs = comparison.runs.len(), "agents compared");
+            if json {
+                println!("{}", serde_json::to_string_pretty(&comparison.to_json())?);
+            } else {
+                print!("{}", comparison.render_terminal());
+            }
+        }
+        Commands::Digest {
+            date,
+            out_dir,
+            stdout,
+            watch,
+        } => {
+            let out_dir = out_dir.or(config.digest.notes_dir.clone());
+            let webhook = match (&config.digest.slack_webhook, &config.digest.discord_webhook) {
+                (Some(url), _) => Some(publish::Webhook::Slack(url.clone())),
+                (None, Some(url)) => Some(publish::Webhook::Discord(url.clone())),
+                (None, None) => None,
+            };
+            let rules = config.redact.as_ref();
+            let redactor = redact::Redactor::new(
+                rules.map(|r| r.patterns.as_slice()).unwrap_or_default(),
+                rules.and_then(|r| r.replacement.as_deref()),
+            )?;
+            let deliver = |day: chrono::NaiveDate| -> anyhow::Result<()> {
+                let digest = digest::Digest::build(&store, day, &chrono::Local)?;
+                debug!(%day, sessions = digest.sessions.len(), "digest built");
+                let markdown = digest.render_markdown();
+                if stdout {
+                    print!("{markdown}");
+                    return Ok(());
+                }
+                match &out_dir {
+                    Some(dir) => {
+                        std::fs::create_dir_all(dir)
+                            .with_context(|| format!("failed to create {}", dir.display()))?;
+                        let path = dir.join(digest.file_name());
+                        std::fs::write(&path, &markdown)
+                            .with_context(|| format!("failed to write {}", path.display()))?;
+                        println!("{}", path.display());
+                    }
+                    None if webhook.is_none() => print!("{markdown}"),
+                    None => {}
+                }
+                if let Some(webhook) = &webhook {
+                    webhook.post(&webhook.text(&redactor.redact(&markdown)))?;
+                    info!(%day, "digest posted");
+                }
+                Ok(())
+            };
+            if watch {
+                let at = config.digest.at()?;
+                // Remi has no daemon; this loop is one, so keep `remi sync`
+                // running on its own schedule to have the day's sessions in.
+                loop {
+                    let (run, day) = digest::next_run(&chrono::Local::now(), at);
+                    info!(%run, %day, "next digest");
+                    let wait = (run - Utc::now()).to_std().unwrap_or_default();
+                    std::thread::sleep(wait);
+                    if let Err(e) = deliver(day) {
+                        tracing::warn!(%day, error = %format!("{e:#}"), "digest failed");
+                    }
+                }
+            }
+            // Defaults to yesterday so a job scheduled just after midnight
+            // summarizes the day that just ended.
+            let day = match date {
+                Some(raw) => chrono::NaiveDate::parse_from_str(&raw, "%Y-%m-%d")
+                    .with_context(|| format!("invalid --date {raw:?}; expected YYYY-MM-DD"))?,
+                None => chrono::Local::now().date_naive() - chrono::Days::new(1),
+            };
+            deliver(day)?;
+        }
+        Commands::Wipe(args) => {
+            let criteria = wipe::Criteria {
+                project: args.project.as_deref().map(wipe::Project::resolve),
+                pattern: args
+                    .matching
+                    .as_deref()
+                    .map(regex::Regex::new)
+                    .transpose()
+                    .context("invalid --matching regex")?,
+            };
+            if let Some(project) = &criteria.project {
+                debug!(path = ?project.path, remote = ?project.repo.as_ref().map(|r| &r.remote), "wipe project resolved");
+            }
+            let sessions = wipe::select(&store, &criteria)?;
+            let archives = archive::wipe_bundles(
+                &archive::archive_dir(),
+                |session, messages, provenance| criteria.matches(session, messages, provenance),
+                args.dry_run,
+            )?;
+            let mut report = wipe::Report {
+                wiped_at: Utc::now(),
+                dry_run: args.dry_run,
+                project: args.project.clone(),
+                matching: args.matching.clone(),
+                sessions,
+                counts: None,
+                cached_queries: 0,
+                archives,
+            };
+            if !args.dry_run {
+                let ids: Vec<String> = report
+                    .sessions
+                    .iter()
+                    .map(|w| w.session.id.clone())
+                    .collect();
+                report.counts = Some(store.wipe_sessions(&ids)?);
+                if let Some(re) = &criteria.pattern {
+                    report.cached_queries = store.delete_cached_queries(|q| re.is_match(q))?;
+                }
+                // The graph is derived data; rebuild it so wiped files and
+                // repositories drop out of it.
+                if !ids.is_empty() && !store.graph(1, &[])?.nodes.is_empty() {
+                    store.rebuild_graph()?;
+                }
+            }
+            info!(
+                sessions = report.sessions.len(),
+                dry_run = args.dry_run,
+                "wipe finished"
+            );
+            if let Some(path) = &args.report {
+                std::fs::write(path, serde_json::to_string_pretty(&report.to_json())?)
+                    .with_context(|| format!("failed to write {}", path.display()))?;
+            }
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&report.to_json())?);
+            } else {
+                print!("{}", report.to_text());
+            }
+        }
+        Commands::Rollup {
+            period,
+            date,
+            count,
+            dry_run,
+        } => {
+            // Without --date the most recent complete period is rolled up,
+            // and --count walks back from there.
+            let period = rollup::Period::from(period);
+            let now = Utc::now();
+            let mut start = match date {
+                Some(raw) => period.start(
+                    chrono::NaiveDate::parse_from_str(&raw, "%Y-%m-%d")
+                        .with_context(|| format!("invalid --date {raw:?}; expected YYYY-MM-DD"))?,
+                ),
+                None => period.previous(period.start(now.date_naive())),
+            };
+            let mut starts = Vec::new();
+            for _ in 0..count.max(1) {
+                starts.push(start);
+                start = period.previous(start);
+            }
+            starts.reverse();
+            let mut written = 0usize;
+            for start in starts {
+                for rollup in rollup::build(&store, period, start)? {
+                    if dry_run {
+                        println!("{}", rollup.render_markdown());
+                        continue;
+                    }
+                    let batch = rollup.to_batch(now);
+                    store.save_batch(&batch)?;
+                    written += 1;
+                    println!(
+                        "rollup {} {} {} {} sessions={}",
+                        batch.sessions[0].id,
+                        period.as_str(),
+                        start,
+                        rollup.project,
+                        rollup.digest.sessions.len()
+                    );
+                }
+            }
+            if written == 0 && !dry_run {
+                eprintln!("hint: no sessions were active in the selected period");
+            }
+            info!(written, "rollups stored");
+        }
+        Commands::Tail {
+            agent,
+            session,
+            lines,
+            interval,
+            json,
+        } => {
+            if let Some(agent) = &agent {
+                agent.parse::<AgentKind>()?;
+            }
+            let interval = humantime::parse_duration(&interval).context("invalid --interval")?;
+            let mut tail = tail::Tail::new(&store, agent, session)?;
+            let use_color = ui::color_enabled();
+            let print = |line: &tail::TailLine| {
+                if json {
+                    println!("{}", line.to_json());
+                } else {
+                    println!("{}", line.render(use_color));
+                }
+            };
+            tail.backlog(&store, lines)?.iter().for_each(print);
+            // Remi has no daemon; new rows appear when `remi sync` runs
+            // elsewhere and are picked up on the next poll.
+            loop {
+                std::thread::sleep(interval);
+                tail.poll(&store)?.iter().for_each(print);
+            }
+        }
         Commands::Archive { command } => match command {
             ArchiveCommand::Plan {
                 older_than,
@@ -582,9 +2779,11 @@ fn main() -> anyhow::Result<()> {
             }
         },
         #[cfg(feature = "semantic")]
-        Commands::Embed { rebuild } => {
+        Commands::Embed { rebuild, drain } => {
             if let Some(embedder) = embedder.as_mut() {
-                if rebuild {
+                if drain {
+                    drain_embeddings(&store, embedder, t)?;
+                } else if rebuild {
                     info!("rebuilding embeddings");
                     let sessions = store.list_sessions()?;
                     let mut count = 0;
@@ -595,7 +2794,7 @@ fn main() -> anyhow::Result<()> {
                                 continue;
                             }
                             if let Ok(vec) = embedder.embed(&m.content, false) {
-                                store.save_embedding(&m.id, &vec)?;
+                                store.save_embedding(&m.id, embedder.model_id(), &vec)?;
                                 count += 1;
                             }
                         }
@@ -605,19 +2804,120 @@ fn main() -> anyhow::Result<()> {
                     }
                     info!(count, elapsed = ?t.elapsed(), "computed embeddings");
                 } else {
-                    info!("use --rebuild to rebuild all embeddings");
+                    info!(
+                        pending = store.embedding_queue_len()?,
+                        "use --drain to embed queued messages or --rebuild to rebuild all embeddings"
+                    );
                 }
             } else {
                 info!("semantic search not enabled or configured");
             }
         }
-        Commands::Doctor => {
+        #[cfg(feature = "semantic")]
+        Commands::Cluster {
+            k,
+            agent,
+            map,
+            json,
+        } => {
+            let agent = agent.map(|a| a.parse::<AgentKind>()).transpose()?;
+            // Without a loaded model, cluster the vectors most messages have.
+            let model = match embedder.as_ref() {
+                Some(embedder) => embedder.model_id().to_string(),
+                None => store
+                    .embedding_models()?
+                    .into_iter()
+                    .next()
+                    .map(|(model, _)| model)
+                    .unwrap_or_default(),
+            };
+            let items = cluster::load(&store, &model, agent)?;
+            let k = k.unwrap_or_else(|| cluster::default_k(items.len()));
+            info!(sessions = items.len(), k, "clustering sessions");
+            let report = cluster::build(items, k)?;
+            info!(clusters = report.clusters.len(), elapsed = ?t.elapsed(), "clustering done");
+            if let Some(path) = &map {
+                std::fs::write(path, report.render_html())
+                    .with_context(|| format!("writing {}", path.display()))?;
+                info!(path = %path.display(), "cluster map written");
+            }
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report.to_json())?);
+            } else {
+                print!("{}", report.to_text());
+            }
+        }
+        Commands::Doctor { repair, .. } => {
             info!("running integrity check");
             let check = store.integrity_check()?;
             let sessions = store.list_sessions()?;
+            let fts = store.fts_health()?;
             info!(elapsed = ?t.elapsed(), "integrity check done");
             println!("integrity_check={check}");
             println!("sessions={}", sessions.len());
+            println!("fts_stray_rows={}", fts.stray_rows);
+            println!("fts_missing_rows={}", fts.missing_rows);
+            let orphans = store.orphan_rows()?;
+            println!("orphan_embeddings={}", orphans.embeddings);
+            println!("orphan_provenance={}", orphans.provenance);
+            let raw = store.raw_records_usage()?;
+            println!("raw_records={}", raw.records);
+            println!("raw_records_bytes={}", raw.bytes);
+            #[cfg(feature = "semantic")]
+            {
+                let queued = store.embedding_queue_len()?;
+                let failed = store.failed_embedding_jobs(ingest::EMBED_MAX_ATTEMPTS)?;
+                println!("embedding_queue={queued}");
+                println!("embedding_queue_failed={failed}");
+                if queued > failed {
+                    println!(
+                        "hint: run `remi embed --drain` to embed {} queued messages",
+                        queued - failed
+                    );
+                }
+            }
+            if repair {
+                let fixed = store.repair_fts()?;
+                println!(
+                    "fts_repaired stray_rows={} missing_rows={}",
+                    fixed.stray_rows, fixed.missing_rows
+                );
+                let removed = store.repair_orphans()?;
+                println!(
+                    "orphans_removed embeddings={} provenance={}",
+                    removed.embeddings, removed.provenance
+                );
+            } else if fts != store_sqlite::FtsHealth::default()
+                || orphans != store_sqlite::OrphanRows::default()
+            {
+                println!(
+                    "hint: run `remi doctor --repair` to rebuild the affected FTS rows and drop orphans"
+                );
+            }
+            for (name, adapter) in remi::adapters(
+                |kind| config.discovery.options_for(kind),
+                config.capture.options(),
+                |kind| config.timezone_for(kind),
+            ) {
+                let Some(home) = sources.home_for(adapter.kind()) else {
+                    println!(
+                        "adapter={name} label={} home=missing sources=0",
+                        adapter.source_label()
+                    );
+                    println!(
+                        "hint: no home directory; set {} to scan {name}",
+                        adapter_common::source_home_env_var(adapter.kind())
+                    );
+                    continue;
+                };
+                let paths = adapter.discover_source_paths(&sources)?;
+                println!(
+                    "adapter={name} label={} home={} sources={}",
+                    adapter.source_label(),
+                    home.display(),
+                    paths.len()
+                );
+            }
         }
     }
 
@@ -643,11 +2943,11 @@ struct ScannedDoc {
     content: String,
 }
 
-fn index_docs_root(root: &Path) -> anyhow::Result<DocsIndexSummary> {
-    index_docs_root_with_db(root, &default_db_path())
-}
-
-fn index_docs_root_with_db(root: &Path, db_path: &Path) -> anyhow::Result<DocsIndexSummary> {
+fn index_docs_root(
+    root: &Path,
+    db_path: &Path,
+    key: Option<&str>,
+) -> anyhow::Result<DocsIndexSummary> {
     let canonical_root = root
         .canonicalize()
         .with_context(|| format!("canonicalizing root {}", root.display()))?;
@@ -659,7 +2959,7 @@ fn index_docs_root_with_db(root: &Path, db_path: &Path) -> anyhow::Result<DocsIn
         fs::create_dir_all(parent)
             .with_context(|| format!("creating parent dir for {}", db_path.display()))?;
     }
-    let mut store = SqliteStore::open(db_path)
+    let mut store = SqliteStore::open_with_key(db_path, key)
         .with_context(|| format!("opening sqlite db {}", db_path.display()))?;
     store.init_schema()?;
 
@@ -820,13 +3120,6 @@ fn scan_docs_tree(root: &Path) -> anyhow::Result<DocsScan> {
     })
 }
 
-fn default_db_path() -> PathBuf {
-    dirs::data_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("remi")
-        .join("remi.db")
-}
-
 fn normalize_relative_path(root: &Path, path: &Path) -> anyhow::Result<String> {
     let rel = path
         .strip_prefix(root)
@@ -868,36 +3161,150 @@ fn doc_title(path: &Path, content: &str) -> String {
         .unwrap_or_else(|| path.display().to_string())
 }
 
+// `--css` wins over `[export] css`.
+fn custom_css(path: Option<PathBuf>, config: &config::Config) -> anyhow::Result<Option<String>> {
+    let Some(path) = path.or_else(|| config.export.css.clone()) else {
+        return Ok(None);
+    };
+    std::fs::read_to_string(&path)
+        .map(Some)
+        .with_context(|| format!("failed to read CSS file {}", path.display()))
+}
+
+fn burst_gap(flag: Option<u64>, config: &config::Config) -> u64 {
+    flag.or(config.export.burst_gap_minutes)
+        .unwrap_or(stats::DEFAULT_BURST_GAP_MINUTES)
+}
+
+fn config_dir(profile: &profile::Profile) -> PathBuf {
+    profile
+        .config_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+}
+
+fn render_template(
+    store: &SqliteStore,
+    template: &template::Template,
+    session: &core_model::Session,
+    messages: &[core_model::Message],
+) -> anyhow::Result<String> {
+    let stats = store
+        .session_aggregate(&session.id)?
+        .map(|aggregate| stats::SessionStats::new(aggregate, messages));
+    template.render(&template::session_context(
+        session,
+        messages,
+        stats.as_ref(),
+    ))
+}
+
+// A configured env var must be set; otherwise the keyring secret is tried
+// before falling back to `GITHUB_TOKEN`.
+fn github_token(config: &config::Config) -> anyhow::Result<String> {
+    if let Some(var) = config.publish.github_token_env.as_deref() {
+        return std::env::var(var).with_context(|| format!("github_token_env {var} is not set"));
+    }
+    let stored = secrets::get(secrets::GITHUB_TOKEN).unwrap_or_else(|e| {
+        debug!(error = %e, "keyring unavailable for the GitHub token");
+        None
+    });
+    match stored {
+        Some(token) => Ok(token),
+        None => std::env::var(publish::DEFAULT_TOKEN_ENV).with_context(|| {
+            format!(
+                "no GitHub token: set {}, or store one with `remi secrets set {}`",
+                publish::DEFAULT_TOKEN_ENV,
+                secrets::GITHUB_TOKEN
+            )
+        }),
+    }
+}
+
+// A remote endpoint wins over a local model when both are configured.
+#[cfg(feature = "semantic")]
+fn load_reranker(config: &config::Config) -> anyhow::Result<embeddings::Reranker> {
+    let semantic = config.semantic.as_ref();
+    if let Some(url) = semantic.and_then(|s| s.rerank_url.as_deref()) {
+        let api_key = match semantic.and_then(|s| s.rerank_api_key_env.as_deref()) {
+            Some(var) => Some(
+                std::env::var(var)
+                    .with_context(|| format!("rerank_api_key_env {var} is not set"))?,
+            ),
+            None => secrets::get(secrets::RERANK_API_KEY).unwrap_or_else(|e| {
+                debug!(error = %e, "keyring unavailable for the rerank API key");
+                None
+            }),
+        };
+        info!(url, "using remote reranker");
+        return Ok(embeddings::Reranker::remote(
+            url,
+            semantic.and_then(|s| s.rerank_model.as_deref()),
+            api_key,
+        ));
+    }
+    let path = semantic
+        .and_then(|s| s.rerank_model_path.as_deref())
+        .context("--rerank needs [semantic] rerank_model_path or rerank_url in the config")?;
+    info!(path, "loading reranker model");
+    embeddings::Reranker::onnx(path, config.performance.embed_threads)
+}
+
 #[cfg(feature = "semantic")]
-fn configure_ort(cli: &Cli) -> anyhow::Result<()> {
+fn configure_ort(cli: &Cli, config: &config::Config) -> anyhow::Result<()> {
     if let Some(path) = &cli.ort_dylib_path {
         if !path.exists() {
             return Err(anyhow::anyhow!("ORT dylib not found at {}", path.display()));
         }
-        std::env::set_var("ORT_DYLIB_PATH", path);
+        set_ort_dylib_path(path);
+        return Ok(());
+    }
+    if std::env::var_os("ORT_DYLIB_PATH").is_some() {
         return Ok(());
     }
 
-    if cli.auto_ort {
-        if std::env::var_os("ORT_DYLIB_PATH").is_some() {
+    if let Some(path) = config
+        .semantic
+        .as_ref()
+        .and_then(|s| s.ort_dylib_path.as_deref())
+    {
+        if Path::new(path).exists() {
+            set_ort_dylib_path(Path::new(path));
             return Ok(());
         }
+        tracing::warn!(
+            path,
+            "configured ort_dylib_path does not exist; run `remi doctor --install-ort`"
+        );
+    }
+
+    if cli.auto_ort {
         if let Some(found) = find_ort_dylib()? {
             info!(path = %found.display(), "using ORT dylib");
-            std::env::set_var("ORT_DYLIB_PATH", found);
+            set_ort_dylib_path(&found);
         } else {
-            tracing::warn!("failed to auto-detect libonnxruntime.so");
+            tracing::warn!(
+                library = ort_runtime::LIBRARY_NAME,
+                "failed to auto-detect onnxruntime; run `remi doctor --install-ort`"
+            );
         }
     }
 
     Ok(())
 }
 
+#[cfg(feature = "semantic")]
+fn set_ort_dylib_path(path: &Path) {
+    // SAFETY: called from main before any other thread is started.
+    unsafe { std::env::set_var("ORT_DYLIB_PATH", path) };
+}
+
 #[cfg(feature = "semantic")]
 fn find_ort_dylib() -> anyhow::Result<Option<PathBuf>> {
     let mut roots = Vec::new();
-    if let Some(cache_dir) = dirs::cache_dir() {
-        roots.push(cache_dir.join("onnxruntime"));
+    if let Ok(cache_root) = ort_runtime::cache_root() {
+        roots.push(cache_root);
     }
     roots.push(PathBuf::from("/usr/lib"));
     roots.push(PathBuf::from("/usr/local/lib"));
@@ -908,7 +3315,7 @@ fn find_ort_dylib() -> anyhow::Result<Option<PathBuf>> {
             continue;
         }
         for path in walk_paths(&root)? {
-            if path.file_name().and_then(|s| s.to_str()) == Some("libonnxruntime.so") {
+            if path.file_name().and_then(|s| s.to_str()) == Some(ort_runtime::LIBRARY_NAME) {
                 let modified = std::fs::metadata(&path)
                     .and_then(|m| m.modified())
                     .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
@@ -943,31 +3350,242 @@ fn walk_paths(root: &std::path::Path) -> anyhow::Result<Vec<PathBuf>> {
     Ok(out)
 }
 
-fn adapters() -> Vec<(&'static str, Box<dyn core_model::AgentAdapter>)> {
-    vec![
-        ("pi", Box::new(pi::PiAdapter)),
-        ("droid", Box::new(droid::DroidAdapter)),
-        ("opencode", Box::new(opencode::OpenCodeAdapter)),
-        ("claude", Box::new(claude::ClaudeAdapter)),
-        ("amp", Box::new(amp::AmpAdapter)),
-        ("codex", Box::new(codex::CodexAdapter)),
-    ]
+// Compiled-in capabilities for `remi doctor --features`.
+fn compiled_features() -> Vec<(String, bool)> {
+    remi::ADAPTER_FEATURES
+        .iter()
+        .map(|(kind, on)| (format!("adapter_{kind}"), *on))
+        .chain([
+            ("semantic".to_string(), cfg!(feature = "semantic")),
+            ("sqlcipher".to_string(), cfg!(feature = "sqlcipher")),
+            ("grpc".to_string(), cfg!(feature = "grpc")),
+        ])
+        .collect()
+}
+
+fn run_profile_command(
+    profiles: &profile::Profiles,
+    command: &ProfileCommand,
+    selected: Option<&str>,
+) -> anyhow::Result<()> {
+    match command {
+        ProfileCommand::List => {
+            let active = match selected {
+                Some(name) => name.to_string(),
+                None => profiles.active()?,
+            };
+            for name in profiles.list()? {
+                let marker = if name == active { "*" } else { " " };
+                let profile = profiles.get(&name);
+                println!("{marker} {name}\t{}", profile.db_path.display());
+            }
+        }
+        ProfileCommand::Create { name } => {
+            let profile = profiles.create(name)?;
+            println!("config={}", profile.config_path.display());
+            println!("db={}", profile.db_path.display());
+        }
+        ProfileCommand::Use { name } => {
+            profiles.set_active(name)?;
+            println!("active_profile={name}");
+        }
+    }
+    Ok(())
+}
+
+// The value is read from stdin rather than argv so it stays out of shell
+// history.
+fn run_secrets_command(command: &SecretsCommand) -> anyhow::Result<()> {
+    match command {
+        SecretsCommand::Set { name } => {
+            let mut value = String::new();
+            std::io::stdin()
+                .read_line(&mut value)
+                .context("failed to read the secret from stdin")?;
+            secrets::set(name, value.trim_end_matches(['\r', '\n']))?;
+            eprintln!("stored {name}");
+        }
+        SecretsCommand::Get { name } => {
+            let value = secrets::get(name)?.with_context(|| format!("no secret named {name}"))?;
+            println!("{value}");
+        }
+        SecretsCommand::Delete { name } => secrets::delete(name)?,
+    }
+    Ok(())
+}
+
+// The preset's values, or the `remi search query` defaults where it has none.
+fn preset_settings(config: &config::Config, name: Option<&str>) -> anyhow::Result<batch::Settings> {
+    let preset = match name {
+        Some(name) => config.search_profile(name)?.clone(),
+        None => config::SearchProfile::default(),
+    };
+    Ok(batch::Settings {
+        limit: preset.limit.unwrap_or(query::DEFAULT_LIMIT),
+        raw_fts: preset.raw_fts.unwrap_or(false),
+        reasoning: preset_reasoning(None, &preset)?.into(),
+        weights: preset.weights.weights()?,
+        lang: preset.lang,
+        filters: query::FilterSpec {
+            agent: preset.agent,
+            title: preset.title,
+            id: preset.id,
+            contains: preset.contains,
+        },
+        as_of: None,
+        explain: false,
+    })
+}
+
+fn preset_reasoning(
+    flag: Option<ReasoningMode>,
+    preset: &config::SearchProfile,
+) -> anyhow::Result<ReasoningMode> {
+    match flag {
+        Some(reasoning) => Ok(reasoning),
+        None => Ok(preset_value("reasoning", preset.reasoning.as_deref())?
+            .unwrap_or(ReasoningMode::Include)),
+    }
+}
+
+// Preset values use the flag spellings, so `format = "markdown"` parses like
+// `--format markdown`.
+fn preset_value<T: ValueEnum>(key: &str, value: Option<&str>) -> anyhow::Result<Option<T>> {
+    value
+        .map(|value| {
+            T::from_str(value, true)
+                .map_err(|err| anyhow::anyhow!("invalid {key} in search preset: {err}"))
+        })
+        .transpose()
+}
+
+fn parse_cutoff(flag: &str, raw: &str) -> anyhow::Result<DateTime<Utc>> {
+    if let Ok(ts) = DateTime::parse_from_rfc3339(raw) {
+        return Ok(ts.with_timezone(&Utc));
+    }
+    let date = chrono::NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+        .with_context(|| format!("invalid {flag} {raw:?}; expected YYYY-MM-DD or RFC3339"))?;
+    Ok(date.and_time(chrono::NaiveTime::MIN).and_utc())
+}
+
+// Cheap enough to run after every sync, so drift is noticed long before it
+// shows up as wrong search results.
+fn warn_on_drift(store: &SqliteStore) -> anyhow::Result<()> {
+    let fts = store.fts_health()?;
+    let orphans = store.orphan_rows()?;
+    if fts != store_sqlite::FtsHealth::default() || orphans != store_sqlite::OrphanRows::default() {
+        tracing::warn!(
+            fts_stray_rows = fts.stray_rows,
+            fts_missing_rows = fts.missing_rows,
+            orphan_embeddings = orphans.embeddings,
+            orphan_provenance = orphans.provenance,
+            "store is inconsistent; run `remi doctor --repair`"
+        );
+    }
+    Ok(())
+}
+
+fn with_hooks(
+    adapters: Vec<(&'static str, Box<dyn core_model::AgentAdapter>)>,
+    config: &config::Config,
+    dir: &Path,
+) -> anyhow::Result<Vec<(&'static str, Box<dyn core_model::AgentAdapter>)>> {
+    adapters
+        .into_iter()
+        .map(|(name, adapter)| Ok((name, with_hook(adapter, config, dir)?)))
+        .collect()
+}
+
+// Relative script paths in `[agents.<agent>] hook` resolve next to
+// config.toml.
+fn with_hook(
+    adapter: Box<dyn core_model::AgentAdapter>,
+    config: &config::Config,
+    dir: &Path,
+) -> anyhow::Result<Box<dyn core_model::AgentAdapter>> {
+    Ok(match config.hook_for(adapter.kind()) {
+        Some(script) => Box::new(hook::Hooked::new(
+            adapter,
+            hook::Script::load(&dir.join(script))?,
+        )),
+        None => adapter,
+    })
+}
+
+fn adapter_for(
+    kind: AgentKind,
+    opts: impl Fn(AgentKind) -> DiscoveryOptions,
+    config: &config::Config,
+) -> anyhow::Result<Box<dyn core_model::AgentAdapter>> {
+    remi::adapters(opts, config.capture.options(), |kind| {
+        config.timezone_for(kind)
+    })
+    .into_iter()
+    .map(|(_, adapter)| adapter)
+    .find(|adapter| adapter.kind() == kind)
+    .with_context(|| format!("this build of remi has no {kind} adapter (cargo feature `{kind}`)"))
+}
+
+fn backfill_with_timing(
+    name: &str,
+    adapter: &dyn core_model::AgentAdapter,
+    sources: &SourceContext,
+    store: &mut SqliteStore,
+    #[cfg(feature = "semantic")] queue_embeddings: bool,
+    before: Option<DateTime<Utc>>,
+) -> anyhow::Result<usize> {
+    let started = Instant::now();
+    info!(name, before = ?before, "backfill start");
+    let count = ingest::backfill_adapter(
+        adapter,
+        sources,
+        store,
+        #[cfg(feature = "semantic")]
+        queue_embeddings,
+        before,
+        |phase| trace!(name, phase = ?phase, "backfill phase"),
+    )?;
+    info!(name, count, elapsed = ?started.elapsed(), "backfill done");
+    Ok(count)
+}
+
+#[cfg(feature = "semantic")]
+fn drain_embeddings(
+    store: &SqliteStore,
+    embedder: &mut embeddings::Embedder,
+    started: Instant,
+) -> anyhow::Result<()> {
+    let pending = store.embedding_queue_len()?;
+    if pending == 0 {
+        return Ok(());
+    }
+    info!(pending, "draining embedding queue");
+    let count = ingest::drain_embedding_queue(store, embedder, 100, |processed| {
+        info!(processed, "processed messages");
+    })?;
+    let remaining = store.embedding_queue_len()?;
+    info!(count, remaining, elapsed = ?started.elapsed(), "computed embeddings");
+    Ok(())
 }
 
 fn sync_with_timing(
     name: &str,
     adapter: &dyn core_model::AgentAdapter,
+    sources: &SourceContext,
     store: &mut SqliteStore,
-    #[cfg(feature = "semantic")] embedder: Option<&mut embeddings::Embedder>,
+    #[cfg(feature = "semantic")] queue_embeddings: bool,
+    chunk_size: usize,
 ) -> anyhow::Result<usize> {
     let started = Instant::now();
     info!(name, "sync start");
     let count = sync_one(
         name,
         adapter,
+        sources,
         store,
         #[cfg(feature = "semantic")]
-        embedder,
+        queue_embeddings,
+        chunk_size,
     )?;
     info!(name, count, elapsed = ?started.elapsed(), "sync done");
     Ok(count)
@@ -976,16 +3594,20 @@ fn sync_with_timing(
 fn sync_one(
     name: &str,
     adapter: &dyn core_model::AgentAdapter,
+    sources: &SourceContext,
     store: &mut SqliteStore,
-    #[cfg(feature = "semantic")] embedder: Option<&mut embeddings::Embedder>,
+    #[cfg(feature = "semantic")] queue_embeddings: bool,
+    chunk_size: usize,
 ) -> anyhow::Result<usize> {
     let started = Instant::now();
     let last = RefCell::new(started);
-    ingest::sync_adapter(
+    ingest::sync_adapter_chunked(
         adapter,
+        sources,
         store,
         #[cfg(feature = "semantic")]
-        embedder,
+        queue_embeddings,
+        chunk_size,
         |phase| match phase {
             SyncPhase::Discovering => {
                 let now = Instant::now();
@@ -1034,13 +3656,20 @@ fn sync_one(
                     "saving messages"
                 );
             }
-            SyncPhase::Done { total_records } => {
+            SyncPhase::Done {
+                total_records,
+                report,
+            } => {
                 let now = Instant::now();
                 let since_last = now.duration_since(*last.borrow());
                 *last.borrow_mut() = now;
+                let report = report.unwrap_or_default();
                 info!(
                     name,
                     total_records,
+                    files_seen = report.files_seen,
+                    files_skipped = report.files_skipped,
+                    parse_errors = report.parse_errors,
                     elapsed = ?started.elapsed(),
                     delta = ?since_last,
                     "sync done"
@@ -1053,20 +3682,19 @@ fn sync_one(
 #[cfg(feature = "semantic")]
 fn detect_model_path() -> Option<PathBuf> {
     let mut candidates = Vec::new();
-    if let Ok(exe) = std::env::current_exe() {
-        if let Some(dir) = exe.parent() {
-            candidates.push(dir.join("models").join("bge-small-en-v1.5"));
-            candidates.push(dir.join("model"));
-        }
+    if let Ok(exe) = std::env::current_exe()
+        && let Some(dir) = exe.parent()
+    {
+        candidates.push(dir.join("models").join("bge-small-en-v1.5"));
+        candidates.push(dir.join("model"));
     }
     if let Some(cache_dir) = dirs::cache_dir() {
         candidates.push(cache_dir.join("remi").join("bge-small-en-v1.5"));
     }
 
     for path in candidates {
-        let model = path.join("model.onnx");
-        let tokenizer = path.join("tokenizer.json");
-        if model.exists() && tokenizer.exists() {
+        let has_model = path.join("model.onnx").exists() || path.join("model.safetensors").exists();
+        if has_model && path.join("tokenizer.json").exists() {
             info!(path = %path.display(), "auto-detected model");
             return Some(path);
         }
@@ -1080,6 +3708,91 @@ mod tests {
     use super::*;
     use std::time::{SystemTime, UNIX_EPOCH};
 
+    #[test]
+    fn every_agent_kind_has_a_registered_adapter_and_sync_option() {
+        let registered = remi::adapters(
+            |_| DiscoveryOptions::default(),
+            adapter_common::CaptureOptions::default(),
+            |_| None,
+        );
+        for kind in AgentKind::ALL {
+            let matches: Vec<_> = registered
+                .iter()
+                .filter(|(_, adapter)| adapter.kind() == kind)
+                .collect();
+            assert_eq!(matches.len(), 1, "expected one adapter for {kind}");
+            assert_eq!(matches[0].0, kind.as_str());
+            assert!(
+                AgentOpt::value_variants()
+                    .iter()
+                    .any(|opt| opt.kind() == Some(kind)),
+                "missing --agent option for {kind}"
+            );
+        }
+        assert_eq!(registered.len(), AgentKind::ALL.len());
+    }
+
+    #[test]
+    fn sync_selection_honors_only_except_and_config() {
+        let select = |args: &[&str], enabled: &dyn Fn(AgentKind) -> bool| {
+            let cli = Cli::try_parse_from([&["remi", "sync"], args].concat()).unwrap();
+            let Commands::Sync(sync) = cli.command else {
+                unreachable!()
+            };
+            sync.selected(enabled)
+        };
+        let all = |_| true;
+        let no_droid = |kind| kind != AgentKind::Droid;
+        assert_eq!(
+            select(&["--all", "--except", "droid,amp"], &all).unwrap(),
+            vec![
+                AgentKind::Pi,
+                AgentKind::OpenCode,
+                AgentKind::Claude,
+                AgentKind::Codex,
+                AgentKind::Gemini,
+                AgentKind::Aider
+            ]
+        );
+        assert_eq!(
+            select(&["--only", "codex,pi"], &no_droid).unwrap(),
+            vec![AgentKind::Pi, AgentKind::Codex]
+        );
+        assert_eq!(
+            select(&["--only", "droid"], &no_droid).unwrap(),
+            vec![AgentKind::Droid]
+        );
+        assert!(
+            !select(&["--agent", "all"], &no_droid)
+                .unwrap()
+                .contains(&AgentKind::Droid)
+        );
+        assert_eq!(
+            select(&["--agent", "droid"], &no_droid).unwrap(),
+            vec![AgentKind::Droid]
+        );
+        assert!(select(&["--agent", "pi", "--except", "amp"], &all).is_err());
+        assert!(select(&["--all", "--except", "all"], &all).is_err());
+        assert!(Cli::try_parse_from(["remi", "sync"]).is_err());
+        assert!(Cli::try_parse_from(["remi", "sync", "--all", "--only", "pi"]).is_err());
+    }
+
+    #[test]
+    fn parse_cutoff_accepts_dates_and_timestamps() {
+        assert_eq!(
+            parse_cutoff("--before", "2026-03-01").unwrap().to_rfc3339(),
+            "2026-03-01T00:00:00+00:00"
+        );
+        assert_eq!(
+            parse_cutoff("--as-of", "2026-03-01T12:00:00+02:00")
+                .unwrap()
+                .to_rfc3339(),
+            "2026-03-01T10:00:00+00:00"
+        );
+        let err = parse_cutoff("--as-of", "last tuesday").unwrap_err();
+        assert!(err.to_string().starts_with("invalid --as-of"));
+    }
+
     #[test]
     fn sanitize_title_strips_newlines() {
         assert_eq!(sanitize_title("hello\nworld"), "hello");
@@ -1138,24 +3851,24 @@ mod tests {
         #[cfg(unix)]
         std::os::unix::fs::symlink(&doc_path, root.join("guide-link.md")).unwrap();
 
-        let first = index_docs_root_with_db(&root, &db_path).unwrap();
+        let first = index_docs_root(&root, &db_path, None).unwrap();
         assert_eq!(first.indexed, 1);
         #[cfg(unix)]
         assert!(first.skipped >= 2);
         #[cfg(not(unix))]
         assert!(first.skipped >= 1);
 
-        let second = index_docs_root_with_db(&root, &db_path).unwrap();
+        let second = index_docs_root(&root, &db_path, None).unwrap();
         assert_eq!(second.indexed, 0);
         assert_eq!(second.updated, 0);
 
         let renamed = root.join("renamed-guide.md");
         fs::rename(&doc_path, &renamed).unwrap();
-        let third = index_docs_root_with_db(&root, &db_path).unwrap();
+        let third = index_docs_root(&root, &db_path, None).unwrap();
         assert_eq!(third.deleted, 1);
         assert_eq!(third.indexed, 1);
 
-        let hits = search::search_docs_at(&db_path, "unique-doc-token", 10, false).unwrap();
+        let hits = search::search_docs_at(&db_path, None, "unique-doc-token", 10, false).unwrap();
         assert_eq!(hits.len(), 1);
         assert_eq!(hits[0].path, "renamed-guide.md");
 
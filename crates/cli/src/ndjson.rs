@@ -0,0 +1,125 @@
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use anyhow::Context;
+use serde::Serialize;
+
+// Writes one compact JSON document per line as it is produced, so exports
+// of any size run in constant memory and `jq` can start on the first line.
+// A reader that goes away early, such as `head`, ends the output quietly:
+// later writes are dropped and `closed` tells the caller to stop.
+pub struct Writer {
+    inner: Box<dyn Write>,
+    closed: bool,
+    lines: usize,
+}
+
+impl Writer {
+    pub fn new(inner: impl Write + 'static) -> Self {
+        Self {
+            inner: Box::new(inner),
+            closed: false,
+            lines: 0,
+        }
+    }
+
+    // A file when `out` is set, standard output otherwise.
+    pub fn open(out: Option<&Path>) -> anyhow::Result<Self> {
+        Ok(match out {
+            Some(path) => Self::new(BufWriter::new(
+                std::fs::File::create(path)
+                    .with_context(|| format!("failed to write {}", path.display()))?,
+            )),
+            None => Self::new(BufWriter::new(io::stdout())),
+        })
+    }
+
+    pub fn write(&mut self, value: &impl Serialize) -> anyhow::Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+        let written = serde_json::to_writer(&mut self.inner, value)
+            .map_err(io::Error::from)
+            .and_then(|()| self.inner.write_all(b"\n"));
+        self.check(written)?;
+        if !self.closed {
+            self.lines += 1;
+        }
+        Ok(())
+    }
+
+    pub fn closed(&self) -> bool {
+        self.closed
+    }
+
+    pub fn lines(&self) -> usize {
+        self.lines
+    }
+
+    pub fn finish(mut self) -> anyhow::Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+        let flushed = self.inner.flush();
+        self.check(flushed)
+    }
+
+    fn check(&mut self, result: io::Result<()>) -> anyhow::Result<()> {
+        match result {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                self.closed = true;
+                Ok(())
+            }
+            other => Ok(other?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Pipe {
+        buf: Arc<Mutex<Vec<u8>>>,
+        capacity: usize,
+    }
+
+    impl Write for Pipe {
+        fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+            let mut buf = self.buf.lock().unwrap();
+            if buf.len() + data.len() > self.capacity {
+                return Err(io::ErrorKind::BrokenPipe.into());
+            }
+            buf.extend_from_slice(data);
+            Ok(data.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn lines_are_compact_and_a_closed_reader_stops_the_output() {
+        let pipe = Pipe {
+            capacity: 20,
+            ..Default::default()
+        };
+        let mut writer = Writer::new(pipe.clone());
+        writer.write(&json!({ "a": 1 })).unwrap();
+        writer.write(&json!({ "b": [2, 3] })).unwrap();
+        assert!(!writer.closed());
+        writer
+            .write(&json!({ "c": "too long for the pipe" }))
+            .unwrap();
+        assert!(writer.closed());
+        writer.write(&json!({ "d": 4 })).unwrap();
+        assert_eq!(writer.lines(), 2);
+        writer.finish().unwrap();
+        let text = String::from_utf8(pipe.buf.lock().unwrap().clone()).unwrap();
+        assert!(text.starts_with("{\"a\":1}\n{\"b\":[2,3]}\n"), "{text}");
+    }
+}
@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+// `remi sync --nice` scans on one thread, waits before each source file and
+// commits in smaller transactions, so a background sync never holds the disk
+// or the database for long.
+pub const SCAN_THREADS: usize = 1;
+pub const FILE_PAUSE: Duration = Duration::from_millis(10);
+pub const CHUNK_SIZE: usize = 500;
+const NICENESS: i32 = 10;
+
+// The scan pool size: `[performance] scan_threads`, but never more than
+// `SCAN_THREADS` under `--nice`.
+pub fn scan_threads(configured: Option<usize>, nice: bool) -> Option<usize> {
+    match (configured, nice) {
+        // Rayon reads 0 as one thread per core.
+        (Some(threads), true) => Some(threads.clamp(1, SCAN_THREADS)),
+        (None, true) => Some(SCAN_THREADS),
+        (configured, false) => configured,
+    }
+}
+
+// Threads inherit the value, so this runs before any pool is built. On Linux
+// the IO scheduler derives the IO priority from it as well.
+#[cfg(unix)]
+pub fn lower_priority() -> std::io::Result<()> {
+    // SAFETY: setpriority takes plain integers and touches no memory of ours.
+    let rc = unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, NICENESS) };
+    if rc == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(unix))]
+pub fn lower_priority() -> std::io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nice_caps_configured_scan_threads() {
+        assert_eq!(scan_threads(Some(8), true), Some(SCAN_THREADS));
+        assert_eq!(scan_threads(Some(0), true), Some(SCAN_THREADS));
+        assert_eq!(scan_threads(None, true), Some(SCAN_THREADS));
+        assert_eq!(scan_threads(Some(8), false), Some(8));
+        assert_eq!(scan_threads(None, false), None);
+    }
+}
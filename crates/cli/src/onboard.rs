@@ -0,0 +1,192 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use core_model::{AgentAdapter, AgentKind, SourceContext};
+use toml_edit::{DocumentMut, Item, Table, value};
+
+use crate::ui::prompt_line;
+
+pub struct AgentProbe {
+    pub kind: AgentKind,
+    pub name: &'static str,
+    pub label: String,
+    pub home: Option<PathBuf>,
+    pub sources: usize,
+    pub sessions: usize,
+}
+
+impl AgentProbe {
+    fn found(&self) -> String {
+        match &self.home {
+            None => format!(
+                "no home directory (set {})",
+                adapter_common::source_home_env_var(self.kind)
+            ),
+            Some(home) if self.sources == 0 => format!("nothing found under {}", home.display()),
+            Some(_) => format!(
+                "~{} session{} in {} source file{}",
+                self.sessions,
+                if self.sessions == 1 { "" } else { "s" },
+                self.sources,
+                if self.sources == 1 { "" } else { "s" }
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Choices {
+    pub agents: Vec<(AgentKind, bool)>,
+    pub semantic: Option<bool>,
+}
+
+pub fn probe(
+    adapters: &[(&'static str, Box<dyn AgentAdapter>)],
+    ctx: &SourceContext,
+) -> anyhow::Result<Vec<AgentProbe>> {
+    adapters
+        .iter()
+        .map(|(name, adapter)| {
+            let kind = adapter.kind();
+            let home = ctx.home_for(kind).map(Path::to_path_buf);
+            let sources = if home.is_some() {
+                adapter.discover_source_paths(ctx)?
+            } else {
+                Vec::new()
+            };
+            Ok(AgentProbe {
+                kind,
+                name,
+                label: adapter.source_label(),
+                home,
+                sessions: adapter.estimate_sessions(&sources),
+                sources: sources.len(),
+            })
+        })
+        .collect()
+}
+
+// `semantic` is the current setting, or None when this build has no semantic
+// search to offer.
+pub fn run(
+    probes: &[AgentProbe],
+    enabled: impl Fn(AgentKind) -> bool,
+    semantic: Option<bool>,
+    config_path: &Path,
+) -> anyhow::Result<()> {
+    println!("Agents found on this machine:");
+    for p in probes {
+        println!("  {:<9} {:<14} {}", p.name, p.label, p.found());
+    }
+    println!();
+
+    let mut choices = Choices::default();
+    for p in probes {
+        let default = p.sources > 0 && enabled(p.kind);
+        let keep = confirm(&format!("Sync {}?", p.name), default)?;
+        choices.agents.push((p.kind, keep));
+    }
+    match semantic {
+        Some(current) => {
+            choices.semantic = Some(confirm(
+                "Enable semantic search (needs a local embedding model)?",
+                current,
+            )?);
+        }
+        None => println!("Semantic search is not available in this build (--features semantic)."),
+    }
+
+    let existing = match std::fs::read_to_string(config_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            return Err(e).with_context(|| format!("failed to read {}", config_path.display()));
+        }
+    };
+    let updated = apply(&existing, &choices)?;
+    if !confirm(&format!("Write {}?", config_path.display()), true)? {
+        println!("config not written");
+        return Ok(());
+    }
+    if let Some(dir) = config_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(config_path, updated)
+        .with_context(|| format!("failed to write {}", config_path.display()))?;
+    println!("wrote {}", config_path.display());
+    println!("next: remi sync --all");
+    Ok(())
+}
+
+// Edits the existing file in place so comments and unrelated settings survive.
+pub fn apply(existing: &str, choices: &Choices) -> anyhow::Result<String> {
+    let mut doc: DocumentMut = existing
+        .parse()
+        .context("failed to parse existing config file")?;
+    if !choices.agents.is_empty() {
+        let agents = doc
+            .entry("agents")
+            .or_insert_with(|| {
+                let mut table = Table::new();
+                table.set_implicit(true);
+                Item::Table(table)
+            })
+            .as_table_mut()
+            .context("config key `agents` is not a table")?;
+        for (kind, enabled) in &choices.agents {
+            let agent = agents
+                .entry(kind.as_str())
+                .or_insert_with(|| Item::Table(Table::new()))
+                .as_table_mut()
+                .with_context(|| format!("config key `agents.{kind}` is not a table"))?;
+            agent["enabled"] = value(*enabled);
+        }
+    }
+    if let Some(enabled) = choices.semantic {
+        let semantic = doc
+            .entry("semantic")
+            .or_insert_with(|| Item::Table(Table::new()))
+            .as_table_mut()
+            .context("config key `semantic` is not a table")?;
+        semantic["enabled"] = value(enabled);
+    }
+    Ok(doc.to_string())
+}
+
+fn confirm(question: &str, default: bool) -> anyhow::Result<bool> {
+    let hint = if default { "[Y/n]" } else { "[y/N]" };
+    loop {
+        let answer = prompt_line(&format!("{question} {hint} "))?;
+        match answer.trim().to_ascii_lowercase().as_str() {
+            "" => return Ok(default),
+            "y" | "yes" => return Ok(true),
+            "n" | "no" => return Ok(false),
+            _ => println!("please answer y or n"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_keeps_existing_settings_and_comments() {
+        let existing = "# my settings\n[capture]\nsystem_prompts = true\n\n[agents.droid]\nenabled = true # work laptop\n";
+        let choices = Choices {
+            agents: vec![(AgentKind::Pi, true), (AgentKind::Droid, false)],
+            semantic: Some(false),
+        };
+        let updated = apply(existing, &choices).unwrap();
+        assert!(updated.starts_with("# my settings\n[capture]\nsystem_prompts = true\n"));
+        assert!(!updated.contains("[agents]\n"));
+
+        let config: crate::config::Config = toml::from_str(&updated).unwrap();
+        assert!(config.agent_enabled(AgentKind::Pi));
+        assert!(!config.agent_enabled(AgentKind::Droid));
+        assert!(config.capture.system_prompts);
+        assert!(updated.contains("[semantic]\nenabled = false"));
+
+        assert!(apply("agents = 1\n", &choices).is_err());
+    }
+}
@@ -0,0 +1,345 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{Context, bail};
+use sha2::{Digest, Sha256};
+use toml_edit::{DocumentMut, Item, Table, value};
+use tracing::{info, warn};
+
+// Matches the ORT API version the `ort` crate is built against.
+pub const DEFAULT_ORT_VERSION: &str = "1.23.2";
+pub const BASE_URL_ENV: &str = "REMI_ORT_BASE_URL";
+const DEFAULT_BASE_URL: &str = "https://github.com/microsoft/onnxruntime/releases/download";
+
+// SHA-256 of the release archives `install` accepts without `--ort-sha256`,
+// taken from the digests on the onnxruntime release page. A mirror set with
+// REMI_ORT_BASE_URL must serve byte-identical archives to pass. Add entries
+// here for every entry of PLATFORMS when DEFAULT_ORT_VERSION moves.
+const PINNED_SHA256: &[(&str, &str)] = &[];
+
+// (os, arch) pairs with a prebuilt release, and the archive's platform tag
+// and extension.
+const PLATFORMS: &[(&str, &str, &str, &str)] = &[
+    ("linux", "x86_64", "linux-x64", "tgz"),
+    ("linux", "aarch64", "linux-aarch64", "tgz"),
+    ("macos", "aarch64", "osx-arm64", "tgz"),
+    ("macos", "x86_64", "osx-x86_64", "tgz"),
+    ("windows", "x86_64", "win-x64", "zip"),
+    ("windows", "aarch64", "win-arm64", "zip"),
+];
+
+pub const LIBRARY_NAME: &str = if cfg!(windows) {
+    "onnxruntime.dll"
+} else if cfg!(target_os = "macos") {
+    "libonnxruntime.dylib"
+} else {
+    "libonnxruntime.so"
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct OrtAsset {
+    pub name: String,
+    pub archive: String,
+    pub url: String,
+}
+
+pub fn asset_for(os: &str, arch: &str, version: &str, base_url: &str) -> anyhow::Result<OrtAsset> {
+    let Some((_, _, platform, ext)) = PLATFORMS
+        .iter()
+        .find(|(known_os, known_arch, _, _)| *known_os == os && *known_arch == arch)
+    else {
+        bail!(
+            "no prebuilt onnxruntime {version} for {os}/{arch}; install it manually and pass --ort-dylib-path"
+        );
+    };
+    let name = format!("onnxruntime-{platform}-{version}");
+    let archive = format!("{name}.{ext}");
+    let url = format!("{}/v{version}/{archive}", base_url.trim_end_matches('/'));
+    Ok(OrtAsset { name, archive, url })
+}
+
+pub fn cache_root() -> anyhow::Result<PathBuf> {
+    Ok(dirs::cache_dir()
+        .context("no cache directory to install onnxruntime into")?
+        .join("onnxruntime"))
+}
+
+// The digest the downloaded archive must have: `--ort-sha256` when given,
+// otherwise the pinned one. Nothing is downloaded without either.
+pub fn expected_sha256(
+    archive: &str,
+    explicit: Option<&str>,
+    base_url: Option<&str>,
+) -> anyhow::Result<String> {
+    if let Some(explicit) = explicit {
+        let digest = explicit.trim().to_ascii_lowercase();
+        if digest.len() != 64 || !digest.bytes().all(|b| b.is_ascii_hexdigit()) {
+            bail!("--ort-sha256 must be 64 hex characters, got {explicit}");
+        }
+        return Ok(digest);
+    }
+    if let Some((_, digest)) = PINNED_SHA256.iter().find(|(name, _)| *name == archive) {
+        return Ok(digest.to_string());
+    }
+    match base_url {
+        Some(url) => bail!(
+            "{BASE_URL_ENV} points at {url} and no SHA-256 is pinned for {archive}; pass --ort-sha256 with the archive's digest"
+        ),
+        None => bail!(
+            "no SHA-256 is pinned for {archive}; pass --ort-sha256 with the digest from the onnxruntime release page"
+        ),
+    }
+}
+
+pub fn sha256_file(path: &Path) -> anyhow::Result<String> {
+    let mut file =
+        std::fs::File::open(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect())
+}
+
+// Downloads, verifies and unpacks the release archive under
+// `<root>/<version>`, reusing an earlier install. Only an archive whose
+// SHA-256 matches is unpacked. curl and tar ship with Linux, macOS, and
+// Windows 10+.
+pub fn install(root: &Path, version: &str, sha256: Option<&str>) -> anyhow::Result<PathBuf> {
+    let overridden = std::env::var(BASE_URL_ENV).ok();
+    let base_url = overridden.as_deref().unwrap_or(DEFAULT_BASE_URL);
+    let asset = asset_for(
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        version,
+        base_url,
+    )?;
+    let dir = root.join(version);
+    if let Some(found) = find_library(&dir.join(&asset.name)) {
+        info!(path = %found.display(), "onnxruntime already installed");
+        return Ok(found);
+    }
+    let expected = expected_sha256(&asset.archive, sha256, overridden.as_deref())?;
+    if let Some(url) = &overridden {
+        warn!(url = %url, "downloading onnxruntime from {BASE_URL_ENV}");
+    }
+    std::fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let archive = dir.join(&asset.archive);
+    info!(url = %asset.url, "downloading onnxruntime");
+    run(Command::new("curl")
+        .args(["--fail", "--location", "--silent", "--show-error"])
+        .args(["--retry", "3", "--output"])
+        .arg(&archive)
+        .arg(&asset.url))
+    .with_context(|| format!("failed to download {}", asset.url))?;
+    let actual = sha256_file(&archive);
+    if actual.as_deref().ok() != Some(expected.as_str()) {
+        let _ = std::fs::remove_file(&archive);
+        bail!(
+            "{} failed verification: expected SHA-256 {expected}, got {}",
+            asset.url,
+            actual?
+        );
+    }
+    let unpacked = run(Command::new("tar")
+        .arg("-xf")
+        .arg(&archive)
+        .arg("-C")
+        .arg(&dir))
+    .with_context(|| format!("failed to unpack {}", archive.display()));
+    let _ = std::fs::remove_file(&archive);
+    unpacked?;
+    find_library(&dir.join(&asset.name)).with_context(|| {
+        format!(
+            "{} did not contain {LIBRARY_NAME} under {}/lib",
+            asset.archive, asset.name
+        )
+    })
+}
+
+fn run(command: &mut Command) -> anyhow::Result<()> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let output = command
+        .output()
+        .with_context(|| format!("failed to run {program}; is it installed?"))?;
+    if !output.status.success() {
+        bail!(
+            "{program} exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+// Releases ship the unversioned name as a symlink on Unix; fall back to the
+// versioned file when an extractor dropped the link.
+fn find_library(unpacked: &Path) -> Option<PathBuf> {
+    let lib = unpacked.join("lib");
+    let exact = lib.join(LIBRARY_NAME);
+    if exact.is_file() {
+        return Some(exact);
+    }
+    let stem = LIBRARY_NAME
+        .trim_end_matches(".so")
+        .trim_end_matches(".dylib");
+    let mut versioned: Vec<PathBuf> = std::fs::read_dir(&lib)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(&format!("{stem}.")))
+        })
+        .collect();
+    versioned.sort();
+    versioned.pop()
+}
+
+pub fn persist(config_path: &Path, library: &Path) -> anyhow::Result<()> {
+    let existing = match std::fs::read_to_string(config_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            return Err(e).with_context(|| format!("failed to read {}", config_path.display()));
+        }
+    };
+    let mut doc: DocumentMut = existing
+        .parse()
+        .context("failed to parse existing config file")?;
+    doc.entry("semantic")
+        .or_insert_with(|| Item::Table(Table::new()))
+        .as_table_mut()
+        .context("config key `semantic` is not a table")?["ort_dylib_path"] =
+        value(library.to_string_lossy().as_ref());
+    if let Some(dir) = config_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(config_path, doc.to_string())
+        .with_context(|| format!("failed to write {}", config_path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assets_follow_release_naming() {
+        let asset = asset_for("linux", "x86_64", "1.23.2", DEFAULT_BASE_URL).unwrap();
+        assert_eq!(asset.archive, "onnxruntime-linux-x64-1.23.2.tgz");
+        assert_eq!(
+            asset.url,
+            "https://github.com/microsoft/onnxruntime/releases/download/v1.23.2/onnxruntime-linux-x64-1.23.2.tgz"
+        );
+        assert_eq!(
+            asset_for("windows", "x86_64", "1.23.2", "file:///mirror/")
+                .unwrap()
+                .url,
+            "file:///mirror/v1.23.2/onnxruntime-win-x64-1.23.2.zip"
+        );
+        assert!(asset_for("freebsd", "x86_64", "1.23.2", DEFAULT_BASE_URL).is_err());
+    }
+
+    #[test]
+    fn checksums_are_required_and_well_formed() {
+        let archive = "onnxruntime-linux-x64-0.0.0.tgz";
+        let err = expected_sha256(archive, None, Some("https://mirror"))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("REMI_ORT_BASE_URL points at https://mirror"),
+            "{err}"
+        );
+        assert!(expected_sha256(archive, Some("abc"), None).is_err());
+        assert_eq!(
+            expected_sha256(archive, Some(&"AB".repeat(32)), None).unwrap(),
+            "ab".repeat(32)
+        );
+        for (name, digest) in PINNED_SHA256 {
+            assert_eq!(expected_sha256(name, None, None).unwrap(), *digest);
+        }
+    }
+
+    // The digests have to be copied from the onnxruntime release page, which
+    // is not reachable from every build machine, so this runs on request.
+    #[test]
+    #[ignore = "needs the pinned digests for DEFAULT_ORT_VERSION"]
+    fn every_platform_has_a_pinned_digest() {
+        for (os, arch, _, _) in PLATFORMS {
+            let asset = asset_for(os, arch, DEFAULT_ORT_VERSION, DEFAULT_BASE_URL).unwrap();
+            assert!(
+                expected_sha256(&asset.archive, None, None).is_ok(),
+                "no pinned SHA-256 for {}",
+                asset.archive
+            );
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn install_unpacks_from_mirror_and_persists_path() {
+        let root = std::env::temp_dir().join(format!("remi-ort-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let asset = asset_for("linux", std::env::consts::ARCH, "9.9.9", "").unwrap();
+        let staging = root.join("staging");
+        let lib_dir = staging.join(&asset.name).join("lib");
+        std::fs::create_dir_all(&lib_dir).unwrap();
+        std::fs::write(lib_dir.join("libonnxruntime.so.9.9.9"), b"elf").unwrap();
+        let mirror = root.join("mirror/v9.9.9");
+        std::fs::create_dir_all(&mirror).unwrap();
+        run(Command::new("tar")
+            .arg("-czf")
+            .arg(mirror.join(&asset.archive))
+            .arg("-C")
+            .arg(&staging)
+            .arg(&asset.name))
+        .unwrap();
+
+        // SAFETY: no other test in this binary reads REMI_ORT_BASE_URL.
+        unsafe {
+            std::env::set_var(
+                BASE_URL_ENV,
+                format!("file://{}", root.join("mirror").display()),
+            )
+        };
+        let cache = root.join("cache");
+        let err = install(&cache, "9.9.9", None).unwrap_err().to_string();
+        assert!(err.contains("no SHA-256 is pinned"), "{err}");
+        let err = install(&cache, "9.9.9", Some(&"0".repeat(64)))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("failed verification"), "{err}");
+        assert!(!cache.join("9.9.9").join(&asset.archive).exists());
+        assert!(find_library(&cache.join("9.9.9").join(&asset.name)).is_none());
+
+        let digest = sha256_file(&mirror.join(&asset.archive)).unwrap();
+        let installed = install(&cache, "9.9.9", Some(&digest.to_uppercase())).unwrap();
+        assert_eq!(
+            installed,
+            cache
+                .join("9.9.9")
+                .join(&asset.name)
+                .join("lib/libonnxruntime.so.9.9.9")
+        );
+        assert!(!cache.join("9.9.9").join(&asset.archive).exists());
+        assert_eq!(install(&cache, "9.9.9", None).unwrap(), installed);
+
+        let config = root.join("config/remi/config.toml");
+        std::fs::create_dir_all(config.parent().unwrap()).unwrap();
+        std::fs::write(&config, "# mine\n[semantic]\nenabled = true\n").unwrap();
+        persist(&config, &installed).unwrap();
+        let written = std::fs::read_to_string(&config).unwrap();
+        assert!(written.starts_with("# mine\n[semantic]\nenabled = true\n"));
+        let parsed: crate::config::Config = toml::from_str(&written).unwrap();
+        assert_eq!(
+            parsed.semantic.unwrap().ort_dylib_path.as_deref(),
+            Some(installed.to_string_lossy().as_ref())
+        );
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,224 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+pub const DEFAULT_PROFILE: &str = "default";
+pub const PROFILE_ENV: &str = "REMI_PROFILE";
+const ACTIVE_FILE: &str = "active_profile";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    pub config_path: PathBuf,
+    pub db_path: PathBuf,
+}
+
+// The default profile keeps the original locations, so existing installs
+// need no migration; named profiles live under `profiles/<name>/` in both
+// the config and data directories.
+pub struct Profiles {
+    config_root: PathBuf,
+    data_root: PathBuf,
+}
+
+impl Profiles {
+    pub fn new() -> Self {
+        let base = |dir: Option<PathBuf>| dir.unwrap_or_else(|| PathBuf::from(".")).join("remi");
+        Self::at(base(dirs::config_dir()), base(dirs::data_dir()))
+    }
+
+    fn at(config_root: PathBuf, data_root: PathBuf) -> Self {
+        Self {
+            config_root,
+            data_root,
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Profile {
+        if name == DEFAULT_PROFILE {
+            return Profile {
+                name: name.to_string(),
+                config_path: self.config_root.join("config.toml"),
+                db_path: self.data_root.join("remi.db"),
+            };
+        }
+        Profile {
+            name: name.to_string(),
+            config_path: self
+                .config_root
+                .join("profiles")
+                .join(name)
+                .join("config.toml"),
+            db_path: self.data_root.join("profiles").join(name).join("remi.db"),
+        }
+    }
+
+    fn exists(&self, name: &str) -> bool {
+        name == DEFAULT_PROFILE || self.config_root.join("profiles").join(name).is_dir()
+    }
+
+    pub fn list(&self) -> anyhow::Result<Vec<String>> {
+        let mut names = vec![DEFAULT_PROFILE.to_string()];
+        let dir = self.config_root.join("profiles");
+        if dir.is_dir() {
+            let mut named = Vec::new();
+            for entry in
+                std::fs::read_dir(&dir).with_context(|| format!("reading {}", dir.display()))?
+            {
+                let entry = entry?;
+                if entry.file_type()?.is_dir()
+                    && let Some(name) = entry.file_name().to_str()
+                    && validate_name(name).is_ok()
+                {
+                    named.push(name.to_string());
+                }
+            }
+            named.sort();
+            names.extend(named);
+        }
+        Ok(names)
+    }
+
+    // Creates the config directory with an empty config.toml; the database
+    // is created on first use.
+    pub fn create(&self, name: &str) -> anyhow::Result<Profile> {
+        validate_name(name)?;
+        if self.exists(name) {
+            anyhow::bail!("profile {name} already exists");
+        }
+        let profile = self.get(name);
+        for path in [&profile.config_path, &profile.db_path] {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("creating {}", parent.display()))?;
+            }
+        }
+        std::fs::write(&profile.config_path, "")
+            .with_context(|| format!("writing {}", profile.config_path.display()))?;
+        Ok(profile)
+    }
+
+    pub fn active(&self) -> anyhow::Result<String> {
+        let path = self.config_root.join(ACTIVE_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(name) if !name.trim().is_empty() => Ok(name.trim().to_string()),
+            Ok(_) => Ok(DEFAULT_PROFILE.to_string()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(DEFAULT_PROFILE.to_string()),
+            Err(e) => Err(e).with_context(|| format!("reading {}", path.display())),
+        }
+    }
+
+    pub fn set_active(&self, name: &str) -> anyhow::Result<()> {
+        self.ensure_exists(name)?;
+        let path = self.config_root.join(ACTIVE_FILE);
+        if name == DEFAULT_PROFILE {
+            if path.exists() {
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("removing {}", path.display()))?;
+            }
+            return Ok(());
+        }
+        std::fs::create_dir_all(&self.config_root)
+            .with_context(|| format!("creating {}", self.config_root.display()))?;
+        std::fs::write(&path, format!("{name}\n"))
+            .with_context(|| format!("writing {}", path.display()))
+    }
+
+    // `--profile` wins over REMI_PROFILE, which wins over `remi profile use`.
+    pub fn resolve(&self, flag: Option<&str>, env: Option<&str>) -> anyhow::Result<Profile> {
+        let name = match flag.or(env).filter(|n| !n.is_empty()) {
+            Some(name) => name.to_string(),
+            None => self.active()?,
+        };
+        self.ensure_exists(&name)?;
+        Ok(self.get(&name))
+    }
+
+    fn ensure_exists(&self, name: &str) -> anyhow::Result<()> {
+        validate_name(name)?;
+        if !self.exists(name) {
+            anyhow::bail!(
+                "profile {name} does not exist; create it with `remi profile create {name}`"
+            );
+        }
+        Ok(())
+    }
+}
+
+fn validate_name(name: &str) -> anyhow::Result<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        anyhow::bail!("invalid profile name {name:?}; use letters, digits, '-' and '_'");
+    }
+    Ok(())
+}
+
+impl Profile {
+    pub fn data_dir(&self) -> &Path {
+        self.db_path.parent().unwrap_or_else(|| Path::new("."))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profiles(name: &str) -> (PathBuf, Profiles) {
+        let root =
+            std::env::temp_dir().join(format!("remi-profiles-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let profiles = Profiles::at(root.join("config"), root.join("data"));
+        (root, profiles)
+    }
+
+    #[test]
+    fn default_profile_keeps_original_paths() {
+        let (root, profiles) = profiles("default");
+        let profile = profiles.resolve(None, None).unwrap();
+        assert_eq!(profile.name, "default");
+        assert_eq!(profile.config_path, root.join("config/config.toml"));
+        assert_eq!(profile.db_path, root.join("data/remi.db"));
+        assert_eq!(profiles.list().unwrap(), vec!["default"]);
+    }
+
+    #[test]
+    fn named_profiles_are_created_selected_and_listed() {
+        let (root, profiles) = profiles("named");
+        let work = profiles.create("work").unwrap();
+        assert_eq!(
+            work.config_path,
+            root.join("config/profiles/work/config.toml")
+        );
+        assert_eq!(work.db_path, root.join("data/profiles/work/remi.db"));
+        assert!(work.config_path.exists());
+        assert!(work.data_dir().is_dir());
+        profiles.create("personal").unwrap();
+        assert!(profiles.create("work").is_err());
+        assert!(profiles.create("../escape").is_err());
+        assert_eq!(
+            profiles.list().unwrap(),
+            vec!["default", "personal", "work"]
+        );
+
+        assert!(profiles.resolve(Some("missing"), None).is_err());
+        profiles.set_active("work").unwrap();
+        assert_eq!(profiles.resolve(None, None).unwrap().name, "work");
+        assert_eq!(
+            profiles.resolve(None, Some("personal")).unwrap().name,
+            "personal"
+        );
+        assert_eq!(
+            profiles
+                .resolve(Some("default"), Some("personal"))
+                .unwrap()
+                .name,
+            "default"
+        );
+        profiles.set_active("default").unwrap();
+        assert_eq!(profiles.active().unwrap(), "default");
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
@@ -1,13 +1,16 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 
 use anyhow::Context;
 use askama::Template;
 use clap::ValueEnum;
-use core_model::{Message, Session};
+use core_model::{Message, NativeRecord, Provenance, RepoRef, Session};
 use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
 use serde_json::Value;
 
-use crate::ui::truncate_text;
+use query::truncate_text;
+
+use crate::i18n;
+use crate::stats::{self, BurstBreak, SessionStats};
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
 pub enum HtmlSafety {
@@ -21,9 +24,107 @@ pub enum HtmlSafety {
 pub struct SessionTemplate<'a> {
     pub title: &'a str,
     pub agent: &'a str,
+    pub agent_icon: &'a str,
+    pub agent_color: &'a str,
     pub id: &'a str,
     pub message_count: usize,
+    pub stats: Vec<(String, String)>,
     pub messages: Vec<ViewMessage>,
+    pub custom_css: &'a str,
+    pub lang: &'a str,
+}
+
+// Labels come from the active locale; see `crate::i18n`.
+impl SessionTemplate<'_> {
+    fn text(&self, id: &str) -> String {
+        i18n::text(id)
+    }
+
+    fn text_with(&self, id: &str, name: &str, value: &str) -> String {
+        i18n::text_with(id, &[(name, value.into())])
+    }
+
+    fn count(&self, id: &str, count: &usize) -> String {
+        i18n::count(id, *count)
+    }
+}
+
+// Class names and custom properties the session template keeps stable, so
+// user CSS from `--css` or `[export] css` can rely on them.
+pub const STABLE_CLASSES: &[(&str, &str)] = &[
+    ("header-wrapper", "sticky page header"),
+    ("container", "centered column inside the header"),
+    ("meta", "agent, id and message count line"),
+    ("agent-badge", "agent name; colored by --agent-color"),
+    ("session-stats", "collapsible stats block (details)"),
+    ("messages", "list of all messages"),
+    (
+        "burst-break",
+        "separator before a message that follows a long idle gap",
+    ),
+    ("message", "one message row"),
+    ("user", "added to .message for user prompts"),
+    ("tool", "added to .message for tool calls and results"),
+    ("message-inner", "message body column"),
+    ("role-badge", "role label above the content"),
+    ("timestamp", "message time inside .role-badge"),
+    ("content", "rendered message content"),
+    ("oversized", "collapsed body of a large message (details)"),
+    ("oversized-preview", "preview of a large message"),
+    ("oversized-link", "link to a large message's sidecar file"),
+    ("provenance", "collapsible source block (details)"),
+    (
+        "tool-block",
+        "collapsible tool call inside .content (details)",
+    ),
+    ("tool-content", "tool call arguments or output"),
+    ("diff-viewer", "rendered patch or diff"),
+    ("diff-header", "title bar of a .diff-viewer"),
+    ("diff-table", "diff lines table"),
+    ("diff-add", "added line row"),
+    ("diff-rem", "removed line row"),
+    ("diff-ctx", "context line row"),
+    ("diff-hunk", "hunk header row"),
+    ("diff-marker", "+/- column"),
+    ("diff-code", "line text column"),
+];
+
+pub const CSS_VARIABLES: &[(&str, &str)] = &[
+    ("--bg", "page background"),
+    ("--bg-surface", "message background"),
+    ("--bg-subtle", "subtle fills"),
+    ("--text", "body text"),
+    ("--text-muted", "secondary text"),
+    ("--border", "borders and separators"),
+    ("--accent", "links and highlights"),
+    ("--user-bg", "user message background"),
+    ("--user-border", "user message border"),
+    ("--code-bg", "code block background"),
+    ("--diff-add-bg", "added diff lines"),
+    ("--diff-add-text", "added diff text"),
+    ("--diff-rem-bg", "removed diff lines"),
+    ("--diff-rem-text", "removed diff text"),
+    ("--font-sans", "body font stack"),
+    ("--font-mono", "code font stack"),
+    ("--agent-color", "agent accent, set on .agent-badge"),
+];
+
+pub fn dump_classes() -> String {
+    let width = STABLE_CLASSES
+        .iter()
+        .map(|(name, _)| name.len() + 1)
+        .chain(CSS_VARIABLES.iter().map(|(name, _)| name.len()))
+        .max()
+        .unwrap_or(0);
+    let mut out = String::from("/* classes */\n");
+    for (name, about) in STABLE_CLASSES {
+        out.push_str(&format!("{:<width$}  {about}\n", format!(".{name}")));
+    }
+    out.push_str("\n/* custom properties (set on :root) */\n");
+    for (name, about) in CSS_VARIABLES {
+        out.push_str(&format!("{name:<width$}  {about}\n"));
+    }
+    out
 }
 
 pub struct ViewMessage {
@@ -32,6 +133,36 @@ pub struct ViewMessage {
     pub is_tool: bool,
     pub ts: String,
     pub content_html: String,
+    pub provenance: Vec<ViewProvenance>,
+    pub oversized: Option<Oversized>,
+    pub burst: Option<BurstBreak>,
+}
+
+pub struct Oversized {
+    pub preview: String,
+    pub size_label: String,
+    pub sidecar_href: Option<String>,
+}
+
+pub struct Sidecar {
+    pub file_name: String,
+    pub content: String,
+}
+
+pub struct HtmlExport {
+    pub html: String,
+    pub sidecars: Vec<Sidecar>,
+}
+
+pub const OVERSIZED_MESSAGE_BYTES: usize = 32 * 1024;
+const OVERSIZED_PREVIEW_CHARS: usize = 2_000;
+
+pub struct ViewProvenance {
+    pub agent: String,
+    pub source_path: String,
+    pub source_id: String,
+    pub repo: Option<String>,
+    pub href: Option<String>,
 }
 
 #[derive(Clone)]
@@ -40,25 +171,73 @@ struct PendingToolCall {
     label: String,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn render_session_html(
     session: &Session,
     messages: &[Message],
+    provenance: &[Provenance],
+    stats: Option<&SessionStats>,
     safety: HtmlSafety,
-) -> anyhow::Result<String> {
-    let view_messages = if safety == HtmlSafety::Strict {
+    sidecar_dir: Option<&str>,
+    custom_css: Option<&str>,
+    burst_gap_minutes: u64,
+) -> anyhow::Result<HtmlExport> {
+    let mut view_messages = if safety == HtmlSafety::Strict {
         build_strict_messages(messages)
     } else {
         build_markdown_messages(messages, safety)
     };
+    attach_provenance(&mut view_messages, messages, provenance);
+    for b in stats::burst_breaks(messages, burst_gap_minutes) {
+        let index = b.index;
+        view_messages[index].burst = Some(b);
+    }
+    let sidecars = collapse_oversized(&mut view_messages, messages, sidecar_dir);
+    let theme = crate::theme::agent_theme(session.agent);
     let tpl = SessionTemplate {
         title: &session.title,
         agent: session.agent.as_str(),
+        agent_icon: theme.icon,
+        agent_color: theme.hex,
         id: &session.id,
         message_count: messages.len(),
+        stats: stats.map(SessionStats::lines).unwrap_or_default(),
         messages: view_messages,
+        custom_css: custom_css.unwrap_or_default(),
+        lang: i18n::locale(),
     };
-    tpl.render()
-        .with_context(|| "rendering session HTML template")
+    let html = tpl
+        .render()
+        .with_context(|| "rendering session HTML template")?;
+    Ok(HtmlExport { html, sidecars })
+}
+
+fn collapse_oversized(
+    view_messages: &mut [ViewMessage],
+    messages: &[Message],
+    sidecar_dir: Option<&str>,
+) -> Vec<Sidecar> {
+    let mut sidecars = Vec::new();
+    for (idx, (view, m)) in view_messages.iter_mut().zip(messages).enumerate() {
+        if m.content.len() < OVERSIZED_MESSAGE_BYTES {
+            continue;
+        }
+        let sidecar_href = sidecar_dir.map(|dir| {
+            let file_name = format!("message-{idx:05}.txt");
+            sidecars.push(Sidecar {
+                file_name: file_name.clone(),
+                content: m.content.clone(),
+            });
+            view.content_html.clear();
+            format!("{dir}/{file_name}")
+        });
+        view.oversized = Some(Oversized {
+            preview: truncate_text(&m.content, OVERSIZED_PREVIEW_CHARS),
+            size_label: format!("{:.1} KiB", m.content.len() as f64 / 1024.0),
+            sidecar_href,
+        });
+    }
+    sidecars
 }
 
 fn build_markdown_messages(messages: &[Message], safety: HtmlSafety) -> Vec<ViewMessage> {
@@ -79,6 +258,9 @@ fn build_markdown_messages(messages: &[Message], safety: HtmlSafety) -> Vec<View
             is_tool,
             ts: m.ts.to_rfc3339(),
             content_html: render_markdown_to_html(&m.content, &mut pending_tool_calls, safety),
+            provenance: Vec::new(),
+            oversized: None,
+            burst: None,
         });
     }
     view_messages
@@ -98,11 +280,95 @@ fn build_strict_messages(messages: &[Message]) -> Vec<ViewMessage> {
             is_tool,
             ts: m.ts.to_rfc3339(),
             content_html: format!("<pre>{}</pre>", escape_html(&m.content)),
+            provenance: Vec::new(),
+            oversized: None,
+            burst: None,
         });
     }
     view_messages
 }
 
+fn attach_provenance(
+    view_messages: &mut [ViewMessage],
+    messages: &[Message],
+    provenance: &[Provenance],
+) {
+    let mut by_message: HashMap<&str, Vec<&Provenance>> = HashMap::new();
+    for p in provenance.iter().filter(|p| p.entity_type == "message") {
+        by_message.entry(p.entity_id.as_str()).or_default().push(p);
+    }
+    for (view, m) in view_messages.iter_mut().zip(messages) {
+        let Some(entries) = by_message.get(m.id.as_str()) else {
+            continue;
+        };
+        view.provenance = entries
+            .iter()
+            .map(|p| ViewProvenance {
+                agent: p.agent.as_str().to_string(),
+                source_path: p.source_path.clone(),
+                source_id: p.source_id.clone(),
+                repo: p.repo.as_ref().map(|r| {
+                    if r.path.is_empty() {
+                        r.remote.clone()
+                    } else {
+                        format!("{}/{}", r.remote, r.path)
+                    }
+                }),
+                href: source_href(p),
+            })
+            .collect();
+    }
+}
+
+// A workspace that has moved no longer exists at its recorded path, so link
+// the repository it belonged to instead.
+fn source_href(p: &Provenance) -> Option<String> {
+    if let Some(repo) = &p.repo
+        && !std::path::Path::new(&p.source_path).exists()
+    {
+        return repo_url(repo);
+    }
+    file_url(&p.source_path)
+}
+
+fn repo_url(repo: &RepoRef) -> Option<String> {
+    if repo.remote.starts_with('/') || !repo.remote.contains('.') {
+        return None;
+    }
+    let mut out = format!("https://{}", repo.remote);
+    if !repo.path.is_empty() {
+        out.push_str("/tree/HEAD/");
+        push_percent_encoded(&mut out, &repo.path);
+    }
+    Some(out)
+}
+
+fn file_url(path: &str) -> Option<String> {
+    let path = std::path::Path::new(path);
+    if !path.is_absolute() {
+        return None;
+    }
+    let raw = path.to_string_lossy().replace('\\', "/");
+    let mut out = String::from(if raw.starts_with('/') {
+        "file://"
+    } else {
+        "file:///"
+    });
+    push_percent_encoded(&mut out, &raw);
+    Some(out)
+}
+
+fn push_percent_encoded(out: &mut String, raw: &str) {
+    for byte in raw.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' | b':' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{byte:02X}")),
+        }
+    }
+}
+
 pub fn render_session_markdown(session: &Session, messages: &[Message]) -> String {
     let mut out = String::new();
     out.push_str(&format!("# {}\n\n", session.title));
@@ -120,6 +386,33 @@ pub fn render_session_markdown(session: &Session, messages: &[Message]) -> Strin
     out
 }
 
+// One entry per retained native record, with the source file and message
+// ids from provenance so a payload can be traced to what it produced.
+pub fn render_raw_records(
+    records: &[NativeRecord],
+    provenance: &[Provenance],
+) -> anyhow::Result<String> {
+    let entries: Vec<Value> = records
+        .iter()
+        .map(|rec| {
+            let matching: Vec<&Provenance> = provenance
+                .iter()
+                .filter(|p| p.source_id == rec.source_id)
+                .collect();
+            serde_json::json!({
+                "source_id": rec.source_id,
+                "source_path": matching.first().map(|p| p.source_path.as_str()),
+                "updated_at": rec.updated_at.to_rfc3339(),
+                "message_ids": matching.iter().map(|p| p.entity_id.as_str()).collect::<Vec<_>>(),
+                "payload": rec.payload,
+            })
+        })
+        .collect();
+    Ok(serde_json::to_string_pretty(&crate::schema::document(
+        "records", entries,
+    ))?)
+}
+
 fn message_contains_tool_markers(text: &str) -> bool {
     text.lines()
         .map(str::trim)
@@ -730,7 +1023,7 @@ fn render_sota_diff(diff: &str) -> String {
     html
 }
 
-fn escape_html(input: &str) -> String {
+pub fn escape_html(input: &str) -> String {
     input
         .replace('&', "&amp;")
         .replace('<', "&lt;")
@@ -743,6 +1036,286 @@ fn escape_html(input: &str) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn html_embeds_message_provenance_with_file_links() {
+        let now = chrono::Utc::now();
+        let session = Session {
+            id: "s1".to_string(),
+            agent: core_model::AgentKind::Pi,
+            source_ref: "ref".to_string(),
+            title: "Provenance".to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+        let messages = vec![Message {
+            id: "m1".to_string(),
+            session_id: "s1".to_string(),
+            role: "user".to_string(),
+            content: "hello".to_string(),
+            ts: now,
+        }];
+        let provenance = vec![Provenance {
+            id: "p1".to_string(),
+            entity_type: "message".to_string(),
+            entity_id: "m1".to_string(),
+            agent: core_model::AgentKind::Pi,
+            source_path: "/tmp/my logs/session.jsonl".to_string(),
+            source_id: "sess:0001".to_string(),
+            workspace: None,
+            repo: None,
+        }];
+        let html = render_session_html(
+            &session,
+            &messages,
+            &provenance,
+            None,
+            HtmlSafety::Strict,
+            None,
+            None,
+            0,
+        )
+        .unwrap()
+        .html;
+        assert!(html.contains(r#"<details class="provenance">"#));
+        assert!(html.contains(r#"href="file:///tmp/my%20logs/session.jsonl""#));
+        assert!(html.contains("sess:0001"));
+        assert!(html.contains("--agent-color: #a855f7"));
+
+        let bare = render_session_html(
+            &session,
+            &messages,
+            &[],
+            None,
+            HtmlSafety::Strict,
+            None,
+            None,
+            0,
+        )
+        .unwrap()
+        .html;
+        assert!(!bare.contains(r#"<details class="provenance">"#));
+        assert!(!bare.contains(r#"<details class="session-stats">"#));
+
+        let aggregate = store_sqlite::SessionAggregate {
+            message_count: 1,
+            first_ts: now,
+            last_ts: now,
+            by_role: vec![("user".to_string(), 1)],
+        };
+        let stats = SessionStats::new(aggregate, &messages);
+        let with_stats = render_session_html(
+            &session,
+            &messages,
+            &[],
+            Some(&stats),
+            HtmlSafety::Strict,
+            None,
+            None,
+            0,
+        )
+        .unwrap()
+        .html;
+        assert!(with_stats.contains(r#"<details class="session-stats">"#));
+        assert!(with_stats.contains("<dt>roles</dt><dd>user=1</dd>"));
+    }
+
+    #[test]
+    fn html_separates_work_bursts() {
+        let start = chrono::Utc::now();
+        let session = Session {
+            id: "s1".to_string(),
+            agent: core_model::AgentKind::Pi,
+            source_ref: "ref".to_string(),
+            title: "Bursts".to_string(),
+            created_at: start,
+            updated_at: start,
+        };
+        let messages: Vec<Message> = [0, 5, 185]
+            .iter()
+            .map(|minutes| Message {
+                id: format!("m{minutes}"),
+                session_id: "s1".to_string(),
+                role: "user".to_string(),
+                content: "hello".to_string(),
+                ts: start + chrono::Duration::minutes(*minutes),
+            })
+            .collect();
+        let render = |gap| {
+            render_session_html(
+                &session,
+                &messages,
+                &[],
+                None,
+                HtmlSafety::Strict,
+                None,
+                None,
+                gap,
+            )
+            .unwrap()
+            .html
+        };
+        let html = render(30);
+        assert_eq!(html.matches(r#"<div class="burst-break""#).count(), 1);
+        assert!(html.contains(r##"id="burst-2"><a href="#burst-2">burst 2 of 2 · 3h idle</a>"##));
+        assert!(!render(0).contains(r#"<div class="burst-break""#));
+    }
+
+    #[test]
+    fn template_classes_are_documented_and_custom_css_is_appended() {
+        let source = include_str!("../templates/session.html");
+        for attr in source.split("class=\"").skip(1) {
+            let value = &attr[..attr.find('"').unwrap()];
+            let value = value.replace("{% if msg.is_user %}user{% endif %}", "user");
+            let value = value.replace("{% if msg.is_tool %} tool{% endif %}", " tool");
+            for class in value.split_whitespace() {
+                assert!(
+                    STABLE_CLASSES.iter().any(|(name, _)| *name == class),
+                    "undocumented class {class}"
+                );
+            }
+        }
+        for line in source.lines() {
+            if let Some((name, _)) = line.trim().split_once(':')
+                && name.starts_with("--")
+            {
+                assert!(
+                    CSS_VARIABLES.iter().any(|(var, _)| *var == name),
+                    "undocumented variable {name}"
+                );
+            }
+        }
+        assert!(dump_classes().contains(".role-badge"));
+
+        let session = Session {
+            id: "s1".to_string(),
+            agent: core_model::AgentKind::Pi,
+            source_ref: "/tmp/s1.jsonl".to_string(),
+            title: "t".to_string(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        let styled = render_session_html(
+            &session,
+            &[],
+            &[],
+            None,
+            HtmlSafety::Strict,
+            None,
+            Some(".message.user { border: 0; }"),
+            0,
+        )
+        .unwrap()
+        .html;
+        let custom = styled.find(".message.user { border: 0; }").unwrap();
+        assert!(custom > styled.find("</style>").unwrap());
+        assert!(custom < styled.find("</head>").unwrap());
+        let plain =
+            render_session_html(&session, &[], &[], None, HtmlSafety::Strict, None, None, 0)
+                .unwrap()
+                .html;
+        assert_eq!(plain.matches("<style>").count(), 1);
+    }
+
+    #[test]
+    fn moved_workspaces_link_to_their_repository() {
+        let repo = RepoRef {
+            remote: "github.com/lsj5031/Remi".to_string(),
+            path: "crates/cli".to_string(),
+        };
+        let mut p = Provenance {
+            id: "p1".to_string(),
+            entity_type: "message".to_string(),
+            entity_id: "m1".to_string(),
+            agent: core_model::AgentKind::Codex,
+            source_path: "/nonexistent/remi-moved/crates/cli".to_string(),
+            source_id: "sess:0001".to_string(),
+            workspace: None,
+            repo: Some(repo),
+        };
+        assert_eq!(
+            source_href(&p).as_deref(),
+            Some("https://github.com/lsj5031/Remi/tree/HEAD/crates/cli")
+        );
+
+        let present = std::env::temp_dir();
+        p.source_path = present.to_string_lossy().into_owned();
+        assert_eq!(source_href(&p), file_url(&p.source_path));
+
+        p.source_path = "/nonexistent/remi-moved".to_string();
+        p.repo = Some(RepoRef {
+            remote: "/srv/git/remi".to_string(),
+            path: String::new(),
+        });
+        assert_eq!(source_href(&p), None);
+    }
+
+    #[test]
+    fn oversized_messages_collapse_or_move_to_sidecars() {
+        let now = chrono::Utc::now();
+        let session = Session {
+            id: "s1".to_string(),
+            agent: core_model::AgentKind::Pi,
+            source_ref: "ref".to_string(),
+            title: "Big".to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+        let big = format!("start {} end_marker", "x".repeat(OVERSIZED_MESSAGE_BYTES));
+        let messages = vec![
+            Message {
+                id: "m1".to_string(),
+                session_id: "s1".to_string(),
+                role: "user".to_string(),
+                content: "small".to_string(),
+                ts: now,
+            },
+            Message {
+                id: "m2".to_string(),
+                session_id: "s1".to_string(),
+                role: "tool".to_string(),
+                content: big.clone(),
+                ts: now,
+            },
+        ];
+
+        let inline = render_session_html(
+            &session,
+            &messages,
+            &[],
+            None,
+            HtmlSafety::Strict,
+            None,
+            None,
+            0,
+        )
+        .unwrap();
+        assert!(inline.sidecars.is_empty());
+        assert!(inline.html.contains(r#"<details class="oversized">"#));
+        assert!(inline.html.contains("end_marker"));
+
+        let split = render_session_html(
+            &session,
+            &messages,
+            &[],
+            None,
+            HtmlSafety::Strict,
+            Some("session_s1_files"),
+            None,
+            0,
+        )
+        .unwrap();
+        assert_eq!(split.sidecars.len(), 1);
+        assert_eq!(split.sidecars[0].file_name, "message-00001.txt");
+        assert_eq!(split.sidecars[0].content, big);
+        assert!(
+            split
+                .html
+                .contains(r#"href="session_s1_files/message-00001.txt""#)
+        );
+        assert!(!split.html.contains("end_marker"));
+        assert!(split.html.contains("small"));
+    }
+
     #[test]
     fn renders_diff_from_tool_json_payload() {
         let payload =
@@ -903,4 +1476,30 @@ mod tests {
             "assistant preface\n\ntool_result: payload"
         ));
     }
+
+    #[test]
+    fn raw_records_carry_their_provenance() {
+        let record = NativeRecord {
+            source_id: "r1".to_string(),
+            updated_at: chrono::Utc::now(),
+            payload: serde_json::json!({ "type": "user", "__source_path": "/tmp/a.jsonl" }),
+        };
+        let provenance = Provenance {
+            id: "p1".to_string(),
+            entity_type: "message".to_string(),
+            entity_id: "m1".to_string(),
+            agent: core_model::AgentKind::Claude,
+            source_path: "/tmp/a.jsonl".to_string(),
+            source_id: "r1".to_string(),
+            workspace: None,
+            repo: None,
+        };
+        let out = render_raw_records(&[record], &[provenance]).unwrap();
+        let parsed: Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(parsed["schema_version"], core_model::JSON_SCHEMA_VERSION);
+        let record = &parsed["records"][0];
+        assert_eq!(record["source_path"], "/tmp/a.jsonl");
+        assert_eq!(record["message_ids"], serde_json::json!(["m1"]));
+        assert_eq!(record["payload"]["type"], "user");
+    }
 }
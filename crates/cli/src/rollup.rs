@@ -0,0 +1,256 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, Utc};
+use core_model::{AgentKind, Message, NormalizedBatch, Session, deterministic_id};
+use store_sqlite::SqliteStore;
+
+use crate::digest::{Digest, active_sessions};
+
+// Sessions whose messages carry no repository remote are rolled up together.
+pub const NO_PROJECT: &str = "(no repository)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Day,
+    Week,
+}
+
+impl Period {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Period::Day => "day",
+            Period::Week => "week",
+        }
+    }
+
+    // Weeks run Monday to Sunday, in UTC like the rest of the store.
+    pub fn start(self, day: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Day => day,
+            Period::Week => day - Days::new(day.weekday().num_days_from_monday() as u64),
+        }
+    }
+
+    pub fn end(self, start: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Day => start,
+            Period::Week => start + Days::new(6),
+        }
+    }
+
+    pub fn previous(self, start: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Day => start - Days::new(1),
+            Period::Week => start - Days::new(7),
+        }
+    }
+}
+
+pub struct Rollup {
+    pub period: Period,
+    pub project: String,
+    pub digest: Digest,
+}
+
+// One rollup per project with activity in the period starting at `start`.
+pub fn build(store: &SqliteStore, period: Period, start: NaiveDate) -> anyhow::Result<Vec<Rollup>> {
+    let end = period.end(start);
+    let repos = store.session_repo_remotes()?;
+    let mut projects: BTreeMap<String, Vec<Session>> = BTreeMap::new();
+    for session in active_sessions(store, start, end, &Utc)? {
+        let project = repos
+            .get(&session.id)
+            .and_then(|remotes| remotes.first())
+            .map_or(NO_PROJECT, String::as_str)
+            .to_string();
+        projects.entry(project).or_default().push(session);
+    }
+    projects
+        .into_iter()
+        .map(|(project, sessions)| {
+            Ok(Rollup {
+                period,
+                digest: Digest::for_sessions(store, start, end, &Utc, &sessions)?,
+                project,
+            })
+        })
+        .collect()
+}
+
+impl Rollup {
+    pub fn source_ref(&self) -> String {
+        format!(
+            "rollup/{}/{}/{}",
+            self.period.as_str(),
+            self.digest.from,
+            self.project
+        )
+    }
+
+    pub fn title(&self) -> String {
+        match self.period {
+            Period::Day => format!("{}: {}", self.digest.from, self.project),
+            Period::Week => format!("Week of {}: {}", self.digest.from, self.project),
+        }
+    }
+
+    pub fn render_markdown(&self) -> String {
+        format!("# {}\n\n{}", self.title(), self.digest.render_body())
+    }
+
+    // A `meta` session with the summary as its only message. Ids derive from
+    // the period and project, so a rerun replaces the earlier rollup.
+    pub fn to_batch(&self, now: DateTime<Utc>) -> NormalizedBatch {
+        let source_ref = self.source_ref();
+        let kind = AgentKind::Meta.as_str();
+        let session_id = deterministic_id(&[kind, "session", &source_ref]);
+        let start = self.digest.from.and_hms_opt(0, 0, 0).unwrap().and_utc();
+        let end = self
+            .digest
+            .to
+            .and_hms_opt(23, 59, 59)
+            .unwrap()
+            .and_utc()
+            .min(now);
+        NormalizedBatch {
+            sessions: vec![Session {
+                id: session_id.clone(),
+                agent: AgentKind::Meta,
+                source_ref: source_ref.clone(),
+                title: self.title(),
+                created_at: start,
+                updated_at: end,
+            }],
+            messages: vec![Message {
+                id: deterministic_id(&[kind, "message", &source_ref]),
+                session_id,
+                role: "assistant".to_string(),
+                content: self.render_markdown(),
+                ts: end,
+            }],
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use core_model::{Provenance, RepoRef};
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 5, d).unwrap()
+    }
+
+    fn session(store: &mut SqliteStore, id: &str, d: u32, repo: Option<&str>, content: &str) {
+        let ts = Utc.with_ymd_and_hms(2025, 5, d, 12, 0, 0).unwrap();
+        store
+            .save_batch(&NormalizedBatch {
+                sessions: vec![Session {
+                    id: id.to_string(),
+                    agent: AgentKind::Claude,
+                    source_ref: id.to_string(),
+                    title: format!("{id} work"),
+                    created_at: ts,
+                    updated_at: ts,
+                }],
+                messages: vec![Message {
+                    id: format!("{id}-m"),
+                    session_id: id.to_string(),
+                    role: "user".to_string(),
+                    content: content.to_string(),
+                    ts,
+                }],
+                provenance: vec![Provenance {
+                    id: format!("{id}-p"),
+                    entity_type: "message".to_string(),
+                    entity_id: format!("{id}-m"),
+                    agent: AgentKind::Claude,
+                    source_path: "/src".to_string(),
+                    source_id: String::new(),
+                    workspace: None,
+                    repo: repo.map(|remote| RepoRef {
+                        remote: remote.to_string(),
+                        path: String::new(),
+                    }),
+                }],
+                ..Default::default()
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn periods_align_to_days_and_iso_weeks() {
+        // 2025-05-07 is a Wednesday.
+        assert_eq!(Period::Week.start(day(7)), day(5));
+        assert_eq!(Period::Week.end(day(5)), day(11));
+        assert_eq!(
+            Period::Week.previous(day(5)),
+            NaiveDate::from_ymd_opt(2025, 4, 28).unwrap()
+        );
+        assert_eq!(Period::Day.start(day(7)), day(7));
+        assert_eq!(Period::Day.end(day(7)), day(7));
+    }
+
+    #[test]
+    fn weekly_rollups_group_sessions_by_project_and_replace_themselves() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        session(
+            &mut store,
+            "a",
+            5,
+            Some("github.com/o/app"),
+            "the login redirect loops",
+        );
+        session(
+            &mut store,
+            "b",
+            9,
+            Some("github.com/o/app"),
+            "login tests are flaky",
+        );
+        session(&mut store, "c", 6, None, "write a shell alias");
+        session(&mut store, "d", 12, Some("github.com/o/app"), "next week");
+
+        let rollups = build(&store, Period::Week, day(5)).unwrap();
+        let projects: Vec<&str> = rollups.iter().map(|r| r.project.as_str()).collect();
+        assert_eq!(projects, vec![NO_PROJECT, "github.com/o/app"]);
+        let app = &rollups[1];
+        assert_eq!(app.digest.sessions.len(), 2);
+        assert_eq!(app.title(), "Week of 2025-05-05: github.com/o/app");
+        let markdown = app.render_markdown();
+        assert!(
+            markdown
+                .starts_with("# Week of 2025-05-05: github.com/o/app\n\n2 sessions active (2 new)"),
+            "{markdown}"
+        );
+        assert!(markdown.contains("login (2)"), "{markdown}");
+
+        let now = Utc.with_ymd_and_hms(2025, 6, 1, 0, 0, 0).unwrap();
+        let batch = app.to_batch(now);
+        assert_eq!(batch.sessions[0].agent, AgentKind::Meta);
+        assert_eq!(
+            batch.messages[0].ts,
+            Utc.with_ymd_and_hms(2025, 5, 11, 23, 59, 59).unwrap()
+        );
+        store.save_batch(&batch).unwrap();
+        store.save_batch(&app.to_batch(now)).unwrap();
+        let meta: Vec<Session> = store
+            .list_sessions()
+            .unwrap()
+            .into_iter()
+            .filter(|s| s.agent == AgentKind::Meta)
+            .collect();
+        assert_eq!(meta.len(), 1);
+        assert_eq!(
+            meta[0].source_ref,
+            "rollup/week/2025-05-05/github.com/o/app"
+        );
+
+        // A stored rollup is not itself rolled up.
+        let again = build(&store, Period::Week, day(5)).unwrap();
+        assert_eq!(again[1].digest.sessions.len(), 2);
+    }
+}
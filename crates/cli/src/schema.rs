@@ -0,0 +1,880 @@
+use core_model::JSON_SCHEMA_VERSION;
+use serde_json::{Map, Value, json};
+
+const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+// Array outputs are wrapped in an object so every document can carry its
+// schema version next to the rows.
+pub fn document(key: &str, rows: Vec<Value>) -> Value {
+    json!({ "schema_version": JSON_SCHEMA_VERSION, key: rows })
+}
+
+// Names accepted by `remi schema dump <NAME>`, in the order they are listed.
+pub fn names() -> Vec<&'static str> {
+    schemas().into_iter().map(|(name, _)| name).collect()
+}
+
+pub fn dump(name: Option<&str>) -> anyhow::Result<Value> {
+    let schemas = schemas();
+    if let Some(name) = name {
+        let Some((_, schema)) = schemas.into_iter().find(|(n, _)| *n == name) else {
+            anyhow::bail!("unknown schema {name}; known: {}", names().join(", "));
+        };
+        let mut schema = schema;
+        schema["$schema"] = json!(DIALECT);
+        return Ok(schema);
+    }
+    let defs: Map<String, Value> = schemas
+        .into_iter()
+        .map(|(name, schema)| (name.to_string(), schema))
+        .collect();
+    Ok(json!({
+        "$schema": DIALECT,
+        "title": "remi JSON outputs",
+        "schema_version": JSON_SCHEMA_VERSION,
+        "$defs": defs,
+    }))
+}
+
+fn schemas() -> Vec<(&'static str, Value)> {
+    let channels = object(&[
+        ("bm25", number()),
+        ("recency", number()),
+        ("semantic", number()),
+        ("substring", number()),
+    ]);
+    let rank = nullable(object(&[("rank", integer()), ("raw", nullable(number()))]));
+    let explain = object(&[
+        ("hits", integer()),
+        (
+            "ranks",
+            object(&[
+                ("bm25", rank.clone()),
+                ("recency", rank.clone()),
+                ("semantic", rank.clone()),
+                ("substring", rank),
+            ]),
+        ),
+        ("matched_terms", array(string())),
+        ("missing_terms", array(string())),
+    ]);
+    let repo = object(&[("remote", string()), ("path", string())]);
+    let session = with_optional(
+        object(&[
+            ("id", string()),
+            ("top_message_id", string()),
+            ("title", string()),
+            ("agent", string()),
+            ("updated_at", date_time()),
+            ("message_count", integer()),
+            ("snippet", string()),
+            ("score", number()),
+            ("channels", channels),
+            (
+                "provenance",
+                array(with_optional(
+                    object(&[("agent", string()), ("source_path", string())]),
+                    &[("repo", repo)],
+                )),
+            ),
+        ]),
+        &[("explain", explain)],
+    );
+    let search = versioned(
+        "remi search query --format json",
+        &[
+            ("query", string()),
+            (
+                "filters",
+                object(&[
+                    ("agent", nullable(string())),
+                    ("title", nullable(string())),
+                    ("id", nullable(string())),
+                    ("contains", nullable(string())),
+                    ("lang", nullable(string())),
+                    ("reasoning", enumeration(&["include", "exclude", "only"])),
+                    ("raw_fts", boolean()),
+                    ("as_of", nullable(date_time())),
+                    ("limit", integer()),
+                ]),
+            ),
+            (
+                "candidates",
+                object(&[
+                    ("bm25", integer()),
+                    ("recency", integer()),
+                    ("semantic", integer()),
+                    ("substring", integer()),
+                    ("messages", integer()),
+                    ("sessions", integer()),
+                    ("before_filters", integer()),
+                    ("returned", integer()),
+                ]),
+            ),
+            ("selected_index", integer()),
+            ("selected_session_id", string()),
+            ("sessions", array(session.clone())),
+        ],
+    );
+    vec![
+        ("search", search.clone()),
+        (
+            "search-ndjson",
+            versioned(
+                "one line of remi search query --format ndjson",
+                &[
+                    ("query", string()),
+                    ("rank", integer()),
+                    ("session", session),
+                ],
+            ),
+        ),
+        (
+            "search-batch",
+            versioned(
+                "remi search batch --format json",
+                &[
+                    ("queries", integer()),
+                    (
+                        "results",
+                        json!({ "type": "object", "additionalProperties": search }),
+                    ),
+                ],
+            ),
+        ),
+        (
+            "files-history",
+            versioned(
+                "remi files history --json",
+                &[(
+                    "sessions",
+                    array(object(&[
+                        ("session_id", string()),
+                        ("agent", string()),
+                        ("title", string()),
+                        ("mentions", integer()),
+                        ("first_ts", date_time()),
+                        ("last_ts", date_time()),
+                        ("tools", array(string())),
+                        ("paths", array(string())),
+                    ])),
+                )],
+            ),
+        ),
+        (
+            "links",
+            versioned(
+                "remi links list --json",
+                &[(
+                    "links",
+                    array(object(&[
+                        ("url", string()),
+                        ("context", string()),
+                        ("session_id", string()),
+                        ("message_id", string()),
+                        ("agent", string()),
+                        ("role", string()),
+                        ("ts", date_time()),
+                    ])),
+                )],
+            ),
+        ),
+        (
+            "commands",
+            versioned(
+                "remi commands search --json",
+                &[(
+                    "commands",
+                    array(object(&[
+                        ("session_id", string()),
+                        ("message_id", string()),
+                        ("agent", string()),
+                        ("tool", string()),
+                        ("command", string()),
+                        ("ts", date_time()),
+                        ("prompt", nullable(string())),
+                    ])),
+                )],
+            ),
+        ),
+        (
+            "stats-tools",
+            versioned(
+                "remi stats tools --json",
+                &[(
+                    "tools",
+                    array(object(&[
+                        ("agent", string()),
+                        ("tool", string()),
+                        ("calls", integer()),
+                        ("results", integer()),
+                        ("failures", integer()),
+                        ("failure_rate", number()),
+                        ("avg_output_bytes", integer()),
+                    ])),
+                )],
+            ),
+        ),
+        (
+            "stats-projects",
+            versioned(
+                "remi stats projects --json",
+                &[(
+                    "projects",
+                    array(object(&[
+                        ("repo", string()),
+                        ("sessions", integer()),
+                        ("messages", integer()),
+                        ("last_active", date_time()),
+                    ])),
+                )],
+            ),
+        ),
+        (
+            "stats-storage",
+            versioned(
+                "remi stats storage --json",
+                &[
+                    ("db_bytes", integer()),
+                    ("free_bytes", integer()),
+                    ("fts_bytes", integer()),
+                    ("embeddings_bytes", integer()),
+                    ("archive_dir", string()),
+                    ("archive_bytes", integer()),
+                    (
+                        "tables",
+                        array(object(&[("table", string()), ("bytes", integer())])),
+                    ),
+                    (
+                        "agents",
+                        array(object(&[
+                            ("agent", string()),
+                            ("messages", integer()),
+                            ("content_bytes", integer()),
+                        ])),
+                    ),
+                ],
+            ),
+        ),
+        (
+            "compare",
+            versioned(
+                "remi compare --json",
+                &[
+                    ("query", string()),
+                    (
+                        "runs",
+                        array(object(&[
+                            ("agent", string()),
+                            ("session_id", string()),
+                            ("title", string()),
+                            ("project", nullable(string())),
+                            ("messages", integer()),
+                            ("duration_secs", integer()),
+                            (
+                                "tools",
+                                array(object(&[("tool", string()), ("calls", integer())])),
+                            ),
+                            ("files", array(string())),
+                        ])),
+                    ),
+                ],
+            ),
+        ),
+        (
+            "tail",
+            versioned(
+                "remi tail --json (one document per line)",
+                &[
+                    ("agent", nullable(string())),
+                    ("session_id", string()),
+                    ("session_title", nullable(string())),
+                    ("message_id", string()),
+                    ("role", string()),
+                    ("ts", date_time()),
+                    ("content", string()),
+                ],
+            ),
+        ),
+        (
+            "debug-normalize",
+            versioned(
+                "remi debug normalize --json",
+                &[
+                    ("agent", string()),
+                    ("file", string()),
+                    (
+                        "scan",
+                        object(&[
+                            ("records", integer()),
+                            ("files_seen", integer()),
+                            ("files_skipped", integer()),
+                            ("parse_errors", integer()),
+                        ]),
+                    ),
+                    (
+                        "batch",
+                        with_optional(
+                            object(&[
+                                (
+                                    "sessions",
+                                    array(object(&[
+                                        ("id", string()),
+                                        ("agent", string()),
+                                        ("source_ref", string()),
+                                        ("title", string()),
+                                        ("created_at", date_time()),
+                                        ("updated_at", date_time()),
+                                    ])),
+                                ),
+                                (
+                                    "messages",
+                                    array(object(&[
+                                        ("id", string()),
+                                        ("session_id", string()),
+                                        ("role", string()),
+                                        ("content", string()),
+                                        ("ts", date_time()),
+                                    ])),
+                                ),
+                                ("events", array(json!({}))),
+                                ("artifacts", array(json!({}))),
+                                ("provenance", array(json!({}))),
+                            ]),
+                            &[("session_meta", array(json!({})))],
+                        ),
+                    ),
+                ],
+            ),
+        ),
+        (
+            "debug-dedupe",
+            versioned("remi debug dedupe --json", &{
+                let candidate = object(&[
+                    ("source_path", string()),
+                    ("line", integer()),
+                    ("source_id", string()),
+                    ("priority", integer()),
+                    ("richness", integer()),
+                    ("ts", date_time()),
+                ]);
+                [
+                    ("files", array(string())),
+                    ("candidates", integer()),
+                    ("kept", integer()),
+                    (
+                        "dropped",
+                        array(object(&[
+                            ("key", string()),
+                            (
+                                "reason",
+                                enumeration(&["priority", "richness", "timestamp", "source_id"]),
+                            ),
+                            ("same_content", boolean()),
+                            ("kept", candidate.clone()),
+                            ("dropped", candidate),
+                        ])),
+                    ),
+                ]
+            }),
+        ),
+        (
+            "eval",
+            versioned("remi eval --json", &{
+                let recall = json!({ "type": "object", "additionalProperties": number() });
+                [
+                    ("queries", integer()),
+                    ("ks", array(integer())),
+                    ("mrr", number()),
+                    ("recall", recall.clone()),
+                    (
+                        "cases",
+                        array(object(&[
+                            ("query", string()),
+                            ("rank", nullable(integer())),
+                            ("reciprocal_rank", number()),
+                            ("recall", recall),
+                            ("expected", integer()),
+                            ("returned", integer()),
+                        ])),
+                    ),
+                ]
+            }),
+        ),
+        (
+            "cluster",
+            versioned(
+                "remi cluster --json",
+                &[
+                    ("sessions", integer()),
+                    (
+                        "clusters",
+                        array(object(&[
+                            ("id", integer()),
+                            ("terms", array(string())),
+                            (
+                                "sessions",
+                                array(object(&[
+                                    ("session_id", string()),
+                                    ("title", string()),
+                                    ("agent", string()),
+                                    ("similarity", number()),
+                                    ("x", number()),
+                                    ("y", number()),
+                                ])),
+                            ),
+                        ])),
+                    ),
+                ],
+            ),
+        ),
+        (
+            "graph",
+            versioned(
+                "remi graph export --format json",
+                &[
+                    (
+                        "nodes",
+                        array(object(&[
+                            ("id", integer()),
+                            ("kind", enumeration(&["repo", "file", "tool", "error"])),
+                            ("name", string()),
+                            ("sessions", integer()),
+                        ])),
+                    ),
+                    (
+                        "edges",
+                        array(object(&[
+                            ("source", integer()),
+                            ("target", integer()),
+                            ("relation", enumeration(&["uses", "co_occurs"])),
+                            ("sessions", integer()),
+                        ])),
+                    ),
+                ],
+            ),
+        ),
+        (
+            "wipe",
+            versioned(
+                "remi wipe --json",
+                &[
+                    ("wiped_at", date_time()),
+                    ("dry_run", boolean()),
+                    (
+                        "criteria",
+                        object(&[
+                            ("project", nullable(string())),
+                            ("matching", nullable(string())),
+                        ]),
+                    ),
+                    (
+                        "sessions",
+                        array(object(&[
+                            ("id", string()),
+                            ("agent", string()),
+                            ("created_at", date_time()),
+                            ("updated_at", date_time()),
+                            ("messages", integer()),
+                            ("trashed", boolean()),
+                        ])),
+                    ),
+                    (
+                        "counts",
+                        nullable(object(&[
+                            ("sessions", integer()),
+                            ("messages", integer()),
+                            ("events", integer()),
+                            ("artifacts", integer()),
+                            ("provenance", integer()),
+                            ("embeddings", integer()),
+                            ("fts_rows", integer()),
+                            ("raw_records", integer()),
+                            ("archive_items", integer()),
+                            ("cached_queries", integer()),
+                        ])),
+                    ),
+                    (
+                        "archives",
+                        array(object(&[
+                            ("run_id", string()),
+                            ("sessions", array(string())),
+                        ])),
+                    ),
+                ],
+            ),
+        ),
+        (
+            "export-jsonl",
+            versioned(
+                "one line of remi export --format jsonl",
+                &[
+                    (
+                        "session",
+                        object(&[
+                            ("id", string()),
+                            ("agent", string()),
+                            ("title", string()),
+                            ("created_at", date_time()),
+                            ("updated_at", date_time()),
+                        ]),
+                    ),
+                    (
+                        "messages",
+                        array(object(&[
+                            ("id", string()),
+                            ("role", string()),
+                            ("content", string()),
+                            ("ts", date_time()),
+                        ])),
+                    ),
+                ],
+            ),
+        ),
+        (
+            "export-rawjson",
+            versioned(
+                "remi search query --format rawjson",
+                &[(
+                    "records",
+                    array(object(&[
+                        ("source_id", string()),
+                        ("source_path", nullable(string())),
+                        ("updated_at", date_time()),
+                        ("message_ids", array(string())),
+                        ("payload", json!({})),
+                    ])),
+                )],
+            ),
+        ),
+    ]
+}
+
+fn versioned(description: &str, properties: &[(&str, Value)]) -> Value {
+    let mut all = vec![(
+        "schema_version",
+        json!({ "type": "integer", "const": JSON_SCHEMA_VERSION }),
+    )];
+    all.extend(properties.iter().cloned());
+    let mut schema = object(&all);
+    schema["description"] = json!(description);
+    schema
+}
+
+// Every listed property is required; unknown ones are allowed so consumers
+// keep validating when a field is added within the same schema version.
+fn object(properties: &[(&str, Value)]) -> Value {
+    json!({
+        "type": "object",
+        "required": properties.iter().map(|(name, _)| *name).collect::<Vec<_>>(),
+        "properties": properties
+            .iter()
+            .map(|(name, schema)| (name.to_string(), schema.clone()))
+            .collect::<Map<String, Value>>(),
+    })
+}
+
+fn with_optional(mut schema: Value, properties: &[(&str, Value)]) -> Value {
+    for (name, property) in properties {
+        schema["properties"][*name] = property.clone();
+    }
+    schema
+}
+
+fn array(items: Value) -> Value {
+    json!({ "type": "array", "items": items })
+}
+
+fn nullable(schema: Value) -> Value {
+    json!({ "anyOf": [schema, { "type": "null" }] })
+}
+
+fn enumeration(values: &[&str]) -> Value {
+    json!({ "type": "string", "enum": values })
+}
+
+fn string() -> Value {
+    json!({ "type": "string" })
+}
+
+fn date_time() -> Value {
+    json!({ "type": "string", "format": "date-time" })
+}
+
+fn integer() -> Value {
+    json!({ "type": "integer" })
+}
+
+fn number() -> Value {
+    json!({ "type": "number" })
+}
+
+fn boolean() -> Value {
+    json!({ "type": "boolean" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use core_model::{AgentKind, Message, NativeRecord, NormalizedBatch, Provenance, Session};
+    use store_sqlite::{SqliteStore, StorageUsage};
+
+    // Checks the subset of JSON Schema the dump uses: types, required
+    // properties, array items, map values, const, enum and nullable unions.
+    fn check(schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+        if let Some(options) = schema.get("anyOf").and_then(Value::as_array) {
+            return options
+                .iter()
+                .find_map(|option| check(option, value, path).ok())
+                .ok_or_else(|| format!("{path}: no anyOf branch matched {value}"));
+        }
+        let matches = match schema.get("type").and_then(Value::as_str) {
+            Some("object") => value.is_object(),
+            Some("array") => value.is_array(),
+            Some("string") => value.is_string(),
+            Some("integer") => value.is_i64() || value.is_u64(),
+            Some("number") => value.is_number(),
+            Some("boolean") => value.is_boolean(),
+            Some("null") => value.is_null(),
+            _ => true,
+        };
+        if !matches {
+            return Err(format!("{path}: expected {schema}, got {value}"));
+        }
+        if let Some(expected) = schema.get("const")
+            && expected != value
+        {
+            return Err(format!("{path}: expected {expected}, got {value}"));
+        }
+        if let Some(options) = schema.get("enum").and_then(Value::as_array)
+            && !options.contains(value)
+        {
+            return Err(format!("{path}: {value} is not one of {options:?}"));
+        }
+        for name in schema["required"].as_array().into_iter().flatten() {
+            let name = name.as_str().unwrap();
+            if value.get(name).is_none() {
+                return Err(format!("{path}: missing {name}"));
+            }
+        }
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            for (name, property) in properties {
+                if let Some(field) = value.get(name) {
+                    check(property, field, &format!("{path}.{name}"))?;
+                }
+            }
+        }
+        if let (Some(each), Some(entries)) = (
+            schema.get("additionalProperties").filter(|v| v.is_object()),
+            value.as_object(),
+        ) {
+            for (key, entry) in entries {
+                check(each, entry, &format!("{path}.{key}"))?;
+            }
+        }
+        if let (Some(items), Some(values)) = (schema.get("items"), value.as_array()) {
+            for (i, item) in values.iter().enumerate() {
+                check(items, item, &format!("{path}[{i}]"))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn conforms(name: &str, value: &Value) {
+        let schema = dump(Some(name)).unwrap();
+        if let Err(err) = check(&schema, value, name) {
+            panic!("{err}\n{value:#}");
+        }
+    }
+
+    #[test]
+    fn dump_lists_every_schema_or_one_by_name() {
+        let all = dump(None).unwrap();
+        assert_eq!(all["schema_version"], JSON_SCHEMA_VERSION);
+        assert_eq!(all["$defs"].as_object().unwrap().len(), names().len());
+        let one = dump(Some("stats-storage")).unwrap();
+        assert_eq!(one["$schema"], DIALECT);
+        assert!(dump(Some("nope")).is_err());
+    }
+
+    #[test]
+    fn outputs_match_their_schemas() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let at = Utc.with_ymd_and_hms(2025, 5, 1, 9, 0, 0).unwrap();
+        store
+            .save_batch(&NormalizedBatch {
+                sessions: vec![Session {
+                    id: "s1".to_string(),
+                    agent: AgentKind::Codex,
+                    source_ref: "/tmp/s1.jsonl".to_string(),
+                    title: "retry loop".to_string(),
+                    created_at: at,
+                    updated_at: at,
+                }],
+                messages: vec![Message {
+                    id: "m1".to_string(),
+                    session_id: "s1".to_string(),
+                    role: "user".to_string(),
+                    content: "fix the retry loop".to_string(),
+                    ts: at,
+                }],
+                provenance: vec![Provenance {
+                    id: "p1".to_string(),
+                    entity_type: "message".to_string(),
+                    entity_id: "m1".to_string(),
+                    agent: AgentKind::Codex,
+                    source_path: "/tmp/s1.jsonl".to_string(),
+                    source_id: "r1".to_string(),
+                    workspace: None,
+                    repo: None,
+                }],
+                ..Default::default()
+            })
+            .unwrap();
+
+        let response = query::SearchRequest::new("retry")
+            .explain(true)
+            .execute(&store)
+            .unwrap();
+        for (rank, session) in response.sessions.clone().into_iter().enumerate() {
+            let line = query::ndjson_line("retry", rank, session);
+            conforms("search-ndjson", &serde_json::to_value(&line).unwrap());
+        }
+        let output = response.into_json(0);
+        conforms("search", &serde_json::to_value(&output).unwrap());
+        conforms(
+            "search-batch",
+            &crate::batch::document(vec![output]).unwrap(),
+        );
+
+        let report = crate::stats::StorageReport::new(
+            StorageUsage {
+                db_bytes: 10,
+                free_bytes: 0,
+                tables: vec![("messages".to_string(), 10)],
+                agents: Vec::new(),
+            },
+            std::path::Path::new("/nonexistent"),
+        );
+        conforms("stats-storage", &serde_json::to_value(report).unwrap());
+
+        let records = [NativeRecord {
+            source_id: "r1".to_string(),
+            updated_at: at,
+            payload: json!({ "type": "user" }),
+        }];
+        let provenance = store.get_provenance_for_session("s1").unwrap();
+        let raw = crate::render::render_raw_records(&records, &provenance).unwrap();
+        conforms("export-rawjson", &serde_json::from_str(&raw).unwrap());
+
+        let session = store.get_session("s1").unwrap().unwrap();
+        let messages = store.get_session_messages("s1").unwrap();
+        conforms(
+            "export-jsonl",
+            &crate::corpus::line(1, &session, &messages, None),
+        );
+        let mut anon = crate::anonymize::Anonymizer::new();
+        conforms(
+            "export-jsonl",
+            &crate::corpus::line(1, &session, &messages, Some(&mut anon)),
+        );
+
+        let line = crate::tail::Tail::new(&store, None, None)
+            .unwrap()
+            .backlog(&store, 1)
+            .unwrap();
+        conforms("tail", &line[0].to_json());
+
+        conforms("links", &document("links", Vec::new()));
+
+        let run = crate::debug::NormalizeRun {
+            agent: "codex",
+            file: "/tmp/s1.jsonl".to_string(),
+            records: 1,
+            report: None,
+            batch: NormalizedBatch {
+                sessions: vec![store.get_session("s1").unwrap().unwrap()],
+                messages: store.get_session_messages("s1").unwrap(),
+                ..Default::default()
+            },
+        };
+        conforms("debug-normalize", &run.to_json());
+
+        #[cfg(feature = "claude")]
+        {
+            let dir =
+                std::env::temp_dir().join(format!("remi_schema_dedupe_{}", std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+            let line =
+                r#"{"id":"m1","role":"user","content":"hi","timestamp":"2025-01-15T00:00:00Z"}"#;
+            let files = [dir.join("a.jsonl"), dir.join("b.jsonl")];
+            for file in &files {
+                std::fs::write(file, line).unwrap();
+            }
+            let run = crate::debug::dedupe_files(&files).unwrap();
+            conforms("debug-dedupe", &run.to_json());
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+
+        let case: crate::eval::GoldCase =
+            serde_json::from_value(json!({ "query": "hi", "sessions": ["s1"] })).unwrap();
+        let ranked = [crate::eval::Ranked {
+            session_id: "s1".to_string(),
+            top_message_id: "m1".to_string(),
+        }];
+        let scored = crate::eval::score_case(&case, &ranked, &[1, 5]);
+        let report = crate::eval::report(vec![scored], &[1, 5]);
+        conforms("eval", &report.to_json().unwrap());
+
+        let graph = store_sqlite::Graph {
+            nodes: vec![store_sqlite::GraphNode {
+                id: 1,
+                kind: "tool".to_string(),
+                name: "edit".to_string(),
+                sessions: 1,
+            }],
+            edges: vec![store_sqlite::GraphEdge {
+                source: 1,
+                target: 1,
+                relation: "co_occurs".to_string(),
+                sessions: 1,
+            }],
+        };
+        conforms("graph", &crate::graph::to_json(&graph));
+        conforms("graph", &crate::graph::to_json(&graph));
+
+        let criteria = crate::wipe::Criteria {
+            project: None,
+            pattern: Some(regex::Regex::new("retry").unwrap()),
+        };
+        let mut report = crate::wipe::Report {
+            wiped_at: at,
+            dry_run: true,
+            project: None,
+            matching: Some("retry".to_string()),
+            sessions: crate::wipe::select(&store, &criteria).unwrap(),
+            counts: None,
+            cached_queries: 0,
+            archives: vec![("run".to_string(), vec!["s0".to_string()])],
+        };
+        conforms("wipe", &report.to_json());
+        report.counts = Some(Default::default());
+        conforms("wipe", &report.to_json());
+
+        #[cfg(feature = "semantic")]
+        {
+            let item = |id: &str, vec: Vec<f32>| crate::cluster::Item {
+                session_id: id.to_string(),
+                title: "t".to_string(),
+                agent: "codex".to_string(),
+                vec,
+                words: Default::default(),
+            };
+            let items = vec![item("s1", vec![1.0, 0.0]), item("s2", vec![0.0, 1.0])];
+            let report = crate::cluster::build(items, 2).unwrap();
+            conforms("cluster", &report.to_json());
+        }
+    }
+}
@@ -0,0 +1,580 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    time::Duration,
+};
+
+use anyhow::Context;
+use serde_json::json;
+use store_sqlite::SqliteStore;
+use tracing::{debug, info, warn};
+
+use crate::{graphql, redact::Redactor, render, stats};
+
+const INDEX_HTML: &str = include_str!("../web/index.html");
+const APP_JS: &str = include_str!("../web/app.js");
+const APP_CSS: &str = include_str!("../web/app.css");
+const MAX_HEADER_BYTES: usize = 16 * 1024;
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+const MAX_LIMIT: usize = 200;
+
+pub struct Server<'a> {
+    store: &'a mut SqliteStore,
+    redactor: Option<Redactor>,
+    // Requests must name one of these hosts, which keeps other sites from
+    // reaching the API through DNS rebinding.
+    hosts: Vec<String>,
+    custom_css: Option<String>,
+    burst_gap_minutes: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub params: HashMap<String, String>,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+    pub headers: Vec<(&'static str, &'static str)>,
+}
+
+impl Response {
+    fn new(status: u16, content_type: &'static str, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            content_type,
+            body: body.into(),
+            headers: Vec::new(),
+        }
+    }
+
+    fn json(status: u16, value: &serde_json::Value) -> Self {
+        Self::new(status, "application/json", value.to_string())
+    }
+
+    fn error(status: u16, message: impl std::fmt::Display) -> Self {
+        Self::json(status, &json!({ "error": message.to_string() }))
+    }
+}
+
+impl<'a> Server<'a> {
+    pub fn new(store: &'a mut SqliteStore, port: u16, redactor: Option<Redactor>) -> Self {
+        let hosts = ["127.0.0.1", "localhost", "[::1]"]
+            .iter()
+            .map(|host| format!("{host}:{port}"))
+            .collect();
+        Self {
+            store,
+            redactor,
+            hosts,
+            custom_css: None,
+            burst_gap_minutes: stats::DEFAULT_BURST_GAP_MINUTES,
+        }
+    }
+
+    pub fn allow_host(&mut self, host: String) {
+        self.hosts.push(host);
+    }
+
+    pub fn custom_css(&mut self, css: String) {
+        self.custom_css = Some(css);
+    }
+
+    pub fn burst_gap(&mut self, minutes: u64) {
+        self.burst_gap_minutes = minutes;
+    }
+
+    // Requests are handled one at a time; the UI is meant for one local user.
+    pub fn run(&mut self, listener: TcpListener) -> anyhow::Result<()> {
+        for stream in listener.incoming() {
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    warn!(error = %e, "failed to accept connection");
+                    continue;
+                }
+            };
+            let _ = stream.set_read_timeout(Some(Duration::from_secs(10)));
+            let response = match read_request(&mut stream) {
+                Ok(request) => {
+                    debug!(method = %request.method, path = %request.path, "request");
+                    self.handle(&request)
+                }
+                Err(e) => Response::error(400, e),
+            };
+            if let Err(e) = write_response(&mut stream, &response) {
+                debug!(error = %e, "failed to write response");
+            }
+        }
+        Ok(())
+    }
+
+    pub fn handle(&mut self, request: &Request) -> Response {
+        let host = request.headers.get("host").map(String::as_str);
+        if !host.is_some_and(|h| self.hosts.iter().any(|allowed| allowed == h)) {
+            return Response::error(403, "unexpected Host header");
+        }
+        if request.method == "POST"
+            && let Some(origin) = request.headers.get("origin")
+            && origin.strip_prefix("http://") != host
+        {
+            return Response::error(403, "cross-origin request refused");
+        }
+        let result = match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/") => Ok(Response::new(200, "text/html; charset=utf-8", INDEX_HTML)),
+            ("GET", "/app.js") => Ok(Response::new(200, "text/javascript; charset=utf-8", APP_JS)),
+            ("GET", "/app.css") => Ok(Response::new(200, "text/css; charset=utf-8", APP_CSS)),
+            ("GET", "/api/search") => self.search(&request.params),
+            ("GET", "/api/archive/runs") => self.archive_runs(),
+            ("GET" | "POST", "/graphql") => self.graphql(request),
+            ("GET", "/graphql/schema") => Ok(Response::new(
+                200,
+                "text/plain; charset=utf-8",
+                graphql::sdl(self.store),
+            )),
+            ("POST", "/api/archive/plan") => self.archive_plan(&request.params),
+            ("GET", path) if path.starts_with("/sessions/") => {
+                self.session(&path["/sessions/".len()..])
+            }
+            ("POST", path) if path.starts_with("/api/archive/runs/") && path.ends_with("/run") => {
+                let run_id = &path["/api/archive/runs/".len()..path.len() - "/run".len()];
+                self.archive_run(run_id, &request.params)
+            }
+            (_, path) if path == "/" || path.starts_with("/api/") || path == "/graphql" => {
+                Ok(Response::error(405, "method not allowed"))
+            }
+            _ => Ok(Response::error(404, "not found")),
+        };
+        result.unwrap_or_else(|e| Response::error(500, format!("{e:#}")))
+    }
+
+    fn search(&mut self, params: &HashMap<String, String>) -> anyhow::Result<Response> {
+        let Some(q) = params.get("q").filter(|q| !q.trim().is_empty()) else {
+            return Ok(Response::error(400, "missing q"));
+        };
+        let param = |key: &str| params.get(key).filter(|v| !v.is_empty()).cloned();
+        let limit = match params.get("limit") {
+            Some(raw) => raw.parse::<usize>().context("invalid limit")?,
+            None => query::DEFAULT_LIMIT,
+        };
+        let reasoning = match params.get("reasoning").map(String::as_str) {
+            None | Some("include") => query::ReasoningFilter::Include,
+            Some("exclude") => query::ReasoningFilter::Exclude,
+            Some("only") => query::ReasoningFilter::Only,
+            Some(other) => return Ok(Response::error(400, format!("invalid reasoning {other}"))),
+        };
+        let response = query::SearchRequest::new(q.as_str())
+            .limit(limit.clamp(1, MAX_LIMIT))
+            .raw_fts(params.get("raw_fts").is_some_and(|v| v == "true"))
+            .reasoning(reasoning)
+            .lang(param("lang"))
+            .filters(query::FilterSpec {
+                agent: param("agent"),
+                title: param("title"),
+                id: param("id"),
+                contains: param("contains"),
+            })
+            .execute(self.store)?;
+        let mut output = response.into_json(0);
+        if let Some(redactor) = &self.redactor {
+            for session in &mut output.sessions {
+                session.title = redactor.redact(&session.title).into_owned();
+                session.snippet = redactor.redact(&session.snippet).into_owned();
+            }
+        }
+        Ok(Response::json(200, &serde_json::to_value(output)?))
+    }
+
+    // GET takes `?query=&operationName=&variables=` (variables as JSON);
+    // POST takes the usual `{"query", "operationName", "variables"}` body.
+    fn graphql(&mut self, request: &Request) -> anyhow::Result<Response> {
+        let graphql_request = if request.method == "GET" {
+            let Some(query) = request.params.get("query") else {
+                return Ok(Response::error(400, "missing query"));
+            };
+            let variables = match request.params.get("variables") {
+                Some(raw) => match serde_json::from_str(raw) {
+                    Ok(variables) => Some(variables),
+                    Err(_) => return Ok(Response::error(400, "variables must be JSON")),
+                },
+                None => None,
+            };
+            juniper::http::GraphQLRequest::new(
+                query.clone(),
+                request.params.get("operationName").cloned(),
+                variables,
+            )
+        } else {
+            match serde_json::from_slice(&request.body) {
+                Ok(body) => body,
+                Err(_) => return Ok(Response::error(400, "body must be a JSON GraphQL request")),
+            }
+        };
+        let response = graphql::execute(self.store, self.redactor.as_ref(), &graphql_request);
+        let status = if response.is_ok() { 200 } else { 400 };
+        Ok(Response::json(status, &serde_json::to_value(&response)?))
+    }
+
+    fn session(&mut self, session_id: &str) -> anyhow::Result<Response> {
+        let Some(store_sqlite::SessionBundle {
+            mut session,
+            mut messages,
+            provenance,
+            ..
+        }) = self.store.get_session_bundle(session_id)?
+        else {
+            return Ok(Response::error(404, "session not found"));
+        };
+        if let Some(redactor) = &self.redactor {
+            session.title = redactor.redact(&session.title).into_owned();
+            redactor.redact_messages(&mut messages);
+        }
+        let session_stats = self
+            .store
+            .session_aggregate(&session.id)?
+            .map(|aggregate| stats::SessionStats::new(aggregate, &messages));
+        let export = render::render_session_html(
+            &session,
+            &messages,
+            &provenance,
+            session_stats.as_ref(),
+            render::HtmlSafety::Relaxed,
+            None,
+            self.custom_css.as_deref(),
+            self.burst_gap_minutes,
+        )?;
+        let mut response = Response::new(200, "text/html; charset=utf-8", export.html);
+        response
+            .headers
+            .push(("Content-Security-Policy", "script-src 'none'"));
+        Ok(response)
+    }
+
+    fn archive_runs(&mut self) -> anyhow::Result<Response> {
+        let mut runs = Vec::new();
+        for run in self.store.archive_runs()? {
+            let sessions = self.store.archive_items_for_run(&run.id)?.len();
+            let mut value = serde_json::to_value(&run)?;
+            value["sessions"] = json!(sessions);
+            runs.push(value);
+        }
+        Ok(Response::json(200, &json!(runs)))
+    }
+
+    fn archive_plan(&mut self, params: &HashMap<String, String>) -> anyhow::Result<Response> {
+        let older_than = params.get("older_than").map_or("90d", String::as_str);
+        let older_than = humantime::parse_duration(older_than).context("invalid older_than")?;
+        let keep_latest = match params.get("keep_latest") {
+            Some(raw) => raw.parse::<usize>().context("invalid keep_latest")?,
+            None => 20,
+        };
+        let run_id = archive::archive_plan(
+            self.store,
+            chrono::Duration::from_std(older_than)?,
+            keep_latest,
+        )?;
+        let sessions = self.store.archive_items_for_run(&run_id)?.len();
+        info!(%run_id, sessions, "archive planned from web ui");
+        Ok(Response::json(
+            200,
+            &json!({ "id": run_id, "sessions": sessions }),
+        ))
+    }
+
+    fn archive_run(
+        &mut self,
+        run_id: &str,
+        params: &HashMap<String, String>,
+    ) -> anyhow::Result<Response> {
+        let flag = |key: &str| params.get(key).is_some_and(|v| v == "true");
+        let execute = flag("execute");
+        let delete_source = flag("delete_source");
+        if delete_source && !execute {
+            return Ok(Response::error(400, "delete_source needs execute"));
+        }
+        let message = archive::archive_run(self.store, run_id, execute, delete_source)?;
+        info!(%run_id, execute, delete_source, "archive run from web ui");
+        Ok(Response::json(200, &json!({ "message": message })))
+    }
+}
+
+fn read_request(stream: &mut TcpStream) -> anyhow::Result<Request> {
+    let mut reader = BufReader::new(stream.take((MAX_HEADER_BYTES + MAX_BODY_BYTES) as u64));
+    let mut line = String::new();
+    let mut header_bytes = reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        anyhow::bail!("malformed request line");
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let mut request = Request {
+        method: method.to_string(),
+        path: percent_decode(path),
+        params: parse_query(query),
+        headers: HashMap::new(),
+        body: Vec::new(),
+    };
+    loop {
+        line.clear();
+        let read = reader.read_line(&mut line)?;
+        header_bytes += read;
+        if read == 0 || header_bytes > MAX_HEADER_BYTES {
+            anyhow::bail!("request headers too large or truncated");
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            request
+                .headers
+                .insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+    if let Some(length) = request.headers.get("content-length") {
+        let length: usize = length.parse().context("invalid Content-Length")?;
+        if length > MAX_BODY_BYTES {
+            anyhow::bail!("request body too large");
+        }
+        request.body.resize(length, 0);
+        reader.read_exact(&mut request.body)?;
+    }
+    Ok(request)
+}
+
+fn write_response(stream: &mut TcpStream, response: &Response) -> std::io::Result<()> {
+    let reason = match response.status {
+        200 => "OK",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error",
+    };
+    let mut head = format!(
+        "HTTP/1.1 {} {reason}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\nX-Content-Type-Options: nosniff\r\n",
+        response.status,
+        response.content_type,
+        response.body.len()
+    );
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(&response.body)?;
+    stream.flush()
+}
+
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (
+                percent_decode(&key.replace('+', " ")),
+                percent_decode(&value.replace('+', " ")),
+            )
+        })
+        .collect()
+}
+
+fn percent_decode(raw: &str) -> String {
+    let bytes = raw.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(hex) = raw.get(i + 1..i + 3)
+            && let Ok(byte) = u8::from_str_radix(hex, 16)
+        {
+            out.push(byte);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration as ChronoDuration, Utc};
+    use core_model::{AgentKind, Message, NormalizedBatch, Session};
+
+    fn store() -> SqliteStore {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let old = Utc::now() - ChronoDuration::days(400);
+        store
+            .save_batch(&NormalizedBatch {
+                sessions: vec![Session {
+                    id: "s1".to_string(),
+                    agent: AgentKind::Claude,
+                    source_ref: "/tmp/s1.jsonl".to_string(),
+                    title: "flaky login test".to_string(),
+                    created_at: old,
+                    updated_at: old,
+                }],
+                messages: vec![Message {
+                    id: "m1".to_string(),
+                    session_id: "s1".to_string(),
+                    role: "user".to_string(),
+                    content: "why is the login test flaky".to_string(),
+                    ts: old,
+                }],
+                ..Default::default()
+            })
+            .unwrap();
+        store
+    }
+
+    fn request(method: &str, target: &str) -> Request {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            params: parse_query(query),
+            headers: HashMap::from([("host".to_string(), "127.0.0.1:7070".to_string())]),
+            body: Vec::new(),
+        }
+    }
+
+    fn json_body(response: &Response) -> serde_json::Value {
+        serde_json::from_slice(&response.body).unwrap()
+    }
+
+    #[test]
+    fn serves_ui_search_and_sessions() {
+        let mut store = store();
+        let mut server = Server::new(&mut store, 7070, None);
+
+        let index = server.handle(&request("GET", "/"));
+        assert_eq!(index.status, 200);
+        assert!(String::from_utf8_lossy(&index.body).contains("/app.js"));
+
+        let hits = server.handle(&request("GET", "/api/search?q=login+test&agent=claude"));
+        assert_eq!(hits.status, 200);
+        let hits = json_body(&hits);
+        assert_eq!(hits["sessions"][0]["id"], "s1");
+        let none = server.handle(&request("GET", "/api/search?q=login&agent=codex"));
+        assert_eq!(json_body(&none)["sessions"].as_array().unwrap().len(), 0);
+        assert_eq!(server.handle(&request("GET", "/api/search")).status, 400);
+
+        let page = server.handle(&request("GET", "/sessions/s1"));
+        assert_eq!(page.status, 200);
+        assert!(String::from_utf8_lossy(&page.body).contains("flaky login test"));
+        assert_eq!(server.handle(&request("GET", "/sessions/nope")).status, 404);
+        assert_eq!(server.handle(&request("GET", "/elsewhere")).status, 404);
+        assert_eq!(server.handle(&request("DELETE", "/api/search")).status, 405);
+    }
+
+    #[test]
+    fn archive_endpoints_plan_and_run() {
+        let mut store = store();
+        let mut server = Server::new(&mut store, 7070, None);
+        let planned = server.handle(&request(
+            "POST",
+            "/api/archive/plan?older_than=30d&keep_latest=0",
+        ));
+        let planned = json_body(&planned);
+        assert_eq!(planned["sessions"], 1);
+        let run_id = planned["id"].as_str().unwrap().to_string();
+
+        let runs = json_body(&server.handle(&request("GET", "/api/archive/runs")));
+        assert_eq!(runs[0]["id"], run_id.as_str());
+        assert_eq!(runs[0]["executed"], false);
+
+        let refused = server.handle(&request(
+            "POST",
+            &format!("/api/archive/runs/{run_id}/run?delete_source=true"),
+        ));
+        assert_eq!(refused.status, 400);
+        let dry = server.handle(&request("POST", &format!("/api/archive/runs/{run_id}/run")));
+        assert_eq!(dry.status, 200);
+        assert!(
+            json_body(&dry)["message"]
+                .as_str()
+                .unwrap()
+                .contains("dry-run")
+        );
+        assert_eq!(
+            server
+                .handle(&request("GET", &format!("/api/archive/runs/{run_id}/run")))
+                .status,
+            405
+        );
+    }
+
+    #[test]
+    fn graphql_accepts_get_and_post() {
+        let mut store = store();
+        let mut server = Server::new(&mut store, 7070, None);
+        let get = server.handle(&request(
+            "GET",
+            "/graphql?query=%7Bsessions%7Bid+messageCount%7D%7D",
+        ));
+        assert_eq!(
+            json_body(&get)["data"]["sessions"][0],
+            json!({ "id": "s1", "messageCount": 1 })
+        );
+
+        let mut post = request("POST", "/graphql");
+        post.body = br#"{"query":"{ session(id: \"s1\") { firstMessage { role } } }"}"#.to_vec();
+        let post = server.handle(&post);
+        assert_eq!(
+            json_body(&post)["data"]["session"]["firstMessage"]["role"],
+            "user"
+        );
+        let get = server.handle(&request(
+            "GET",
+            "/graphql?query=query+One(%24id%3AID!)%7Bsession(id%3A%24id)%7Btitle%7D%7D&variables=%7B%22id%22%3A%22s1%22%7D",
+        ));
+        assert_eq!(get.status, 200);
+        assert!(json_body(&get)["data"]["session"]["title"].is_string());
+        assert_eq!(server.handle(&request("POST", "/graphql")).status, 400);
+        let mut invalid = request("POST", "/graphql");
+        invalid.body = br#"{"query":"{ sessions { nope } }"}"#.to_vec();
+        assert_eq!(server.handle(&invalid).status, 400);
+        let schema = server.handle(&request("GET", "/graphql/schema"));
+        assert_eq!(schema.status, 200);
+        assert!(String::from_utf8_lossy(&schema.body).contains("type Query"));
+    }
+
+    #[test]
+    fn foreign_hosts_and_origins_are_refused() {
+        let mut store = store();
+        let mut server = Server::new(&mut store, 7070, None);
+        let mut rebinding = request("GET", "/api/search?q=login");
+        rebinding
+            .headers
+            .insert("host".to_string(), "evil.example:7070".to_string());
+        assert_eq!(server.handle(&rebinding).status, 403);
+
+        let mut cross = request("POST", "/api/archive/plan");
+        cross
+            .headers
+            .insert("origin".to_string(), "http://evil.example".to_string());
+        assert_eq!(server.handle(&cross).status, 403);
+    }
+
+    #[test]
+    fn query_strings_are_decoded() {
+        let params = parse_query("q=a+b%20c&agent=&x");
+        assert_eq!(params["q"], "a b c");
+        assert_eq!(params["agent"], "");
+        assert_eq!(params["x"], "");
+        assert_eq!(percent_decode("%E2%9C%93%zz"), "✓%zz");
+    }
+}
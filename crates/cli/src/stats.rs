@@ -0,0 +1,511 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::path::Path;
+
+use core_model::Message;
+use serde::Serialize;
+use store_sqlite::{SessionAggregate, StorageUsage};
+
+const UNKNOWN_TOOL: &str = "unknown";
+const FAILURE_MARKERS: &[&str] = &[
+    "error",
+    "failed",
+    "failure",
+    "exception",
+    "traceback",
+    "panicked",
+    "command not found",
+    "permission denied",
+    "no such file",
+];
+// FTS5 keeps its index in shadow tables named after the virtual table.
+const FTS_PREFIX: &str = "fts_";
+const EMBEDDING_TABLES: &[&str] = &["message_embeddings", "query_embeddings", "embedding_queue"];
+pub const DEFAULT_BURST_GAP_MINUTES: u64 = 30;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionStats {
+    pub message_count: usize,
+    pub duration_secs: i64,
+    pub by_role: Vec<(String, usize)>,
+    pub tools: Vec<(String, usize)>,
+    pub estimated_tokens: usize,
+}
+
+impl SessionStats {
+    pub fn new(aggregate: SessionAggregate, messages: &[Message]) -> Self {
+        let mut tools: BTreeMap<String, usize> = BTreeMap::new();
+        for m in messages {
+            for name in tool_use_names(&m.content) {
+                *tools.entry(name.to_string()).or_default() += 1;
+            }
+        }
+        let mut tools: Vec<(String, usize)> = tools.into_iter().collect();
+        tools.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Self {
+            message_count: aggregate.message_count,
+            duration_secs: (aggregate.last_ts - aggregate.first_ts)
+                .num_seconds()
+                .max(0),
+            by_role: aggregate.by_role,
+            tools,
+            estimated_tokens: messages.iter().map(|m| estimate_tokens(&m.content)).sum(),
+        }
+    }
+
+    pub fn duration_label(&self) -> String {
+        humantime::format_duration(std::time::Duration::from_secs(self.duration_secs as u64))
+            .to_string()
+    }
+
+    pub fn lines(&self) -> Vec<(String, String)> {
+        let join = |items: &[(String, usize)]| {
+            if items.is_empty() {
+                return "none".to_string();
+            }
+            items
+                .iter()
+                .map(|(name, count)| format!("{name}={count}"))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        vec![
+            ("duration".to_string(), self.duration_label()),
+            ("messages".to_string(), self.message_count.to_string()),
+            ("roles".to_string(), join(&self.by_role)),
+            ("tools".to_string(), join(&self.tools)),
+            (
+                "estimated_tokens".to_string(),
+                self.estimated_tokens.to_string(),
+            ),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ToolUsage {
+    pub agent: String,
+    pub tool: String,
+    pub calls: usize,
+    pub results: usize,
+    pub failures: usize,
+    pub output_bytes: usize,
+}
+
+impl ToolUsage {
+    pub fn failure_rate(&self) -> f64 {
+        if self.results == 0 {
+            return 0.0;
+        }
+        self.failures as f64 / self.results as f64
+    }
+
+    pub fn avg_output_bytes(&self) -> usize {
+        self.output_bytes.checked_div(self.results).unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ToolAnalytics {
+    usage: BTreeMap<(String, String), ToolUsage>,
+}
+
+impl ToolAnalytics {
+    // Tool results rarely carry the tool name, so they are paired with the
+    // session's earlier tool_use markers in call order.
+    pub fn add_session(&mut self, agent: &str, messages: &[Message]) {
+        let mut pending: VecDeque<String> = VecDeque::new();
+        for m in messages {
+            let mut result: Option<(String, String)> = None;
+            for line in m.content.lines() {
+                let trimmed = line.trim();
+                if let Some(rest) = trimmed.strip_prefix("tool_use:") {
+                    self.finish_result(agent, result.take());
+                    let tool = tool_name(rest).unwrap_or(UNKNOWN_TOOL).to_string();
+                    self.entry(agent, &tool).calls += 1;
+                    pending.push_back(tool);
+                } else if let Some(rest) = trimmed.strip_prefix("tool_result:") {
+                    self.finish_result(agent, result.take());
+                    let tool = pending
+                        .pop_front()
+                        .unwrap_or_else(|| UNKNOWN_TOOL.to_string());
+                    result = Some((tool, rest.trim_start().to_string()));
+                } else if let Some((_, output)) = result.as_mut() {
+                    output.push('\n');
+                    output.push_str(line);
+                }
+            }
+            self.finish_result(agent, result);
+        }
+    }
+
+    pub fn rows(&self, limit_per_agent: usize) -> Vec<&ToolUsage> {
+        let mut rows: Vec<&ToolUsage> = self.usage.values().collect();
+        rows.sort_by(|a, b| {
+            a.agent
+                .cmp(&b.agent)
+                .then_with(|| b.calls.cmp(&a.calls))
+                .then_with(|| a.tool.cmp(&b.tool))
+        });
+        let mut per_agent: BTreeMap<&str, usize> = BTreeMap::new();
+        rows.retain(|row| {
+            let seen = per_agent.entry(row.agent.as_str()).or_default();
+            *seen += 1;
+            *seen <= limit_per_agent
+        });
+        rows
+    }
+
+    fn entry(&mut self, agent: &str, tool: &str) -> &mut ToolUsage {
+        self.usage
+            .entry((agent.to_string(), tool.to_string()))
+            .or_insert_with(|| ToolUsage {
+                agent: agent.to_string(),
+                tool: tool.to_string(),
+                ..ToolUsage::default()
+            })
+    }
+
+    fn finish_result(&mut self, agent: &str, result: Option<(String, String)>) {
+        let Some((tool, output)) = result else {
+            return;
+        };
+        let failed = looks_like_failure(&output);
+        let usage = self.entry(agent, &tool);
+        usage.results += 1;
+        usage.output_bytes += output.trim().len();
+        if failed {
+            usage.failures += 1;
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StorageReport {
+    pub schema_version: u32,
+    pub db_bytes: u64,
+    pub free_bytes: u64,
+    pub fts_bytes: u64,
+    pub embeddings_bytes: u64,
+    pub archive_dir: String,
+    pub archive_bytes: u64,
+    pub tables: Vec<TableBytes>,
+    pub agents: Vec<AgentBytes>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TableBytes {
+    pub table: String,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AgentBytes {
+    pub agent: String,
+    pub messages: usize,
+    pub content_bytes: u64,
+}
+
+impl StorageReport {
+    pub fn new(usage: StorageUsage, archive_dir: &Path) -> Self {
+        let sum = |keep: &dyn Fn(&str) -> bool| -> u64 {
+            usage
+                .tables
+                .iter()
+                .filter(|(table, _)| keep(table))
+                .map(|(_, bytes)| bytes)
+                .sum()
+        };
+        Self {
+            schema_version: core_model::JSON_SCHEMA_VERSION,
+            db_bytes: usage.db_bytes,
+            free_bytes: usage.free_bytes,
+            fts_bytes: sum(&|t| t.starts_with(FTS_PREFIX)),
+            embeddings_bytes: sum(&|t| EMBEDDING_TABLES.contains(&t)),
+            archive_dir: archive_dir.display().to_string(),
+            archive_bytes: dir_size(archive_dir),
+            tables: usage
+                .tables
+                .into_iter()
+                .map(|(table, bytes)| TableBytes { table, bytes })
+                .collect(),
+            agents: usage
+                .agents
+                .into_iter()
+                .map(|a| AgentBytes {
+                    agent: a.agent,
+                    messages: a.messages,
+                    content_bytes: a.content_bytes,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("db_bytes={}", self.db_bytes),
+            format!("free_bytes={}", self.free_bytes),
+            format!("fts_bytes={}", self.fts_bytes),
+            format!("embeddings_bytes={}", self.embeddings_bytes),
+            format!(
+                "archive_dir={} archive_bytes={}",
+                self.archive_dir, self.archive_bytes
+            ),
+        ];
+        lines.extend(
+            self.tables
+                .iter()
+                .map(|t| format!("table={} bytes={}", t.table, t.bytes)),
+        );
+        lines.extend(self.agents.iter().map(|a| {
+            format!(
+                "agent={} messages={} content_bytes={}",
+                a.agent, a.messages, a.content_bytes
+            )
+        }));
+        lines
+    }
+}
+
+// Marks the message that starts a new work burst, i.e. the first one after
+// an idle gap longer than the configured threshold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BurstBreak {
+    pub index: usize,
+    pub burst: usize,
+    pub total: usize,
+    pub idle_secs: i64,
+}
+
+impl BurstBreak {
+    pub fn label(&self) -> String {
+        let idle = std::time::Duration::from_secs((self.idle_secs / 60 * 60) as u64);
+        crate::i18n::text_with(
+            "burst-label",
+            &[
+                ("burst", self.burst.into()),
+                ("total", self.total.into()),
+                ("idle", humantime::format_duration(idle).to_string().into()),
+            ],
+        )
+    }
+}
+
+// A gap of zero minutes turns burst detection off.
+pub fn burst_breaks(messages: &[Message], gap_minutes: u64) -> Vec<BurstBreak> {
+    if gap_minutes == 0 {
+        return Vec::new();
+    }
+    let gap = gap_minutes as i64 * 60;
+    let mut breaks: Vec<BurstBreak> = messages
+        .windows(2)
+        .enumerate()
+        .filter_map(|(i, pair)| {
+            let idle_secs = (pair[1].ts - pair[0].ts).num_seconds();
+            (idle_secs > gap).then_some(BurstBreak {
+                index: i + 1,
+                burst: 0,
+                total: 0,
+                idle_secs,
+            })
+        })
+        .collect();
+    let total = breaks.len() + 1;
+    for (n, b) in breaks.iter_mut().enumerate() {
+        b.burst = n + 2;
+        b.total = total;
+    }
+    breaks
+}
+
+// Missing or unreadable entries count as empty.
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(kind) if kind.is_dir() => dir_size(&entry.path()),
+            Ok(_) => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+pub fn tool_use_names(content: &str) -> impl Iterator<Item = &str> {
+    content
+        .lines()
+        .filter_map(|line| tool_name(line.trim().strip_prefix("tool_use:")?))
+}
+
+fn tool_name(marker_rest: &str) -> Option<&str> {
+    let name = marker_rest.split_whitespace().next()?;
+    (!name.starts_with('{')).then_some(name)
+}
+
+fn looks_like_failure(output: &str) -> bool {
+    let lower = output.to_ascii_lowercase();
+    FAILURE_MARKERS.iter().any(|marker| lower.contains(marker))
+}
+
+pub fn estimate_tokens(content: &str) -> usize {
+    content.chars().count().div_ceil(4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn message(role: &str, content: &str) -> Message {
+        Message {
+            id: content.to_string(),
+            session_id: "s1".to_string(),
+            role: role.to_string(),
+            content: content.to_string(),
+            ts: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn tool_use_names_skip_unnamed_inputs() {
+        let content = "text\ntool_use: Read {\"path\":\"a\"}\n  tool_use: bash\ntool_use: {\"x\":1}\ntool_result: ok";
+        assert_eq!(
+            tool_use_names(content).collect::<Vec<_>>(),
+            vec!["Read", "bash"]
+        );
+    }
+
+    #[test]
+    fn session_stats_combine_aggregate_and_content() {
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 10, 0, 0).unwrap();
+        let aggregate = SessionAggregate {
+            message_count: 3,
+            first_ts: start,
+            last_ts: start + chrono::Duration::seconds(125),
+            by_role: vec![("assistant".to_string(), 2), ("user".to_string(), 1)],
+        };
+        let messages = vec![
+            message("user", "abcd"),
+            message("assistant", "tool_use: Read {}\ntool_use: Edit {}"),
+            message("assistant", "tool_use: Read {}"),
+        ];
+        let stats = SessionStats::new(aggregate, &messages);
+        assert_eq!(
+            stats.tools,
+            vec![("Read".to_string(), 2), ("Edit".to_string(), 1)]
+        );
+        assert_eq!(stats.duration_label(), "2m 5s");
+        assert_eq!(stats.estimated_tokens, 1 + 9 + 5);
+        assert_eq!(stats.lines()[2].1, "assistant=2 user=1");
+    }
+
+    #[test]
+    fn burst_breaks_split_on_long_idle_gaps() {
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 10, 0, 0).unwrap();
+        let at = |minutes: i64, content: &str| Message {
+            ts: start + chrono::Duration::minutes(minutes),
+            ..message("user", content)
+        };
+        let messages = vec![
+            at(0, "a"),
+            at(20, "b"),
+            at(95, "c"),
+            at(96, "d"),
+            at(60 * 26, "e"),
+        ];
+        let breaks = burst_breaks(&messages, 30);
+        assert_eq!(
+            breaks.iter().map(|b| b.index).collect::<Vec<_>>(),
+            vec![2, 4]
+        );
+        assert_eq!(breaks[0].label(), "burst 2 of 3 · 1h 15m idle");
+        assert_eq!(breaks[1].label(), "burst 3 of 3 · 1day 24m idle");
+        assert!(burst_breaks(&messages, 0).is_empty());
+        assert!(burst_breaks(&messages, 60 * 24).len() == 1);
+    }
+
+    #[test]
+    fn tool_analytics_pair_results_with_calls() {
+        let mut analytics = ToolAnalytics::default();
+        analytics.add_session(
+            "pi",
+            &[
+                message(
+                    "assistant",
+                    "tool_use: bash {\"command\":\"ls\"}\ntool_use: Read {}",
+                ),
+                message("tool", "tool_result: Error: no such dir"),
+                message("tool", "tool_result: fn main() {}\nmore"),
+                message("assistant", "tool_use: bash {}"),
+                message("tool", "tool_result: ok"),
+            ],
+        );
+        analytics.add_session("codex", &[message("tool", "tool_result: orphan")]);
+
+        let rows = analytics.rows(10);
+        let summary: Vec<_> = rows
+            .iter()
+            .map(|r| {
+                (
+                    r.agent.as_str(),
+                    r.tool.as_str(),
+                    r.calls,
+                    r.results,
+                    r.failures,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("codex", "unknown", 0, 1, 0),
+                ("pi", "bash", 2, 2, 1),
+                ("pi", "Read", 1, 1, 0),
+            ]
+        );
+        let bash = rows[1];
+        assert_eq!(bash.failure_rate(), 0.5);
+        assert_eq!(
+            bash.avg_output_bytes(),
+            ("Error: no such dir".len() + 2) / 2
+        );
+        assert_eq!(rows[2].output_bytes, "fn main() {}\nmore".len());
+        assert_eq!(analytics.rows(1).len(), 2);
+    }
+
+    #[test]
+    fn storage_report_groups_fts_and_embedding_tables() {
+        let dir = std::env::temp_dir().join(format!("remi_storage_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("run-1")).unwrap();
+        std::fs::write(dir.join("run-1/sessions.json"), "x".repeat(100)).unwrap();
+        let usage = StorageUsage {
+            db_bytes: 8192,
+            free_bytes: 0,
+            tables: vec![
+                ("messages".to_string(), 4096),
+                ("fts_messages".to_string(), 2048),
+                ("fts_documents".to_string(), 512),
+                ("message_embeddings".to_string(), 1024),
+                ("query_embeddings".to_string(), 512),
+            ],
+            agents: vec![store_sqlite::AgentStorage {
+                agent: "pi".to_string(),
+                messages: 3,
+                content_bytes: 42,
+            }],
+        };
+        let report = StorageReport::new(usage, &dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(report.fts_bytes, 2560);
+        assert_eq!(report.embeddings_bytes, 1536);
+        assert_eq!(report.archive_bytes, 100);
+        let lines = report.lines();
+        assert_eq!(lines[5], "table=messages bytes=4096");
+        assert_eq!(
+            lines.last().unwrap(),
+            "agent=pi messages=3 content_bytes=42"
+        );
+        assert_eq!(dir_size(Path::new("/nonexistent/remi")), 0);
+    }
+}
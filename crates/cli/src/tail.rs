@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+use core_model::{Message, Session};
+use serde_json::json;
+use store_sqlite::SqliteStore;
+
+use crate::theme;
+
+const BATCH: i64 = 500;
+const PREVIEW_CHARS: usize = 160;
+
+pub struct Tail {
+    cursor: i64,
+    agent: Option<String>,
+    session_id: Option<String>,
+    sessions: HashMap<String, Option<Session>>,
+}
+
+pub struct TailLine {
+    pub message: Message,
+    pub session: Option<Session>,
+}
+
+impl Tail {
+    // Starts at the newest stored message, so only later inserts are polled.
+    pub fn new(
+        store: &SqliteStore,
+        agent: Option<String>,
+        session_id: Option<String>,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            cursor: store.message_cursor()?,
+            agent,
+            session_id,
+            sessions: HashMap::new(),
+        })
+    }
+
+    pub fn backlog(&mut self, store: &SqliteStore, count: usize) -> anyhow::Result<Vec<TailLine>> {
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let rows = store.latest_messages(
+            self.agent.as_deref(),
+            self.session_id.as_deref(),
+            count as i64,
+        )?;
+        self.lines(store, rows)
+    }
+
+    pub fn poll(&mut self, store: &SqliteStore) -> anyhow::Result<Vec<TailLine>> {
+        let head = store.message_cursor()?;
+        let mut rows = Vec::new();
+        loop {
+            let batch = store.messages_after(
+                self.cursor,
+                self.agent.as_deref(),
+                self.session_id.as_deref(),
+                BATCH,
+            )?;
+            let done = (batch.len() as i64) < BATCH;
+            if let Some((cursor, _)) = batch.last() {
+                self.cursor = *cursor;
+            }
+            rows.extend(batch);
+            if done {
+                break;
+            }
+        }
+        // Skips inserts the filters rejected; anything newer than `head`
+        // is still ahead of the cursor for the next poll.
+        self.cursor = self.cursor.max(head);
+        self.lines(store, rows)
+    }
+
+    fn lines(
+        &mut self,
+        store: &SqliteStore,
+        rows: Vec<(i64, Message)>,
+    ) -> anyhow::Result<Vec<TailLine>> {
+        let mut out = Vec::with_capacity(rows.len());
+        for (_, message) in rows {
+            if !self.sessions.contains_key(&message.session_id) {
+                let session = store.get_session(&message.session_id)?;
+                self.sessions.insert(message.session_id.clone(), session);
+            }
+            let session = self.sessions[&message.session_id].clone();
+            out.push(TailLine { message, session });
+        }
+        Ok(out)
+    }
+}
+
+impl TailLine {
+    pub fn render(&self, use_color: bool) -> String {
+        let agent = self.session.as_ref().map_or("?", |s| s.agent.as_str());
+        let (_, text) = core_model::split_reasoning(&self.message.content);
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let preview = if text.chars().count() > PREVIEW_CHARS {
+            format!("{}…", text.chars().take(PREVIEW_CHARS).collect::<String>())
+        } else {
+            text
+        };
+        format!(
+            "{} {} {} [{}] {preview}",
+            self.message.ts.format("%Y-%m-%d %H:%M:%S"),
+            theme::paint_agent(agent, agent, use_color),
+            self.message.session_id,
+            self.message.role
+        )
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "schema_version": core_model::JSON_SCHEMA_VERSION,
+            "agent": self.session.as_ref().map(|s| s.agent.as_str()),
+            "session_id": self.message.session_id,
+            "session_title": self.session.as_ref().map(|s| &s.title),
+            "message_id": self.message.id,
+            "role": self.message.role,
+            "ts": self.message.ts.to_rfc3339(),
+            "content": self.message.content,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use core_model::{AgentKind, NormalizedBatch};
+
+    fn save(store: &mut SqliteStore, agent: AgentKind, session: &str, id: &str, content: &str) {
+        let at = Utc.with_ymd_and_hms(2025, 5, 1, 9, 30, 0).unwrap();
+        store
+            .save_batch(&NormalizedBatch {
+                sessions: vec![Session {
+                    id: session.to_string(),
+                    agent,
+                    source_ref: format!("/tmp/{session}.jsonl"),
+                    title: format!("{session} title"),
+                    created_at: at,
+                    updated_at: at,
+                }],
+                messages: vec![Message {
+                    id: id.to_string(),
+                    session_id: session.to_string(),
+                    role: "user".to_string(),
+                    content: content.to_string(),
+                    ts: at,
+                }],
+                ..Default::default()
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn tail_prints_backlog_then_new_messages() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        save(&mut store, AgentKind::Claude, "s1", "m1", "old one");
+        save(&mut store, AgentKind::Claude, "s1", "m2", "old two");
+
+        let mut tail = Tail::new(&store, Some("claude".to_string()), None).unwrap();
+        let backlog = tail.backlog(&store, 1).unwrap();
+        assert_eq!(backlog.len(), 1);
+        assert_eq!(backlog[0].message.id, "m2");
+        assert!(tail.poll(&store).unwrap().is_empty());
+
+        save(&mut store, AgentKind::Codex, "s2", "m3", "other agent");
+        save(
+            &mut store,
+            AgentKind::Claude,
+            "s1",
+            "m4",
+            "reasoning: hmm\nfix   the\nbuild",
+        );
+        let new = tail.poll(&store).unwrap();
+        assert_eq!(new.len(), 1);
+        assert_eq!(
+            new[0].render(false),
+            "2025-05-01 09:30:00 claude s1 [user] fix the build"
+        );
+        assert_eq!(new[0].to_json()["session_title"], "s1 title");
+        assert!(tail.poll(&store).unwrap().is_empty());
+    }
+}
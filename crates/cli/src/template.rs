@@ -0,0 +1,885 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, bail};
+use core_model::{Message, Session};
+use serde_json::{Value, json};
+
+use crate::{diff, render, stats::SessionStats};
+
+const MAX_OUTPUT_BYTES: usize = 64 * 1024 * 1024;
+
+// A small Jinja subset: `{{ expr | filter }}`, `{% if %}` / `{% elif %}` /
+// `{% else %}`, `{% for x in xs %}` with `loop.*`, `{# comments #}` and `-`
+// whitespace trimming. Templates ending in `.html` are autoescaped.
+pub struct Template {
+    nodes: Vec<Node>,
+    autoescape: bool,
+    pub extension: String,
+}
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Output(Expr),
+    If(Vec<(Expr, Vec<Node>)>, Vec<Node>),
+    For {
+        var: String,
+        iter: Expr,
+        body: Vec<Node>,
+        empty: Vec<Node>,
+    },
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Literal(Value),
+    Var(String),
+    Attr(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Filter(Box<Expr>, String, Vec<Expr>),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, String, Box<Expr>),
+}
+
+enum Token {
+    Text(String),
+    Output(String),
+    Tag(String),
+}
+
+impl Template {
+    pub fn parse(source: &str, extension: &str) -> anyhow::Result<Self> {
+        let tokens = lex(source)?;
+        let mut pos = 0;
+        let (nodes, end) = parse_nodes(&tokens, &mut pos, &[])?;
+        if let Some(tag) = end {
+            bail!("unexpected {{% {tag} %}}");
+        }
+        Ok(Self {
+            nodes,
+            autoescape: extension.eq_ignore_ascii_case("html"),
+            extension: extension.to_string(),
+        })
+    }
+
+    // `<config dir>/templates/<name>.<ext>`; the extension also names the
+    // rendered file.
+    pub fn load(config_dir: &Path, name: &str) -> anyhow::Result<Self> {
+        if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+            bail!("invalid template name {name:?}");
+        }
+        let dir = config_dir.join("templates");
+        let path = find_template(&dir, name)?
+            .with_context(|| format!("no template named {name} in {}", dir.display()))?;
+        let source = std::fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("txt")
+            .to_string();
+        Self::parse(&source, &extension).with_context(|| format!("in {}", path.display()))
+    }
+
+    pub fn render(&self, context: &Value) -> anyhow::Result<String> {
+        let mut out = String::new();
+        let mut scope = Scope {
+            root: context,
+            locals: Vec::new(),
+        };
+        self.render_nodes(&self.nodes, &mut scope, &mut out)?;
+        Ok(out)
+    }
+
+    fn render_nodes(
+        &self,
+        nodes: &[Node],
+        scope: &mut Scope,
+        out: &mut String,
+    ) -> anyhow::Result<()> {
+        for node in nodes {
+            if out.len() > MAX_OUTPUT_BYTES {
+                bail!("template output exceeds {MAX_OUTPUT_BYTES} bytes");
+            }
+            match node {
+                Node::Text(text) => out.push_str(text),
+                Node::Output(expr) => {
+                    let (value, safe) = eval_marked(expr, scope)?;
+                    let text = display(&value);
+                    if self.autoescape && !safe {
+                        out.push_str(&render::escape_html(&text));
+                    } else {
+                        out.push_str(&text);
+                    }
+                }
+                Node::If(branches, otherwise) => {
+                    let mut taken = false;
+                    for (cond, body) in branches {
+                        if truthy(&eval(cond, scope)?) {
+                            self.render_nodes(body, scope, out)?;
+                            taken = true;
+                            break;
+                        }
+                    }
+                    if !taken {
+                        self.render_nodes(otherwise, scope, out)?;
+                    }
+                }
+                Node::For {
+                    var,
+                    iter,
+                    body,
+                    empty,
+                } => {
+                    let items = match eval(iter, scope)? {
+                        Value::Array(items) => items,
+                        Value::Null => Vec::new(),
+                        other => bail!("cannot loop over {}", type_name(&other)),
+                    };
+                    if items.is_empty() {
+                        self.render_nodes(empty, scope, out)?;
+                    }
+                    let len = items.len();
+                    for (i, item) in items.into_iter().enumerate() {
+                        scope.locals.push((var.clone(), item));
+                        scope.locals.push((
+                            "loop".to_string(),
+                            json!({
+                                "index": i + 1,
+                                "index0": i,
+                                "first": i == 0,
+                                "last": i + 1 == len,
+                                "length": len,
+                            }),
+                        ));
+                        let result = self.render_nodes(body, scope, out);
+                        scope.locals.truncate(scope.locals.len() - 2);
+                        result?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// The context every template receives for one session.
+pub fn session_context(
+    session: &Session,
+    messages: &[Message],
+    stats: Option<&SessionStats>,
+) -> Value {
+    json!({
+        "session": {
+            "id": session.id,
+            "agent": session.agent.as_str(),
+            "title": session.title,
+            "source_ref": session.source_ref,
+            "created_at": session.created_at.to_rfc3339(),
+            "updated_at": session.updated_at.to_rfc3339(),
+        },
+        "messages": messages.iter().map(|m| {
+            let (reasoning, _) = core_model::split_reasoning(&m.content);
+            json!({
+                "id": m.id,
+                "role": m.role,
+                "content": m.content,
+                "text": diff::visible_text(&m.content),
+                "reasoning": reasoning,
+                "tools": crate::stats::tool_use_names(&m.content).collect::<Vec<_>>(),
+                "ts": m.ts.to_rfc3339(),
+            })
+        }).collect::<Vec<_>>(),
+        "stats": stats.map(|s| json!({
+            "message_count": s.message_count,
+            "duration": s.duration_label(),
+            "duration_secs": s.duration_secs,
+            "tools": s.tools.iter().map(|(name, calls)| json!({ "name": name, "calls": calls })).collect::<Vec<_>>(),
+        })),
+        "generated_at": chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+fn find_template(dir: &Path, name: &str) -> anyhow::Result<Option<PathBuf>> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Ok(None);
+    };
+    let mut matches: Vec<PathBuf> = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_file() && path.file_stem().and_then(|s| s.to_str()) == Some(name) {
+            matches.push(path);
+        }
+    }
+    matches.sort();
+    if matches.len() > 1 {
+        bail!(
+            "template name {name} is ambiguous: {}",
+            matches
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    Ok(matches.pop())
+}
+
+fn lex(source: &str) -> anyhow::Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    let mut trim_next = false;
+    while !rest.is_empty() {
+        let next = ["{{", "{%", "{#"]
+            .iter()
+            .filter_map(|open| rest.find(open))
+            .min();
+        let (text, after) = match next {
+            Some(i) => rest.split_at(i),
+            None => (rest, ""),
+        };
+        let mut text = if trim_next { text.trim_start() } else { text };
+        trim_next = false;
+        if after.is_empty() {
+            if !text.is_empty() {
+                tokens.push(Token::Text(text.to_string()));
+            }
+            break;
+        }
+        let close = match &after[..2] {
+            "{{" => "}}",
+            "{%" => "%}",
+            _ => "#}",
+        };
+        let end = after
+            .find(close)
+            .with_context(|| format!("unclosed {}", &after[..2]))?;
+        let mut inner = &after[2..end];
+        if let Some(stripped) = inner.strip_prefix('-') {
+            text = text.trim_end();
+            inner = stripped;
+        }
+        if let Some(stripped) = inner.strip_suffix('-') {
+            trim_next = true;
+            inner = stripped;
+        }
+        if !text.is_empty() {
+            tokens.push(Token::Text(text.to_string()));
+        }
+        match close {
+            "}}" => tokens.push(Token::Output(inner.trim().to_string())),
+            "%}" => tokens.push(Token::Tag(inner.trim().to_string())),
+            _ => {}
+        }
+        rest = &after[end + 2..];
+    }
+    Ok(tokens)
+}
+
+// Parses until one of `stops` (returned) or the end of input.
+fn parse_nodes(
+    tokens: &[Token],
+    pos: &mut usize,
+    stops: &[&str],
+) -> anyhow::Result<(Vec<Node>, Option<String>)> {
+    let mut nodes = Vec::new();
+    while let Some(token) = tokens.get(*pos) {
+        *pos += 1;
+        match token {
+            Token::Text(text) => nodes.push(Node::Text(text.clone())),
+            Token::Output(expr) => nodes.push(Node::Output(parse_expr(expr)?)),
+            Token::Tag(tag) => {
+                let (keyword, rest) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+                if stops.contains(&keyword) {
+                    return Ok((nodes, Some(tag.clone())));
+                }
+                match keyword {
+                    "if" => nodes.push(parse_if(tokens, pos, rest)?),
+                    "for" => nodes.push(parse_for(tokens, pos, rest)?),
+                    other => bail!("unknown tag {{% {other} %}}"),
+                }
+            }
+        }
+    }
+    Ok((nodes, None))
+}
+
+fn parse_if(tokens: &[Token], pos: &mut usize, cond: &str) -> anyhow::Result<Node> {
+    let mut branches = Vec::new();
+    let mut cond = parse_expr(cond)?;
+    loop {
+        let (body, end) = parse_nodes(tokens, pos, &["elif", "else", "endif"])?;
+        branches.push((cond, body));
+        let end = end.context("missing {% endif %}")?;
+        match end.split_once(char::is_whitespace) {
+            Some(("elif", rest)) => cond = parse_expr(rest)?,
+            _ if end == "else" => {
+                let (otherwise, end) = parse_nodes(tokens, pos, &["endif"])?;
+                end.context("missing {% endif %}")?;
+                return Ok(Node::If(branches, otherwise));
+            }
+            _ if end == "endif" => return Ok(Node::If(branches, Vec::new())),
+            _ => bail!("unexpected {{% {end} %}}"),
+        }
+    }
+}
+
+fn parse_for(tokens: &[Token], pos: &mut usize, header: &str) -> anyhow::Result<Node> {
+    let (var, iter) = header
+        .split_once(" in ")
+        .with_context(|| format!("expected `for x in xs`, got `for {header}`"))?;
+    let var = var.trim();
+    if var.is_empty() || !var.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        bail!("invalid loop variable {var:?}");
+    }
+    let iter = parse_expr(iter)?;
+    let (body, end) = parse_nodes(tokens, pos, &["else", "endfor"])?;
+    let empty = match end.as_deref() {
+        Some("endfor") => Vec::new(),
+        Some("else") => {
+            let (empty, end) = parse_nodes(tokens, pos, &["endfor"])?;
+            end.context("missing {% endfor %}")?;
+            empty
+        }
+        _ => bail!("missing {{% endfor %}}"),
+    };
+    Ok(Node::For {
+        var: var.to_string(),
+        iter,
+        body,
+        empty,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Sym(&'static str),
+}
+
+fn tokenize(source: &str) -> anyhow::Result<Vec<Tok>> {
+    const SYMBOLS: &[&str] = &[
+        "==", "!=", ">=", "<=", ">", "<", "|", "(", ")", ",", ".", "[", "]",
+    ];
+    let mut out = Vec::new();
+    let chars: Vec<char> = source.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '"' || c == '\'' {
+            let mut s = String::new();
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                if chars[i] == '\\' && i + 1 < chars.len() {
+                    i += 1;
+                    s.push(match chars[i] {
+                        'n' => '\n',
+                        't' => '\t',
+                        other => other,
+                    });
+                } else {
+                    s.push(chars[i]);
+                }
+                i += 1;
+            }
+            if i == chars.len() {
+                bail!("unterminated string in `{source}`");
+            }
+            i += 1;
+            out.push(Tok::Str(s));
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            out.push(Tok::Num(text.parse().with_context(|| {
+                format!("invalid number {text} in `{source}`")
+            })?));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            out.push(Tok::Ident(chars[start..i].iter().collect()));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let sym = SYMBOLS
+                .iter()
+                .find(|s| rest.starts_with(**s))
+                .with_context(|| format!("unexpected {c:?} in `{source}`"))?;
+            i += sym.chars().count();
+            out.push(Tok::Sym(sym));
+        }
+    }
+    Ok(out)
+}
+
+struct ExprParser {
+    tokens: Vec<Tok>,
+    pos: usize,
+}
+
+fn parse_expr(source: &str) -> anyhow::Result<Expr> {
+    let mut parser = ExprParser {
+        tokens: tokenize(source)?,
+        pos: 0,
+    };
+    let expr = parser.or()?;
+    if parser.pos != parser.tokens.len() {
+        bail!("unexpected trailing input in `{source}`");
+    }
+    Ok(expr)
+}
+
+impl ExprParser {
+    fn peek(&self) -> Option<&Tok> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat_ident(&mut self, word: &str) -> bool {
+        if matches!(self.peek(), Some(Tok::Ident(w)) if w == word) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn eat_sym(&mut self, sym: &str) -> bool {
+        if matches!(self.peek(), Some(Tok::Sym(s)) if *s == sym) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect_sym(&mut self, sym: &str) -> anyhow::Result<()> {
+        if !self.eat_sym(sym) {
+            bail!("expected `{sym}`");
+        }
+        Ok(())
+    }
+
+    fn ident(&mut self) -> anyhow::Result<String> {
+        match self.tokens.get(self.pos) {
+            Some(Tok::Ident(name)) => {
+                self.pos += 1;
+                Ok(name.clone())
+            }
+            _ => bail!("expected a name"),
+        }
+    }
+
+    fn or(&mut self) -> anyhow::Result<Expr> {
+        let mut left = self.and()?;
+        while self.eat_ident("or") {
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> anyhow::Result<Expr> {
+        let mut left = self.not()?;
+        while self.eat_ident("and") {
+            left = Expr::And(Box::new(left), Box::new(self.not()?));
+        }
+        Ok(left)
+    }
+
+    fn not(&mut self) -> anyhow::Result<Expr> {
+        if self.eat_ident("not") {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.compare()
+    }
+
+    fn compare(&mut self) -> anyhow::Result<Expr> {
+        let left = self.filtered()?;
+        for op in ["==", "!=", ">=", "<=", ">", "<"] {
+            if self.eat_sym(op) {
+                let right = self.filtered()?;
+                return Ok(Expr::Compare(
+                    Box::new(left),
+                    op.to_string(),
+                    Box::new(right),
+                ));
+            }
+        }
+        Ok(left)
+    }
+
+    fn filtered(&mut self) -> anyhow::Result<Expr> {
+        let mut expr = self.postfix()?;
+        while self.eat_sym("|") {
+            let name = self.ident()?;
+            let mut args = Vec::new();
+            if self.eat_sym("(") && !self.eat_sym(")") {
+                loop {
+                    args.push(self.or()?);
+                    if self.eat_sym(")") {
+                        break;
+                    }
+                    self.expect_sym(",")?;
+                }
+            }
+            expr = Expr::Filter(Box::new(expr), name, args);
+        }
+        Ok(expr)
+    }
+
+    fn postfix(&mut self) -> anyhow::Result<Expr> {
+        let mut expr = self.primary()?;
+        loop {
+            if self.eat_sym(".") {
+                let key = match self.tokens.get(self.pos) {
+                    Some(Tok::Ident(name)) => name.clone(),
+                    Some(Tok::Num(n)) if n.fract() == 0.0 => (*n as u64).to_string(),
+                    _ => bail!("expected an attribute after `.`"),
+                };
+                self.pos += 1;
+                expr = Expr::Attr(Box::new(expr), key);
+            } else if self.eat_sym("[") {
+                let index = self.or()?;
+                self.expect_sym("]")?;
+                expr = Expr::Index(Box::new(expr), Box::new(index));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn primary(&mut self) -> anyhow::Result<Expr> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        match token {
+            Some(Tok::Str(s)) => Ok(Expr::Literal(Value::String(s))),
+            Some(Tok::Num(n)) => Ok(Expr::Literal(number(n))),
+            Some(Tok::Ident(name)) => Ok(match name.as_str() {
+                "true" => Expr::Literal(Value::Bool(true)),
+                "false" => Expr::Literal(Value::Bool(false)),
+                "none" | "null" => Expr::Literal(Value::Null),
+                _ => Expr::Var(name),
+            }),
+            Some(Tok::Sym("(")) => {
+                let expr = self.or()?;
+                self.expect_sym(")")?;
+                Ok(expr)
+            }
+            _ => bail!("expected a value"),
+        }
+    }
+}
+
+struct Scope<'a> {
+    root: &'a Value,
+    locals: Vec<(String, Value)>,
+}
+
+fn eval(expr: &Expr, scope: &Scope) -> anyhow::Result<Value> {
+    Ok(match expr {
+        Expr::Literal(value) => value.clone(),
+        Expr::Var(name) => scope
+            .locals
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.clone())
+            .or_else(|| scope.root.get(name).cloned())
+            .unwrap_or(Value::Null),
+        Expr::Attr(base, key) => lookup(&eval(base, scope)?, &Value::String(key.clone())),
+        Expr::Index(base, index) => lookup(&eval(base, scope)?, &eval(index, scope)?),
+        Expr::Not(inner) => Value::Bool(!truthy(&eval(inner, scope)?)),
+        Expr::And(a, b) => {
+            let a = eval(a, scope)?;
+            if truthy(&a) { eval(b, scope)? } else { a }
+        }
+        Expr::Or(a, b) => {
+            let a = eval(a, scope)?;
+            if truthy(&a) { a } else { eval(b, scope)? }
+        }
+        Expr::Compare(a, op, b) => {
+            let (a, b) = (eval(a, scope)?, eval(b, scope)?);
+            let ordering = match (&a, &b) {
+                (Value::Number(x), Value::Number(y)) => x.as_f64().partial_cmp(&y.as_f64()),
+                (Value::String(x), Value::String(y)) => Some(x.cmp(y)),
+                _ => None,
+            };
+            Value::Bool(match op.as_str() {
+                "==" => a == b,
+                "!=" => a != b,
+                ">" => ordering.is_some_and(|o| o.is_gt()),
+                "<" => ordering.is_some_and(|o| o.is_lt()),
+                ">=" => ordering.is_some_and(|o| o.is_ge()),
+                _ => ordering.is_some_and(|o| o.is_le()),
+            })
+        }
+        Expr::Filter(..) => eval_marked(expr, scope)?.0,
+    })
+}
+
+// Evaluates `expr` and reports whether the result is markup that autoescaping
+// must leave alone. As in Jinja, `safe` and `escape` mark their result, a
+// second `escape` does nothing to marked text, and the string filters keep the
+// mark; every other filter yields plain text again.
+fn eval_marked(expr: &Expr, scope: &Scope) -> anyhow::Result<(Value, bool)> {
+    let Expr::Filter(base, name, args) = expr else {
+        return Ok((eval(expr, scope)?, false));
+    };
+    let (value, safe) = eval_marked(base, scope)?;
+    let args = args
+        .iter()
+        .map(|a| eval(a, scope))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(match name.as_str() {
+        "safe" => (value, true),
+        "escape" | "e" if safe => (value, true),
+        "escape" | "e" => (apply_filter(name, value, &args)?, true),
+        "upper" | "lower" | "trim" | "truncate" | "indent" => {
+            (apply_filter(name, value, &args)?, safe)
+        }
+        "default" | "d" if truthy(&value) => (value, safe),
+        _ => (apply_filter(name, value, &args)?, false),
+    })
+}
+
+fn apply_filter(name: &str, value: Value, args: &[Value]) -> anyhow::Result<Value> {
+    let arg_usize = |i: usize, default: usize| {
+        args.get(i)
+            .and_then(Value::as_f64)
+            .map_or(default, |n| n.max(0.0) as usize)
+    };
+    let arg_str =
+        |i: usize, default: &str| args.get(i).map_or_else(|| default.to_string(), display);
+    Ok(match name {
+        "safe" => value,
+        "escape" | "e" => Value::String(render::escape_html(&display(&value))),
+        "upper" => Value::String(display(&value).to_uppercase()),
+        "lower" => Value::String(display(&value).to_lowercase()),
+        "trim" => Value::String(display(&value).trim().to_string()),
+        "length" | "count" => number(match &value {
+            Value::Array(items) => items.len(),
+            Value::Object(map) => map.len(),
+            Value::String(s) => s.chars().count(),
+            _ => 0,
+        } as f64),
+        "truncate" => {
+            let text = display(&value);
+            let max = arg_usize(0, 255);
+            if text.chars().count() > max {
+                Value::String(format!("{}…", text.chars().take(max).collect::<String>()))
+            } else {
+                Value::String(text)
+            }
+        }
+        "default" | "d" => {
+            if truthy(&value) {
+                value
+            } else {
+                args.first()
+                    .cloned()
+                    .unwrap_or(Value::String(String::new()))
+            }
+        }
+        "join" => match value {
+            Value::Array(items) => Value::String(
+                items
+                    .iter()
+                    .map(display)
+                    .collect::<Vec<_>>()
+                    .join(&arg_str(0, "")),
+            ),
+            other => other,
+        },
+        "first" => match value {
+            Value::Array(items) => items.into_iter().next().unwrap_or(Value::Null),
+            _ => Value::Null,
+        },
+        "last" => match value {
+            Value::Array(items) => items.into_iter().next_back().unwrap_or(Value::Null),
+            _ => Value::Null,
+        },
+        "selectattr" => {
+            let key = Value::String(arg_str(0, ""));
+            let expected = args.get(1);
+            match value {
+                Value::Array(items) => Value::Array(
+                    items
+                        .into_iter()
+                        .filter(|item| {
+                            let field = lookup(item, &key);
+                            match expected {
+                                Some(expected) => &field == expected,
+                                None => truthy(&field),
+                            }
+                        })
+                        .collect(),
+                ),
+                _ => Value::Array(Vec::new()),
+            }
+        }
+        "indent" => {
+            let pad = " ".repeat(arg_usize(0, 4));
+            Value::String(display(&value).replace('\n', &format!("\n{pad}")))
+        }
+        "tojson" | "json" => Value::String(serde_json::to_string_pretty(&value)?),
+        other => bail!("unknown filter `{other}`"),
+    })
+}
+
+fn lookup(base: &Value, key: &Value) -> Value {
+    match (base, key) {
+        (Value::Object(map), Value::String(k)) => map.get(k).cloned().unwrap_or(Value::Null),
+        (Value::Array(items), Value::String(k)) => k
+            .parse::<usize>()
+            .ok()
+            .and_then(|i| items.get(i).cloned())
+            .unwrap_or(Value::Null),
+        (Value::Array(items), Value::Number(n)) => n
+            .as_u64()
+            .and_then(|i| items.get(i as usize).cloned())
+            .unwrap_or(Value::Null),
+        _ => Value::Null,
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+fn display(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn number(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() < 9e15 {
+        Value::from(n as i64)
+    } else {
+        serde_json::Number::from_f64(n).map_or(Value::Null, Value::Number)
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "none",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "a list",
+        Value::Object(_) => "a map",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(source: &str, extension: &str, context: Value) -> String {
+        Template::parse(source, extension)
+            .unwrap()
+            .render(&context)
+            .unwrap()
+    }
+
+    #[test]
+    fn loops_conditions_and_filters() {
+        let context = json!({
+            "session": { "title": "Fix login", "agent": "codex" },
+            "messages": [
+                { "role": "user", "text": "please fix" },
+                { "role": "assistant", "text": "done   " },
+            ],
+        });
+        let source = "# {{ session.title | upper }}\n\
+            {%- for m in messages %}\n\
+            {{ loop.index }}. {{ m.role }}{% if m.role == \"user\" %} asks{% elif loop.last %} (last){% endif %}: {{ m.text | trim | truncate(3) }}\n\
+            {%- else %}\nnone\n{%- endfor %}\n\
+            {# a comment #}{{ messages | selectattr(\"role\", \"user\") | length }} prompt, agent {{ session[\"agent\"] }}{{ missing | default(\"!\") }}";
+        assert_eq!(
+            render(source, "md", context),
+            "# FIX LOGIN\n1. user asks: ple…\n2. assistant (last): don…\n1 prompt, agent codex!"
+        );
+        assert_eq!(
+            render(
+                "{% for m in messages %}x{% else %}empty{% endfor %}",
+                "md",
+                json!({})
+            ),
+            "empty"
+        );
+    }
+
+    #[test]
+    fn html_templates_are_autoescaped() {
+        let context = json!({ "title": "<b>&" });
+        assert_eq!(
+            render(
+                "{{ title }}|{{ title | safe }}|{{ title | e }}",
+                "html",
+                context.clone()
+            ),
+            "&lt;b&gt;&amp;|<b>&|&lt;b&gt;&amp;"
+        );
+        assert_eq!(render("{{ title }}", "md", context), "<b>&");
+    }
+
+    #[test]
+    fn chained_filters_escape_exactly_once() {
+        let context = json!({ "title": "<b>&", "empty": "" });
+        for (source, expected) in [
+            ("{{ title | e | safe }}", "&lt;b&gt;&amp;"),
+            ("{{ title | safe | e }}", "<b>&"),
+            ("{{ title | e | e }}", "&lt;b&gt;&amp;"),
+            ("{{ title | escape | upper }}", "&LT;B&GT;&AMP;"),
+            ("{{ title | safe | trim }}", "<b>&"),
+            ("{{ title | upper }}", "&lt;B&gt;&amp;"),
+            ("{{ title | safe | length }}", "4"),
+            ("{{ title | safe | join(\",\") }}", "&lt;b&gt;&amp;"),
+            ("{{ empty | safe | default(title) }}", "&lt;b&gt;&amp;"),
+        ] {
+            assert_eq!(
+                render(source, "html", context.clone()),
+                expected,
+                "{source}"
+            );
+        }
+        assert_eq!(
+            render("{{ title | e | upper }}", "md", context),
+            "&LT;B&GT;&AMP;"
+        );
+    }
+
+    #[test]
+    fn syntax_errors_are_reported() {
+        for bad in [
+            "{% if x %}open",
+            "{% for x of xs %}{% endfor %}",
+            "{{ x | }}",
+            "{% endif %}",
+            "{{ x",
+            "{% while x %}",
+        ] {
+            assert!(Template::parse(bad, "md").is_err(), "{bad}");
+        }
+        let unknown = Template::parse("{{ x | nope }}", "md").unwrap();
+        assert!(unknown.render(&json!({ "x": 1 })).is_err());
+    }
+}
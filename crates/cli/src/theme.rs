@@ -0,0 +1,101 @@
+use core_model::AgentKind;
+use owo_colors::{AnsiColors, OwoColorize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AgentTheme {
+    pub icon: &'static str,
+    pub hex: &'static str,
+    pub ansi: AnsiColors,
+}
+
+pub fn agent_theme(kind: AgentKind) -> AgentTheme {
+    match kind {
+        AgentKind::Pi => AgentTheme {
+            icon: "🥧",
+            hex: "#a855f7",
+            ansi: AnsiColors::Magenta,
+        },
+        AgentKind::Droid => AgentTheme {
+            icon: "🤖",
+            hex: "#eab308",
+            ansi: AnsiColors::Yellow,
+        },
+        AgentKind::OpenCode => AgentTheme {
+            icon: "🧩",
+            hex: "#22c55e",
+            ansi: AnsiColors::Green,
+        },
+        AgentKind::Claude => AgentTheme {
+            icon: "🧠",
+            hex: "#f97316",
+            ansi: AnsiColors::BrightRed,
+        },
+        AgentKind::Amp => AgentTheme {
+            icon: "⚡",
+            hex: "#06b6d4",
+            ansi: AnsiColors::Cyan,
+        },
+        AgentKind::Codex => AgentTheme {
+            icon: "📜",
+            hex: "#3b82f6",
+            ansi: AnsiColors::Blue,
+        },
+        AgentKind::Gemini => AgentTheme {
+            icon: "✨",
+            hex: "#4285f4",
+            ansi: AnsiColors::BrightBlue,
+        },
+        AgentKind::Aider => AgentTheme {
+            icon: "🛠",
+            hex: "#14b8a6",
+            ansi: AnsiColors::BrightGreen,
+        },
+        AgentKind::Meta => AgentTheme {
+            icon: "🗂",
+            hex: "#64748b",
+            ansi: AnsiColors::BrightBlack,
+        },
+    }
+}
+
+pub fn paint_agent(agent: &str, label: &str, use_color: bool) -> String {
+    let Some(kind) = agent.parse::<AgentKind>().ok().filter(|_| use_color) else {
+        return label.to_string();
+    };
+    let theme = agent_theme(kind);
+    format!("{} {}", theme.icon, label.color(theme.ansi))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_agent_has_a_distinct_theme() {
+        let kinds = [
+            AgentKind::Pi,
+            AgentKind::Droid,
+            AgentKind::OpenCode,
+            AgentKind::Claude,
+            AgentKind::Amp,
+            AgentKind::Codex,
+            AgentKind::Gemini,
+            AgentKind::Aider,
+            AgentKind::Meta,
+        ];
+        let themes: Vec<AgentTheme> = kinds.iter().map(|k| agent_theme(*k)).collect();
+        for (i, a) in themes.iter().enumerate() {
+            for b in &themes[i + 1..] {
+                assert_ne!(a.icon, b.icon);
+                assert_ne!(a.hex, b.hex);
+            }
+        }
+    }
+
+    #[test]
+    fn paint_agent_is_plain_without_color() {
+        assert_eq!(paint_agent("claude", "claude", false), "claude");
+        assert_eq!(paint_agent("unknown", "unknown", true), "unknown");
+        assert!(paint_agent("claude", "claude", true).starts_with("🧠 "));
+    }
+}
@@ -4,96 +4,33 @@ use std::{
 };
 
 use anyhow::Context;
-use chrono::{DateTime, Utc};
-use core_model::{Message, Session};
 use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
 use owo_colors::OwoColorize;
-use serde::Serialize;
-use store_sqlite::SqliteStore;
+use query::{SessionDisplay, apply_filters, parse_fuzzy_query};
 
-#[derive(Clone)]
-pub struct SessionDisplay {
-    pub session_id: String,
-    pub title: String,
-    pub agent: String,
-    pub updated_at: DateTime<Utc>,
-    pub message_count: usize,
-    pub snippet: String,
-    pub score: f32,
-    pub match_text: String,
-}
-
-#[derive(Default, Clone)]
-pub struct FilterSpec {
-    pub agent: Option<String>,
-    pub title: Option<String>,
-    pub id: Option<String>,
-    pub contains: Option<String>,
-}
-
-#[derive(Serialize)]
-pub struct JsonSession {
-    pub id: String,
-    pub title: String,
-    pub agent: String,
-    pub updated_at: DateTime<Utc>,
-    pub message_count: usize,
-    pub snippet: String,
-    pub score: f32,
-}
-
-#[derive(Serialize)]
-pub struct JsonSearchOutput {
-    pub query: String,
-    pub selected_index: usize,
-    pub selected_session_id: String,
-    pub sessions: Vec<JsonSession>,
-}
+use crate::i18n;
 
-pub fn build_session_displays(
-    store: &SqliteStore,
-    hits: &[search::SessionHit],
-) -> anyhow::Result<Vec<SessionDisplay>> {
-    let mut out = Vec::with_capacity(hits.len());
-    for hit in hits {
-        let Some(session) = store.get_session(&hit.session_id)? else {
-            continue;
-        };
-        let messages = store.get_session_messages(&hit.session_id)?;
-        let message_count = messages.len();
-        let title = session_title(&session, &messages);
-        let snippet = truncate_text(&hit.top_content, 140);
-        let match_text = format!(
-            "{} {} {} {}",
-            title,
-            session.id,
-            snippet,
-            session.agent.as_str()
-        );
-        out.push(SessionDisplay {
-            session_id: session.id.clone(),
-            title,
-            agent: session.agent.as_str().to_string(),
-            updated_at: session.updated_at,
-            message_count,
-            snippet,
-            score: hit.score,
-            match_text,
-        });
+pub fn print_preview(item: &SessionDisplay, terms: &[String]) {
+    let use_color = color_enabled();
+    let header = i18n::text_with(
+        "search-preview",
+        &[
+            ("title", item.title.as_str().into()),
+            ("id", item.session_id.as_str().into()),
+        ],
+    );
+    if use_color {
+        println!("{}", header.bold());
+    } else {
+        println!("{header}");
     }
-    Ok(out)
-}
-
-pub fn session_title(session: &Session, messages: &[Message]) -> String {
-    let title = session.title.trim();
-    if !title.is_empty() {
-        return title.to_string();
+    if item.preview.is_empty() {
+        println!("  {}", i18n::text("search-no-preview"));
+        return;
+    }
+    for line in &item.preview {
+        println!("  > {}", highlight_terms(line, terms, use_color));
     }
-    messages
-        .iter()
-        .find(|m| m.role == "user")
-        .map(|m| truncate_text(&m.content, 60))
-        .unwrap_or_else(|| "Untitled session".to_string())
 }
 
 pub fn prompt_line(prompt: &str) -> anyhow::Result<String> {
@@ -126,7 +63,7 @@ pub fn print_session_list(items: &[SessionDisplay], terms: &[String]) {
         let agent = highlight_terms(&item.agent, terms, use_color);
         let snippet = highlight_terms(&item.snippet, terms, use_color);
         let date = item.updated_at.to_rfc3339();
-        let count = format!("{} msgs", item.message_count);
+        let count = i18n::count("search-message-count", item.message_count);
         let separator = if use_color {
             " | ".dimmed().to_string()
         } else {
@@ -137,11 +74,7 @@ pub fn print_session_list(items: &[SessionDisplay], terms: &[String]) {
         } else {
             title
         };
-        let agent = if use_color {
-            agent.cyan().to_string()
-        } else {
-            agent
-        };
+        let agent = crate::theme::paint_agent(&item.agent, &agent, use_color);
         let count = if use_color {
             count.magenta().to_string()
         } else {
@@ -160,9 +93,63 @@ pub fn print_session_list(items: &[SessionDisplay], terms: &[String]) {
         println!(
             "[{i}] {title}{separator}{agent}{separator}{count}{separator}{date}{separator}{snippet}"
         );
+        for line in explain_lines(item) {
+            if use_color {
+                println!("    {}", line.dimmed());
+            } else {
+                println!("    {line}");
+            }
+        }
     }
 }
 
+// Channels a session never reached are left out; recency and substring ranks
+// have no raw score of their own.
+pub fn explain_lines(item: &SessionDisplay) -> Vec<String> {
+    let Some(explain) = &item.explain else {
+        return Vec::new();
+    };
+    let ranks = &explain.ranks;
+    let channels = &item.channels;
+    let parts: Vec<String> = [
+        ("bm25", ranks.bm25, channels.bm25),
+        ("recency", ranks.recency, channels.recency),
+        ("semantic", ranks.semantic, channels.semantic),
+        ("substring", ranks.substring, channels.substring),
+    ]
+    .into_iter()
+    .filter_map(|(name, rank, contribution)| {
+        let rank = rank?;
+        Some(match rank.raw {
+            Some(raw) => format!("{name} #{} (raw {raw:.3}) {contribution:.4}", rank.rank),
+            None => format!("{name} #{} {contribution:.4}", rank.rank),
+        })
+    })
+    .collect();
+    let list = |terms: &[String]| {
+        if terms.is_empty() {
+            "-".to_string()
+        } else {
+            terms.join(", ")
+        }
+    };
+    vec![
+        format!(
+            "score {:.4} = {} | {}",
+            item.score,
+            parts.join(" + "),
+            i18n::count("search-explain-hits", explain.hits)
+        ),
+        i18n::text_with(
+            "search-explain-terms",
+            &[
+                ("matched", list(&explain.matched_terms).into()),
+                ("missing", list(&explain.missing_terms).into()),
+            ],
+        ),
+    ]
+}
+
 pub fn fuzzy_filter_sessions(
     items: &[SessionDisplay],
     query: &str,
@@ -188,63 +175,6 @@ pub fn fuzzy_filter_sessions(
     (scored.into_iter().map(|(_, item)| item).collect(), terms)
 }
 
-pub fn parse_fuzzy_query(input: &str) -> (FilterSpec, Vec<String>) {
-    let mut filters = FilterSpec::default();
-    let mut terms = Vec::new();
-    for raw in input.split_whitespace() {
-        let Some((key, value)) = raw.split_once(':') else {
-            terms.push(raw.to_string());
-            continue;
-        };
-        let value = value.trim();
-        if value.is_empty() {
-            continue;
-        }
-        match key {
-            "agent" => filters.agent = Some(value.to_string()),
-            "title" => filters.title = Some(value.to_string()),
-            "id" => filters.id = Some(value.to_string()),
-            "contains" => filters.contains = Some(value.to_string()),
-            _ => terms.push(raw.to_string()),
-        }
-    }
-    (filters, terms)
-}
-
-pub fn apply_filters(items: &[SessionDisplay], filters: &FilterSpec) -> Vec<SessionDisplay> {
-    let agent = filters.agent.as_ref().map(|s| s.to_lowercase());
-    let title = filters.title.as_ref().map(|s| s.to_lowercase());
-    let id = filters.id.as_ref().map(|s| s.to_lowercase());
-    let contains = filters.contains.as_ref().map(|s| s.to_lowercase());
-    items
-        .iter()
-        .filter(|item| {
-            if let Some(agent) = agent.as_ref()
-                && !item.agent.to_lowercase().contains(agent)
-            {
-                return false;
-            }
-            if let Some(title) = title.as_ref()
-                && !item.title.to_lowercase().contains(title)
-            {
-                return false;
-            }
-            if let Some(id) = id.as_ref()
-                && !item.session_id.to_lowercase().contains(id)
-            {
-                return false;
-            }
-            if let Some(contains) = contains.as_ref()
-                && !item.match_text.to_lowercase().contains(contains)
-            {
-                return false;
-            }
-            true
-        })
-        .cloned()
-        .collect()
-}
-
 pub fn color_enabled() -> bool {
     io::stdout().is_terminal()
         && io::stderr().is_terminal()
@@ -296,18 +226,6 @@ pub fn highlight_terms(text: &str, terms: &[String], use_color: bool) -> String
     out
 }
 
-pub fn truncate_text(input: &str, max: usize) -> String {
-    let mut out = String::new();
-    for (i, ch) in input.chars().enumerate() {
-        if i >= max {
-            out.push_str("...");
-            return out;
-        }
-        out.push(ch);
-    }
-    out
-}
-
 pub fn resolve_output_dir(dir: Option<PathBuf>) -> anyhow::Result<PathBuf> {
     let base = if let Some(dir) = dir {
         dir
@@ -0,0 +1,343 @@
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use core_model::{AgentKind, JSON_SCHEMA_VERSION, Message, Provenance, RepoRef, Session};
+use regex::Regex;
+use serde_json::{Value, json};
+use store_sqlite::{SqliteStore, WipeCounts};
+
+// A project is a directory and, when it sits in a git repository, the
+// repository remote and the path inside it. Anything that is neither a
+// directory nor an absolute path is taken as a remote such as
+// `github.com/client/app`.
+#[derive(Debug, Clone)]
+pub struct Project {
+    pub path: Option<PathBuf>,
+    pub repo: Option<RepoRef>,
+}
+
+impl Project {
+    pub fn resolve(raw: &str) -> Self {
+        let path = Path::new(raw);
+        if path.is_dir() {
+            let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+            let repo = ingest::RepoResolver::default().resolve(&path.to_string_lossy());
+            return Self {
+                path: Some(path),
+                repo,
+            };
+        }
+        if path.is_absolute() {
+            return Self {
+                path: Some(path.to_path_buf()),
+                repo: None,
+            };
+        }
+        Self {
+            path: None,
+            repo: Some(RepoRef {
+                remote: ingest::normalize_remote(raw),
+                path: String::new(),
+            }),
+        }
+    }
+
+    // Judged by the workspace the session recorded, not by where its log
+    // lives: `~/.codex` sits under `~` whatever project the session was in.
+    fn contains(&self, provenance: &Provenance) -> bool {
+        if let (Some(path), Some(workspace)) = (&self.path, &provenance.workspace)
+            && Path::new(workspace).starts_with(path)
+        {
+            return true;
+        }
+        match (&self.repo, &provenance.repo) {
+            (Some(want), Some(have)) => {
+                want.remote == have.remote
+                    && (want.path.is_empty() || Path::new(&have.path).starts_with(&want.path))
+            }
+            _ => false,
+        }
+    }
+
+    // Rollups carry no provenance; their source ref ends with the remote.
+    fn owns_rollup(&self, session: &Session) -> bool {
+        session.agent == AgentKind::Meta
+            && self.repo.as_ref().is_some_and(|repo| {
+                session
+                    .source_ref
+                    .strip_suffix(&repo.remote)
+                    .is_some_and(|rest| rest.ends_with('/'))
+            })
+    }
+}
+
+// A session is wiped when it belongs to the project or when its title or
+// any message matches the pattern.
+pub struct Criteria {
+    pub project: Option<Project>,
+    pub pattern: Option<Regex>,
+}
+
+impl Criteria {
+    pub fn matches(
+        &self,
+        session: &Session,
+        messages: &[&Message],
+        provenance: &[&Provenance],
+    ) -> bool {
+        let in_project = self.project.as_ref().is_some_and(|project| {
+            project.owns_rollup(session) || provenance.iter().any(|p| project.contains(p))
+        });
+        in_project
+            || self.pattern.as_ref().is_some_and(|re| {
+                re.is_match(&session.title) || messages.iter().any(|m| re.is_match(&m.content))
+            })
+    }
+}
+
+pub struct Wiped {
+    pub session: Session,
+    pub messages: usize,
+    pub trashed: bool,
+}
+
+// Matching sessions, trashed ones included, with their message counts.
+pub fn select(store: &SqliteStore, criteria: &Criteria) -> anyhow::Result<Vec<Wiped>> {
+    let trashed = store.trashed_session_ids()?;
+    let mut ids: Vec<String> = store.list_sessions()?.into_iter().map(|s| s.id).collect();
+    ids.extend(trashed.iter().cloned());
+    let mut selected = Vec::new();
+    for id in ids {
+        let Some(bundle) = store.get_session_bundle(&id)? else {
+            continue;
+        };
+        let messages: Vec<&Message> = bundle.messages.iter().collect();
+        let provenance: Vec<&Provenance> = bundle.provenance.iter().collect();
+        if criteria.matches(&bundle.session, &messages, &provenance) {
+            selected.push(Wiped {
+                trashed: trashed.contains(&id),
+                messages: messages.len(),
+                session: bundle.session,
+            });
+        }
+    }
+    selected.sort_by(|a, b| {
+        b.session
+            .updated_at
+            .cmp(&a.session.updated_at)
+            .then_with(|| a.session.id.cmp(&b.session.id))
+    });
+    Ok(selected)
+}
+
+pub struct Report {
+    pub wiped_at: DateTime<Utc>,
+    pub dry_run: bool,
+    pub project: Option<String>,
+    pub matching: Option<String>,
+    pub sessions: Vec<Wiped>,
+    // Left empty on a dry run.
+    pub counts: Option<WipeCounts>,
+    pub cached_queries: usize,
+    pub archives: Vec<(String, Vec<String>)>,
+}
+
+impl Report {
+    // Ids and counts only, so the report can be kept after the purge.
+    pub fn to_json(&self) -> Value {
+        let sessions: Vec<Value> = self
+            .sessions
+            .iter()
+            .map(|w| {
+                json!({
+                    "id": w.session.id,
+                    "agent": w.session.agent.as_str(),
+                    "created_at": w.session.created_at,
+                    "updated_at": w.session.updated_at,
+                    "messages": w.messages,
+                    "trashed": w.trashed,
+                })
+            })
+            .collect();
+        let counts = self.counts.map(|c| {
+            json!({
+                "sessions": c.sessions,
+                "messages": c.messages,
+                "events": c.events,
+                "artifacts": c.artifacts,
+                "provenance": c.provenance,
+                "embeddings": c.embeddings,
+                "fts_rows": c.fts_rows,
+                "raw_records": c.raw_records,
+                "archive_items": c.archive_items,
+                "cached_queries": self.cached_queries,
+            })
+        });
+        let archives: Vec<Value> = self
+            .archives
+            .iter()
+            .map(|(run_id, sessions)| json!({ "run_id": run_id, "sessions": sessions }))
+            .collect();
+        json!({
+            "schema_version": JSON_SCHEMA_VERSION,
+            "wiped_at": self.wiped_at,
+            "dry_run": self.dry_run,
+            "criteria": { "project": self.project, "matching": self.matching },
+            "sessions": sessions,
+            "counts": counts,
+            "archives": archives,
+        })
+    }
+
+    pub fn to_text(&self) -> String {
+        let messages: usize = self.sessions.iter().map(|w| w.messages).sum();
+        let mut out = match self.counts {
+            Some(c) => format!(
+                "wiped {} sessions: messages={} events={} artifacts={} provenance={} embeddings={} fts_rows={} raw_records={} archive_items={} cached_queries={}\n",
+                c.sessions,
+                c.messages,
+                c.events,
+                c.artifacts,
+                c.provenance,
+                c.embeddings,
+                c.fts_rows,
+                c.raw_records,
+                c.archive_items,
+                self.cached_queries
+            ),
+            None => format!(
+                "would wipe {} sessions ({messages} messages)\n",
+                self.sessions.len()
+            ),
+        };
+        for w in &self.sessions {
+            let _ = writeln!(
+                out,
+                "  {} {} {}{} {}",
+                w.session.id,
+                w.session.agent.as_str(),
+                w.session.updated_at.to_rfc3339(),
+                if w.trashed { " (trash)" } else { "" },
+                crate::sanitize_title(&w.session.title)
+            );
+        }
+        let verb = if self.dry_run {
+            "would remove"
+        } else {
+            "removed"
+        };
+        for (run_id, sessions) in &self.archives {
+            let _ = writeln!(out, "archive {run_id}: {verb} {} sessions", sessions.len());
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provenance(workspace: Option<&str>, repo: Option<(&str, &str)>) -> Provenance {
+        Provenance {
+            id: "p".to_string(),
+            entity_type: "message".to_string(),
+            entity_id: "m".to_string(),
+            agent: AgentKind::Codex,
+            source_path: "/home/me/.codex/sessions/s.jsonl".to_string(),
+            source_id: "src".to_string(),
+            workspace: workspace.map(str::to_string),
+            repo: repo.map(|(remote, path)| RepoRef {
+                remote: remote.to_string(),
+                path: path.to_string(),
+            }),
+        }
+    }
+
+    fn session(agent: AgentKind, title: &str, source_ref: &str) -> Session {
+        let now = Utc::now();
+        Session {
+            id: "s".to_string(),
+            agent,
+            source_ref: source_ref.to_string(),
+            title: title.to_string(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn projects_match_by_path_remote_and_subdirectory() {
+        let remote = Project::resolve("https://github.com/Client/App.git");
+        assert!(remote.path.is_none());
+        assert!(remote.contains(&provenance(None, Some(("github.com/Client/App", "web")))));
+        assert!(!remote.contains(&provenance(None, Some(("github.com/Client/Api", "")))));
+
+        let path = Project::resolve("/nonexistent/clients/acme");
+        assert!(path.contains(&provenance(Some("/nonexistent/clients/acme/app"), None)));
+        assert!(!path.contains(&provenance(Some("/nonexistent/clients/acme-old"), None)));
+
+        let subdir = Project {
+            path: None,
+            repo: Some(RepoRef {
+                remote: "github.com/o/mono".to_string(),
+                path: "clients/acme".to_string(),
+            }),
+        };
+        assert!(subdir.contains(&provenance(
+            None,
+            Some(("github.com/o/mono", "clients/acme/src"))
+        )));
+        assert!(!subdir.contains(&provenance(
+            None,
+            Some(("github.com/o/mono", "clients/other"))
+        )));
+        assert!(subdir.owns_rollup(&session(
+            AgentKind::Meta,
+            "Week of 2025-05-05: github.com/o/mono",
+            "rollup/week/2025-05-05/github.com/o/mono"
+        )));
+        assert!(!subdir.owns_rollup(&session(
+            AgentKind::Meta,
+            "x",
+            "rollup/week/2025-05-05/github.com/o/notmono"
+        )));
+    }
+
+    #[test]
+    fn projects_match_the_workspace_not_the_log_path() {
+        // The log lives under the home directory, the work happened elsewhere.
+        let home = Project::resolve("/home/me");
+        let acme = Project::resolve("/clients/acme");
+        let session = provenance(Some("/clients/acme/app"), None);
+        assert!(!home.contains(&session));
+        assert!(acme.contains(&session));
+        // Without a recorded workspace only the repository can match.
+        assert!(!home.contains(&provenance(None, None)));
+    }
+
+    #[test]
+    fn either_criterion_selects_a_session() {
+        let criteria = Criteria {
+            project: Some(Project::resolve("/clients/acme")),
+            pattern: Some(Regex::new("(?i)acme corp").unwrap()),
+        };
+        let unrelated = provenance(Some("/home/me/dotfiles"), None);
+        let message = Message {
+            id: "m".to_string(),
+            session_id: "s".to_string(),
+            role: "user".to_string(),
+            content: "invoice for ACME Corp".to_string(),
+            ts: Utc::now(),
+        };
+        let plain = session(AgentKind::Codex, "dotfiles", "r");
+        assert!(criteria.matches(&plain, &[&message], &[&unrelated]));
+        assert!(!criteria.matches(&plain, &[], &[&unrelated]));
+        assert!(criteria.matches(&plain, &[], &[&provenance(Some("/clients/acme/x"), None)]));
+        assert!(criteria.matches(
+            &session(AgentKind::Codex, "Acme Corp rollout", "r"),
+            &[],
+            &[]
+        ));
+    }
+}
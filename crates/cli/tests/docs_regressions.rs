@@ -24,7 +24,9 @@ fn fresh_data_home() -> PathBuf {
 
 fn remi_cmd(data_home: &Path) -> Command {
     let mut cmd = Command::new(env!("CARGO_BIN_EXE_remi"));
-    cmd.env("HOME", data_home).env("XDG_DATA_HOME", data_home);
+    cmd.env("HOME", data_home)
+        .env("XDG_DATA_HOME", data_home)
+        .env("REMI_LOCALE", "en");
     cmd
 }
 
@@ -54,6 +56,7 @@ fn seed_session_store(data_home: &Path, query_term: &str) {
         events: vec![],
         artifacts: vec![],
         provenance: vec![],
+        session_meta: vec![],
     };
 
     store.save_batch(&batch).unwrap();
@@ -75,6 +78,8 @@ fn doctor_remains_session_centric() {
     let stdout = String::from_utf8_lossy(&output.stdout);
     assert!(stdout.contains("integrity_check=ok"), "stdout={stdout}");
     assert!(stdout.contains("sessions=1"), "stdout={stdout}");
+    assert!(stdout.contains("fts_stray_rows=0"), "stdout={stdout}");
+    assert!(stdout.contains("fts_missing_rows=0"), "stdout={stdout}");
 }
 
 #[test]
@@ -106,6 +111,7 @@ fn search_query_preserves_session_json_flow() {
     let sessions = json["sessions"].as_array().unwrap();
     assert_eq!(sessions.len(), 1, "json={json}");
     assert_eq!(sessions[0]["id"], "session-1");
+    assert_eq!(sessions[0]["top_message_id"], "message-1");
     assert_eq!(sessions[0]["title"], "docs regression seed");
     assert!(
         sessions[0]["snippet"]
@@ -116,6 +122,239 @@ fn search_query_preserves_session_json_flow() {
     );
 }
 
+#[test]
+fn search_query_streams_ndjson_lines() {
+    let data_home = fresh_data_home();
+    seed_session_store(&data_home, "ndjson-term");
+
+    let output = remi_cmd(&data_home)
+        .args([
+            "search",
+            "query",
+            "ndjson-term",
+            "--format",
+            "ndjson",
+            "--no-interactive",
+        ])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "search query failed:\nstdout={}\nstderr={}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 1, "stdout={stdout}");
+    assert_eq!(lines[0]["schema_version"], 1);
+    assert_eq!(lines[0]["query"], "ndjson-term");
+    assert_eq!(lines[0]["rank"], 0);
+    assert_eq!(lines[0]["session"]["id"], "session-1");
+}
+
+#[test]
+fn search_batch_keys_results_by_query() {
+    let data_home = fresh_data_home();
+    seed_session_store(&data_home, "batch-term");
+    let queries = data_home.join("queries.txt");
+    fs::write(
+        &queries,
+        "# eval
+batch-term
+nothing-matches-this
+",
+    )
+    .unwrap();
+
+    let output = remi_cmd(&data_home)
+        .args(["search", "batch"])
+        .arg(&queries)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "search batch failed:\nstdout={}\nstderr={}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["queries"], 2);
+    let hit = &json["results"]["batch-term"];
+    assert_eq!(hit["sessions"][0]["id"], "session-1", "json={json}");
+    assert_eq!(
+        json["results"]["nothing-matches-this"]["sessions"],
+        serde_json::json!([])
+    );
+
+    let output = remi_cmd(&data_home)
+        .args(["search", "batch", "--format", "jsonl"])
+        .arg(&queries)
+        .output()
+        .unwrap();
+    let lines: Vec<Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["query"], "batch-term");
+}
+
+#[test]
+fn eval_scores_gold_queries() {
+    let data_home = fresh_data_home();
+    seed_session_store(&data_home, "eval-term");
+    let gold = data_home.join("gold.jsonl");
+    fs::write(
+        &gold,
+        concat!(
+            r#"{"query":"eval-term","sessions":["session-1"]}"#,
+            "\n",
+            r#"{"query":"nothing-matches-this","sessions":["session-1"]}"#,
+            "\n",
+        ),
+    )
+    .unwrap();
+
+    let output = remi_cmd(&data_home)
+        .args(["eval", "--json", "--k", "1,3", "--gold"])
+        .arg(&gold)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "eval failed:\nstdout={}\nstderr={}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["queries"], 2);
+    assert_eq!(json["mrr"], 0.5, "json={json}");
+    assert_eq!(json["recall"]["1"], 0.5);
+    assert_eq!(json["cases"][0]["rank"], 1);
+    assert_eq!(json["cases"][1]["rank"], Value::Null);
+}
+
+#[test]
+fn rollup_stores_a_searchable_meta_session() {
+    let data_home = fresh_data_home();
+    seed_session_store(&data_home, "rollup-term");
+    let today = Utc::now().date_naive().to_string();
+
+    let output = remi_cmd(&data_home)
+        .args(["rollup", "--period", "day", "--date", &today])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "rollup failed:\nstdout={}\nstderr={}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(&format!(" day {today} (no repository) sessions=1")),
+        "stdout={stdout}"
+    );
+
+    let output = remi_cmd(&data_home)
+        .args([
+            "search",
+            "query",
+            "regression",
+            "--agent",
+            "meta",
+            "--format",
+            "json",
+            "--no-interactive",
+        ])
+        .output()
+        .unwrap();
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let sessions = json["sessions"].as_array().unwrap();
+    assert_eq!(sessions.len(), 1, "json={json}");
+    assert_eq!(
+        sessions[0]["title"],
+        format!("{today}: (no repository)"),
+        "json={json}"
+    );
+}
+
+#[test]
+fn wipe_purges_matching_sessions_and_reports_counts() {
+    let data_home = fresh_data_home();
+    seed_session_store(&data_home, "acme-contract");
+    let report = data_home.join("wipe.json");
+
+    let output = remi_cmd(&data_home)
+        .args(["wipe", "--matching", "(?i)ACME", "--dry-run"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.starts_with("would wipe 1 sessions (1 messages)"),
+        "stdout={stdout}"
+    );
+
+    let output = remi_cmd(&data_home)
+        .args(["wipe", "--matching", "(?i)ACME", "--report"])
+        .arg(&report)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "wipe failed:\nstdout={}\nstderr={}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let json: Value = serde_json::from_slice(&fs::read(&report).unwrap()).unwrap();
+    assert_eq!(json["sessions"][0]["id"], "session-1", "json={json}");
+    assert_eq!(json["counts"]["messages"], 1);
+    assert_eq!(json["counts"]["fts_rows"], 1);
+    assert!(json["sessions"][0].get("title").is_none());
+
+    let output = remi_cmd(&data_home).arg("doctor").output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("sessions=0"), "stdout={stdout}");
+    assert!(stdout.contains("fts_stray_rows=0"), "stdout={stdout}");
+}
+
+#[test]
+fn export_jsonl_anonymizes_paths_and_emails() {
+    let data_home = fresh_data_home();
+    seed_session_store(
+        &data_home,
+        "in /home/zelda/acme/src/main.rs for zelda@acme.example.com",
+    );
+
+    let output = remi_cmd(&data_home)
+        .args(["export", "--format", "jsonl", "--anonymize"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "export failed:\nstdout={}\nstderr={}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 1, "stdout={stdout}");
+    assert_eq!(lines[0]["session"]["id"], "s1");
+    assert_eq!(
+        lines[0]["messages"][0]["content"],
+        "seeded searchable content in /dir1/main.rs for person1@example.invalid"
+    );
+    assert!(!stdout.contains("zelda"), "stdout={stdout}");
+}
+
 #[test]
 fn search_query_empty_db_still_exits_cleanly() {
     let data_home = fresh_data_home();
@@ -285,3 +524,582 @@ fn docs_index_updates_stay_store_searchable_and_write_rfc3339_timestamps() {
         "modified_at={modified_at}"
     );
 }
+
+#[test]
+fn last_and_show_last_skip_the_search_flow() {
+    let data_home = fresh_data_home();
+    seed_session_store(&data_home, "recent-session-term");
+
+    let output = remi_cmd(&data_home).args(["last", "3"]).output().unwrap();
+    assert!(output.status.success(), "last failed: {output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("session-1"), "stdout={stdout}");
+    assert!(
+        stdout.contains("docs regression seed (1 messages)"),
+        "stdout={stdout}"
+    );
+
+    let output = remi_cmd(&data_home)
+        .args(["show", "last", "--stats"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "show last failed: {output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("messages=1"), "stdout={stdout}");
+    assert!(
+        stdout.contains("seeded searchable content recent-session-term"),
+        "stdout={stdout}"
+    );
+}
+
+#[test]
+fn messages_show_prints_message_with_session_context() {
+    let data_home = fresh_data_home();
+    seed_session_store(&data_home, "context-term");
+
+    let output = remi_cmd(&data_home)
+        .args(["messages", "show", "message-1", "--context", "2"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "messages show failed: {output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("session=session-1 agent=pi"),
+        "stdout={stdout}"
+    );
+    assert!(stdout.contains("position=1/1"), "stdout={stdout}");
+    assert!(stdout.contains("> message-1"), "stdout={stdout}");
+
+    let missing = remi_cmd(&data_home)
+        .args(["messages", "show", "nope"])
+        .output()
+        .unwrap();
+    assert!(!missing.status.success());
+    assert!(String::from_utf8_lossy(&missing.stderr).contains("message not found: nope"));
+}
+
+#[test]
+fn every_agent_kind_is_registered_for_sync_and_doctor() {
+    let data_home = fresh_data_home();
+    seed_session_store(&data_home, "registration-term");
+
+    let output = remi_cmd(&data_home).arg("doctor").output().unwrap();
+    assert!(output.status.success(), "doctor failed: {output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let conn = Connection::open(data_home.join("remi").join("remi.db")).unwrap();
+    for kind in AgentKind::ALL {
+        assert!(
+            stdout.contains(&format!("adapter={kind} ")),
+            "doctor missing {kind}: {stdout}"
+        );
+        let seeded: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM agents WHERE id = ?1",
+                [kind.as_str()],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(seeded, 1, "agents seed missing {kind}");
+
+        let sync = remi_cmd(&data_home)
+            .args(["sync", "--agent", kind.as_str()])
+            .output()
+            .unwrap();
+        assert!(
+            sync.status.success(),
+            "sync --agent {kind} failed: {sync:?}"
+        );
+    }
+}
+
+#[test]
+fn doctor_reports_adapter_home_and_honors_override() {
+    let data_home = fresh_data_home();
+    seed_session_store(&data_home, "home-term");
+    let pi_home = data_home.join("pi-home");
+    let sessions = pi_home.join(".pi/agent/sessions");
+    fs::create_dir_all(&sessions).unwrap();
+    fs::write(sessions.join("s.jsonl"), "").unwrap();
+
+    let output = remi_cmd(&data_home)
+        .env("REMI_PI_HOME", &pi_home)
+        .arg("doctor")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "doctor failed: {output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(&format!(
+            "adapter=pi label=Pi home={} sources=1",
+            pi_home.display()
+        )),
+        "stdout={stdout}"
+    );
+    assert!(
+        stdout.contains(&format!(
+            "label=Factory Droid home={} ",
+            data_home.display()
+        )),
+        "stdout={stdout}"
+    );
+}
+
+#[test]
+fn discovery_config_excludes_paths_and_limits_depth() {
+    let data_home = fresh_data_home();
+    seed_session_store(&data_home, "discovery-term");
+    let sessions = data_home.join(".pi/agent/sessions");
+    fs::create_dir_all(sessions.join("scratch")).unwrap();
+    fs::create_dir_all(sessions.join("a/b")).unwrap();
+    fs::write(sessions.join("keep.jsonl"), "").unwrap();
+    fs::write(sessions.join("scratch/skip.jsonl"), "").unwrap();
+    fs::write(sessions.join("a/b/deep.jsonl"), "").unwrap();
+    let config_dir = data_home.join(".config").join("remi");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(
+        config_dir.join("config.toml"),
+        "[discovery]\nmax_depth = 1\n\n[discovery.agents.pi]\nexclude = [\"scratch\"]\n",
+    )
+    .unwrap();
+
+    let output = remi_cmd(&data_home)
+        .env("XDG_CONFIG_HOME", data_home.join(".config"))
+        .arg("doctor")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "doctor failed: {output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(&format!(
+            "adapter=pi label=Pi home={} sources=1",
+            data_home.display()
+        )),
+        "stdout={stdout}"
+    );
+}
+
+#[test]
+fn sync_caches_settled_directory_listings() {
+    let data_home = fresh_data_home();
+    let sessions = data_home.join(".pi/agent/sessions");
+    fs::create_dir_all(&sessions).unwrap();
+    fs::write(sessions.join("s.jsonl"), "").unwrap();
+    let hour_ago = SystemTime::now() - std::time::Duration::from_secs(3600);
+    fs::File::open(&sessions)
+        .unwrap()
+        .set_modified(hour_ago)
+        .unwrap();
+
+    let output = remi_cmd(&data_home)
+        .args(["sync", "--agent", "pi"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "sync failed: {output:?}");
+
+    let conn = Connection::open(data_home.join("remi").join("remi.db")).unwrap();
+    let entries: String = conn
+        .query_row(
+            "SELECT entries FROM file_state WHERE agent = 'pi' AND kind = 'dir' AND path = ?1",
+            [sessions.to_str().unwrap()],
+            |r| r.get(0),
+        )
+        .unwrap();
+    assert!(entries.contains("s.jsonl"), "entries={entries}");
+}
+
+#[test]
+fn doctor_lists_compiled_features_without_a_database() {
+    let data_home = fresh_data_home();
+    let output = remi_cmd(&data_home)
+        .args(["doctor", "--features"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "doctor failed: {output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for agent in ["pi", "droid", "opencode", "claude", "amp", "codex"] {
+        assert!(
+            stdout.contains(&format!("adapter_{agent}=on\n")),
+            "stdout={stdout}"
+        );
+    }
+    assert!(stdout.contains("\nsemantic="), "stdout={stdout}");
+    assert!(!data_home.join("remi").join("remi.db").exists());
+}
+
+#[test]
+fn nice_sync_ingests_the_same_sessions() {
+    let data_home = fresh_data_home();
+    let sessions = data_home.join(".pi/agent/sessions");
+    fs::create_dir_all(&sessions).unwrap();
+    write_pi_session(&sessions, "sess-a", Utc::now(), "first background note");
+    write_pi_session(&sessions, "sess-b", Utc::now(), "second background note");
+
+    let output = remi_cmd(&data_home)
+        .args(["sync", "--agent", "pi", "--nice"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "sync failed: {output:?}");
+
+    let conn = Connection::open(data_home.join("remi").join("remi.db")).unwrap();
+    let messages: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM messages WHERE content LIKE '%background note%'",
+            [],
+            |r| r.get(0),
+        )
+        .unwrap();
+    assert_eq!(messages, 2);
+}
+
+fn write_pi_session(dir: &Path, id: &str, ts: DateTime<Utc>, text: &str) -> PathBuf {
+    let path = dir.join(format!("{id}.jsonl"));
+    let ts = ts.to_rfc3339();
+    fs::write(
+        &path,
+        format!(
+            "{{\"type\":\"session\",\"version\":3,\"id\":\"{id}\",\"timestamp\":\"{ts}\",\"cwd\":\"/tmp\"}}\n\
+             {{\"type\":\"message\",\"id\":\"m1\",\"parentId\":null,\"timestamp\":\"{ts}\",\"message\":{{\"role\":\"user\",\"content\":[{{\"type\":\"text\",\"text\":\"{text}\"}}]}}}}\n"
+        ),
+    )
+    .unwrap();
+    path
+}
+
+#[test]
+fn max_age_sync_records_cutoff_and_full_sync_backfills() {
+    let data_home = fresh_data_home();
+    let sessions = data_home.join(".pi/agent/sessions");
+    fs::create_dir_all(&sessions).unwrap();
+    let old_ts = Utc::now() - chrono::Duration::days(60);
+    let old = write_pi_session(&sessions, "sess-old", old_ts, "ancient history");
+    write_pi_session(&sessions, "sess-new", Utc::now(), "fresh work");
+    fs::File::options()
+        .write(true)
+        .open(&old)
+        .unwrap()
+        .set_modified(old_ts.into())
+        .unwrap();
+
+    let db = data_home.join("remi").join("remi.db");
+    let count = |sql: &str| -> i64 {
+        Connection::open(&db)
+            .unwrap()
+            .query_row(sql, [], |r| r.get(0))
+            .unwrap()
+    };
+
+    let limited = remi_cmd(&data_home)
+        .args(["sync", "--agent", "pi", "--max-age", "30d"])
+        .output()
+        .unwrap();
+    assert!(limited.status.success(), "sync failed: {limited:?}");
+    assert_eq!(count("SELECT COUNT(*) FROM sessions"), 1);
+    assert_eq!(
+        count("SELECT COUNT(*) FROM sync_cutoffs WHERE agent = 'pi'"),
+        1
+    );
+
+    let full = remi_cmd(&data_home)
+        .args(["sync", "--agent", "pi"])
+        .output()
+        .unwrap();
+    assert!(full.status.success(), "sync failed: {full:?}");
+    assert_eq!(count("SELECT COUNT(*) FROM sessions"), 2);
+    assert_eq!(count("SELECT COUNT(*) FROM sync_cutoffs"), 0);
+
+    let bad = remi_cmd(&data_home)
+        .args(["sync", "--agent", "pi", "--max-age", "soon"])
+        .output()
+        .unwrap();
+    assert!(!bad.status.success());
+    assert!(String::from_utf8_lossy(&bad.stderr).contains("invalid --max-age"));
+}
+
+#[test]
+fn backfill_ingests_history_behind_the_checkpoint() {
+    let data_home = fresh_data_home();
+    let sessions = data_home.join(".pi/agent/sessions");
+    fs::create_dir_all(&sessions).unwrap();
+    for (id, days) in [("sess-ancient", 60), ("sess-older", 20)] {
+        let ts = Utc::now() - chrono::Duration::days(days);
+        let path = write_pi_session(&sessions, id, ts, "older history");
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(ts.into())
+            .unwrap();
+    }
+    write_pi_session(&sessions, "sess-new", Utc::now(), "fresh work");
+
+    let db = data_home.join("remi").join("remi.db");
+    let count = |sql: &str| -> i64 {
+        Connection::open(&db)
+            .unwrap()
+            .query_row(sql, [], |r| r.get(0))
+            .unwrap()
+    };
+
+    let limited = remi_cmd(&data_home)
+        .args(["sync", "--agent", "pi", "--max-age", "10d"])
+        .output()
+        .unwrap();
+    assert!(limited.status.success(), "sync failed: {limited:?}");
+    assert_eq!(count("SELECT COUNT(*) FROM sessions"), 1);
+
+    let before = (Utc::now() - chrono::Duration::days(40))
+        .format("%Y-%m-%d")
+        .to_string();
+    let partial = remi_cmd(&data_home)
+        .args(["backfill", "--agent", "pi", "--before", &before])
+        .output()
+        .unwrap();
+    assert!(partial.status.success(), "backfill failed: {partial:?}");
+    assert!(String::from_utf8_lossy(&partial.stdout).contains("agent=pi backfilled=1"));
+    assert_eq!(count("SELECT COUNT(*) FROM sessions"), 2);
+    assert_eq!(count("SELECT COUNT(*) FROM sync_cutoffs"), 1);
+
+    let rest = remi_cmd(&data_home)
+        .args(["backfill", "--agent", "pi"])
+        .output()
+        .unwrap();
+    assert!(rest.status.success(), "backfill failed: {rest:?}");
+    assert_eq!(count("SELECT COUNT(*) FROM sessions"), 3);
+    assert_eq!(count("SELECT COUNT(*) FROM sync_cutoffs"), 0);
+
+    let bad = remi_cmd(&data_home)
+        .args(["backfill", "--agent", "pi", "--before", "yesterday"])
+        .output()
+        .unwrap();
+    assert!(!bad.status.success());
+    assert!(String::from_utf8_lossy(&bad.stderr).contains("invalid --before"));
+}
+
+#[test]
+fn system_prompts_are_captured_as_session_meta_when_enabled() {
+    let data_home = fresh_data_home();
+    let sessions = data_home.join(".codex/sessions");
+    fs::create_dir_all(&sessions).unwrap();
+    fs::write(
+        sessions.join("rollout.jsonl"),
+        [
+            r#"{"timestamp":"2025-01-15T10:30:00Z","type":"session_meta","payload":{"id":"sess-sys","cwd":"/tmp"}}"#,
+            r#"{"timestamp":"2025-01-15T10:30:01Z","type":"response_item","payload":{"type":"message","role":"developer","content":[{"type":"text","text":"be terse"}]}}"#,
+            r#"{"timestamp":"2025-01-15T10:30:02Z","type":"response_item","payload":{"type":"message","role":"user","content":[{"type":"input_text","text":"hello"}]}}"#,
+        ]
+        .join("\n"),
+    )
+    .unwrap();
+    let config_dir = data_home.join(".config").join("remi");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(
+        config_dir.join("config.toml"),
+        "[capture]\nsystem_prompts = true\n",
+    )
+    .unwrap();
+
+    let sync = remi_cmd(&data_home)
+        .env("XDG_CONFIG_HOME", data_home.join(".config"))
+        .args(["sync", "--agent", "codex"])
+        .output()
+        .unwrap();
+    assert!(sync.status.success(), "sync failed: {sync:?}");
+
+    let show = remi_cmd(&data_home)
+        .args(["show", "last", "--system-prompts"])
+        .output()
+        .unwrap();
+    assert!(show.status.success(), "show failed: {show:?}");
+    let stdout = String::from_utf8_lossy(&show.stdout);
+    assert!(stdout.contains("[developer] be terse"), "stdout={stdout}");
+    assert!(stdout.contains("[user] hello"), "stdout={stdout}");
+    assert_eq!(stdout.matches("be terse").count(), 1, "stdout={stdout}");
+}
+
+#[test]
+fn search_reasoning_filter_separates_thinking_from_answers() {
+    let data_home = fresh_data_home();
+    let sessions = data_home.join(".pi/agent/sessions");
+    fs::create_dir_all(&sessions).unwrap();
+    fs::write(
+        sessions.join("sess-think.jsonl"),
+        [
+            r#"{"type":"session","version":3,"id":"sess-think","timestamp":"2026-02-08T10:54:12.530Z","cwd":"/tmp"}"#,
+            r#"{"type":"message","id":"m1","parentId":null,"timestamp":"2026-02-08T10:55:00.000Z","message":{"role":"user","content":[{"type":"text","text":"pick a lock type"}]}}"#,
+            r#"{"type":"message","id":"m2","parentId":"m1","timestamp":"2026-02-08T10:55:01.000Z","message":{"role":"assistant","content":[{"type":"thinking","thinking":"a quokka sized spinlock is overkill"},{"type":"text","text":"use a plain mutex"}]}}"#,
+        ]
+        .join("\n"),
+    )
+    .unwrap();
+    let sync = remi_cmd(&data_home)
+        .args(["sync", "--agent", "pi"])
+        .output()
+        .unwrap();
+    assert!(sync.status.success(), "sync failed: {sync:?}");
+
+    let search = |term: &str, mode: &str| {
+        let output = remi_cmd(&data_home)
+            .args([
+                "search",
+                "query",
+                term,
+                "--reasoning",
+                mode,
+                "--format",
+                "json",
+                "--no-interactive",
+            ])
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "search failed: {output:?}");
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if stdout.is_empty() {
+            return 0;
+        }
+        let json: Value = serde_json::from_str(&stdout).unwrap();
+        json["sessions"].as_array().unwrap().len()
+    };
+    assert_eq!(search("quokka", "include"), 1);
+    assert_eq!(search("quokka", "only"), 1);
+    assert_eq!(search("quokka", "exclude"), 0);
+    assert_eq!(search("mutex", "exclude"), 1);
+    assert_eq!(search("mutex", "only"), 0);
+}
+
+#[test]
+fn workspace_repo_identity_survives_directory_moves() {
+    let data_home = fresh_data_home();
+    let checkout = data_home.join("code/remi");
+    let workspace = checkout.join("crates/cli");
+    fs::create_dir_all(&workspace).unwrap();
+    fs::create_dir_all(checkout.join(".git")).unwrap();
+    fs::write(
+        checkout.join(".git/config"),
+        "[remote \"origin\"]\n\turl = git@github.com:lsj5031/Remi.git\n",
+    )
+    .unwrap();
+    let sessions = data_home.join(".pi/agent/sessions");
+    fs::create_dir_all(&sessions).unwrap();
+    fs::write(
+        sessions.join("sess-repo.jsonl"),
+        [
+            format!(
+                r#"{{"type":"session","version":3,"id":"sess-repo","timestamp":"2026-02-08T10:54:12.530Z","cwd":"{}"}}"#,
+                workspace.display()
+            ),
+            r#"{"type":"message","id":"m1","parentId":null,"timestamp":"2026-02-08T10:55:00.000Z","message":{"role":"user","content":[{"type":"text","text":"tidy the cli"}]}}"#.to_string(),
+        ]
+        .join("\n"),
+    )
+    .unwrap();
+    let sync = remi_cmd(&data_home)
+        .args(["sync", "--agent", "pi"])
+        .output()
+        .unwrap();
+    assert!(sync.status.success(), "sync failed: {sync:?}");
+
+    let projects = || {
+        let output = remi_cmd(&data_home)
+            .args(["stats", "projects", "--json"])
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "stats projects failed: {output:?}");
+        let json = serde_json::from_slice::<Value>(&output.stdout).unwrap();
+        assert_eq!(json["schema_version"], 1);
+        json["projects"].clone()
+    };
+    let listed = projects();
+    assert_eq!(listed[0]["repo"], "github.com/lsj5031/Remi");
+    assert_eq!(listed[0]["sessions"], 1);
+    assert_eq!(listed[0]["messages"], 1);
+
+    fs::rename(&checkout, data_home.join("code/remi-moved")).unwrap();
+    let backfill = remi_cmd(&data_home)
+        .args(["backfill", "--agent", "pi"])
+        .output()
+        .unwrap();
+    assert!(backfill.status.success(), "backfill failed: {backfill:?}");
+    assert_eq!(projects()[0]["repo"], "github.com/lsj5031/Remi");
+    let conn = Connection::open(data_home.join("remi").join("remi.db")).unwrap();
+    let repo_path: String = conn
+        .query_row("SELECT repo_path FROM provenance", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(repo_path, "crates/cli");
+}
+
+#[test]
+fn sync_all_skips_excluded_and_disabled_agents() {
+    let data_home = fresh_data_home();
+    let sessions = data_home.join(".pi/agent/sessions");
+    fs::create_dir_all(&sessions).unwrap();
+    write_pi_session(&sessions, "sess-select", Utc::now(), "pick me");
+    let config_dir = data_home.join(".config/remi");
+    fs::create_dir_all(&config_dir).unwrap();
+    let db = data_home.join("remi").join("remi.db");
+    let sync = |args: &[&str]| {
+        let output = remi_cmd(&data_home)
+            .env("XDG_CONFIG_HOME", data_home.join(".config"))
+            .arg("sync")
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "sync {args:?} failed: {output:?}");
+        Connection::open(&db)
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM sessions", [], |r| r.get::<_, i64>(0))
+            .unwrap()
+    };
+
+    assert_eq!(sync(&["--all", "--except", "droid,pi"]), 0);
+    fs::write(
+        config_dir.join("config.toml"),
+        "[agents.pi]\nenabled = false\n",
+    )
+    .unwrap();
+    assert_eq!(sync(&["--all"]), 0);
+    assert_eq!(sync(&["--only", "pi,amp"]), 1);
+}
+
+#[test]
+fn init_interactive_reports_agents_and_writes_config() {
+    let data_home = fresh_data_home();
+    let sessions = data_home.join(".pi/agent/sessions");
+    fs::create_dir_all(&sessions).unwrap();
+    write_pi_session(&sessions, "sess-a", Utc::now(), "first");
+    write_pi_session(&sessions, "sess-b", Utc::now(), "second");
+    let config_dir = data_home.join(".config/remi");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(
+        config_dir.join("config.toml"),
+        "# keep me\n[capture]\nsystem_prompts = true\n",
+    )
+    .unwrap();
+
+    // Closed stdin accepts every default: sync what was found, write the file.
+    let output = remi_cmd(&data_home)
+        .env("XDG_CONFIG_HOME", data_home.join(".config"))
+        .args(["init", "--interactive"])
+        .stdin(std::process::Stdio::null())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "init failed: {output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("~2 sessions in 2 source files"), "{stdout}");
+    assert!(stdout.contains("nothing found under"), "{stdout}");
+
+    let written = fs::read_to_string(config_dir.join("config.toml")).unwrap();
+    assert!(written.starts_with("# keep me\n[capture]\nsystem_prompts = true\n"));
+    assert!(written.contains("[agents.pi]\nenabled = true"), "{written}");
+    assert!(
+        written.contains("[agents.droid]\nenabled = false"),
+        "{written}"
+    );
+
+    let sync = remi_cmd(&data_home)
+        .env("XDG_CONFIG_HOME", data_home.join(".config"))
+        .args(["sync", "--all"])
+        .output()
+        .unwrap();
+    assert!(sync.status.success(), "sync failed: {sync:?}");
+}
@@ -0,0 +1,129 @@
+"use strict";
+
+const $ = (selector) => document.querySelector(selector);
+
+function text(tag, value, className) {
+  const el = document.createElement(tag);
+  el.textContent = value;
+  if (className) el.className = className;
+  return el;
+}
+
+async function api(method, path) {
+  const response = await fetch(path, { method });
+  const body = await response.json();
+  if (!response.ok) throw new Error(body.error || response.statusText);
+  return body;
+}
+
+for (const button of document.querySelectorAll("nav button")) {
+  button.addEventListener("click", () => {
+    for (const other of document.querySelectorAll("nav button")) other.classList.remove("active");
+    button.classList.add("active");
+    for (const tab of document.querySelectorAll(".tab")) tab.hidden = tab.id !== button.dataset.tab;
+    if (button.dataset.tab === "archive") loadRuns();
+  });
+}
+
+function openSession(item, id) {
+  for (const li of document.querySelectorAll("#results li")) li.classList.remove("selected");
+  item.classList.add("selected");
+  $("#reader").src = "/sessions/" + encodeURIComponent(id);
+}
+
+$("#search-form").addEventListener("submit", async (event) => {
+  event.preventDefault();
+  const params = new URLSearchParams();
+  for (const [key, value] of new FormData(event.target)) {
+    if (value !== "") params.set(key, value);
+  }
+  history.replaceState(null, "", "?" + params);
+  $("#search-status").textContent = "Searching…";
+  const results = $("#results");
+  results.replaceChildren();
+  try {
+    const output = await api("GET", "/api/search?" + params);
+    $("#search-status").textContent = output.sessions.length + " sessions";
+    for (const session of output.sessions) {
+      const li = document.createElement("li");
+      li.append(
+        text("div", session.title),
+        text("div", session.agent + " · " + session.message_count + " messages · " + session.updated_at.slice(0, 10), "meta"),
+        text("div", session.snippet, "snippet"),
+      );
+      li.addEventListener("click", () => openSession(li, session.id));
+      results.append(li);
+    }
+    if (results.firstChild) openSession(results.firstChild, output.sessions[0].id);
+  } catch (error) {
+    $("#search-status").textContent = error.message;
+  }
+});
+
+async function loadRuns() {
+  const body = $("#runs");
+  try {
+    const runs = await api("GET", "/api/archive/runs");
+    body.replaceChildren();
+    for (const run of runs) {
+      const row = document.createElement("tr");
+      const code = text("code", run.id.slice(0, 12));
+      code.title = run.id;
+      const state = run.executed ? (run.dry_run ? "dry-run done" : "archived") : "planned";
+      const actions = document.createElement("td");
+      if (!run.executed) {
+        for (const [label, query] of [["Dry run", ""], ["Archive", "execute=true"], ["Archive + delete", "execute=true&delete_source=true"]]) {
+          const button = text("button", label);
+          button.addEventListener("click", () => runPlan(run.id, query, label));
+          actions.append(button);
+        }
+      }
+      const id = document.createElement("td");
+      id.append(code);
+      row.append(
+        id,
+        text("td", run.created_at.slice(0, 19).replace("T", " ")),
+        text("td", Math.round(run.older_than_secs / 86400) + "d"),
+        text("td", run.keep_latest),
+        text("td", run.sessions),
+        text("td", state),
+        actions,
+      );
+      body.append(row);
+    }
+  } catch (error) {
+    $("#archive-status").textContent = error.message;
+  }
+}
+
+async function runPlan(id, query, label) {
+  if (query.includes("delete_source") && !confirm("Archive and delete these sessions from the database?")) return;
+  try {
+    const result = await api("POST", "/api/archive/runs/" + encodeURIComponent(id) + "/run?" + query);
+    $("#archive-status").textContent = label + ": " + result.message;
+  } catch (error) {
+    $("#archive-status").textContent = error.message;
+  }
+  loadRuns();
+}
+
+$("#plan-form").addEventListener("submit", async (event) => {
+  event.preventDefault();
+  const params = new URLSearchParams(new FormData(event.target));
+  try {
+    const run = await api("POST", "/api/archive/plan?" + params);
+    $("#archive-status").textContent = "Planned " + run.sessions + " sessions in run " + run.id.slice(0, 12);
+  } catch (error) {
+    $("#archive-status").textContent = error.message;
+  }
+  loadRuns();
+});
+
+const initial = new URLSearchParams(location.search);
+if (initial.has("q")) {
+  for (const [key, value] of initial) {
+    const field = $("#search-form").elements[key];
+    if (field) field.value = value;
+  }
+  $("#search-form").requestSubmit();
+}
@@ -0,0 +1,201 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::tools::{TOOL_USE_PREFIX, tool_file_mentions};
+
+pub const RELATION_USES: &str = "uses";
+pub const RELATION_CO_OCCURS: &str = "co_occurs";
+const ERROR_SUFFIXES: &[&str] = &["Error", "Exception"];
+const RUST_ERROR_CODE: &str = "error[E";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EntityKind {
+    Repo,
+    File,
+    Tool,
+    Error,
+}
+
+impl EntityKind {
+    pub const ALL: [EntityKind; 4] = [
+        EntityKind::Repo,
+        EntityKind::File,
+        EntityKind::Tool,
+        EntityKind::Error,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EntityKind::Repo => "repo",
+            EntityKind::File => "file",
+            EntityKind::Tool => "tool",
+            EntityKind::Error => "error",
+        }
+    }
+}
+
+impl std::str::FromStr for EntityKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        EntityKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| {
+                anyhow::anyhow!("unknown entity kind {s:?}; expected repo, file, tool or error")
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Entity {
+    pub kind: EntityKind,
+    pub name: String,
+}
+
+impl Entity {
+    fn new(kind: EntityKind, name: impl Into<String>) -> Self {
+        Self {
+            kind,
+            name: name.into(),
+        }
+    }
+}
+
+// Exception and error type names (`TypeError`, `NullPointerException`) and
+// rustc error codes (`error[E0599]` gives `E0599`).
+pub fn error_types(content: &str) -> Vec<String> {
+    let mut out: Vec<String> = content
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .filter(|word| {
+            word.starts_with(|c: char| c.is_ascii_uppercase())
+                && ERROR_SUFFIXES
+                    .iter()
+                    .any(|suffix| word.len() > suffix.len() && word.ends_with(suffix))
+        })
+        .map(str::to_string)
+        .collect();
+    for (i, _) in content.match_indices(RUST_ERROR_CODE) {
+        let code = &content.as_bytes()[i + RUST_ERROR_CODE.len() - 1..];
+        if code.len() > 5 && code[1..5].iter().all(u8::is_ascii_digit) && code[5] == b']' {
+            out.push(String::from_utf8_lossy(&code[..5]).into_owned());
+        }
+    }
+    out.sort();
+    out.dedup();
+    out
+}
+
+// Node and edge weights are the number of sessions they appear in. A tool
+// call naming a file gives a directed `uses` edge from the tool to the
+// file; every other pair of entities in a session is joined by an undirected
+// `co_occurs` edge, except file pairs, which would grow with the square of
+// the files a session touches.
+#[derive(Debug, Default)]
+pub struct GraphBuilder {
+    pub nodes: HashMap<Entity, usize>,
+    pub edges: HashMap<(Entity, Entity, &'static str), usize>,
+}
+
+impl GraphBuilder {
+    pub fn add_session<'a>(
+        &mut self,
+        repos: &[String],
+        messages: impl IntoIterator<Item = &'a str>,
+    ) {
+        let mut entities: BTreeSet<Entity> = repos
+            .iter()
+            .map(|repo| Entity::new(EntityKind::Repo, repo))
+            .collect();
+        let mut uses: BTreeSet<(Entity, Entity)> = BTreeSet::new();
+        for content in messages {
+            for line in content.lines() {
+                let Some(rest) = line.trim().strip_prefix(TOOL_USE_PREFIX) else {
+                    continue;
+                };
+                if let Some(tool) = rest
+                    .split_whitespace()
+                    .next()
+                    .filter(|t| !t.starts_with('{'))
+                {
+                    entities.insert(Entity::new(EntityKind::Tool, tool.to_lowercase()));
+                }
+            }
+            for mention in tool_file_mentions(content) {
+                let tool = Entity::new(EntityKind::Tool, mention.tool.to_lowercase());
+                let file = Entity::new(EntityKind::File, mention.path);
+                entities.insert(tool.clone());
+                entities.insert(file.clone());
+                uses.insert((tool, file));
+            }
+            for error in error_types(content) {
+                entities.insert(Entity::new(EntityKind::Error, error));
+            }
+        }
+
+        for entity in &entities {
+            *self.nodes.entry(entity.clone()).or_default() += 1;
+        }
+        for (tool, file) in &uses {
+            *self
+                .edges
+                .entry((tool.clone(), file.clone(), RELATION_USES))
+                .or_default() += 1;
+        }
+        let entities: Vec<&Entity> = entities.iter().collect();
+        for (i, a) in entities.iter().enumerate() {
+            for b in &entities[i + 1..] {
+                let linked = uses.contains(&((*a).clone(), (*b).clone()))
+                    || uses.contains(&((*b).clone(), (*a).clone()));
+                if linked || (a.kind == EntityKind::File && b.kind == EntityKind::File) {
+                    continue;
+                }
+                *self
+                    .edges
+                    .entry(((*a).clone(), (*b).clone(), RELATION_CO_OCCURS))
+                    .or_default() += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_types_find_exception_names_and_rustc_codes() {
+        let content = "Traceback: TypeError: bad\nerror[E0599]: no method\nerror[E123é]\njava.lang.NullPointerException\nError: plain\nerror[E12]";
+        assert_eq!(
+            error_types(content),
+            vec!["E0599", "NullPointerException", "TypeError"]
+        );
+        assert!(error_types("an error occurred, Errors happen").is_empty());
+    }
+
+    #[test]
+    fn sessions_add_weighted_nodes_and_edges() {
+        let mut graph = GraphBuilder::default();
+        let first = "tool_use: Edit {\"file_path\":\"src/a.rs\"}\ntool_use: Read {\"file_path\":\"src/b.rs\"}";
+        let second = "tool_result: KeyError: 'x'\ntool_use: edit {\"file_path\":\"src/a.rs\"}";
+        graph.add_session(&["github.com/o/r".to_string()], [first]);
+        graph.add_session(&["github.com/o/r".to_string()], [second]);
+
+        let node = |kind, name: &str| graph.nodes[&Entity::new(kind, name)];
+        assert_eq!(node(EntityKind::Tool, "edit"), 2);
+        assert_eq!(node(EntityKind::File, "src/a.rs"), 2);
+        assert_eq!(node(EntityKind::Error, "KeyError"), 1);
+        assert_eq!(graph.nodes.len(), 6);
+
+        let edge = |a: Entity, b: Entity, relation| graph.edges.get(&(a, b, relation)).copied();
+        let edit = Entity::new(EntityKind::Tool, "edit");
+        let a = Entity::new(EntityKind::File, "src/a.rs");
+        let b = Entity::new(EntityKind::File, "src/b.rs");
+        let repo = Entity::new(EntityKind::Repo, "github.com/o/r");
+        assert_eq!(edge(edit.clone(), a.clone(), RELATION_USES), Some(2));
+        assert_eq!(edge(a.clone(), edit.clone(), RELATION_CO_OCCURS), None);
+        assert_eq!(edge(edit.clone(), a.clone(), RELATION_CO_OCCURS), None);
+        assert_eq!(edge(repo.clone(), a.clone(), RELATION_CO_OCCURS), Some(2));
+        assert_eq!(edge(a.clone(), b.clone(), RELATION_CO_OCCURS), None);
+        assert_eq!(edge(repo, edit, RELATION_CO_OCCURS), Some(2));
+    }
+}
@@ -0,0 +1,175 @@
+const FENCE: &str = "```";
+
+const ALIASES: &[(&str, &str)] = &[
+    ("rust", "rust"),
+    ("rs", "rust"),
+    ("python", "python"),
+    ("py", "python"),
+    ("python3", "python"),
+    ("javascript", "javascript"),
+    ("js", "javascript"),
+    ("jsx", "javascript"),
+    ("mjs", "javascript"),
+    ("node", "javascript"),
+    ("typescript", "typescript"),
+    ("ts", "typescript"),
+    ("tsx", "typescript"),
+    ("go", "go"),
+    ("golang", "go"),
+    ("shell", "shell"),
+    ("sh", "shell"),
+    ("bash", "shell"),
+    ("zsh", "shell"),
+    ("console", "shell"),
+    ("shell-session", "shell"),
+    ("sql", "sql"),
+    ("json", "json"),
+    ("jsonc", "json"),
+    ("yaml", "yaml"),
+    ("yml", "yaml"),
+    ("toml", "toml"),
+    ("html", "html"),
+    ("css", "css"),
+    ("c", "c"),
+    ("h", "c"),
+    ("cpp", "cpp"),
+    ("c++", "cpp"),
+    ("cc", "cpp"),
+    ("hpp", "cpp"),
+    ("java", "java"),
+    ("kotlin", "kotlin"),
+    ("kt", "kotlin"),
+    ("swift", "swift"),
+    ("ruby", "ruby"),
+    ("rb", "ruby"),
+    ("php", "php"),
+    ("csharp", "csharp"),
+    ("cs", "csharp"),
+    ("c#", "csharp"),
+    ("diff", "diff"),
+    ("patch", "diff"),
+];
+
+pub fn canonical_language(name: &str) -> Option<&'static str> {
+    let name = name.trim().to_ascii_lowercase();
+    ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map(|(_, lang)| *lang)
+}
+
+// Fence info strings win; untagged blocks fall back to keyword heuristics.
+pub fn detect_code_languages(content: &str) -> Vec<&'static str> {
+    let mut langs = Vec::new();
+    let mut lines = content.lines();
+    while let Some(line) = lines.next() {
+        let Some(info) = line.trim_start().strip_prefix(FENCE) else {
+            continue;
+        };
+        let mut body = String::new();
+        for line in lines.by_ref() {
+            if line.trim_start().starts_with(FENCE) {
+                break;
+            }
+            body.push_str(line);
+            body.push('\n');
+        }
+        let tag = info.split([' ', ',', '{']).next().unwrap_or_default();
+        if let Some(lang) = canonical_language(tag).or_else(|| guess_language(&body)) {
+            langs.push(lang);
+        }
+    }
+    langs.sort_unstable();
+    langs.dedup();
+    langs
+}
+
+fn guess_language(body: &str) -> Option<&'static str> {
+    let trimmed = body.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    let has_line = |prefixes: &[&str]| {
+        trimmed.lines().any(|line| {
+            let line = line.trim_start();
+            prefixes.iter().any(|p| line.starts_with(p))
+        })
+    };
+    if ((trimmed.starts_with('{') && trimmed.ends_with('}'))
+        || (trimmed.starts_with('[') && trimmed.ends_with(']')))
+        && serde_json::from_str::<serde_json::Value>(trimmed).is_ok()
+    {
+        return Some("json");
+    }
+    if has_line(&[
+        "fn ",
+        "pub fn ",
+        "impl ",
+        "use std::",
+        "let mut ",
+        "#[derive",
+    ]) || trimmed.contains("-> anyhow::Result")
+    {
+        return Some("rust");
+    }
+    if has_line(&["package main", "func "]) && trimmed.contains('{') {
+        return Some("go");
+    }
+    if has_line(&["def ", "from ", "elif ", "class "]) && trimmed.contains(':') {
+        return Some("python");
+    }
+    if (has_line(&["interface ", "type "]) && trimmed.contains(": "))
+        || trimmed.contains(": string")
+        || trimmed.contains(": number")
+    {
+        return Some("typescript");
+    }
+    if has_line(&["const ", "function ", "export ", "import "])
+        && (trimmed.contains("=>") || trimmed.contains(';') || trimmed.contains("require("))
+    {
+        return Some("javascript");
+    }
+    if has_line(&[
+        "#!/bin/", "$ ", "sudo ", "cargo ", "npm ", "git ", "cd ", "export ",
+    ]) {
+        return Some("shell");
+    }
+    let upper = trimmed.to_ascii_uppercase();
+    if (upper.starts_with("SELECT ") && upper.contains(" FROM "))
+        || upper.starts_with("CREATE TABLE")
+        || upper.starts_with("INSERT INTO")
+    {
+        return Some("sql");
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fence_tags_map_to_canonical_names() {
+        let content = "```rs\nlet x = 1;\n```\ntext\n```Python\nprint(1)\n```\n```ts\n```";
+        assert_eq!(
+            detect_code_languages(content),
+            vec!["python", "rust", "typescript"]
+        );
+        assert_eq!(canonical_language("golang"), Some("go"));
+        assert_eq!(canonical_language("klingon"), None);
+    }
+
+    #[test]
+    fn untagged_blocks_fall_back_to_heuristics() {
+        let content = "```\npub fn retry() -> anyhow::Result<()> {\n    Ok(())\n}\n```\n\
+                       ```\ndef retry(n):\n    return n\n```\n\
+                       ```\n$ cargo test\n```\n\
+                       ```\n{\"a\": 1}\n```\n\
+                       ```\nplain words only\n```";
+        assert_eq!(
+            detect_code_languages(content),
+            vec!["json", "python", "rust", "shell"]
+        );
+        assert!(detect_code_languages("no code here").is_empty());
+    }
+}
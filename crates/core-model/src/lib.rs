@@ -1,7 +1,19 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 use std::fmt;
+use std::path::{Path, PathBuf};
+
+mod graph;
+mod lang;
+mod links;
+mod tools;
+
+pub use graph::{Entity, EntityKind, GraphBuilder, RELATION_CO_OCCURS, RELATION_USES, error_types};
+pub use lang::{canonical_language, detect_code_languages};
+pub use links::{IssueRef, Link, extract_links, extract_refs};
+pub use tools::{CommandRun, FileMention, tool_commands, tool_file_mentions, tool_file_paths};
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum AgentKind {
@@ -11,9 +23,26 @@ pub enum AgentKind {
     Claude,
     Amp,
     Codex,
+    Gemini,
+    Aider,
+    // Sessions Remi writes itself, such as weekly rollups. There is no
+    // adapter for it, so it is not part of `ALL`.
+    Meta,
 }
 
 impl AgentKind {
+    // Every agent with a source adapter.
+    pub const ALL: [AgentKind; 8] = [
+        AgentKind::Pi,
+        AgentKind::Droid,
+        AgentKind::OpenCode,
+        AgentKind::Claude,
+        AgentKind::Amp,
+        AgentKind::Codex,
+        AgentKind::Gemini,
+        AgentKind::Aider,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             AgentKind::Pi => "pi",
@@ -22,6 +51,9 @@ impl AgentKind {
             AgentKind::Claude => "claude",
             AgentKind::Amp => "amp",
             AgentKind::Codex => "codex",
+            AgentKind::Gemini => "gemini",
+            AgentKind::Aider => "aider",
+            AgentKind::Meta => "meta",
         }
     }
 }
@@ -43,11 +75,58 @@ impl std::str::FromStr for AgentKind {
             "claude" => Ok(AgentKind::Claude),
             "amp" => Ok(AgentKind::Amp),
             "codex" => Ok(AgentKind::Codex),
+            "gemini" => Ok(AgentKind::Gemini),
+            "aider" => Ok(AgentKind::Aider),
+            "meta" => Ok(AgentKind::Meta),
             _ => anyhow::bail!("unknown agent kind: {s}"),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    User,
+    Assistant,
+    System,
+    Tool,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Assistant => "assistant",
+            Role::System => "system",
+            Role::Tool => "tool",
+        }
+    }
+
+    pub fn from_native(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "user" | "human" => Some(Role::User),
+            "assistant" | "model" | "ai" | "agent" | "bot" => Some(Role::Assistant),
+            "system" | "developer" => Some(Role::System),
+            "tool" | "toolresult" | "tool_result" | "function" | "tool_use" => Some(Role::Tool),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Role {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Role::from_native(s).ok_or_else(|| anyhow::anyhow!("unknown role: {s}"))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Session {
     pub id: String,
@@ -93,6 +172,19 @@ pub struct Provenance {
     pub agent: AgentKind,
     pub source_path: String,
     pub source_id: String,
+    // The directory the agent worked in, when the log records it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo: Option<RepoRef>,
+}
+
+// Stable identity for a workspace inside a git repository: the normalized
+// remote (`github.com/owner/name`) and the path relative to the repo root.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RepoRef {
+    pub remote: String,
+    pub path: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -134,6 +226,29 @@ pub struct NormalizedBatch {
     pub events: Vec<Event>,
     pub artifacts: Vec<Artifact>,
     pub provenance: Vec<Provenance>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub session_meta: Vec<SessionMeta>,
+}
+
+// Session-level data kept out of the message list, such as captured system prompts.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SessionMeta {
+    pub session_id: String,
+    pub meta: Value,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DirListing {
+    pub modified_at: DateTime<Utc>,
+    pub dirs: Vec<String>,
+    pub files: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ScanReport {
+    pub files_seen: usize,
+    pub files_skipped: usize,
+    pub parse_errors: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -142,9 +257,40 @@ pub enum ArchiveCapability {
     CentralizedCopy,
 }
 
+// Where adapters look for their sources. The CLI builds it from config and the
+// environment, so adapters never read the real home directory themselves.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceContext {
+    pub home: Option<PathBuf>,
+    pub overrides: HashMap<AgentKind, PathBuf>,
+    pub xdg_data_home: Option<PathBuf>,
+    pub app_data: Option<PathBuf>,
+    pub local_app_data: Option<PathBuf>,
+}
+
+impl SourceContext {
+    pub fn with_home(home: impl Into<PathBuf>) -> Self {
+        Self {
+            home: Some(home.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn home_for(&self, kind: AgentKind) -> Option<&Path> {
+        self.overrides
+            .get(&kind)
+            .or(self.home.as_ref())
+            .map(PathBuf::as_path)
+    }
+
+    pub fn is_overridden(&self, kind: AgentKind) -> bool {
+        self.overrides.contains_key(&kind)
+    }
+}
+
 pub trait AgentAdapter {
     fn kind(&self) -> AgentKind;
-    fn discover_source_paths(&self) -> anyhow::Result<Vec<String>>;
+    fn discover_source_paths(&self, ctx: &SourceContext) -> anyhow::Result<Vec<String>>;
     fn scan_changes_since(
         &self,
         source_paths: &[String],
@@ -153,6 +299,30 @@ pub trait AgentAdapter {
     fn normalize(&self, records: &[NativeRecord]) -> anyhow::Result<NormalizedBatch>;
     fn checkpoint_cursor(&self, records: &[NativeRecord]) -> Option<String>;
     fn archive_capability(&self) -> ArchiveCapability;
+
+    fn scan_report(&self) -> Option<ScanReport> {
+        None
+    }
+
+    fn source_label(&self) -> String {
+        self.kind().as_str().to_string()
+    }
+
+    // A cheap guess for onboarding; most agents keep one file per session.
+    fn estimate_sessions(&self, source_paths: &[String]) -> usize {
+        source_paths.len()
+    }
+
+    // Sources that must be scanned and normalized together because they can
+    // hold the same session. Sync saves whole groups, so a session is never
+    // split across two chunks.
+    fn scan_groups(&self, source_paths: &[String]) -> Vec<Vec<String>> {
+        source_paths.iter().map(|path| vec![path.clone()]).collect()
+    }
+
+    fn tracks_file_state(&self) -> bool {
+        true
+    }
 }
 
 pub fn deterministic_id(parts: &[&str]) -> String {
@@ -164,6 +334,37 @@ pub fn deterministic_id(parts: &[&str]) -> String {
     hasher.finalize().to_hex().to_string()
 }
 
+// Stamped on every JSON document the CLI prints or exports. Bump it when a
+// field is removed, renamed or changes type; adding fields keeps the version.
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+// Adapters write each reasoning block as one line starting with this prefix.
+pub const REASONING_PREFIX: &str = "reasoning: ";
+
+// Lowercased words of a search query, without FTS operators and punctuation.
+pub fn query_terms(query: &str) -> Vec<String> {
+    query
+        .split_whitespace()
+        .map(|raw| {
+            raw.trim_matches(|c: char| !c.is_alphanumeric() && c != '_')
+                .to_lowercase()
+        })
+        .filter(|term| term.chars().count() >= 2 && !matches!(term.as_str(), "and" | "or" | "not"))
+        .collect()
+}
+
+// Returns (reasoning, everything else) of normalized message content.
+pub fn split_reasoning(content: &str) -> (String, String) {
+    let (reasoning, rest): (Vec<&str>, Vec<&str>) = content
+        .lines()
+        .partition(|line| line.starts_with(REASONING_PREFIX));
+    let reasoning: Vec<&str> = reasoning
+        .into_iter()
+        .map(|line| &line[REASONING_PREFIX.len()..])
+        .collect();
+    (reasoning.join("\n"), rest.join("\n"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -197,5 +398,27 @@ mod tests {
         assert_eq!(AgentKind::Claude.as_str(), "claude");
         assert_eq!(AgentKind::Amp.as_str(), "amp");
         assert_eq!(AgentKind::Codex.as_str(), "codex");
+        assert_eq!(AgentKind::Gemini.as_str(), "gemini");
+        assert_eq!(AgentKind::Aider.as_str(), "aider");
+    }
+
+    #[test]
+    fn agent_kind_all_round_trips() {
+        for kind in AgentKind::ALL {
+            assert_eq!(kind.as_str().parse::<AgentKind>().unwrap(), kind);
+        }
+    }
+
+    #[test]
+    fn role_from_native_maps_aliases() {
+        assert_eq!(Role::from_native("User"), Some(Role::User));
+        assert_eq!(Role::from_native("human"), Some(Role::User));
+        assert_eq!(Role::from_native("model"), Some(Role::Assistant));
+        assert_eq!(Role::from_native("developer"), Some(Role::System));
+        assert_eq!(Role::from_native("toolResult"), Some(Role::Tool));
+        assert_eq!(Role::from_native("tool_result"), Some(Role::Tool));
+        assert_eq!(Role::from_native("narrator"), None);
+        assert_eq!("ASSISTANT".parse::<Role>().unwrap(), Role::Assistant);
+        assert!("narrator".parse::<Role>().is_err());
     }
 }
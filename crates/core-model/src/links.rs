@@ -0,0 +1,275 @@
+const SCHEMES: &[&str] = &["https://", "http://"];
+const CONTEXT_CHARS: usize = 60;
+const TRAILING: &[char] = &['.', ',', ';', ':', '!', '?', '\'', '"', '*', '_', '`'];
+const FENCE: &str = "```";
+const GITHUB: &str = "https://github.com/";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub url: String,
+    pub context: String,
+}
+
+// URLs end at whitespace or a quote/bracket delimiter; trailing sentence
+// punctuation and unbalanced closing parens (markdown links) are dropped.
+pub fn extract_links(content: &str) -> Vec<Link> {
+    let mut out: Vec<Link> = Vec::new();
+    let mut offset = 0;
+    while let Some((start, scheme)) = next_scheme(&content[offset..]) {
+        let start = offset + start;
+        let tail = &content[start..];
+        let end = tail
+            .find(|c: char| c.is_whitespace() || matches!(c, '<' | '>' | '"' | '`' | '|'))
+            .unwrap_or(tail.len());
+        let url = trim_url(&tail[..end]);
+        offset = start + end.max(scheme.len());
+        if url.len() <= scheme.len() || out.iter().any(|l| l.url == url) {
+            continue;
+        }
+        out.push(Link {
+            url: url.to_string(),
+            context: snippet(content, start, start + url.len()),
+        });
+    }
+    out
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IssueRef {
+    // `owner/name`, lowercased; `None` for a bare `#123`, which only has
+    // meaning relative to the session's own repository.
+    pub repo: Option<String>,
+    pub number: u64,
+}
+
+impl IssueRef {
+    // Accepts `owner/name#123`, `#123` and GitHub issue or pull request URLs.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if text.starts_with(GITHUB) {
+            return github_ref(text);
+        }
+        let (repo, number) = text.split_once('#')?;
+        let number = number.parse().ok().filter(|n| *n > 0)?;
+        if repo.is_empty() {
+            return Some(Self { repo: None, number });
+        }
+        valid_repo(repo).then(|| Self {
+            repo: Some(repo.to_ascii_lowercase()),
+            number,
+        })
+    }
+}
+
+impl std::fmt::Display for IssueRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}#{}", self.repo.as_deref().unwrap_or(""), self.number)
+    }
+}
+
+// Issue and pull request references: GitHub URLs anywhere, and `#123` or
+// `owner/name#123` in prose. Fenced code is skipped for the short forms,
+// where `#123` is more often a color or a comment than a ticket.
+pub fn extract_refs(content: &str) -> Vec<IssueRef> {
+    let mut out: Vec<IssueRef> = extract_links(content)
+        .iter()
+        .filter_map(|l| github_ref(&l.url))
+        .collect();
+    let mut in_fence = false;
+    for line in content.lines() {
+        if line.trim_start().starts_with(FENCE) {
+            in_fence = !in_fence;
+            continue;
+        }
+        if !in_fence {
+            short_refs(line, &mut out);
+        }
+    }
+    out.sort();
+    out.dedup();
+    out
+}
+
+fn short_refs(line: &str, out: &mut Vec<IssueRef>) {
+    let repo_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-' | '/');
+    for (hash, _) in line.match_indices('#') {
+        let after = &line[hash + 1..];
+        let digits = after
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(after.len());
+        if digits == 0 || after[digits..].starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+            continue;
+        }
+        let Some(number) = after[..digits].parse().ok().filter(|n| *n > 0) else {
+            continue;
+        };
+        let before = &line[..hash];
+        let start = before
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| repo_char(*c))
+            .last()
+            .map_or(hash, |(i, _)| i);
+        let repo = &before[start..];
+        let lead = before[..start].chars().next_back();
+        if lead.is_some_and(|c| !c.is_whitespace() && !"([{,;:\"'*".contains(c)) {
+            continue;
+        }
+        if repo.is_empty() {
+            out.push(IssueRef { repo: None, number });
+        } else if valid_repo(repo) {
+            out.push(IssueRef {
+                repo: Some(repo.to_ascii_lowercase()),
+                number,
+            });
+        }
+    }
+}
+
+fn valid_repo(repo: &str) -> bool {
+    let mut parts = repo.split('/');
+    let (Some(owner), Some(name), None) = (parts.next(), parts.next(), parts.next()) else {
+        return false;
+    };
+    let part = |p: &str| {
+        !p.is_empty()
+            && !p.starts_with('.')
+            && p.chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+    };
+    part(owner) && part(name)
+}
+
+fn github_ref(url: &str) -> Option<IssueRef> {
+    let path = url.strip_prefix(GITHUB)?;
+    let mut parts = path.split(['/', '#', '?']);
+    let (owner, name, kind, number) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+    if !matches!(kind, "issues" | "pull") {
+        return None;
+    }
+    let repo = format!("{owner}/{name}");
+    Some(IssueRef {
+        repo: valid_repo(&repo).then(|| repo.to_ascii_lowercase()),
+        number: number.parse().ok().filter(|n| *n > 0)?,
+    })
+    .filter(|r| r.repo.is_some())
+}
+
+fn next_scheme(text: &str) -> Option<(usize, &'static str)> {
+    SCHEMES
+        .iter()
+        .filter_map(|scheme| text.find(scheme).map(|i| (i, *scheme)))
+        .min_by_key(|(i, _)| *i)
+}
+
+fn trim_url(url: &str) -> &str {
+    let mut url = url;
+    loop {
+        let trimmed = url.trim_end_matches(TRAILING);
+        let trimmed = match trimmed.chars().last() {
+            Some(close @ (')' | ']' | '}')) => {
+                let open = match close {
+                    ')' => '(',
+                    ']' => '[',
+                    _ => '{',
+                };
+                if trimmed.matches(close).count() > trimmed.matches(open).count() {
+                    &trimmed[..trimmed.len() - 1]
+                } else {
+                    trimmed
+                }
+            }
+            _ => trimmed,
+        };
+        if trimmed.len() == url.len() {
+            return url;
+        }
+        url = trimmed;
+    }
+}
+
+fn snippet(content: &str, start: usize, end: usize) -> String {
+    let before: String = {
+        let mut chars: Vec<char> = content[..start].chars().rev().take(CONTEXT_CHARS).collect();
+        chars.reverse();
+        chars.into_iter().collect()
+    };
+    let after: String = content[end..].chars().take(CONTEXT_CHARS).collect();
+    format!("{before}{}{after}", &content[start..end])
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls(content: &str) -> Vec<String> {
+        extract_links(content).into_iter().map(|l| l.url).collect()
+    }
+
+    #[test]
+    fn urls_are_trimmed_and_deduplicated() {
+        let content = "See https://docs.rs/tokio/latest/tokio/.\n\
+            The [guide](https://example.com/guide) and (https://en.wikipedia.org/wiki/Rust_(programming_language)).\n\
+            Again: https://docs.rs/tokio/latest/tokio/ and \"http://localhost:8080/api?q=1\"";
+        assert_eq!(
+            urls(content),
+            vec![
+                "https://docs.rs/tokio/latest/tokio/",
+                "https://example.com/guide",
+                "https://en.wikipedia.org/wiki/Rust_(programming_language)",
+                "http://localhost:8080/api?q=1",
+            ]
+        );
+        assert!(urls("no links, just https:// alone").is_empty());
+    }
+
+    #[test]
+    fn refs_come_from_urls_and_short_forms() {
+        let content = "Fixes #42 and lsj5031/Remi#7, see https://github.com/Tokio-RS/tokio/pull/5123.\n\
+            Also (#9), https://github.com/org/repo/issues/3#issuecomment-1 and org/repo/blob#1.\n\
+            ```css\ncolor: #123456;\n```\n\
+            Not refs: #0, #12abc, a#5, https://example.com/page#8, #[derive].";
+        let found: Vec<String> = extract_refs(content)
+            .iter()
+            .map(|r| r.to_string())
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                "#9",
+                "#42",
+                "lsj5031/remi#7",
+                "org/repo#3",
+                "tokio-rs/tokio#5123"
+            ]
+        );
+    }
+
+    #[test]
+    fn refs_parse_from_cli_arguments() {
+        let parsed = IssueRef::parse("Org/Repo#123").unwrap();
+        assert_eq!(parsed.repo.as_deref(), Some("org/repo"));
+        assert_eq!(parsed.number, 123);
+        assert_eq!(IssueRef::parse("#5").unwrap().repo, None);
+        assert_eq!(
+            IssueRef::parse("https://github.com/a/b/pull/8")
+                .unwrap()
+                .to_string(),
+            "a/b#8"
+        );
+        assert!(IssueRef::parse("org/repo").is_none());
+        assert!(IssueRef::parse("a/b/c#1").is_none());
+    }
+
+    #[test]
+    fn context_is_a_collapsed_window_around_the_url() {
+        let links = extract_links("Read the\n  migration notes at https://example.com/v2 first.");
+        assert_eq!(
+            links[0].context,
+            "Read the migration notes at https://example.com/v2 first."
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use serde_json::Value;
+
+pub(crate) const TOOL_USE_PREFIX: &str = "tool_use:";
+const PATH_KEYS: &[&str] = &["path", "file_path", "filePath", "filename", "notebook_path"];
+const PATCH_HEADERS: &[&str] = &["*** Update File: ", "*** Add File: ", "*** Delete File: "];
+const SHELL_TOOLS: &[&str] = &[
+    "bash",
+    "shell",
+    "shell_command",
+    "exec_command",
+    "local_shell",
+    "run_shell_command",
+    "run_terminal_cmd",
+    "execute_command",
+    "terminal",
+];
+const SHELL_WRAPPERS: &[&str] = &["bash", "sh", "zsh", "/bin/bash", "/bin/sh", "/bin/zsh"];
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FileMention {
+    pub tool: String,
+    pub path: String,
+}
+
+// Paths come from tool call arguments (`path`, `file_path`, ...) and from
+// the file headers of apply_patch payloads, which some agents pass as JSON
+// (`patchText`, `input`) and others as the raw patch on the following lines.
+pub fn tool_file_mentions(content: &str) -> Vec<FileMention> {
+    let mut out = Vec::new();
+    let mut lines = content.lines().peekable();
+    while let Some(line) = lines.next() {
+        let Some(rest) = line.trim().strip_prefix(TOOL_USE_PREFIX) else {
+            continue;
+        };
+        let rest = rest.trim();
+        let (tool, params) = rest.split_once(' ').unwrap_or((rest, ""));
+        if tool.is_empty() || tool.starts_with('{') {
+            continue;
+        }
+        let mut push = |path: &str| {
+            let path = path.trim();
+            let path = path.strip_prefix("./").unwrap_or(path);
+            if !path.is_empty() {
+                out.push(FileMention {
+                    tool: tool.to_string(),
+                    path: path.to_string(),
+                });
+            }
+        };
+        match serde_json::from_str::<Value>(params.trim()) {
+            Ok(Value::Object(map)) => {
+                for key in PATH_KEYS {
+                    if let Some(path) = map.get(*key).and_then(Value::as_str) {
+                        push(path);
+                    }
+                }
+                for key in ["patchText", "patch", "input"] {
+                    if let Some(patch) = map.get(key).and_then(Value::as_str) {
+                        patch.lines().filter_map(patch_header).for_each(&mut push);
+                    }
+                }
+            }
+            _ => {
+                if let Some(path) = patch_header(params.trim()) {
+                    push(path);
+                }
+                while let Some(next) = lines.peek() {
+                    if next.trim_start().starts_with(TOOL_USE_PREFIX)
+                        || next.trim_start().starts_with("tool_result:")
+                    {
+                        break;
+                    }
+                    if let Some(path) = patch_header(next) {
+                        push(path);
+                    }
+                    lines.next();
+                }
+            }
+        }
+    }
+    out.sort();
+    out.dedup();
+    out
+}
+
+pub fn tool_file_paths(content: &str) -> Vec<String> {
+    let mut paths: Vec<String> = tool_file_mentions(content)
+        .into_iter()
+        .map(|m| m.path)
+        .collect();
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CommandRun {
+    pub tool: String,
+    pub command: String,
+}
+
+// Shell tools pass the command as a string or as an argv array; arrays that
+// just wrap a script in `bash -lc` are unwrapped to the script.
+pub fn tool_commands(content: &str) -> Vec<CommandRun> {
+    let mut out = Vec::new();
+    for line in content.lines() {
+        let Some(rest) = line.trim().strip_prefix(TOOL_USE_PREFIX) else {
+            continue;
+        };
+        let rest = rest.trim();
+        let (tool, params) = rest.split_once(' ').unwrap_or((rest, ""));
+        if !SHELL_TOOLS.contains(&tool.to_ascii_lowercase().as_str()) {
+            continue;
+        }
+        let command = match serde_json::from_str::<Value>(params.trim()) {
+            Ok(Value::Object(map)) => map
+                .get("command")
+                .or_else(|| map.get("cmd"))
+                .and_then(command_text),
+            Ok(_) => None,
+            Err(_) => Some(params.trim().to_string()),
+        };
+        if let Some(command) = command.filter(|c| !c.is_empty()) {
+            out.push(CommandRun {
+                tool: tool.to_string(),
+                command,
+            });
+        }
+    }
+    out.sort();
+    out.dedup();
+    out
+}
+
+fn command_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.trim().to_string()),
+        Value::Array(items) => {
+            let argv: Vec<&str> = items.iter().filter_map(Value::as_str).collect();
+            match argv.as_slice() {
+                [shell, flag, script]
+                    if SHELL_WRAPPERS.contains(shell)
+                        && flag.starts_with('-')
+                        && flag.ends_with('c') =>
+                {
+                    Some(script.trim().to_string())
+                }
+                _ => Some(argv.join(" ")),
+            }
+        }
+        _ => None,
+    }
+}
+
+fn patch_header(line: &str) -> Option<&str> {
+    PATCH_HEADERS
+        .iter()
+        .find_map(|prefix| line.strip_prefix(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mention(tool: &str, path: &str) -> FileMention {
+        FileMention {
+            tool: tool.to_string(),
+            path: path.to_string(),
+        }
+    }
+
+    #[test]
+    fn paths_come_from_arguments_and_patch_headers() {
+        let content = [
+            "Let me look.",
+            r#"tool_use: Read {"file_path":"./src/lib.rs"}"#,
+            "tool_result: fn main() {}",
+            r#"tool_use: Edit {"file_path":"src/lib.rs","old_string":"*** Update File: nope"}"#,
+            r#"tool_use: apply_patch {"patchText":"*** Begin Patch\n*** Add File: docs/a.md\n+hi\n*** Delete File: old.txt"}"#,
+            "tool_use: apply_patch *** Begin Patch",
+            "*** Update File: crates/cli/src/main.rs",
+            "@@",
+            "tool_use: bash {\"command\":\"cat README.md\"}",
+            "tool_use: {\"path\":\"unnamed.rs\"}",
+        ]
+        .join("\n");
+        assert_eq!(
+            tool_file_mentions(&content),
+            vec![
+                mention("Edit", "src/lib.rs"),
+                mention("Read", "src/lib.rs"),
+                mention("apply_patch", "crates/cli/src/main.rs"),
+                mention("apply_patch", "docs/a.md"),
+                mention("apply_patch", "old.txt"),
+            ]
+        );
+        assert_eq!(
+            tool_file_paths(&content),
+            vec![
+                "crates/cli/src/main.rs",
+                "docs/a.md",
+                "old.txt",
+                "src/lib.rs"
+            ]
+        );
+        assert!(tool_file_mentions("plain *** Update File: x.rs").is_empty());
+    }
+
+    #[test]
+    fn commands_come_from_shell_tools() {
+        let content = [
+            r#"tool_use: bash {"command":"cargo nextest run"}"#,
+            r#"tool_use: shell {"command":["bash","-lc","git status"]}"#,
+            r#"tool_use: exec_command {"cmd":["rg","-n","TODO"]}"#,
+            "tool_use: Bash ls -la",
+            r#"tool_use: str_replace_editor {"command":"view"}"#,
+            r#"tool_use: bash {"command":"cargo nextest run"}"#,
+            r#"tool_use: bash {"description":"no command"}"#,
+        ]
+        .join("\n");
+        let commands: Vec<(String, String)> = tool_commands(&content)
+            .into_iter()
+            .map(|c| (c.tool, c.command))
+            .collect();
+        assert_eq!(
+            commands,
+            vec![
+                ("Bash".to_string(), "ls -la".to_string()),
+                ("bash".to_string(), "cargo nextest run".to_string()),
+                ("exec_command".to_string(), "rg -n TODO".to_string()),
+                ("shell".to_string(), "git status".to_string()),
+            ]
+        );
+    }
+}
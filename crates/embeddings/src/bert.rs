@@ -0,0 +1,268 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::Context;
+use candle_core::{DType, Device, Tensor};
+use candle_nn::VarBuilder;
+use candle_transformers::models::bert::{self, Config};
+use ndarray::Array2;
+
+// A BERT encoder run in-process by candle, for safetensors checkpoints such
+// as bge-small and all-MiniLM.
+pub struct BertModel {
+    model: bert::BertModel,
+    config: Config,
+}
+
+impl BertModel {
+    pub fn load(model_dir: &Path) -> anyhow::Result<Self> {
+        let config_path = model_dir.join("config.json");
+        let config: serde_json::Value = serde_json::from_slice(
+            &std::fs::read(&config_path)
+                .with_context(|| format!("config.json not found in {}", model_dir.display()))?,
+        )
+        .with_context(|| format!("invalid {}", config_path.display()))?;
+        let weights_path = model_dir.join("model.safetensors");
+        let weights = std::fs::read(&weights_path)
+            .with_context(|| format!("failed to read {}", weights_path.display()))?;
+        let tensors = candle_core::safetensors::load_buffer(&weights, &Device::Cpu)
+            .with_context(|| format!("invalid {}", weights_path.display()))?;
+        Self::from_tensors(config, tensors)
+    }
+
+    pub fn from_tensors(
+        config: serde_json::Value,
+        tensors: HashMap<String, Tensor>,
+    ) -> anyhow::Result<Self> {
+        let model_type = config["model_type"].as_str().unwrap_or("bert");
+        if model_type != "bert" {
+            anyhow::bail!(
+                "model_type {model_type:?} is not supported by the native backend; use backend = \"onnx\""
+            );
+        }
+        let config: Config =
+            serde_json::from_value(config).context("config.json is not a BERT config")?;
+        if config.num_attention_heads == 0
+            || !config
+                .hidden_size
+                .is_multiple_of(config.num_attention_heads)
+        {
+            anyhow::bail!(
+                "hidden_size {} is not divisible by {} attention heads",
+                config.hidden_size,
+                config.num_attention_heads
+            );
+        }
+        // Older checkpoints name layer norm parameters gamma and beta.
+        let tensors = tensors
+            .into_iter()
+            .map(|(name, tensor)| {
+                let name = match name.rsplit_once('.') {
+                    Some((prefix, "gamma")) => format!("{prefix}.weight"),
+                    Some((prefix, "beta")) => format!("{prefix}.bias"),
+                    _ => name,
+                };
+                (name, tensor)
+            })
+            .collect();
+        let vb = VarBuilder::from_tensors(tensors, DType::F32, &Device::Cpu);
+        let model = bert::BertModel::load(vb, &config).context("failed to load BERT weights")?;
+        Ok(Self { model, config })
+    }
+
+    pub fn max_positions(&self) -> usize {
+        self.config.max_position_embeddings
+    }
+
+    // Returns the last hidden state, one row per token.
+    pub fn forward(&self, input_ids: &[u32], type_ids: &[u32]) -> anyhow::Result<Array2<f32>> {
+        let seq = input_ids.len().min(self.max_positions());
+        let input_ids = &input_ids[..seq];
+        let type_ids: Vec<u32> = (0..seq)
+            .map(|pos| type_ids.get(pos).copied().unwrap_or(0))
+            .collect();
+        for (&id, &type_id) in input_ids.iter().zip(&type_ids) {
+            if id as usize >= self.config.vocab_size
+                || type_id as usize >= self.config.type_vocab_size
+            {
+                anyhow::bail!("token id {id} (type {type_id}) is outside the model vocabulary");
+            }
+        }
+        let ids = Tensor::new(input_ids, &Device::Cpu)?.unsqueeze(0)?;
+        let types = Tensor::new(type_ids.as_slice(), &Device::Cpu)?.unsqueeze(0)?;
+        let hidden = self.model.forward(&ids, &types, None)?.squeeze(0)?;
+        let cols = hidden.dim(1)?;
+        let data = hidden.flatten_all()?.to_vec1::<f32>()?;
+        Ok(Array2::from_shape_vec((seq, cols), data)?)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) const VOCAB: usize = 12;
+    pub(crate) const HIDDEN: usize = 8;
+
+    // Deterministic small weights; layer norms start at identity so their
+    // effect on the output is easy to check. The embedding norm uses the
+    // legacy gamma/beta names.
+    pub(crate) fn tiny_checkpoint(prefix: &str) -> (serde_json::Value, HashMap<String, Tensor>) {
+        let mut seed = 7u32;
+        let mut values = |n: usize| -> Vec<f32> {
+            (0..n)
+                .map(|_| {
+                    seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                    ((seed >> 16) % 200) as f32 / 1000.0 - 0.1
+                })
+                .collect()
+        };
+        let mut tensors: Vec<(String, Vec<usize>, Vec<f32>)> = vec![
+            (
+                "embeddings.word_embeddings.weight".into(),
+                vec![VOCAB, HIDDEN],
+                values(VOCAB * HIDDEN),
+            ),
+            (
+                "embeddings.position_embeddings.weight".into(),
+                vec![16, HIDDEN],
+                values(16 * HIDDEN),
+            ),
+            (
+                "embeddings.token_type_embeddings.weight".into(),
+                vec![2, HIDDEN],
+                values(2 * HIDDEN),
+            ),
+            (
+                "embeddings.LayerNorm.gamma".into(),
+                vec![HIDDEN],
+                vec![1.0; HIDDEN],
+            ),
+            (
+                "embeddings.LayerNorm.beta".into(),
+                vec![HIDDEN],
+                vec![0.0; HIDDEN],
+            ),
+        ];
+        let layer = "encoder.layer.0";
+        for (name, out, inp) in [
+            ("attention.self.query", HIDDEN, HIDDEN),
+            ("attention.self.key", HIDDEN, HIDDEN),
+            ("attention.self.value", HIDDEN, HIDDEN),
+            ("attention.output.dense", HIDDEN, HIDDEN),
+            ("intermediate.dense", 16, HIDDEN),
+            ("output.dense", HIDDEN, 16),
+        ] {
+            tensors.push((
+                format!("{layer}.{name}.weight"),
+                vec![out, inp],
+                values(out * inp),
+            ));
+            tensors.push((format!("{layer}.{name}.bias"), vec![out], values(out)));
+        }
+        for norm in ["attention.output.LayerNorm", "output.LayerNorm"] {
+            tensors.push((
+                format!("{layer}.{norm}.weight"),
+                vec![HIDDEN],
+                vec![1.0; HIDDEN],
+            ));
+            tensors.push((
+                format!("{layer}.{norm}.bias"),
+                vec![HIDDEN],
+                vec![0.0; HIDDEN],
+            ));
+        }
+        let tensors = tensors
+            .into_iter()
+            .map(|(name, shape, data)| {
+                let tensor = Tensor::from_vec(data, shape, &Device::Cpu).unwrap();
+                (format!("{prefix}{name}"), tensor)
+            })
+            .collect();
+        let config = serde_json::json!({
+            "model_type": "bert",
+            "hidden_act": "gelu",
+            "vocab_size": VOCAB,
+            "hidden_size": HIDDEN,
+            "num_attention_heads": 2,
+            "num_hidden_layers": 1,
+            "intermediate_size": 16,
+            "hidden_dropout_prob": 0.1,
+            "max_position_embeddings": 16,
+            "type_vocab_size": 2,
+            "initializer_range": 0.02,
+            "layer_norm_eps": 1e-12,
+            "pad_token_id": 0,
+        });
+        (config, tensors)
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f32], tolerance: f32) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < tolerance, "{actual:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn forward_yields_normalized_rows_per_token() {
+        for prefix in ["", "bert."] {
+            let (config, tensors) = tiny_checkpoint(prefix);
+            let model = BertModel::from_tensors(config, tensors).unwrap();
+            let out = model.forward(&[1, 5, 3, 2], &[0, 0, 0, 0]).unwrap();
+            assert_eq!(out.dim(), (4, HIDDEN));
+            for row in out.rows() {
+                assert!(row.iter().all(|v| v.is_finite()));
+                assert!(row.mean().unwrap().abs() < 1e-4);
+            }
+            assert_eq!(out, model.forward(&[1, 5, 3, 2], &[]).unwrap());
+            assert_ne!(
+                out.row(0),
+                model.forward(&[1, 6, 3, 2], &[]).unwrap().row(0),
+                "attention should mix later tokens into the first"
+            );
+            assert_eq!(model.forward(&[0; 40], &[]).unwrap().nrows(), 16);
+            assert!(model.forward(&[VOCAB as u32], &[]).is_err());
+        }
+    }
+
+    // Reference rows come from a plain float64 BERT forward pass (erf GELU,
+    // per-head softmax attention, eps 1e-12 layer norms) over the same
+    // weights, written independently of candle.
+    #[test]
+    fn forward_matches_reference_vectors() {
+        let (config, tensors) = tiny_checkpoint("");
+        let model = BertModel::from_tensors(config, tensors).unwrap();
+        let out = model.forward(&[1, 5, 3, 2], &[]).unwrap();
+        let first = [
+            1.386374, 0.299512, -2.291642, 0.737725, -0.091083, -0.253237, -0.114538, 0.326889,
+        ];
+        let last = [
+            1.483715, 1.00067, -0.138291, -0.912633, 1.078717, -0.994807, -1.324701, -0.192669,
+        ];
+        assert_close(out.row(0).as_slice().unwrap(), &first, 1e-4);
+        assert_close(out.row(3).as_slice().unwrap(), &last, 1e-4);
+
+        // Half-precision checkpoints are widened to f32 on load.
+        let (config, tensors) = tiny_checkpoint("");
+        let half = tensors
+            .into_iter()
+            .map(|(name, t)| (name, t.to_dtype(DType::F16).unwrap()))
+            .collect();
+        let model = BertModel::from_tensors(config, half).unwrap();
+        let out = model.forward(&[1, 5, 3, 2], &[]).unwrap();
+        assert_close(out.row(0).as_slice().unwrap(), &first, 2e-2);
+    }
+
+    #[test]
+    fn rejects_unsupported_architectures() {
+        let (mut config, tensors) = tiny_checkpoint("");
+        config["model_type"] = "xlm-roberta".into();
+        assert!(BertModel::from_tensors(config, tensors).is_err());
+        let (mut config, tensors) = tiny_checkpoint("");
+        config["hidden_size"] = 9.into();
+        assert!(BertModel::from_tensors(config, tensors).is_err());
+        let (mut config, tensors) = tiny_checkpoint("");
+        config["hidden_size"] = 16.into();
+        assert!(BertModel::from_tensors(config, tensors).is_err());
+    }
+}
@@ -1,21 +1,96 @@
-use std::path::Path;
+mod bert;
+mod rerank;
 
-use ndarray::Array;
+use std::{path::Path, str::FromStr};
+
+use ndarray::{Array, Array2};
 use ort::session::{Session, builder::GraphOptimizationLevel};
 use ort::value::Value;
 use tokenizers::Tokenizer;
 
+use crate::bert::BertModel;
+
+pub use crate::rerank::Reranker;
+
+// Intra-op threads for onnxruntime sessions when none are configured.
+pub const DEFAULT_THREADS: usize = 4;
+
 #[derive(Debug, Clone, Copy)]
 pub enum PoolingMode {
     Mean,
     Cls,
 }
 
+// `Native` runs safetensors BERT checkpoints in-process, for machines where
+// the onnxruntime shared library cannot be installed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Backend {
+    #[default]
+    Auto,
+    Onnx,
+    Native,
+}
+
+impl Backend {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Backend::Auto => "auto",
+            Backend::Onnx => "onnx",
+            Backend::Native => "native",
+        }
+    }
+
+    // Auto prefers ONNX when the model directory ships both formats.
+    fn resolve(self, model_dir: &Path) -> anyhow::Result<Self> {
+        let (onnx, native) = (
+            model_dir.join("model.onnx"),
+            model_dir.join("model.safetensors"),
+        );
+        match self {
+            Backend::Auto if onnx.exists() => Ok(Backend::Onnx),
+            Backend::Auto if native.exists() => Ok(Backend::Native),
+            Backend::Auto => anyhow::bail!(
+                "neither model.onnx nor model.safetensors found in {}",
+                model_dir.display()
+            ),
+            Backend::Onnx if !onnx.exists() => {
+                anyhow::bail!("model.onnx not found in {}", model_dir.display())
+            }
+            Backend::Native if !native.exists() => {
+                anyhow::bail!("model.safetensors not found in {}", model_dir.display())
+            }
+            resolved => Ok(resolved),
+        }
+    }
+}
+
+impl FromStr for Backend {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(Backend::Auto),
+            "onnx" => Ok(Backend::Onnx),
+            "native" => Ok(Backend::Native),
+            other => anyhow::bail!(
+                "unknown embedding backend `{other}` (expected auto, onnx, or native)"
+            ),
+        }
+    }
+}
+
+enum Model {
+    Onnx(Session),
+    Native(Box<BertModel>),
+}
+
 pub struct Embedder {
     tokenizer: Tokenizer,
-    session: Session,
+    model: Model,
     pooling: PoolingMode,
     query_prefix: Option<String>,
+    model_version: String,
+    model_id: String,
 }
 
 impl Embedder {
@@ -23,6 +98,16 @@ impl Embedder {
         model_dir: impl AsRef<Path>,
         pooling: Option<&str>,
         query_prefix: Option<&str>,
+    ) -> anyhow::Result<Self> {
+        Self::with_backend(model_dir, Backend::Auto, pooling, query_prefix, None)
+    }
+
+    pub fn with_backend(
+        model_dir: impl AsRef<Path>,
+        backend: Backend,
+        pooling: Option<&str>,
+        query_prefix: Option<&str>,
+        threads: Option<usize>,
     ) -> anyhow::Result<Self> {
         let model_dir = model_dir.as_ref();
         let pooling = match pooling.unwrap_or("mean").to_lowercase().as_str() {
@@ -32,31 +117,61 @@ impl Embedder {
         let query_prefix = query_prefix.map(|s| s.to_string());
 
         let tokenizer_path = model_dir.join("tokenizer.json");
-        let model_path = model_dir.join("model.onnx");
 
         if !tokenizer_path.exists() {
             anyhow::bail!("tokenizer.json not found in {}", model_dir.display());
         }
-        if !model_path.exists() {
-            anyhow::bail!("model.onnx not found in {}", model_dir.display());
-        }
+        let backend = backend.resolve(model_dir)?;
 
         let tokenizer = Tokenizer::from_file(&tokenizer_path)
             .map_err(|e| anyhow::anyhow!("failed to load tokenizer: {}", e))?;
 
-        let session = Session::builder()?
-            .with_optimization_level(GraphOptimizationLevel::Level3)?
-            .with_intra_threads(4)?
-            .commit_from_file(model_path)?;
+        let weights = model_dir.join(match backend {
+            Backend::Native => "model.safetensors",
+            _ => "model.onnx",
+        });
+        let model_version = model_version(&weights, backend, pooling, query_prefix.as_deref());
+        let model_id = format!("{}:{}:{pooling:?}", backend.as_str(), weights.display());
+
+        let model = match backend {
+            Backend::Native => Model::Native(Box::new(BertModel::load(model_dir)?)),
+            _ => Model::Onnx(
+                Session::builder()?
+                    .with_optimization_level(GraphOptimizationLevel::Level3)?
+                    .with_intra_threads(threads.unwrap_or(DEFAULT_THREADS))?
+                    .commit_from_file(&weights)?,
+            ),
+        };
 
         Ok(Self {
             tokenizer,
-            session,
+            model,
             pooling,
             query_prefix,
+            model_version,
+            model_id,
         })
     }
 
+    // Identifies everything that shapes a query vector, so persisted query
+    // embeddings are not reused after the model or its settings change.
+    pub fn model_version(&self) -> &str {
+        &self.model_version
+    }
+
+    // Names the backend, weights and pooling behind document vectors. It is
+    // stored with each vector so ONNX and native vectors are never compared.
+    pub fn model_id(&self) -> &str {
+        &self.model_id
+    }
+
+    pub fn backend(&self) -> Backend {
+        match self.model {
+            Model::Onnx(_) => Backend::Onnx,
+            Model::Native(_) => Backend::Native,
+        }
+    }
+
     pub fn embed(&mut self, text: &str, is_query: bool) -> anyhow::Result<Vec<f32>> {
         let text_cow = if is_query {
             if let Some(prefix) = &self.query_prefix {
@@ -73,53 +188,14 @@ impl Embedder {
             .encode(text_cow.as_ref(), true)
             .map_err(|e| anyhow::anyhow!("encoding error: {}", e))?;
 
-        let input_ids: Vec<i64> = encoding.get_ids().iter().map(|&x| x as i64).collect();
-        let attention_mask: Vec<i64> = encoding
-            .get_attention_mask()
-            .iter()
-            .map(|&x| x as i64)
-            .collect();
-        let token_type_ids: Vec<i64> = encoding.get_type_ids().iter().map(|&x| x as i64).collect();
-
-        let batch_size = 1;
-        let seq_len = input_ids.len();
-
-        let input_ids_array = Array::from_shape_vec((batch_size, seq_len), input_ids)?;
-        let attention_mask_array = Array::from_shape_vec((batch_size, seq_len), attention_mask)?;
-        let token_type_ids_array = Array::from_shape_vec((batch_size, seq_len), token_type_ids)?;
-
-        let input_ids_val = Value::from_array(input_ids_array)?;
-        let attention_mask_val = Value::from_array(attention_mask_array)?;
-        let token_type_ids_val = Value::from_array(token_type_ids_array)?;
-
-        let has_token_type_ids = self
-            .session
-            .inputs()
-            .iter()
-            .any(|i| i.name() == "token_type_ids");
-
-        let outputs = if has_token_type_ids {
-            self.session.run(ort::inputs![
-                "input_ids" => input_ids_val,
-                "attention_mask" => attention_mask_val,
-                "token_type_ids" => token_type_ids_val,
-            ])?
-        } else {
-            self.session.run(ort::inputs![
-                "input_ids" => input_ids_val,
-                "attention_mask" => attention_mask_val,
-            ])?
+        let hidden_states = match &mut self.model {
+            Model::Onnx(session) => run_onnx(session, &encoding)?,
+            Model::Native(model) => model.forward(encoding.get_ids(), encoding.get_type_ids())?,
         };
-
-        // Extract last_hidden_state (batch, seq_len, hidden_size)
-        // Usually output 0 is last_hidden_state
-        let (shape, data) = outputs[0].try_extract_tensor::<f32>()?;
-
-        let batch = shape[0] as usize;
-        let seq = shape[1] as usize;
-        let hidden = shape[2] as usize;
-
-        assert_eq!(batch, 1);
+        let (seq, hidden) = hidden_states.dim();
+        let data = hidden_states
+            .as_slice()
+            .ok_or_else(|| anyhow::anyhow!("hidden states are not contiguous"))?;
 
         let mut pooled = vec![0.0f32; hidden];
 
@@ -158,3 +234,150 @@ impl Embedder {
         Ok(pooled)
     }
 }
+
+fn model_version(
+    weights: &Path,
+    backend: Backend,
+    pooling: PoolingMode,
+    query_prefix: Option<&str>,
+) -> String {
+    let (len, modified) = std::fs::metadata(weights)
+        .map(|meta| {
+            let modified = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_secs());
+            (meta.len(), modified)
+        })
+        .unwrap_or_default();
+    format!(
+        "{}:{}:{len}:{modified}:{pooling:?}:{}",
+        backend.as_str(),
+        weights.display(),
+        query_prefix.unwrap_or_default()
+    )
+}
+
+fn run_onnx(session: &mut Session, encoding: &tokenizers::Encoding) -> anyhow::Result<Array2<f32>> {
+    let input_ids: Vec<i64> = encoding.get_ids().iter().map(|&x| x as i64).collect();
+    let attention_mask: Vec<i64> = encoding
+        .get_attention_mask()
+        .iter()
+        .map(|&x| x as i64)
+        .collect();
+    let token_type_ids: Vec<i64> = encoding.get_type_ids().iter().map(|&x| x as i64).collect();
+
+    let batch_size = 1;
+    let seq_len = input_ids.len();
+
+    let input_ids_array = Array::from_shape_vec((batch_size, seq_len), input_ids)?;
+    let attention_mask_array = Array::from_shape_vec((batch_size, seq_len), attention_mask)?;
+    let token_type_ids_array = Array::from_shape_vec((batch_size, seq_len), token_type_ids)?;
+
+    let input_ids_val = Value::from_array(input_ids_array)?;
+    let attention_mask_val = Value::from_array(attention_mask_array)?;
+    let token_type_ids_val = Value::from_array(token_type_ids_array)?;
+
+    let has_token_type_ids = session
+        .inputs()
+        .iter()
+        .any(|i| i.name() == "token_type_ids");
+
+    let outputs = if has_token_type_ids {
+        session.run(ort::inputs![
+            "input_ids" => input_ids_val,
+            "attention_mask" => attention_mask_val,
+            "token_type_ids" => token_type_ids_val,
+        ])?
+    } else {
+        session.run(ort::inputs![
+            "input_ids" => input_ids_val,
+            "attention_mask" => attention_mask_val,
+        ])?
+    };
+
+    // Extract last_hidden_state (batch, seq_len, hidden_size)
+    // Usually output 0 is last_hidden_state
+    let (shape, data) = outputs[0].try_extract_tensor::<f32>()?;
+
+    let batch = shape[0] as usize;
+    let seq = shape[1] as usize;
+    let hidden = shape[2] as usize;
+
+    assert_eq!(batch, 1);
+
+    Ok(Array2::from_shape_vec((seq, hidden), data.to_vec())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokenizers::models::wordlevel::WordLevel;
+    use tokenizers::pre_tokenizers::whitespace::Whitespace;
+
+    fn native_model_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("remi-embed-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let (config, tensors) = bert::tests::tiny_checkpoint("");
+        std::fs::write(dir.join("config.json"), config.to_string()).unwrap();
+        candle_core::safetensors::save(&tensors, dir.join("model.safetensors")).unwrap();
+        let vocab = ["[UNK]", "hello", "world", "query:", "rust", "agents"]
+            .iter()
+            .enumerate()
+            .map(|(i, w)| (w.to_string(), i as u32))
+            .collect();
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("[UNK]".into())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(Some(Whitespace {}));
+        tokenizer.save(dir.join("tokenizer.json"), false).unwrap();
+        dir
+    }
+
+    #[test]
+    fn native_backend_embeds_safetensors_models() {
+        let dir = native_model_dir("native");
+        assert!(Embedder::with_backend(&dir, Backend::Onnx, None, None, None).is_err());
+
+        let mut embedder = Embedder::new(&dir, None, Some("query: ")).unwrap();
+        assert_eq!(embedder.backend(), Backend::Native);
+        let doc = embedder.embed("hello world", false).unwrap();
+        assert_eq!(doc.len(), bert::tests::HIDDEN);
+        let norm: f32 = doc.iter().map(|v| v * v).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-5);
+        assert_eq!(doc, embedder.embed("hello world", false).unwrap());
+        assert_ne!(doc, embedder.embed("hello world", true).unwrap());
+
+        let mut cls =
+            Embedder::with_backend(&dir, Backend::Native, Some("cls"), None, None).unwrap();
+        assert_ne!(doc, cls.embed("hello world", false).unwrap());
+        assert_ne!(embedder.model_version(), cls.model_version());
+        assert_ne!(embedder.model_id(), cls.model_id());
+        assert!(embedder.model_id().starts_with("native:"));
+        assert_eq!(
+            embedder.model_version(),
+            Embedder::new(&dir, None, Some("query: "))
+                .unwrap()
+                .model_version()
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn backend_names_parse() {
+        assert_eq!("native".parse::<Backend>().unwrap(), Backend::Native);
+        assert_eq!("ONNX".parse::<Backend>().unwrap(), Backend::Onnx);
+        assert_eq!(Backend::default().as_str(), "auto");
+        assert!("candle".parse::<Backend>().is_err());
+        assert!(
+            Backend::Auto
+                .resolve(&std::env::temp_dir().join("remi-embed-missing"))
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use std::{
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use anyhow::Context;
+use ndarray::Array;
+use ort::session::{Session, builder::GraphOptimizationLevel};
+use ort::value::Value;
+use serde_json::{Value as Json, json};
+use tokenizers::{Tokenizer, TruncationParams};
+
+// Cross-encoders are trained on 512-token pairs; longer messages are cut.
+const MAX_PAIR_TOKENS: usize = 512;
+
+pub enum Reranker {
+    Onnx {
+        tokenizer: Box<Tokenizer>,
+        session: Session,
+    },
+    Remote {
+        url: String,
+        model: Option<String>,
+        api_key: Option<String>,
+    },
+}
+
+impl Reranker {
+    // Expects `model.onnx` and `tokenizer.json`, as exported for
+    // ms-marco-MiniLM or bge-reranker.
+    pub fn onnx(model_dir: impl AsRef<Path>, threads: Option<usize>) -> anyhow::Result<Self> {
+        let model_dir = model_dir.as_ref();
+        let tokenizer_path = model_dir.join("tokenizer.json");
+        let model_path = model_dir.join("model.onnx");
+        if !tokenizer_path.exists() {
+            anyhow::bail!("tokenizer.json not found in {}", model_dir.display());
+        }
+        if !model_path.exists() {
+            anyhow::bail!("model.onnx not found in {}", model_dir.display());
+        }
+        let mut tokenizer = Tokenizer::from_file(&tokenizer_path)
+            .map_err(|e| anyhow::anyhow!("failed to load reranker tokenizer: {}", e))?;
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: MAX_PAIR_TOKENS,
+                ..Default::default()
+            }))
+            .map_err(|e| anyhow::anyhow!("failed to configure truncation: {}", e))?;
+        let session = Session::builder()?
+            .with_optimization_level(GraphOptimizationLevel::Level3)?
+            .with_intra_threads(threads.unwrap_or(crate::DEFAULT_THREADS))?
+            .commit_from_file(model_path)?;
+        Ok(Reranker::Onnx {
+            tokenizer: Box::new(tokenizer),
+            session,
+        })
+    }
+
+    // Speaks the Cohere/Jina `/rerank` request shape, which TEI and most
+    // hosted rerankers also accept.
+    pub fn remote(url: &str, model: Option<&str>, api_key: Option<String>) -> Self {
+        Reranker::Remote {
+            url: url.to_string(),
+            model: model.map(str::to_string),
+            api_key,
+        }
+    }
+
+    // One relevance score per passage, higher is more relevant.
+    pub fn score(&mut self, query: &str, passages: &[&str]) -> anyhow::Result<Vec<f32>> {
+        if passages.is_empty() {
+            return Ok(Vec::new());
+        }
+        match self {
+            Reranker::Onnx { tokenizer, session } => passages
+                .iter()
+                .map(|passage| score_pair(tokenizer, session, query, passage))
+                .collect(),
+            Reranker::Remote {
+                url,
+                model,
+                api_key,
+            } => {
+                let mut body = json!({
+                    "query": query,
+                    "documents": passages,
+                    "top_n": passages.len(),
+                });
+                if let Some(model) = model {
+                    body["model"] = Json::from(model.as_str());
+                }
+                let response = post_json(url, api_key.as_deref(), &body)?;
+                parse_remote_scores(&response, passages.len())
+            }
+        }
+    }
+}
+
+fn score_pair(
+    tokenizer: &Tokenizer,
+    session: &mut Session,
+    query: &str,
+    passage: &str,
+) -> anyhow::Result<f32> {
+    let encoding = tokenizer
+        .encode((query, passage), true)
+        .map_err(|e| anyhow::anyhow!("encoding error: {}", e))?;
+    let seq_len = encoding.get_ids().len();
+    let to_array = |values: &[u32]| {
+        Array::from_shape_vec(
+            (1, seq_len),
+            values.iter().map(|&x| x as i64).collect::<Vec<_>>(),
+        )
+    };
+    let input_ids = Value::from_array(to_array(encoding.get_ids())?)?;
+    let attention_mask = Value::from_array(to_array(encoding.get_attention_mask())?)?;
+    let token_type_ids = Value::from_array(to_array(encoding.get_type_ids())?)?;
+
+    let has_token_type_ids = session
+        .inputs()
+        .iter()
+        .any(|i| i.name() == "token_type_ids");
+    let outputs = if has_token_type_ids {
+        session.run(ort::inputs![
+            "input_ids" => input_ids,
+            "attention_mask" => attention_mask,
+            "token_type_ids" => token_type_ids,
+        ])?
+    } else {
+        session.run(ort::inputs![
+            "input_ids" => input_ids,
+            "attention_mask" => attention_mask,
+        ])?
+    };
+
+    // Logits are (1, 1) for regression heads and (1, 2) for binary
+    // classifiers, where the last column is the "relevant" class.
+    let (_, data) = outputs[0].try_extract_tensor::<f32>()?;
+    data.last()
+        .copied()
+        .context("reranker returned an empty logits tensor")
+}
+
+fn post_json(url: &str, api_key: Option<&str>, body: &Json) -> anyhow::Result<Json> {
+    let mut command = Command::new("curl");
+    command
+        .args(["--fail", "--silent", "--show-error", "--max-time", "30"])
+        .args(["--header", "Content-Type: application/json"])
+        .args(["--data-binary", "@-"]);
+    if let Some(key) = api_key {
+        command.args(["--header", &format!("Authorization: Bearer {key}")]);
+    }
+    let mut child = command
+        .arg(url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("failed to run curl; is it installed?")?;
+    child
+        .stdin
+        .take()
+        .context("curl stdin unavailable")?
+        .write_all(body.to_string().as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "rerank request to {url} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    serde_json::from_slice(&output.stdout)
+        .with_context(|| format!("rerank response from {url} is not JSON"))
+}
+
+// Accepts `{"results": [{"index", "relevance_score"}]}` (Cohere/Jina) and a
+// bare `[{"index", "score"}]` array (TEI). Passages the service dropped keep
+// the lowest score so they sort last.
+fn parse_remote_scores(response: &Json, count: usize) -> anyhow::Result<Vec<f32>> {
+    let results = response
+        .get("results")
+        .or(Some(response))
+        .and_then(Json::as_array)
+        .context("rerank response has no results array")?;
+    let mut scores = vec![f32::NEG_INFINITY; count];
+    for result in results {
+        let index = result["index"]
+            .as_u64()
+            .map(|i| i as usize)
+            .filter(|i| *i < count)
+            .context("rerank result has a missing or out-of-range index")?;
+        let score = result
+            .get("relevance_score")
+            .or_else(|| result.get("score"))
+            .and_then(Json::as_f64)
+            .context("rerank result has no score")?;
+        scores[index] = score as f32;
+    }
+    Ok(scores)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remote_scores_follow_result_indices() {
+        let cohere = json!({"results": [
+            {"index": 2, "relevance_score": 0.9},
+            {"index": 0, "relevance_score": 0.1},
+        ]});
+        assert_eq!(
+            parse_remote_scores(&cohere, 3).unwrap(),
+            vec![0.1, f32::NEG_INFINITY, 0.9]
+        );
+        let tei = json!([{"index": 1, "score": 2.5}, {"index": 0, "score": -1.0}]);
+        assert_eq!(parse_remote_scores(&tei, 2).unwrap(), vec![-1.0, 2.5]);
+        assert!(parse_remote_scores(&json!([{"index": 5, "score": 1.0}]), 2).is_err());
+        assert!(parse_remote_scores(&json!({"error": "nope"}), 2).is_err());
+    }
+
+    #[test]
+    fn onnx_reranker_requires_model_files() {
+        let dir = std::env::temp_dir().join("remi-rerank-missing");
+        assert!(Reranker::onnx(&dir, None).is_err());
+    }
+}
@@ -1,26 +1,73 @@
-use chrono::Utc;
-use core_model::{AgentAdapter, Checkpoint};
-use store_sqlite::SqliteStore;
-use tracing::{debug, trace};
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use core_model::{AgentAdapter, Checkpoint, NativeRecord, ScanReport, SourceContext};
+use serde_json::Value;
+use store_sqlite::{FileState, SqliteStore};
+use tracing::{debug, info, trace, warn};
+
+mod repo;
+
+pub use repo::{RepoResolver, normalize_remote};
 
 #[derive(Debug, Clone)]
 pub enum SyncPhase {
     Discovering,
-    Scanning { file_count: usize },
-    Normalizing { record_count: usize },
-    Saving { message_count: usize },
-    Done { total_records: usize },
+    Scanning {
+        file_count: usize,
+    },
+    Normalizing {
+        record_count: usize,
+    },
+    Saving {
+        message_count: usize,
+    },
+    Done {
+        total_records: usize,
+        report: Option<ScanReport>,
+    },
 }
 
+pub const SYNC_CHUNK_SIZE: usize = 5_000;
+
+// Scan groups handed to the adapter per call, so it can still read files in
+// parallel while sync holds at most one window and one chunk of records.
+const SCAN_WINDOW: usize = 64;
+
 pub fn sync_adapter(
     adapter: &dyn AgentAdapter,
+    ctx: &SourceContext,
     store: &mut SqliteStore,
-    #[cfg(feature = "semantic")] embedder: Option<&mut embeddings::Embedder>,
+    #[cfg(feature = "semantic")] queue_embeddings: bool,
+    on_progress: impl Fn(SyncPhase),
+) -> anyhow::Result<usize> {
+    sync_adapter_chunked(
+        adapter,
+        ctx,
+        store,
+        #[cfg(feature = "semantic")]
+        queue_embeddings,
+        SYNC_CHUNK_SIZE,
+        on_progress,
+    )
+}
+
+// Records stream out of the adapter one window of scan groups at a time and
+// are saved in chunks of whole groups, each chunk with the file state of the
+// sources it came from. An interrupted sync skips those files on the next
+// run; the cursor only moves once every group is saved, because groups are
+// not in time order and an early cursor would skip records not yet reached.
+pub fn sync_adapter_chunked(
+    adapter: &dyn AgentAdapter,
+    ctx: &SourceContext,
+    store: &mut SqliteStore,
+    #[cfg(feature = "semantic")] queue_embeddings: bool,
+    chunk_size: usize,
     on_progress: impl Fn(SyncPhase),
 ) -> anyhow::Result<usize> {
     on_progress(SyncPhase::Discovering);
 
-    let sources = adapter.discover_source_paths()?;
+    let sources = adapter.discover_source_paths(ctx)?;
     debug!(agent = %adapter.kind(), source_count = sources.len(), "discovered source paths");
 
     on_progress(SyncPhase::Scanning {
@@ -29,50 +76,520 @@ pub fn sync_adapter(
 
     let checkpoint = store.get_checkpoint(adapter.kind().as_str())?;
     trace!(agent = %adapter.kind(), checkpoint = ?checkpoint.as_deref(), "loaded checkpoint");
-    let records = adapter.scan_changes_since(&sources, checkpoint.as_deref())?;
+    let mut saver = Saver::new(
+        adapter,
+        store,
+        chunk_size,
+        #[cfg(feature = "semantic")]
+        queue_embeddings,
+        store.retains_raw_records(),
+        &on_progress,
+    )?;
+    let mut report = None;
+    let rewritten = if adapter.tracks_file_state() {
+        scan_tracked_files(
+            adapter,
+            store,
+            &sources,
+            checkpoint.as_deref(),
+            &mut saver,
+            &mut report,
+        )?
+    } else {
+        for window in scan_windows(adapter, &sources) {
+            let records = adapter.scan_changes_since(&window.concat(), checkpoint.as_deref())?;
+            merge_report(&mut report, adapter.scan_report());
+            for group in split_groups(&window, records) {
+                saver.push(store, group, Vec::new())?;
+            }
+        }
+        0
+    };
+    if let Some(report) = report {
+        debug!(
+            agent = %adapter.kind(),
+            files_seen = report.files_seen,
+            files_skipped = report.files_skipped,
+            parse_errors = report.parse_errors,
+            "scan report"
+        );
+        if report.parse_errors > 0 {
+            warn!(
+                agent = %adapter.kind(),
+                source = %adapter.source_label(),
+                parse_errors = report.parse_errors,
+                "source records failed to parse"
+            );
+        }
+    }
 
-    on_progress(SyncPhase::Normalizing {
-        record_count: records.len(),
-    });
+    let total = saver.finish(store, true)?;
 
-    let batch = adapter.normalize(&records)?;
-    debug!(agent = %adapter.kind(), sessions = batch.sessions.len(), messages = batch.messages.len(), "normalized batch");
+    if (checkpoint.is_none() && total > 0) || rewritten > 0 {
+        let removed = store.dedupe_agent_messages(adapter.kind().as_str())?;
+        debug!(agent = %adapter.kind(), removed, rewritten, "removed duplicate messages after full scan");
+    }
 
-    on_progress(SyncPhase::Saving {
-        message_count: batch.messages.len(),
+    on_progress(SyncPhase::Done {
+        total_records: total,
+        report,
     });
 
-    store.save_batch(&batch)?;
+    Ok(total)
+}
 
-    #[cfg(feature = "semantic")]
-    if let Some(embedder) = embedder {
-        let mut embedded = 0usize;
-        for msg in &batch.messages {
-            if let Ok(vec) = embedder.embed(&msg.content, false) {
-                let _ = store.save_embedding(&msg.id, &vec);
-                embedded += 1;
-            }
+// Ignores the checkpoint and file state to ingest history an earlier sync
+// skipped. Message ids are deterministic, so re-ingested records upsert in
+// place and any leftovers are removed by the dedupe pass.
+pub fn backfill_adapter(
+    adapter: &dyn AgentAdapter,
+    ctx: &SourceContext,
+    store: &mut SqliteStore,
+    #[cfg(feature = "semantic")] queue_embeddings: bool,
+    before: Option<DateTime<Utc>>,
+    on_progress: impl Fn(SyncPhase),
+) -> anyhow::Result<usize> {
+    on_progress(SyncPhase::Discovering);
+    let sources = adapter.discover_source_paths(ctx)?;
+    on_progress(SyncPhase::Scanning {
+        file_count: sources.len(),
+    });
+    let mut saver = Saver::new(
+        adapter,
+        store,
+        SYNC_CHUNK_SIZE,
+        #[cfg(feature = "semantic")]
+        queue_embeddings,
+        store.retains_raw_records(),
+        &on_progress,
+    )?;
+    let mut report = None;
+    for window in scan_windows(adapter, &sources) {
+        let mut records = adapter.scan_changes_since(&window.concat(), None)?;
+        merge_report(&mut report, adapter.scan_report());
+        if let Some(before) = before {
+            records.retain(|rec| rec.updated_at < before);
+        }
+        for group in split_groups(&window, records) {
+            saver.push(store, group, Vec::new())?;
         }
-        debug!(agent = %adapter.kind(), embedded, total = batch.messages.len(), "computed embeddings");
     }
+    let total = saver.finish(store, false)?;
+    debug!(agent = %adapter.kind(), sources = sources.len(), records = total, before = ?before, "backfill scan");
 
-    if let Some(cursor) = adapter.checkpoint_cursor(&records) {
-        trace!(agent = %adapter.kind(), cursor = %cursor, "saving checkpoint");
-        store.upsert_checkpoint(&Checkpoint {
-            agent: adapter.kind(),
-            cursor,
-            updated_at: Utc::now(),
-        })?;
+    if total > 0 {
+        let removed = store.dedupe_agent_messages(adapter.kind().as_str())?;
+        debug!(agent = %adapter.kind(), removed, "removed duplicate messages after backfill");
     }
 
-    let total = records.len();
     on_progress(SyncPhase::Done {
         total_records: total,
+        report,
     });
+    Ok(total)
+}
+
+// Runs the adapter's normalize over the retained raw records instead of the
+// source files, so improved extraction applies to history that has since
+// been rotated away. The checkpoint and file state are left alone.
+pub fn renormalize_adapter(
+    adapter: &dyn AgentAdapter,
+    store: &mut SqliteStore,
+    #[cfg(feature = "semantic")] queue_embeddings: bool,
+    on_progress: impl Fn(SyncPhase),
+) -> anyhow::Result<usize> {
+    let records = store.raw_records(adapter.kind())?;
+    debug!(agent = %adapter.kind(), records = records.len(), "renormalize from raw records");
+
+    // Regrouped by the sources they were read from, so each session is still
+    // normalized whole.
+    let mut by_path: HashMap<String, Vec<NativeRecord>> = HashMap::new();
+    for rec in records {
+        let path = record_source_path(&rec).unwrap_or_default().to_string();
+        by_path.entry(path).or_default().push(rec);
+    }
+    let mut paths: Vec<String> = by_path.keys().cloned().collect();
+    paths.sort_unstable();
+    let mut saver = Saver::new(
+        adapter,
+        store,
+        SYNC_CHUNK_SIZE,
+        #[cfg(feature = "semantic")]
+        queue_embeddings,
+        false,
+        &on_progress,
+    )?;
+    for group in adapter.scan_groups(&paths) {
+        let records = group
+            .iter()
+            .filter_map(|path| by_path.remove(path))
+            .flatten()
+            .collect();
+        saver.push(store, records, Vec::new())?;
+    }
+    let total = saver.finish(store, false)?;
+    if total > 0 {
+        let removed = store.dedupe_agent_messages(adapter.kind().as_str())?;
+        debug!(agent = %adapter.kind(), removed, "removed duplicate messages after renormalize");
+    }
 
+    on_progress(SyncPhase::Done {
+        total_records: total,
+        report: None,
+    });
     Ok(total)
 }
 
+fn scan_windows(adapter: &dyn AgentAdapter, sources: &[String]) -> Vec<Vec<Vec<String>>> {
+    adapter
+        .scan_groups(sources)
+        .chunks(SCAN_WINDOW)
+        .map(<[Vec<String>]>::to_vec)
+        .collect()
+}
+
+// Sorts a window's records back into its scan groups by source path, so a
+// chunk can close between any two of them. Records that do not name their
+// source cannot be placed and keep the window together.
+fn split_groups(window: &[Vec<String>], records: Vec<NativeRecord>) -> Vec<Vec<NativeRecord>> {
+    let index: HashMap<&str, usize> = window
+        .iter()
+        .enumerate()
+        .flat_map(|(i, group)| group.iter().map(move |path| (path.as_str(), i)))
+        .collect();
+    if records
+        .iter()
+        .any(|rec| record_source_path(rec).is_none_or(|path| !index.contains_key(path)))
+    {
+        return vec![records];
+    }
+    let mut groups: Vec<Vec<NativeRecord>> = window.iter().map(|_| Vec::new()).collect();
+    for rec in records {
+        let group = record_source_path(&rec).map_or(0, |path| index[path]);
+        groups[group].push(rec);
+    }
+    groups
+}
+
+// Adapters reset their counters on every scan, so the per-window reports are
+// summed into one for the whole sync.
+fn merge_report(total: &mut Option<ScanReport>, next: Option<ScanReport>) {
+    let Some(next) = next else {
+        return;
+    };
+    let total = total.get_or_insert_with(ScanReport::default);
+    total.files_seen += next.files_seen;
+    total.files_skipped += next.files_skipped;
+    total.parse_errors += next.parse_errors;
+}
+
+// Collects scanned records until a chunk is full, then normalizes and saves
+// it in one transaction together with the file state of its sources.
+struct Saver<'a, F: Fn(SyncPhase)> {
+    adapter: &'a dyn AgentAdapter,
+    chunk_size: usize,
+    #[cfg(feature = "semantic")]
+    queue_embeddings: bool,
+    retain_raw: bool,
+    on_progress: &'a F,
+    repos: RepoResolver,
+    aliases: HashMap<String, String>,
+    records: Vec<NativeRecord>,
+    file_states: Vec<FileState>,
+    // The newest record saved so far, kept for the final checkpoint.
+    newest: Option<NativeRecord>,
+    chunks: usize,
+    total: usize,
+}
+
+impl<'a, F: Fn(SyncPhase)> Saver<'a, F> {
+    fn new(
+        adapter: &'a dyn AgentAdapter,
+        store: &SqliteStore,
+        chunk_size: usize,
+        #[cfg(feature = "semantic")] queue_embeddings: bool,
+        retain_raw: bool,
+        on_progress: &'a F,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            adapter,
+            chunk_size: chunk_size.max(1),
+            #[cfg(feature = "semantic")]
+            queue_embeddings,
+            retain_raw,
+            on_progress,
+            repos: RepoResolver::default(),
+            aliases: store.session_aliases(adapter.kind())?,
+            records: Vec::new(),
+            file_states: Vec::new(),
+            newest: None,
+            chunks: 0,
+            total: 0,
+        })
+    }
+
+    // `records` must hold whole scan groups; a chunk closes only between
+    // pushes, so it can run over the chunk size but never splits a group.
+    fn push(
+        &mut self,
+        store: &mut SqliteStore,
+        records: Vec<NativeRecord>,
+        file_states: Vec<FileState>,
+    ) -> anyhow::Result<()> {
+        self.records.extend(records);
+        self.file_states.extend(file_states);
+        if self.records.len() >= self.chunk_size {
+            self.flush(store)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self, store: &mut SqliteStore) -> anyhow::Result<()> {
+        let records = std::mem::take(&mut self.records);
+        let file_states = std::mem::take(&mut self.file_states);
+        let adapter = self.adapter;
+        if !records.is_empty() {
+            let chunk = self.chunks;
+            self.chunks += 1;
+            (self.on_progress)(SyncPhase::Normalizing {
+                record_count: records.len(),
+            });
+
+            if self.retain_raw {
+                let kept = store.save_raw_records(adapter.kind(), &records)?;
+                trace!(agent = %adapter.kind(), chunk, kept, "retained raw records");
+            }
+            let mut batch = adapter.normalize(&records)?;
+            adapter_common::apply_session_aliases(adapter.kind(), &mut batch, &self.aliases);
+            self.repos.annotate(&mut batch);
+            debug!(
+                agent = %adapter.kind(),
+                chunk,
+                sessions = batch.sessions.len(),
+                messages = batch.messages.len(),
+                "normalized batch"
+            );
+
+            (self.on_progress)(SyncPhase::Saving {
+                message_count: batch.messages.len(),
+            });
+
+            store.save_batch(&batch)?;
+
+            #[cfg(feature = "semantic")]
+            if self.queue_embeddings {
+                let ids: Vec<String> = batch
+                    .messages
+                    .iter()
+                    .filter(|m| !m.content.trim().is_empty())
+                    .map(|m| m.id.clone())
+                    .collect();
+                let queued = store.enqueue_embeddings(&ids)?;
+                debug!(agent = %adapter.kind(), queued, "queued embeddings");
+            }
+
+            self.total += records.len();
+            let newest = records
+                .into_iter()
+                .max_by(|a, b| {
+                    a.updated_at
+                        .cmp(&b.updated_at)
+                        .then_with(|| a.source_id.cmp(&b.source_id))
+                })
+                .filter(|rec| {
+                    self.newest.as_ref().is_none_or(|prev| {
+                        (rec.updated_at, &rec.source_id) > (prev.updated_at, &prev.source_id)
+                    })
+                });
+            if newest.is_some() {
+                self.newest = newest;
+            }
+        }
+        if !file_states.is_empty() {
+            store.upsert_file_states(adapter.kind().as_str(), &file_states)?;
+        }
+        Ok(())
+    }
+
+    fn finish(
+        mut self,
+        store: &mut SqliteStore,
+        advance_checkpoint: bool,
+    ) -> anyhow::Result<usize> {
+        self.flush(store)?;
+        if advance_checkpoint
+            && let Some(newest) = self.newest.as_ref()
+            && let Some(cursor) = self.adapter.checkpoint_cursor(std::slice::from_ref(newest))
+        {
+            trace!(agent = %self.adapter.kind(), chunks = self.chunks, cursor = %cursor, "saving checkpoint");
+            store.upsert_checkpoint(&Checkpoint {
+                agent: self.adapter.kind(),
+                cursor,
+                updated_at: Utc::now(),
+            })?;
+        }
+        Ok(self.total)
+    }
+}
+
+// Plans the scan from the saved file state, then streams the changed files
+// through `saver`. Returns how many files were rewritten rather than
+// appended to.
+fn scan_tracked_files<F: Fn(SyncPhase)>(
+    adapter: &dyn AgentAdapter,
+    store: &mut SqliteStore,
+    sources: &[String],
+    cursor: Option<&str>,
+    saver: &mut Saver<'_, F>,
+    report: &mut Option<ScanReport>,
+) -> anyhow::Result<usize> {
+    let previous = store.get_file_states(adapter.kind().as_str())?;
+    let mut to_scan = Vec::new();
+    let mut planned: HashMap<String, FileState> = HashMap::new();
+    let mut touched = Vec::new();
+    for path in sources {
+        let Some((size_bytes, modified_at)) = file_stat(path) else {
+            to_scan.push(path.clone());
+            continue;
+        };
+        let prior = previous.get(path);
+        if prior.is_some_and(|p| p.size_bytes == size_bytes && p.modified_at == modified_at) {
+            continue;
+        }
+        let Ok(bytes) = std::fs::read(path) else {
+            to_scan.push(path.clone());
+            continue;
+        };
+        let content_hash = blake3::hash(&bytes).to_hex().to_string();
+        let state = FileState {
+            path: path.clone(),
+            content_hash,
+            size_bytes,
+            modified_at,
+            record_count: prior.map(|p| p.record_count).unwrap_or_default(),
+        };
+        if prior.is_some_and(|p| p.content_hash == state.content_hash) {
+            touched.push(state);
+            continue;
+        }
+        to_scan.push(path.clone());
+        planned.insert(path.clone(), state);
+    }
+    debug!(
+        agent = %adapter.kind(),
+        scan = to_scan.len(),
+        unchanged = sources.len() - to_scan.len(),
+        "planned tracked file scan"
+    );
+
+    let parsed_cursor = cursor.and_then(adapter_common::parse_cursor);
+    let mut rewritten = 0;
+    for window in scan_windows(adapter, &to_scan) {
+        let scanned = adapter.scan_changes_since(&window.concat(), None)?;
+        merge_report(report, adapter.scan_report());
+        let mut counts: HashMap<&str, i64> = HashMap::new();
+        for rec in &scanned {
+            if let Some(path) = record_source_path(rec) {
+                *counts.entry(path).or_default() += 1;
+            }
+        }
+        let mut rewritten_paths = HashSet::new();
+        let mut states = HashMap::new();
+        for path in window.iter().flatten() {
+            let Some(mut state) = planned.remove(path) else {
+                continue;
+            };
+            let count = counts.get(path.as_str()).copied().unwrap_or_default();
+            if let Some(prior) = previous.get(path)
+                && count < prior.record_count
+            {
+                info!(
+                    agent = %adapter.kind(),
+                    path = %path,
+                    previous = prior.record_count,
+                    current = count,
+                    "source file rewritten; rescanning in full"
+                );
+                rewritten_paths.insert(path.clone());
+            }
+            state.record_count = count;
+            states.insert(path.clone(), state);
+        }
+        rewritten += rewritten_paths.len();
+
+        let records = scanned
+            .into_iter()
+            .filter(|rec| {
+                let Some(cur) = parsed_cursor.as_ref() else {
+                    return true;
+                };
+                record_source_path(rec).is_some_and(|p| rewritten_paths.contains(p))
+                    || !adapter_common::should_skip(rec.updated_at, &rec.source_id, cur)
+            })
+            .collect();
+        let groups = split_groups(&window, records);
+        let whole = groups.len() < window.len();
+        for (group, records) in window.iter().zip(groups) {
+            let group_states = if whole {
+                states.drain().map(|(_, state)| state).collect()
+            } else {
+                group
+                    .iter()
+                    .filter_map(|path| states.remove(path))
+                    .collect()
+            };
+            saver.push(store, records, group_states)?;
+        }
+    }
+    saver.push(store, Vec::new(), touched)?;
+    Ok(rewritten)
+}
+
+fn file_stat(path: &str) -> Option<(i64, DateTime<Utc>)> {
+    let meta = std::fs::metadata(path).ok()?;
+    let modified = meta.modified().ok()?;
+    Some((meta.len() as i64, DateTime::<Utc>::from(modified)))
+}
+
+fn record_source_path(rec: &NativeRecord) -> Option<&str> {
+    rec.payload.get("__source_path").and_then(Value::as_str)
+}
+
+#[cfg(feature = "semantic")]
+pub const EMBED_MAX_ATTEMPTS: i64 = 3;
+
+#[cfg(feature = "semantic")]
+pub fn drain_embedding_queue(
+    store: &SqliteStore,
+    embedder: &mut embeddings::Embedder,
+    batch_size: usize,
+    on_progress: impl Fn(usize),
+) -> anyhow::Result<usize> {
+    let mut embedded = 0usize;
+    loop {
+        let jobs = store.pending_embedding_jobs(batch_size.max(1), EMBED_MAX_ATTEMPTS)?;
+        if jobs.is_empty() {
+            break;
+        }
+        for job in jobs {
+            match embedder.embed(&job.content, false) {
+                Ok(vec) => {
+                    store.save_embedding(&job.message_id, embedder.model_id(), &vec)?;
+                    store.complete_embedding_job(&job.message_id)?;
+                    embedded += 1;
+                }
+                Err(err) => {
+                    warn!(message_id = %job.message_id, attempts = job.attempts + 1, error = %err, "embedding failed");
+                    store.fail_embedding_job(&job.message_id)?;
+                }
+            }
+        }
+        on_progress(embedded);
+    }
+    debug!(embedded, "embedding queue drained");
+    Ok(embedded)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -88,7 +605,7 @@ mod tests {
         fn kind(&self) -> AgentKind {
             AgentKind::Pi
         }
-        fn discover_source_paths(&self) -> anyhow::Result<Vec<String>> {
+        fn discover_source_paths(&self, _ctx: &SourceContext) -> anyhow::Result<Vec<String>> {
             Ok(vec!["fake/path".to_string()])
         }
         fn scan_changes_since(
@@ -130,6 +647,12 @@ mod tests {
         fn archive_capability(&self) -> ArchiveCapability {
             ArchiveCapability::CentralizedCopy
         }
+        fn scan_report(&self) -> Option<ScanReport> {
+            Some(ScanReport {
+                files_seen: 1,
+                ..ScanReport::default()
+            })
+        }
     }
 
     #[test]
@@ -145,9 +668,16 @@ mod tests {
         store.init_schema().unwrap();
 
         #[cfg(feature = "semantic")]
-        let count = sync_adapter(&adapter, &mut store, None, |_| {}).unwrap();
+        let count = sync_adapter(
+            &adapter,
+            &SourceContext::default(),
+            &mut store,
+            false,
+            |_| {},
+        )
+        .unwrap();
         #[cfg(not(feature = "semantic"))]
-        let count = sync_adapter(&adapter, &mut store, |_| {}).unwrap();
+        let count = sync_adapter(&adapter, &SourceContext::default(), &mut store, |_| {}).unwrap();
 
         assert_eq!(count, 1);
         let sessions = store.list_sessions().unwrap();
@@ -170,19 +700,380 @@ mod tests {
 
         #[cfg(feature = "semantic")]
         {
-            sync_adapter(&adapter, &mut store, None, |_| {}).unwrap();
-            sync_adapter(&adapter, &mut store, None, |_| {}).unwrap();
+            sync_adapter(
+                &adapter,
+                &SourceContext::default(),
+                &mut store,
+                false,
+                |_| {},
+            )
+            .unwrap();
+            sync_adapter(
+                &adapter,
+                &SourceContext::default(),
+                &mut store,
+                false,
+                |_| {},
+            )
+            .unwrap();
         }
         #[cfg(not(feature = "semantic"))]
         {
-            sync_adapter(&adapter, &mut store, |_| {}).unwrap();
-            sync_adapter(&adapter, &mut store, |_| {}).unwrap();
+            sync_adapter(&adapter, &SourceContext::default(), &mut store, |_| {}).unwrap();
+            sync_adapter(&adapter, &SourceContext::default(), &mut store, |_| {}).unwrap();
         }
 
         let sessions = store.list_sessions().unwrap();
         assert_eq!(sessions.len(), 1);
     }
 
+    #[test]
+    fn backfill_keeps_checkpoint_and_honors_before() {
+        let old = Utc::now() - chrono::Duration::days(60);
+        let recent = Utc::now() - chrono::Duration::days(1);
+        let record = |id: &str, ts| NativeRecord {
+            source_id: id.to_string(),
+            updated_at: ts,
+            payload: Value::String(id.to_string()),
+        };
+        let adapter = FakeAdapter {
+            records: vec![record("old", old), record("recent", recent)],
+        };
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        store
+            .upsert_checkpoint(&Checkpoint {
+                agent: AgentKind::Pi,
+                cursor: "existing".to_string(),
+                updated_at: Utc::now(),
+            })
+            .unwrap();
+
+        let before = Some(Utc::now() - chrono::Duration::days(30));
+        #[cfg(feature = "semantic")]
+        let count = backfill_adapter(
+            &adapter,
+            &SourceContext::default(),
+            &mut store,
+            false,
+            before,
+            |_| {},
+        )
+        .unwrap();
+        #[cfg(not(feature = "semantic"))]
+        let count = backfill_adapter(
+            &adapter,
+            &SourceContext::default(),
+            &mut store,
+            before,
+            |_| {},
+        )
+        .unwrap();
+
+        assert_eq!(count, 1);
+        assert!(store.get_message("m_old").unwrap().is_some());
+        assert!(store.get_message("m_recent").unwrap().is_none());
+        assert_eq!(
+            store.get_checkpoint("pi").unwrap().as_deref(),
+            Some("existing")
+        );
+    }
+
+    #[test]
+    fn renormalize_reads_retained_records_without_sources() {
+        let adapter = FakeAdapter {
+            records: vec![NativeRecord {
+                source_id: "r1".to_string(),
+                updated_at: Utc::now(),
+                payload: Value::String("kept".to_string()),
+            }],
+        };
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        store.set_retain_raw_records(true);
+        #[cfg(feature = "semantic")]
+        sync_adapter(
+            &adapter,
+            &SourceContext::default(),
+            &mut store,
+            false,
+            |_| {},
+        )
+        .unwrap();
+        #[cfg(not(feature = "semantic"))]
+        sync_adapter(&adapter, &SourceContext::default(), &mut store, |_| {}).unwrap();
+        let checkpoint = store.get_checkpoint("pi").unwrap();
+        store.delete_session_cascade("s_r1").unwrap();
+
+        let gone = FakeAdapter { records: vec![] };
+        #[cfg(feature = "semantic")]
+        let count = renormalize_adapter(&gone, &mut store, false, |_| {}).unwrap();
+        #[cfg(not(feature = "semantic"))]
+        let count = renormalize_adapter(&gone, &mut store, |_| {}).unwrap();
+
+        assert_eq!(count, 1);
+        assert_eq!(
+            store.get_message("m_r1").unwrap().unwrap().content,
+            "\"kept\""
+        );
+        assert_eq!(store.get_checkpoint("pi").unwrap(), checkpoint);
+    }
+
+    #[test]
+    fn sync_adapter_reports_scan_stats_in_done_phase() {
+        let adapter = FakeAdapter { records: vec![] };
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let done = std::cell::RefCell::new(None);
+        let on_progress = |phase| {
+            if let SyncPhase::Done { report, .. } = phase {
+                *done.borrow_mut() = Some(report);
+            }
+        };
+
+        #[cfg(feature = "semantic")]
+        sync_adapter(
+            &adapter,
+            &SourceContext::default(),
+            &mut store,
+            false,
+            on_progress,
+        )
+        .unwrap();
+        #[cfg(not(feature = "semantic"))]
+        sync_adapter(&adapter, &SourceContext::default(), &mut store, on_progress).unwrap();
+
+        assert_eq!(
+            done.into_inner(),
+            Some(Some(ScanReport {
+                files_seen: 1,
+                files_skipped: 0,
+                parse_errors: 0,
+            }))
+        );
+    }
+
+    #[cfg(feature = "semantic")]
+    #[test]
+    fn sync_adapter_queues_embeddings() {
+        let adapter = FakeAdapter {
+            records: vec![NativeRecord {
+                source_id: "r1".to_string(),
+                updated_at: Utc::now(),
+                payload: Value::String("embed me".to_string()),
+            }],
+        };
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        sync_adapter(
+            &adapter,
+            &SourceContext::default(),
+            &mut store,
+            true,
+            |_| {},
+        )
+        .unwrap();
+        assert_eq!(store.embedding_queue_len().unwrap(), 1);
+    }
+
+    // One session per file, named by a leading `# ` line or else by the file
+    // stem, the way agents fall back when the naming record is missing.
+    struct LinesAdapter {
+        paths: Vec<String>,
+    }
+
+    impl AgentAdapter for LinesAdapter {
+        fn kind(&self) -> AgentKind {
+            AgentKind::Droid
+        }
+        fn discover_source_paths(&self, _ctx: &SourceContext) -> anyhow::Result<Vec<String>> {
+            Ok(self.paths.clone())
+        }
+        fn scan_changes_since(
+            &self,
+            source_paths: &[String],
+            cursor: Option<&str>,
+        ) -> anyhow::Result<Vec<NativeRecord>> {
+            let cursor = cursor.and_then(adapter_common::parse_cursor);
+            let mut out = Vec::new();
+            for path in source_paths {
+                let content = std::fs::read_to_string(path)?;
+                for (idx, line) in content.lines().enumerate() {
+                    let (ts, text) = line.split_once('|').unwrap();
+                    let ts = chrono::DateTime::parse_from_rfc3339(ts)
+                        .unwrap()
+                        .with_timezone(&Utc);
+                    let stem = std::path::Path::new(path).file_stem().unwrap();
+                    let source_id = format!("{}-{idx:04}", stem.to_string_lossy());
+                    if let Some(cur) = cursor.as_ref()
+                        && adapter_common::should_skip(ts, &source_id, cur)
+                    {
+                        continue;
+                    }
+                    out.push(NativeRecord {
+                        source_id,
+                        updated_at: ts,
+                        payload: serde_json::json!({"text": text, "__source_path": path}),
+                    });
+                }
+            }
+            Ok(out)
+        }
+        fn normalize(&self, records: &[NativeRecord]) -> anyhow::Result<NormalizedBatch> {
+            let mut batch = NormalizedBatch::default();
+            for rec in records {
+                let (session, _) = rec.source_id.split_once('-').unwrap();
+                let text = rec.payload["text"].as_str().unwrap();
+                let title = text.strip_prefix("# ").unwrap_or(session);
+                match batch.sessions.iter_mut().find(|s| s.id == session) {
+                    Some(existing) if existing.title == session => {
+                        existing.title = title.to_string();
+                    }
+                    Some(_) => {}
+                    None => batch.sessions.push(core_model::Session {
+                        id: session.to_string(),
+                        agent: AgentKind::Droid,
+                        source_ref: session.to_string(),
+                        title: title.to_string(),
+                        created_at: rec.updated_at,
+                        updated_at: rec.updated_at,
+                    }),
+                }
+                batch.messages.push(core_model::Message {
+                    id: format!("m_{}", rec.source_id),
+                    session_id: session.to_string(),
+                    role: "user".to_string(),
+                    content: text.to_string(),
+                    ts: rec.updated_at,
+                });
+            }
+            Ok(batch)
+        }
+        fn checkpoint_cursor(&self, records: &[NativeRecord]) -> Option<String> {
+            adapter_common::checkpoint_cursor_from_records(records)
+        }
+        fn archive_capability(&self) -> ArchiveCapability {
+            ArchiveCapability::CentralizedCopy
+        }
+    }
+
+    fn sync_lines(adapter: &LinesAdapter, store: &mut SqliteStore) -> usize {
+        #[cfg(feature = "semantic")]
+        let count = sync_adapter(adapter, &SourceContext::default(), store, false, |_| {}).unwrap();
+        #[cfg(not(feature = "semantic"))]
+        let count = sync_adapter(adapter, &SourceContext::default(), store, |_| {}).unwrap();
+        count
+    }
+
+    #[test]
+    fn sync_adapter_rescans_compacted_files_and_dedupes() {
+        let dir = std::env::temp_dir().join(format!("remi_ingest_rewrite_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("lines.txt");
+        std::fs::write(
+            &path,
+            "2026-01-01T00:00:01Z|alpha\n2026-01-01T00:00:02Z|beta\n2026-01-01T00:00:03Z|gamma\n",
+        )
+        .unwrap();
+        let adapter = LinesAdapter {
+            paths: vec![path.to_string_lossy().to_string()],
+        };
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+
+        assert_eq!(sync_lines(&adapter, &mut store), 3);
+        assert_eq!(sync_lines(&adapter, &mut store), 0);
+
+        std::fs::write(
+            &path,
+            "2026-01-01T00:00:02Z|beta\n2026-01-01T00:00:03Z|gamma\n",
+        )
+        .unwrap();
+        assert_eq!(sync_lines(&adapter, &mut store), 2);
+
+        let contents: Vec<String> = store
+            .get_session_messages("lines")
+            .unwrap()
+            .into_iter()
+            .map(|m| m.content)
+            .collect();
+        assert_eq!(contents, vec!["beta", "gamma"]);
+        let states = store.get_file_states("droid").unwrap();
+        assert_eq!(states[&adapter.paths[0]].record_count, 2);
+    }
+
+    #[test]
+    fn sync_adapter_chunked_saves_whole_sessions() {
+        let dir = std::env::temp_dir().join(format!("remi_ingest_chunks_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let files = [
+            (
+                "a.txt",
+                "2026-01-01T00:00:01Z|# Alpha\n2026-01-01T00:00:02Z|one\n2026-01-01T00:00:09Z|two\n",
+            ),
+            ("b.txt", "2026-01-01T00:00:03Z|bee\n"),
+            ("c.txt", "2026-01-01T00:00:04Z|sea\n"),
+        ];
+        let mut paths = Vec::new();
+        for (name, content) in files {
+            std::fs::write(dir.join(name), content).unwrap();
+            paths.push(dir.join(name).to_string_lossy().to_string());
+        }
+        let adapter = LinesAdapter { paths };
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let chunks = std::cell::RefCell::new(Vec::new());
+        let on_progress = |phase| {
+            if let SyncPhase::Normalizing { record_count } = phase {
+                chunks.borrow_mut().push(record_count);
+            }
+        };
+
+        #[cfg(feature = "semantic")]
+        let count = sync_adapter_chunked(
+            &adapter,
+            &SourceContext::default(),
+            &mut store,
+            false,
+            2,
+            on_progress,
+        )
+        .unwrap();
+        #[cfg(not(feature = "semantic"))]
+        let count = sync_adapter_chunked(
+            &adapter,
+            &SourceContext::default(),
+            &mut store,
+            2,
+            on_progress,
+        )
+        .unwrap();
+
+        // The three-record session crosses the chunk size but is saved whole.
+        assert_eq!(count, 5);
+        assert_eq!(chunks.into_inner(), vec![3, 2]);
+        assert_eq!(store.get_session("a").unwrap().unwrap().title, "Alpha");
+        let contents: Vec<String> = store
+            .get_session_messages("a")
+            .unwrap()
+            .into_iter()
+            .map(|m| m.content)
+            .collect();
+        assert_eq!(contents, vec!["# Alpha", "one", "two"]);
+        assert_eq!(store.get_file_states("droid").unwrap().len(), 3);
+        assert_eq!(
+            store.get_checkpoint("droid").unwrap(),
+            adapter_common::checkpoint_cursor_from_records(&[NativeRecord {
+                source_id: "a-0002".to_string(),
+                updated_at: "2026-01-01T00:00:09Z".parse().unwrap(),
+                payload: Value::Null,
+            }])
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn sync_adapter_empty() {
         let adapter = FakeAdapter { records: vec![] };
@@ -190,9 +1081,16 @@ mod tests {
         store.init_schema().unwrap();
 
         #[cfg(feature = "semantic")]
-        let count = sync_adapter(&adapter, &mut store, None, |_| {}).unwrap();
+        let count = sync_adapter(
+            &adapter,
+            &SourceContext::default(),
+            &mut store,
+            false,
+            |_| {},
+        )
+        .unwrap();
         #[cfg(not(feature = "semantic"))]
-        let count = sync_adapter(&adapter, &mut store, |_| {}).unwrap();
+        let count = sync_adapter(&adapter, &SourceContext::default(), &mut store, |_| {}).unwrap();
 
         assert_eq!(count, 0);
         assert!(store.get_checkpoint("pi").unwrap().is_none());
@@ -0,0 +1,841 @@
+use chrono::{DateTime, Utc};
+use core_model::{Message, Provenance, RepoRef, Session};
+use serde::Serialize;
+use store_sqlite::SqliteStore;
+use tracing::debug;
+
+#[cfg(feature = "semantic")]
+use embeddings::{Embedder, Reranker};
+#[cfg(feature = "semantic")]
+use search::SemanticCache;
+
+pub use search::{ReasoningFilter, Weights, query_terms};
+
+pub const DEFAULT_LIMIT: usize = 20;
+pub const PREVIEW_MESSAGES: usize = 3;
+const PREVIEW_CHARS: usize = 160;
+// Language filtering happens after ranking, so it needs a deeper pool.
+const LANG_CANDIDATES: usize = 200;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionDisplay {
+    pub session_id: String,
+    pub top_message_id: String,
+    pub title: String,
+    pub agent: String,
+    pub updated_at: DateTime<Utc>,
+    pub message_count: usize,
+    pub snippet: String,
+    // The best message in full, and how many message hits the session merged.
+    #[serde(skip)]
+    pub top_content: String,
+    #[serde(skip)]
+    pub hits: usize,
+    pub score: f32,
+    pub match_text: String,
+    pub preview: Vec<String>,
+    pub channels: ChannelScores,
+    pub provenance: Vec<SessionSource>,
+    pub explain: Option<Explanation>,
+}
+
+// Per-channel parts of `score`, summed over the session's hits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ChannelScores {
+    pub bm25: f32,
+    pub recency: f32,
+    pub semantic: f32,
+    pub substring: f32,
+}
+
+impl From<search::ChannelScores> for ChannelScores {
+    fn from(c: search::ChannelScores) -> Self {
+        Self {
+            bm25: c.bm25,
+            recency: c.recency,
+            semantic: c.semantic,
+            substring: c.substring,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ChannelRank {
+    pub rank: usize,
+    pub raw: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ChannelRanks {
+    pub bm25: Option<ChannelRank>,
+    pub recency: Option<ChannelRank>,
+    pub semantic: Option<ChannelRank>,
+    pub substring: Option<ChannelRank>,
+}
+
+impl From<search::ChannelRanks> for ChannelRanks {
+    fn from(r: search::ChannelRanks) -> Self {
+        let rank = |r: Option<search::ChannelRank>| {
+            r.map(|r| ChannelRank {
+                rank: r.rank,
+                raw: r.raw,
+            })
+        };
+        Self {
+            bm25: rank(r.bm25),
+            recency: rank(r.recency),
+            semantic: rank(r.semantic),
+            substring: rank(r.substring),
+        }
+    }
+}
+
+// Why a session ranked where it did: the best rank its messages reached in
+// each channel, how many of them matched, and which query terms occur in it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Explanation {
+    pub hits: usize,
+    pub ranks: ChannelRanks,
+    pub matched_terms: Vec<String>,
+    pub missing_terms: Vec<String>,
+}
+
+// A log file a session was read from, with the repository it belongs to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SessionSource {
+    pub agent: String,
+    pub source_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repo: Option<RepoRef>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppliedFilters {
+    pub agent: Option<String>,
+    pub title: Option<String>,
+    pub id: Option<String>,
+    pub contains: Option<String>,
+    pub lang: Option<String>,
+    pub reasoning: &'static str,
+    pub raw_fts: bool,
+    pub as_of: Option<DateTime<Utc>>,
+    pub limit: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct CandidateCounts {
+    pub bm25: usize,
+    pub recency: usize,
+    pub semantic: usize,
+    pub substring: usize,
+    pub messages: usize,
+    pub sessions: usize,
+    pub before_filters: usize,
+    pub returned: usize,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FilterSpec {
+    pub agent: Option<String>,
+    pub title: Option<String>,
+    pub id: Option<String>,
+    pub contains: Option<String>,
+}
+
+impl FilterSpec {
+    pub fn is_empty(&self) -> bool {
+        self.agent.is_none() && self.title.is_none() && self.id.is_none() && self.contains.is_none()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonSession {
+    pub id: String,
+    pub top_message_id: String,
+    pub title: String,
+    pub agent: String,
+    pub updated_at: DateTime<Utc>,
+    pub message_count: usize,
+    pub snippet: String,
+    pub score: f32,
+    pub channels: ChannelScores,
+    pub provenance: Vec<SessionSource>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explain: Option<Explanation>,
+}
+
+impl From<SessionDisplay> for JsonSession {
+    fn from(item: SessionDisplay) -> Self {
+        JsonSession {
+            id: item.session_id,
+            top_message_id: item.top_message_id,
+            title: item.title,
+            agent: item.agent,
+            updated_at: item.updated_at,
+            message_count: item.message_count,
+            snippet: item.snippet,
+            score: item.score,
+            channels: item.channels,
+            provenance: item.provenance,
+            explain: item.explain,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonSearchOutput {
+    pub schema_version: u32,
+    pub query: String,
+    pub filters: AppliedFilters,
+    pub candidates: CandidateCounts,
+    pub selected_index: usize,
+    pub selected_session_id: String,
+    pub sessions: Vec<JsonSession>,
+}
+
+// One line of `remi search query --format ndjson`: a session in rank order,
+// with the query it matched so lines can be read on their own.
+#[derive(Debug, Serialize)]
+pub struct NdjsonLine<'a> {
+    pub schema_version: u32,
+    pub query: &'a str,
+    pub rank: usize,
+    pub session: JsonSession,
+}
+
+pub fn ndjson_line(query: &str, rank: usize, session: SessionDisplay) -> NdjsonLine<'_> {
+    NdjsonLine {
+        schema_version: core_model::JSON_SCHEMA_VERSION,
+        query,
+        rank,
+        session: session.into(),
+    }
+}
+
+pub struct SearchRequest<'a> {
+    query: String,
+    limit: usize,
+    raw_fts: bool,
+    reasoning: ReasoningFilter,
+    lang: Option<String>,
+    filters: FilterSpec,
+    as_of: Option<DateTime<Utc>>,
+    explain: bool,
+    weights: Weights,
+    #[cfg(feature = "semantic")]
+    embedder: Option<&'a mut Embedder>,
+    #[cfg(feature = "semantic")]
+    cache: Option<&'a mut SemanticCache>,
+    #[cfg(feature = "semantic")]
+    reranker: Option<&'a mut Reranker>,
+    #[cfg(not(feature = "semantic"))]
+    _semantic: std::marker::PhantomData<&'a ()>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResponse {
+    pub query: String,
+    // Sessions found before `FilterSpec` was applied, to tell "no results"
+    // apart from "filtered everything out".
+    pub unfiltered: usize,
+    pub sessions: Vec<SessionDisplay>,
+    pub filters: AppliedFilters,
+    pub candidates: search::Candidates,
+}
+
+impl<'a> SearchRequest<'a> {
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            limit: DEFAULT_LIMIT,
+            raw_fts: false,
+            reasoning: ReasoningFilter::default(),
+            lang: None,
+            filters: FilterSpec::default(),
+            as_of: None,
+            explain: false,
+            weights: Weights::default(),
+            #[cfg(feature = "semantic")]
+            embedder: None,
+            #[cfg(feature = "semantic")]
+            cache: None,
+            #[cfg(feature = "semantic")]
+            reranker: None,
+            #[cfg(not(feature = "semantic"))]
+            _semantic: std::marker::PhantomData,
+        }
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn raw_fts(mut self, raw_fts: bool) -> Self {
+        self.raw_fts = raw_fts;
+        self
+    }
+
+    pub fn reasoning(mut self, reasoning: ReasoningFilter) -> Self {
+        self.reasoning = reasoning;
+        self
+    }
+
+    pub fn lang(mut self, lang: Option<String>) -> Self {
+        self.lang = lang;
+        self
+    }
+
+    pub fn filters(mut self, filters: FilterSpec) -> Self {
+        self.filters = filters;
+        self
+    }
+
+    // Only messages stored before `as_of` are searched and shown.
+    pub fn as_of(mut self, as_of: Option<DateTime<Utc>>) -> Self {
+        self.as_of = as_of;
+        self
+    }
+
+    pub fn weights(mut self, weights: Weights) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    pub fn explain(mut self, explain: bool) -> Self {
+        self.explain = explain;
+        self
+    }
+
+    #[cfg(feature = "semantic")]
+    pub fn embedder(mut self, embedder: Option<&'a mut Embedder>) -> Self {
+        self.embedder = embedder;
+        self
+    }
+
+    #[cfg(feature = "semantic")]
+    pub fn cache(mut self, cache: Option<&'a mut SemanticCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    #[cfg(feature = "semantic")]
+    pub fn reranker(mut self, reranker: Option<&'a mut Reranker>) -> Self {
+        self.reranker = reranker;
+        self
+    }
+
+    pub fn execute(self, store: &SqliteStore) -> anyhow::Result<SearchResponse> {
+        let lang = self
+            .lang
+            .as_deref()
+            .map(|l| {
+                core_model::canonical_language(l)
+                    .ok_or_else(|| anyhow::anyhow!("unknown language: {l}"))
+            })
+            .transpose()?;
+        let (mut hits, candidates) = search::search_sessions(
+            store,
+            &self.query,
+            if lang.is_some() {
+                LANG_CANDIDATES.max(self.limit)
+            } else {
+                self.limit
+            },
+            self.raw_fts,
+            self.weights,
+            self.reasoning,
+            self.as_of,
+            #[cfg(feature = "semantic")]
+            self.embedder,
+            #[cfg(feature = "semantic")]
+            self.cache,
+            #[cfg(feature = "semantic")]
+            self.reranker,
+        )?;
+        debug!(hits = hits.len(), "search returned hits");
+        if let Some(lang) = lang {
+            let with_lang = store.sessions_with_language(lang)?;
+            hits.retain(|hit| with_lang.contains(&hit.session_id));
+            hits.truncate(self.limit);
+            debug!(lang, hits = hits.len(), "filtered hits by code language");
+        }
+        let sessions = build_session_displays(store, &hits, &self.query, self.as_of, self.explain)?;
+        let unfiltered = sessions.len();
+        let sessions = if self.filters.is_empty() {
+            sessions
+        } else {
+            apply_filters(&sessions, &self.filters)
+        };
+        let filters = AppliedFilters {
+            agent: self.filters.agent,
+            title: self.filters.title,
+            id: self.filters.id,
+            contains: self.filters.contains,
+            lang: lang.map(str::to_string),
+            reasoning: match self.reasoning {
+                ReasoningFilter::Include => "include",
+                ReasoningFilter::Exclude => "exclude",
+                ReasoningFilter::Only => "only",
+            },
+            raw_fts: self.raw_fts,
+            as_of: self.as_of,
+            limit: self.limit,
+        };
+        Ok(SearchResponse {
+            query: self.query,
+            unfiltered,
+            sessions,
+            filters,
+            candidates,
+        })
+    }
+}
+
+impl SearchResponse {
+    pub fn into_json(self, selected_index: usize) -> JsonSearchOutput {
+        let selected_session_id = self
+            .sessions
+            .get(selected_index)
+            .map(|item| item.session_id.clone())
+            .unwrap_or_default();
+        let c = self.candidates;
+        let candidates = CandidateCounts {
+            bm25: c.bm25,
+            recency: c.recency,
+            semantic: c.semantic,
+            substring: c.substring,
+            messages: c.messages,
+            sessions: c.sessions,
+            before_filters: self.unfiltered,
+            returned: self.sessions.len(),
+        };
+        JsonSearchOutput {
+            schema_version: core_model::JSON_SCHEMA_VERSION,
+            query: self.query,
+            filters: self.filters,
+            candidates,
+            selected_index,
+            selected_session_id,
+            sessions: self.sessions.into_iter().map(JsonSession::from).collect(),
+        }
+    }
+}
+
+pub fn build_session_displays(
+    store: &SqliteStore,
+    hits: &[search::SessionHit],
+    query: &str,
+    as_of: Option<DateTime<Utc>>,
+    explain: bool,
+) -> anyhow::Result<Vec<SessionDisplay>> {
+    let terms = query_terms(query);
+    let mut out = Vec::with_capacity(hits.len());
+    for hit in hits {
+        let Some(store_sqlite::SessionBundle {
+            session,
+            mut messages,
+            provenance,
+            ..
+        }) = store.get_session_bundle(&hit.session_id)?
+        else {
+            continue;
+        };
+        if let Some(cutoff) = as_of {
+            let known = store.ingested_before(messages.iter().map(|m| m.id.as_str()), cutoff)?;
+            messages.retain(|m| known.contains(&m.id));
+        }
+        let message_count = messages.len();
+        let title = session_title(&session, &messages);
+        let snippet = truncate_text(&hit.top_content, 140);
+        let match_text = format!(
+            "{} {} {} {}",
+            title,
+            session.id,
+            snippet,
+            session.agent.as_str()
+        );
+        let explain = explain.then(|| explain_hit(hit, &title, &messages, &terms));
+        out.push(SessionDisplay {
+            session_id: session.id.clone(),
+            top_message_id: hit.top_message_id.clone(),
+            title,
+            agent: session.agent.as_str().to_string(),
+            updated_at: session.updated_at,
+            message_count,
+            snippet,
+            top_content: hit.top_content.clone(),
+            hits: hit.hits,
+            score: hit.score,
+            match_text,
+            preview: preview_messages(&messages, &terms, PREVIEW_MESSAGES),
+            channels: hit.channels.into(),
+            provenance: session_sources(&provenance),
+            explain,
+        });
+    }
+    Ok(out)
+}
+
+fn explain_hit(
+    hit: &search::SessionHit,
+    title: &str,
+    messages: &[Message],
+    terms: &[String],
+) -> Explanation {
+    let texts: Vec<String> = std::iter::once(title)
+        .chain(messages.iter().map(|m| m.content.as_str()))
+        .map(str::to_lowercase)
+        .collect();
+    let (matched_terms, missing_terms) = terms
+        .iter()
+        .cloned()
+        .partition(|term| texts.iter().any(|text| text.contains(term.as_str())));
+    Explanation {
+        hits: hit.hits,
+        ranks: hit.ranks.into(),
+        matched_terms,
+        missing_terms,
+    }
+}
+
+// One entry per distinct source file, in the order they were first seen.
+fn session_sources(provenance: &[Provenance]) -> Vec<SessionSource> {
+    let mut out: Vec<SessionSource> = Vec::new();
+    for p in provenance {
+        if out.iter().any(|s| s.source_path == p.source_path) {
+            continue;
+        }
+        out.push(SessionSource {
+            agent: p.agent.as_str().to_string(),
+            source_path: p.source_path.clone(),
+            repo: p.repo.clone(),
+        });
+    }
+    out
+}
+
+pub fn session_title(session: &Session, messages: &[Message]) -> String {
+    let title = session.title.trim();
+    if !title.is_empty() {
+        return title.to_string();
+    }
+    messages
+        .iter()
+        .find(|m| m.role == "user")
+        .map(|m| truncate_text(&m.content, 60))
+        .unwrap_or_else(|| "Untitled session".to_string())
+}
+
+pub fn preview_messages(messages: &[Message], terms: &[String], limit: usize) -> Vec<String> {
+    if terms.is_empty() {
+        return Vec::new();
+    }
+    let mut scored: Vec<(usize, usize, &Message)> = messages
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, m)| {
+            let lower = m.content.to_lowercase();
+            let hits: usize = terms
+                .iter()
+                .map(|term| lower.matches(term.as_str()).count())
+                .sum();
+            (hits > 0).then_some((hits, idx, m))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, _, m)| format!("[{}] {}", m.role, excerpt(&m.content, terms, PREVIEW_CHARS)))
+        .collect()
+}
+
+fn excerpt(content: &str, terms: &[String], max: usize) -> String {
+    let flat = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    let lower = flat.to_lowercase();
+    let first_byte = terms
+        .iter()
+        .filter_map(|term| lower.find(term.as_str()))
+        .min()
+        .unwrap_or(0);
+    let first_char = lower[..first_byte].chars().count();
+    let start = first_char.saturating_sub(max / 3);
+    let body: String = flat.chars().skip(start).collect();
+    let body = truncate_text(&body, max);
+    if start > 0 {
+        format!("...{body}")
+    } else {
+        body
+    }
+}
+
+pub fn parse_fuzzy_query(input: &str) -> (FilterSpec, Vec<String>) {
+    let mut filters = FilterSpec::default();
+    let mut terms = Vec::new();
+    for raw in input.split_whitespace() {
+        let Some((key, value)) = raw.split_once(':') else {
+            terms.push(raw.to_string());
+            continue;
+        };
+        let value = value.trim();
+        if value.is_empty() {
+            continue;
+        }
+        match key {
+            "agent" => filters.agent = Some(value.to_string()),
+            "title" => filters.title = Some(value.to_string()),
+            "id" => filters.id = Some(value.to_string()),
+            "contains" => filters.contains = Some(value.to_string()),
+            _ => terms.push(raw.to_string()),
+        }
+    }
+    (filters, terms)
+}
+
+pub fn apply_filters(items: &[SessionDisplay], filters: &FilterSpec) -> Vec<SessionDisplay> {
+    let agent = filters.agent.as_ref().map(|s| s.to_lowercase());
+    let title = filters.title.as_ref().map(|s| s.to_lowercase());
+    let id = filters.id.as_ref().map(|s| s.to_lowercase());
+    let contains = filters.contains.as_ref().map(|s| s.to_lowercase());
+    items
+        .iter()
+        .filter(|item| {
+            if let Some(agent) = agent.as_ref()
+                && !item.agent.to_lowercase().contains(agent)
+            {
+                return false;
+            }
+            if let Some(title) = title.as_ref()
+                && !item.title.to_lowercase().contains(title)
+            {
+                return false;
+            }
+            if let Some(id) = id.as_ref()
+                && !item.session_id.to_lowercase().contains(id)
+            {
+                return false;
+            }
+            if let Some(contains) = contains.as_ref()
+                && !item.match_text.to_lowercase().contains(contains)
+            {
+                return false;
+            }
+            true
+        })
+        .cloned()
+        .collect()
+}
+
+pub fn truncate_text(input: &str, max: usize) -> String {
+    let mut out = String::new();
+    for (i, ch) in input.chars().enumerate() {
+        if i >= max {
+            out.push_str("...");
+            return out;
+        }
+        out.push(ch);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core_model::{AgentKind, NormalizedBatch};
+
+    fn message(id: &str, content: &str) -> Message {
+        Message {
+            id: id.to_string(),
+            session_id: "s1".to_string(),
+            role: "assistant".to_string(),
+            content: content.to_string(),
+            ts: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn query_terms_drop_operators_and_punctuation() {
+        assert_eq!(
+            query_terms("\"retry logic\" OR backoff* a"),
+            vec!["retry", "logic", "backoff"]
+        );
+    }
+
+    #[test]
+    fn preview_messages_ranks_by_term_hits_and_limits() {
+        let messages = vec![
+            message("m1", "nothing relevant here"),
+            message("m2", "retry once"),
+            message("m3", "retry with backoff, then retry again"),
+            message("m4", "backoff only"),
+            message("m5", "retry"),
+        ];
+        let terms = query_terms("retry backoff");
+        let preview = preview_messages(&messages, &terms, PREVIEW_MESSAGES);
+        assert_eq!(
+            preview,
+            vec![
+                "[assistant] retry with backoff, then retry again",
+                "[assistant] retry once",
+                "[assistant] backoff only",
+            ]
+        );
+    }
+
+    #[test]
+    fn preview_excerpt_centers_on_first_match() {
+        let long = format!("{} needle tail", "word ".repeat(100));
+        let preview = preview_messages(&[message("m1", &long)], &query_terms("needle"), 1);
+        assert!(preview[0].starts_with("[assistant] ..."));
+        assert!(preview[0].contains("needle tail"));
+    }
+
+    fn store_with(sessions: &[(AgentKind, &str, &str)]) -> SqliteStore {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        for (agent, id, content) in sessions {
+            let now = Utc::now();
+            store
+                .save_batch(&NormalizedBatch {
+                    sessions: vec![Session {
+                        id: id.to_string(),
+                        agent: *agent,
+                        source_ref: format!("/test/{id}.jsonl"),
+                        title: format!("{id} title"),
+                        created_at: now,
+                        updated_at: now,
+                    }],
+                    messages: vec![Message {
+                        id: format!("{id}-m1"),
+                        session_id: id.to_string(),
+                        role: "user".to_string(),
+                        content: content.to_string(),
+                        ts: now,
+                    }],
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        store
+    }
+
+    #[test]
+    fn request_builds_filtered_response_and_json() {
+        let mut store = store_with(&[
+            (AgentKind::Claude, "s1", "fix the retry loop"),
+            (AgentKind::Pi, "s2", "retry with backoff"),
+        ]);
+        let source = |id: &str| Provenance {
+            id: format!("p-{id}"),
+            entity_type: "message".to_string(),
+            entity_id: id.to_string(),
+            agent: AgentKind::Pi,
+            source_path: "/test/s2.jsonl".to_string(),
+            source_id: id.to_string(),
+            workspace: None,
+            repo: None,
+        };
+        store
+            .save_batch(&NormalizedBatch {
+                provenance: vec![source("s2-m1"), source("s2")],
+                ..Default::default()
+            })
+            .unwrap();
+        let response = SearchRequest::new("retry").execute(&store).unwrap();
+        assert_eq!(response.unfiltered, 2);
+        assert_eq!(response.sessions.len(), 2);
+
+        let response = SearchRequest::new("retry")
+            .filters(FilterSpec {
+                agent: Some("PI".into()),
+                ..Default::default()
+            })
+            .execute(&store)
+            .unwrap();
+        assert_eq!(response.unfiltered, 2);
+        assert_eq!(response.sessions.len(), 1);
+        let json = serde_json::to_value(response.into_json(0)).unwrap();
+        assert_eq!(json["selected_session_id"], "s2");
+        assert_eq!(json["sessions"][0]["id"], "s2");
+        assert_eq!(json["sessions"][0]["top_message_id"], "s2-m1");
+        assert!(json["sessions"][0].get("match_text").is_none());
+        assert_eq!(json["schema_version"], core_model::JSON_SCHEMA_VERSION);
+        assert_eq!(json["filters"]["agent"], "PI");
+        assert_eq!(json["filters"]["reasoning"], "include");
+        assert_eq!(json["filters"]["limit"], DEFAULT_LIMIT);
+        assert_eq!(json["candidates"]["bm25"], 2);
+        assert_eq!(json["candidates"]["before_filters"], 2);
+        assert_eq!(json["candidates"]["returned"], 1);
+        let channels = &json["sessions"][0]["channels"];
+        assert!(channels["bm25"].as_f64().unwrap() > 0.0);
+        assert_eq!(channels["semantic"], 0.0);
+        assert_eq!(
+            json["sessions"][0]["provenance"],
+            serde_json::json!([{ "agent": "pi", "source_path": "/test/s2.jsonl" }])
+        );
+
+        assert!(json["sessions"][0].get("explain").is_none());
+
+        let response = SearchRequest::new("retry backoff widget")
+            .explain(true)
+            .execute(&store)
+            .unwrap();
+        let json = serde_json::to_value(response.into_json(0)).unwrap();
+        let explain = &json["sessions"][0]["explain"];
+        assert_eq!(json["sessions"][0]["id"], "s2");
+        assert_eq!(explain["hits"], 1);
+        assert_eq!(explain["ranks"]["bm25"]["rank"], 1);
+        assert!(explain["ranks"]["bm25"]["raw"].as_f64().unwrap() > 0.0);
+        assert!(explain["ranks"]["recency"]["raw"].is_null());
+        assert!(explain["ranks"]["semantic"].is_null());
+        assert_eq!(
+            explain["matched_terms"],
+            serde_json::json!(["retry", "backoff"])
+        );
+        assert_eq!(explain["missing_terms"], serde_json::json!(["widget"]));
+
+        assert!(
+            SearchRequest::new("retry")
+                .lang(Some("klingon".into()))
+                .execute(&store)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn as_of_hides_messages_stored_later() {
+        let mut store = store_with(&[(AgentKind::Claude, "s1", "fix the retry loop")]);
+        let cutoff = Utc::now();
+        let mut later = message("s1-m2", "retry passed");
+        later.ts = cutoff - chrono::Duration::days(1);
+        store
+            .save_batch(&NormalizedBatch {
+                messages: vec![later],
+                ..Default::default()
+            })
+            .unwrap();
+
+        let now = SearchRequest::new("passed").execute(&store).unwrap();
+        assert_eq!(now.sessions[0].message_count, 2);
+        assert_eq!(now.sessions[0].top_message_id, "s1-m2");
+        // The later message did not exist yet, so it neither matches nor
+        // counts towards the session.
+        let then = SearchRequest::new("passed")
+            .as_of(Some(cutoff))
+            .execute(&store)
+            .unwrap();
+        assert!(then.sessions.is_empty());
+        let then = SearchRequest::new("retry")
+            .as_of(Some(cutoff))
+            .execute(&store)
+            .unwrap();
+        assert_eq!(then.sessions[0].message_count, 1);
+        assert_eq!(then.sessions[0].top_message_id, "s1-m1");
+        let before = SearchRequest::new("retry")
+            .as_of(Some(cutoff - chrono::Duration::days(1)))
+            .execute(&store)
+            .unwrap();
+        assert_eq!(before.unfiltered, 0);
+    }
+}
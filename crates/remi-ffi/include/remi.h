@@ -0,0 +1,42 @@
+/* C interface to a Remi store. Link against libremi_ffi.
+ *
+ * Results are JSON documents in UTF-8, owned by the caller and released with
+ * remi_string_free. Functions that fail return NULL and leave a message for
+ * remi_last_error on the calling thread. A handle must only be used from one
+ * thread at a time. */
+#ifndef REMI_H
+#define REMI_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct RemiHandle RemiHandle;
+
+/* Opens the database at path, or the CLI's default database when path is
+ * NULL. Release with remi_close. */
+RemiHandle *remi_open(const char *path);
+
+void remi_close(RemiHandle *handle);
+
+/* {"schema_version": 1, "hits": [{"session_id", "message_id", "score",
+ * "content", "hits"}, ...]}, best first. */
+char *remi_search(RemiHandle *handle, const char *query, uint32_t limit);
+
+/* {"schema_version": 1, "session": {"id", "agent", "source_ref", "title",
+ * "created_at", "updated_at", "messages": [{"id", "session_id", "role",
+ * "content", "ts"}, ...]}}, with "session": null for an unknown id. */
+char *remi_get_session(RemiHandle *handle, const char *id);
+
+void remi_string_free(char *s);
+
+/* The last error on this thread, or NULL. Valid until the next call. */
+const char *remi_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,245 @@
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char};
+use std::ptr;
+
+use remi::core_model::JSON_SCHEMA_VERSION;
+use serde_json::{Value, json};
+
+// C ABI over the `remi` facade; `include/remi.h` declares it. Results are
+// JSON strings so that callers such as LuaJIT need no struct layouts.
+
+pub struct RemiHandle {
+    inner: remi::Remi,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+fn clear_error() {
+    LAST_ERROR.with(|e| *e.borrow_mut() = None);
+}
+
+// Turns a result into an owned string for the caller, or NULL with the error
+// recorded.
+fn respond(result: anyhow::Result<Value>) -> *mut c_char {
+    match result.and_then(|value| Ok(CString::new(serde_json::to_string(&value)?)?)) {
+        Ok(s) => {
+            clear_error();
+            s.into_raw()
+        }
+        Err(e) => {
+            set_error(format!("{e:#}"));
+            ptr::null_mut()
+        }
+    }
+}
+
+unsafe fn text<'a>(s: *const c_char, what: &str) -> anyhow::Result<&'a str> {
+    if s.is_null() {
+        anyhow::bail!("{what} is NULL");
+    }
+    // SAFETY: the caller passes a NUL-terminated string that outlives the call.
+    let s = unsafe { CStr::from_ptr(s) };
+    s.to_str()
+        .map_err(|_| anyhow::anyhow!("{what} is not valid UTF-8"))
+}
+
+unsafe fn handle<'a>(handle: *mut RemiHandle) -> anyhow::Result<&'a mut RemiHandle> {
+    // SAFETY: the caller passes a handle from `remi_open` that is not closed.
+    unsafe { handle.as_mut() }.ok_or_else(|| anyhow::anyhow!("handle is NULL"))
+}
+
+/// Opens the database at `path`, or the CLI's default one when `path` is
+/// NULL. Returns NULL on error.
+///
+/// # Safety
+///
+/// `path` must be NULL or a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn remi_open(path: *const c_char) -> *mut RemiHandle {
+    let opened = if path.is_null() {
+        remi::Remi::open()
+    } else {
+        unsafe { text(path, "path") }.and_then(remi::Remi::open_at)
+    };
+    match opened {
+        Ok(inner) => {
+            clear_error();
+            Box::into_raw(Box::new(RemiHandle { inner }))
+        }
+        Err(e) => {
+            set_error(format!("{e:#}"));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// # Safety
+///
+/// `handle` must be NULL or come from `remi_open`, and is invalid afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn remi_close(handle: *mut RemiHandle) {
+    if !handle.is_null() {
+        drop(unsafe { Box::from_raw(handle) });
+    }
+}
+
+/// # Safety
+///
+/// `handle` must come from `remi_open` and `query` must be a NUL-terminated
+/// string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn remi_search(
+    handle: *mut RemiHandle,
+    query: *const c_char,
+    limit: u32,
+) -> *mut c_char {
+    respond((|| {
+        let remi = unsafe { self::handle(handle) }?;
+        let query = unsafe { text(query, "query") }?;
+        let hits: Vec<Value> = remi
+            .inner
+            .search(query, limit as usize)?
+            .into_iter()
+            .map(|h| {
+                json!({
+                    "session_id": h.session_id,
+                    "message_id": h.top_message_id,
+                    "score": h.score,
+                    "content": h.top_content,
+                    "hits": h.hits,
+                })
+            })
+            .collect();
+        Ok(json!({ "schema_version": JSON_SCHEMA_VERSION, "hits": hits }))
+    })())
+}
+
+/// # Safety
+///
+/// `handle` must come from `remi_open` and `id` must be a NUL-terminated
+/// string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn remi_get_session(
+    handle: *mut RemiHandle,
+    id: *const c_char,
+) -> *mut c_char {
+    respond((|| {
+        let remi = unsafe { self::handle(handle) }?;
+        let id = unsafe { text(id, "id") }?;
+        let session = remi.inner.store().get_session_bundle(id)?.map(|bundle| {
+            let s = bundle.session;
+            let messages: Vec<Value> = bundle
+                .messages
+                .iter()
+                .map(|m| {
+                    json!({
+                        "id": m.id,
+                        "session_id": m.session_id,
+                        "role": m.role,
+                        "content": m.content,
+                        "ts": m.ts,
+                    })
+                })
+                .collect();
+            json!({
+                "id": s.id,
+                "agent": s.agent.as_str(),
+                "source_ref": s.source_ref,
+                "title": s.title,
+                "created_at": s.created_at,
+                "updated_at": s.updated_at,
+                "messages": messages,
+            })
+        });
+        Ok(json!({ "schema_version": JSON_SCHEMA_VERSION, "session": session }))
+    })())
+}
+
+/// # Safety
+///
+/// `s` must be NULL or a string returned by this library, not yet freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn remi_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn remi_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn take(s: *mut c_char) -> Value {
+        assert!(!s.is_null(), "{:?}", unsafe {
+            CStr::from_ptr(remi_last_error())
+        });
+        let value = serde_json::from_str(unsafe { CStr::from_ptr(s) }.to_str().unwrap()).unwrap();
+        unsafe { remi_string_free(s) };
+        value
+    }
+
+    #[test]
+    fn opens_searches_and_reads_sessions_through_the_c_abi() {
+        let root = std::env::temp_dir().join(format!("remi_ffi_{}", std::process::id()));
+        let sessions = root.join("home/.pi/agent/sessions");
+        std::fs::create_dir_all(&sessions).unwrap();
+        std::fs::write(
+            sessions.join("s1.jsonl"),
+            concat!(
+                r#"{"type":"session","version":3,"id":"s1","timestamp":"2025-05-05T10:00:00Z","cwd":"/tmp"}"#,
+                "\n",
+                r#"{"type":"message","id":"m1","parentId":null,"timestamp":"2025-05-05T10:00:01Z","message":{"role":"user","content":[{"type":"text","text":"the login redirect loops"}]}}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+        let db_path = root.join("data/remi.db");
+        remi::Remi::open_at(&db_path)
+            .unwrap()
+            .with_sources(remi::SourceContext::with_home(root.join("home")))
+            .sync(remi::AgentKind::Pi)
+            .unwrap();
+
+        let db = CString::new(db_path.to_str().unwrap()).unwrap();
+        let handle = unsafe { remi_open(db.as_ptr()) };
+        assert!(!handle.is_null());
+
+        let query = CString::new("login redirect").unwrap();
+        let found = take(unsafe { remi_search(handle, query.as_ptr(), 5) });
+        assert_eq!(found["schema_version"], JSON_SCHEMA_VERSION);
+        let session_id = found["hits"][0]["session_id"].as_str().unwrap();
+        assert!(
+            found["hits"][0]["content"]
+                .as_str()
+                .unwrap()
+                .contains("login redirect")
+        );
+
+        let id = CString::new(session_id).unwrap();
+        let session = take(unsafe { remi_get_session(handle, id.as_ptr()) });
+        assert_eq!(session["session"]["agent"], "pi");
+        assert_eq!(session["session"]["messages"][0]["role"], "user");
+        let missing = CString::new("missing").unwrap();
+        let none = take(unsafe { remi_get_session(handle, missing.as_ptr()) });
+        assert!(none["session"].is_null());
+
+        assert!(unsafe { remi_search(handle, ptr::null(), 5) }.is_null());
+        let error = unsafe { CStr::from_ptr(remi_last_error()) };
+        assert_eq!(error.to_str().unwrap(), "query is NULL");
+
+        unsafe { remi_close(handle) };
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,7 @@
+// Compiles the proto with protox, so building needs no protoc install.
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=proto/remi.proto");
+    let descriptors = protox::compile(["remi.proto"], ["proto"])?;
+    tonic_build::configure().compile_fds(descriptors)?;
+    Ok(())
+}
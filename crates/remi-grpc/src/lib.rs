@@ -0,0 +1,477 @@
+// A tonic gRPC front end for a Remi store: search, whole sessions, and a
+// stream of messages as they are stored. The stream polls the store the way
+// `remi tail` does, so it picks up `remi sync` runs from other processes.
+//
+//     let service = remi_grpc::Service::new(store).with_interval(interval);
+//     remi_grpc::serve(service, std::net::TcpListener::bind(addr)?)?;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use remi::{AgentKind, core_model, store::SqliteStore};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::{Request, Response, Status};
+
+pub mod proto {
+    tonic::include_proto!("remi.v1");
+}
+
+use proto::remi_server::RemiServer;
+
+const BATCH: i64 = 500;
+const DEFAULT_LIMIT: usize = 20;
+const MAX_LIMIT: usize = 500;
+
+// Applied to titles and message content before they leave the process.
+pub type Redact = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+#[derive(Clone)]
+pub struct Service {
+    remi: Arc<Mutex<remi::Remi>>,
+    interval: Duration,
+    redact: Option<Redact>,
+}
+
+impl Service {
+    pub fn new(store: SqliteStore) -> Self {
+        Self {
+            remi: Arc::new(Mutex::new(remi::Remi::from_store(store))),
+            interval: Duration::from_secs(2),
+            redact: None,
+        }
+    }
+
+    // How often each StreamNewMessages call polls the store.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn with_redact(mut self, redact: Redact) -> Self {
+        self.redact = Some(redact);
+        self
+    }
+
+    // SQLite calls are blocking, so they run off the async workers; one
+    // connection serves every request in turn.
+    async fn read<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&remi::Remi) -> anyhow::Result<T> + Send + 'static,
+    ) -> Result<T, Status> {
+        let remi = self.remi.clone();
+        tokio::task::spawn_blocking(move || {
+            let remi = remi
+                .lock()
+                .map_err(|_| anyhow::anyhow!("store lock poisoned"))?;
+            f(&remi)
+        })
+        .await
+        .map_err(|e| Status::internal(e.to_string()))?
+        .map_err(|e| Status::internal(format!("{e:#}")))
+    }
+}
+
+// Serves until the listener fails; the runtime lives inside so callers stay
+// synchronous.
+pub fn serve(service: Service, listener: std::net::TcpListener) -> anyhow::Result<()> {
+    listener.set_nonblocking(true)?;
+    tokio::runtime::Runtime::new()?
+        .block_on(async { run(service, tokio::net::TcpListener::from_std(listener)?).await })
+}
+
+pub async fn run(service: Service, listener: tokio::net::TcpListener) -> anyhow::Result<()> {
+    tonic::transport::Server::builder()
+        .add_service(RemiServer::new(service))
+        .serve_with_incoming(TcpListenerStream::new(listener))
+        .await?;
+    Ok(())
+}
+
+#[tonic::async_trait]
+impl proto::remi_server::Remi for Service {
+    async fn search(
+        &self,
+        request: Request<proto::SearchRequest>,
+    ) -> Result<Response<proto::SearchResponse>, Status> {
+        let proto::SearchRequest { query, limit } = request.into_inner();
+        if query.trim().is_empty() {
+            return Err(Status::invalid_argument("query is empty"));
+        }
+        let limit = match limit as usize {
+            0 => DEFAULT_LIMIT,
+            n => n.min(MAX_LIMIT),
+        };
+        let hits = self.read(move |remi| remi.search(&query, limit)).await?;
+        let hits = hits
+            .into_iter()
+            .map(|h| proto::SearchHit {
+                session_id: h.session_id,
+                message_id: h.top_message_id,
+                content: redacted(self.redact.as_ref(), h.top_content),
+                score: h.score,
+                hits: h.hits as u32,
+            })
+            .collect();
+        Ok(Response::new(proto::SearchResponse { hits }))
+    }
+
+    async fn get_session(
+        &self,
+        request: Request<proto::GetSessionRequest>,
+    ) -> Result<Response<proto::GetSessionResponse>, Status> {
+        let id = request.into_inner().id;
+        let bundle = self
+            .read({
+                let id = id.clone();
+                move |remi| remi.store().get_session_bundle(&id)
+            })
+            .await?
+            .ok_or_else(|| Status::not_found(format!("session {id} not found")))?;
+        let redact = self.redact.as_ref();
+        Ok(Response::new(proto::GetSessionResponse {
+            session: Some(session(bundle.session, redact)),
+            messages: bundle
+                .messages
+                .into_iter()
+                .map(|m| message(m, redact))
+                .collect(),
+        }))
+    }
+
+    type StreamNewMessagesStream = ReceiverStream<Result<proto::NewMessage, Status>>;
+
+    async fn stream_new_messages(
+        &self,
+        request: Request<proto::StreamNewMessagesRequest>,
+    ) -> Result<Response<Self::StreamNewMessagesStream>, Status> {
+        let proto::StreamNewMessagesRequest {
+            agent,
+            session_id,
+            backlog,
+        } = request.into_inner();
+        if let Some(agent) = &agent {
+            agent
+                .parse::<AgentKind>()
+                .map_err(|e| Status::invalid_argument(format!("{e:#}")))?;
+        }
+        let feed = Feed {
+            cursor: 0,
+            agent,
+            session_id,
+            sessions: HashMap::new(),
+            redact: self.redact.clone(),
+        };
+        let (mut feed, backlog) = self
+            .read(move |remi| {
+                let mut feed = feed;
+                let backlog = feed.start(remi.store(), backlog)?;
+                Ok((feed, backlog))
+            })
+            .await?;
+
+        let (tx, rx) = mpsc::channel(64);
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut batch = backlog;
+            loop {
+                for item in batch {
+                    if tx.send(Ok(item)).await.is_err() {
+                        return;
+                    }
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(service.interval) => {}
+                    _ = tx.closed() => return,
+                }
+                match service
+                    .read(move |remi| {
+                        let batch = feed.poll(remi.store())?;
+                        Ok((feed, batch))
+                    })
+                    .await
+                {
+                    Ok((next, polled)) => (feed, batch) = (next, polled),
+                    Err(status) => {
+                        tracing::warn!(%status, "message stream stopped");
+                        let _ = tx.send(Err(status)).await;
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
+
+// One subscriber's position in the messages table, as in `remi tail`.
+struct Feed {
+    cursor: i64,
+    agent: Option<String>,
+    session_id: Option<String>,
+    sessions: HashMap<String, Option<proto::Session>>,
+    redact: Option<Redact>,
+}
+
+impl Feed {
+    fn start(
+        &mut self,
+        store: &SqliteStore,
+        backlog: u32,
+    ) -> anyhow::Result<Vec<proto::NewMessage>> {
+        self.cursor = store.message_cursor()?;
+        if backlog == 0 {
+            return Ok(Vec::new());
+        }
+        let rows = store.latest_messages(
+            self.agent.as_deref(),
+            self.session_id.as_deref(),
+            i64::from(backlog).min(BATCH),
+        )?;
+        self.items(store, rows)
+    }
+
+    fn poll(&mut self, store: &SqliteStore) -> anyhow::Result<Vec<proto::NewMessage>> {
+        let head = store.message_cursor()?;
+        let mut rows = Vec::new();
+        loop {
+            let batch = store.messages_after(
+                self.cursor,
+                self.agent.as_deref(),
+                self.session_id.as_deref(),
+                BATCH,
+            )?;
+            let done = (batch.len() as i64) < BATCH;
+            if let Some((cursor, _)) = batch.last() {
+                self.cursor = *cursor;
+            }
+            rows.extend(batch);
+            if done {
+                break;
+            }
+        }
+        self.cursor = self.cursor.max(head);
+        self.items(store, rows)
+    }
+
+    fn items(
+        &mut self,
+        store: &SqliteStore,
+        rows: Vec<(i64, core_model::Message)>,
+    ) -> anyhow::Result<Vec<proto::NewMessage>> {
+        let redact = self.redact.as_ref();
+        let mut out = Vec::with_capacity(rows.len());
+        for (cursor, m) in rows {
+            if !self.sessions.contains_key(&m.session_id) {
+                let found = store.get_session(&m.session_id)?;
+                self.sessions
+                    .insert(m.session_id.clone(), found.map(|s| session(s, redact)));
+            }
+            out.push(proto::NewMessage {
+                cursor,
+                session: self.sessions[&m.session_id].clone(),
+                message: Some(message(m, redact)),
+            });
+        }
+        Ok(out)
+    }
+}
+
+fn redacted(redact: Option<&Redact>, text: String) -> String {
+    match redact {
+        Some(redact) => redact(&text),
+        None => text,
+    }
+}
+
+fn session(s: core_model::Session, redact: Option<&Redact>) -> proto::Session {
+    proto::Session {
+        id: s.id,
+        agent: s.agent.as_str().to_string(),
+        source_ref: s.source_ref,
+        title: redacted(redact, s.title),
+        created_at: s.created_at.to_rfc3339(),
+        updated_at: s.updated_at.to_rfc3339(),
+    }
+}
+
+fn message(m: core_model::Message, redact: Option<&Redact>) -> proto::Message {
+    proto::Message {
+        id: m.id,
+        session_id: m.session_id,
+        role: m.role,
+        content: redacted(redact, m.content),
+        ts: m.ts.to_rfc3339(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use core_model::NormalizedBatch;
+    use proto::remi_client::RemiClient;
+    use tonic::{Code, transport::Channel};
+
+    fn save(store: &mut SqliteStore, agent: AgentKind, session: &str, id: &str, content: &str) {
+        let at = Utc.with_ymd_and_hms(2025, 5, 1, 9, 30, 0).unwrap();
+        store
+            .save_batch(&NormalizedBatch {
+                sessions: vec![core_model::Session {
+                    id: session.to_string(),
+                    agent,
+                    source_ref: format!("/tmp/{session}.jsonl"),
+                    title: format!("{session} title"),
+                    created_at: at,
+                    updated_at: at,
+                }],
+                messages: vec![core_model::Message {
+                    id: id.to_string(),
+                    session_id: session.to_string(),
+                    role: "user".to_string(),
+                    content: content.to_string(),
+                    ts: at,
+                }],
+                ..Default::default()
+            })
+            .unwrap();
+    }
+
+    fn open(root: &std::path::Path) -> SqliteStore {
+        std::fs::create_dir_all(root).unwrap();
+        let store = SqliteStore::open(root.join("remi.db")).unwrap();
+        store.init_schema().unwrap();
+        store
+    }
+
+    async fn connect(service: Service) -> RemiClient<Channel> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(run(service, listener));
+        RemiClient::connect(format!("http://{addr}")).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn searches_and_fetches_sessions() {
+        let root = std::env::temp_dir().join(format!("remi_grpc_search_{}", std::process::id()));
+        let mut store = open(&root);
+        save(
+            &mut store,
+            AgentKind::Claude,
+            "s1",
+            "m1",
+            "the login redirect loops",
+        );
+        let mut client = connect(Service::new(store)).await;
+
+        let hits = client
+            .search(proto::SearchRequest {
+                query: "login redirect".into(),
+                limit: 5,
+            })
+            .await
+            .unwrap()
+            .into_inner()
+            .hits;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].session_id, "s1");
+        assert_eq!(hits[0].message_id, "m1");
+        let empty = client
+            .search(proto::SearchRequest {
+                query: " ".into(),
+                limit: 0,
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(empty.code(), Code::InvalidArgument);
+
+        let found = client
+            .get_session(proto::GetSessionRequest { id: "s1".into() })
+            .await
+            .unwrap()
+            .into_inner();
+        let session = found.session.unwrap();
+        assert_eq!(session.agent, "claude");
+        assert_eq!(session.title, "s1 title");
+        assert_eq!(session.created_at, "2025-05-01T09:30:00+00:00");
+        assert_eq!(found.messages.len(), 1);
+        assert_eq!(found.messages[0].content, "the login redirect loops");
+        let missing = client
+            .get_session(proto::GetSessionRequest { id: "nope".into() })
+            .await
+            .unwrap_err();
+        assert_eq!(missing.code(), Code::NotFound);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn streams_messages_stored_by_another_connection() {
+        let root = std::env::temp_dir().join(format!("remi_grpc_stream_{}", std::process::id()));
+        let mut store = open(&root);
+        save(&mut store, AgentKind::Claude, "s1", "m0", "older");
+        save(
+            &mut store,
+            AgentKind::Claude,
+            "s1",
+            "m1",
+            "before subscribing",
+        );
+        let service = Service::new(store)
+            .with_interval(Duration::from_millis(20))
+            .with_redact(Arc::new(|text: &str| text.replace("hunter2", "[redacted]")));
+        let mut client = connect(service).await;
+
+        let bad = client
+            .stream_new_messages(proto::StreamNewMessagesRequest {
+                agent: Some("nope".into()),
+                ..Default::default()
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(bad.code(), Code::InvalidArgument);
+
+        let mut stream = client
+            .stream_new_messages(proto::StreamNewMessagesRequest {
+                agent: Some("claude".into()),
+                session_id: None,
+                backlog: 1,
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        let first = stream.message().await.unwrap().unwrap();
+        assert_eq!(first.message.unwrap().id, "m1");
+        assert_eq!(first.session.unwrap().title, "s1 title");
+
+        // Written through a second connection, as `remi sync` would.
+        let mut writer = open(&root);
+        save(
+            &mut writer,
+            AgentKind::Claude,
+            "s1",
+            "m2",
+            "after subscribing",
+        );
+        save(&mut writer, AgentKind::Pi, "s2", "m3", "another agent");
+        save(
+            &mut writer,
+            AgentKind::Claude,
+            "s3",
+            "m4",
+            "password hunter2",
+        );
+
+        let second = stream.message().await.unwrap().unwrap();
+        assert_eq!(second.message.unwrap().id, "m2");
+        let third = stream.message().await.unwrap().unwrap();
+        assert!(third.cursor > second.cursor);
+        let message = third.message.unwrap();
+        assert_eq!(message.id, "m4");
+        assert_eq!(message.content, "password [redacted]");
+        assert_eq!(third.session.unwrap().id, "s3");
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,37 @@
+import assert from 'node:assert/strict'
+import { mkdirSync, mkdtempSync, rmSync, writeFileSync } from 'node:fs'
+import { createRequire } from 'node:module'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { test } from 'node:test'
+
+const { Remi } = createRequire(import.meta.url)('../index.js')
+
+test('searches and reads sessions in-process', () => {
+  const root = mkdtempSync(join(tmpdir(), 'remi-node-'))
+  const sessions = join(root, 'home/.pi/agent/sessions')
+  mkdirSync(sessions, { recursive: true })
+  writeFileSync(
+    join(sessions, 's1.jsonl'),
+    [
+      '{"type":"session","version":3,"id":"s1","timestamp":"2025-05-05T10:00:00Z","cwd":"/tmp"}',
+      '{"type":"message","id":"m1","parentId":null,"timestamp":"2025-05-05T10:00:01Z","message":{"role":"user","content":[{"type":"text","text":"the login redirect loops"}]}}',
+      '',
+    ].join('\n'),
+  )
+  try {
+    const remi = Remi.open(join(root, 'data/remi.db'), join(root, 'home'))
+    assert.ok(remi.sync('pi') > 0)
+    const hits = remi.search('login redirect')
+    assert.equal(hits.length, 1)
+    assert.match(hits[0].content, /login redirect/)
+    const session = remi.getSession(hits[0].sessionId)
+    assert.equal(session.agent, 'pi')
+    assert.ok(session.createdAt instanceof Date)
+    assert.deepEqual(session.messages.map((m) => m.role), ['user'])
+    assert.equal(remi.getSession('missing'), null)
+    assert.throws(() => remi.sync('nope'), /unknown agent kind/)
+  } finally {
+    rmSync(root, { recursive: true, force: true })
+  }
+})
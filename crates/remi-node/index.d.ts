@@ -0,0 +1,35 @@
+export interface Message {
+  id: string
+  sessionId: string
+  role: string
+  content: string
+  ts: Date
+}
+
+export interface Session {
+  id: string
+  agent: string
+  sourceRef: string
+  title: string
+  createdAt: Date
+  updatedAt: Date
+  messages: Array<Message>
+}
+
+export interface Hit {
+  sessionId: string
+  messageId: string
+  score: number
+  content: string
+  /** Message hits merged into this session. */
+  hits: number
+}
+
+export declare class Remi {
+  /** Opens the CLI's default database, or the one at `path`. `home` replaces the home directory agent sources are looked for under. */
+  static open(path?: string | undefined | null, home?: string | undefined | null): Remi
+  /** Syncs one agent by name, or every compiled-in agent. Blocks the calling thread. */
+  sync(agent?: string | undefined | null): number
+  search(query: string, limit?: number | undefined | null): Array<Hit>
+  getSession(id: string): Session | null
+}
@@ -0,0 +1,131 @@
+use chrono::{DateTime, Utc};
+use napi::{Error, Result};
+use napi_derive::napi;
+
+// Node bindings over the `remi` facade, for editor extensions and Electron
+// tools that read the store in-process. Records are plain objects with
+// camelCase fields and `Date` timestamps.
+
+fn js_error(e: anyhow::Error) -> Error {
+    Error::from_reason(format!("{e:#}"))
+}
+
+#[napi(object)]
+pub struct Message {
+    pub id: String,
+    pub session_id: String,
+    pub role: String,
+    pub content: String,
+    pub ts: DateTime<Utc>,
+}
+
+#[napi(object)]
+pub struct Session {
+    pub id: String,
+    pub agent: String,
+    pub source_ref: String,
+    pub title: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub messages: Vec<Message>,
+}
+
+#[napi(object)]
+pub struct Hit {
+    pub session_id: String,
+    pub message_id: String,
+    pub score: f64,
+    pub content: String,
+    // Message hits merged into this session.
+    pub hits: u32,
+}
+
+#[napi]
+pub struct Remi {
+    inner: remi::Remi,
+}
+
+#[napi]
+impl Remi {
+    // Opens the CLI's default database, or the one at `path`. `home` replaces
+    // the home directory that agent sources are looked for under.
+    #[napi(factory)]
+    pub fn open(path: Option<String>, home: Option<String>) -> Result<Self> {
+        let mut inner = match path {
+            Some(path) => remi::Remi::open_at(path),
+            None => remi::Remi::open(),
+        }
+        .map_err(js_error)?;
+        if let Some(home) = home {
+            inner = inner.with_sources(remi::SourceContext::with_home(home));
+        }
+        Ok(Self { inner })
+    }
+
+    // Syncs one agent by name, or every compiled-in agent, and returns the
+    // number of source records read. It blocks the calling thread.
+    #[napi]
+    pub fn sync(&mut self, agent: Option<String>) -> Result<u32> {
+        let count = match agent {
+            Some(agent) => {
+                let kind = agent
+                    .parse()
+                    .map_err(|e: anyhow::Error| Error::from_reason(e.to_string()))?;
+                self.inner.sync(kind)
+            }
+            None => self.inner.sync_all(),
+        }
+        .map_err(js_error)?;
+        Ok(count as u32)
+    }
+
+    #[napi]
+    pub fn search(&self, query: String, limit: Option<u32>) -> Result<Vec<Hit>> {
+        let hits = self
+            .inner
+            .search(&query, limit.unwrap_or(10) as usize)
+            .map_err(js_error)?;
+        Ok(hits
+            .into_iter()
+            .map(|h| Hit {
+                session_id: h.session_id,
+                message_id: h.top_message_id,
+                score: f64::from(h.score),
+                content: h.top_content,
+                hits: h.hits as u32,
+            })
+            .collect())
+    }
+
+    #[napi]
+    pub fn get_session(&self, id: String) -> Result<Option<Session>> {
+        let Some(bundle) = self
+            .inner
+            .store()
+            .get_session_bundle(&id)
+            .map_err(js_error)?
+        else {
+            return Ok(None);
+        };
+        let s = bundle.session;
+        Ok(Some(Session {
+            id: s.id,
+            agent: s.agent.as_str().to_string(),
+            source_ref: s.source_ref,
+            title: s.title,
+            created_at: s.created_at,
+            updated_at: s.updated_at,
+            messages: bundle
+                .messages
+                .into_iter()
+                .map(|m| Message {
+                    id: m.id,
+                    session_id: m.session_id,
+                    role: m.role,
+                    content: m.content,
+                    ts: m.ts,
+                })
+                .collect(),
+        }))
+    }
+}
@@ -0,0 +1,35 @@
+from datetime import datetime
+from os import PathLike
+from typing import Optional, Union
+
+_Path = Union[str, PathLike[str]]
+
+class Message:
+    id: str
+    session_id: str
+    role: str
+    content: str
+    ts: datetime
+
+class Session:
+    id: str
+    agent: str
+    source_ref: str
+    title: str
+    created_at: datetime
+    updated_at: datetime
+    messages: list[Message]
+
+class Hit:
+    session_id: str
+    message_id: str
+    score: float
+    content: str
+    hits: int
+
+class Remi:
+    def sync(self, agent: Optional[str] = None) -> int: ...
+    def search(self, query: str, limit: int = 10) -> list[Hit]: ...
+    def get_session(self, id: str) -> Optional[Session]: ...
+
+def open(path: Optional[_Path] = None, home: Optional[_Path] = None) -> Remi: ...
@@ -0,0 +1,232 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+
+// Python bindings over the `remi` facade. Records are returned as frozen
+// copies, so a notebook can hold on to them after the store is closed.
+
+fn runtime_error(e: anyhow::Error) -> PyErr {
+    PyRuntimeError::new_err(format!("{e:#}"))
+}
+
+#[pyclass(frozen, get_all, module = "remi")]
+#[derive(Clone)]
+pub struct Message {
+    id: String,
+    session_id: String,
+    role: String,
+    content: String,
+    ts: DateTime<Utc>,
+}
+
+impl From<remi::core_model::Message> for Message {
+    fn from(m: remi::core_model::Message) -> Self {
+        Self {
+            id: m.id,
+            session_id: m.session_id,
+            role: m.role,
+            content: m.content,
+            ts: m.ts,
+        }
+    }
+}
+
+#[pymethods]
+impl Message {
+    fn __repr__(&self) -> String {
+        format!(
+            "Message(id={:?}, role={:?}, ts={})",
+            self.id,
+            self.role,
+            self.ts.to_rfc3339()
+        )
+    }
+}
+
+#[pyclass(frozen, get_all, module = "remi")]
+#[derive(Clone)]
+pub struct Session {
+    id: String,
+    agent: String,
+    source_ref: String,
+    title: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    messages: Vec<Message>,
+}
+
+#[pymethods]
+impl Session {
+    fn __repr__(&self) -> String {
+        format!(
+            "Session(id={:?}, agent={:?}, title={:?}, messages={})",
+            self.id,
+            self.agent,
+            self.title,
+            self.messages.len()
+        )
+    }
+}
+
+#[pyclass(frozen, get_all, module = "remi")]
+pub struct Hit {
+    session_id: String,
+    message_id: String,
+    score: f32,
+    content: String,
+    // Message hits merged into this session.
+    hits: usize,
+}
+
+#[pymethods]
+impl Hit {
+    fn __repr__(&self) -> String {
+        format!(
+            "Hit(session_id={:?}, score={:.4}, hits={})",
+            self.session_id, self.score, self.hits
+        )
+    }
+}
+
+// A store handle. SQLite connections stay on the thread that opened them.
+#[pyclass(unsendable, module = "remi", name = "Remi")]
+pub struct PyRemi {
+    inner: remi::Remi,
+}
+
+#[pymethods]
+impl PyRemi {
+    // Syncs one agent by name, or every compiled-in agent, and returns the
+    // number of source records read.
+    #[pyo3(signature = (agent = None))]
+    fn sync(&mut self, agent: Option<&str>) -> PyResult<usize> {
+        match agent {
+            Some(agent) => {
+                let kind = agent
+                    .parse()
+                    .map_err(|e: anyhow::Error| PyValueError::new_err(e.to_string()))?;
+                self.inner.sync(kind)
+            }
+            None => self.inner.sync_all(),
+        }
+        .map_err(runtime_error)
+    }
+
+    #[pyo3(signature = (query, limit = 10))]
+    fn search(&self, query: &str, limit: usize) -> PyResult<Vec<Hit>> {
+        let hits = self.inner.search(query, limit).map_err(runtime_error)?;
+        Ok(hits
+            .into_iter()
+            .map(|h| Hit {
+                session_id: h.session_id,
+                message_id: h.top_message_id,
+                score: h.score,
+                content: h.top_content,
+                hits: h.hits,
+            })
+            .collect())
+    }
+
+    fn get_session(&self, id: &str) -> PyResult<Option<Session>> {
+        let Some(bundle) = self
+            .inner
+            .store()
+            .get_session_bundle(id)
+            .map_err(runtime_error)?
+        else {
+            return Ok(None);
+        };
+        let s = bundle.session;
+        Ok(Some(Session {
+            id: s.id,
+            agent: s.agent.as_str().to_string(),
+            source_ref: s.source_ref,
+            title: s.title,
+            created_at: s.created_at,
+            updated_at: s.updated_at,
+            messages: bundle.messages.into_iter().map(Message::from).collect(),
+        }))
+    }
+}
+
+// Opens the CLI's default database, or the one at `path`. `home` replaces
+// the home directory that agent sources are looked for under.
+#[pyfunction]
+#[pyo3(signature = (path = None, home = None))]
+fn open(path: Option<PathBuf>, home: Option<PathBuf>) -> PyResult<PyRemi> {
+    let mut inner = match path {
+        Some(path) => remi::Remi::open_at(path),
+        None => remi::Remi::open(),
+    }
+    .map_err(runtime_error)?;
+    if let Some(home) = home {
+        inner = inner.with_sources(remi::SourceContext::with_home(home));
+    }
+    Ok(PyRemi { inner })
+}
+
+#[pymodule]
+#[pyo3(name = "remi")]
+fn remi_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(open, m)?)?;
+    m.add_class::<PyRemi>()?;
+    m.add_class::<Session>()?;
+    m.add_class::<Message>()?;
+    m.add_class::<Hit>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::types::PyDict;
+
+    #[test]
+    fn python_opens_syncs_searches_and_reads_sessions() {
+        let root = std::env::temp_dir().join(format!("remi_py_{}", std::process::id()));
+        let sessions = root.join("home/.pi/agent/sessions");
+        std::fs::create_dir_all(&sessions).unwrap();
+        std::fs::write(
+            sessions.join("s1.jsonl"),
+            concat!(
+                r#"{"type":"session","version":3,"id":"s1","timestamp":"2025-05-05T10:00:00Z","cwd":"/tmp"}"#,
+                "\n",
+                r#"{"type":"message","id":"m1","parentId":null,"timestamp":"2025-05-05T10:00:01Z","message":{"role":"user","content":[{"type":"text","text":"the login redirect loops"}]}}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+
+        Python::with_gil(|py| -> PyResult<()> {
+            let module = PyModule::new(py, "remi")?;
+            remi_py(&module)?;
+            let locals = PyDict::new(py);
+            locals.set_item("remi", module)?;
+            locals.set_item("db_path", root.join("data/remi.db"))?;
+            locals.set_item("home", root.join("home"))?;
+            py.run(
+                cr#"
+db = remi.open(db_path, home=home)
+assert db.sync("pi") > 0
+hits = db.search("login redirect")
+assert len(hits) == 1 and "login redirect" in hits[0].content, hits
+session = db.get_session(hits[0].session_id)
+assert session.agent == "pi" and session.created_at.year == 2025, session
+assert [m.role for m in session.messages] == ["user"]
+assert db.get_session("missing") is None
+try:
+    db.sync("nope")
+    raise AssertionError("unknown agent accepted")
+except ValueError:
+    pass
+"#,
+                None,
+                Some(&locals),
+            )
+        })
+        .unwrap();
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
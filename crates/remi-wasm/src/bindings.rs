@@ -0,0 +1,30 @@
+use wasm_bindgen::prelude::*;
+
+use crate::Snapshot;
+
+// Results are JSON strings for `JSON.parse`, shaped like the CLI's JSON.
+#[wasm_bindgen]
+pub struct Archive {
+    snapshot: Snapshot,
+}
+
+#[wasm_bindgen]
+impl Archive {
+    #[wasm_bindgen(constructor)]
+    pub fn new(text: &str) -> Result<Archive, JsError> {
+        let snapshot = Snapshot::parse(text).map_err(|e| JsError::new(&format!("{e:#}")))?;
+        Ok(Archive { snapshot })
+    }
+
+    pub fn sessions(&self) -> String {
+        self.snapshot.sessions_json().to_string()
+    }
+
+    pub fn session(&self, id: &str) -> String {
+        self.snapshot.session_json(id).to_string()
+    }
+
+    pub fn search(&self, query: &str, limit: usize) -> String {
+        self.snapshot.search_json(query, limit).to_string()
+    }
+}
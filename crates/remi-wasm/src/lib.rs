@@ -0,0 +1,10 @@
+// A read-only query layer over an exported snapshot, small enough to run in
+// a browser: `cargo build -p remi-wasm --target wasm32-unknown-unknown
+// --profile wasm`, then `wasm-bindgen` for the JS glue.
+
+mod snapshot;
+
+#[cfg(target_arch = "wasm32")]
+mod bindings;
+
+pub use snapshot::{Hit, Snapshot};
@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use core_model::{AgentKind, JSON_SCHEMA_VERSION, Message, Session, query_terms};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+// BM25 parameters, the defaults of SQLite FTS5.
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+// An archive bundle, as written to `archive/<run_id>/sessions.json`.
+#[derive(Deserialize)]
+struct Bundle {
+    sessions: Vec<Session>,
+    messages: Vec<Message>,
+}
+
+// One line of `remi export --format jsonl`.
+#[derive(Deserialize)]
+struct Line {
+    session: LineSession,
+    messages: Vec<LineMessage>,
+}
+
+#[derive(Deserialize)]
+struct LineSession {
+    id: String,
+    agent: String,
+    title: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+struct LineMessage {
+    id: String,
+    role: String,
+    content: String,
+    ts: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    pub session_id: String,
+    pub message_id: String,
+    pub score: f64,
+    pub content: String,
+    // Message hits merged into this session.
+    pub hits: usize,
+}
+
+// Sessions newest first, their messages in order, and an inverted index of
+// message words built once at load.
+pub struct Snapshot {
+    sessions: Vec<Session>,
+    messages: Vec<Message>,
+    by_session: HashMap<String, Vec<usize>>,
+    postings: HashMap<String, Vec<(usize, u32)>>,
+    lengths: Vec<u32>,
+    average_length: f64,
+}
+
+impl Snapshot {
+    // Takes an archive bundle or a jsonl export, told apart by whether the
+    // text is one object with a `sessions` array.
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        if let Ok(bundle) = serde_json::from_str::<Bundle>(text) {
+            return Ok(Self::new(bundle.sessions, bundle.messages));
+        }
+        let mut sessions = Vec::new();
+        let mut messages = Vec::new();
+        for (i, raw) in text.lines().enumerate() {
+            if raw.trim().is_empty() {
+                continue;
+            }
+            let line: Line = serde_json::from_str(raw)
+                .map_err(|e| anyhow::anyhow!("line {}: not a session export: {e}", i + 1))?;
+            let s = line.session;
+            let agent: AgentKind = s
+                .agent
+                .parse()
+                .map_err(|e| anyhow::anyhow!("line {}: {e}", i + 1))?;
+            messages.extend(line.messages.into_iter().map(|m| Message {
+                id: m.id,
+                session_id: s.id.clone(),
+                role: m.role,
+                content: m.content,
+                ts: m.ts,
+            }));
+            sessions.push(Session {
+                id: s.id,
+                agent,
+                source_ref: String::new(),
+                title: s.title,
+                created_at: s.created_at,
+                updated_at: s.updated_at,
+            });
+        }
+        Ok(Self::new(sessions, messages))
+    }
+
+    fn new(mut sessions: Vec<Session>, mut messages: Vec<Message>) -> Self {
+        sessions.sort_by(|a, b| {
+            b.updated_at
+                .cmp(&a.updated_at)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        messages.sort_by(|a, b| a.ts.cmp(&b.ts).then_with(|| a.id.cmp(&b.id)));
+        let mut by_session: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut postings: HashMap<String, Vec<(usize, u32)>> = HashMap::new();
+        let mut lengths = Vec::with_capacity(messages.len());
+        for (i, message) in messages.iter().enumerate() {
+            by_session
+                .entry(message.session_id.clone())
+                .or_default()
+                .push(i);
+            let mut counts: HashMap<String, u32> = HashMap::new();
+            let mut length = 0;
+            for word in words(&message.content) {
+                *counts.entry(word).or_default() += 1;
+                length += 1;
+            }
+            for (word, count) in counts {
+                postings.entry(word).or_default().push((i, count));
+            }
+            lengths.push(length);
+        }
+        let average_length = if lengths.is_empty() {
+            0.0
+        } else {
+            lengths.iter().map(|&l| l as f64).sum::<f64>() / lengths.len() as f64
+        };
+        Self {
+            sessions,
+            messages,
+            by_session,
+            postings,
+            lengths,
+            average_length,
+        }
+    }
+
+    pub fn sessions(&self) -> &[Session] {
+        &self.sessions
+    }
+
+    pub fn messages(&self, session_id: &str) -> Vec<&Message> {
+        self.by_session
+            .get(session_id)
+            .map(|ids| ids.iter().map(|&i| &self.messages[i]).collect())
+            .unwrap_or_default()
+    }
+
+    // Messages are scored with BM25 and grouped by session: a session scores
+    // the sum of its messages and shows its best one.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<Hit> {
+        let total = self.messages.len() as f64;
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        let mut terms = query_terms(query);
+        terms.sort();
+        terms.dedup();
+        for term in &terms {
+            let Some(postings) = self.postings.get(term) else {
+                continue;
+            };
+            let found = postings.len() as f64;
+            let idf = ((total - found + 0.5) / (found + 0.5) + 1.0).ln();
+            for &(i, count) in postings {
+                let tf = count as f64;
+                let norm = 1.0 - B + B * self.lengths[i] as f64 / self.average_length.max(1.0);
+                *scores.entry(i).or_default() += idf * tf * (K1 + 1.0) / (tf + K1 * norm);
+            }
+        }
+        let mut sessions: HashMap<&str, (f64, usize, Hit)> = HashMap::new();
+        for (i, score) in scores {
+            let message = &self.messages[i];
+            let entry = sessions
+                .entry(message.session_id.as_str())
+                .or_insert_with(|| {
+                    (
+                        f64::MIN,
+                        0,
+                        Hit {
+                            session_id: message.session_id.clone(),
+                            message_id: String::new(),
+                            score: 0.0,
+                            content: String::new(),
+                            hits: 0,
+                        },
+                    )
+                });
+            entry.2.score += score;
+            entry.2.hits += 1;
+            if score > entry.0 || (score == entry.0 && i < entry.1) {
+                entry.0 = score;
+                entry.1 = i;
+                entry.2.message_id = message.id.clone();
+                entry.2.content = message.content.clone();
+            }
+        }
+        let mut hits: Vec<Hit> = sessions.into_values().map(|(_, _, hit)| hit).collect();
+        hits.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.session_id.cmp(&b.session_id))
+        });
+        hits.truncate(limit);
+        hits
+    }
+
+    pub fn sessions_json(&self) -> Value {
+        let sessions: Vec<Value> = self
+            .sessions
+            .iter()
+            .map(|s| {
+                let mut value = session_json(s);
+                value["messages"] = json!(self.by_session.get(&s.id).map_or(0, Vec::len));
+                value
+            })
+            .collect();
+        json!({ "schema_version": JSON_SCHEMA_VERSION, "sessions": sessions })
+    }
+
+    pub fn session_json(&self, id: &str) -> Value {
+        let session = self.sessions.iter().find(|s| s.id == id).map(|s| {
+            let mut value = session_json(s);
+            value["messages"] = self
+                .messages(id)
+                .into_iter()
+                .map(|m| json!({ "id": m.id, "role": m.role, "content": m.content, "ts": m.ts }))
+                .collect();
+            value
+        });
+        json!({ "schema_version": JSON_SCHEMA_VERSION, "session": session })
+    }
+
+    pub fn search_json(&self, query: &str, limit: usize) -> Value {
+        let hits: Vec<Value> = self
+            .search(query, limit)
+            .into_iter()
+            .map(|h| {
+                json!({
+                    "session_id": h.session_id,
+                    "message_id": h.message_id,
+                    "score": h.score,
+                    "content": h.content,
+                    "hits": h.hits,
+                })
+            })
+            .collect();
+        json!({ "schema_version": JSON_SCHEMA_VERSION, "hits": hits })
+    }
+}
+
+fn session_json(s: &Session) -> Value {
+    json!({
+        "id": s.id,
+        "agent": s.agent.as_str(),
+        "title": s.title,
+        "created_at": s.created_at,
+        "updated_at": s.updated_at,
+    })
+}
+
+// Lowercased words, split roughly as the FTS5 tokenizer splits them.
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = concat!(
+        r#"{"schema_version":1,"session":{"id":"s1","agent":"claude","title":"Login loop","created_at":"2025-05-05T10:00:00Z","updated_at":"2025-05-05T11:00:00Z"},"messages":[{"id":"m1","role":"user","content":"the login redirect loops","ts":"2025-05-05T10:00:00Z"},{"id":"m2","role":"assistant","content":"Check the redirect URI; the login cookie is dropped.","ts":"2025-05-05T10:01:00Z"}]}"#,
+        "\n",
+        r#"{"schema_version":1,"session":{"id":"s2","agent":"codex","title":"Shell alias","created_at":"2025-05-06T10:00:00Z","updated_at":"2025-05-06T10:00:00Z"},"messages":[{"id":"m3","role":"user","content":"write a shell alias for git log","ts":"2025-05-06T10:00:00Z"}]}"#,
+        "\n",
+    );
+
+    #[test]
+    fn jsonl_exports_load_and_rank_sessions_by_bm25() {
+        let snapshot = Snapshot::parse(EXPORT).unwrap();
+        let ids: Vec<&str> = snapshot.sessions().iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["s2", "s1"]);
+        assert_eq!(snapshot.messages("s1").len(), 2);
+
+        let hits = snapshot.search("Login redirect", 10);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].session_id, "s1");
+        assert_eq!(hits[0].hits, 2);
+        assert_eq!(hits[0].message_id, "m1");
+        assert!(snapshot.search("alias", 10)[0].session_id == "s2");
+        assert!(snapshot.search("and or", 10).is_empty());
+
+        let session = snapshot.session_json("s1");
+        assert_eq!(session["session"]["agent"], "claude");
+        assert_eq!(session["session"]["messages"][1]["role"], "assistant");
+        assert!(snapshot.session_json("nope")["session"].is_null());
+        assert_eq!(snapshot.sessions_json()["sessions"][1]["messages"], 2);
+        assert_eq!(
+            snapshot.search_json("alias", 1)["hits"][0]["message_id"],
+            "m3"
+        );
+    }
+
+    #[test]
+    fn archive_bundles_load_and_bad_lines_are_reported() {
+        let bundle = json!({
+            "run_id": "r1",
+            "sessions": [{
+                "id": "s1", "agent": "Pi", "source_ref": "/x.jsonl", "title": "t",
+                "created_at": "2025-05-05T10:00:00Z", "updated_at": "2025-05-05T10:00:00Z",
+            }],
+            "messages": [{
+                "id": "m1", "session_id": "s1", "role": "user", "content": "flaky test",
+                "ts": "2025-05-05T10:00:00Z",
+            }],
+            "events": [], "artifacts": [], "provenance": [],
+        });
+        let snapshot = Snapshot::parse(&bundle.to_string()).unwrap();
+        assert_eq!(snapshot.sessions()[0].agent, AgentKind::Pi);
+        assert_eq!(snapshot.search("flaky", 5)[0].message_id, "m1");
+
+        let err = Snapshot::parse("{\"nope\":1}\n").err().unwrap().to_string();
+        assert!(err.starts_with("line 1: not a session export"), "{err}");
+    }
+}
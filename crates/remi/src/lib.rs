@@ -0,0 +1,281 @@
+// One dependency for applications that embed Remi: the workspace crates are
+// re-exported as they are, and `Remi` covers the common path of opening the
+// store, syncing every compiled-in agent and searching.
+//
+//     let mut remi = remi::Remi::open()?;
+//     remi.sync_all()?;
+//     for hit in remi.search("login redirect", 10)? { ... }
+//
+// Searches go through `query::SearchRequest` like `remi search query`.
+
+use std::path::{Path, PathBuf};
+
+pub use adapter_common::{CaptureOptions, DiscoveryOptions, SourceTimezone};
+pub use core_model::{AgentAdapter, AgentKind, SourceContext};
+pub use query::{FilterSpec, ReasoningFilter, SearchRequest, SearchResponse, SessionDisplay};
+pub use store_sqlite::SqliteStore;
+pub use {archive, core_model, ingest, query, search, store_sqlite as store};
+
+// Each adapter sits behind a cargo feature of the same name, all on by
+// default, so an embedding build can leave out the agents it does not need.
+pub const ADAPTER_FEATURES: [(AgentKind, bool); 8] = [
+    (AgentKind::Pi, cfg!(feature = "pi")),
+    (AgentKind::Droid, cfg!(feature = "droid")),
+    (AgentKind::OpenCode, cfg!(feature = "opencode")),
+    (AgentKind::Claude, cfg!(feature = "claude")),
+    (AgentKind::Amp, cfg!(feature = "amp")),
+    (AgentKind::Codex, cfg!(feature = "codex")),
+    (AgentKind::Gemini, cfg!(feature = "gemini")),
+    (AgentKind::Aider, cfg!(feature = "aider")),
+];
+
+pub fn adapter_compiled(kind: AgentKind) -> bool {
+    ADAPTER_FEATURES
+        .iter()
+        .any(|(compiled, on)| *compiled == kind && *on)
+}
+
+// How many pushes remain depends on the enabled features.
+#[allow(unused_variables, unused_mut, clippy::vec_init_then_push)]
+pub fn adapters(
+    opts: impl Fn(AgentKind) -> DiscoveryOptions,
+    capture: CaptureOptions,
+    timezone: impl Fn(AgentKind) -> Option<SourceTimezone>,
+) -> Vec<(&'static str, Box<dyn AgentAdapter>)> {
+    let mut adapters: Vec<(&'static str, Box<dyn AgentAdapter>)> = Vec::new();
+    #[cfg(feature = "pi")]
+    adapters.push((
+        "pi",
+        Box::new(
+            pi::PiAdapter::with_discovery(opts(AgentKind::Pi))
+                .with_capture(capture)
+                .with_timezone(timezone(AgentKind::Pi)),
+        ),
+    ));
+    #[cfg(feature = "droid")]
+    adapters.push((
+        "droid",
+        Box::new(
+            droid::DroidAdapter::with_discovery(opts(AgentKind::Droid))
+                .with_capture(capture)
+                .with_timezone(timezone(AgentKind::Droid)),
+        ),
+    ));
+    #[cfg(feature = "opencode")]
+    adapters.push((
+        "opencode",
+        Box::new(
+            opencode::OpenCodeAdapter::with_discovery(opts(AgentKind::OpenCode))
+                .with_capture(capture)
+                .with_timezone(timezone(AgentKind::OpenCode)),
+        ),
+    ));
+    #[cfg(feature = "claude")]
+    adapters.push((
+        "claude",
+        Box::new(
+            claude::ClaudeAdapter::with_discovery(opts(AgentKind::Claude))
+                .with_capture(capture)
+                .with_timezone(timezone(AgentKind::Claude)),
+        ),
+    ));
+    #[cfg(feature = "amp")]
+    adapters.push((
+        "amp",
+        Box::new(
+            amp::AmpAdapter::with_discovery(opts(AgentKind::Amp))
+                .with_capture(capture)
+                .with_timezone(timezone(AgentKind::Amp)),
+        ),
+    ));
+    #[cfg(feature = "codex")]
+    adapters.push((
+        "codex",
+        Box::new(
+            codex::CodexAdapter::with_discovery(opts(AgentKind::Codex))
+                .with_capture(capture)
+                .with_timezone(timezone(AgentKind::Codex)),
+        ),
+    ));
+    #[cfg(feature = "gemini")]
+    adapters.push((
+        "gemini",
+        Box::new(
+            gemini::GeminiAdapter::with_discovery(opts(AgentKind::Gemini))
+                .with_capture(capture)
+                .with_timezone(timezone(AgentKind::Gemini)),
+        ),
+    ));
+    #[cfg(feature = "aider")]
+    adapters.push((
+        "aider",
+        Box::new(
+            aider::AiderAdapter::with_discovery(opts(AgentKind::Aider))
+                .with_capture(capture)
+                .with_timezone(timezone(AgentKind::Aider)),
+        ),
+    ));
+    adapters
+}
+
+// The database of the CLI's default profile.
+pub fn default_db_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("remi")
+        .join("remi.db")
+}
+
+pub struct Remi {
+    store: SqliteStore,
+    sources: SourceContext,
+    discovery: DiscoveryOptions,
+    capture: CaptureOptions,
+}
+
+impl Remi {
+    // Opens the database `remi` itself uses, creating it if needed.
+    pub fn open() -> anyhow::Result<Self> {
+        Self::open_at(default_db_path())
+    }
+
+    pub fn open_at(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+        let store = SqliteStore::open(path)?;
+        store.init_schema()?;
+        Ok(Self::from_store(store))
+    }
+
+    // Sources are found the way the CLI finds them, from the home directory
+    // and `REMI_<AGENT>_HOME`.
+    pub fn from_store(store: SqliteStore) -> Self {
+        Self {
+            store,
+            sources: adapter_common::env_source_context(),
+            discovery: DiscoveryOptions::default(),
+            capture: CaptureOptions::default(),
+        }
+    }
+
+    pub fn with_sources(mut self, sources: SourceContext) -> Self {
+        self.sources = sources;
+        self
+    }
+
+    pub fn with_discovery(mut self, discovery: DiscoveryOptions) -> Self {
+        self.discovery = discovery;
+        self
+    }
+
+    pub fn with_capture(mut self, capture: CaptureOptions) -> Self {
+        self.capture = capture;
+        self
+    }
+
+    pub fn store(&self) -> &SqliteStore {
+        &self.store
+    }
+
+    pub fn store_mut(&mut self) -> &mut SqliteStore {
+        &mut self.store
+    }
+
+    // Returns the number of source records read across all agents.
+    pub fn sync_all(&mut self) -> anyhow::Result<usize> {
+        let mut synced = 0;
+        for (_, adapter) in adapters(|_| self.discovery.clone(), self.capture, |_| None) {
+            synced += self.sync_adapter(adapter.as_ref())?;
+        }
+        Ok(synced)
+    }
+
+    pub fn sync(&mut self, kind: AgentKind) -> anyhow::Result<usize> {
+        let Some((_, adapter)) = adapters(|_| self.discovery.clone(), self.capture, |_| None)
+            .into_iter()
+            .find(|(_, adapter)| adapter.kind() == kind)
+        else {
+            anyhow::bail!("this build of remi has no {kind} adapter (cargo feature `{kind}`)");
+        };
+        self.sync_adapter(adapter.as_ref())
+    }
+
+    fn sync_adapter(&mut self, adapter: &dyn AgentAdapter) -> anyhow::Result<usize> {
+        ingest::sync_adapter(
+            adapter,
+            &self.sources,
+            &mut self.store,
+            #[cfg(feature = "semantic")]
+            false,
+            |_| {},
+        )
+    }
+
+    // `remi search query` with its defaults; trashed sessions are left out.
+    pub fn search(&self, query: &str, limit: usize) -> anyhow::Result<Vec<SessionDisplay>> {
+        Ok(self
+            .search_with(SearchRequest::new(query).limit(limit))?
+            .sessions)
+    }
+
+    // For filters, `--reasoning`, `--as-of`, weights and the rest of the
+    // request options.
+    pub fn search_with(&self, request: SearchRequest) -> anyhow::Result<SearchResponse> {
+        request.execute(&self.store)
+    }
+}
+
+#[cfg(all(test, feature = "pi"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn syncs_and_searches_through_the_facade() {
+        let root = std::env::temp_dir().join(format!("remi_facade_{}", std::process::id()));
+        let sessions = root.join("home/.pi/agent/sessions");
+        std::fs::create_dir_all(&sessions).unwrap();
+        std::fs::write(
+            sessions.join("s1.jsonl"),
+            concat!(
+                r#"{"type":"session","version":3,"id":"s1","timestamp":"2025-05-05T10:00:00Z","cwd":"/tmp"}"#,
+                "\n",
+                r#"{"type":"message","id":"m1","parentId":null,"timestamp":"2025-05-05T10:00:01Z","message":{"role":"user","content":[{"type":"text","text":"the login redirect loops"}]}}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+
+        let mut remi = Remi::open_at(root.join("data/remi.db"))
+            .unwrap()
+            .with_sources(SourceContext::with_home(root.join("home")));
+        assert!(remi.sync(AgentKind::Pi).unwrap() > 0);
+        assert_eq!(remi.sync_all().unwrap(), 0);
+        let hits = remi.search("login redirect", 5).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].top_content.contains("login redirect"));
+        assert_eq!(hits[0].title, "the login redirect loops");
+        let filtered = |agent: &str| {
+            remi.search_with(SearchRequest::new("login").filters(FilterSpec {
+                agent: Some(agent.to_string()),
+                ..FilterSpec::default()
+            }))
+            .unwrap()
+        };
+        assert_eq!(filtered("pi").sessions.len(), 1);
+        let other = filtered("codex");
+        assert_eq!((other.unfiltered, other.sessions.len()), (1, 0));
+        let reasoning = remi
+            .search_with(SearchRequest::new("login").reasoning(ReasoningFilter::Only))
+            .unwrap();
+        assert!(reasoning.sessions.is_empty());
+        assert_eq!(remi.store().list_sessions().unwrap().len(), 1);
+        let id = hits[0].session_id.clone();
+        remi.store_mut().trash_session(&id).unwrap();
+        assert!(remi.search("login redirect", 5).unwrap().is_empty());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
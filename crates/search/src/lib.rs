@@ -1,16 +1,25 @@
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
-use anyhow::Context;
-#[cfg(feature = "semantic")]
 use chrono::{DateTime, Utc};
 use rusqlite::{Connection, OptionalExtension, params};
 
-use store_sqlite::SqliteStore;
+pub use store_sqlite::ReasoningFilter;
+use store_sqlite::{SearchScope, SqliteStore};
 use tracing::debug;
 
+pub use core_model::query_terms;
+
+#[cfg(feature = "semantic")]
+use embeddings::{Embedder, Reranker};
+
+// Cross-encoders are slow per pair, so only the head of the fused list is
+// rescored.
+#[cfg(feature = "semantic")]
+pub const RERANK_CANDIDATES: usize = 50;
+
 #[cfg(feature = "semantic")]
-use embeddings::Embedder;
+pub const QUERY_EMBEDDING_CAPACITY: usize = 1000;
 
 #[derive(Debug, Clone)]
 pub struct RankedHit {
@@ -18,6 +27,8 @@ pub struct RankedHit {
     pub session_id: String,
     pub content: String,
     pub score: f32,
+    pub channels: ChannelScores,
+    pub ranks: ChannelRanks,
 }
 
 #[derive(Debug, Clone)]
@@ -26,6 +37,105 @@ pub struct SessionHit {
     pub top_message_id: String,
     pub top_content: String,
     pub score: f32,
+    pub channels: ChannelScores,
+    pub ranks: ChannelRanks,
+    // Message hits merged into this session.
+    pub hits: usize,
+}
+
+// The RRF contribution of each ranking channel to a fused score. A reranked
+// hit keeps its channels, but its score is the one of the slot it moved to.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ChannelScores {
+    pub bm25: f32,
+    pub recency: f32,
+    pub semantic: f32,
+    pub substring: f32,
+}
+
+// 1-based position of a hit in a channel's result list, with the channel's
+// own score where it has one: the negated FTS5 bm25 value, or the cosine
+// similarity for semantic hits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChannelRank {
+    pub rank: usize,
+    pub raw: Option<f32>,
+}
+
+// For a session, the best rank any of its messages reached in each channel.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ChannelRanks {
+    pub bm25: Option<ChannelRank>,
+    pub recency: Option<ChannelRank>,
+    pub semantic: Option<ChannelRank>,
+    pub substring: Option<ChannelRank>,
+}
+
+impl ChannelRanks {
+    fn merge(&mut self, other: &ChannelRanks) {
+        for (slot, rank) in [
+            (&mut self.bm25, other.bm25),
+            (&mut self.recency, other.recency),
+            (&mut self.semantic, other.semantic),
+            (&mut self.substring, other.substring),
+        ] {
+            if let Some(rank) = rank {
+                keep_best(slot, rank);
+            }
+        }
+    }
+}
+
+fn keep_best(slot: &mut Option<ChannelRank>, rank: ChannelRank) {
+    if slot.is_none_or(|current| rank.rank < current.rank) {
+        *slot = Some(rank);
+    }
+}
+
+impl RankedHit {
+    fn add(&mut self, channels: ChannelScores) {
+        self.score += channels.bm25 + channels.recency + channels.semantic + channels.substring;
+        self.channels.add(&channels);
+    }
+}
+
+impl ChannelScores {
+    fn add(&mut self, other: &ChannelScores) {
+        self.bm25 += other.bm25;
+        self.recency += other.recency;
+        self.semantic += other.semantic;
+        self.substring += other.substring;
+    }
+}
+
+// Rows each channel returned before fusion, and the unique messages and
+// sessions those rows covered before the result limit was applied.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Candidates {
+    pub bm25: usize,
+    pub recency: usize,
+    pub semantic: usize,
+    pub substring: usize,
+    pub messages: usize,
+    pub sessions: usize,
+}
+
+// Multipliers for each channel's reciprocal-rank contribution.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Weights {
+    pub bm25: f32,
+    pub recency: f32,
+    pub semantic: f32,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            bm25: 1.0,
+            recency: 0.3,
+            semantic: 0.5,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -40,19 +150,26 @@ pub struct DocHit {
 #[derive(Default)]
 pub struct SemanticCache {
     embeddings: Option<Vec<(String, Vec<f32>)>>,
+    model: String,
     len: usize,
     updated_at: Option<DateTime<Utc>>,
 }
 
 #[cfg(feature = "semantic")]
 impl SemanticCache {
-    pub fn embeddings(&mut self, store: &SqliteStore) -> anyhow::Result<&[(String, Vec<f32>)]> {
-        let stats = store.embedding_stats()?;
+    pub fn embeddings(
+        &mut self,
+        store: &SqliteStore,
+        model: &str,
+    ) -> anyhow::Result<&[(String, Vec<f32>)]> {
+        let stats = store.embedding_stats(model)?;
         let should_refresh = self.embeddings.is_none()
+            || self.model != model
             || stats.len != self.len
             || stats.updated_at != self.updated_at;
         if should_refresh {
-            self.embeddings = Some(store.load_all_embeddings()?);
+            self.embeddings = Some(store.load_all_embeddings(model)?);
+            self.model = model.to_string();
             self.len = stats.len;
             self.updated_at = stats.updated_at;
         }
@@ -61,19 +178,24 @@ impl SemanticCache {
 
     pub fn clear(&mut self) {
         self.embeddings = None;
+        self.model.clear();
         self.len = 0;
         self.updated_at = None;
     }
 }
 
+#[cfg_attr(feature = "semantic", allow(clippy::too_many_arguments))]
 pub fn search(
     store: &SqliteStore,
     query: &str,
     limit: usize,
     raw_fts: bool,
+    weights: Weights,
+    scope: &SearchScope,
     #[cfg(feature = "semantic")] embedder: Option<&mut Embedder>,
     #[cfg(feature = "semantic")] cache: Option<&mut SemanticCache>,
-) -> anyhow::Result<Vec<RankedHit>> {
+    #[cfg(feature = "semantic")] reranker: Option<&mut Reranker>,
+) -> anyhow::Result<(Vec<RankedHit>, Candidates)> {
     let fts_query = if raw_fts {
         query.trim().to_string()
     } else {
@@ -82,11 +204,15 @@ pub fn search(
     debug!(raw_query = %query, fts_query = %fts_query, raw_fts, "search query prepared");
 
     let bm25_rows = if !fts_query.is_empty() {
-        store.search_lexical(&fts_query, 200)?
+        store.search_lexical(&fts_query, 200, scope)?
     } else {
         Vec::new()
     };
     debug!(bm25_rows = bm25_rows.len(), "BM25 results");
+    let mut candidates = Candidates {
+        bm25: bm25_rows.len(),
+        ..Default::default()
+    };
 
     if bm25_rows.is_empty() {
         #[cfg(feature = "semantic")]
@@ -95,35 +221,50 @@ pub fn search(
         let has_semantic = false;
 
         if !has_semantic {
-            let fallback = store.search_substring(query, limit as i64)?;
-            if !fallback.is_empty() {
-                return Ok(fallback
-                    .into_iter()
-                    .enumerate()
-                    .map(|(i, r)| RankedHit {
+            let fallback = store.search_substring(query, limit as i64, scope)?;
+            candidates.substring = fallback.len();
+            candidates.messages = fallback.len();
+            let hits = fallback
+                .into_iter()
+                .enumerate()
+                .map(|(i, r)| {
+                    let score = 1.0 / (60.0 + i as f32 + 1.0);
+                    RankedHit {
                         message_id: r.message_id,
                         session_id: r.session_id,
                         content: r.content,
-                        score: 1.0 / (60.0 + i as f32 + 1.0),
-                    })
-                    .collect());
-            }
-            return Ok(Vec::new());
+                        score,
+                        channels: ChannelScores {
+                            substring: score,
+                            ..Default::default()
+                        },
+                        ranks: ChannelRanks {
+                            substring: Some(ChannelRank {
+                                rank: i + 1,
+                                raw: None,
+                            }),
+                            ..Default::default()
+                        },
+                    }
+                })
+                .collect();
+            return Ok((hits, candidates));
         }
     }
 
-    let recency_rows = store.recent_messages(200)?;
+    let recency_rows = store.recent_messages(200, scope)?;
     debug!(recency_rows = recency_rows.len(), "recency rows loaded");
+    candidates.recency = recency_rows.len();
 
     #[cfg(feature = "semantic")]
     let semantic_rows = if let Some(embedder) = embedder {
-        let query_vec = embedder.embed(query, true)?;
-        let mut owned_embeddings = None;
+        let query_vec = query_embedding(store, embedder, query)?;
+        let owned_embeddings;
         let embeddings = if let Some(cache) = cache {
-            cache.embeddings(store)?
+            cache.embeddings(store, embedder.model_id())?
         } else {
-            owned_embeddings = Some(store.load_all_embeddings()?);
-            owned_embeddings.as_deref().unwrap_or(&[])
+            owned_embeddings = store.load_all_embeddings(embedder.model_id())?;
+            &owned_embeddings
         };
         let mut scored: Vec<(String, f32)> = embeddings
             .iter()
@@ -133,139 +274,204 @@ pub fn search(
             })
             .collect();
         scored.sort_by(|a, b| b.1.total_cmp(&a.1));
-        scored.truncate(200);
-        scored
+        let mut kept = Vec::with_capacity(200);
+        for (id, score) in scored {
+            if kept.len() == 200 {
+                break;
+            }
+            if store.message_in_scope(&id, scope)? {
+                kept.push((id, score));
+            }
+        }
+        kept
     } else {
         Vec::new()
     };
 
-    let mut scores: HashMap<String, (f32, String, String, String)> = HashMap::new();
-
-    let bm25_weight = 1.0_f32;
-    let recency_weight = 0.3_f32;
     #[cfg(feature = "semantic")]
-    let semantic_weight = 0.5_f32;
+    {
+        candidates.semantic = semantic_rows.len();
+    }
+
+    let mut scores: HashMap<String, RankedHit> = HashMap::new();
+
     let k = 60.0_f32;
+    // The reasoning filter is judged by where the query terms match, so once
+    // it applies, recency and semantic neighbours only rank lexical hits.
+    let lexical_only = scope.reasoning != ReasoningFilter::Include && !fts_query.is_empty();
 
     for (rank, row) in bm25_rows.iter().enumerate() {
-        let rrf = bm25_weight / (k + rank as f32 + 1.0);
-        scores
-            .entry(row.message_id.clone())
-            .and_modify(|(s, _, _, _)| *s += rrf)
-            .or_insert((
-                rrf,
-                row.session_id.clone(),
-                row.content.clone(),
-                row.message_id.clone(),
-            ));
+        let bm25 = weights.bm25 / (k + rank as f32 + 1.0);
+        let hit = ranked_entry(&mut scores, &row.message_id, &row.session_id, &row.content);
+        hit.add(ChannelScores {
+            bm25,
+            ..Default::default()
+        });
+        keep_best(
+            &mut hit.ranks.bm25,
+            ChannelRank {
+                rank: rank + 1,
+                raw: Some(row.score as f32),
+            },
+        );
     }
 
     for (rank, row) in recency_rows.iter().enumerate() {
-        let rrf = recency_weight / (k + rank as f32 + 1.0);
-        scores
-            .entry(row.message_id.clone())
-            .and_modify(|(s, _, _, _)| *s += rrf)
-            .or_insert((
-                rrf,
-                row.session_id.clone(),
-                row.content.clone(),
-                row.message_id.clone(),
-            ));
+        if lexical_only && !scores.contains_key(&row.message_id) {
+            continue;
+        }
+        let recency = weights.recency / (k + rank as f32 + 1.0);
+        let hit = ranked_entry(&mut scores, &row.message_id, &row.session_id, &row.content);
+        hit.add(ChannelScores {
+            recency,
+            ..Default::default()
+        });
+        keep_best(
+            &mut hit.ranks.recency,
+            ChannelRank {
+                rank: rank + 1,
+                raw: None,
+            },
+        );
     }
 
     #[cfg(feature = "semantic")]
-    for (rank, (msg_id, _score)) in semantic_rows.iter().enumerate() {
-        let rrf = semantic_weight / (k + rank as f32 + 1.0);
-        scores
-            .entry(msg_id.clone())
-            .and_modify(|(s, _, _, _)| *s += rrf)
-            .or_insert_with(|| {
-                if let Ok(Some(msg)) = store.get_message(msg_id) {
-                    (rrf, msg.session_id, msg.content, msg_id.clone())
-                } else {
-                    (0.0, String::new(), String::new(), msg_id.clone())
-                }
-            });
+    for (rank, (msg_id, similarity)) in semantic_rows.iter().enumerate() {
+        let semantic = weights.semantic / (k + rank as f32 + 1.0);
+        let channels = ChannelScores {
+            semantic,
+            ..Default::default()
+        };
+        let hit = if let Some(hit) = scores.get_mut(msg_id) {
+            hit
+        } else if lexical_only {
+            continue;
+        } else if let Ok(Some(msg)) = store.get_message(msg_id) {
+            ranked_entry(&mut scores, msg_id, &msg.session_id, &msg.content)
+        } else {
+            continue;
+        };
+        hit.add(channels);
+        keep_best(
+            &mut hit.ranks.semantic,
+            ChannelRank {
+                rank: rank + 1,
+                raw: Some(*similarity),
+            },
+        );
     }
 
-    let mut out: Vec<RankedHit> = scores
-        .into_values()
-        .filter(|(s, _, _, _)| *s > 0.0)
-        .map(|(score, session_id, content, message_id)| RankedHit {
-            message_id,
-            session_id,
-            content,
-            score,
-        })
-        .collect();
+    let mut out: Vec<RankedHit> = scores.into_values().filter(|hit| hit.score > 0.0).collect();
+    candidates.messages = out.len();
 
     out.sort_by(|a, b| b.score.total_cmp(&a.score));
+    #[cfg(feature = "semantic")]
+    if let Some(reranker) = reranker {
+        rerank_head(&mut out, |passages| reranker.score(query, passages))?;
+    }
     out.truncate(limit);
     debug!(total = out.len(), "RRF scored results");
 
-    Ok(out)
+    Ok((out, candidates))
 }
 
+fn ranked_entry<'a>(
+    scores: &'a mut HashMap<String, RankedHit>,
+    message_id: &str,
+    session_id: &str,
+    content: &str,
+) -> &'a mut RankedHit {
+    scores
+        .entry(message_id.to_string())
+        .or_insert_with(|| RankedHit {
+            message_id: message_id.to_string(),
+            session_id: session_id.to_string(),
+            content: content.to_string(),
+            score: 0.0,
+            channels: ChannelScores::default(),
+            ranks: ChannelRanks::default(),
+        })
+}
+
+#[cfg_attr(feature = "semantic", allow(clippy::too_many_arguments))]
 pub fn search_sessions(
     store: &SqliteStore,
     query: &str,
     limit: usize,
     raw_fts: bool,
+    weights: Weights,
+    reasoning: ReasoningFilter,
+    as_of: Option<DateTime<Utc>>,
     #[cfg(feature = "semantic")] embedder: Option<&mut Embedder>,
     #[cfg(feature = "semantic")] cache: Option<&mut SemanticCache>,
-) -> anyhow::Result<Vec<SessionHit>> {
-    let hits = search(
+    #[cfg(feature = "semantic")] reranker: Option<&mut Reranker>,
+) -> anyhow::Result<(Vec<SessionHit>, Candidates)> {
+    // Messages stored after `as_of` did not exist yet in that view.
+    let scope = SearchScope {
+        ingested_before: as_of,
+        include_trashed: false,
+        reasoning,
+    };
+    let (hits, mut candidates) = search(
         store,
         query,
         limit * 5,
         raw_fts,
+        weights,
+        &scope,
         #[cfg(feature = "semantic")]
         embedder,
         #[cfg(feature = "semantic")]
         cache,
+        #[cfg(feature = "semantic")]
+        reranker,
     )?;
 
-    let mut grouped: HashMap<String, (f32, f32, String, String)> = HashMap::new();
+    let mut grouped: HashMap<String, (f32, SessionHit)> = HashMap::new();
     for hit in hits {
-        grouped
-            .entry(hit.session_id.clone())
-            .and_modify(|(total, top_score, top_id, top_content)| {
-                *total += hit.score;
+        match grouped.get_mut(&hit.session_id) {
+            Some((top_score, session)) => {
+                session.score += hit.score;
+                session.channels.add(&hit.channels);
+                session.ranks.merge(&hit.ranks);
+                session.hits += 1;
                 if hit.score > *top_score {
                     *top_score = hit.score;
-                    *top_id = hit.message_id.clone();
-                    *top_content = hit.content.clone();
+                    session.top_message_id = hit.message_id;
+                    session.top_content = hit.content;
                 }
-            })
-            .or_insert((hit.score, hit.score, hit.message_id, hit.content));
+            }
+            None => {
+                let session = SessionHit {
+                    session_id: hit.session_id.clone(),
+                    top_message_id: hit.message_id,
+                    top_content: hit.content,
+                    score: hit.score,
+                    channels: hit.channels,
+                    ranks: hit.ranks,
+                    hits: 1,
+                };
+                grouped.insert(hit.session_id, (hit.score, session));
+            }
+        }
     }
+    candidates.sessions = grouped.len();
 
-    let mut out: Vec<SessionHit> = grouped
-        .into_iter()
-        .map(
-            |(session_id, (score, _top_score, top_message_id, top_content))| SessionHit {
-                session_id,
-                top_message_id,
-                top_content,
-                score,
-            },
-        )
-        .collect();
-
+    let mut out: Vec<SessionHit> = grouped.into_values().map(|(_, hit)| hit).collect();
     out.sort_by(|a, b| b.score.total_cmp(&a.score));
     out.truncate(limit);
     debug!(sessions = out.len(), "session hits grouped");
-    Ok(out)
+    Ok((out, candidates))
 }
 
 pub fn search_docs_at(
     db_path: impl AsRef<Path>,
+    key: Option<&str>,
     query: &str,
     limit: usize,
     raw_fts: bool,
 ) -> anyhow::Result<Vec<DocHit>> {
-    let conn = Connection::open(db_path.as_ref())
-        .with_context(|| format!("opening sqlite db {}", db_path.as_ref().display()))?;
+    let conn = store_sqlite::open_connection(db_path.as_ref(), key)?;
     if !has_docs_index(&conn)? {
         return Ok(Vec::new());
     }
@@ -431,6 +637,62 @@ fn fallback_doc_snippet(content: &str, query: &str) -> String {
     }
 }
 
+// Scripts and the MCP server repeat the same queries, so query vectors are
+// persisted across processes. Whitespace is collapsed before keying.
+#[cfg(feature = "semantic")]
+fn query_embedding(
+    store: &SqliteStore,
+    embedder: &mut Embedder,
+    query: &str,
+) -> anyhow::Result<Vec<f32>> {
+    let key = query.split_whitespace().collect::<Vec<_>>().join(" ");
+    if let Some(vec) = store.get_query_embedding(&key, embedder.model_version())? {
+        debug!("query embedding cache hit");
+        return Ok(vec);
+    }
+    let vec = embedder.embed(&key, true)?;
+    store.save_query_embedding(
+        &key,
+        embedder.model_version(),
+        &vec,
+        QUERY_EMBEDDING_CAPACITY,
+    )?;
+    Ok(vec)
+}
+
+// Reorders the top candidates by cross-encoder relevance. The fused scores are
+// handed back out in the new order, so the head stays above the tail and
+// session grouping still sums positive RRF-scale values.
+#[cfg(feature = "semantic")]
+fn rerank_head(
+    hits: &mut [RankedHit],
+    score: impl FnOnce(&[&str]) -> anyhow::Result<Vec<f32>>,
+) -> anyhow::Result<()> {
+    let head = hits.len().min(RERANK_CANDIDATES);
+    let passages: Vec<&str> = hits[..head].iter().map(|h| h.content.as_str()).collect();
+    let relevance = score(&passages)?;
+    if relevance.len() != head {
+        anyhow::bail!(
+            "reranker returned {} scores for {head} passages",
+            relevance.len()
+        );
+    }
+    let fused: Vec<f32> = hits[..head].iter().map(|h| h.score).collect();
+    let mut order: Vec<usize> = (0..head).collect();
+    order.sort_by(|&a, &b| relevance[b].total_cmp(&relevance[a]));
+    let reordered: Vec<RankedHit> = order
+        .iter()
+        .zip(fused)
+        .map(|(&i, score)| RankedHit {
+            score,
+            ..hits[i].clone()
+        })
+        .collect();
+    hits[..head].clone_from_slice(&reordered);
+    debug!(reranked = head, "reranked head of fused results");
+    Ok(())
+}
+
 #[cfg(feature = "semantic")]
 fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
@@ -483,12 +745,63 @@ mod tests {
             events: vec![],
             artifacts: vec![],
             provenance: vec![],
+            session_meta: vec![],
         };
         let mut store_mut = store;
         store_mut.save_batch(&batch).unwrap();
         store_mut
     }
 
+    fn save_message(store: &mut SqliteStore, session: &str, id: &str, content: &str) {
+        let now = Utc::now();
+        store
+            .save_batch(&NormalizedBatch {
+                sessions: vec![Session {
+                    id: session.to_string(),
+                    agent: AgentKind::Pi,
+                    source_ref: session.to_string(),
+                    title: session.to_string(),
+                    created_at: now,
+                    updated_at: now,
+                }],
+                messages: vec![Message {
+                    id: id.to_string(),
+                    session_id: session.to_string(),
+                    role: "assistant".to_string(),
+                    content: content.to_string(),
+                    ts: now,
+                }],
+                ..Default::default()
+            })
+            .unwrap();
+    }
+
+    fn session_search(
+        store: &SqliteStore,
+        query: &str,
+        limit: usize,
+        reasoning: ReasoningFilter,
+        as_of: Option<DateTime<Utc>>,
+    ) -> Vec<SessionHit> {
+        search_sessions(
+            store,
+            query,
+            limit,
+            false,
+            Weights::default(),
+            reasoning,
+            as_of,
+            #[cfg(feature = "semantic")]
+            None,
+            #[cfg(feature = "semantic")]
+            None,
+            #[cfg(feature = "semantic")]
+            None,
+        )
+        .unwrap()
+        .0
+    }
+
     fn temp_db_path(name: &str) -> PathBuf {
         let unique = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -542,21 +855,97 @@ mod tests {
     fn search_finds_match() {
         let store = setup_store();
         #[cfg(feature = "semantic")]
-        let hits = search(&store, "rust", 10, false, None, None).unwrap();
+        let (hits, _) = search(
+            &store,
+            "rust",
+            10,
+            false,
+            Weights::default(),
+            &SearchScope::default(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
         #[cfg(not(feature = "semantic"))]
-        let hits = search(&store, "rust", 10, false).unwrap();
+        let (hits, _) = search(
+            &store,
+            "rust",
+            10,
+            false,
+            Weights::default(),
+            &SearchScope::default(),
+        )
+        .unwrap();
         assert!(!hits.is_empty());
         assert_eq!(hits[0].message_id, "m1");
         assert!(hits[0].score > 0.0);
+        let channels = hits[0].channels;
+        assert!(channels.bm25 > 0.0 && channels.recency > 0.0);
+        assert_eq!(hits[0].score, channels.bm25 + channels.recency);
+        let bm25 = hits[0].ranks.bm25.unwrap();
+        assert_eq!(bm25.rank, 1);
+        assert!(bm25.raw.unwrap() > 0.0);
+        assert!(hits[0].ranks.recency.unwrap().raw.is_none());
+        assert!(hits[0].ranks.semantic.is_none());
+
+        let lexical_only = Weights {
+            recency: 0.0,
+            ..Default::default()
+        };
+        #[cfg(feature = "semantic")]
+        let (hits, _) = search(
+            &store,
+            "rust",
+            10,
+            false,
+            lexical_only,
+            &SearchScope::default(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        #[cfg(not(feature = "semantic"))]
+        let (hits, _) = search(
+            &store,
+            "rust",
+            10,
+            false,
+            lexical_only,
+            &SearchScope::default(),
+        )
+        .unwrap();
+        assert!(hits.iter().all(|hit| hit.channels.bm25 > 0.0));
+        assert!(hits.iter().all(|hit| hit.channels.recency == 0.0));
     }
 
     #[test]
     fn search_no_match() {
         let store = setup_store();
         #[cfg(feature = "semantic")]
-        let hits = search(&store, "java", 10, false, None, None).unwrap();
+        let (hits, _) = search(
+            &store,
+            "java",
+            10,
+            false,
+            Weights::default(),
+            &SearchScope::default(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
         #[cfg(not(feature = "semantic"))]
-        let hits = search(&store, "java", 10, false).unwrap();
+        let (hits, _) = search(
+            &store,
+            "java",
+            10,
+            false,
+            Weights::default(),
+            &SearchScope::default(),
+        )
+        .unwrap();
         assert!(hits.is_empty());
     }
 
@@ -564,26 +953,252 @@ mod tests {
     fn search_ranked_by_score() {
         let store = setup_store();
         #[cfg(feature = "semantic")]
-        let hits = search(&store, "rust OR python", 10, true, None, None).unwrap();
+        let (hits, _) = search(
+            &store,
+            "rust OR python",
+            10,
+            true,
+            Weights::default(),
+            &SearchScope::default(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
         #[cfg(not(feature = "semantic"))]
-        let hits = search(&store, "rust OR python", 10, true).unwrap();
+        let (hits, _) = search(
+            &store,
+            "rust OR python",
+            10,
+            true,
+            Weights::default(),
+            &SearchScope::default(),
+        )
+        .unwrap();
         assert!(!hits.is_empty());
         for w in hits.windows(2) {
             assert!(w[0].score >= w[1].score);
         }
     }
 
+    #[cfg(feature = "semantic")]
+    #[test]
+    fn rerank_reorders_head_and_keeps_fused_scores() {
+        let hit = |id: &str, score: f32| RankedHit {
+            message_id: id.into(),
+            session_id: "s".into(),
+            content: format!("content {id}"),
+            score,
+            channels: ChannelScores::default(),
+            ranks: ChannelRanks::default(),
+        };
+        let mut hits: Vec<RankedHit> = (0..RERANK_CANDIDATES + 2)
+            .map(|i| hit(&format!("m{i}"), 1.0 - i as f32 / 100.0))
+            .collect();
+        let tail: Vec<f32> = hits[RERANK_CANDIDATES..].iter().map(|h| h.score).collect();
+        rerank_head(&mut hits, |passages| {
+            assert_eq!(passages.len(), RERANK_CANDIDATES);
+            Ok((0..passages.len())
+                .map(|i| if i == 3 { 10.0 } else { -(i as f32) })
+                .collect())
+        })
+        .unwrap();
+        assert_eq!(hits[0].message_id, "m3");
+        assert_eq!(hits[0].score, 1.0);
+        assert_eq!(hits[1].message_id, "m0");
+        assert_eq!(hits[4].message_id, "m4");
+        for w in hits.windows(2) {
+            assert!(w[0].score >= w[1].score);
+        }
+        let reranked_tail: Vec<f32> = hits[RERANK_CANDIDATES..].iter().map(|h| h.score).collect();
+        assert_eq!(reranked_tail, tail);
+
+        assert!(rerank_head(&mut hits, |_| Ok(vec![1.0])).is_err());
+    }
+
+    #[test]
+    fn reasoning_filter_judges_where_terms_match() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let in_reasoning = "reasoning: maybe a mutex\nuse a channel";
+        let in_answer = "reasoning: think it over\nuse a mutex";
+        save_message(&mut store, "thought", "m1", in_reasoning);
+        save_message(&mut store, "answer", "m2", in_answer);
+        save_message(&mut store, "plain", "m3", "nothing relevant");
+        let sessions = |mode: ReasoningFilter| {
+            let mut ids: Vec<String> = session_search(&store, "mutex", 10, mode, None)
+                .into_iter()
+                .map(|hit| hit.session_id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(
+            sessions(ReasoningFilter::Include),
+            ["answer", "plain", "thought"]
+        );
+        assert_eq!(sessions(ReasoningFilter::Exclude), ["answer"]);
+        assert_eq!(sessions(ReasoningFilter::Only), ["thought"]);
+    }
+
+    #[test]
+    fn reasoning_filter_applies_before_capping_candidates() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        save_message(
+            &mut store,
+            "answer",
+            "m_answer",
+            "use a mutex around the counter",
+        );
+        // Far more than limit * 5 reasoning-only hits, each ranking above it.
+        for i in 0..30 {
+            save_message(
+                &mut store,
+                &format!("thought{i}"),
+                &format!("m{i}"),
+                "reasoning: mutex mutex\nok",
+            );
+        }
+        let hits = session_search(&store, "mutex", 2, ReasoningFilter::Exclude, None);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].session_id, "answer");
+    }
+
     #[test]
     fn search_sessions_groups_hits() {
         let store = setup_store();
         #[cfg(feature = "semantic")]
-        let sessions = search_sessions(&store, "rust", 10, false, None, None).unwrap();
+        let (sessions, candidates) = search_sessions(
+            &store,
+            "rust",
+            10,
+            false,
+            Weights::default(),
+            ReasoningFilter::Include,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
         #[cfg(not(feature = "semantic"))]
-        let sessions = search_sessions(&store, "rust", 10, false).unwrap();
+        let (sessions, candidates) = search_sessions(
+            &store,
+            "rust",
+            10,
+            false,
+            Weights::default(),
+            ReasoningFilter::Include,
+            None,
+        )
+        .unwrap();
         assert_eq!(sessions.len(), 1);
+        assert_eq!(candidates.sessions, 1);
+        assert!(candidates.bm25 >= 1 && candidates.messages >= candidates.bm25);
+        assert!(sessions[0].channels.bm25 > 0.0);
         assert_eq!(sessions[0].session_id, "s1");
         assert!(sessions[0].score > 0.0);
         assert_eq!(sessions[0].top_message_id, "m1");
+
+        let as_of = Some(Utc::now() - chrono::Duration::days(1));
+        #[cfg(feature = "semantic")]
+        let (before, _) = search_sessions(
+            &store,
+            "rust",
+            10,
+            false,
+            Weights::default(),
+            ReasoningFilter::Include,
+            as_of,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        #[cfg(not(feature = "semantic"))]
+        let (before, _) = search_sessions(
+            &store,
+            "rust",
+            10,
+            false,
+            Weights::default(),
+            ReasoningFilter::Include,
+            as_of,
+        )
+        .unwrap();
+        assert!(before.is_empty());
+
+        store.trash_session("s1").unwrap();
+        #[cfg(feature = "semantic")]
+        let (trashed, _) = search_sessions(
+            &store,
+            "rust",
+            10,
+            false,
+            Weights::default(),
+            ReasoningFilter::Include,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        #[cfg(not(feature = "semantic"))]
+        let (trashed, _) = search_sessions(
+            &store,
+            "rust",
+            10,
+            false,
+            Weights::default(),
+            ReasoningFilter::Include,
+            None,
+        )
+        .unwrap();
+        assert!(trashed.is_empty());
+    }
+
+    #[test]
+    fn as_of_searches_before_capping_candidates() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        save_message(&mut store, "old", "m_old", "needle in an older haystack");
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        let cutoff = Utc::now();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        // Far more than limit * 5 later hits, each ranking above the old one.
+        for i in 0..30 {
+            save_message(
+                &mut store,
+                &format!("new{i}"),
+                &format!("m{i}"),
+                "needle needle",
+            );
+        }
+        assert!(
+            !session_search(&store, "needle", 2, ReasoningFilter::Include, None)
+                .iter()
+                .any(|hit| hit.session_id == "old")
+        );
+        let hits = session_search(&store, "needle", 2, ReasoningFilter::Include, Some(cutoff));
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].session_id, "old");
+    }
+
+    #[test]
+    fn trashed_sessions_do_not_crowd_out_live_hits() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        save_message(&mut store, "live", "m_live", "needle in a live haystack");
+        // Far more than limit * 5 trashed hits, each ranking above the live one.
+        for i in 0..30 {
+            let session = format!("gone{i}");
+            save_message(&mut store, &session, &format!("m{i}"), "needle needle");
+            store.trash_session(&session).unwrap();
+        }
+        let hits = session_search(&store, "needle", 2, ReasoningFilter::Include, None);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].session_id, "live");
     }
 
     #[test]
@@ -599,11 +1214,32 @@ mod tests {
     fn search_substring_fallback() {
         let store = setup_store();
         #[cfg(feature = "semantic")]
-        let hits = search(&store, "progr", 10, false, None, None).unwrap();
+        let (hits, candidates) = search(
+            &store,
+            "progr",
+            10,
+            false,
+            Weights::default(),
+            &SearchScope::default(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
         #[cfg(not(feature = "semantic"))]
-        let hits = search(&store, "progr", 10, false).unwrap();
+        let (hits, candidates) = search(
+            &store,
+            "progr",
+            10,
+            false,
+            Weights::default(),
+            &SearchScope::default(),
+        )
+        .unwrap();
         assert_eq!(hits.len(), 1);
         assert_eq!(hits[0].message_id, "m1");
+        assert_eq!(hits[0].channels.substring, hits[0].score);
+        assert_eq!((candidates.bm25, candidates.substring), (0, 1));
     }
 
     #[test]
@@ -611,7 +1247,7 @@ mod tests {
         let db_path = temp_db_path("docs-fts");
         setup_docs_db(&db_path);
 
-        let hits = search_docs_at(&db_path, "docs-search", 10, false).unwrap();
+        let hits = search_docs_at(&db_path, None, "docs-search", 10, false).unwrap();
         assert_eq!(hits.len(), 1);
         assert_eq!(hits[0].path, "guides/setup.md");
         assert_eq!(hits[0].title, "Setup Guide");
@@ -626,7 +1262,7 @@ mod tests {
         let db_path = temp_db_path("docs-substring");
         setup_docs_db(&db_path);
 
-        let hits = search_docs_at(&db_path, "docs-sear", 10, false).unwrap();
+        let hits = search_docs_at(&db_path, None, "docs-sear", 10, false).unwrap();
         assert_eq!(hits.len(), 1);
         assert_eq!(hits[0].path, "guides/setup.md");
         assert!(hits[0].snippet.to_lowercase().contains("docs-search"));
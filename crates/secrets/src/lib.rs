@@ -0,0 +1,323 @@
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+use anyhow::{Context, bail};
+
+mod wincred;
+
+pub const SERVICE: &str = "remi";
+pub const DB_KEY_ENV: &str = "REMI_DB_KEY";
+pub const RERANK_API_KEY: &str = "rerank-api-key";
+pub const GITHUB_TOKEN: &str = "github-token";
+pub const S3_ACCESS_KEY_ID: &str = "s3-access-key-id";
+pub const S3_SECRET_ACCESS_KEY: &str = "s3-secret-access-key";
+
+// Secrets are reached through the platform's own keyring CLI (libsecret's
+// `secret-tool`, macOS `security`) so no keyring bindings are linked in.
+// Windows has no such CLI, so the Credential Manager is called directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Keyring {
+    SecretTool,
+    Security,
+    CredentialManager,
+}
+
+impl Keyring {
+    fn current() -> anyhow::Result<Self> {
+        if cfg!(target_os = "macos") {
+            Ok(Keyring::Security)
+        } else if cfg!(windows) {
+            Ok(Keyring::CredentialManager)
+        } else if cfg!(unix) {
+            Ok(Keyring::SecretTool)
+        } else {
+            bail!("no supported keyring on this platform")
+        }
+    }
+
+    fn lookup(self, name: &str) -> Command {
+        match self {
+            Keyring::SecretTool => command(
+                "secret-tool",
+                &["lookup", "service", SERVICE, "account", name],
+            ),
+            Keyring::Security => command(
+                "security",
+                &["find-generic-password", "-s", SERVICE, "-a", name, "-w"],
+            ),
+            Keyring::CredentialManager => unreachable!("the Credential Manager has no CLI"),
+        }
+    }
+
+    // Both tools get the secret on stdin, so it never shows up in the process
+    // list. `security` has no such flag, but its interactive mode reads whole
+    // commands from stdin.
+    fn store(self, name: &str, secret: &str) -> anyhow::Result<(Command, String)> {
+        match self {
+            Keyring::SecretTool => {
+                let label = format!("{SERVICE} {name}");
+                let command = command(
+                    "secret-tool",
+                    &[
+                        "store", "--label", &label, "service", SERVICE, "account", name,
+                    ],
+                );
+                Ok((command, secret.to_string()))
+            }
+            Keyring::Security => {
+                if secret.contains(['\n', '\r']) {
+                    bail!("the macOS keyring cannot store a secret with line breaks");
+                }
+                let quoted = secret.replace('\\', "\\\\").replace('"', "\\\"");
+                let line =
+                    format!("add-generic-password -U -s {SERVICE} -a {name} -w \"{quoted}\"\n");
+                Ok((command("security", &["-i"]), line))
+            }
+            Keyring::CredentialManager => unreachable!("the Credential Manager has no CLI"),
+        }
+    }
+
+    fn clear(self, name: &str) -> Command {
+        match self {
+            Keyring::SecretTool => command(
+                "secret-tool",
+                &["clear", "service", SERVICE, "account", name],
+            ),
+            Keyring::Security => command(
+                "security",
+                &["delete-generic-password", "-s", SERVICE, "-a", name],
+            ),
+            Keyring::CredentialManager => unreachable!("the Credential Manager has no CLI"),
+        }
+    }
+}
+
+fn command(program: &str, args: &[&str]) -> Command {
+    let mut command = Command::new(program);
+    command.args(args);
+    command
+}
+
+fn run(mut command: Command, stdin: Option<String>) -> anyhow::Result<Output> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let mut child = command
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to run {program}; is it installed?"))?;
+    if let Some(input) = stdin {
+        child
+            .stdin
+            .take()
+            .context("keyring stdin unavailable")?
+            .write_all(input.as_bytes())?;
+    }
+    Ok(child.wait_with_output()?)
+}
+
+fn failure(output: &Output, action: &str) -> anyhow::Error {
+    anyhow::anyhow!(
+        "keyring {action} failed ({}): {}",
+        output.status,
+        String::from_utf8_lossy(&output.stderr).trim()
+    )
+}
+
+pub fn validate_name(name: &str) -> anyhow::Result<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+    {
+        bail!("invalid secret name {name:?}; use letters, digits, '-', '_', '.' and ':'");
+    }
+    Ok(())
+}
+
+// Credential Manager entries are named after the service, like the account
+// of the other keyrings.
+fn target(name: &str) -> String {
+    format!("{SERVICE}:{name}")
+}
+
+pub fn get(name: &str) -> anyhow::Result<Option<String>> {
+    validate_name(name)?;
+    let keyring = Keyring::current()?;
+    if keyring == Keyring::CredentialManager {
+        return wincred::read(&target(name));
+    }
+    let output = run(keyring.lookup(name), None)?;
+    // Both tools exit non-zero with empty output when the entry is missing.
+    if !output.status.success() {
+        if output.stdout.is_empty() {
+            return Ok(None);
+        }
+        return Err(failure(&output, "lookup"));
+    }
+    let secret = String::from_utf8(output.stdout).context("keyring secret is not UTF-8")?;
+    let secret = secret.trim_end_matches(['\r', '\n']);
+    Ok((!secret.is_empty()).then(|| secret.to_string()))
+}
+
+pub fn set(name: &str, secret: &str) -> anyhow::Result<()> {
+    validate_name(name)?;
+    if secret.is_empty() {
+        bail!("refusing to store an empty secret for {name}");
+    }
+    let keyring = Keyring::current()?;
+    if keyring == Keyring::CredentialManager {
+        return wincred::write(&target(name), name, secret);
+    }
+    let (command, stdin) = keyring.store(name, secret)?;
+    let output = run(command, Some(stdin))?;
+    // `security -i` exits zero when a command it read fails, but reports it.
+    if !output.status.success() || (keyring == Keyring::Security && !output.stderr.is_empty()) {
+        return Err(failure(&output, "store"));
+    }
+    Ok(())
+}
+
+pub fn delete(name: &str) -> anyhow::Result<()> {
+    validate_name(name)?;
+    let keyring = Keyring::current()?;
+    if keyring == Keyring::CredentialManager {
+        return wincred::delete(&target(name));
+    }
+    let output = run(keyring.clear(name), None)?;
+    if !output.status.success() {
+        return Err(failure(&output, "delete"));
+    }
+    Ok(())
+}
+
+pub fn database_key_name(profile: &str) -> String {
+    format!("database:{profile}")
+}
+
+// REMI_DB_KEY wins so scripts and CI can run without a keyring daemon.
+pub fn database_key(profile: &str) -> anyhow::Result<String> {
+    if let Ok(key) = std::env::var(DB_KEY_ENV)
+        && !key.is_empty()
+    {
+        return Ok(key);
+    }
+    let name = database_key_name(profile);
+    get(&name)?.with_context(|| {
+        format!(
+            "no database key for profile {profile}; set {DB_KEY_ENV} or run `remi secrets set {name}`"
+        )
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct S3Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+// The standard AWS variables win, as REMI_DB_KEY does for the database key.
+pub fn s3_credentials() -> anyhow::Result<Option<S3Credentials>> {
+    let env = |var: &str| std::env::var(var).ok().filter(|value| !value.is_empty());
+    let (access_key_id, secret_access_key) =
+        match (env("AWS_ACCESS_KEY_ID"), env("AWS_SECRET_ACCESS_KEY")) {
+            (Some(id), Some(secret)) => (Some(id), Some(secret)),
+            _ => (get(S3_ACCESS_KEY_ID)?, get(S3_SECRET_ACCESS_KEY)?),
+        };
+    match (access_key_id, secret_access_key) {
+        (Some(access_key_id), Some(secret_access_key)) => Ok(Some(S3Credentials {
+            access_key_id,
+            secret_access_key,
+        })),
+        (None, None) => Ok(None),
+        _ => bail!(
+            "only one of {S3_ACCESS_KEY_ID} and {S3_SECRET_ACCESS_KEY} is stored; run `remi secrets set` for both"
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(command: &Command) -> Vec<String> {
+        std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn keyring_commands_address_the_remi_service() {
+        assert_eq!(
+            args(&Keyring::SecretTool.lookup("rerank-api-key")),
+            [
+                "secret-tool",
+                "lookup",
+                "service",
+                "remi",
+                "account",
+                "rerank-api-key"
+            ]
+        );
+        let (store, stdin) = Keyring::SecretTool
+            .store("database:work", "hunter2")
+            .unwrap();
+        assert!(!args(&store).contains(&"hunter2".to_string()));
+        assert_eq!(stdin, "hunter2");
+        let (store, stdin) = Keyring::Security
+            .store("database:work", r#"hun"ter\2"#)
+            .unwrap();
+        assert_eq!(args(&store), ["security", "-i"]);
+        assert_eq!(
+            stdin,
+            "add-generic-password -U -s remi -a database:work -w \"hun\\\"ter\\\\2\"\n"
+        );
+        assert!(Keyring::Security.store("x", "two\nlines").is_err());
+        assert_eq!(
+            args(&Keyring::Security.clear("x")),
+            [
+                "security",
+                "delete-generic-password",
+                "-s",
+                "remi",
+                "-a",
+                "x"
+            ]
+        );
+    }
+
+    #[test]
+    fn s3_credentials_prefer_the_aws_variables() {
+        // SAFETY: no other test in this crate reads the AWS variables.
+        unsafe {
+            std::env::set_var("AWS_ACCESS_KEY_ID", "AKIDEXAMPLE");
+            std::env::set_var("AWS_SECRET_ACCESS_KEY", "wJalrXUtnFEMI");
+        }
+        assert_eq!(
+            s3_credentials().unwrap(),
+            Some(S3Credentials {
+                access_key_id: "AKIDEXAMPLE".to_string(),
+                secret_access_key: "wJalrXUtnFEMI".to_string(),
+            })
+        );
+        assert!(validate_name(S3_ACCESS_KEY_ID).is_ok());
+        assert!(validate_name(S3_SECRET_ACCESS_KEY).is_ok());
+    }
+
+    #[test]
+    fn secret_names_are_restricted() {
+        assert!(validate_name("database:work").is_ok());
+        assert!(validate_name("s3.secret_key").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("has space").is_err());
+        assert!(set("name", "").is_err());
+        assert_eq!(database_key_name("work"), "database:work");
+    }
+}
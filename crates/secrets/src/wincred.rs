@@ -0,0 +1,101 @@
+// Generic credentials in the Windows Credential Manager, addressed by target
+// name. There is no CLI that reads a stored secret back, so this goes through
+// the Win32 API.
+
+#[cfg(windows)]
+mod imp {
+    use anyhow::{Context, bail};
+    use windows_sys::Win32::Foundation::{ERROR_NOT_FOUND, GetLastError};
+    use windows_sys::Win32::Security::Credentials::{
+        CRED_PERSIST_LOCAL_MACHINE, CRED_TYPE_GENERIC, CREDENTIALW, CredDeleteW, CredFree,
+        CredReadW, CredWriteW,
+    };
+
+    fn wide(text: &str) -> Vec<u16> {
+        text.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    pub fn read(target: &str) -> anyhow::Result<Option<String>> {
+        let target = wide(target);
+        let mut credential: *mut CREDENTIALW = std::ptr::null_mut();
+        // SAFETY: `target` is NUL-terminated and outlives the call.
+        if unsafe { CredReadW(target.as_ptr(), CRED_TYPE_GENERIC, 0, &mut credential) } == 0 {
+            // SAFETY: reads the calling thread's last error code.
+            let code = unsafe { GetLastError() };
+            if code == ERROR_NOT_FOUND {
+                return Ok(None);
+            }
+            bail!("keyring lookup failed (Windows error {code})");
+        }
+        // SAFETY: CredReadW succeeded, so `credential` points to a valid
+        // CREDENTIALW whose blob holds `CredentialBlobSize` bytes. It is
+        // copied out before the buffer is released.
+        let blob = unsafe {
+            let size = (*credential).CredentialBlobSize as usize;
+            let blob = if size == 0 {
+                Vec::new()
+            } else {
+                std::slice::from_raw_parts((*credential).CredentialBlob, size).to_vec()
+            };
+            CredFree(credential as *const _);
+            blob
+        };
+        let secret = String::from_utf8(blob).context("keyring secret is not UTF-8")?;
+        Ok((!secret.is_empty()).then_some(secret))
+    }
+
+    pub fn write(target: &str, user: &str, secret: &str) -> anyhow::Result<()> {
+        let mut target = wide(target);
+        let mut user = wide(user);
+        let mut blob = secret.as_bytes().to_vec();
+        let credential = CREDENTIALW {
+            Type: CRED_TYPE_GENERIC,
+            TargetName: target.as_mut_ptr(),
+            CredentialBlobSize: u32::try_from(blob.len()).context("secret is too large")?,
+            CredentialBlob: blob.as_mut_ptr(),
+            Persist: CRED_PERSIST_LOCAL_MACHINE,
+            UserName: user.as_mut_ptr(),
+            ..Default::default()
+        };
+        // SAFETY: every pointer in `credential` refers to a buffer that lives
+        // until the call returns; CredWriteW copies what it keeps.
+        if unsafe { CredWriteW(&credential, 0) } == 0 {
+            // SAFETY: reads the calling thread's last error code.
+            bail!("keyring store failed (Windows error {})", unsafe {
+                GetLastError()
+            });
+        }
+        Ok(())
+    }
+
+    pub fn delete(target: &str) -> anyhow::Result<()> {
+        let target = wide(target);
+        // SAFETY: `target` is NUL-terminated and outlives the call.
+        if unsafe { CredDeleteW(target.as_ptr(), CRED_TYPE_GENERIC, 0) } == 0 {
+            // SAFETY: reads the calling thread's last error code.
+            bail!("keyring delete failed (Windows error {})", unsafe {
+                GetLastError()
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(windows))]
+mod imp {
+    use anyhow::bail;
+
+    pub fn read(_target: &str) -> anyhow::Result<Option<String>> {
+        bail!("the Windows Credential Manager is only available on Windows")
+    }
+
+    pub fn write(_target: &str, _user: &str, _secret: &str) -> anyhow::Result<()> {
+        bail!("the Windows Credential Manager is only available on Windows")
+    }
+
+    pub fn delete(_target: &str) -> anyhow::Result<()> {
+        bail!("the Windows Credential Manager is only available on Windows")
+    }
+}
+
+pub use imp::{delete, read, write};
@@ -1,19 +1,84 @@
-use std::path::{Path, PathBuf};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+};
 
 use anyhow::Context;
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use core_model::{
-    ArchiveItem, ArchiveRun, Checkpoint, Message, NormalizedBatch, Provenance, Session,
-    deterministic_id,
+    AgentKind, ArchiveItem, ArchiveRun, Checkpoint, DirListing, IssueRef, Message, NativeRecord,
+    NormalizedBatch, Provenance, RepoRef, Session, detect_code_languages, deterministic_id,
+    extract_links, extract_refs, split_reasoning, tool_commands, tool_file_mentions,
+};
+use rusqlite::{
+    Connection, OptionalExtension, Row, named_params, params, types::Value as SqlValue,
 };
-use rusqlite::{Connection, OptionalExtension, params};
 use std::time::Instant;
 use tracing::{debug, info, trace};
 
+pub const SQLCIPHER: bool = cfg!(feature = "sqlcipher");
+pub const COMPRESS_THRESHOLD_BYTES: usize = 8 * 1024;
+pub const FTS_CONTENT_LIMIT_BYTES: usize = 64 * 1024;
+const ZSTD_LEVEL: i32 = 3;
+
 pub struct SqliteStore {
     conn: Connection,
+    retain_raw_records: bool,
+}
+
+// Which messages search candidates are drawn from. The limits apply inside
+// the candidate queries, so they never use up slots of a capped result.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SearchScope {
+    // Only messages first stored before this instant (`--as-of`).
+    pub ingested_before: Option<DateTime<Utc>>,
+    // Sessions in the trash are left out unless this is set.
+    pub include_trashed: bool,
+    pub reasoning: ReasoningFilter,
+}
+
+// Which part of a message a query has to match. Reasoning lines are indexed
+// in their own FTS column, the rest of the content in `content`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReasoningFilter {
+    #[default]
+    Include,
+    Exclude,
+    Only,
+}
+
+impl ReasoningFilter {
+    fn column(self) -> Option<&'static str> {
+        match self {
+            ReasoningFilter::Include => None,
+            ReasoningFilter::Exclude => Some("content"),
+            ReasoningFilter::Only => Some("reasoning"),
+        }
+    }
+}
+
+impl SearchScope {
+    fn before(&self) -> Option<String> {
+        self.ingested_before.map(|cutoff| cutoff.to_rfc3339())
+    }
+
+    fn part(&self) -> Option<&'static str> {
+        self.reasoning.column()
+    }
 }
 
+// Matches messages `m` against a `SearchScope` bound as `:before`, `:trashed`
+// and `:part`. Timestamps on both sides are UTC RFC 3339 strings from chrono,
+// which sort as they compare.
+const SCOPE_FILTER: &str = "(:before IS NULL OR m.ingested_at < :before)
+    AND (:trashed OR NOT EXISTS (
+      SELECT 1 FROM sessions ts WHERE ts.id = m.session_id AND ts.deleted_at IS NOT NULL
+    ))
+    AND (:part IS NULL OR EXISTS (
+      SELECT 1 FROM fts_messages pf WHERE pf.rowid = m.rowid
+        AND (CASE :part WHEN 'content' THEN pf.content ELSE pf.reasoning END) <> ''
+    ))";
+
 #[derive(Debug, Clone)]
 pub struct SearchRow {
     pub message_id: String,
@@ -71,6 +136,163 @@ pub struct DocumentSearchRow {
     pub score: f64,
 }
 
+#[derive(Debug, Clone)]
+pub struct SessionBundle {
+    pub session: Session,
+    pub messages: Vec<Message>,
+    pub events: Vec<core_model::Event>,
+    pub artifacts: Vec<core_model::Artifact>,
+    pub provenance: Vec<Provenance>,
+    pub meta: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StorageUsage {
+    pub db_bytes: u64,
+    pub free_bytes: u64,
+    // Pages per table, with indexes counted under the table they index.
+    // Empty when the SQLite build lacks the `dbstat` virtual table.
+    pub tables: Vec<(String, u64)>,
+    pub agents: Vec<AgentStorage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentStorage {
+    pub agent: String,
+    pub messages: usize,
+    // Stored (possibly compressed) message content size.
+    pub content_bytes: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RawRecordsUsage {
+    pub records: usize,
+    // Stored (compressed) payload size.
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionAggregate {
+    pub message_count: usize,
+    pub first_ts: DateTime<Utc>,
+    pub last_ts: DateTime<Utc>,
+    pub by_role: Vec<(String, usize)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectSummary {
+    pub remote: String,
+    pub session_count: usize,
+    pub message_count: usize,
+    pub last_ts: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphNode {
+    pub id: i64,
+    pub kind: String,
+    pub name: String,
+    pub sessions: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphEdge {
+    pub source: i64,
+    pub target: i64,
+    pub relation: String,
+    pub sessions: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Graph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileHistoryRow {
+    pub session_id: String,
+    pub agent: AgentKind,
+    pub title: String,
+    pub mentions: usize,
+    pub first_ts: DateTime<Utc>,
+    pub last_ts: DateTime<Utc>,
+    pub tools: Vec<String>,
+    pub paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandRow {
+    pub message_id: String,
+    pub session_id: String,
+    pub agent: AgentKind,
+    pub tool: String,
+    pub command: String,
+    pub ts: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LinkRow {
+    pub message_id: String,
+    pub session_id: String,
+    pub agent: AgentKind,
+    pub role: String,
+    pub url: String,
+    pub context: String,
+    pub ts: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Bookmark {
+    pub message: Message,
+    pub tags: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FtsHealth {
+    pub stray_rows: usize,
+    pub missing_rows: usize,
+}
+
+// Rows whose message (or other entity) no longer exists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OrphanRows {
+    pub embeddings: usize,
+    pub provenance: usize,
+}
+
+// Rows removed by `wipe_sessions`, by table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WipeCounts {
+    pub sessions: usize,
+    pub messages: usize,
+    pub events: usize,
+    pub artifacts: usize,
+    pub provenance: usize,
+    pub embeddings: usize,
+    pub fts_rows: usize,
+    pub raw_records: usize,
+    pub archive_items: usize,
+}
+
+const ORPHAN_EMBEDDINGS: &str = r#"FROM message_embeddings e
+    WHERE NOT EXISTS (SELECT 1 FROM messages m WHERE m.id = e.message_id)"#;
+const ORPHAN_PROVENANCE: &str = r#"FROM provenance p
+    WHERE (p.entity_type = 'message' AND NOT EXISTS (SELECT 1 FROM messages x WHERE x.id = p.entity_id))
+       OR (p.entity_type = 'session' AND NOT EXISTS (SELECT 1 FROM sessions x WHERE x.id = p.entity_id))
+       OR (p.entity_type = 'event' AND NOT EXISTS (SELECT 1 FROM events x WHERE x.id = p.entity_id))
+       OR (p.entity_type = 'artifact' AND NOT EXISTS (SELECT 1 FROM artifacts x WHERE x.id = p.entity_id))"#;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileState {
+    pub path: String,
+    pub content_hash: String,
+    pub size_bytes: i64,
+    pub modified_at: DateTime<Utc>,
+    pub record_count: i64,
+}
+
 #[cfg(feature = "semantic")]
 #[derive(Debug, Clone, Default)]
 pub struct EmbeddingStats {
@@ -78,6 +300,14 @@ pub struct EmbeddingStats {
     pub updated_at: Option<DateTime<Utc>>,
 }
 
+#[cfg(feature = "semantic")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddingJob {
+    pub message_id: String,
+    pub content: String,
+    pub attempts: i64,
+}
+
 impl SqliteStore {
     pub fn open_default() -> anyhow::Result<Self> {
         let base = dirs::data_dir().unwrap_or_else(|| PathBuf::from("."));
@@ -90,14 +320,20 @@ impl SqliteStore {
     }
 
     pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
-        debug!(path = %path.as_ref().display(), "opening sqlite connection");
-        let conn = Connection::open(path.as_ref())
-            .with_context(|| format!("opening sqlite db {}", path.as_ref().display()))?;
+        Self::open_with_key(path, None)
+    }
+
+    pub fn open_with_key(path: impl AsRef<Path>, key: Option<&str>) -> anyhow::Result<Self> {
+        debug!(path = %path.as_ref().display(), encrypted = key.is_some(), "opening sqlite connection");
+        let conn = open_connection(path.as_ref(), key)?;
         conn.execute_batch(
             "PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL; PRAGMA foreign_keys = ON;",
         )?;
         conn.busy_timeout(std::time::Duration::from_secs(5))?;
-        Ok(Self { conn })
+        Ok(Self {
+            conn,
+            retain_raw_records: false,
+        })
     }
 
     pub fn init_schema(&self) -> anyhow::Result<()> {
@@ -253,526 +489,3116 @@ impl SqliteStore {
                 "#,
             )?;
         }
-        for (id, name) in [
-            ("pi", "pi"),
-            ("droid", "droid"),
-            ("opencode", "opencode"),
-            ("claude", "claude"),
-            ("amp", "amp"),
-        ] {
-            self.conn.execute(
-                "INSERT OR IGNORE INTO agents (id, name) VALUES (?1, ?2)",
-                params![id, name],
+        if version < 4 {
+            self.conn.execute_batch(
+                r#"
+                CREATE TABLE IF NOT EXISTS embedding_queue (
+                  message_id TEXT PRIMARY KEY,
+                  enqueued_at TEXT NOT NULL,
+                  attempts INTEGER NOT NULL DEFAULT 0,
+                  FOREIGN KEY(message_id) REFERENCES messages(id) ON DELETE CASCADE
+                );
+                PRAGMA user_version = 4;
+                "#,
             )?;
         }
-        Ok(())
-    }
-
-    pub fn save_batch(&mut self, batch: &NormalizedBatch) -> anyhow::Result<()> {
-        let started = Instant::now();
-        let mut last = started;
-        info!(
-            sessions = batch.sessions.len(),
-            messages = batch.messages.len(),
-            events = batch.events.len(),
-            artifacts = batch.artifacts.len(),
-            provenance = batch.provenance.len(),
-            "save_batch start"
-        );
-        let tx = self.conn.transaction()?;
-        {
-            let mut stmt_session = tx.prepare_cached(
-                r#"INSERT INTO sessions (id, agent, source_ref, title, created_at, updated_at)
-                VALUES (?1, ?2, ?3, ?4, ?5, ?6)
-                ON CONFLICT(id) DO UPDATE SET
-                  agent=excluded.agent,
-                  source_ref=excluded.source_ref,
-                  title=excluded.title,
-                  updated_at=excluded.updated_at"#,
+        if version < 5 {
+            self.conn.execute_batch(
+                r#"
+                ALTER TABLE messages ADD COLUMN content_encoding TEXT NOT NULL DEFAULT 'plain';
+                PRAGMA user_version = 5;
+                "#,
             )?;
-            for s in &batch.sessions {
-                stmt_session.execute(params![
-                    s.id,
-                    s.agent.as_str(),
-                    s.source_ref,
-                    s.title,
-                    s.created_at.to_rfc3339(),
-                    s.updated_at.to_rfc3339()
-                ])?;
-            }
         }
-        let now = Instant::now();
-        info!(
-            elapsed = ?now.duration_since(started),
-            delta = ?now.duration_since(last),
-            "sessions upserted"
-        );
-        last = now;
-        {
-            let mut stmt_msg = tx.prepare_cached(
-                r#"INSERT INTO messages (id, session_id, role, content, ts)
-                VALUES (?1, ?2, ?3, ?4, ?5)
-                ON CONFLICT(id) DO UPDATE SET
-                  role=excluded.role,
-                  content=excluded.content,
-                  ts=excluded.ts"#,
+        if version < 6 {
+            self.conn.execute_batch(
+                r#"
+                DELETE FROM checkpoints WHERE agent IN ('pi', 'codex');
+                PRAGMA user_version = 6;
+                "#,
             )?;
-            for m in &batch.messages {
-                stmt_msg.execute(params![
-                    m.id,
-                    m.session_id,
-                    m.role,
-                    m.content,
-                    m.ts.to_rfc3339()
-                ])?;
-            }
         }
-        let now = Instant::now();
-        info!(
-            elapsed = ?now.duration_since(started),
-            delta = ?now.duration_since(last),
-            "messages upserted"
-        );
-        last = now;
-        {
-            let mut seen_message_ids = std::collections::HashSet::new();
-            let mut stmt_lookup_rowid =
-                tx.prepare_cached("SELECT rowid FROM messages WHERE id = ?1")?;
-            let mut stmt_delete_message =
-                tx.prepare_cached("DELETE FROM fts_messages WHERE rowid = ?1")?;
-            let mut stmt_insert = tx.prepare_cached(
-                "INSERT INTO fts_messages (rowid, message_id, session_id, content, ts)
-                VALUES (?1, ?2, ?3, ?4, ?5)",
+        if version < 7 {
+            self.conn.execute_batch(
+                r#"
+                CREATE TABLE IF NOT EXISTS file_state (
+                  agent TEXT NOT NULL,
+                  path TEXT NOT NULL,
+                  content_hash TEXT NOT NULL,
+                  size_bytes INTEGER NOT NULL,
+                  modified_at TEXT NOT NULL,
+                  record_count INTEGER NOT NULL,
+                  updated_at TEXT NOT NULL,
+                  PRIMARY KEY(agent, path)
+                );
+                PRAGMA user_version = 7;
+                "#,
             )?;
-            for m in &batch.messages {
-                if seen_message_ids.insert(&m.id) {
-                    let rowid: i64 = stmt_lookup_rowid.query_row(params![m.id], |r| r.get(0))?;
-                    stmt_delete_message.execute(params![rowid])?;
-                    stmt_insert.execute(params![
-                        rowid,
-                        m.id,
-                        m.session_id,
-                        m.content,
-                        m.ts.to_rfc3339()
-                    ])?;
-                }
-            }
         }
-        let now = Instant::now();
-        info!(
-            elapsed = ?now.duration_since(started),
-            delta = ?now.duration_since(last),
-            "fts updated"
-        );
-        last = now;
-        {
-            let mut stmt_event = tx.prepare_cached(
-                r#"INSERT INTO events (id, session_id, kind, payload, ts)
-                VALUES (?1, ?2, ?3, ?4, ?5)
-                ON CONFLICT(id) DO UPDATE SET kind=excluded.kind, payload=excluded.payload, ts=excluded.ts"#,
+        if version < 8 {
+            self.conn.execute_batch(
+                r#"
+                UPDATE messages SET role = CASE lower(trim(role))
+                  WHEN 'user' THEN 'user'
+                  WHEN 'human' THEN 'user'
+                  WHEN 'assistant' THEN 'assistant'
+                  WHEN 'model' THEN 'assistant'
+                  WHEN 'ai' THEN 'assistant'
+                  WHEN 'agent' THEN 'assistant'
+                  WHEN 'bot' THEN 'assistant'
+                  WHEN 'system' THEN 'system'
+                  WHEN 'developer' THEN 'system'
+                  WHEN 'tool' THEN 'tool'
+                  WHEN 'toolresult' THEN 'tool'
+                  WHEN 'tool_result' THEN 'tool'
+                  WHEN 'tool_use' THEN 'tool'
+                  WHEN 'function' THEN 'tool'
+                  ELSE 'user'
+                END
+                WHERE role NOT IN ('user', 'assistant', 'system', 'tool');
+                PRAGMA user_version = 8;
+                "#,
             )?;
-            for e in &batch.events {
-                stmt_event.execute(params![
-                    e.id,
-                    e.session_id,
-                    e.kind,
-                    serde_json::to_string(&e.payload)?,
-                    e.ts.to_rfc3339()
-                ])?;
-            }
         }
-        let now = Instant::now();
-        info!(
-            elapsed = ?now.duration_since(started),
-            delta = ?now.duration_since(last),
-            "events upserted"
-        );
-        last = now;
-        {
-            let mut stmt_artifact = tx.prepare_cached(
-                r#"INSERT INTO artifacts (id, session_id, path, checksum, metadata)
-                VALUES (?1, ?2, ?3, ?4, ?5)
-                ON CONFLICT(id) DO UPDATE SET path=excluded.path, checksum=excluded.checksum, metadata=excluded.metadata"#,
+        if version < 9 {
+            self.conn.execute_batch(
+                r#"
+                CREATE TABLE IF NOT EXISTS message_languages (
+                  message_id TEXT NOT NULL,
+                  lang TEXT NOT NULL,
+                  PRIMARY KEY(message_id, lang),
+                  FOREIGN KEY(message_id) REFERENCES messages(id) ON DELETE CASCADE
+                );
+                CREATE INDEX IF NOT EXISTS idx_message_languages_lang ON message_languages(lang);
+                "#,
             )?;
-            for a in &batch.artifacts {
-                stmt_artifact.execute(params![
-                    a.id,
-                    a.session_id,
-                    a.path,
-                    a.checksum,
-                    serde_json::to_string(&a.metadata)?
-                ])?;
-            }
+            let backfilled = self.backfill_message_languages()?;
+            debug!(backfilled, "backfilled message languages");
+            self.conn.execute_batch("PRAGMA user_version = 9;")?;
         }
-        let now = Instant::now();
-        info!(
-            elapsed = ?now.duration_since(started),
-            delta = ?now.duration_since(last),
-            "artifacts upserted"
-        );
-        last = now;
-        {
-            let mut stmt_prov = tx.prepare_cached(
-                r#"INSERT INTO provenance (id, entity_type, entity_id, agent, source_path, source_id)
-                VALUES (?1, ?2, ?3, ?4, ?5, ?6)
-                ON CONFLICT(id) DO UPDATE SET source_path=excluded.source_path"#,
+        if version < 10 {
+            let has_kind: bool = self.conn.query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('file_state') WHERE name = 'kind'",
+                [],
+                |r| r.get(0),
             )?;
-            for p in &batch.provenance {
-                stmt_prov.execute(params![
-                    p.id,
-                    p.entity_type,
-                    p.entity_id,
-                    p.agent.as_str(),
-                    p.source_path,
-                    p.source_id
-                ])?;
+            if !has_kind {
+                self.conn.execute_batch(
+                    r#"
+                    ALTER TABLE file_state ADD COLUMN kind TEXT NOT NULL DEFAULT 'file';
+                    ALTER TABLE file_state ADD COLUMN entries TEXT;
+                    "#,
+                )?;
             }
+            self.conn.execute_batch("PRAGMA user_version = 10;")?;
         }
-        let now = Instant::now();
-        info!(
+        if version < 11 {
+            self.conn.execute_batch(
+                r#"
+                CREATE TABLE IF NOT EXISTS sync_cutoffs (
+                  agent TEXT PRIMARY KEY,
+                  cutoff TEXT NOT NULL,
+                  updated_at TEXT NOT NULL
+                );
+                PRAGMA user_version = 11;
+                "#,
+            )?;
+        }
+        if version < 12 {
+            let has_meta: bool = self.conn.query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('sessions') WHERE name = 'session_meta'",
+                [],
+                |r| r.get(0),
+            )?;
+            if !has_meta {
+                self.conn
+                    .execute_batch("ALTER TABLE sessions ADD COLUMN session_meta TEXT;")?;
+            }
+            self.conn.execute_batch("PRAGMA user_version = 12;")?;
+        }
+        if version < 13 {
+            let has_repo: bool = self.conn.query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('provenance') WHERE name = 'repo_remote'",
+                [],
+                |r| r.get(0),
+            )?;
+            if !has_repo {
+                self.conn.execute_batch(
+                    r#"
+                    ALTER TABLE provenance ADD COLUMN repo_remote TEXT;
+                    ALTER TABLE provenance ADD COLUMN repo_path TEXT;
+                    "#,
+                )?;
+            }
+            self.conn.execute_batch(
+                r#"
+                CREATE INDEX IF NOT EXISTS idx_provenance_repo_remote ON provenance(repo_remote);
+                PRAGMA user_version = 13;
+                "#,
+            )?;
+        }
+        if version < 14 {
+            self.conn.execute_batch(
+                r#"
+                CREATE TABLE IF NOT EXISTS query_embeddings (
+                  query TEXT NOT NULL,
+                  model_version TEXT NOT NULL,
+                  vec BLOB NOT NULL,
+                  last_used TEXT NOT NULL,
+                  PRIMARY KEY(query, model_version)
+                );
+                CREATE INDEX IF NOT EXISTS idx_query_embeddings_last_used ON query_embeddings(last_used);
+                PRAGMA user_version = 14;
+                "#,
+            )?;
+        }
+        if version < 15 {
+            self.conn.execute_batch(
+                r#"
+                CREATE TABLE IF NOT EXISTS bookmarks (
+                  message_id TEXT PRIMARY KEY,
+                  created_at TEXT NOT NULL,
+                  FOREIGN KEY(message_id) REFERENCES messages(id) ON DELETE CASCADE
+                );
+                CREATE TABLE IF NOT EXISTS bookmark_tags (
+                  message_id TEXT NOT NULL,
+                  tag TEXT NOT NULL,
+                  PRIMARY KEY(message_id, tag),
+                  FOREIGN KEY(message_id) REFERENCES bookmarks(message_id) ON DELETE CASCADE
+                );
+                CREATE INDEX IF NOT EXISTS idx_bookmark_tags_tag ON bookmark_tags(tag);
+                PRAGMA user_version = 15;
+                "#,
+            )?;
+        }
+        if version < 16 {
+            self.conn.execute_batch(
+                r#"
+                CREATE TABLE IF NOT EXISTS file_mentions (
+                  message_id TEXT NOT NULL,
+                  tool TEXT NOT NULL,
+                  path TEXT NOT NULL,
+                  PRIMARY KEY(message_id, tool, path),
+                  FOREIGN KEY(message_id) REFERENCES messages(id) ON DELETE CASCADE
+                );
+                CREATE INDEX IF NOT EXISTS idx_file_mentions_path ON file_mentions(path);
+                "#,
+            )?;
+            let backfilled = self.backfill_file_mentions()?;
+            debug!(backfilled, "backfilled file mentions");
+            self.conn.execute_batch("PRAGMA user_version = 16;")?;
+        }
+        if version < 17 {
+            self.conn.execute_batch(
+                r#"
+                CREATE TABLE IF NOT EXISTS commands (
+                  message_id TEXT NOT NULL,
+                  tool TEXT NOT NULL,
+                  command TEXT NOT NULL,
+                  PRIMARY KEY(message_id, tool, command),
+                  FOREIGN KEY(message_id) REFERENCES messages(id) ON DELETE CASCADE
+                );
+                "#,
+            )?;
+            let backfilled = self.backfill_commands()?;
+            debug!(backfilled, "backfilled commands");
+            self.conn.execute_batch("PRAGMA user_version = 17;")?;
+        }
+        if version < 18 {
+            self.conn.execute_batch(
+                r#"
+                CREATE TABLE IF NOT EXISTS links (
+                  message_id TEXT NOT NULL,
+                  url TEXT NOT NULL,
+                  context TEXT NOT NULL,
+                  PRIMARY KEY(message_id, url),
+                  FOREIGN KEY(message_id) REFERENCES messages(id) ON DELETE CASCADE
+                );
+                CREATE INDEX IF NOT EXISTS idx_links_url ON links(url);
+                "#,
+            )?;
+            let backfilled = self.backfill_links()?;
+            debug!(backfilled, "backfilled links");
+            self.conn.execute_batch("PRAGMA user_version = 18;")?;
+        }
+        if version < 19 {
+            self.conn.execute_batch(
+                r#"
+                CREATE TABLE IF NOT EXISTS issue_refs (
+                  message_id TEXT NOT NULL,
+                  repo TEXT NOT NULL,
+                  number INTEGER NOT NULL,
+                  PRIMARY KEY(message_id, repo, number),
+                  FOREIGN KEY(message_id) REFERENCES messages(id) ON DELETE CASCADE
+                );
+                CREATE INDEX IF NOT EXISTS idx_issue_refs_number ON issue_refs(number, repo);
+                "#,
+            )?;
+            let backfilled = self.backfill_issue_refs()?;
+            debug!(backfilled, "backfilled issue refs");
+            self.conn.execute_batch("PRAGMA user_version = 19;")?;
+        }
+        if version < 20 {
+            self.conn.execute_batch(
+                r#"
+                CREATE TABLE IF NOT EXISTS raw_records (
+                  agent TEXT NOT NULL,
+                  source_id TEXT NOT NULL,
+                  updated_at TEXT NOT NULL,
+                  payload TEXT NOT NULL,
+                  PRIMARY KEY(agent, source_id)
+                );
+                PRAGMA user_version = 20;
+                "#,
+            )?;
+        }
+        if version < 21 {
+            let has_encoding: bool = self.conn.query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('raw_records') WHERE name = 'encoding'",
+                [],
+                |r| r.get(0),
+            )?;
+            if !has_encoding {
+                self.conn.execute_batch(
+                    "ALTER TABLE raw_records ADD COLUMN encoding TEXT NOT NULL DEFAULT 'plain';",
+                )?;
+            }
+            let compressed = self.compress_raw_records()?;
+            debug!(compressed, "compressed raw records");
+            self.conn.execute_batch("PRAGMA user_version = 21;")?;
+        }
+        if version < 22 {
+            self.conn.execute_batch(
+                r#"
+                CREATE TABLE IF NOT EXISTS session_merges (
+                  from_id TEXT PRIMARY KEY,
+                  into_id TEXT NOT NULL,
+                  created_at TEXT NOT NULL
+                );
+                CREATE TABLE IF NOT EXISTS session_splits (
+                  new_id TEXT PRIMARY KEY,
+                  session_id TEXT NOT NULL,
+                  at_ts TEXT NOT NULL,
+                  at_message_id TEXT NOT NULL,
+                  created_at TEXT NOT NULL
+                );
+                CREATE INDEX IF NOT EXISTS idx_session_splits_session ON session_splits(session_id);
+                PRAGMA user_version = 22;
+                "#,
+            )?;
+        }
+        if version < 23 {
+            self.conn.execute_batch(
+                r#"
+                CREATE TABLE IF NOT EXISTS session_aliases (
+                  agent TEXT NOT NULL,
+                  source_ref TEXT NOT NULL,
+                  canonical TEXT NOT NULL,
+                  created_at TEXT NOT NULL,
+                  PRIMARY KEY(agent, source_ref)
+                );
+                PRAGMA user_version = 23;
+                "#,
+            )?;
+        }
+        if version < 24 {
+            let has_ingested_at: bool = self.conn.query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('messages') WHERE name = 'ingested_at'",
+                [],
+                |r| r.get(0),
+            )?;
+            if !has_ingested_at {
+                self.conn
+                    .execute_batch("ALTER TABLE messages ADD COLUMN ingested_at TEXT;")?;
+            }
+            // Insertion times were not kept before; a message's own timestamp
+            // is the earliest it could have been ingested.
+            self.conn.execute_batch(
+                r#"
+                UPDATE messages SET ingested_at = ts WHERE ingested_at IS NULL;
+                PRAGMA user_version = 24;
+                "#,
+            )?;
+        }
+        if version < 25 {
+            let has_deleted_at: bool = self.conn.query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('sessions') WHERE name = 'deleted_at'",
+                [],
+                |r| r.get(0),
+            )?;
+            if !has_deleted_at {
+                self.conn
+                    .execute_batch("ALTER TABLE sessions ADD COLUMN deleted_at TEXT;")?;
+            }
+            self.conn.execute_batch(
+                r#"
+                CREATE INDEX IF NOT EXISTS idx_sessions_deleted_at ON sessions(deleted_at);
+                PRAGMA user_version = 25;
+                "#,
+            )?;
+        }
+        if version < 26 {
+            self.conn.execute_batch(
+                r#"
+                CREATE TABLE IF NOT EXISTS nodes (
+                  id INTEGER PRIMARY KEY,
+                  kind TEXT NOT NULL,
+                  name TEXT NOT NULL,
+                  sessions INTEGER NOT NULL,
+                  UNIQUE(kind, name)
+                );
+                CREATE TABLE IF NOT EXISTS edges (
+                  source INTEGER NOT NULL,
+                  target INTEGER NOT NULL,
+                  relation TEXT NOT NULL,
+                  sessions INTEGER NOT NULL,
+                  PRIMARY KEY(source, target, relation),
+                  FOREIGN KEY(source) REFERENCES nodes(id) ON DELETE CASCADE,
+                  FOREIGN KEY(target) REFERENCES nodes(id) ON DELETE CASCADE
+                );
+                PRAGMA user_version = 26;
+                "#,
+            )?;
+        }
+        if version < 27 {
+            let has_model: bool = self.conn.query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('message_embeddings') WHERE name = 'model'",
+                [],
+                |r| r.get(0),
+            )?;
+            if !has_model {
+                self.conn.execute_batch(
+                    "ALTER TABLE message_embeddings ADD COLUMN model TEXT NOT NULL DEFAULT '';",
+                )?;
+            }
+            // Vectors written before the column existed have an unknown
+            // backend; the empty model marks them for re-embedding.
+            self.conn.execute_batch(
+                r#"
+                CREATE INDEX IF NOT EXISTS idx_message_embeddings_model ON message_embeddings(model);
+                PRAGMA user_version = 27;
+                "#,
+            )?;
+        }
+        if version < 28 {
+            // An FTS row shares its message's rowid. Removing it with the
+            // message, including cascades from sessions, keeps a re-ingested
+            // message from getting a second row under a new rowid.
+            self.conn.execute_batch(
+                r#"
+                CREATE TRIGGER IF NOT EXISTS messages_fts_delete AFTER DELETE ON messages
+                BEGIN
+                  DELETE FROM fts_messages WHERE rowid = old.rowid;
+                END;
+                DELETE FROM fts_messages WHERE rowid IN (
+                  SELECT f.rowid FROM fts_messages f
+                  WHERE NOT EXISTS (
+                    SELECT 1 FROM messages m WHERE m.rowid = f.rowid AND m.id = f.message_id
+                  )
+                );
+                PRAGMA user_version = 28;
+                "#,
+            )?;
+        }
+        if version < 29 {
+            // Reasoning lines get their own column so `--reasoning` is
+            // answered by the FTS query. FTS5 cannot add columns, so the
+            // index is rebuilt from the stored messages.
+            self.conn.execute_batch(
+                r#"
+                DROP TABLE IF EXISTS fts_messages;
+                CREATE VIRTUAL TABLE fts_messages USING fts5(
+                  message_id UNINDEXED,
+                  session_id UNINDEXED,
+                  content,
+                  reasoning,
+                  ts UNINDEXED,
+                  tokenize = 'unicode61 tokenchars ''_./:-'''
+                );
+                "#,
+            )?;
+            index_missing_fts(&self.conn)?;
+            self.conn.execute_batch("PRAGMA user_version = 29;")?;
+        }
+        if version < 30 {
+            let has_workspace: bool = self.conn.query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('provenance') WHERE name = 'workspace'",
+                [],
+                |r| r.get(0),
+            )?;
+            if !has_workspace {
+                self.conn
+                    .execute_batch("ALTER TABLE provenance ADD COLUMN workspace TEXT;")?;
+            }
+            // Adapters used to put a known workspace in `source_path` and
+            // the log file otherwise; only the directories are workspaces.
+            let paths: Vec<String> = self
+                .conn
+                .prepare("SELECT DISTINCT source_path FROM provenance WHERE workspace IS NULL")?
+                .query_map([], |r| r.get(0))?
+                .collect::<rusqlite::Result<_>>()?;
+            let mut stmt = self.conn.prepare(
+                "UPDATE provenance SET workspace = ?1 WHERE source_path = ?1 AND workspace IS NULL",
+            )?;
+            for path in paths.iter().filter(|p| Path::new(p).is_dir()) {
+                stmt.execute(params![path])?;
+            }
+            self.conn.execute_batch("PRAGMA user_version = 30;")?;
+        }
+        for kind in AgentKind::ALL {
+            self.conn.execute(
+                "INSERT OR IGNORE INTO agents (id, name) VALUES (?1, ?2)",
+                params![kind.as_str(), kind.as_str()],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn save_batch(&mut self, batch: &NormalizedBatch) -> anyhow::Result<()> {
+        let started = Instant::now();
+        let mut last = started;
+        let corrected;
+        let mut touched = HashSet::new();
+        let batch = match SessionCorrections::load(&self.conn)? {
+            Some(corrections) => {
+                (corrected, touched) = corrections.apply(batch);
+                &corrected
+            }
+            None => batch,
+        };
+        info!(
+            sessions = batch.sessions.len(),
+            messages = batch.messages.len(),
+            events = batch.events.len(),
+            artifacts = batch.artifacts.len(),
+            provenance = batch.provenance.len(),
+            "save_batch start"
+        );
+        let tx = self.conn.transaction()?;
+        {
+            // Adapters fall back to an empty title or the source ref when a
+            // batch lacks the record that names the session, so a fallback
+            // never replaces a title an earlier batch found.
+            let mut stmt_session = tx.prepare_cached(
+                r#"INSERT INTO sessions (id, agent, source_ref, title, created_at, updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                ON CONFLICT(id) DO UPDATE SET
+                  agent=excluded.agent,
+                  source_ref=excluded.source_ref,
+                  title=CASE
+                    WHEN sessions.title <> ''
+                      AND (excluded.title = '' OR excluded.title = excluded.source_ref)
+                    THEN sessions.title
+                    ELSE excluded.title
+                  END,
+                  created_at=MIN(sessions.created_at, excluded.created_at),
+                  updated_at=MAX(sessions.updated_at, excluded.updated_at)"#,
+            )?;
+            for s in &batch.sessions {
+                stmt_session.execute(params![
+                    s.id,
+                    s.agent.as_str(),
+                    s.source_ref,
+                    s.title,
+                    s.created_at.to_rfc3339(),
+                    s.updated_at.to_rfc3339()
+                ])?;
+            }
+        }
+        if !batch.session_meta.is_empty() {
+            let mut stmt_select = tx.prepare_cached(
+                "SELECT session_meta FROM sessions WHERE id = ?1 AND session_meta IS NOT NULL",
+            )?;
+            let mut stmt_update =
+                tx.prepare_cached("UPDATE sessions SET session_meta = ?2 WHERE id = ?1")?;
+            for m in &batch.session_meta {
+                let existing: Option<String> = stmt_select
+                    .query_row(params![m.session_id], |r| r.get(0))
+                    .optional()?;
+                let merged = match existing.and_then(|raw| serde_json::from_str(&raw).ok()) {
+                    Some(existing) => merge_session_meta(existing, &m.meta),
+                    None => m.meta.clone(),
+                };
+                stmt_update.execute(params![m.session_id, merged.to_string()])?;
+            }
+        }
+        let now = Instant::now();
+        info!(
             elapsed = ?now.duration_since(started),
             delta = ?now.duration_since(last),
-            "provenance upserted"
+            "sessions upserted"
         );
-        let commit_start = Instant::now();
+        last = now;
+        {
+            // `ingested_at` keeps the first insertion so `--as-of` views do not
+            // move when a message is re-synced.
+            let ingested_at = Utc::now().to_rfc3339();
+            let mut stmt_msg = tx.prepare_cached(
+                r#"INSERT INTO messages (id, session_id, role, content, ts, content_encoding, ingested_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                ON CONFLICT(id) DO UPDATE SET
+                  role=excluded.role,
+                  content=excluded.content,
+                  ts=excluded.ts,
+                  content_encoding=excluded.content_encoding"#,
+            )?;
+            for m in &batch.messages {
+                let (content, encoding) = encode_content(&m.content)?;
+                stmt_msg.execute(params![
+                    m.id,
+                    m.session_id,
+                    m.role,
+                    content,
+                    m.ts.to_rfc3339(),
+                    encoding,
+                    ingested_at
+                ])?;
+            }
+        }
+        let now = Instant::now();
+        info!(
+            elapsed = ?now.duration_since(started),
+            delta = ?now.duration_since(last),
+            "messages upserted"
+        );
+        last = now;
+        {
+            let mut seen_message_ids = std::collections::HashSet::new();
+            let mut stmt_lookup_rowid =
+                tx.prepare_cached("SELECT rowid FROM messages WHERE id = ?1")?;
+            let mut stmt_delete_message =
+                tx.prepare_cached("DELETE FROM fts_messages WHERE rowid = ?1")?;
+            let mut stmt_insert = tx.prepare_cached(FTS_INSERT)?;
+            for m in &batch.messages {
+                if seen_message_ids.insert(&m.id) {
+                    let rowid: i64 = stmt_lookup_rowid.query_row(params![m.id], |r| r.get(0))?;
+                    stmt_delete_message.execute(params![rowid])?;
+                    let (reasoning, rest) = split_reasoning(searchable_content(&m.content));
+                    stmt_insert.execute(params![
+                        rowid,
+                        m.id,
+                        m.session_id,
+                        rest,
+                        reasoning,
+                        m.ts.to_rfc3339()
+                    ])?;
+                }
+            }
+        }
+        let now = Instant::now();
+        info!(
+            elapsed = ?now.duration_since(started),
+            delta = ?now.duration_since(last),
+            "fts updated"
+        );
+        last = now;
+        {
+            let mut stmt_clear =
+                tx.prepare_cached("DELETE FROM message_languages WHERE message_id = ?1")?;
+            let mut stmt_lang = tx.prepare_cached(
+                "INSERT OR IGNORE INTO message_languages (message_id, lang) VALUES (?1, ?2)",
+            )?;
+            for m in &batch.messages {
+                stmt_clear.execute(params![m.id])?;
+                for lang in detect_code_languages(&m.content) {
+                    stmt_lang.execute(params![m.id, lang])?;
+                }
+            }
+        }
+        {
+            let mut stmt_clear =
+                tx.prepare_cached("DELETE FROM file_mentions WHERE message_id = ?1")?;
+            let mut stmt_mention = tx.prepare_cached(
+                "INSERT OR IGNORE INTO file_mentions (message_id, tool, path) VALUES (?1, ?2, ?3)",
+            )?;
+            for m in &batch.messages {
+                stmt_clear.execute(params![m.id])?;
+                for mention in tool_file_mentions(&m.content) {
+                    stmt_mention.execute(params![m.id, mention.tool, mention.path])?;
+                }
+            }
+        }
+        {
+            let mut stmt_clear = tx.prepare_cached("DELETE FROM commands WHERE message_id = ?1")?;
+            let mut stmt_command = tx.prepare_cached(
+                "INSERT OR IGNORE INTO commands (message_id, tool, command) VALUES (?1, ?2, ?3)",
+            )?;
+            for m in &batch.messages {
+                stmt_clear.execute(params![m.id])?;
+                for run in tool_commands(&m.content) {
+                    stmt_command.execute(params![m.id, run.tool, run.command])?;
+                }
+            }
+        }
+        {
+            let mut stmt_clear = tx.prepare_cached("DELETE FROM links WHERE message_id = ?1")?;
+            let mut stmt_link = tx.prepare_cached(
+                "INSERT OR IGNORE INTO links (message_id, url, context) VALUES (?1, ?2, ?3)",
+            )?;
+            for m in &batch.messages {
+                stmt_clear.execute(params![m.id])?;
+                for link in extract_links(&m.content) {
+                    stmt_link.execute(params![m.id, link.url, link.context])?;
+                }
+            }
+        }
+        {
+            let mut stmt_clear =
+                tx.prepare_cached("DELETE FROM issue_refs WHERE message_id = ?1")?;
+            let mut stmt_ref = tx.prepare_cached(
+                "INSERT OR IGNORE INTO issue_refs (message_id, repo, number) VALUES (?1, ?2, ?3)",
+            )?;
+            for m in &batch.messages {
+                stmt_clear.execute(params![m.id])?;
+                for r in extract_refs(&m.content) {
+                    stmt_ref.execute(params![m.id, r.repo.unwrap_or_default(), r.number as i64])?;
+                }
+            }
+        }
+        {
+            let mut stmt_event = tx.prepare_cached(
+                r#"INSERT INTO events (id, session_id, kind, payload, ts)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                ON CONFLICT(id) DO UPDATE SET kind=excluded.kind, payload=excluded.payload, ts=excluded.ts"#,
+            )?;
+            for e in &batch.events {
+                stmt_event.execute(params![
+                    e.id,
+                    e.session_id,
+                    e.kind,
+                    serde_json::to_string(&e.payload)?,
+                    e.ts.to_rfc3339()
+                ])?;
+            }
+        }
+        let now = Instant::now();
+        info!(
+            elapsed = ?now.duration_since(started),
+            delta = ?now.duration_since(last),
+            "events upserted"
+        );
+        last = now;
+        {
+            let mut stmt_artifact = tx.prepare_cached(
+                r#"INSERT INTO artifacts (id, session_id, path, checksum, metadata)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                ON CONFLICT(id) DO UPDATE SET path=excluded.path, checksum=excluded.checksum, metadata=excluded.metadata"#,
+            )?;
+            for a in &batch.artifacts {
+                stmt_artifact.execute(params![
+                    a.id,
+                    a.session_id,
+                    a.path,
+                    a.checksum,
+                    serde_json::to_string(&a.metadata)?
+                ])?;
+            }
+        }
+        let now = Instant::now();
+        info!(
+            elapsed = ?now.duration_since(started),
+            delta = ?now.duration_since(last),
+            "artifacts upserted"
+        );
+        last = now;
+        {
+            let mut stmt_prov = tx.prepare_cached(
+                r#"INSERT INTO provenance (id, entity_type, entity_id, agent, source_path, source_id, repo_remote, repo_path, workspace)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                ON CONFLICT(id) DO UPDATE SET
+                  source_path=excluded.source_path,
+                  workspace=COALESCE(excluded.workspace, provenance.workspace),
+                  repo_remote=COALESCE(excluded.repo_remote, provenance.repo_remote),
+                  repo_path=CASE WHEN excluded.repo_remote IS NULL THEN provenance.repo_path ELSE excluded.repo_path END"#,
+            )?;
+            // A workspace that has since moved or been deleted no longer
+            // resolves, so keep the identity recorded while it still existed.
+            for p in &batch.provenance {
+                stmt_prov.execute(params![
+                    p.id,
+                    p.entity_type,
+                    p.entity_id,
+                    p.agent.as_str(),
+                    p.source_path,
+                    p.source_id,
+                    p.repo.as_ref().map(|r| r.remote.as_str()),
+                    p.repo.as_ref().map(|r| r.path.as_str()),
+                    p.workspace
+                ])?;
+            }
+        }
+        let now = Instant::now();
+        info!(
+            elapsed = ?now.duration_since(started),
+            delta = ?now.duration_since(last),
+            "provenance upserted"
+        );
+        for id in &touched {
+            refresh_session_bounds(&tx, id)?;
+        }
+        let commit_start = Instant::now();
+        tx.commit()?;
+        info!(
+            commit = ?commit_start.elapsed(),
+            total = ?started.elapsed(),
+            "commit done"
+        );
+        debug!(
+            sessions = batch.sessions.len(),
+            messages = batch.messages.len(),
+            provenance = batch.provenance.len(),
+            "save_batch complete"
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "semantic")]
+    pub fn save_embedding(&self, message_id: &str, model: &str, vec: &[f32]) -> anyhow::Result<()> {
+        let dim = vec.len() as i64;
+        let blob: Vec<u8> = vec.iter().flat_map(|f| f.to_le_bytes()).collect();
+        self.conn.execute(
+            "INSERT INTO message_embeddings (message_id, dim, vec, model) VALUES (?1, ?2, ?3, ?4) ON CONFLICT(message_id) DO UPDATE SET dim=excluded.dim, vec=excluded.vec, model=excluded.model",
+            params![message_id, dim, blob, model],
+        )?;
+        Ok(())
+    }
+
+    // Only vectors made by `model` are returned, so a query vector is never
+    // compared with one from another backend or checkpoint.
+    #[cfg(feature = "semantic")]
+    pub fn load_all_embeddings(&self, model: &str) -> anyhow::Result<Vec<(String, Vec<f32>)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT message_id, vec FROM message_embeddings WHERE model = ?1")?;
+        let rows = stmt.query_map([model], |r| {
+            let id: String = r.get(0)?;
+            let blob: Vec<u8> = r.get(1)?;
+            let vec: Vec<f32> = blob
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+                .collect();
+            Ok((id, vec))
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Into::into)
+    }
+
+    // The mean of each session's message vectors, for comparing whole
+    // sessions. Sessions without embedded messages are left out.
+    #[cfg(feature = "semantic")]
+    pub fn load_session_embeddings(&self, model: &str) -> anyhow::Result<Vec<(String, Vec<f32>)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT m.session_id, e.vec FROM message_embeddings e JOIN messages m ON e.message_id = m.id WHERE e.model = ?1 ORDER BY m.session_id",
+        )?;
+        let mut rows = stmt.query([model])?;
+        let mut sessions: Vec<(String, Vec<f32>, usize)> = Vec::new();
+        while let Some(row) = rows.next()? {
+            let session_id: String = row.get(0)?;
+            let blob: Vec<u8> = row.get(1)?;
+            let vec = blob
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes(b.try_into().unwrap()));
+            match sessions.last_mut() {
+                // Vectors of another width are skipped, not mixed.
+                Some((id, sum, n)) if *id == session_id => {
+                    if sum.len() * 4 == blob.len() {
+                        sum.iter_mut().zip(vec).for_each(|(s, v)| *s += v);
+                        *n += 1;
+                    }
+                }
+                _ => sessions.push((session_id, vec.collect(), 1)),
+            }
+        }
+        Ok(sessions
+            .into_iter()
+            .map(|(id, sum, n)| (id, sum.into_iter().map(|v| v / n as f32).collect()))
+            .collect())
+    }
+
+    #[cfg(feature = "semantic")]
+    pub fn embedding_stats(&self, model: &str) -> anyhow::Result<EmbeddingStats> {
+        let mut stmt = self.conn.prepare(
+            "SELECT COUNT(*), MAX(m.ts) FROM message_embeddings e LEFT JOIN messages m ON e.message_id = m.id WHERE e.model = ?1",
+        )?;
+        let (count, ts): (i64, Option<String>) =
+            stmt.query_row([model], |r| Ok((r.get(0)?, r.get(1)?)))?;
+        Ok(EmbeddingStats {
+            len: count as usize,
+            updated_at: ts.map(parse_ts),
+        })
+    }
+
+    // Stored vector counts per model, most common first.
+    #[cfg(feature = "semantic")]
+    pub fn embedding_models(&self) -> anyhow::Result<Vec<(String, usize)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT model, COUNT(*) FROM message_embeddings GROUP BY model ORDER BY COUNT(*) DESC, model",
+        )?;
+        let rows = stmt.query_map([], |r| Ok((r.get(0)?, r.get::<_, i64>(1)? as usize)))?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Into::into)
+    }
+
+    // Queues messages whose vector came from another model, so the drain
+    // replaces them. Jobs already queued keep their attempts.
+    #[cfg(feature = "semantic")]
+    pub fn requeue_stale_embeddings(&self, model: &str) -> anyhow::Result<usize> {
+        let queued = self.conn.execute(
+            r#"INSERT INTO embedding_queue (message_id, enqueued_at, attempts)
+            SELECT message_id, ?2, 0 FROM message_embeddings WHERE model <> ?1
+            ON CONFLICT(message_id) DO NOTHING"#,
+            params![model, Utc::now().to_rfc3339()],
+        )?;
+        debug!(queued, "stale embeddings queued");
+        Ok(queued)
+    }
+
+    // Touches the entry on a hit so eviction drops the least recently used.
+    #[cfg(feature = "semantic")]
+    pub fn get_query_embedding(
+        &self,
+        query: &str,
+        model_version: &str,
+    ) -> anyhow::Result<Option<Vec<f32>>> {
+        let blob: Option<Vec<u8>> = self
+            .conn
+            .query_row(
+                "UPDATE query_embeddings SET last_used = ?3 WHERE query = ?1 AND model_version = ?2 RETURNING vec",
+                params![query, model_version, Utc::now().to_rfc3339()],
+                |r| r.get(0),
+            )
+            .optional()?;
+        Ok(blob.map(|blob| {
+            blob.chunks_exact(4)
+                .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+                .collect()
+        }))
+    }
+
+    #[cfg(feature = "semantic")]
+    pub fn save_query_embedding(
+        &self,
+        query: &str,
+        model_version: &str,
+        vec: &[f32],
+        capacity: usize,
+    ) -> anyhow::Result<()> {
+        let blob: Vec<u8> = vec.iter().flat_map(|f| f.to_le_bytes()).collect();
+        self.conn.execute(
+            "INSERT INTO query_embeddings (query, model_version, vec, last_used) VALUES (?1, ?2, ?3, ?4) ON CONFLICT(query, model_version) DO UPDATE SET vec=excluded.vec, last_used=excluded.last_used",
+            params![query, model_version, blob, Utc::now().to_rfc3339()],
+        )?;
+        let evicted = self.conn.execute(
+            "DELETE FROM query_embeddings WHERE rowid IN (SELECT rowid FROM query_embeddings ORDER BY last_used DESC, rowid DESC LIMIT -1 OFFSET ?1)",
+            params![capacity as i64],
+        )?;
+        if evicted > 0 {
+            debug!(evicted, "evicted query embeddings");
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "semantic")]
+    pub fn enqueue_embeddings(&mut self, message_ids: &[String]) -> anyhow::Result<usize> {
+        let enqueued_at = Utc::now().to_rfc3339();
+        let tx = self.conn.transaction()?;
+        let mut inserted = 0usize;
+        {
+            let mut stmt = tx.prepare_cached(
+                r#"INSERT INTO embedding_queue (message_id, enqueued_at, attempts)
+                VALUES (?1, ?2, 0)
+                ON CONFLICT(message_id) DO UPDATE SET enqueued_at=excluded.enqueued_at, attempts=0"#,
+            )?;
+            for id in message_ids {
+                inserted += stmt.execute(params![id, enqueued_at])?;
+            }
+        }
+        tx.commit()?;
+        debug!(inserted, "embedding jobs enqueued");
+        Ok(inserted)
+    }
+
+    #[cfg(feature = "semantic")]
+    pub fn pending_embedding_jobs(
+        &self,
+        limit: usize,
+        max_attempts: i64,
+    ) -> anyhow::Result<Vec<EmbeddingJob>> {
+        let mut stmt = self.conn.prepare_cached(
+            r#"SELECT q.message_id, m.content, m.content_encoding, q.attempts
+            FROM embedding_queue q
+            JOIN messages m ON m.id = q.message_id
+            WHERE q.attempts < ?2
+            ORDER BY q.enqueued_at, q.message_id
+            LIMIT ?1"#,
+        )?;
+        let rows = stmt.query_map(params![limit as i64, max_attempts], |r| {
+            Ok(EmbeddingJob {
+                message_id: r.get(0)?,
+                content: read_content(r, 1, 2)?,
+                attempts: r.get(3)?,
+            })
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Into::into)
+    }
+
+    #[cfg(feature = "semantic")]
+    pub fn complete_embedding_job(&self, message_id: &str) -> anyhow::Result<()> {
+        self.conn.execute(
+            "DELETE FROM embedding_queue WHERE message_id = ?1",
+            params![message_id],
+        )?;
+        Ok(())
+    }
+
+    #[cfg(feature = "semantic")]
+    pub fn fail_embedding_job(&self, message_id: &str) -> anyhow::Result<()> {
+        self.conn.execute(
+            "UPDATE embedding_queue SET attempts = attempts + 1 WHERE message_id = ?1",
+            params![message_id],
+        )?;
+        Ok(())
+    }
+
+    #[cfg(feature = "semantic")]
+    pub fn embedding_queue_len(&self) -> anyhow::Result<usize> {
+        let count: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM embedding_queue", [], |r| r.get(0))?;
+        Ok(count as usize)
+    }
+
+    // Jobs that used up their attempts stay queued but are never retried.
+    #[cfg(feature = "semantic")]
+    pub fn failed_embedding_jobs(&self, max_attempts: i64) -> anyhow::Result<usize> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM embedding_queue WHERE attempts >= ?1",
+            params![max_attempts],
+            |r| r.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    pub fn get_checkpoint(&self, agent: &str) -> anyhow::Result<Option<String>> {
+        let result: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT cursor FROM checkpoints WHERE agent = ?1",
+                params![agent],
+                |r| r.get(0),
+            )
+            .optional()?;
+        debug!(agent, cursor = ?result.as_deref(), "checkpoint loaded");
+        Ok(result)
+    }
+
+    pub fn get_sync_cutoff(&self, agent: &str) -> anyhow::Result<Option<DateTime<Utc>>> {
+        let cutoff: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT cutoff FROM sync_cutoffs WHERE agent = ?1",
+                params![agent],
+                |r| r.get(0),
+            )
+            .optional()?;
+        Ok(cutoff.map(parse_ts))
+    }
+
+    // Keeps the latest cutoff: everything modified before it may be missing.
+    pub fn record_sync_cutoff(&self, agent: &str, cutoff: DateTime<Utc>) -> anyhow::Result<()> {
+        self.conn.execute(
+            r#"INSERT INTO sync_cutoffs (agent, cutoff, updated_at) VALUES (?1, ?2, ?3)
+            ON CONFLICT(agent) DO UPDATE SET
+              cutoff=MAX(cutoff, excluded.cutoff),
+              updated_at=excluded.updated_at"#,
+            params![agent, cutoff.to_rfc3339(), Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    pub fn clear_sync_cutoff(&self, agent: &str) -> anyhow::Result<()> {
+        self.conn
+            .execute("DELETE FROM sync_cutoffs WHERE agent = ?1", params![agent])?;
+        Ok(())
+    }
+
+    pub fn upsert_checkpoint(&self, checkpoint: &Checkpoint) -> anyhow::Result<()> {
+        trace!(agent = %checkpoint.agent, cursor = %checkpoint.cursor, "upserting checkpoint");
+        self.conn.execute(
+            r#"INSERT INTO checkpoints (agent, cursor, updated_at) VALUES (?1, ?2, ?3)
+            ON CONFLICT(agent) DO UPDATE SET cursor=excluded.cursor, updated_at=excluded.updated_at"#,
+            params![
+                checkpoint.agent.as_str(),
+                checkpoint.cursor,
+                checkpoint.updated_at.to_rfc3339()
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_file_states(&self, agent: &str) -> anyhow::Result<HashMap<String, FileState>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT path, content_hash, size_bytes, modified_at, record_count FROM file_state WHERE agent = ?1 AND kind = 'file'",
+        )?;
+        let rows = stmt.query_map(params![agent], |r| {
+            Ok(FileState {
+                path: r.get(0)?,
+                content_hash: r.get(1)?,
+                size_bytes: r.get(2)?,
+                modified_at: parse_ts(r.get(3)?),
+                record_count: r.get(4)?,
+            })
+        })?;
+        let mut out = HashMap::new();
+        for row in rows {
+            let state = row?;
+            out.insert(state.path.clone(), state);
+        }
+        Ok(out)
+    }
+
+    pub fn upsert_file_states(&mut self, agent: &str, states: &[FileState]) -> anyhow::Result<()> {
+        let updated_at = Utc::now().to_rfc3339();
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt = tx.prepare_cached(
+                r#"INSERT INTO file_state (agent, path, content_hash, size_bytes, modified_at, record_count, updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                ON CONFLICT(agent, path) DO UPDATE SET
+                  content_hash=excluded.content_hash,
+                  size_bytes=excluded.size_bytes,
+                  modified_at=excluded.modified_at,
+                  record_count=excluded.record_count,
+                  updated_at=excluded.updated_at"#,
+            )?;
+            for state in states {
+                stmt.execute(params![
+                    agent,
+                    state.path,
+                    state.content_hash,
+                    state.size_bytes,
+                    state.modified_at.to_rfc3339(),
+                    state.record_count,
+                    updated_at
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn get_dir_listings(&self, agent: &str) -> anyhow::Result<HashMap<String, DirListing>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT path, modified_at, entries FROM file_state WHERE agent = ?1 AND kind = 'dir'",
+        )?;
+        let rows = stmt.query_map(params![agent], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                parse_ts(r.get(1)?),
+                r.get::<_, Option<String>>(2)?,
+            ))
+        })?;
+        let mut out = HashMap::new();
+        for row in rows {
+            let (path, modified_at, entries) = row?;
+            let Some(mut listing) = entries
+                .as_deref()
+                .and_then(|e| serde_json::from_str::<DirListing>(e).ok())
+            else {
+                continue;
+            };
+            listing.modified_at = modified_at;
+            out.insert(path, listing);
+        }
+        Ok(out)
+    }
+
+    pub fn upsert_dir_listings(
+        &mut self,
+        agent: &str,
+        listings: &[(String, DirListing)],
+    ) -> anyhow::Result<()> {
+        if listings.is_empty() {
+            return Ok(());
+        }
+        let updated_at = Utc::now().to_rfc3339();
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt = tx.prepare_cached(
+                r#"INSERT INTO file_state (agent, path, content_hash, size_bytes, modified_at, record_count, updated_at, kind, entries)
+                VALUES (?1, ?2, '', ?3, ?4, 0, ?5, 'dir', ?6)
+                ON CONFLICT(agent, path) DO UPDATE SET
+                  size_bytes=excluded.size_bytes,
+                  modified_at=excluded.modified_at,
+                  updated_at=excluded.updated_at,
+                  kind=excluded.kind,
+                  entries=excluded.entries"#,
+            )?;
+            for (path, listing) in listings {
+                stmt.execute(params![
+                    agent,
+                    path,
+                    (listing.dirs.len() + listing.files.len()) as i64,
+                    listing.modified_at.to_rfc3339(),
+                    updated_at,
+                    serde_json::to_string(listing)?
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn begin_doc_sync(&mut self, canonical_path: &str) -> anyhow::Result<DocSyncState> {
+        let root_id = doc_root_id(canonical_path);
+        let started_at = Utc::now().to_rfc3339();
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT OR IGNORE INTO doc_roots (root_id, canonical_path, current_generation, last_completed_generation, scan_status) VALUES (?1, ?2, 0, 0, 'idle')",
+            params![root_id.as_str(), canonical_path],
+        )?;
+        let generation: i64 = tx.query_row(
+            "SELECT current_generation + 1 FROM doc_roots WHERE root_id = ?1",
+            params![root_id.as_str()],
+            |r| r.get(0),
+        )?;
+        tx.execute(
+            "UPDATE doc_roots SET current_generation = ?2, scan_started_at = ?3, scan_completed_at = NULL, scan_status = 'running' WHERE root_id = ?1",
+            params![root_id.as_str(), generation, started_at],
+        )?;
+        tx.commit()?;
+        Ok(DocSyncState {
+            root_id,
+            canonical_path: canonical_path.to_string(),
+            generation,
+        })
+    }
+
+    pub fn get_doc_root(&self, canonical_path: &str) -> anyhow::Result<Option<DocRootRecord>> {
+        self.conn
+            .query_row(
+                "SELECT root_id, canonical_path, current_generation, last_completed_generation, scan_started_at, scan_completed_at, scan_status FROM doc_roots WHERE canonical_path = ?1",
+                params![canonical_path],
+                |r| {
+                    Ok(DocRootRecord {
+                        root_id: r.get(0)?,
+                        canonical_path: r.get(1)?,
+                        current_generation: r.get(2)?,
+                        last_completed_generation: r.get(3)?,
+                        scan_started_at: parse_optional_ts(r.get(4)?),
+                        scan_completed_at: parse_optional_ts(r.get(5)?),
+                        scan_status: r.get(6)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    pub fn get_document_by_path(
+        &self,
+        root_id: &str,
+        relative_path: &str,
+    ) -> anyhow::Result<Option<DocumentRecord>> {
+        self.conn
+            .query_row(
+                "SELECT id, root_id, relative_path, title, modified_at, size_bytes, content_hash, last_seen_generation, indexed_generation, indexed_at FROM documents WHERE root_id = ?1 AND relative_path = ?2",
+                params![root_id, relative_path],
+                |r| {
+                    Ok(DocumentRecord {
+                        document_id: r.get(0)?,
+                        root_id: r.get(1)?,
+                        relative_path: r.get(2)?,
+                        title: r.get(3)?,
+                        modified_at: parse_ts(r.get(4)?),
+                        size_bytes: r.get(5)?,
+                        content_hash: r.get(6)?,
+                        last_seen_generation: r.get(7)?,
+                        indexed_generation: r.get(8)?,
+                        indexed_at: parse_ts(r.get(9)?),
+                    })
+                },
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    pub fn mark_document_seen(
+        &self,
+        root_id: &str,
+        relative_path: &str,
+        generation: i64,
+    ) -> anyhow::Result<bool> {
+        let updated = self.conn.execute(
+            "UPDATE documents SET last_seen_generation = ?3 WHERE root_id = ?1 AND relative_path = ?2",
+            params![root_id, relative_path, generation],
+        )?;
+        Ok(updated > 0)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn upsert_document(
+        &mut self,
+        root_id: &str,
+        relative_path: &str,
+        title: &str,
+        modified_at: DateTime<Utc>,
+        size_bytes: i64,
+        content_hash: &str,
+        content: &str,
+        generation: i64,
+    ) -> anyhow::Result<String> {
+        let document_id = document_id(root_id, relative_path);
+        let indexed_at = Utc::now().to_rfc3339();
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            r#"INSERT INTO documents (id, root_id, relative_path, title, modified_at, size_bytes, content_hash, last_seen_generation, indexed_generation, indexed_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8, ?9)
+            ON CONFLICT(root_id, relative_path) DO UPDATE SET
+              id=excluded.id,
+              title=excluded.title,
+              modified_at=excluded.modified_at,
+              size_bytes=excluded.size_bytes,
+              content_hash=excluded.content_hash,
+              last_seen_generation=excluded.last_seen_generation,
+              indexed_generation=excluded.indexed_generation,
+              indexed_at=excluded.indexed_at"#,
+            params![
+                document_id.as_str(),
+                root_id,
+                relative_path,
+                title,
+                modified_at.to_rfc3339(),
+                size_bytes,
+                content_hash,
+                generation,
+                indexed_at,
+            ],
+        )?;
+        let rowid: i64 = tx.query_row(
+            "SELECT rowid FROM documents WHERE root_id = ?1 AND relative_path = ?2",
+            params![root_id, relative_path],
+            |r| r.get(0),
+        )?;
+        tx.execute("DELETE FROM fts_documents WHERE rowid = ?1", params![rowid])?;
+        tx.execute(
+            "INSERT INTO fts_documents (rowid, document_id, root_id, path, title, content) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![rowid, document_id.as_str(), root_id, relative_path, title, content],
+        )?;
+        tx.commit()?;
+        Ok(document_id)
+    }
+
+    pub fn finalize_doc_sync(
+        &mut self,
+        root_id: &str,
+        generation: i64,
+    ) -> anyhow::Result<DocFinalizeResult> {
+        let completed_at = Utc::now().to_rfc3339();
+        let tx = self.conn.transaction()?;
+        let current_generation: Option<i64> = tx
+            .query_row(
+                "SELECT current_generation FROM doc_roots WHERE root_id = ?1",
+                params![root_id],
+                |r| r.get(0),
+            )
+            .optional()?;
+        let current_generation = current_generation.with_context(|| {
+            format!("doc root {root_id} missing while finalizing generation {generation}")
+        })?;
+        if current_generation != generation {
+            return Err(anyhow::anyhow!(
+                "stale doc sync finalize for root {root_id}: current generation {current_generation}, attempted {generation}"
+            ));
+        }
+        let deleted_documents: i64 = tx.query_row(
+            "SELECT COUNT(*) FROM documents WHERE root_id = ?1 AND last_seen_generation < ?2",
+            params![root_id, generation],
+            |r| r.get(0),
+        )?;
+        tx.execute(
+            "DELETE FROM fts_documents WHERE rowid IN (SELECT rowid FROM documents WHERE root_id = ?1 AND last_seen_generation < ?2)",
+            params![root_id, generation],
+        )?;
+        tx.execute(
+            "DELETE FROM documents WHERE root_id = ?1 AND last_seen_generation < ?2",
+            params![root_id, generation],
+        )?;
+        tx.execute(
+            "UPDATE doc_roots SET last_completed_generation = ?2, scan_status = 'completed', scan_completed_at = ?3 WHERE root_id = ?1",
+            params![root_id, generation, completed_at],
+        )?;
+        tx.commit()?;
+        Ok(DocFinalizeResult {
+            deleted_documents: deleted_documents as usize,
+        })
+    }
+
+    pub fn fail_doc_sync(&self, root_id: &str, generation: i64) -> anyhow::Result<()> {
+        let updated = self.conn.execute(
+            "UPDATE doc_roots SET scan_status = 'failed', scan_completed_at = ?3 WHERE root_id = ?1 AND current_generation = ?2",
+            params![root_id, generation, Utc::now().to_rfc3339()],
+        )?;
+        if updated == 0 {
+            return Err(anyhow::anyhow!(
+                "doc root {root_id} missing or generation {generation} is no longer current"
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn search_documents_lexical(
+        &self,
+        query: &str,
+        limit: i64,
+    ) -> anyhow::Result<Vec<DocumentSearchRow>> {
+        debug!(query, limit, "docs lexical search");
+        let mut stmt = self.conn.prepare(
+            "SELECT d.id, d.root_id, dr.canonical_path, d.relative_path, d.title, snippet(fts_documents, 4, '[', ']', ' … ', 16), bm25(fts_documents) AS rank FROM fts_documents INNER JOIN documents d ON d.rowid = fts_documents.rowid INNER JOIN doc_roots dr ON dr.root_id = d.root_id WHERE fts_documents MATCH ?1 ORDER BY rank LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![query, limit], |r| {
+            let rank: f64 = r.get(6)?;
+            Ok(DocumentSearchRow {
+                document_id: r.get(0)?,
+                root_id: r.get(1)?,
+                root_path: r.get(2)?,
+                relative_path: r.get(3)?,
+                title: r.get(4)?,
+                snippet: r.get::<_, Option<String>>(5)?.unwrap_or_default(),
+                score: -rank,
+            })
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Into::into)
+    }
+
+    pub fn search_documents_substring(
+        &self,
+        query: &str,
+        limit: i64,
+    ) -> anyhow::Result<Vec<DocumentSearchRow>> {
+        debug!(query, limit, "docs substring search");
+        let pattern = format!("%{}%", escape_like_pattern(&query.to_lowercase()));
+        let mut stmt = self.conn.prepare(
+            "SELECT d.id, d.root_id, dr.canonical_path, d.relative_path, d.title, f.content FROM documents d INNER JOIN doc_roots dr ON dr.root_id = d.root_id INNER JOIN fts_documents f ON f.rowid = d.rowid WHERE lower(f.content) LIKE ?1 ESCAPE '\\' ORDER BY d.indexed_at DESC, d.relative_path ASC LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![pattern, limit], |r| {
+            let content: String = r.get(5)?;
+            Ok(DocumentSearchRow {
+                document_id: r.get(0)?,
+                root_id: r.get(1)?,
+                root_path: r.get(2)?,
+                relative_path: r.get(3)?,
+                title: r.get(4)?,
+                snippet: build_document_snippet(&content, query),
+                score: 0.0,
+            })
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Into::into)
+    }
+
+    pub fn list_sessions(&self) -> anyhow::Result<Vec<Session>> {
+        self.recent_sessions(-1)
+    }
+
+    pub fn recent_sessions(&self, limit: i64) -> anyhow::Result<Vec<Session>> {
+        self.sessions_page(None, (limit >= 0).then_some(limit as usize), 0)
+    }
+
+    // One page of live sessions, newest first, optionally of one agent. No
+    // limit means every row after `offset`.
+    pub fn sessions_page(
+        &self,
+        agent: Option<&str>,
+        limit: Option<usize>,
+        offset: usize,
+    ) -> anyhow::Result<Vec<Session>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, agent, source_ref, title, created_at, updated_at FROM sessions
+            WHERE deleted_at IS NULL AND (?1 IS NULL OR agent = ?1)
+            ORDER BY updated_at DESC, id ASC LIMIT ?2 OFFSET ?3",
+        )?;
+        let limit = limit.map_or(-1, |n| n as i64);
+        let rows = stmt.query_map(params![agent, limit, offset as i64], |r| {
+            let agent_str: String = r.get(1)?;
+            Ok(Session {
+                id: r.get(0)?,
+                agent: parse_agent(&agent_str)?,
+                source_ref: r.get(2)?,
+                title: r.get(3)?,
+                created_at: parse_ts(r.get(4)?),
+                updated_at: parse_ts(r.get(5)?),
+            })
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Into::into)
+    }
+
+    // Sessions with at least one message in `[start, end)`, busiest first.
+    // Callers pick the bounds, so a day can end at local midnight.
+    pub fn sessions_active_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<Session>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT s.id, s.agent, s.source_ref, s.title, s.created_at, s.updated_at
+            FROM sessions s INNER JOIN messages m ON m.session_id = s.id
+            WHERE m.ts >= ?1 AND m.ts < ?2 AND s.deleted_at IS NULL
+            GROUP BY s.id
+            ORDER BY COUNT(m.id) DESC, s.id ASC"#,
+        )?;
+        let rows = stmt.query_map(params![start.to_rfc3339(), end.to_rfc3339()], |r| {
+            let agent_str: String = r.get(1)?;
+            Ok(Session {
+                id: r.get(0)?,
+                agent: parse_agent(&agent_str)?,
+                source_ref: r.get(2)?,
+                title: r.get(3)?,
+                created_at: parse_ts(r.get(4)?),
+                updated_at: parse_ts(r.get(5)?),
+            })
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Into::into)
+    }
+
+    pub fn get_session_messages(&self, session_id: &str) -> anyhow::Result<Vec<Message>> {
+        select_session_messages(&self.conn, session_id)
+    }
+
+    pub fn get_session_events(&self, session_id: &str) -> anyhow::Result<Vec<core_model::Event>> {
+        select_session_events(&self.conn, session_id)
+    }
+
+    pub fn get_session_artifacts(
+        &self,
+        session_id: &str,
+    ) -> anyhow::Result<Vec<core_model::Artifact>> {
+        select_session_artifacts(&self.conn, session_id)
+    }
+
+    pub fn get_message(&self, message_id: &str) -> anyhow::Result<Option<Message>> {
+        self.conn
+            .query_row(
+                "SELECT id, session_id, role, content, ts, content_encoding FROM messages WHERE id = ?1",
+                params![message_id],
+                |r| {
+                    Ok(Message {
+                        id: r.get(0)?,
+                        session_id: r.get(1)?,
+                        role: r.get(2)?,
+                        content: read_content(r, 3, 5)?,
+                        ts: parse_ts(r.get(4)?),
+                    })
+                },
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    // Re-bookmarking keeps the original timestamp and adds any new tags.
+    pub fn add_bookmark(&mut self, message_id: &str, tags: &[String]) -> anyhow::Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT OR IGNORE INTO bookmarks (message_id, created_at) VALUES (?1, ?2)",
+            params![message_id, Utc::now().to_rfc3339()],
+        )?;
+        for tag in tags {
+            tx.execute(
+                "INSERT OR IGNORE INTO bookmark_tags (message_id, tag) VALUES (?1, ?2)",
+                params![message_id, tag],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn remove_bookmark(&self, message_id: &str) -> anyhow::Result<bool> {
+        let removed = self.conn.execute(
+            "DELETE FROM bookmarks WHERE message_id = ?1",
+            params![message_id],
+        )?;
+        Ok(removed > 0)
+    }
+
+    // Oldest first, optionally only those carrying `tag`.
+    pub fn bookmarks(&self, tag: Option<&str>) -> anyhow::Result<Vec<Bookmark>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT b.message_id, b.created_at,
+                (SELECT group_concat(tag, char(31)) FROM
+                  (SELECT tag FROM bookmark_tags t WHERE t.message_id = b.message_id ORDER BY tag))
+            FROM bookmarks b
+            WHERE ?1 IS NULL OR EXISTS
+              (SELECT 1 FROM bookmark_tags t WHERE t.message_id = b.message_id AND t.tag = ?1)
+            ORDER BY b.created_at, b.message_id"#,
+        )?;
+        let rows = stmt
+            .query_map(params![tag], |r| {
+                Ok((
+                    r.get::<_, String>(0)?,
+                    r.get::<_, String>(1)?,
+                    r.get::<_, Option<String>>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut out = Vec::with_capacity(rows.len());
+        for (message_id, created_at, tags) in rows {
+            let Some(message) = self.get_message(&message_id)? else {
+                continue;
+            };
+            out.push(Bookmark {
+                message,
+                tags: tags
+                    .map(|t| t.split('\u{1f}').map(str::to_string).collect())
+                    .unwrap_or_default(),
+                created_at: parse_ts(created_at),
+            });
+        }
+        Ok(out)
+    }
+
+    pub fn get_session(&self, session_id: &str) -> anyhow::Result<Option<Session>> {
+        select_session(&self.conn, session_id)
+    }
+
+    pub fn session_aggregate(&self, session_id: &str) -> anyhow::Result<Option<SessionAggregate>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT role, COUNT(*), MIN(ts), MAX(ts) FROM messages WHERE session_id = ?1 GROUP BY role ORDER BY COUNT(*) DESC, role ASC",
+        )?;
+        let rows = stmt
+            .query_map(params![session_id], |r| {
+                Ok((
+                    r.get::<_, String>(0)?,
+                    r.get::<_, i64>(1)? as usize,
+                    parse_ts(r.get(2)?),
+                    parse_ts(r.get(3)?),
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let Some(first) = rows.first() else {
+            return Ok(None);
+        };
+        let mut aggregate = SessionAggregate {
+            message_count: 0,
+            first_ts: first.2,
+            last_ts: first.3,
+            by_role: Vec::with_capacity(rows.len()),
+        };
+        for (role, count, first_ts, last_ts) in rows {
+            aggregate.message_count += count;
+            aggregate.first_ts = aggregate.first_ts.min(first_ts);
+            aggregate.last_ts = aggregate.last_ts.max(last_ts);
+            aggregate.by_role.push((role, count));
+        }
+        Ok(Some(aggregate))
+    }
+
+    pub fn project_summaries(&self) -> anyhow::Result<Vec<ProjectSummary>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT p.repo_remote, COUNT(DISTINCT m.session_id), COUNT(DISTINCT m.id), MAX(m.ts)
+            FROM provenance p INNER JOIN messages m ON p.entity_type = 'message' AND p.entity_id = m.id
+            WHERE p.repo_remote IS NOT NULL
+            GROUP BY p.repo_remote
+            ORDER BY MAX(m.ts) DESC, p.repo_remote ASC"#,
+        )?;
+        let rows = stmt.query_map([], |r| {
+            Ok(ProjectSummary {
+                remote: r.get(0)?,
+                session_count: r.get::<_, i64>(1)? as usize,
+                message_count: r.get::<_, i64>(2)? as usize,
+                last_ts: parse_ts(r.get(3)?),
+            })
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Into::into)
+    }
+
+    // Repository remotes recorded for each session's messages, the one with
+    // the most messages first.
+    pub fn session_repo_remotes(&self) -> anyhow::Result<HashMap<String, Vec<String>>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT m.session_id, p.repo_remote
+            FROM provenance p INNER JOIN messages m ON p.entity_type = 'message' AND p.entity_id = m.id
+            WHERE p.repo_remote IS NOT NULL
+            GROUP BY m.session_id, p.repo_remote
+            ORDER BY m.session_id ASC, COUNT(*) DESC, p.repo_remote ASC"#,
+        )?;
+        let rows = stmt.query_map([], |r| Ok((r.get::<_, String>(0)?, r.get(1)?)))?;
+        let mut repos: HashMap<String, Vec<String>> = HashMap::new();
+        for row in rows {
+            let (session_id, remote) = row?;
+            repos.entry(session_id).or_default().push(remote);
+        }
+        Ok(repos)
+    }
+
+    // Replaces the `nodes` and `edges` tables with entities extracted from
+    // every agent session that is not in the trash. Returns the node and edge counts.
+    pub fn rebuild_graph(&mut self) -> anyhow::Result<(usize, usize)> {
+        let repos = self.session_repo_remotes()?;
+        let mut graph = core_model::GraphBuilder::default();
+        for session in self.list_sessions()? {
+            if session.agent == AgentKind::Meta {
+                continue;
+            }
+            let messages = self.get_session_messages(&session.id)?;
+            graph.add_session(
+                repos
+                    .get(&session.id)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default(),
+                messages.iter().map(|m| m.content.as_str()),
+            );
+        }
+
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM edges", [])?;
+        tx.execute("DELETE FROM nodes", [])?;
+        let mut ids: HashMap<&core_model::Entity, i64> = HashMap::new();
+        {
+            let mut stmt =
+                tx.prepare("INSERT INTO nodes (kind, name, sessions) VALUES (?1, ?2, ?3)")?;
+            for (entity, sessions) in &graph.nodes {
+                stmt.execute(params![entity.kind.as_str(), entity.name, *sessions as i64])?;
+                ids.insert(entity, tx.last_insert_rowid());
+            }
+            let mut stmt = tx.prepare(
+                "INSERT INTO edges (source, target, relation, sessions) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for ((source, target, relation), sessions) in &graph.edges {
+                stmt.execute(params![
+                    ids[source],
+                    ids[target],
+                    relation,
+                    *sessions as i64
+                ])?;
+            }
+        }
+        tx.commit()?;
+        debug!(
+            nodes = graph.nodes.len(),
+            edges = graph.edges.len(),
+            "graph rebuilt"
+        );
+        Ok((graph.nodes.len(), graph.edges.len()))
+    }
+
+    // Nodes and edges seen in at least `min_sessions` sessions, heaviest
+    // first. Edges are only kept when both ends are.
+    pub fn graph(&self, min_sessions: usize, kinds: &[&str]) -> anyhow::Result<Graph> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, kind, name, sessions FROM nodes WHERE sessions >= ?1 ORDER BY sessions DESC, kind ASC, name ASC",
+        )?;
+        let nodes = stmt
+            .query_map(params![min_sessions as i64], |r| {
+                Ok(GraphNode {
+                    id: r.get(0)?,
+                    kind: r.get(1)?,
+                    name: r.get(2)?,
+                    sessions: r.get::<_, i64>(3)? as usize,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|n| kinds.is_empty() || kinds.contains(&n.kind.as_str()))
+            .collect::<Vec<_>>();
+        let kept: HashSet<i64> = nodes.iter().map(|n| n.id).collect();
+        let mut stmt = self.conn.prepare(
+            "SELECT source, target, relation, sessions FROM edges WHERE sessions >= ?1 ORDER BY sessions DESC, source ASC, target ASC, relation ASC",
+        )?;
+        let edges = stmt
+            .query_map(params![min_sessions as i64], |r| {
+                Ok(GraphEdge {
+                    source: r.get(0)?,
+                    target: r.get(1)?,
+                    relation: r.get(2)?,
+                    sessions: r.get::<_, i64>(3)? as usize,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|e| kept.contains(&e.source) && kept.contains(&e.target))
+            .collect();
+        Ok(Graph { nodes, edges })
+    }
+
+    // Days are UTC calendar days; `to` is inclusive.
+    pub fn messages_per_day(
+        &self,
+        agent: Option<AgentKind>,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> anyhow::Result<BTreeMap<NaiveDate, usize>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT substr(m.ts, 1, 10) AS day, COUNT(*)
+            FROM messages m INNER JOIN sessions s ON s.id = m.session_id
+            WHERE (?1 IS NULL OR s.agent = ?1) AND day BETWEEN ?2 AND ?3
+            GROUP BY day"#,
+        )?;
+        let rows = stmt.query_map(
+            params![agent.map(|a| a.as_str()), from.to_string(), to.to_string()],
+            |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?)),
+        )?;
+        let mut out = BTreeMap::new();
+        for row in rows {
+            let (day, count) = row?;
+            if let Ok(day) = day.parse::<NaiveDate>() {
+                out.insert(day, count as usize);
+            }
+        }
+        Ok(out)
+    }
+
+    pub fn get_session_meta(&self, session_id: &str) -> anyhow::Result<Option<serde_json::Value>> {
+        select_session_meta(&self.conn, session_id)
+    }
+
+    pub fn get_session_bundle(&self, session_id: &str) -> anyhow::Result<Option<SessionBundle>> {
+        let tx = self.conn.unchecked_transaction()?;
+        let Some(session) = select_session(&tx, session_id)? else {
+            return Ok(None);
+        };
+        let bundle = SessionBundle {
+            session,
+            messages: select_session_messages(&tx, session_id)?,
+            events: select_session_events(&tx, session_id)?,
+            artifacts: select_session_artifacts(&tx, session_id)?,
+            provenance: select_session_provenance(&tx, session_id)?,
+            meta: select_session_meta(&tx, session_id)?,
+        };
+        tx.commit()?;
+        Ok(Some(bundle))
+    }
+
+    pub fn message_languages(&self, message_id: &str) -> anyhow::Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT lang FROM message_languages WHERE message_id = ?1 ORDER BY lang")?;
+        let rows = stmt.query_map(params![message_id], |r| r.get(0))?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Into::into)
+    }
+
+    pub fn sessions_with_language(&self, lang: &str) -> anyhow::Result<HashSet<String>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT DISTINCT m.session_id FROM message_languages l
+            JOIN messages m ON m.id = l.message_id
+            WHERE l.lang = ?1"#,
+        )?;
+        let rows = stmt.query_map(params![lang], |r| r.get(0))?;
+        rows.collect::<rusqlite::Result<HashSet<_>>>()
+            .map_err(Into::into)
+    }
+
+    fn backfill_message_languages(&self) -> anyhow::Result<usize> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, content, content_encoding FROM messages")?;
+        let detected = stmt
+            .query_map([], |r| {
+                let content = read_content(r, 1, 2)?;
+                Ok((r.get::<_, String>(0)?, detect_code_languages(&content)))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut insert = self.conn.prepare(
+            "INSERT OR IGNORE INTO message_languages (message_id, lang) VALUES (?1, ?2)",
+        )?;
+        let mut tagged = 0;
+        for (id, langs) in &detected {
+            for lang in langs {
+                insert.execute(params![id, lang])?;
+            }
+            tagged += usize::from(!langs.is_empty());
+        }
+        Ok(tagged)
+    }
+
+    fn backfill_file_mentions(&self) -> anyhow::Result<usize> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, content, content_encoding FROM messages")?;
+        let mentions = stmt
+            .query_map([], |r| {
+                let content = read_content(r, 1, 2)?;
+                Ok((r.get::<_, String>(0)?, tool_file_mentions(&content)))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut insert = self.conn.prepare(
+            "INSERT OR IGNORE INTO file_mentions (message_id, tool, path) VALUES (?1, ?2, ?3)",
+        )?;
+        let mut tagged = 0;
+        for (id, found) in &mentions {
+            for mention in found {
+                insert.execute(params![id, mention.tool, mention.path])?;
+            }
+            tagged += usize::from(!found.is_empty());
+        }
+        Ok(tagged)
+    }
+
+    fn backfill_commands(&self) -> anyhow::Result<usize> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, content, content_encoding FROM messages")?;
+        let found = stmt
+            .query_map([], |r| {
+                let content = read_content(r, 1, 2)?;
+                Ok((r.get::<_, String>(0)?, tool_commands(&content)))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut insert = self.conn.prepare(
+            "INSERT OR IGNORE INTO commands (message_id, tool, command) VALUES (?1, ?2, ?3)",
+        )?;
+        let mut tagged = 0;
+        for (id, runs) in &found {
+            for run in runs {
+                insert.execute(params![id, run.tool, run.command])?;
+            }
+            tagged += usize::from(!runs.is_empty());
+        }
+        Ok(tagged)
+    }
+
+    fn backfill_links(&self) -> anyhow::Result<usize> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, content, content_encoding FROM messages")?;
+        let found = stmt
+            .query_map([], |r| {
+                let content = read_content(r, 1, 2)?;
+                Ok((r.get::<_, String>(0)?, extract_links(&content)))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut insert = self.conn.prepare(
+            "INSERT OR IGNORE INTO links (message_id, url, context) VALUES (?1, ?2, ?3)",
+        )?;
+        let mut tagged = 0;
+        for (id, links) in &found {
+            for link in links {
+                insert.execute(params![id, link.url, link.context])?;
+            }
+            tagged += usize::from(!links.is_empty());
+        }
+        Ok(tagged)
+    }
+
+    fn backfill_issue_refs(&self) -> anyhow::Result<usize> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, content, content_encoding FROM messages")?;
+        let found = stmt
+            .query_map([], |r| {
+                let content = read_content(r, 1, 2)?;
+                Ok((r.get::<_, String>(0)?, extract_refs(&content)))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut insert = self.conn.prepare(
+            "INSERT OR IGNORE INTO issue_refs (message_id, repo, number) VALUES (?1, ?2, ?3)",
+        )?;
+        let mut tagged = 0;
+        for (id, refs) in &found {
+            for r in refs {
+                insert.execute(params![
+                    id,
+                    r.repo.as_deref().unwrap_or(""),
+                    r.number as i64
+                ])?;
+            }
+            tagged += usize::from(!refs.is_empty());
+        }
+        Ok(tagged)
+    }
+
+    // A bare `#123` counts for `owner/name#123` when the session's messages
+    // were recorded in a checkout of that repository; a reference without a
+    // repo matches the number in any repository.
+    pub fn sessions_with_ref(&self, reference: &IssueRef) -> anyhow::Result<Vec<Session>> {
+        let repo = reference.repo.as_deref().map(str::to_ascii_lowercase);
+        let remote_suffix = repo
+            .as_deref()
+            .map(|repo| format!("%/{}", escape_like_pattern(repo)));
+        let mut stmt = self.conn.prepare(
+            r#"SELECT s.id, s.agent, s.source_ref, s.title, s.created_at, s.updated_at
+            FROM sessions s
+            WHERE s.deleted_at IS NULL AND s.id IN (
+              SELECT m.session_id FROM issue_refs r
+              INNER JOIN messages m ON m.id = r.message_id
+              WHERE r.number = ?1 AND (?2 IS NULL OR r.repo = ?2)
+              UNION
+              SELECT m.session_id FROM issue_refs r
+              INNER JOIN messages m ON m.id = r.message_id
+              WHERE r.number = ?1 AND r.repo = '' AND ?3 IS NOT NULL
+                AND EXISTS (
+                  SELECT 1 FROM messages pm
+                  INNER JOIN provenance p ON p.entity_type = 'message' AND p.entity_id = pm.id
+                  WHERE pm.session_id = m.session_id
+                    AND lower(p.repo_remote) LIKE ?3 ESCAPE '\'
+                )
+            )
+            ORDER BY s.updated_at DESC, s.id ASC"#,
+        )?;
+        let rows = stmt.query_map(params![reference.number as i64, repo, remote_suffix], |r| {
+            let agent_str: String = r.get(1)?;
+            Ok(Session {
+                id: r.get(0)?,
+                agent: parse_agent(&agent_str)?,
+                source_ref: r.get(2)?,
+                title: r.get(3)?,
+                created_at: parse_ts(r.get(4)?),
+                updated_at: parse_ts(r.get(5)?),
+            })
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Into::into)
+    }
+
+    // Moves the message and everything after it into a new session and
+    // records the split point, so later syncs put new messages of the
+    // original session on the right side of it.
+    pub fn split_session(
+        &mut self,
+        session_id: &str,
+        at_message_id: &str,
+    ) -> anyhow::Result<Session> {
+        let session = select_session(&self.conn, session_id)?
+            .with_context(|| format!("session not found: {session_id}"))?;
+        let tx = self.conn.transaction()?;
+        let at_ts: String = tx
+            .query_row(
+                "SELECT ts FROM messages WHERE id = ?1 AND session_id = ?2",
+                params![at_message_id, session_id],
+                |r| r.get(0),
+            )
+            .optional()?
+            .with_context(|| format!("message {at_message_id} is not in session {session_id}"))?;
+        let earlier: i64 = tx.query_row(
+            "SELECT COUNT(*) FROM messages WHERE session_id = ?1 AND ts < ?2",
+            params![session_id, at_ts],
+            |r| r.get(0),
+        )?;
+        if earlier == 0 {
+            anyhow::bail!("{at_message_id} is the first message; nothing to split off");
+        }
+        let new = Session {
+            id: deterministic_id(&[session.agent.as_str(), "split", session_id, at_message_id]),
+            title: format!("{} (split)", session.title),
+            ..session
+        };
+        tx.execute(
+            r#"INSERT INTO sessions (id, agent, source_ref, title, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?5)"#,
+            params![new.id, new.agent.as_str(), new.source_ref, new.title, at_ts],
+        )
+        .with_context(|| format!("session {session_id} was already split at {at_message_id}"))?;
+        for table in ["messages", "fts_messages", "events"] {
+            tx.execute(
+                &format!("UPDATE {table} SET session_id = ?1 WHERE session_id = ?2 AND ts >= ?3"),
+                params![new.id, session_id, at_ts],
+            )?;
+        }
+        tx.execute(
+            r#"INSERT INTO session_splits (new_id, session_id, at_ts, at_message_id, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)"#,
+            params![
+                new.id,
+                session_id,
+                at_ts,
+                at_message_id,
+                Utc::now().to_rfc3339()
+            ],
+        )?;
+        refresh_session_bounds(&tx, session_id)?;
+        refresh_session_bounds(&tx, &new.id)?;
+        tx.commit()?;
+        select_session(&self.conn, &new.id)?.context("split session missing")
+    }
+
+    // Folds `from` into `into` and remembers the redirect, so messages the
+    // adapter keeps assigning to `from` land in `into` on later syncs.
+    pub fn merge_sessions(&mut self, into: &str, from: &str) -> anyhow::Result<usize> {
+        if into == from {
+            anyhow::bail!("cannot merge a session into itself");
+        }
+        let target = select_session(&self.conn, into)?
+            .with_context(|| format!("session not found: {into}"))?;
+        let source = select_session(&self.conn, from)?
+            .with_context(|| format!("session not found: {from}"))?;
+        if target.agent != source.agent {
+            anyhow::bail!(
+                "cannot merge a {} session into a {} session",
+                source.agent,
+                target.agent
+            );
+        }
+        let meta = select_session_meta(&self.conn, from)?;
+        let tx = self.conn.transaction()?;
+        let moved = tx.execute(
+            "UPDATE messages SET session_id = ?1 WHERE session_id = ?2",
+            params![into, from],
+        )?;
+        for table in ["fts_messages", "events", "artifacts", "archive_items"] {
+            tx.execute(
+                &format!("UPDATE {table} SET session_id = ?1 WHERE session_id = ?2"),
+                params![into, from],
+            )?;
+        }
+        if let Some(meta) = meta {
+            let merged = match select_session_meta(&tx, into)? {
+                Some(existing) => merge_session_meta(existing, &meta),
+                None => meta,
+            };
+            tx.execute(
+                "UPDATE sessions SET session_meta = ?2 WHERE id = ?1",
+                params![into, merged.to_string()],
+            )?;
+        }
+        tx.execute("DELETE FROM sessions WHERE id = ?1", params![from])?;
+        // Merging a split-off part back undoes the split.
+        tx.execute(
+            "DELETE FROM session_splits WHERE new_id = ?1 AND session_id = ?2",
+            params![from, into],
+        )?;
+        tx.execute(
+            "UPDATE session_merges SET into_id = ?1 WHERE into_id = ?2",
+            params![into, from],
+        )?;
+        tx.execute(
+            r#"INSERT INTO session_merges (from_id, into_id, created_at) VALUES (?1, ?2, ?3)
+            ON CONFLICT(from_id) DO UPDATE SET into_id = excluded.into_id"#,
+            params![from, into, Utc::now().to_rfc3339()],
+        )?;
+        refresh_session_bounds(&tx, into)?;
+        tx.commit()?;
+        Ok(moved)
+    }
+
+    // Records that the adapter's session key `source_ref` belongs to the
+    // session keyed `canonical`, and folds an already stored session over.
+    // Returns the canonical session id when one was moved.
+    pub fn add_session_alias(
+        &mut self,
+        agent: AgentKind,
+        source_ref: &str,
+        canonical: &str,
+    ) -> anyhow::Result<Option<String>> {
+        if source_ref == canonical {
+            anyhow::bail!("a session key cannot alias itself");
+        }
+        self.conn.execute(
+            r#"INSERT INTO session_aliases (agent, source_ref, canonical, created_at)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT(agent, source_ref) DO UPDATE SET canonical = excluded.canonical"#,
+            params![
+                agent.as_str(),
+                source_ref,
+                canonical,
+                Utc::now().to_rfc3339()
+            ],
+        )?;
+        let from = deterministic_id(&[agent.as_str(), "session", source_ref]);
+        let Some(session) = select_session(&self.conn, &from)? else {
+            return Ok(None);
+        };
+        let into = deterministic_id(&[agent.as_str(), "session", canonical]);
+        if select_session(&self.conn, &into)?.is_none() {
+            let title = if session.title == session.source_ref {
+                canonical
+            } else {
+                &session.title
+            };
+            self.conn.execute(
+                r#"INSERT INTO sessions (id, agent, source_ref, title, created_at, updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+                params![
+                    into,
+                    agent.as_str(),
+                    canonical,
+                    title,
+                    session.created_at.to_rfc3339(),
+                    session.updated_at.to_rfc3339()
+                ],
+            )?;
+        }
+        self.merge_sessions(&into, &from)?;
+        Ok(Some(into))
+    }
+
+    // The subset of `message_ids` that was first stored before `cutoff`.
+    pub fn ingested_before<'a>(
+        &self,
+        message_ids: impl IntoIterator<Item = &'a str>,
+        cutoff: DateTime<Utc>,
+    ) -> anyhow::Result<HashSet<String>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT ingested_at FROM messages WHERE id = ?1")?;
+        let mut out = HashSet::new();
+        for id in message_ids {
+            let ingested_at: Option<String> = stmt
+                .query_row(params![id], |r| r.get(0))
+                .optional()?
+                .flatten();
+            if ingested_at.is_some_and(|ts| parse_ts(ts) < cutoff) {
+                out.insert(id.to_string());
+            }
+        }
+        Ok(out)
+    }
+
+    pub fn session_aliases(&self, agent: AgentKind) -> anyhow::Result<HashMap<String, String>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT source_ref, canonical FROM session_aliases WHERE agent = ?1")?;
+        let rows = stmt.query_map(params![agent.as_str()], |r| Ok((r.get(0)?, r.get(1)?)))?;
+        rows.collect::<rusqlite::Result<_>>().map_err(Into::into)
+    }
+
+    // Raw payloads are only kept when the profile opts in; they cost disk
+    // space but survive the source files being rotated or deleted.
+    pub fn set_retain_raw_records(&mut self, retain: bool) {
+        self.retain_raw_records = retain;
+    }
+
+    pub fn retains_raw_records(&self) -> bool {
+        self.retain_raw_records
+    }
+
+    pub fn save_raw_records(
+        &mut self,
+        agent: AgentKind,
+        records: &[NativeRecord],
+    ) -> anyhow::Result<usize> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt = tx.prepare_cached(
+                r#"INSERT INTO raw_records (agent, source_id, updated_at, payload, encoding)
+                VALUES (?1, ?2, ?3, ?4, 'zstd')
+                ON CONFLICT(agent, source_id) DO UPDATE SET
+                  updated_at = excluded.updated_at,
+                  payload = excluded.payload,
+                  encoding = excluded.encoding"#,
+            )?;
+            for rec in records {
+                stmt.execute(params![
+                    agent.as_str(),
+                    rec.source_id,
+                    rec.updated_at.to_rfc3339(),
+                    encode_raw_payload(&rec.payload)?
+                ])?;
+            }
+        }
         tx.commit()?;
-        info!(
-            commit = ?commit_start.elapsed(),
-            total = ?started.elapsed(),
-            "commit done"
-        );
-        debug!(
-            sessions = batch.sessions.len(),
-            messages = batch.messages.len(),
-            provenance = batch.provenance.len(),
-            "save_batch complete"
-        );
-        Ok(())
+        Ok(records.len())
     }
 
-    #[cfg(feature = "semantic")]
-    pub fn save_embedding(&self, message_id: &str, vec: &[f32]) -> anyhow::Result<()> {
-        let dim = vec.len() as i64;
-        let blob: Vec<u8> = vec.iter().flat_map(|f| f.to_le_bytes()).collect();
-        self.conn.execute(
-            "INSERT INTO message_embeddings (message_id, dim, vec) VALUES (?1, ?2, ?3) ON CONFLICT(message_id) DO UPDATE SET dim=excluded.dim, vec=excluded.vec",
-            params![message_id, dim, blob],
+    // Rows retained before payloads were compressed.
+    fn compress_raw_records(&self) -> anyhow::Result<usize> {
+        let plain: Vec<(String, String, String)> = {
+            let mut stmt = self.conn.prepare(
+                "SELECT agent, source_id, payload FROM raw_records WHERE encoding = 'plain'",
+            )?;
+            stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))?
+                .collect::<rusqlite::Result<_>>()?
+        };
+        let mut stmt = self.conn.prepare(
+            "UPDATE raw_records SET payload = ?3, encoding = 'zstd' WHERE agent = ?1 AND source_id = ?2",
         )?;
-        Ok(())
+        for (agent, source_id, payload) in &plain {
+            let compressed = zstd::encode_all(payload.as_bytes(), ZSTD_LEVEL)?;
+            stmt.execute(params![agent, source_id, compressed])?;
+        }
+        Ok(plain.len())
     }
 
-    #[cfg(feature = "semantic")]
-    pub fn load_all_embeddings(&self) -> anyhow::Result<Vec<(String, Vec<f32>)>> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT message_id, vec FROM message_embeddings")?;
-        let rows = stmt.query_map([], |r| {
-            let id: String = r.get(0)?;
-            let blob: Vec<u8> = r.get(1)?;
-            let vec: Vec<f32> = blob
-                .chunks_exact(4)
-                .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
-                .collect();
-            Ok((id, vec))
+    pub fn raw_records_usage(&self) -> anyhow::Result<RawRecordsUsage> {
+        self.conn
+            .query_row(
+                "SELECT COUNT(*), COALESCE(SUM(length(payload)), 0) FROM raw_records",
+                [],
+                |r| {
+                    Ok(RawRecordsUsage {
+                        records: r.get::<_, i64>(0)? as usize,
+                        bytes: r.get::<_, i64>(1)? as u64,
+                    })
+                },
+            )
+            .map_err(Into::into)
+    }
+
+    pub fn storage_usage(&self) -> anyhow::Result<StorageUsage> {
+        let pragma = |name: &str| -> anyhow::Result<u64> {
+            let value: i64 = self
+                .conn
+                .query_row(&format!("PRAGMA {name}"), [], |r| r.get(0))?;
+            Ok(value as u64)
+        };
+        let page_size = pragma("page_size")?;
+        let tables = match self.conn.prepare(
+            r#"SELECT COALESCE(m.tbl_name, d.name) AS owner, SUM(d.pgsize) AS bytes
+            FROM dbstat d LEFT JOIN sqlite_master m ON m.name = d.name
+            GROUP BY owner ORDER BY bytes DESC, owner ASC"#,
+        ) {
+            Ok(mut stmt) => stmt
+                .query_map([], |r| Ok((r.get(0)?, r.get::<_, i64>(1)? as u64)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?,
+            Err(err) => {
+                debug!(%err, "dbstat unavailable");
+                Vec::new()
+            }
+        };
+        let mut stmt = self.conn.prepare(
+            r#"SELECT s.agent, COUNT(*), COALESCE(SUM(length(m.content)), 0)
+            FROM messages m INNER JOIN sessions s ON s.id = m.session_id
+            GROUP BY s.agent ORDER BY 3 DESC, s.agent ASC"#,
+        )?;
+        let agents = stmt
+            .query_map([], |r| {
+                Ok(AgentStorage {
+                    agent: r.get(0)?,
+                    messages: r.get::<_, i64>(1)? as usize,
+                    content_bytes: r.get::<_, i64>(2)? as u64,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(StorageUsage {
+            db_bytes: pragma("page_count")? * page_size,
+            free_bytes: pragma("freelist_count")? * page_size,
+            tables,
+            agents,
+        })
+    }
+
+    // The retained records behind a session's messages, matched through
+    // their provenance source ids.
+    pub fn session_raw_records(&self, session_id: &str) -> anyhow::Result<Vec<NativeRecord>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT r.source_id, r.updated_at, r.payload, r.encoding
+            FROM raw_records r
+            WHERE EXISTS (
+              SELECT 1 FROM provenance p
+              INNER JOIN messages m ON m.id = p.entity_id
+              WHERE m.session_id = ?1 AND p.entity_type = 'message'
+                AND p.agent = r.agent AND p.source_id = r.source_id
+            )
+            ORDER BY r.updated_at ASC, r.source_id ASC"#,
+        )?;
+        let rows = stmt.query_map(params![session_id], raw_record_from_row)?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Into::into)
+    }
+
+    // Every retained record for an agent, in the order adapters scan them.
+    pub fn raw_records(&self, agent: AgentKind) -> anyhow::Result<Vec<NativeRecord>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT source_id, updated_at, payload, encoding
+            FROM raw_records
+            WHERE agent = ?1
+            ORDER BY updated_at ASC, source_id ASC"#,
+        )?;
+        let rows = stmt.query_map(params![agent.as_str()], raw_record_from_row)?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Into::into)
+    }
+
+    pub fn list_links(
+        &self,
+        since: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> anyhow::Result<Vec<LinkRow>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT l.message_id, m.session_id, s.agent, m.role, l.url, l.context, m.ts
+            FROM links l
+            INNER JOIN messages m ON m.id = l.message_id
+            INNER JOIN sessions s ON s.id = m.session_id
+            WHERE ?1 IS NULL OR m.ts >= ?1
+            ORDER BY m.ts DESC, m.rowid DESC, l.url ASC
+            LIMIT ?2"#,
+        )?;
+        let rows = stmt.query_map(params![since.map(|t| t.to_rfc3339()), limit], |r| {
+            let agent: String = r.get(2)?;
+            Ok(LinkRow {
+                message_id: r.get(0)?,
+                session_id: r.get(1)?,
+                agent: parse_agent(&agent)?,
+                role: r.get(3)?,
+                url: r.get(4)?,
+                context: r.get(5)?,
+                ts: parse_ts(r.get(6)?),
+            })
         })?;
         rows.collect::<rusqlite::Result<Vec<_>>>()
             .map_err(Into::into)
     }
 
-    #[cfg(feature = "semantic")]
-    pub fn embedding_stats(&self) -> anyhow::Result<EmbeddingStats> {
+    // Case-insensitive substring match, newest runs first.
+    pub fn search_commands(&self, query: &str, limit: i64) -> anyhow::Result<Vec<CommandRow>> {
+        let pattern = format!("%{}%", escape_like_pattern(query.trim()));
         let mut stmt = self.conn.prepare(
-            "SELECT COUNT(*), MAX(m.ts) FROM message_embeddings e LEFT JOIN messages m ON e.message_id = m.id",
+            r#"SELECT c.message_id, m.session_id, s.agent, c.tool, c.command, m.ts
+            FROM commands c
+            INNER JOIN messages m ON m.id = c.message_id
+            INNER JOIN sessions s ON s.id = m.session_id
+            WHERE lower(c.command) LIKE ?1 ESCAPE '\'
+            ORDER BY m.ts DESC, m.rowid DESC
+            LIMIT ?2"#,
         )?;
-        let (count, ts): (i64, Option<String>) =
-            stmt.query_row([], |r| Ok((r.get(0)?, r.get(1)?)))?;
-        Ok(EmbeddingStats {
-            len: count as usize,
-            updated_at: ts.map(parse_ts),
-        })
+        let rows = stmt.query_map(params![pattern, limit], |r| {
+            let agent: String = r.get(2)?;
+            Ok(CommandRow {
+                message_id: r.get(0)?,
+                session_id: r.get(1)?,
+                agent: parse_agent(&agent)?,
+                tool: r.get(3)?,
+                command: r.get(4)?,
+                ts: parse_ts(r.get(5)?),
+            })
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Into::into)
     }
 
-    pub fn get_checkpoint(&self, agent: &str) -> anyhow::Result<Option<String>> {
-        let result: Option<String> = self
-            .conn
+    // The user prompt a message answers: the latest user message at or
+    // before it in the same session.
+    pub fn prompt_before(&self, message_id: &str) -> anyhow::Result<Option<Message>> {
+        self.conn
             .query_row(
-                "SELECT cursor FROM checkpoints WHERE agent = ?1",
-                params![agent],
-                |r| r.get(0),
+                r#"SELECT u.id, u.session_id, u.role, u.content, u.ts, u.content_encoding
+                FROM messages m
+                INNER JOIN messages u ON u.session_id = m.session_id
+                WHERE m.id = ?1 AND u.role = 'user'
+                  AND (u.ts < m.ts OR (u.ts = m.ts AND u.rowid <= m.rowid))
+                ORDER BY u.ts DESC, u.rowid DESC
+                LIMIT 1"#,
+                params![message_id],
+                |r| {
+                    Ok(Message {
+                        id: r.get(0)?,
+                        session_id: r.get(1)?,
+                        role: r.get(2)?,
+                        content: read_content(r, 3, 5)?,
+                        ts: parse_ts(r.get(4)?),
+                    })
+                },
             )
-            .optional()?;
-        debug!(agent, cursor = ?result.as_deref(), "checkpoint loaded");
-        Ok(result)
+            .optional()
+            .map_err(Into::into)
     }
 
-    pub fn upsert_checkpoint(&self, checkpoint: &Checkpoint) -> anyhow::Result<()> {
-        trace!(agent = %checkpoint.agent, cursor = %checkpoint.cursor, "upserting checkpoint");
+    // `path` matches exactly or as a trailing path component sequence, so a
+    // repo-relative path also finds tools that were given absolute paths.
+    pub fn file_history(&self, path: &str, limit: i64) -> anyhow::Result<Vec<FileHistoryRow>> {
+        let path = path.trim().trim_start_matches("./");
+        let suffix = format!("%/{}", escape_like_pattern(path));
+        let mut stmt = self.conn.prepare(
+            r#"SELECT s.id, s.agent, s.title, COUNT(*), MIN(m.ts), MAX(m.ts),
+              GROUP_CONCAT(DISTINCT f.tool), GROUP_CONCAT(DISTINCT f.path)
+            FROM file_mentions f
+            INNER JOIN messages m ON m.id = f.message_id
+            INNER JOIN sessions s ON s.id = m.session_id
+            WHERE f.path = ?1 OR f.path LIKE ?2 ESCAPE '\'
+            GROUP BY s.id
+            ORDER BY MAX(m.ts) DESC, s.id ASC
+            LIMIT ?3"#,
+        )?;
+        let split = |joined: Option<String>| -> Vec<String> {
+            let mut items: Vec<String> = joined
+                .unwrap_or_default()
+                .split(',')
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect();
+            items.sort();
+            items
+        };
+        let rows = stmt.query_map(params![path, suffix, limit], |r| {
+            let agent: String = r.get(1)?;
+            Ok(FileHistoryRow {
+                session_id: r.get(0)?,
+                agent: parse_agent(&agent)?,
+                title: r.get(2)?,
+                mentions: r.get::<_, i64>(3)? as usize,
+                first_ts: parse_ts(r.get(4)?),
+                last_ts: parse_ts(r.get(5)?),
+                tools: split(r.get(6)?),
+                paths: split(r.get(7)?),
+            })
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Into::into)
+    }
+
+    pub fn get_provenance_for_session(&self, session_id: &str) -> anyhow::Result<Vec<Provenance>> {
+        select_session_provenance(&self.conn, session_id)
+    }
+
+    pub fn search_lexical(
+        &self,
+        query: &str,
+        limit: i64,
+        scope: &SearchScope,
+    ) -> anyhow::Result<Vec<SearchRow>> {
+        debug!(query, limit, ?scope, "lexical search");
+        let query = match scope.part() {
+            Some(column) => format!("{column} : ({query})"),
+            None => query.to_string(),
+        };
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT m.id, m.session_id, m.content, m.content_encoding, m.ts, bm25(fts_messages) AS rank
+            FROM fts_messages JOIN messages m ON m.rowid = fts_messages.rowid
+            WHERE fts_messages MATCH :query AND {SCOPE_FILTER}
+            ORDER BY rank LIMIT :limit"
+        ))?;
+        let rows = stmt.query_map(
+            named_params! { ":query": query, ":limit": limit, ":before": scope.before(), ":trashed": scope.include_trashed, ":part": scope.part() },
+            |r| {
+                let rank: f64 = r.get(5)?;
+                Ok(SearchRow {
+                    message_id: r.get(0)?,
+                    session_id: r.get(1)?,
+                    content: read_content(r, 2, 3)?,
+                    ts: parse_ts(r.get(4)?),
+                    score: -rank,
+                })
+            },
+        )?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Into::into)
+    }
+
+    pub fn recent_messages(
+        &self,
+        limit: i64,
+        scope: &SearchScope,
+    ) -> anyhow::Result<Vec<SearchRow>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT m.id, m.session_id, m.content, m.ts, m.content_encoding FROM messages m
+            WHERE {SCOPE_FILTER} ORDER BY m.ts DESC LIMIT :limit"
+        ))?;
+        let rows = stmt.query_map(
+            named_params! { ":limit": limit, ":before": scope.before(), ":trashed": scope.include_trashed, ":part": scope.part() },
+            |r| {
+                Ok(SearchRow {
+                    message_id: r.get(0)?,
+                    session_id: r.get(1)?,
+                    content: read_content(r, 2, 4)?,
+                    ts: parse_ts(r.get(3)?),
+                    score: 0.0,
+                })
+            },
+        )?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Into::into)
+    }
+
+    // Whether one message falls inside `scope`, for candidates that do not
+    // come from SQL, such as semantic neighbours.
+    pub fn message_in_scope(&self, message_id: &str, scope: &SearchScope) -> anyhow::Result<bool> {
+        let mut stmt = self.conn.prepare_cached(&format!(
+            "SELECT COUNT(*) > 0 FROM messages m WHERE m.id = :id AND {SCOPE_FILTER}"
+        ))?;
+        Ok(stmt.query_row(
+            named_params! { ":id": message_id, ":before": scope.before(), ":trashed": scope.include_trashed, ":part": scope.part() },
+            |r| r.get(0),
+        )?)
+    }
+
+    // New messages get increasing rowids and upserts keep theirs, so the
+    // largest rowid seen is a cursor for following later inserts.
+    pub fn messages_after(
+        &self,
+        cursor: i64,
+        agent: Option<&str>,
+        session_id: Option<&str>,
+        limit: i64,
+    ) -> anyhow::Result<Vec<(i64, Message)>> {
+        select_tail_messages(
+            &self.conn,
+            "m.rowid > ?1 AND (?2 IS NULL OR s.agent = ?2) AND (?3 IS NULL OR m.session_id = ?3) ORDER BY m.rowid ASC LIMIT ?4",
+            params![cursor, agent, session_id, limit],
+        )
+    }
+
+    pub fn latest_messages(
+        &self,
+        agent: Option<&str>,
+        session_id: Option<&str>,
+        count: i64,
+    ) -> anyhow::Result<Vec<(i64, Message)>> {
+        let mut rows = select_tail_messages(
+            &self.conn,
+            "(?1 IS NULL OR s.agent = ?1) AND (?2 IS NULL OR m.session_id = ?2) ORDER BY m.rowid DESC LIMIT ?3",
+            params![agent, session_id, count],
+        )?;
+        rows.reverse();
+        Ok(rows)
+    }
+
+    pub fn message_cursor(&self) -> anyhow::Result<i64> {
+        Ok(self
+            .conn
+            .query_row("SELECT COALESCE(MAX(rowid), 0) FROM messages", [], |r| {
+                r.get(0)
+            })?)
+    }
+
+    pub fn search_substring(
+        &self,
+        query: &str,
+        limit: i64,
+        scope: &SearchScope,
+    ) -> anyhow::Result<Vec<SearchRow>> {
+        debug!(query, limit, ?scope, "substring search");
+        let pattern = format!("%{}%", escape_like_pattern(&query.to_lowercase()));
+        let matches = match scope.part() {
+            Some(column) => format!("lower(f.{column}) LIKE :pattern ESCAPE '\\'"),
+            None => "(lower(f.content) LIKE :pattern ESCAPE '\\'
+                OR lower(f.reasoning) LIKE :pattern ESCAPE '\\')"
+                .to_string(),
+        };
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT m.id, m.session_id, m.content, m.content_encoding, m.ts
+            FROM fts_messages f JOIN messages m ON m.rowid = f.rowid
+            WHERE {matches} AND {SCOPE_FILTER}
+            ORDER BY m.ts DESC LIMIT :limit"
+        ))?;
+        let rows = stmt.query_map(
+            named_params! { ":pattern": pattern, ":limit": limit, ":before": scope.before(), ":trashed": scope.include_trashed, ":part": scope.part() },
+            |r| {
+                Ok(SearchRow {
+                    message_id: r.get(0)?,
+                    session_id: r.get(1)?,
+                    content: read_content(r, 2, 3)?,
+                    ts: parse_ts(r.get(4)?),
+                    score: 0.0,
+                })
+            },
+        )?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Into::into)
+    }
+
+    pub fn plan_archive(
+        &self,
+        older_than: Duration,
+        keep_latest: usize,
+    ) -> anyhow::Result<ArchiveRun> {
+        let now = Utc::now();
+        let run_id = deterministic_id(&[
+            "archive_run",
+            &now.timestamp_nanos_opt().unwrap_or_default().to_string(),
+            &older_than.num_seconds().to_string(),
+            &keep_latest.to_string(),
+        ]);
+        let cutoff = now - older_than;
         self.conn.execute(
-            r#"INSERT INTO checkpoints (agent, cursor, updated_at) VALUES (?1, ?2, ?3)
-            ON CONFLICT(agent) DO UPDATE SET cursor=excluded.cursor, updated_at=excluded.updated_at"#,
-            params![
-                checkpoint.agent.as_str(),
-                checkpoint.cursor,
-                checkpoint.updated_at.to_rfc3339()
-            ],
+            "INSERT INTO archive_runs (id, created_at, older_than_secs, keep_latest, dry_run, executed) VALUES (?1, ?2, ?3, ?4, 1, 0)",
+            params![run_id, now.to_rfc3339(), older_than.num_seconds(), keep_latest as i64],
+        )?;
+
+        let sessions = self.list_sessions()?;
+        let mut by_agent: std::collections::HashMap<&str, Vec<Session>> =
+            std::collections::HashMap::new();
+        for s in sessions {
+            by_agent.entry(s.agent.as_str()).or_default().push(s);
+        }
+        for grouped in by_agent.values_mut() {
+            grouped.sort_by_key(|session| std::cmp::Reverse(session.updated_at));
+            for s in grouped.iter().skip(keep_latest) {
+                if s.updated_at < cutoff {
+                    let already_planned: bool = self.conn.query_row(
+                        "SELECT EXISTS(SELECT 1 FROM archive_items ai JOIN archive_runs ar ON ai.run_id = ar.id WHERE ai.session_id = ?1 AND ar.executed = 0)",
+                        params![s.id],
+                        |r| r.get(0),
+                    )?;
+                    if already_planned {
+                        continue;
+                    }
+                    let item_id = deterministic_id(&[&run_id, &s.id]);
+                    self.conn.execute(
+                        "INSERT INTO archive_items (id, run_id, session_id, planned_delete) VALUES (?1, ?2, ?3, 1)",
+                        params![item_id, run_id, s.id],
+                    )?;
+                }
+            }
+        }
+
+        Ok(ArchiveRun {
+            id: run_id,
+            created_at: now,
+            older_than_secs: older_than.num_seconds(),
+            keep_latest: keep_latest as i64,
+            dry_run: true,
+            executed: false,
+        })
+    }
+
+    pub fn archive_items_for_run(&self, run_id: &str) -> anyhow::Result<Vec<ArchiveItem>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, run_id, session_id, planned_delete FROM archive_items WHERE run_id = ?1",
+        )?;
+        let rows = stmt.query_map(params![run_id], |r| {
+            Ok(ArchiveItem {
+                id: r.get(0)?,
+                run_id: r.get(1)?,
+                session_id: r.get(2)?,
+                planned_delete: r.get::<_, i64>(3)? == 1,
+            })
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Into::into)
+    }
+
+    // Newest first.
+    pub fn archive_runs(&self) -> anyhow::Result<Vec<ArchiveRun>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, created_at, older_than_secs, keep_latest, dry_run, executed FROM archive_runs ORDER BY created_at DESC, id",
+        )?;
+        let rows = stmt.query_map([], |r| {
+            Ok(ArchiveRun {
+                id: r.get(0)?,
+                created_at: parse_ts(r.get(1)?),
+                older_than_secs: r.get(2)?,
+                keep_latest: r.get(3)?,
+                dry_run: r.get::<_, i64>(4)? == 1,
+                executed: r.get::<_, i64>(5)? == 1,
+            })
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Into::into)
+    }
+
+    pub fn mark_archive_executed(&self, run_id: &str, dry_run: bool) -> anyhow::Result<()> {
+        self.conn.execute(
+            "UPDATE archive_runs SET dry_run = ?2, executed = 1 WHERE id = ?1",
+            params![run_id, if dry_run { 1 } else { 0 }],
         )?;
         Ok(())
     }
 
-    pub fn begin_doc_sync(&mut self, canonical_path: &str) -> anyhow::Result<DocSyncState> {
-        let root_id = doc_root_id(canonical_path);
-        let started_at = Utc::now().to_rfc3339();
-        let tx = self.conn.transaction()?;
-        tx.execute(
-            "INSERT OR IGNORE INTO doc_roots (root_id, canonical_path, current_generation, last_completed_generation, scan_status) VALUES (?1, ?2, 0, 0, 'idle')",
-            params![root_id.as_str(), canonical_path],
+    pub fn delete_session_cascade(&self, session_id: &str) -> anyhow::Result<()> {
+        debug!(session_id, "cascading delete session");
+        // Provenance has no foreign key, so it is removed while the message
+        // ids can still be looked up.
+        self.conn.execute(
+            r#"DELETE FROM provenance WHERE entity_type = 'message'
+            AND entity_id IN (SELECT id FROM messages WHERE session_id = ?1)"#,
+            params![session_id],
         )?;
-        let generation: i64 = tx.query_row(
-            "SELECT current_generation + 1 FROM doc_roots WHERE root_id = ?1",
-            params![root_id.as_str()],
-            |r| r.get(0),
+        self.conn
+            .execute("DELETE FROM sessions WHERE id = ?1", params![session_id])?;
+        self.conn.execute(
+            "DELETE FROM fts_messages WHERE session_id = ?1",
+            params![session_id],
         )?;
-        tx.execute(
-            "UPDATE doc_roots SET current_generation = ?2, scan_started_at = ?3, scan_completed_at = NULL, scan_status = 'running' WHERE root_id = ?1",
-            params![root_id.as_str(), generation, started_at],
+        Ok(())
+    }
+
+    // Trashed sessions keep all their data and keep syncing, but are left out
+    // of listings and search until restored or purged by `empty_trash`.
+    pub fn trash_session(&self, session_id: &str) -> anyhow::Result<()> {
+        let trashed = self.conn.execute(
+            "UPDATE sessions SET deleted_at = ?2 WHERE id = ?1 AND deleted_at IS NULL",
+            params![session_id, Utc::now().to_rfc3339()],
         )?;
-        tx.commit()?;
-        Ok(DocSyncState {
-            root_id,
-            canonical_path: canonical_path.to_string(),
-            generation,
-        })
+        if trashed == 0 {
+            match select_session(&self.conn, session_id)? {
+                Some(_) => anyhow::bail!("session {session_id} is already in the trash"),
+                None => anyhow::bail!("session not found: {session_id}"),
+            }
+        }
+        Ok(())
     }
 
-    pub fn get_doc_root(&self, canonical_path: &str) -> anyhow::Result<Option<DocRootRecord>> {
-        self.conn
-            .query_row(
-                "SELECT root_id, canonical_path, current_generation, last_completed_generation, scan_started_at, scan_completed_at, scan_status FROM doc_roots WHERE canonical_path = ?1",
-                params![canonical_path],
-                |r| {
-                    Ok(DocRootRecord {
-                        root_id: r.get(0)?,
-                        canonical_path: r.get(1)?,
-                        current_generation: r.get(2)?,
-                        last_completed_generation: r.get(3)?,
-                        scan_started_at: parse_optional_ts(r.get(4)?),
-                        scan_completed_at: parse_optional_ts(r.get(5)?),
-                        scan_status: r.get(6)?,
-                    })
-                },
-            )
-            .optional()
-            .map_err(Into::into)
+    pub fn restore_session(&self, session_id: &str) -> anyhow::Result<()> {
+        let restored = self.conn.execute(
+            "UPDATE sessions SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL",
+            params![session_id],
+        )?;
+        if restored == 0 {
+            anyhow::bail!("session {session_id} is not in the trash");
+        }
+        Ok(())
     }
 
-    pub fn get_document_by_path(
-        &self,
-        root_id: &str,
-        relative_path: &str,
-    ) -> anyhow::Result<Option<DocumentRecord>> {
-        self.conn
-            .query_row(
-                "SELECT id, root_id, relative_path, title, modified_at, size_bytes, content_hash, last_seen_generation, indexed_generation, indexed_at FROM documents WHERE root_id = ?1 AND relative_path = ?2",
-                params![root_id, relative_path],
-                |r| {
-                    Ok(DocumentRecord {
-                        document_id: r.get(0)?,
-                        root_id: r.get(1)?,
-                        relative_path: r.get(2)?,
-                        title: r.get(3)?,
-                        modified_at: parse_ts(r.get(4)?),
-                        size_bytes: r.get(5)?,
-                        content_hash: r.get(6)?,
-                        last_seen_generation: r.get(7)?,
-                        indexed_generation: r.get(8)?,
-                        indexed_at: parse_ts(r.get(9)?),
-                    })
+    // Most recently trashed first, with the time each was trashed.
+    pub fn trashed_sessions(&self) -> anyhow::Result<Vec<(Session, DateTime<Utc>)>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT id, agent, source_ref, title, created_at, updated_at, deleted_at
+            FROM sessions WHERE deleted_at IS NOT NULL
+            ORDER BY deleted_at DESC, id ASC"#,
+        )?;
+        let rows = stmt.query_map([], |r| {
+            let agent_str: String = r.get(1)?;
+            Ok((
+                Session {
+                    id: r.get(0)?,
+                    agent: parse_agent(&agent_str)?,
+                    source_ref: r.get(2)?,
+                    title: r.get(3)?,
+                    created_at: parse_ts(r.get(4)?),
+                    updated_at: parse_ts(r.get(5)?),
                 },
-            )
-            .optional()
+                parse_ts(r.get(6)?),
+            ))
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
             .map_err(Into::into)
     }
 
-    pub fn mark_document_seen(
-        &self,
-        root_id: &str,
-        relative_path: &str,
-        generation: i64,
-    ) -> anyhow::Result<bool> {
-        let updated = self.conn.execute(
-            "UPDATE documents SET last_seen_generation = ?3 WHERE root_id = ?1 AND relative_path = ?2",
-            params![root_id, relative_path, generation],
-        )?;
-        Ok(updated > 0)
+    pub fn trashed_session_ids(&self) -> anyhow::Result<HashSet<String>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT id FROM sessions WHERE deleted_at IS NOT NULL")?;
+        let rows = stmt.query_map([], |r| r.get(0))?;
+        rows.collect::<rusqlite::Result<_>>().map_err(Into::into)
     }
 
-    #[allow(clippy::too_many_arguments)]
-    pub fn upsert_document(
-        &mut self,
-        root_id: &str,
-        relative_path: &str,
-        title: &str,
-        modified_at: DateTime<Utc>,
-        size_bytes: i64,
-        content_hash: &str,
-        content: &str,
-        generation: i64,
-    ) -> anyhow::Result<String> {
-        let document_id = document_id(root_id, relative_path);
-        let indexed_at = Utc::now().to_rfc3339();
+    // Permanently deletes trashed sessions, all of them or those trashed
+    // before `before`.
+    pub fn empty_trash(&self, before: Option<DateTime<Utc>>) -> anyhow::Result<usize> {
+        let ids: Vec<String> = self
+            .trashed_sessions()?
+            .into_iter()
+            .filter(|(_, deleted_at)| before.is_none_or(|before| *deleted_at < before))
+            .map(|(session, _)| session.id)
+            .collect();
+        for id in &ids {
+            self.delete_session_cascade(id)?;
+        }
+        Ok(ids.len())
+    }
+
+    // Permanently removes sessions, trashed or not, with everything derived
+    // from them, then rewrites the database file so the deleted content does
+    // not linger in free pages or the WAL.
+    pub fn wipe_sessions(&mut self, ids: &[String]) -> anyhow::Result<WipeCounts> {
+        let mut counts = WipeCounts::default();
         let tx = self.conn.transaction()?;
-        tx.execute(
-            r#"INSERT INTO documents (id, root_id, relative_path, title, modified_at, size_bytes, content_hash, last_seen_generation, indexed_generation, indexed_at)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8, ?9)
-            ON CONFLICT(root_id, relative_path) DO UPDATE SET
-              id=excluded.id,
-              title=excluded.title,
-              modified_at=excluded.modified_at,
-              size_bytes=excluded.size_bytes,
-              content_hash=excluded.content_hash,
-              last_seen_generation=excluded.last_seen_generation,
-              indexed_generation=excluded.indexed_generation,
-              indexed_at=excluded.indexed_at"#,
-            params![
-                document_id.as_str(),
-                root_id,
-                relative_path,
-                title,
-                modified_at.to_rfc3339(),
-                size_bytes,
-                content_hash,
-                generation,
-                indexed_at,
-            ],
+        tx.execute_batch(
+            "CREATE TEMP TABLE IF NOT EXISTS wipe_ids (id TEXT PRIMARY KEY); DELETE FROM wipe_ids;",
         )?;
-        let rowid: i64 = tx.query_row(
-            "SELECT rowid FROM documents WHERE root_id = ?1 AND relative_path = ?2",
-            params![root_id, relative_path],
-            |r| r.get(0),
+        {
+            let mut stmt = tx.prepare("INSERT OR IGNORE INTO wipe_ids (id) VALUES (?1)")?;
+            for id in ids {
+                stmt.execute(params![id])?;
+            }
+        }
+        let count = |sql: &str| -> rusqlite::Result<usize> {
+            tx.query_row(sql, [], |r| r.get::<_, i64>(0))
+                .map(|n| n as usize)
+        };
+        counts.messages = count(
+            "SELECT COUNT(*) FROM messages WHERE session_id IN (SELECT id FROM temp.wipe_ids)",
         )?;
-        tx.execute("DELETE FROM fts_documents WHERE rowid = ?1", params![rowid])?;
-        tx.execute(
-            "INSERT INTO fts_documents (rowid, document_id, root_id, path, title, content) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            params![rowid, document_id.as_str(), root_id, relative_path, title, content],
+        counts.events = count(
+            "SELECT COUNT(*) FROM events WHERE session_id IN (SELECT id FROM temp.wipe_ids)",
+        )?;
+        counts.artifacts = count(
+            "SELECT COUNT(*) FROM artifacts WHERE session_id IN (SELECT id FROM temp.wipe_ids)",
+        )?;
+        counts.embeddings = count(
+            r#"SELECT COUNT(*) FROM message_embeddings
+            WHERE message_id IN (SELECT id FROM messages WHERE session_id IN (SELECT id FROM temp.wipe_ids))"#,
+        )?;
+        counts.archive_items = count(
+            "SELECT COUNT(*) FROM archive_items WHERE session_id IN (SELECT id FROM temp.wipe_ids)",
+        )?;
+        // Raw records are shared by agent and source id, so they go before
+        // the provenance that links them to messages.
+        counts.raw_records = tx.execute(
+            r#"DELETE FROM raw_records WHERE EXISTS (
+              SELECT 1 FROM provenance p
+              INNER JOIN messages m ON m.id = p.entity_id
+              WHERE p.entity_type = 'message' AND m.session_id IN (SELECT id FROM temp.wipe_ids)
+                AND p.agent = raw_records.agent AND p.source_id = raw_records.source_id
+            )"#,
+            [],
+        )?;
+        counts.provenance = tx.execute(
+            r#"DELETE FROM provenance
+            WHERE (entity_type = 'message' AND entity_id IN (SELECT id FROM messages WHERE session_id IN (SELECT id FROM temp.wipe_ids)))
+               OR (entity_type = 'event' AND entity_id IN (SELECT id FROM events WHERE session_id IN (SELECT id FROM temp.wipe_ids)))
+               OR (entity_type = 'artifact' AND entity_id IN (SELECT id FROM artifacts WHERE session_id IN (SELECT id FROM temp.wipe_ids)))
+               OR (entity_type = 'session' AND entity_id IN (SELECT id FROM temp.wipe_ids))"#,
+            [],
+        )?;
+        counts.fts_rows = tx.execute(
+            "DELETE FROM fts_messages WHERE session_id IN (SELECT id FROM temp.wipe_ids)",
+            [],
         )?;
+        tx.execute_batch(
+            r#"DELETE FROM session_aliases WHERE EXISTS (
+              SELECT 1 FROM sessions s WHERE s.id IN (SELECT id FROM temp.wipe_ids)
+                AND s.agent = session_aliases.agent
+                AND s.source_ref IN (session_aliases.source_ref, session_aliases.canonical)
+            );
+            DELETE FROM session_merges WHERE from_id IN (SELECT id FROM temp.wipe_ids) OR into_id IN (SELECT id FROM temp.wipe_ids);
+            DELETE FROM session_splits WHERE new_id IN (SELECT id FROM temp.wipe_ids) OR session_id IN (SELECT id FROM temp.wipe_ids);"#,
+        )?;
+        // Messages, events, artifacts and everything keyed by message id
+        // follow through their foreign keys.
+        counts.sessions = tx.execute(
+            "DELETE FROM sessions WHERE id IN (SELECT id FROM temp.wipe_ids)",
+            [],
+        )?;
+        tx.execute_batch("DELETE FROM wipe_ids;")?;
         tx.commit()?;
-        Ok(document_id)
+        debug!(?counts, "sessions wiped");
+        if counts.sessions > 0 {
+            self.conn.execute_batch(
+                r#"INSERT INTO fts_messages(fts_messages) VALUES ('optimize');
+                VACUUM;
+                PRAGMA wal_checkpoint(TRUNCATE);"#,
+            )?;
+        }
+        Ok(counts)
     }
 
-    pub fn finalize_doc_sync(
-        &mut self,
-        root_id: &str,
-        generation: i64,
-    ) -> anyhow::Result<DocFinalizeResult> {
-        let completed_at = Utc::now().to_rfc3339();
+    // Drops cached query embeddings whose query text matches.
+    pub fn delete_cached_queries(&self, matches: impl Fn(&str) -> bool) -> anyhow::Result<usize> {
+        let queries: Vec<String> = {
+            let mut stmt = self
+                .conn
+                .prepare("SELECT DISTINCT query FROM query_embeddings")?;
+            let rows = stmt.query_map([], |r| r.get(0))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        let mut deleted = 0;
+        for query in queries.iter().filter(|q| matches(q)) {
+            deleted += self.conn.execute(
+                "DELETE FROM query_embeddings WHERE query = ?1",
+                params![query],
+            )?;
+        }
+        Ok(deleted)
+    }
+
+    pub fn dedupe_agent_messages(&mut self, agent: &str) -> anyhow::Result<usize> {
         let tx = self.conn.transaction()?;
-        let current_generation: Option<i64> = tx
-            .query_row(
-                "SELECT current_generation FROM doc_roots WHERE root_id = ?1",
-                params![root_id],
-                |r| r.get(0),
-            )
-            .optional()?;
-        let current_generation = current_generation.with_context(|| {
-            format!("doc root {root_id} missing while finalizing generation {generation}")
-        })?;
-        if current_generation != generation {
-            return Err(anyhow::anyhow!(
-                "stale doc sync finalize for root {root_id}: current generation {current_generation}, attempted {generation}"
-            ));
+        let duplicates: Vec<(i64, String)> = {
+            let mut stmt = tx.prepare(
+                r#"SELECT m.rowid, m.id FROM messages m
+                JOIN sessions s ON s.id = m.session_id
+                WHERE s.agent = ?1 AND EXISTS (
+                  SELECT 1 FROM messages d
+                  WHERE d.session_id = m.session_id
+                    AND d.role = m.role
+                    AND d.ts = m.ts
+                    AND d.content_encoding = m.content_encoding
+                    AND d.content = m.content
+                    AND d.rowid > m.rowid
+                )"#,
+            )?;
+            stmt.query_map(params![agent], |r| Ok((r.get(0)?, r.get(1)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        {
+            let mut stmt_fts = tx.prepare_cached("DELETE FROM fts_messages WHERE rowid = ?1")?;
+            let mut stmt_prov = tx.prepare_cached(
+                "DELETE FROM provenance WHERE entity_type = 'message' AND entity_id = ?1",
+            )?;
+            let mut stmt_msg = tx.prepare_cached("DELETE FROM messages WHERE rowid = ?1")?;
+            for (rowid, id) in &duplicates {
+                stmt_fts.execute(params![rowid])?;
+                stmt_prov.execute(params![id])?;
+                stmt_msg.execute(params![rowid])?;
+            }
         }
-        let deleted_documents: i64 = tx.query_row(
-            "SELECT COUNT(*) FROM documents WHERE root_id = ?1 AND last_seen_generation < ?2",
-            params![root_id, generation],
-            |r| r.get(0),
-        )?;
-        tx.execute(
-            "DELETE FROM fts_documents WHERE rowid IN (SELECT rowid FROM documents WHERE root_id = ?1 AND last_seen_generation < ?2)",
-            params![root_id, generation],
-        )?;
-        tx.execute(
-            "DELETE FROM documents WHERE root_id = ?1 AND last_seen_generation < ?2",
-            params![root_id, generation],
-        )?;
-        tx.execute(
-            "UPDATE doc_roots SET last_completed_generation = ?2, scan_status = 'completed', scan_completed_at = ?3 WHERE root_id = ?1",
-            params![root_id, generation, completed_at],
-        )?;
         tx.commit()?;
-        Ok(DocFinalizeResult {
-            deleted_documents: deleted_documents as usize,
-        })
+        debug!(agent, removed = duplicates.len(), "deduplicated messages");
+        Ok(duplicates.len())
     }
 
-    pub fn fail_doc_sync(&self, root_id: &str, generation: i64) -> anyhow::Result<()> {
-        let updated = self.conn.execute(
-            "UPDATE doc_roots SET scan_status = 'failed', scan_completed_at = ?3 WHERE root_id = ?1 AND current_generation = ?2",
-            params![root_id, generation, Utc::now().to_rfc3339()],
+    pub fn fts_health(&self) -> anyhow::Result<FtsHealth> {
+        let stray_rows: i64 = self.conn.query_row(
+            r#"SELECT COUNT(*) FROM fts_messages f
+            WHERE NOT EXISTS (
+              SELECT 1 FROM messages m WHERE m.rowid = f.rowid AND m.id = f.message_id
+            )"#,
+            [],
+            |r| r.get(0),
         )?;
-        if updated == 0 {
-            return Err(anyhow::anyhow!(
-                "doc root {root_id} missing or generation {generation} is no longer current"
-            ));
-        }
-        Ok(())
+        let missing_rows: i64 = self.conn.query_row(
+            r#"SELECT COUNT(*) FROM messages m
+            WHERE NOT EXISTS (SELECT 1 FROM fts_messages f WHERE f.rowid = m.rowid)"#,
+            [],
+            |r| r.get(0),
+        )?;
+        Ok(FtsHealth {
+            stray_rows: stray_rows as usize,
+            missing_rows: missing_rows as usize,
+        })
     }
 
-    pub fn search_documents_lexical(
-        &self,
-        query: &str,
-        limit: i64,
-    ) -> anyhow::Result<Vec<DocumentSearchRow>> {
-        debug!(query, limit, "docs lexical search");
-        let mut stmt = self.conn.prepare(
-            "SELECT d.id, d.root_id, dr.canonical_path, d.relative_path, d.title, snippet(fts_documents, 4, '[', ']', ' … ', 16), bm25(fts_documents) AS rank FROM fts_documents INNER JOIN documents d ON d.rowid = fts_documents.rowid INNER JOIN doc_roots dr ON dr.root_id = d.root_id WHERE fts_documents MATCH ?1 ORDER BY rank LIMIT ?2",
+    pub fn repair_fts(&mut self) -> anyhow::Result<FtsHealth> {
+        let tx = self.conn.transaction()?;
+        let stray_rows = tx.execute(
+            r#"DELETE FROM fts_messages WHERE rowid IN (
+              SELECT f.rowid FROM fts_messages f
+              WHERE NOT EXISTS (
+                SELECT 1 FROM messages m WHERE m.rowid = f.rowid AND m.id = f.message_id
+              )
+            )"#,
+            [],
         )?;
-        let rows = stmt.query_map(params![query, limit], |r| {
-            let rank: f64 = r.get(6)?;
-            Ok(DocumentSearchRow {
-                document_id: r.get(0)?,
-                root_id: r.get(1)?,
-                root_path: r.get(2)?,
-                relative_path: r.get(3)?,
-                title: r.get(4)?,
-                snippet: r.get::<_, Option<String>>(5)?.unwrap_or_default(),
-                score: -rank,
-            })
-        })?;
-        rows.collect::<rusqlite::Result<Vec<_>>>()
-            .map_err(Into::into)
+        let missing_rows = index_missing_fts(&tx)?;
+        tx.commit()?;
+        debug!(stray_rows, missing_rows, "repaired message fts index");
+        Ok(FtsHealth {
+            stray_rows,
+            missing_rows,
+        })
     }
 
-    pub fn search_documents_substring(
-        &self,
-        query: &str,
-        limit: i64,
-    ) -> anyhow::Result<Vec<DocumentSearchRow>> {
-        debug!(query, limit, "docs substring search");
-        let pattern = format!("%{}%", escape_like_pattern(&query.to_lowercase()));
-        let mut stmt = self.conn.prepare(
-            "SELECT d.id, d.root_id, dr.canonical_path, d.relative_path, d.title, f.content FROM documents d INNER JOIN doc_roots dr ON dr.root_id = d.root_id INNER JOIN fts_documents f ON f.rowid = d.rowid WHERE lower(f.content) LIKE ?1 ESCAPE '\\' ORDER BY d.indexed_at DESC, d.relative_path ASC LIMIT ?2",
+    pub fn orphan_rows(&self) -> anyhow::Result<OrphanRows> {
+        let count = |from: &str| -> anyhow::Result<usize> {
+            let n: i64 = self
+                .conn
+                .query_row(&format!("SELECT COUNT(*) {from}"), [], |r| r.get(0))?;
+            Ok(n as usize)
+        };
+        Ok(OrphanRows {
+            embeddings: count(ORPHAN_EMBEDDINGS)?,
+            provenance: count(ORPHAN_PROVENANCE)?,
+        })
+    }
+
+    pub fn repair_orphans(&mut self) -> anyhow::Result<OrphanRows> {
+        let tx = self.conn.transaction()?;
+        let embeddings = tx.execute(
+            &format!("DELETE FROM message_embeddings WHERE message_id IN (SELECT e.message_id {ORPHAN_EMBEDDINGS})"),
+            [],
         )?;
-        let rows = stmt.query_map(params![pattern, limit], |r| {
-            let content: String = r.get(5)?;
-            Ok(DocumentSearchRow {
-                document_id: r.get(0)?,
-                root_id: r.get(1)?,
-                root_path: r.get(2)?,
-                relative_path: r.get(3)?,
-                title: r.get(4)?,
-                snippet: build_document_snippet(&content, query),
-                score: 0.0,
-            })
-        })?;
-        rows.collect::<rusqlite::Result<Vec<_>>>()
+        let provenance = tx.execute(
+            &format!("DELETE FROM provenance WHERE id IN (SELECT p.id {ORPHAN_PROVENANCE})"),
+            [],
+        )?;
+        tx.commit()?;
+        debug!(embeddings, provenance, "removed orphan rows");
+        Ok(OrphanRows {
+            embeddings,
+            provenance,
+        })
+    }
+
+    pub fn integrity_check(&self) -> anyhow::Result<String> {
+        self.conn
+            .query_row("PRAGMA integrity_check;", [], |r| r.get(0))
             .map_err(Into::into)
     }
+}
 
-    pub fn list_sessions(&self) -> anyhow::Result<Vec<Session>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, agent, source_ref, title, created_at, updated_at FROM sessions ORDER BY updated_at DESC",
-        )?;
-        let rows = stmt.query_map([], |r| {
+fn select_session_messages(conn: &Connection, session_id: &str) -> anyhow::Result<Vec<Message>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, session_id, role, content, ts, content_encoding FROM messages WHERE session_id = ?1 ORDER BY ts ASC, rowid ASC",
+    )?;
+    let rows = stmt.query_map(params![session_id], |r| {
+        Ok(Message {
+            id: r.get(0)?,
+            session_id: r.get(1)?,
+            role: r.get(2)?,
+            content: read_content(r, 3, 5)?,
+            ts: parse_ts(r.get(4)?),
+        })
+    })?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(Into::into)
+}
+
+fn select_tail_messages(
+    conn: &Connection,
+    clause: &str,
+    params: impl rusqlite::Params,
+) -> anyhow::Result<Vec<(i64, Message)>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT m.rowid, m.id, m.session_id, m.role, m.content, m.ts, m.content_encoding FROM messages m INNER JOIN sessions s ON s.id = m.session_id WHERE {clause}"
+    ))?;
+    let rows = stmt.query_map(params, |r| {
+        Ok((
+            r.get(0)?,
+            Message {
+                id: r.get(1)?,
+                session_id: r.get(2)?,
+                role: r.get(3)?,
+                content: read_content(r, 4, 6)?,
+                ts: parse_ts(r.get(5)?),
+            },
+        ))
+    })?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(Into::into)
+}
+
+fn select_session_events(
+    conn: &Connection,
+    session_id: &str,
+) -> anyhow::Result<Vec<core_model::Event>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, session_id, kind, payload, ts FROM events WHERE session_id = ?1 ORDER BY ts ASC",
+    )?;
+    let rows = stmt.query_map(params![session_id], |r| {
+        Ok(core_model::Event {
+            id: r.get(0)?,
+            session_id: r.get(1)?,
+            kind: r.get(2)?,
+            payload: serde_json::from_str(&r.get::<_, String>(3)?).unwrap_or_default(),
+            ts: parse_ts(r.get(4)?),
+        })
+    })?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(Into::into)
+}
+
+fn select_session_artifacts(
+    conn: &Connection,
+    session_id: &str,
+) -> anyhow::Result<Vec<core_model::Artifact>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, session_id, path, checksum, metadata FROM artifacts WHERE session_id = ?1",
+    )?;
+    let rows = stmt.query_map(params![session_id], |r| {
+        Ok(core_model::Artifact {
+            id: r.get(0)?,
+            session_id: r.get(1)?,
+            path: r.get(2)?,
+            checksum: r.get(3)?,
+            metadata: serde_json::from_str(&r.get::<_, String>(4)?).unwrap_or_default(),
+        })
+    })?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(Into::into)
+}
+
+// Plain SQLite ignores `PRAGMA key`, so a key is refused outright unless the
+// SQLCipher build is in use rather than silently writing plaintext.
+pub fn open_connection(path: &Path, key: Option<&str>) -> anyhow::Result<Connection> {
+    let conn =
+        Connection::open(path).with_context(|| format!("opening sqlite db {}", path.display()))?;
+    let Some(key) = key else {
+        return Ok(conn);
+    };
+    if !SQLCIPHER {
+        anyhow::bail!("database encryption needs a build with the `sqlcipher` feature");
+    }
+    conn.pragma_update(None, "key", key)?;
+    conn.query_row("SELECT count(*) FROM sqlite_master", [], |r| r.get::<_, i64>(0))
+        .with_context(|| {
+            format!(
+                "cannot read {} with the configured key; the key is wrong or the database is not encrypted",
+                path.display()
+            )
+        })?;
+    Ok(conn)
+}
+
+fn select_session(conn: &Connection, session_id: &str) -> anyhow::Result<Option<Session>> {
+    conn.query_row(
+        "SELECT id, agent, source_ref, title, created_at, updated_at FROM sessions WHERE id = ?1",
+        params![session_id],
+        |r| {
             let agent_str: String = r.get(1)?;
             Ok(Session {
                 id: r.get(0)?,
@@ -782,398 +3608,1002 @@ impl SqliteStore {
                 created_at: parse_ts(r.get(4)?),
                 updated_at: parse_ts(r.get(5)?),
             })
-        })?;
-        rows.collect::<rusqlite::Result<Vec<_>>>()
-            .map_err(Into::into)
+        },
+    )
+    .optional()
+    .map_err(Into::into)
+}
+
+fn select_session_meta(
+    conn: &Connection,
+    session_id: &str,
+) -> anyhow::Result<Option<serde_json::Value>> {
+    let raw: Option<Option<String>> = conn
+        .query_row(
+            "SELECT session_meta FROM sessions WHERE id = ?1",
+            params![session_id],
+            |r| r.get(0),
+        )
+        .optional()?;
+    Ok(raw
+        .flatten()
+        .and_then(|raw| serde_json::from_str(&raw).ok()))
+}
+
+// Incremental syncs only see newly appended entries, so list-valued keys are
+// extended rather than replaced.
+// Manual splits and merges, replayed over every batch before it is saved.
+struct SessionCorrections {
+    merges: HashMap<String, String>,
+    // Split points per session, ordered by timestamp.
+    splits: HashMap<String, Vec<(String, String)>>,
+}
+
+impl SessionCorrections {
+    fn load(conn: &Connection) -> anyhow::Result<Option<Self>> {
+        let merges: HashMap<String, String> = conn
+            .prepare_cached("SELECT from_id, into_id FROM session_merges")?
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        let mut splits: HashMap<String, Vec<(String, String)>> = HashMap::new();
+        let mut stmt = conn.prepare_cached(
+            "SELECT session_id, at_ts, new_id FROM session_splits ORDER BY at_ts ASC",
+        )?;
+        let rows = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))?;
+        for row in rows {
+            let (session_id, at_ts, new_id): (String, String, String) = row?;
+            splits.entry(session_id).or_default().push((at_ts, new_id));
+        }
+        Ok((!merges.is_empty() || !splits.is_empty()).then_some(Self { merges, splits }))
+    }
+
+    // Splits only apply to entities with a timestamp. A cycle of
+    // corrections stops at the first session seen twice.
+    fn resolve(&self, session_id: &str, ts: Option<&str>) -> String {
+        let mut id = session_id.to_string();
+        let mut seen = HashSet::new();
+        while seen.insert(id.clone()) {
+            if let Some(into) = self.merges.get(&id) {
+                id = into.clone();
+                continue;
+            }
+            let split = ts.and_then(|ts| {
+                self.splits
+                    .get(&id)
+                    .and_then(|points| points.iter().rev().find(|(at_ts, _)| at_ts.as_str() <= ts))
+            });
+            match split {
+                Some((_, new_id)) => id = new_id.clone(),
+                None => break,
+            }
+        }
+        id
+    }
+
+    fn apply(&self, batch: &NormalizedBatch) -> (NormalizedBatch, HashSet<String>) {
+        let mut out = batch.clone();
+        let mut touched = HashSet::new();
+        let mut reassign = |session_id: &mut String, ts: Option<&str>| {
+            let resolved = self.resolve(session_id, ts);
+            if resolved != *session_id {
+                touched.insert(std::mem::replace(session_id, resolved.clone()));
+                touched.insert(resolved);
+            }
+        };
+        for m in &mut out.messages {
+            let ts = m.ts.to_rfc3339();
+            reassign(&mut m.session_id, Some(&ts));
+        }
+        for e in &mut out.events {
+            let ts = e.ts.to_rfc3339();
+            reassign(&mut e.session_id, Some(&ts));
+        }
+        for a in &mut out.artifacts {
+            reassign(&mut a.session_id, None);
+        }
+        for m in &mut out.session_meta {
+            reassign(&mut m.session_id, None);
+        }
+        out.sessions.retain(|s| !self.merges.contains_key(&s.id));
+        touched.retain(|id| !self.merges.contains_key(id));
+        (out, touched)
+    }
+}
+
+fn refresh_session_bounds(conn: &Connection, session_id: &str) -> anyhow::Result<()> {
+    conn.execute(
+        r#"UPDATE sessions SET
+          created_at = COALESCE((SELECT MIN(ts) FROM messages WHERE session_id = ?1), created_at),
+          updated_at = COALESCE((SELECT MAX(ts) FROM messages WHERE session_id = ?1), updated_at)
+        WHERE id = ?1"#,
+        params![session_id],
+    )?;
+    Ok(())
+}
+
+fn merge_session_meta(
+    mut existing: serde_json::Value,
+    incoming: &serde_json::Value,
+) -> serde_json::Value {
+    let (Some(target), Some(incoming)) = (existing.as_object_mut(), incoming.as_object()) else {
+        return incoming.clone();
+    };
+    for (key, value) in incoming {
+        match (target.get_mut(key), value) {
+            (Some(serde_json::Value::Array(current)), serde_json::Value::Array(items)) => {
+                for item in items {
+                    if !current.contains(item) {
+                        current.push(item.clone());
+                    }
+                }
+            }
+            _ => {
+                target.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    existing
+}
+
+fn select_session_provenance(
+    conn: &Connection,
+    session_id: &str,
+) -> anyhow::Result<Vec<Provenance>> {
+    let mut stmt = conn.prepare(
+        "SELECT p.id, p.entity_type, p.entity_id, p.agent, p.source_path, p.source_id, p.repo_remote, p.repo_path, p.workspace FROM provenance p INNER JOIN messages m ON p.entity_id = m.id WHERE m.session_id = ?1",
+    )?;
+    let rows = stmt.query_map(params![session_id], |r| {
+        let agent_str: String = r.get(3)?;
+        let remote: Option<String> = r.get(6)?;
+        let path: Option<String> = r.get(7)?;
+        Ok(Provenance {
+            id: r.get(0)?,
+            entity_type: r.get(1)?,
+            entity_id: r.get(2)?,
+            agent: parse_agent(&agent_str)?,
+            source_path: r.get(4)?,
+            source_id: r.get(5)?,
+            workspace: r.get(8)?,
+            repo: remote.map(|remote| RepoRef {
+                remote,
+                path: path.unwrap_or_default(),
+            }),
+        })
+    })?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(Into::into)
+}
+
+fn parse_ts(ts: String) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(&ts)
+        .map(|v| v.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now())
+}
+
+fn parse_optional_ts(ts: Option<String>) -> Option<DateTime<Utc>> {
+    ts.map(parse_ts)
+}
+
+fn doc_root_id(canonical_path: &str) -> String {
+    deterministic_id(&["doc_root", canonical_path])
+}
+
+fn document_id(root_id: &str, relative_path: &str) -> String {
+    deterministic_id(&["document", root_id, relative_path])
+}
+
+fn build_document_snippet(content: &str, query: &str) -> String {
+    const MAX_CHARS: usize = 180;
+    let collapsed = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed.is_empty() {
+        return String::new();
+    }
+    if collapsed.is_ascii() && query.is_ascii() {
+        let lower = collapsed.to_lowercase();
+        let needle = query.trim().to_lowercase();
+        if !needle.is_empty()
+            && let Some(start) = lower.find(&needle)
+        {
+            let snippet_start = start.saturating_sub(60);
+            let snippet_end = (start + needle.len() + 60).min(collapsed.len());
+            let mut snippet = collapsed[snippet_start..snippet_end].to_string();
+            if snippet_start > 0 {
+                snippet = format!("…{}", snippet.trim_start());
+            }
+            if snippet_end < collapsed.len() {
+                snippet.push('…');
+            }
+            return snippet;
+        }
+    }
+    let shortened: String = collapsed.chars().take(MAX_CHARS).collect();
+    if shortened.len() == collapsed.len() {
+        shortened
+    } else {
+        format!("{shortened}…")
+    }
+}
+
+fn encode_content(content: &str) -> anyhow::Result<(SqlValue, &'static str)> {
+    if content.len() < COMPRESS_THRESHOLD_BYTES {
+        return Ok((SqlValue::Text(content.to_string()), "plain"));
+    }
+    let compressed = zstd::encode_all(content.as_bytes(), ZSTD_LEVEL)?;
+    Ok((SqlValue::Blob(compressed), "zstd"))
+}
+
+fn raw_record_from_row(r: &Row<'_>) -> rusqlite::Result<NativeRecord> {
+    let payload = read_content(r, 2, 3)?;
+    Ok(NativeRecord {
+        source_id: r.get(0)?,
+        updated_at: parse_ts(r.get(1)?),
+        payload: serde_json::from_str(&payload).map_err(|err| {
+            rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(err))
+        })?,
+    })
+}
+
+fn encode_raw_payload(payload: &serde_json::Value) -> anyhow::Result<Vec<u8>> {
+    Ok(zstd::encode_all(
+        serde_json::to_vec(payload)?.as_slice(),
+        ZSTD_LEVEL,
+    )?)
+}
+
+fn read_content(
+    row: &Row<'_>,
+    content_idx: usize,
+    encoding_idx: usize,
+) -> rusqlite::Result<String> {
+    let encoding: String = row.get(encoding_idx)?;
+    if encoding != "zstd" {
+        return row.get(content_idx);
+    }
+    let blob: Vec<u8> = row.get(content_idx)?;
+    zstd::decode_all(blob.as_slice())
+        .and_then(|bytes| {
+            String::from_utf8(bytes)
+                .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+        })
+        .map_err(|err| {
+            rusqlite::Error::FromSqlConversionFailure(
+                content_idx,
+                rusqlite::types::Type::Blob,
+                Box::new(err),
+            )
+        })
+}
+
+const FTS_INSERT: &str =
+    "INSERT INTO fts_messages (rowid, message_id, session_id, content, reasoning, ts)
+    VALUES (?1, ?2, ?3, ?4, ?5, ?6)";
+
+// Indexes every message that has no FTS row yet, returning how many it added.
+fn index_missing_fts(conn: &Connection) -> anyhow::Result<usize> {
+    let missing: Vec<(i64, String, String, String, String)> = {
+        let mut stmt = conn.prepare(
+            r#"SELECT m.rowid, m.id, m.session_id, m.content, m.content_encoding, m.ts
+            FROM messages m
+            WHERE NOT EXISTS (SELECT 1 FROM fts_messages f WHERE f.rowid = m.rowid)"#,
+        )?;
+        stmt.query_map([], |r| {
+            Ok((
+                r.get(0)?,
+                r.get(1)?,
+                r.get(2)?,
+                read_content(r, 3, 4)?,
+                r.get(5)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+    };
+    let mut stmt_insert = conn.prepare_cached(FTS_INSERT)?;
+    for (rowid, id, session_id, content, ts) in &missing {
+        let (reasoning, rest) = split_reasoning(searchable_content(content));
+        stmt_insert.execute(params![rowid, id, session_id, rest, reasoning, ts])?;
+    }
+    Ok(missing.len())
+}
+
+fn searchable_content(content: &str) -> &str {
+    if content.len() <= FTS_CONTENT_LIMIT_BYTES {
+        return content;
+    }
+    let mut end = FTS_CONTENT_LIMIT_BYTES;
+    while !content.is_char_boundary(end) {
+        end -= 1;
+    }
+    &content[..end]
+}
+
+fn parse_agent(s: &str) -> rusqlite::Result<AgentKind> {
+    s.parse::<AgentKind>().map_err(|err| {
+        rusqlite::Error::FromSqlConversionFailure(
+            0,
+            rusqlite::types::Type::Text,
+            Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, err)),
+        )
+    })
+}
+
+fn escape_like_pattern(query: &str) -> String {
+    let mut escaped = String::with_capacity(query.len());
+    for ch in query.to_lowercase().chars() {
+        if matches!(ch, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_and_integrity() {
+        let mut store = SqliteStore::open(":memory:").expect("open");
+        store.init_schema().expect("schema");
+        let check = store.integrity_check().expect("integrity");
+        assert_eq!(check, "ok");
+
+        let batch = NormalizedBatch::default();
+        store.save_batch(&batch).expect("empty batch is fine");
+    }
+
+    fn make_batch(
+        agent: AgentKind,
+        session_id: &str,
+        msg_id: &str,
+        content: &str,
+    ) -> NormalizedBatch {
+        let now = Utc::now();
+        NormalizedBatch {
+            sessions: vec![Session {
+                id: session_id.to_string(),
+                agent,
+                source_ref: "test-ref".to_string(),
+                title: "test session".to_string(),
+                created_at: now,
+                updated_at: now,
+            }],
+            messages: vec![Message {
+                id: msg_id.to_string(),
+                session_id: session_id.to_string(),
+                role: "user".to_string(),
+                content: content.to_string(),
+                ts: now,
+            }],
+            events: vec![],
+            artifacts: vec![],
+            provenance: vec![Provenance {
+                id: format!("prov_{}", msg_id),
+                entity_type: "message".to_string(),
+                entity_id: msg_id.to_string(),
+                agent,
+                source_path: "/test/path".to_string(),
+                source_id: "src-1".to_string(),
+                workspace: None,
+                repo: None,
+            }],
+            session_meta: vec![],
+        }
+    }
+
+    #[cfg(not(feature = "sqlcipher"))]
+    #[test]
+    fn keys_are_refused_without_sqlcipher() {
+        let err = SqliteStore::open_with_key(":memory:", Some("secret"))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("sqlcipher"));
+    }
+
+    #[cfg(feature = "sqlcipher")]
+    #[test]
+    fn encrypted_store_needs_its_key() {
+        let path = std::env::temp_dir().join(format!("remi-sqlcipher-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        {
+            let mut store = SqliteStore::open_with_key(&path, Some("right")).unwrap();
+            store.init_schema().unwrap();
+            store
+                .save_batch(&make_batch(AgentKind::Pi, "s1", "m1", "secret content"))
+                .unwrap();
+        }
+        assert!(
+            !std::fs::read(&path)
+                .unwrap()
+                .starts_with(b"SQLite format 3")
+        );
+        assert!(SqliteStore::open_with_key(&path, Some("wrong")).is_err());
+        assert!(
+            SqliteStore::open(&path)
+                .and_then(|store| store.init_schema())
+                .is_err()
+        );
+        let store = SqliteStore::open_with_key(&path, Some("right")).unwrap();
+        assert_eq!(
+            store.get_message("m1").unwrap().unwrap().content,
+            "secret content"
+        );
+        let _ = std::fs::remove_file(&path);
     }
 
-    pub fn get_session_messages(&self, session_id: &str) -> anyhow::Result<Vec<Message>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, session_id, role, content, ts FROM messages WHERE session_id = ?1 ORDER BY ts ASC, rowid ASC",
-        )?;
-        let rows = stmt.query_map(params![session_id], |r| {
-            Ok(Message {
-                id: r.get(0)?,
-                session_id: r.get(1)?,
-                role: r.get(2)?,
-                content: r.get(3)?,
-                ts: parse_ts(r.get(4)?),
-            })
-        })?;
-        rows.collect::<rusqlite::Result<Vec<_>>>()
-            .map_err(Into::into)
-    }
+    #[test]
+    fn bookmarks_filter_by_tag_and_follow_messages() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        store
+            .save_batch(&make_batch(AgentKind::Pi, "s1", "m1", "first"))
+            .unwrap();
+        store
+            .save_batch(&make_batch(AgentKind::Pi, "s2", "m2", "second"))
+            .unwrap();
+        store
+            .add_bookmark("m1", &["learning".to_string(), "rust".to_string()])
+            .unwrap();
+        store.add_bookmark("m2", &[]).unwrap();
+        store.add_bookmark("m2", &["learning".to_string()]).unwrap();
 
-    pub fn get_session_events(&self, session_id: &str) -> anyhow::Result<Vec<core_model::Event>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, session_id, kind, payload, ts FROM events WHERE session_id = ?1 ORDER BY ts ASC",
-        )?;
-        let rows = stmt.query_map(params![session_id], |r| {
-            Ok(core_model::Event {
-                id: r.get(0)?,
-                session_id: r.get(1)?,
-                kind: r.get(2)?,
-                payload: serde_json::from_str(&r.get::<_, String>(3)?).unwrap_or_default(),
-                ts: parse_ts(r.get(4)?),
-            })
-        })?;
-        rows.collect::<rusqlite::Result<Vec<_>>>()
-            .map_err(Into::into)
+        let all = store.bookmarks(None).unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].message.content, "first");
+        assert_eq!(all[0].tags, vec!["learning", "rust"]);
+        let rust = store.bookmarks(Some("rust")).unwrap();
+        assert_eq!(rust.len(), 1);
+        assert_eq!(rust[0].message.id, "m1");
+        assert_eq!(store.bookmarks(Some("learning")).unwrap().len(), 2);
+
+        assert!(store.remove_bookmark("m1").unwrap());
+        assert!(!store.remove_bookmark("m1").unwrap());
+        assert!(store.bookmarks(Some("rust")).unwrap().is_empty());
+        store.delete_session_cascade("s2").unwrap();
+        assert!(store.bookmarks(None).unwrap().is_empty());
     }
 
-    pub fn get_session_artifacts(
-        &self,
-        session_id: &str,
-    ) -> anyhow::Result<Vec<core_model::Artifact>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, session_id, path, checksum, metadata FROM artifacts WHERE session_id = ?1",
-        )?;
-        let rows = stmt.query_map(params![session_id], |r| {
-            Ok(core_model::Artifact {
-                id: r.get(0)?,
-                session_id: r.get(1)?,
-                path: r.get(2)?,
-                checksum: r.get(3)?,
-                metadata: serde_json::from_str(&r.get::<_, String>(4)?).unwrap_or_default(),
-            })
-        })?;
-        rows.collect::<rusqlite::Result<Vec<_>>>()
-            .map_err(Into::into)
+    #[test]
+    fn messages_per_day_counts_by_agent() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let day = |d: u32| NaiveDate::from_ymd_opt(2025, 3, d).unwrap();
+        for (agent, session, id, d) in [
+            (AgentKind::Pi, "s1", "m1", 4),
+            (AgentKind::Pi, "s1", "m2", 4),
+            (AgentKind::Claude, "s2", "m3", 4),
+            (AgentKind::Claude, "s2", "m4", 9),
+        ] {
+            let mut batch = make_batch(agent, session, id, id);
+            batch.messages[0].ts = day(d).and_hms_opt(23, 30, 0).unwrap().and_utc();
+            store.save_batch(&batch).unwrap();
+        }
+        assert_eq!(
+            store.messages_per_day(None, day(1), day(31)).unwrap(),
+            BTreeMap::from([(day(4), 3), (day(9), 1)])
+        );
+        assert_eq!(
+            store
+                .messages_per_day(Some(AgentKind::Pi), day(1), day(31))
+                .unwrap(),
+            BTreeMap::from([(day(4), 2)])
+        );
+        assert!(
+            store
+                .messages_per_day(None, day(5), day(8))
+                .unwrap()
+                .is_empty()
+        );
+        let midnight = |d: u32| day(d).and_hms_opt(0, 0, 0).unwrap().and_utc();
+        let active: Vec<String> = store
+            .sessions_active_between(midnight(4), midnight(5))
+            .unwrap()
+            .into_iter()
+            .map(|s| s.id)
+            .collect();
+        assert_eq!(active, ["s1", "s2"]);
+        assert_eq!(
+            store
+                .sessions_active_between(midnight(9), midnight(10))
+                .unwrap()[0]
+                .id,
+            "s2"
+        );
+        // The messages sit at 23:30 UTC. One hour ahead of UTC that is the
+        // next day, which starts at 23:00 UTC.
+        let shifted = |d: u32| midnight(d) - chrono::Duration::hours(1);
+        assert!(
+            store
+                .sessions_active_between(shifted(4), shifted(5))
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            store
+                .sessions_active_between(shifted(5), shifted(6))
+                .unwrap()
+                .len(),
+            2
+        );
     }
 
     #[cfg(feature = "semantic")]
-    pub fn get_message(&self, message_id: &str) -> anyhow::Result<Option<Message>> {
-        self.conn
-            .query_row(
-                "SELECT id, session_id, role, content, ts FROM messages WHERE id = ?1",
-                params![message_id],
-                |r| {
-                    Ok(Message {
-                        id: r.get(0)?,
-                        session_id: r.get(1)?,
-                        role: r.get(2)?,
-                        content: r.get(3)?,
-                        ts: parse_ts(r.get(4)?),
-                    })
-                },
-            )
-            .optional()
-            .map_err(Into::into)
-    }
+    #[test]
+    fn embedding_queue_roundtrip() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        store
+            .save_batch(&make_batch(AgentKind::Pi, "s1", "m1", "queued"))
+            .unwrap();
+        store.enqueue_embeddings(&["m1".to_string()]).unwrap();
+        assert_eq!(store.embedding_queue_len().unwrap(), 1);
 
-    pub fn get_session(&self, session_id: &str) -> anyhow::Result<Option<Session>> {
-        self.conn
-            .query_row(
-                "SELECT id, agent, source_ref, title, created_at, updated_at FROM sessions WHERE id = ?1",
-                params![session_id],
-                |r| {
-                    let agent_str: String = r.get(1)?;
-                    Ok(Session {
-                        id: r.get(0)?,
-                        agent: parse_agent(&agent_str)?,
-                        source_ref: r.get(2)?,
-                        title: r.get(3)?,
-                        created_at: parse_ts(r.get(4)?),
-                        updated_at: parse_ts(r.get(5)?),
-                    })
-                },
-            )
-            .optional()
-            .map_err(Into::into)
+        let jobs = store.pending_embedding_jobs(10, 3).unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].content, "queued");
+
+        store.fail_embedding_job("m1").unwrap();
+        assert!(store.pending_embedding_jobs(10, 1).unwrap().is_empty());
+        assert_eq!(store.failed_embedding_jobs(1).unwrap(), 1);
+        assert_eq!(store.failed_embedding_jobs(2).unwrap(), 0);
+
+        store.complete_embedding_job("m1").unwrap();
+        assert_eq!(store.embedding_queue_len().unwrap(), 0);
     }
 
-    pub fn get_provenance_for_session(&self, session_id: &str) -> anyhow::Result<Vec<Provenance>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT p.id, p.entity_type, p.entity_id, p.agent, p.source_path, p.source_id FROM provenance p INNER JOIN messages m ON p.entity_id = m.id WHERE m.session_id = ?1",
-        )?;
-        let rows = stmt.query_map(params![session_id], |r| {
-            let agent_str: String = r.get(3)?;
-            Ok(Provenance {
-                id: r.get(0)?,
-                entity_type: r.get(1)?,
-                entity_id: r.get(2)?,
-                agent: parse_agent(&agent_str)?,
-                source_path: r.get(4)?,
-                source_id: r.get(5)?,
-            })
-        })?;
-        rows.collect::<rusqlite::Result<Vec<_>>>()
-            .map_err(Into::into)
+    #[cfg(feature = "semantic")]
+    #[test]
+    fn session_embeddings_average_message_vectors() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let mut batch = make_batch(AgentKind::Pi, "s1", "m1", "one");
+        batch
+            .messages
+            .extend(make_batch(AgentKind::Pi, "s1", "m2", "two").messages);
+        store.save_batch(&batch).unwrap();
+        store
+            .save_batch(&make_batch(AgentKind::Pi, "s2", "m3", "three"))
+            .unwrap();
+        store.save_embedding("m1", "onnx", &[1.0, 0.0]).unwrap();
+        store.save_embedding("m2", "onnx", &[0.0, 1.0]).unwrap();
+        store.save_embedding("m3", "onnx", &[2.0, 2.0]).unwrap();
+
+        assert_eq!(
+            store.load_session_embeddings("onnx").unwrap(),
+            vec![
+                ("s1".to_string(), vec![0.5, 0.5]),
+                ("s2".to_string(), vec![2.0, 2.0])
+            ]
+        );
+
+        store.save_embedding("m2", "native", &[9.0, 9.0]).unwrap();
+        assert_eq!(
+            store.load_session_embeddings("onnx").unwrap(),
+            vec![
+                ("s1".to_string(), vec![1.0, 0.0]),
+                ("s2".to_string(), vec![2.0, 2.0])
+            ]
+        );
     }
 
-    pub fn search_lexical(&self, query: &str, limit: i64) -> anyhow::Result<Vec<SearchRow>> {
-        debug!(query, limit, "lexical search");
-        let mut stmt = self.conn.prepare(
-            "SELECT message_id, session_id, content, ts, bm25(fts_messages) AS rank FROM fts_messages WHERE fts_messages MATCH ?1 ORDER BY rank LIMIT ?2",
-        )?;
-        let rows = stmt.query_map(params![query, limit], |r| {
-            let rank: f64 = r.get(4)?;
-            Ok(SearchRow {
-                message_id: r.get(0)?,
-                session_id: r.get(1)?,
-                content: r.get(2)?,
-                ts: parse_ts(r.get(3)?),
-                score: -rank,
-            })
-        })?;
-        rows.collect::<rusqlite::Result<Vec<_>>>()
-            .map_err(Into::into)
+    #[cfg(feature = "semantic")]
+    #[test]
+    fn embeddings_from_another_model_are_hidden_and_requeued() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let mut batch = make_batch(AgentKind::Pi, "s1", "m1", "one");
+        batch
+            .messages
+            .extend(make_batch(AgentKind::Pi, "s1", "m2", "two").messages);
+        store.save_batch(&batch).unwrap();
+        store.save_embedding("m1", "onnx", &[1.0]).unwrap();
+        store.save_embedding("m2", "native", &[2.0]).unwrap();
+
+        assert_eq!(
+            store.load_all_embeddings("native").unwrap(),
+            vec![("m2".to_string(), vec![2.0])]
+        );
+        assert_eq!(store.embedding_stats("native").unwrap().len, 1);
+        assert_eq!(
+            store.embedding_models().unwrap(),
+            vec![("native".to_string(), 1), ("onnx".to_string(), 1)]
+        );
+
+        assert_eq!(store.requeue_stale_embeddings("native").unwrap(), 1);
+        let jobs = store.pending_embedding_jobs(10, 3).unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].message_id, "m1");
+        assert_eq!(store.requeue_stale_embeddings("native").unwrap(), 0);
     }
 
-    pub fn recent_messages(&self, limit: i64) -> anyhow::Result<Vec<SearchRow>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT m.id, m.session_id, m.content, m.ts FROM messages m ORDER BY m.ts DESC LIMIT ?1",
-        )?;
-        let rows = stmt.query_map(params![limit], |r| {
-            Ok(SearchRow {
-                message_id: r.get(0)?,
-                session_id: r.get(1)?,
-                content: r.get(2)?,
-                ts: parse_ts(r.get(3)?),
-                score: 0.0,
-            })
-        })?;
-        rows.collect::<rusqlite::Result<Vec<_>>>()
-            .map_err(Into::into)
+    #[cfg(feature = "semantic")]
+    #[test]
+    fn query_embeddings_evict_least_recently_used() {
+        let store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        store
+            .save_query_embedding("a", "v1", &[1.0, 2.0], 2)
+            .unwrap();
+        store.save_query_embedding("b", "v1", &[3.0], 2).unwrap();
+        assert_eq!(
+            store.get_query_embedding("a", "v1").unwrap(),
+            Some(vec![1.0, 2.0])
+        );
+        assert_eq!(store.get_query_embedding("a", "v2").unwrap(), None);
+
+        store.save_query_embedding("c", "v1", &[4.0], 2).unwrap();
+        assert!(store.get_query_embedding("a", "v1").unwrap().is_some());
+        assert_eq!(store.get_query_embedding("b", "v1").unwrap(), None);
+        assert_eq!(
+            store.get_query_embedding("c", "v1").unwrap(),
+            Some(vec![4.0])
+        );
     }
 
-    pub fn search_substring(&self, query: &str, limit: i64) -> anyhow::Result<Vec<SearchRow>> {
-        debug!(query, limit, "substring search");
-        let pattern = format!("%{}%", escape_like_pattern(&query.to_lowercase()));
-        let mut stmt = self.conn.prepare(
-            "SELECT m.id, m.session_id, m.content, m.ts FROM messages m WHERE lower(m.content) LIKE ?1 ESCAPE '\\' ORDER BY m.ts DESC LIMIT ?2",
-        )?;
-        let rows = stmt.query_map(params![pattern, limit], |r| {
-            Ok(SearchRow {
-                message_id: r.get(0)?,
-                session_id: r.get(1)?,
-                content: r.get(2)?,
-                ts: parse_ts(r.get(3)?),
-                score: 0.0,
-            })
-        })?;
-        rows.collect::<rusqlite::Result<Vec<_>>>()
-            .map_err(Into::into)
+    #[test]
+    fn migration_normalizes_message_roles() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        for (id, role) in [("m1", "Human"), ("m2", "toolResult"), ("m3", "developer")] {
+            let mut batch = make_batch(AgentKind::Pi, "s1", id, id);
+            batch.messages[0].role = role.to_string();
+            store.save_batch(&batch).unwrap();
+        }
+        store
+            .conn
+            .execute_batch("PRAGMA user_version = 7;")
+            .unwrap();
+        store.init_schema().unwrap();
+
+        let mut roles: Vec<(String, String)> = store
+            .get_session_messages("s1")
+            .unwrap()
+            .into_iter()
+            .map(|m| (m.id, m.role))
+            .collect();
+        roles.sort();
+        assert_eq!(
+            roles,
+            vec![
+                ("m1".to_string(), "user".to_string()),
+                ("m2".to_string(), "tool".to_string()),
+                ("m3".to_string(), "system".to_string()),
+            ]
+        );
     }
 
-    pub fn plan_archive(
-        &self,
-        older_than: Duration,
-        keep_latest: usize,
-    ) -> anyhow::Result<ArchiveRun> {
-        let now = Utc::now();
-        let run_id = deterministic_id(&[
-            "archive_run",
-            &now.timestamp_nanos_opt().unwrap_or_default().to_string(),
-            &older_than.num_seconds().to_string(),
-            &keep_latest.to_string(),
-        ]);
-        let cutoff = now - older_than;
-        self.conn.execute(
-            "INSERT INTO archive_runs (id, created_at, older_than_secs, keep_latest, dry_run, executed) VALUES (?1, ?2, ?3, ?4, 1, 0)",
-            params![run_id, now.to_rfc3339(), older_than.num_seconds(), keep_latest as i64],
-        )?;
+    #[test]
+    fn message_languages_track_saves_and_backfill() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        store
+            .save_batch(&make_batch(
+                AgentKind::Pi,
+                "s1",
+                "m1",
+                "retry logic\n```rust\nloop {}\n```",
+            ))
+            .unwrap();
+        store
+            .save_batch(&make_batch(AgentKind::Pi, "s2", "m2", "```py\npass\n```"))
+            .unwrap();
+        assert_eq!(store.message_languages("m1").unwrap(), vec!["rust"]);
+        assert_eq!(
+            store.sessions_with_language("rust").unwrap(),
+            HashSet::from(["s1".to_string()])
+        );
 
-        let sessions = self.list_sessions()?;
-        let mut by_agent: std::collections::HashMap<&str, Vec<Session>> =
-            std::collections::HashMap::new();
-        for s in sessions {
-            by_agent.entry(s.agent.as_str()).or_default().push(s);
-        }
-        for grouped in by_agent.values_mut() {
-            grouped.sort_by_key(|session| std::cmp::Reverse(session.updated_at));
-            for s in grouped.iter().skip(keep_latest) {
-                if s.updated_at < cutoff {
-                    let already_planned: bool = self.conn.query_row(
-                        "SELECT EXISTS(SELECT 1 FROM archive_items ai JOIN archive_runs ar ON ai.run_id = ar.id WHERE ai.session_id = ?1 AND ar.executed = 0)",
-                        params![s.id],
-                        |r| r.get(0),
-                    )?;
-                    if already_planned {
-                        continue;
-                    }
-                    let item_id = deterministic_id(&[&run_id, &s.id]);
-                    self.conn.execute(
-                        "INSERT INTO archive_items (id, run_id, session_id, planned_delete) VALUES (?1, ?2, ?3, 1)",
-                        params![item_id, run_id, s.id],
-                    )?;
-                }
-            }
-        }
+        store
+            .save_batch(&make_batch(AgentKind::Pi, "s1", "m1", "no code anymore"))
+            .unwrap();
+        assert!(store.message_languages("m1").unwrap().is_empty());
 
-        Ok(ArchiveRun {
-            id: run_id,
-            created_at: now,
-            older_than_secs: older_than.num_seconds(),
-            keep_latest: keep_latest as i64,
-            dry_run: true,
-            executed: false,
-        })
+        store
+            .conn
+            .execute_batch("DROP TABLE message_languages; PRAGMA user_version = 8;")
+            .unwrap();
+        store.init_schema().unwrap();
+        assert_eq!(store.message_languages("m2").unwrap(), vec!["python"]);
+        assert!(store.sessions_with_language("rust").unwrap().is_empty());
     }
 
-    pub fn archive_items_for_run(&self, run_id: &str) -> anyhow::Result<Vec<ArchiveItem>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, run_id, session_id, planned_delete FROM archive_items WHERE run_id = ?1",
-        )?;
-        let rows = stmt.query_map(params![run_id], |r| {
-            Ok(ArchiveItem {
-                id: r.get(0)?,
-                run_id: r.get(1)?,
-                session_id: r.get(2)?,
-                planned_delete: r.get::<_, i64>(3)? == 1,
-            })
-        })?;
-        rows.collect::<rusqlite::Result<Vec<_>>>()
-            .map_err(Into::into)
-    }
+    #[test]
+    fn repair_fts_removes_duplicate_rows_and_reindexes_missing() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        store
+            .save_batch(&make_batch(AgentKind::Pi, "s1", "m1", "duplicated needle"))
+            .unwrap();
+        store
+            .save_batch(&make_batch(AgentKind::Pi, "s1", "m2", "missing needle"))
+            .unwrap();
+        assert_eq!(store.fts_health().unwrap(), FtsHealth::default());
 
-    pub fn mark_archive_executed(&self, run_id: &str, dry_run: bool) -> anyhow::Result<()> {
-        self.conn.execute(
-            "UPDATE archive_runs SET dry_run = ?2, executed = 1 WHERE id = ?1",
-            params![run_id, if dry_run { 1 } else { 0 }],
-        )?;
-        Ok(())
-    }
+        store
+            .conn
+            .execute_batch(
+                r#"INSERT INTO fts_messages (message_id, session_id, content, ts)
+                SELECT message_id, session_id, content, ts FROM fts_messages WHERE message_id = 'm1';
+                DELETE FROM fts_messages WHERE message_id = 'm2';"#,
+            )
+            .unwrap();
+        // Search joins FTS rows to their message, so the stray copy never
+        // surfaces as a second hit.
+        assert_eq!(
+            store
+                .search_lexical("duplicated", 10, &SearchScope::default())
+                .unwrap()
+                .len(),
+            1
+        );
+        let broken = FtsHealth {
+            stray_rows: 1,
+            missing_rows: 1,
+        };
+        assert_eq!(store.fts_health().unwrap(), broken);
 
-    pub fn delete_session_cascade(&self, session_id: &str) -> anyhow::Result<()> {
-        debug!(session_id, "cascading delete session");
-        self.conn
-            .execute("DELETE FROM sessions WHERE id = ?1", params![session_id])?;
-        self.conn.execute(
-            "DELETE FROM fts_messages WHERE session_id = ?1",
-            params![session_id],
-        )?;
-        Ok(())
+        assert_eq!(store.repair_fts().unwrap(), broken);
+        assert_eq!(store.fts_health().unwrap(), FtsHealth::default());
+        assert_eq!(
+            store
+                .search_lexical("duplicated", 10, &SearchScope::default())
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            store
+                .search_lexical("missing", 10, &SearchScope::default())
+                .unwrap()
+                .len(),
+            1
+        );
     }
 
-    pub fn integrity_check(&self) -> anyhow::Result<String> {
-        self.conn
-            .query_row("PRAGMA integrity_check;", [], |r| r.get(0))
-            .map_err(Into::into)
+    #[test]
+    fn reingesting_keeps_one_fts_row_per_message() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let fts_rows = |store: &SqliteStore| -> Vec<(String, i64)> {
+            let mut stmt = store
+                .conn
+                .prepare(
+                    "SELECT message_id, COUNT(*) FROM fts_messages GROUP BY message_id ORDER BY message_id",
+                )
+                .unwrap();
+            stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+                .unwrap()
+                .collect::<rusqlite::Result<_>>()
+                .unwrap()
+        };
+        let batch = make_batch(AgentKind::Pi, "s1", "m1", "needle in the index");
+        store.save_batch(&batch).unwrap();
+        store.save_batch(&batch).unwrap();
+        assert_eq!(fts_rows(&store), vec![("m1".to_string(), 1)]);
+
+        // A session deleted outside the store API cascades to its messages;
+        // the message comes back under a new rowid on the next ingest.
+        store
+            .conn
+            .execute("DELETE FROM sessions WHERE id = 's1'", [])
+            .unwrap();
+        assert!(fts_rows(&store).is_empty());
+        store.save_batch(&batch).unwrap();
+        store.save_batch(&batch).unwrap();
+        assert_eq!(fts_rows(&store), vec![("m1".to_string(), 1)]);
+        assert_eq!(
+            store
+                .search_lexical("needle", 10, &SearchScope::default())
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(store.fts_health().unwrap(), FtsHealth::default());
     }
-}
 
-fn parse_ts(ts: String) -> DateTime<Utc> {
-    DateTime::parse_from_rfc3339(&ts)
-        .map(|v| v.with_timezone(&Utc))
-        .unwrap_or_else(|_| Utc::now())
-}
+    #[test]
+    fn orphan_embeddings_and_provenance_are_found_and_removed() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        store
+            .save_batch(&make_batch(AgentKind::Pi, "s1", "m1", "kept"))
+            .unwrap();
+        store
+            .save_batch(&make_batch(AgentKind::Pi, "s2", "m2", "dropped"))
+            .unwrap();
+        assert_eq!(store.orphan_rows().unwrap(), OrphanRows::default());
 
-fn parse_optional_ts(ts: Option<String>) -> Option<DateTime<Utc>> {
-    ts.map(parse_ts)
-}
+        // Deleting a session takes its provenance along.
+        store.delete_session_cascade("s2").unwrap();
+        assert_eq!(store.orphan_rows().unwrap(), OrphanRows::default());
 
-fn doc_root_id(canonical_path: &str) -> String {
-    deterministic_id(&["doc_root", canonical_path])
-}
+        store
+            .conn
+            .execute_batch(
+                r#"PRAGMA foreign_keys = OFF;
+                INSERT INTO message_embeddings (message_id, dim, vec) VALUES ('gone', 1, x'00000000');
+                INSERT INTO provenance (id, entity_type, entity_id, agent, source_path, source_id)
+                VALUES ('p_gone', 'message', 'gone', 'pi', '/tmp', 'gone');
+                PRAGMA foreign_keys = ON;"#,
+            )
+            .unwrap();
+        let broken = OrphanRows {
+            embeddings: 1,
+            provenance: 1,
+        };
+        assert_eq!(store.orphan_rows().unwrap(), broken);
+        assert_eq!(store.repair_orphans().unwrap(), broken);
+        assert_eq!(store.orphan_rows().unwrap(), OrphanRows::default());
+        assert!(store.get_message("m1").unwrap().is_some());
+    }
 
-fn document_id(root_id: &str, relative_path: &str) -> String {
-    deterministic_id(&["document", root_id, relative_path])
-}
+    #[test]
+    fn file_state_roundtrip() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let mut state = FileState {
+            path: "/tmp/a.jsonl".to_string(),
+            content_hash: "h1".to_string(),
+            size_bytes: 10,
+            modified_at: DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+            record_count: 3,
+        };
+        store
+            .upsert_file_states("pi", std::slice::from_ref(&state))
+            .unwrap();
+        state.content_hash = "h2".to_string();
+        state.record_count = 2;
+        store
+            .upsert_file_states("pi", std::slice::from_ref(&state))
+            .unwrap();
 
-fn build_document_snippet(content: &str, query: &str) -> String {
-    const MAX_CHARS: usize = 180;
-    let collapsed = content.split_whitespace().collect::<Vec<_>>().join(" ");
-    if collapsed.is_empty() {
-        return String::new();
-    }
-    if collapsed.is_ascii() && query.is_ascii() {
-        let lower = collapsed.to_lowercase();
-        let needle = query.trim().to_lowercase();
-        if !needle.is_empty()
-            && let Some(start) = lower.find(&needle)
-        {
-            let snippet_start = start.saturating_sub(60);
-            let snippet_end = (start + needle.len() + 60).min(collapsed.len());
-            let mut snippet = collapsed[snippet_start..snippet_end].to_string();
-            if snippet_start > 0 {
-                snippet = format!("…{}", snippet.trim_start());
-            }
-            if snippet_end < collapsed.len() {
-                snippet.push('…');
-            }
-            return snippet;
-        }
+        let states = store.get_file_states("pi").unwrap();
+        assert_eq!(states.get("/tmp/a.jsonl"), Some(&state));
+        assert!(store.get_file_states("codex").unwrap().is_empty());
     }
-    let shortened: String = collapsed.chars().take(MAX_CHARS).collect();
-    if shortened.len() == collapsed.len() {
-        shortened
-    } else {
-        format!("{shortened}…")
+
+    #[test]
+    fn sync_cutoff_keeps_latest_until_cleared() {
+        let store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let early = DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let late = DateTime::parse_from_rfc3339("2026-02-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(store.get_sync_cutoff("claude").unwrap(), None);
+        store.record_sync_cutoff("claude", late).unwrap();
+        store.record_sync_cutoff("claude", early).unwrap();
+        assert_eq!(store.get_sync_cutoff("claude").unwrap(), Some(late));
+        assert_eq!(store.get_sync_cutoff("pi").unwrap(), None);
+        store.clear_sync_cutoff("claude").unwrap();
+        assert_eq!(store.get_sync_cutoff("claude").unwrap(), None);
     }
-}
 
-fn parse_agent(s: &str) -> rusqlite::Result<core_model::AgentKind> {
-    s.parse::<core_model::AgentKind>().map_err(|err| {
-        rusqlite::Error::FromSqlConversionFailure(
-            0,
-            rusqlite::types::Type::Text,
-            Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, err)),
-        )
-    })
-}
+    #[test]
+    fn dir_listings_roundtrip_separately_from_file_states() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let listing = DirListing {
+            modified_at: DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+            dirs: vec!["sub".to_string()],
+            files: vec!["a.jsonl".to_string()],
+        };
+        store
+            .upsert_dir_listings("pi", &[("/tmp/sessions".to_string(), listing.clone())])
+            .unwrap();
 
-fn escape_like_pattern(query: &str) -> String {
-    let mut escaped = String::with_capacity(query.len());
-    for ch in query.to_lowercase().chars() {
-        if matches!(ch, '%' | '_' | '\\') {
-            escaped.push('\\');
-        }
-        escaped.push(ch);
+        let listings = store.get_dir_listings("pi").unwrap();
+        assert_eq!(listings.get("/tmp/sessions"), Some(&listing));
+        assert!(store.get_file_states("pi").unwrap().is_empty());
+        assert!(store.get_dir_listings("codex").unwrap().is_empty());
     }
-    escaped
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use core_model::AgentKind;
 
     #[test]
-    fn schema_and_integrity() {
-        let mut store = SqliteStore::open(":memory:").expect("open");
-        store.init_schema().expect("schema");
-        let check = store.integrity_check().expect("integrity");
-        assert_eq!(check, "ok");
+    fn dedupe_agent_messages_keeps_latest_copy() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let mut batch = make_batch(AgentKind::Pi, "s1", "old-id", "same text");
+        store.save_batch(&batch).unwrap();
+        batch.messages[0].id = "new-id".to_string();
+        batch.provenance[0].id = "prov_new-id".to_string();
+        batch.provenance[0].entity_id = "new-id".to_string();
+        store.save_batch(&batch).unwrap();
+        store
+            .save_batch(&make_batch(AgentKind::Pi, "s1", "other", "different"))
+            .unwrap();
 
-        let batch = NormalizedBatch::default();
-        store.save_batch(&batch).expect("empty batch is fine");
+        assert_eq!(store.dedupe_agent_messages("pi").unwrap(), 1);
+        let ids: Vec<String> = store
+            .get_session_messages("s1")
+            .unwrap()
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        assert!(ids.contains(&"new-id".to_string()));
+        assert!(!ids.contains(&"old-id".to_string()));
+        assert_eq!(
+            store
+                .search_lexical("same", 10, &SearchScope::default())
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(store.dedupe_agent_messages("pi").unwrap(), 0);
     }
 
-    fn make_batch(
-        agent: AgentKind,
-        session_id: &str,
-        msg_id: &str,
-        content: &str,
-    ) -> NormalizedBatch {
-        let now = Utc::now();
-        NormalizedBatch {
-            sessions: vec![Session {
-                id: session_id.to_string(),
-                agent,
-                source_ref: "test-ref".to_string(),
-                title: "test session".to_string(),
-                created_at: now,
-                updated_at: now,
-            }],
-            messages: vec![Message {
-                id: msg_id.to_string(),
-                session_id: session_id.to_string(),
-                role: "user".to_string(),
-                content: content.to_string(),
-                ts: now,
-            }],
-            events: vec![],
-            artifacts: vec![],
-            provenance: vec![Provenance {
-                id: format!("prov_{}", msg_id),
-                entity_type: "message".to_string(),
-                entity_id: msg_id.to_string(),
-                agent,
-                source_path: "/test/path".to_string(),
-                source_id: "src-1".to_string(),
-            }],
-        }
+    #[test]
+    fn large_message_content_is_compressed_and_searchable() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let body = format!(
+            "needle_start {} tail_marker",
+            "tool output line\n".repeat(FTS_CONTENT_LIMIT_BYTES / 8)
+        );
+        store
+            .save_batch(&make_batch(AgentKind::Pi, "s1", "m1", &body))
+            .unwrap();
+
+        let encoding: String = store
+            .conn
+            .query_row(
+                "SELECT content_encoding FROM messages WHERE id = 'm1'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(encoding, "zstd");
+
+        let msgs = store.get_session_messages("s1").unwrap();
+        assert_eq!(msgs[0].content, body);
+        assert_eq!(
+            store.recent_messages(1, &SearchScope::default()).unwrap()[0].content,
+            body
+        );
+
+        assert_eq!(
+            store
+                .search_lexical("needle_start", 10, &SearchScope::default())
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            store
+                .search_substring("needle_start", 10, &SearchScope::default())
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(
+            store
+                .search_lexical("tail_marker", 10, &SearchScope::default())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn small_message_content_stays_plain() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        store
+            .save_batch(&make_batch(AgentKind::Pi, "s1", "m1", "short"))
+            .unwrap();
+        let encoding: String = store
+            .conn
+            .query_row(
+                "SELECT content_encoding FROM messages WHERE id = 'm1'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(encoding, "plain");
     }
 
     fn upsert_test_document(
@@ -1202,11 +4632,16 @@ mod tests {
     fn agents_populated() {
         let store = SqliteStore::open(":memory:").unwrap();
         store.init_schema().unwrap();
-        let count: i64 = store
-            .conn
-            .query_row("SELECT COUNT(*) FROM agents", [], |r| r.get(0))
+        let mut stmt = store.conn.prepare("SELECT id FROM agents").unwrap();
+        let seeded: HashSet<String> = stmt
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
             .unwrap();
-        assert_eq!(count, 5);
+        assert_eq!(seeded.len(), AgentKind::ALL.len());
+        for kind in AgentKind::ALL {
+            assert!(seeded.contains(kind.as_str()), "missing seed for {kind}");
+        }
     }
 
     #[test]
@@ -1234,6 +4669,31 @@ mod tests {
         assert_eq!(msgs.len(), 1);
     }
 
+    #[test]
+    fn save_batch_keeps_title_over_fallbacks() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        store
+            .save_batch(&make_batch(AgentKind::Pi, "s1", "m1", "hello"))
+            .unwrap();
+        for fallback in ["", "test-ref"] {
+            let mut batch = make_batch(AgentKind::Pi, "s1", "m2", "again");
+            batch.sessions[0].title = fallback.to_string();
+            store.save_batch(&batch).unwrap();
+            assert_eq!(
+                store.get_session("s1").unwrap().unwrap().title,
+                "test session"
+            );
+        }
+        let mut renamed = make_batch(AgentKind::Pi, "s1", "m3", "renamed");
+        renamed.sessions[0].title = "better title".to_string();
+        store.save_batch(&renamed).unwrap();
+        assert_eq!(
+            store.get_session("s1").unwrap().unwrap().title,
+            "better title"
+        );
+    }
+
     #[test]
     fn get_session_and_messages() {
         let mut store = SqliteStore::open(":memory:").unwrap();
@@ -1250,6 +4710,129 @@ mod tests {
         assert_eq!(msgs[0].content, "test content");
     }
 
+    #[test]
+    fn recent_sessions_orders_by_update_and_limits() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let start = Utc::now();
+        for (offset, id) in [(0, "old"), (120, "newest"), (60, "middle")] {
+            let mut batch = make_batch(AgentKind::Pi, id, &format!("{id}-m"), id);
+            batch.sessions[0].updated_at = start + Duration::seconds(offset);
+            store.save_batch(&batch).unwrap();
+        }
+        let ids: Vec<String> = store
+            .recent_sessions(2)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.id)
+            .collect();
+        assert_eq!(ids, vec!["newest", "middle"]);
+        assert_eq!(store.list_sessions().unwrap().len(), 3);
+        let page = |agent: Option<&str>, limit: Option<usize>, offset: usize| -> Vec<String> {
+            store
+                .sessions_page(agent, limit, offset)
+                .unwrap()
+                .into_iter()
+                .map(|s| s.id)
+                .collect()
+        };
+        assert_eq!(page(None, Some(1), 1), ["middle"]);
+        assert_eq!(page(None, None, 1), ["middle", "old"]);
+        assert_eq!(page(Some("pi"), Some(5), 2), ["old"]);
+        assert!(page(Some("codex"), None, 0).is_empty());
+    }
+
+    #[test]
+    fn session_meta_merges_across_batches() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let mut batch = make_batch(AgentKind::Codex, "s1", "m1", "hello");
+        batch.session_meta.push(core_model::SessionMeta {
+            session_id: "s1".to_string(),
+            meta: serde_json::json!({"system_prompts": [{"content": "first"}]}),
+        });
+        store.save_batch(&batch).unwrap();
+        store.save_batch(&batch).unwrap();
+        assert_eq!(
+            store.get_session_meta("s1").unwrap(),
+            Some(serde_json::json!({"system_prompts": [{"content": "first"}]}))
+        );
+
+        let mut next = make_batch(AgentKind::Codex, "s1", "m2", "again");
+        next.session_meta.push(core_model::SessionMeta {
+            session_id: "s1".to_string(),
+            meta: serde_json::json!({"system_prompts": [{"content": "second"}]}),
+        });
+        store.save_batch(&next).unwrap();
+        store
+            .save_batch(&make_batch(AgentKind::Codex, "s1", "m3", "plain"))
+            .unwrap();
+        let bundle = store.get_session_bundle("s1").unwrap().unwrap();
+        assert_eq!(
+            bundle.meta,
+            Some(serde_json::json!({
+                "system_prompts": [{"content": "first"}, {"content": "second"}]
+            }))
+        );
+        assert_eq!(store.get_session_meta("missing").unwrap(), None);
+    }
+
+    #[test]
+    fn get_session_bundle_returns_all_session_rows() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let mut batch = make_batch(AgentKind::Claude, "s1", "m1", "hello bundle");
+        batch.events.push(core_model::Event {
+            id: "e1".to_string(),
+            session_id: "s1".to_string(),
+            kind: "tool".to_string(),
+            payload: serde_json::json!({"name": "bash"}),
+            ts: Utc::now(),
+        });
+        batch.artifacts.push(core_model::Artifact {
+            id: "a1".to_string(),
+            session_id: "s1".to_string(),
+            path: "/tmp/out.txt".to_string(),
+            checksum: "abc".to_string(),
+            metadata: serde_json::json!({}),
+        });
+        store.save_batch(&batch).unwrap();
+
+        let bundle = store.get_session_bundle("s1").unwrap().unwrap();
+        assert_eq!(bundle.session.id, "s1");
+        assert_eq!(bundle.messages.len(), 1);
+        assert_eq!(bundle.messages[0].content, "hello bundle");
+        assert_eq!(bundle.events.len(), 1);
+        assert_eq!(bundle.artifacts.len(), 1);
+        assert_eq!(bundle.provenance.len(), 1);
+        assert!(store.get_session_bundle("missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn session_aggregate_counts_roles_and_span() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let mut batch = make_batch(AgentKind::Pi, "s1", "m1", "one");
+        let start = batch.messages[0].ts;
+        for (id, role, offset) in [("m2", "assistant", 30), ("m3", "assistant", 90)] {
+            let mut m = batch.messages[0].clone();
+            m.id = id.to_string();
+            m.role = role.to_string();
+            m.ts = start + chrono::Duration::seconds(offset);
+            batch.messages.push(m);
+        }
+        store.save_batch(&batch).unwrap();
+
+        let aggregate = store.session_aggregate("s1").unwrap().unwrap();
+        assert_eq!(aggregate.message_count, 3);
+        assert_eq!(
+            aggregate.by_role,
+            vec![("assistant".to_string(), 2), ("user".to_string(), 1)]
+        );
+        assert_eq!((aggregate.last_ts - aggregate.first_ts).num_seconds(), 90);
+        assert!(store.session_aggregate("missing").unwrap().is_none());
+    }
+
     #[test]
     fn get_session_messages_is_stable_when_timestamps_match() {
         let mut store = SqliteStore::open(":memory:").unwrap();
@@ -1290,135 +4873,809 @@ mod tests {
             events: vec![],
             artifacts: vec![],
             provenance: vec![],
+            session_meta: vec![],
+        };
+
+        store.save_batch(&batch).unwrap();
+
+        let msgs = store.get_session_messages("s_same_ts").unwrap();
+        let contents = msgs.into_iter().map(|msg| msg.content).collect::<Vec<_>>();
+        assert_eq!(contents, vec!["first", "second", "third"]);
+    }
+
+    #[test]
+    fn provenance_for_session() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let batch = make_batch(core_model::AgentKind::Claude, "s3", "m3", "prov test");
+        store.save_batch(&batch).unwrap();
+        let prov = store.get_provenance_for_session("s3").unwrap();
+        assert_eq!(prov.len(), 1);
+        assert_eq!(prov[0].entity_id, "m3");
+        assert_eq!(prov[0].source_path, "/test/path");
+    }
+
+    #[test]
+    fn checkpoint_upsert_and_get() {
+        let store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        assert!(store.get_checkpoint("pi").unwrap().is_none());
+        let cp = Checkpoint {
+            agent: core_model::AgentKind::Pi,
+            cursor: "2025-01-01T00:00:00+00:00".to_string(),
+            updated_at: Utc::now(),
+        };
+        store.upsert_checkpoint(&cp).unwrap();
+        let got = store.get_checkpoint("pi").unwrap().unwrap();
+        assert_eq!(got, "2025-01-01T00:00:00+00:00");
+        let cp2 = Checkpoint {
+            agent: core_model::AgentKind::Pi,
+            cursor: "2025-06-01T00:00:00+00:00".to_string(),
+            updated_at: Utc::now(),
+        };
+        store.upsert_checkpoint(&cp2).unwrap();
+        let got2 = store.get_checkpoint("pi").unwrap().unwrap();
+        assert_eq!(got2, "2025-06-01T00:00:00+00:00");
+    }
+
+    #[test]
+    fn fts_search() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let batch = make_batch(
+            core_model::AgentKind::Pi,
+            "s1",
+            "m1",
+            "rust programming language",
+        );
+        store.save_batch(&batch).unwrap();
+        let results = store
+            .search_lexical("rust", 10, &SearchScope::default())
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].message_id, "m1");
+        assert!(results[0].score > 0.0, "BM25 score should be positive");
+        let empty = store
+            .search_lexical("python", 10, &SearchScope::default())
+            .unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn delete_session_cascade() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let batch = make_batch(core_model::AgentKind::Pi, "s1", "m1", "cascade test");
+        store.save_batch(&batch).unwrap();
+        assert_eq!(store.list_sessions().unwrap().len(), 1);
+        assert_eq!(store.get_session_messages("s1").unwrap().len(), 1);
+        store.delete_session_cascade("s1").unwrap();
+        assert!(store.list_sessions().unwrap().is_empty());
+        assert!(store.get_session_messages("s1").unwrap().is_empty());
+        let fts = store
+            .search_lexical("cascade", 10, &SearchScope::default())
+            .unwrap();
+        assert!(fts.is_empty());
+    }
+
+    #[test]
+    fn wipe_removes_sessions_with_their_derived_rows() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        store
+            .save_batch(&make_batch(
+                AgentKind::Codex,
+                "s1",
+                "m1",
+                "client secret plan",
+            ))
+            .unwrap();
+        let mut other = make_batch(AgentKind::Codex, "s2", "m2", "unrelated work");
+        other.provenance[0].source_id = "src-2".to_string();
+        store.save_batch(&other).unwrap();
+        let record = |source_id: &str| NativeRecord {
+            source_id: source_id.to_string(),
+            updated_at: Utc::now(),
+            payload: serde_json::json!({"text": source_id}),
         };
+        store
+            .save_raw_records(AgentKind::Codex, &[record("src-1"), record("src-2")])
+            .unwrap();
+        store.trash_session("s1").unwrap();
+
+        let counts = store.wipe_sessions(&["s1".to_string()]).unwrap();
+        assert_eq!(
+            counts,
+            WipeCounts {
+                sessions: 1,
+                messages: 1,
+                provenance: 1,
+                fts_rows: 1,
+                raw_records: 1,
+                ..Default::default()
+            }
+        );
+        assert!(store.get_session("s1").unwrap().is_none());
+        assert!(
+            store
+                .search_lexical("client", 10, &SearchScope::default())
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(store.raw_records(AgentKind::Codex).unwrap().len(), 1);
+        assert_eq!(store.orphan_rows().unwrap(), OrphanRows::default());
+        assert_eq!(store.get_session_messages("s2").unwrap().len(), 1);
+        assert_eq!(
+            store.wipe_sessions(&["s1".to_string()]).unwrap(),
+            WipeCounts::default()
+        );
+    }
+
+    #[test]
+    fn archive_plan_and_idempotency() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let old_time = Utc::now() - Duration::days(60);
+        let mut batch = NormalizedBatch::default();
+        for i in 0..5 {
+            batch.sessions.push(Session {
+                id: format!("s{}", i),
+                agent: core_model::AgentKind::Pi,
+                source_ref: format!("ref{}", i),
+                title: format!("session {}", i),
+                created_at: old_time,
+                updated_at: old_time,
+            });
+        }
+        store.save_batch(&batch).unwrap();
+        let run1 = store.plan_archive(Duration::days(30), 2).unwrap();
+        let items1 = store.archive_items_for_run(&run1.id).unwrap();
+        assert_eq!(items1.len(), 3);
+        let run2 = store.plan_archive(Duration::days(30), 2).unwrap();
+        let items2 = store.archive_items_for_run(&run2.id).unwrap();
+        assert_eq!(
+            items2.len(),
+            0,
+            "idempotency: already-planned sessions should be skipped"
+        );
+        store.mark_archive_executed(&run1.id, true).unwrap();
+        let runs = store.archive_runs().unwrap();
+        assert_eq!(runs.len(), 2);
+        let listed = runs.iter().find(|r| r.id == run1.id).unwrap();
+        assert!(listed.executed && listed.dry_run);
+        assert_eq!(listed.keep_latest, 2);
+    }
+
+    #[test]
+    fn init_schema_idempotent() {
+        let store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        store.init_schema().unwrap();
+        let count: i64 = store
+            .conn
+            .query_row("SELECT COUNT(*) FROM agents", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(count, AgentKind::ALL.len() as i64);
+    }
+
+    #[test]
+    fn recent_messages_ordering() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let batch = make_batch(core_model::AgentKind::Pi, "s1", "m1", "first message");
+        store.save_batch(&batch).unwrap();
+        let batch2 = make_batch(core_model::AgentKind::Pi, "s2", "m2", "second message");
+        store.save_batch(&batch2).unwrap();
+        let recent = store.recent_messages(10, &SearchScope::default()).unwrap();
+        assert_eq!(recent.len(), 2);
+    }
+
+    #[test]
+    fn file_mentions_track_saves_and_backfill() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        store
+            .save_batch(&make_batch(
+                AgentKind::Claude,
+                "s1",
+                "m1",
+                r#"tool_use: Read {"file_path":"/home/me/repo/src/lib.rs"}"#,
+            ))
+            .unwrap();
+        store
+            .save_batch(&make_batch(
+                AgentKind::Codex,
+                "s2",
+                "m2",
+                "tool_use: apply_patch *** Begin Patch\n*** Update File: src/lib.rs\n@@",
+            ))
+            .unwrap();
+        store
+            .save_batch(&make_batch(
+                AgentKind::Pi,
+                "s3",
+                "m3",
+                r#"tool_use: Read {"path":"other/src/lib.rs.bak"}"#,
+            ))
+            .unwrap();
+
+        let history = store.file_history("./src/lib.rs", 10).unwrap();
+        let sessions: Vec<&str> = history.iter().map(|h| h.session_id.as_str()).collect();
+        assert_eq!(sessions.len(), 2);
+        assert!(sessions.contains(&"s1") && sessions.contains(&"s2"));
+        let codex = history.iter().find(|h| h.session_id == "s2").unwrap();
+        assert_eq!(codex.tools, vec!["apply_patch"]);
+        assert_eq!(codex.paths, vec!["src/lib.rs"]);
+        assert_eq!(codex.mentions, 1);
+        assert_eq!(
+            store
+                .file_history("/home/me/repo/src/lib.rs", 10)
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Editing the message away drops its mention.
+        store
+            .save_batch(&make_batch(AgentKind::Claude, "s1", "m1", "no tools"))
+            .unwrap();
+        assert_eq!(store.file_history("src/lib.rs", 10).unwrap().len(), 1);
+
+        store
+            .conn
+            .execute_batch("DROP TABLE file_mentions; PRAGMA user_version = 15;")
+            .unwrap();
+        store.init_schema().unwrap();
+        assert_eq!(
+            store.file_history("src/lib.rs", 10).unwrap()[0].session_id,
+            "s2"
+        );
+    }
+
+    #[test]
+    fn commands_are_indexed_with_their_prompt() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let mut batch = make_batch(AgentKind::Codex, "s1", "m1", "speed up the test suite");
+        let mut reply = batch.messages[0].clone();
+        reply.id = "m2".to_string();
+        reply.role = "assistant".to_string();
+        reply.content =
+            r#"tool_use: shell {"command":["bash","-lc","cargo nextest run --workspace"]}"#
+                .to_string();
+        reply.ts += chrono::Duration::seconds(1);
+        batch.messages.push(reply);
+        store.save_batch(&batch).unwrap();
+        store
+            .save_batch(&make_batch(
+                AgentKind::Pi,
+                "s2",
+                "m3",
+                r#"tool_use: bash {"command":"cargo test"}"#,
+            ))
+            .unwrap();
+
+        let hits = store.search_commands("Cargo Nextest", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].command, "cargo nextest run --workspace");
+        assert_eq!(hits[0].session_id, "s1");
+        assert_eq!(hits[0].agent, AgentKind::Codex);
+        assert_eq!(store.search_commands("cargo", 10).unwrap().len(), 2);
+        assert!(store.search_commands("100%", 10).unwrap().is_empty());
+        let prompt = store.prompt_before(&hits[0].message_id).unwrap().unwrap();
+        assert_eq!(prompt.content, "speed up the test suite");
+
+        store
+            .conn
+            .execute_batch("DROP TABLE commands; PRAGMA user_version = 16;")
+            .unwrap();
+        store.init_schema().unwrap();
+        assert_eq!(store.search_commands("cargo", 10).unwrap().len(), 2);
+    }
 
+    #[test]
+    fn graph_is_rebuilt_from_live_sessions() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let mut batch = make_batch(
+            AgentKind::Claude,
+            "s1",
+            "m1",
+            "tool_use: Edit {\"file_path\":\"src/a.rs\"}\ntool_result: TypeError: x",
+        );
+        batch.provenance[0].repo = Some(RepoRef {
+            remote: "github.com/o/r".to_string(),
+            path: String::new(),
+        });
         store.save_batch(&batch).unwrap();
+        store
+            .save_batch(&make_batch(
+                AgentKind::Codex,
+                "s2",
+                "m2",
+                "tool_use: edit {\"path\":\"src/a.rs\"}",
+            ))
+            .unwrap();
+        store
+            .save_batch(&make_batch(AgentKind::Codex, "s3", "m3", "ValueError"))
+            .unwrap();
+        store.trash_session("s3").unwrap();
 
-        let msgs = store.get_session_messages("s_same_ts").unwrap();
-        let contents = msgs.into_iter().map(|msg| msg.content).collect::<Vec<_>>();
-        assert_eq!(contents, vec!["first", "second", "third"]);
+        assert_eq!(store.rebuild_graph().unwrap(), (4, 6));
+        assert_eq!(store.rebuild_graph().unwrap(), (4, 6));
+        let graph = store.graph(1, &[]).unwrap();
+        let names: Vec<(&str, &str, usize)> = graph
+            .nodes
+            .iter()
+            .map(|n| (n.kind.as_str(), n.name.as_str(), n.sessions))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("file", "src/a.rs", 2),
+                ("tool", "edit", 2),
+                ("error", "TypeError", 1),
+                ("repo", "github.com/o/r", 1)
+            ]
+        );
+        assert_eq!(graph.edges[0].relation, "uses");
+        assert_eq!(graph.edges[0].sessions, 2);
+
+        let heavy = store.graph(2, &[]).unwrap();
+        assert_eq!((heavy.nodes.len(), heavy.edges.len()), (2, 1));
+        let tools = store.graph(1, &["tool", "error"]).unwrap();
+        assert_eq!((tools.nodes.len(), tools.edges.len()), (2, 1));
     }
 
     #[test]
-    fn provenance_for_session() {
+    fn provenance_keeps_the_recorded_workspace() {
         let mut store = SqliteStore::open(":memory:").unwrap();
         store.init_schema().unwrap();
-        let batch = make_batch(core_model::AgentKind::Claude, "s3", "m3", "prov test");
+        let mut batch = make_batch(AgentKind::Codex, "s1", "m1", "hello");
+        batch.provenance[0].workspace = Some("/work/app".to_string());
         store.save_batch(&batch).unwrap();
-        let prov = store.get_provenance_for_session("s3").unwrap();
-        assert_eq!(prov.len(), 1);
-        assert_eq!(prov[0].entity_id, "m3");
-        assert_eq!(prov[0].source_path, "/test/path");
+        // A later read without the workspace line keeps the one recorded.
+        batch.provenance[0].workspace = None;
+        store.save_batch(&batch).unwrap();
+        let bundle = store.get_session_bundle("s1").unwrap().unwrap();
+        assert_eq!(bundle.provenance[0].workspace.as_deref(), Some("/work/app"));
     }
 
     #[test]
-    fn checkpoint_upsert_and_get() {
-        let store = SqliteStore::open(":memory:").unwrap();
+    fn sessions_are_found_by_issue_ref() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
         store.init_schema().unwrap();
-        assert!(store.get_checkpoint("pi").unwrap().is_none());
-        let cp = Checkpoint {
-            agent: core_model::AgentKind::Pi,
-            cursor: "2025-01-01T00:00:00+00:00".to_string(),
-            updated_at: Utc::now(),
-        };
-        store.upsert_checkpoint(&cp).unwrap();
-        let got = store.get_checkpoint("pi").unwrap().unwrap();
-        assert_eq!(got, "2025-01-01T00:00:00+00:00");
-        let cp2 = Checkpoint {
-            agent: core_model::AgentKind::Pi,
-            cursor: "2025-06-01T00:00:00+00:00".to_string(),
-            updated_at: Utc::now(),
+        let mut in_repo = make_batch(AgentKind::Claude, "s1", "m1", "Working on #12 now.");
+        in_repo.provenance[0].repo = Some(RepoRef {
+            remote: "github.com/Org/Repo".to_string(),
+            path: "src".to_string(),
+        });
+        store.save_batch(&in_repo).unwrap();
+        for (session, id, content) in [
+            ("s2", "m2", "Context: https://github.com/org/repo/issues/12"),
+            ("s3", "m3", "See other/thing#12."),
+            ("s4", "m4", "Bare #12 with no repository."),
+        ] {
+            store
+                .save_batch(&make_batch(AgentKind::Codex, session, id, content))
+                .unwrap();
+        }
+
+        let ids = |reference: &str| -> Vec<String> {
+            let mut ids: Vec<String> = store
+                .sessions_with_ref(&IssueRef::parse(reference).unwrap())
+                .unwrap()
+                .into_iter()
+                .map(|s| s.id)
+                .collect();
+            ids.sort();
+            ids
         };
-        store.upsert_checkpoint(&cp2).unwrap();
-        let got2 = store.get_checkpoint("pi").unwrap().unwrap();
-        assert_eq!(got2, "2025-06-01T00:00:00+00:00");
+        assert_eq!(ids("Org/Repo#12"), vec!["s1", "s2"]);
+        assert_eq!(ids("other/thing#12"), vec!["s3"]);
+        assert_eq!(ids("#12"), vec!["s1", "s2", "s3", "s4"]);
+        assert!(ids("org/repo#13").is_empty());
+
+        store
+            .conn
+            .execute_batch("DROP TABLE issue_refs; PRAGMA user_version = 18;")
+            .unwrap();
+        store.init_schema().unwrap();
+        assert_eq!(ids("org/repo#12"), vec!["s1", "s2"]);
     }
 
     #[test]
-    fn fts_search() {
+    fn links_are_listed_newest_first() {
         let mut store = SqliteStore::open(":memory:").unwrap();
         store.init_schema().unwrap();
-        let batch = make_batch(
-            core_model::AgentKind::Pi,
+        let mut old = make_batch(
+            AgentKind::Claude,
             "s1",
             "m1",
-            "rust programming language",
+            "Per https://docs.rs/serde/latest/serde/ this derives.",
         );
-        store.save_batch(&batch).unwrap();
-        let results = store.search_lexical("rust", 10).unwrap();
-        assert_eq!(results.len(), 1);
-        assert_eq!(results[0].message_id, "m1");
-        assert!(results[0].score > 0.0, "BM25 score should be positive");
-        let empty = store.search_lexical("python", 10).unwrap();
-        assert!(empty.is_empty());
+        old.messages[0].ts -= chrono::Duration::days(60);
+        store.save_batch(&old).unwrap();
+        store
+            .save_batch(&make_batch(
+                AgentKind::Codex,
+                "s2",
+                "m2",
+                "See [the RFC](https://rust-lang.github.io/rfcs/2094-nll.html).",
+            ))
+            .unwrap();
+
+        let all = store.list_links(None, 10).unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].url, "https://rust-lang.github.io/rfcs/2094-nll.html");
+        assert_eq!(all[0].agent, AgentKind::Codex);
+        assert!(all[0].context.starts_with("See [the RFC]"));
+        let recent = store
+            .list_links(Some(Utc::now() - chrono::Duration::days(30)), 10)
+            .unwrap();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].session_id, "s2");
+
+        store
+            .conn
+            .execute_batch("DROP TABLE links; PRAGMA user_version = 17;")
+            .unwrap();
+        store.init_schema().unwrap();
+        assert_eq!(store.list_links(None, 10).unwrap().len(), 2);
     }
 
     #[test]
-    fn delete_session_cascade() {
+    fn raw_records_join_back_through_provenance() {
         let mut store = SqliteStore::open(":memory:").unwrap();
         store.init_schema().unwrap();
-        let batch = make_batch(core_model::AgentKind::Pi, "s1", "m1", "cascade test");
-        store.save_batch(&batch).unwrap();
-        assert_eq!(store.list_sessions().unwrap().len(), 1);
-        assert_eq!(store.get_session_messages("s1").unwrap().len(), 1);
-        store.delete_session_cascade("s1").unwrap();
-        assert!(store.list_sessions().unwrap().is_empty());
-        assert!(store.get_session_messages("s1").unwrap().is_empty());
-        let fts = store.search_lexical("cascade", 10).unwrap();
-        assert!(fts.is_empty());
+        assert!(!store.retains_raw_records());
+        store
+            .save_batch(&make_batch(AgentKind::Claude, "s1", "m1", "hello"))
+            .unwrap();
+        let record = |id: &str, text: &str| NativeRecord {
+            source_id: id.to_string(),
+            updated_at: Utc::now(),
+            payload: serde_json::json!({ "type": "user", "text": text }),
+        };
+        store
+            .save_raw_records(
+                AgentKind::Claude,
+                &[record("src-1", "first"), record("src-2", "unrelated")],
+            )
+            .unwrap();
+        store
+            .save_raw_records(AgentKind::Codex, &[record("src-1", "other agent")])
+            .unwrap();
+        store
+            .save_raw_records(AgentKind::Claude, &[record("src-1", "rewritten")])
+            .unwrap();
+
+        let raw = store.session_raw_records("s1").unwrap();
+        assert_eq!(raw.len(), 1);
+        assert_eq!(raw[0].source_id, "src-1");
+        assert_eq!(raw[0].payload["text"], "rewritten");
+        assert!(store.session_raw_records("missing").unwrap().is_empty());
+        let claude: Vec<String> = store
+            .raw_records(AgentKind::Claude)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.source_id)
+            .collect();
+        assert_eq!(claude, vec!["src-2", "src-1"]);
+        let usage = store.raw_records_usage().unwrap();
+        assert_eq!(usage.records, 3);
+        assert!(usage.bytes > 0);
     }
 
     #[test]
-    fn archive_plan_and_idempotency() {
+    fn plain_raw_records_are_compressed_on_upgrade() {
         let mut store = SqliteStore::open(":memory:").unwrap();
         store.init_schema().unwrap();
-        let old_time = Utc::now() - Duration::days(60);
-        let mut batch = NormalizedBatch::default();
-        for i in 0..5 {
-            batch.sessions.push(Session {
-                id: format!("s{}", i),
-                agent: core_model::AgentKind::Pi,
-                source_ref: format!("ref{}", i),
-                title: format!("session {}", i),
-                created_at: old_time,
-                updated_at: old_time,
+        store
+            .save_batch(&make_batch(AgentKind::Pi, "s1", "m1", "hello"))
+            .unwrap();
+        store
+            .conn
+            .execute_batch(
+                r#"DROP TABLE raw_records;
+                CREATE TABLE raw_records (
+                  agent TEXT NOT NULL,
+                  source_id TEXT NOT NULL,
+                  updated_at TEXT NOT NULL,
+                  payload TEXT NOT NULL,
+                  PRIMARY KEY(agent, source_id)
+                );
+                INSERT INTO raw_records VALUES ('pi', 'src-1', '2025-05-01T09:00:00+00:00', '{"type":"message"}');
+                PRAGMA user_version = 20;"#,
+            )
+            .unwrap();
+        store.init_schema().unwrap();
+
+        let encoding: String = store
+            .conn
+            .query_row("SELECT encoding FROM raw_records", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(encoding, "zstd");
+        let raw = store.session_raw_records("s1").unwrap();
+        assert_eq!(raw[0].payload["type"], "message");
+    }
+
+    fn conversation(agent: AgentKind, session_id: &str, contents: &[&str]) -> NormalizedBatch {
+        let start = Utc::now() - chrono::Duration::hours(1);
+        let mut batch = make_batch(agent, session_id, &format!("{session_id}-0"), contents[0]);
+        batch.messages[0].ts = start;
+        batch.provenance.clear();
+        for (i, content) in contents.iter().enumerate().skip(1) {
+            batch.messages.push(Message {
+                id: format!("{session_id}-{i}"),
+                session_id: session_id.to_string(),
+                role: "user".to_string(),
+                content: content.to_string(),
+                ts: start + chrono::Duration::minutes(i as i64),
             });
         }
+        batch
+    }
+
+    #[test]
+    fn split_sessions_stay_split_across_syncs() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        let batch = conversation(AgentKind::OpenCode, "s1", &["alpha", "beta", "gamma"]);
         store.save_batch(&batch).unwrap();
-        let run1 = store.plan_archive(Duration::days(30), 2).unwrap();
-        let items1 = store.archive_items_for_run(&run1.id).unwrap();
-        assert_eq!(items1.len(), 3);
-        let run2 = store.plan_archive(Duration::days(30), 2).unwrap();
-        let items2 = store.archive_items_for_run(&run2.id).unwrap();
+
+        assert!(store.split_session("s1", "s1-0").is_err());
+        assert!(store.split_session("s1", "missing").is_err());
+        let new = store.split_session("s1", "s1-1").unwrap();
+        assert_eq!(new.title, "test session (split)");
+        assert_eq!(new.created_at, batch.messages[1].ts);
+        assert!(store.split_session("s1", "s1-1").is_err());
+        let ids = |store: &SqliteStore, session: &str| -> Vec<String> {
+            store
+                .get_session_messages(session)
+                .unwrap()
+                .into_iter()
+                .map(|m| m.id)
+                .collect()
+        };
+        assert_eq!(ids(&store, "s1"), vec!["s1-0"]);
+        assert_eq!(ids(&store, &new.id), vec!["s1-1", "s1-2"]);
         assert_eq!(
-            items2.len(),
-            0,
-            "idempotency: already-planned sessions should be skipped"
+            store.get_session("s1").unwrap().unwrap().updated_at,
+            batch.messages[0].ts
+        );
+
+        let resynced = conversation(
+            AgentKind::OpenCode,
+            "s1",
+            &["alpha", "beta", "gamma", "delta"],
+        );
+        store.save_batch(&resynced).unwrap();
+        assert_eq!(ids(&store, "s1"), vec!["s1-0"]);
+        assert_eq!(ids(&store, &new.id), vec!["s1-1", "s1-2", "s1-3"]);
+        let hits = store
+            .search_lexical("delta", 10, &SearchScope::default())
+            .unwrap();
+        assert_eq!(hits[0].session_id, new.id);
+        assert_eq!(
+            store.get_session(&new.id).unwrap().unwrap().updated_at,
+            resynced.messages[3].ts
         );
+
+        store.merge_sessions("s1", &new.id).unwrap();
+        store.save_batch(&resynced).unwrap();
+        assert_eq!(ids(&store, "s1"), vec!["s1-0", "s1-1", "s1-2", "s1-3"]);
+        assert!(store.get_session(&new.id).unwrap().is_none());
     }
 
     #[test]
-    fn init_schema_idempotent() {
-        let store = SqliteStore::open(":memory:").unwrap();
-        store.init_schema().unwrap();
+    fn merged_sessions_absorb_later_messages() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
         store.init_schema().unwrap();
-        let count: i64 = store
-            .conn
-            .query_row("SELECT COUNT(*) FROM agents", [], |r| r.get(0))
+        store
+            .save_batch(&conversation(AgentKind::OpenCode, "s1", &["alpha"]))
+            .unwrap();
+        store
+            .save_batch(&conversation(AgentKind::OpenCode, "s2", &["beta"]))
+            .unwrap();
+        store
+            .save_batch(&conversation(AgentKind::Claude, "s3", &["gamma"]))
+            .unwrap();
+
+        assert!(store.merge_sessions("s1", "s1").is_err());
+        assert!(store.merge_sessions("s1", "s3").is_err());
+        assert_eq!(store.merge_sessions("s1", "s2").unwrap(), 1);
+        assert!(store.get_session("s2").unwrap().is_none());
+
+        store
+            .save_batch(&conversation(
+                AgentKind::OpenCode,
+                "s2",
+                &["beta", "epsilon"],
+            ))
+            .unwrap();
+        assert!(store.get_session("s2").unwrap().is_none());
+        let messages = store.get_session_messages("s1").unwrap();
+        assert_eq!(messages.len(), 3);
+        let hits = store
+            .search_lexical("epsilon", 10, &SearchScope::default())
             .unwrap();
-        assert_eq!(count, 5);
+        assert_eq!(hits[0].session_id, "s1");
     }
 
     #[test]
-    fn recent_messages_ordering() {
+    fn aliases_fold_stored_sessions_into_the_canonical_key() {
         let mut store = SqliteStore::open(":memory:").unwrap();
         store.init_schema().unwrap();
-        let batch = make_batch(core_model::AgentKind::Pi, "s1", "m1", "first message");
+        let legacy = deterministic_id(&["opencode", "session", "legacy"]);
+        let mut batch = conversation(AgentKind::OpenCode, &legacy, &["alpha", "beta"]);
+        batch.sessions[0].source_ref = "legacy".to_string();
         store.save_batch(&batch).unwrap();
-        let batch2 = make_batch(core_model::AgentKind::Pi, "s2", "m2", "second message");
-        store.save_batch(&batch2).unwrap();
-        let recent = store.recent_messages(10).unwrap();
-        assert_eq!(recent.len(), 2);
+
+        assert!(
+            store
+                .add_session_alias(AgentKind::OpenCode, "legacy", "legacy")
+                .is_err()
+        );
+        assert_eq!(
+            store
+                .add_session_alias(AgentKind::OpenCode, "unknown", "canonical")
+                .unwrap(),
+            None
+        );
+        let canonical = store
+            .add_session_alias(AgentKind::OpenCode, "legacy", "canonical")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            canonical,
+            deterministic_id(&["opencode", "session", "canonical"])
+        );
+        assert!(store.get_session(&legacy).unwrap().is_none());
+        let session = store.get_session(&canonical).unwrap().unwrap();
+        assert_eq!(session.source_ref, "canonical");
+        assert_eq!(session.title, "test session");
+        assert_eq!(store.get_session_messages(&canonical).unwrap().len(), 2);
+        assert_eq!(
+            store.session_aliases(AgentKind::OpenCode).unwrap(),
+            HashMap::from([
+                ("legacy".to_string(), "canonical".to_string()),
+                ("unknown".to_string(), "canonical".to_string()),
+            ])
+        );
+        assert!(store.session_aliases(AgentKind::Claude).unwrap().is_empty());
+    }
+
+    #[test]
+    fn trashed_sessions_are_hidden_until_restored_or_purged() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        for id in ["s1", "s2"] {
+            store
+                .save_batch(&conversation(AgentKind::Pi, id, &["alpha"]))
+                .unwrap();
+        }
+        let listed = |store: &SqliteStore| -> Vec<String> {
+            let mut ids: Vec<String> = store
+                .list_sessions()
+                .unwrap()
+                .into_iter()
+                .map(|s| s.id)
+                .collect();
+            ids.sort();
+            ids
+        };
+
+        store.trash_session("s1").unwrap();
+        assert!(store.trash_session("s1").is_err());
+        assert!(store.trash_session("missing").is_err());
+        assert_eq!(listed(&store), vec!["s2"]);
+        assert_eq!(store.trashed_sessions().unwrap()[0].0.id, "s1");
+        // Syncing a trashed session again does not bring it back.
+        store
+            .save_batch(&conversation(AgentKind::Pi, "s1", &["alpha", "beta"]))
+            .unwrap();
+        assert_eq!(listed(&store), vec!["s2"]);
+
+        store.restore_session("s1").unwrap();
+        assert!(store.restore_session("s1").is_err());
+        assert_eq!(listed(&store), vec!["s1", "s2"]);
+
+        store.trash_session("s2").unwrap();
+        let an_hour_ago = Utc::now() - chrono::Duration::hours(1);
+        assert_eq!(store.empty_trash(Some(an_hour_ago)).unwrap(), 0);
+        assert_eq!(store.empty_trash(None).unwrap(), 1);
+        assert!(store.get_session("s2").unwrap().is_none());
+        assert!(store.get_session_messages("s2").unwrap().is_empty());
+        assert!(store.trashed_sessions().unwrap().is_empty());
+    }
+
+    #[test]
+    fn storage_usage_breaks_down_tables_and_agents() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        store
+            .save_batch(&conversation(AgentKind::Pi, "s1", &["alpha", "beta"]))
+            .unwrap();
+        store
+            .save_batch(&conversation(AgentKind::Claude, "s2", &["gamma"]))
+            .unwrap();
+        let usage = store.storage_usage().unwrap();
+        assert!(usage.db_bytes > 0);
+        assert_eq!(
+            usage.agents,
+            vec![
+                AgentStorage {
+                    agent: "pi".to_string(),
+                    messages: 2,
+                    content_bytes: 9,
+                },
+                AgentStorage {
+                    agent: "claude".to_string(),
+                    messages: 1,
+                    content_bytes: 5,
+                },
+            ]
+        );
+        let tables: Vec<&str> = usage.tables.iter().map(|(t, _)| t.as_str()).collect();
+        assert!(tables.contains(&"messages"));
+        assert!(!tables.iter().any(|t| t.starts_with("sqlite_autoindex")));
+    }
+
+    #[test]
+    fn messages_after_follows_new_inserts() {
+        let mut store = SqliteStore::open(":memory:").unwrap();
+        store.init_schema().unwrap();
+        assert_eq!(store.message_cursor().unwrap(), 0);
+        store
+            .save_batch(&make_batch(core_model::AgentKind::Pi, "s1", "m1", "first"))
+            .unwrap();
+        let cursor = store.message_cursor().unwrap();
+        store
+            .save_batch(&make_batch(
+                core_model::AgentKind::Claude,
+                "s2",
+                "m2",
+                "second",
+            ))
+            .unwrap();
+        store
+            .save_batch(&make_batch(
+                core_model::AgentKind::Pi,
+                "s1",
+                "m1",
+                "first, edited",
+            ))
+            .unwrap();
+
+        let new = store.messages_after(cursor, None, None, 10).unwrap();
+        assert_eq!(new.len(), 1);
+        assert_eq!(new[0].1.id, "m2");
+        assert!(new[0].0 > cursor);
+        assert!(
+            store
+                .messages_after(cursor, Some("pi"), None, 10)
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            store.messages_after(0, None, Some("s2"), 10).unwrap()[0]
+                .1
+                .id
+                == "m2"
+        );
+
+        let latest = store.latest_messages(None, None, 5).unwrap();
+        let ids: Vec<&str> = latest.iter().map(|(_, m)| m.id.as_str()).collect();
+        assert_eq!(ids, ["m1", "m2"]);
+        assert_eq!(latest[0].1.content, "first, edited");
+        assert_eq!(
+            store
+                .latest_messages(Some("claude"), None, 5)
+                .unwrap()
+                .len(),
+            1
+        );
     }
 
     #[test]
@@ -1432,9 +5689,13 @@ mod tests {
             "hello_world function",
         );
         store.save_batch(&batch).unwrap();
-        let results = store.search_substring("hello_world", 10).unwrap();
+        let results = store
+            .search_substring("hello_world", 10, &SearchScope::default())
+            .unwrap();
         assert_eq!(results.len(), 1);
-        let empty = store.search_substring("nonexistent", 10).unwrap();
+        let empty = store
+            .search_substring("nonexistent", 10, &SearchScope::default())
+            .unwrap();
         assert!(empty.is_empty());
     }
 
@@ -1457,7 +5718,9 @@ mod tests {
         );
         store.save_batch(&with_x).unwrap();
 
-        let results = store.search_substring("hello_world", 10).unwrap();
+        let results = store
+            .search_substring("hello_world", 10, &SearchScope::default())
+            .unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].message_id, "m1");
     }
@@ -1493,10 +5756,14 @@ mod tests {
         });
         store.save_batch(&batch).unwrap();
 
-        let r = store.search_lexical("alpha", 10).unwrap();
+        let r = store
+            .search_lexical("alpha", 10, &SearchScope::default())
+            .unwrap();
         assert_eq!(r.len(), 1);
         assert_eq!(r[0].message_id, "m1");
-        let r2 = store.search_lexical("gamma", 10).unwrap();
+        let r2 = store
+            .search_lexical("gamma", 10, &SearchScope::default())
+            .unwrap();
         assert_eq!(r2.len(), 1);
         assert_eq!(r2[0].message_id, "m2");
 
@@ -1525,11 +5792,17 @@ mod tests {
         });
         store.save_batch(&batch2).unwrap();
 
-        let r3 = store.search_lexical("updated", 10).unwrap();
+        let r3 = store
+            .search_lexical("updated", 10, &SearchScope::default())
+            .unwrap();
         assert_eq!(r3.len(), 1, "m1 FTS should reflect updated content");
-        let r4 = store.search_lexical("epsilon", 10).unwrap();
+        let r4 = store
+            .search_lexical("epsilon", 10, &SearchScope::default())
+            .unwrap();
         assert_eq!(r4.len(), 1, "new message m3 should be in FTS");
-        let r5 = store.search_lexical("gamma", 10).unwrap();
+        let r5 = store
+            .search_lexical("gamma", 10, &SearchScope::default())
+            .unwrap();
         assert_eq!(r5.len(), 1, "untouched m2 should remain in FTS");
         assert_eq!(r5[0].message_id, "m2");
     }
@@ -1561,7 +5834,7 @@ mod tests {
 
         for i in 0..3 {
             let r = store
-                .search_lexical(&format!("unique_keyword_{i}"), 10)
+                .search_lexical(&format!("unique_keyword_{i}"), 10, &SearchScope::default())
                 .unwrap();
             assert_eq!(r.len(), 1, "session {i} message should be in FTS");
             assert_eq!(r[0].session_id, format!("s{i}"));